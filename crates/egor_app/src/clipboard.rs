@@ -0,0 +1,138 @@
+//! Copy & paste text through the system clipboard. Native platforms use it with the
+//! `clipboard` feature; browsers use the async Clipboard API, so pasted text arrives a
+//! frame or more after it's asked for. Where there's no system clipboard (or it refuses),
+//! text copied within the app is pasted instead
+
+use std::{cell::RefCell, rc::Rc};
+
+thread_local! {
+    /// Last text copied in the app, pasted when the system clipboard can't be read
+    static COPIED: RefCell<String> = const { RefCell::new(String::new()) };
+}
+
+/// A handle to the clipboard. Pastes are asked for with [`Self::request_paste`] &
+/// picked up with [`Self::take_paste`], the same way on every platform
+#[derive(Default)]
+pub struct Clipboard {
+    pasted: Rc<RefCell<Option<String>>>,
+}
+
+impl Clipboard {
+    /// Put `text` on the clipboard
+    pub fn copy(&self, text: &str) {
+        COPIED.with_borrow_mut(|copied| text.clone_into(copied));
+        system::write(text);
+    }
+
+    /// Start reading the clipboard. The text is ready for [`Self::take_paste`] right away on
+    /// native platforms & once the browser hands it over on the web, which may ask the user
+    /// for permission first
+    pub fn request_paste(&self) {
+        system::read(self.pasted.clone());
+    }
+
+    /// Text read by the last [`Self::request_paste`], once
+    pub fn take_paste(&self) -> Option<String> {
+        self.pasted.take()
+    }
+}
+
+fn copied() -> String {
+    COPIED.with_borrow(Clone::clone)
+}
+
+#[cfg(all(
+    feature = "clipboard",
+    not(any(target_arch = "wasm32", target_os = "android"))
+))]
+mod system {
+    use std::{cell::RefCell, rc::Rc};
+
+    thread_local! {
+        /// Opened on first use, `None` if the platform has no clipboard to open
+        static SYSTEM: RefCell<Option<arboard::Clipboard>> =
+            RefCell::new(arboard::Clipboard::new().ok());
+    }
+
+    pub fn write(text: &str) {
+        SYSTEM.with_borrow_mut(|system| {
+            if let Some(clipboard) = system {
+                _ = clipboard.set_text(text);
+            }
+        });
+    }
+
+    pub fn read(pasted: Rc<RefCell<Option<String>>>) {
+        let text = SYSTEM.with_borrow_mut(|system| system.as_mut()?.get_text().ok());
+        *pasted.borrow_mut() = Some(text.unwrap_or_else(super::copied));
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+mod system {
+    use std::{cell::RefCell, rc::Rc};
+    use wasm_bindgen::JsValue;
+    use wasm_bindgen_futures::JsFuture;
+    use web_sys::js_sys::Reflect;
+
+    /// The browser's clipboard, missing outside secure (https or localhost) pages
+    fn clipboard() -> Option<web_sys::Clipboard> {
+        let navigator = web_sys::window()?.navigator();
+        Reflect::get(&navigator, &JsValue::from_str("clipboard"))
+            .ok()
+            .filter(|clipboard| !clipboard.is_undefined())?;
+        Some(navigator.clipboard())
+    }
+
+    pub fn write(text: &str) {
+        if let Some(clipboard) = clipboard() {
+            let written = JsFuture::from(clipboard.write_text(text));
+            wasm_bindgen_futures::spawn_local(async move {
+                _ = written.await;
+            });
+        }
+    }
+
+    pub fn read(pasted: Rc<RefCell<Option<String>>>) {
+        let Some(clipboard) = clipboard() else {
+            *pasted.borrow_mut() = Some(super::copied());
+            return;
+        };
+        let read = JsFuture::from(clipboard.read_text());
+        wasm_bindgen_futures::spawn_local(async move {
+            // denied reads paste what was copied in the app
+            let text = read.await.ok().and_then(|text| text.as_string());
+            *pasted.borrow_mut() = Some(text.unwrap_or_else(super::copied));
+        });
+    }
+}
+
+#[cfg(not(any(
+    target_arch = "wasm32",
+    all(feature = "clipboard", not(target_os = "android"))
+)))]
+mod system {
+    use std::{cell::RefCell, rc::Rc};
+
+    pub fn write(_: &str) {}
+
+    pub fn read(pasted: Rc<RefCell<Option<String>>>) {
+        *pasted.borrow_mut() = Some(super::copied());
+    }
+}
+
+// the system clipboard is shared with whatever else runs
+#[cfg(all(test, not(feature = "clipboard")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pastes_what_was_copied_without_a_system_clipboard() {
+        let clipboard = Clipboard::default();
+        assert_eq!(clipboard.take_paste(), None);
+        clipboard.copy("hello");
+        clipboard.request_paste();
+        assert_eq!(clipboard.take_paste().as_deref(), Some("hello"));
+        assert_eq!(clipboard.take_paste(), None);
+    }
+}
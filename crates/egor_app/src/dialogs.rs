@@ -0,0 +1,259 @@
+//! Open & save dialogs that don't block the event loop. Native platforms show the system
+//! dialog; browsers show their file picker & download the saved file
+
+use std::{
+    io,
+    sync::{
+        Arc,
+        mpsc::{self, Receiver, Sender},
+    },
+};
+
+use rfd::AsyncFileDialog;
+use winit::window::Window;
+
+/// Files a dialog lists, e.g. `FileFilter::new("Images", &["png", "jpg"])`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FileFilter {
+    pub name: String,
+    /// Extensions without the dot
+    pub extensions: Vec<String>,
+}
+
+impl FileFilter {
+    pub fn new(name: &str, extensions: &[&str]) -> Self {
+        Self {
+            name: name.to_string(),
+            extensions: extensions.iter().map(|e| e.to_string()).collect(),
+        }
+    }
+}
+
+/// A file picked in a dialog: a path on native, the browser's file on the web
+pub struct FileHandle {
+    #[cfg(not(target_arch = "wasm32"))]
+    path: std::path::PathBuf,
+    #[cfg(target_arch = "wasm32")]
+    inner: rfd::FileHandle,
+    /// Browsers only hand out contents asynchronously, so opened files are read before
+    /// their callback runs. `None` for save handles
+    #[cfg(target_arch = "wasm32")]
+    contents: Option<Vec<u8>>,
+}
+
+impl FileHandle {
+    /// File name with its extension
+    pub fn name(&self) -> String {
+        #[cfg(not(target_arch = "wasm32"))]
+        return self
+            .path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        #[cfg(target_arch = "wasm32")]
+        self.inner.file_name()
+    }
+
+    /// Where the file is on disk
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn path(&self) -> &std::path::Path {
+        &self.path
+    }
+
+    /// Contents of the file. Browsers can only read files picked with [`Dialogs::open_file`]
+    pub fn read(&self) -> io::Result<Vec<u8>> {
+        #[cfg(not(target_arch = "wasm32"))]
+        return std::fs::read(&self.path);
+        #[cfg(target_arch = "wasm32")]
+        self.contents.clone().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::Unsupported,
+                "browsers can't read files picked with save_file",
+            )
+        })
+    }
+
+    /// Replace the file's contents. Browsers download the bytes under the picked name & can
+    /// only write files picked with [`Dialogs::save_file`]
+    pub fn write(&self, bytes: &[u8]) -> io::Result<()> {
+        #[cfg(not(target_arch = "wasm32"))]
+        return std::fs::write(&self.path, bytes);
+        #[cfg(target_arch = "wasm32")]
+        {
+            if self.contents.is_some() {
+                return Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    "browsers can't write files picked with open_file",
+                ));
+            }
+            let (inner, bytes) = (self.inner.clone(), bytes.to_vec());
+            wasm_bindgen_futures::spawn_local(async move {
+                let _ = inner.write(&bytes).await;
+            });
+            Ok(())
+        }
+    }
+}
+
+type DialogCallback = Box<dyn FnOnce(Option<FileHandle>)>;
+
+/// Shows file dialogs without blocking frames. Each callback runs once on the main thread,
+/// before the first frame after its dialog closes, with `None` if it was cancelled
+pub struct Dialogs {
+    window: Option<Arc<Window>>,
+    sender: Sender<(u64, Option<FileHandle>)>,
+    finished: Receiver<(u64, Option<FileHandle>)>,
+    pending: Vec<(u64, DialogCallback)>,
+    next_id: u64,
+}
+
+impl Default for Dialogs {
+    fn default() -> Self {
+        let (sender, finished) = mpsc::channel();
+        Self {
+            window: None,
+            sender,
+            finished,
+            pending: Vec::new(),
+            next_id: 0,
+        }
+    }
+}
+
+impl Dialogs {
+    /// Window dialogs are shown over & that gets redrawn once one closes
+    pub fn set_window(&mut self, window: Arc<Window>) {
+        self.window = Some(window);
+    }
+
+    /// Ask for a file to open
+    pub fn open_file(
+        &mut self,
+        filters: &[FileFilter],
+        callback: impl FnOnce(Option<FileHandle>) + 'static,
+    ) {
+        let dialog = self.dialog(filters);
+        self.spawn(dialog, false, Box::new(callback));
+    }
+
+    /// Ask where to save a file, suggesting `file_name`
+    pub fn save_file(
+        &mut self,
+        filters: &[FileFilter],
+        file_name: &str,
+        callback: impl FnOnce(Option<FileHandle>) + 'static,
+    ) {
+        let dialog = self.dialog(filters).set_file_name(file_name);
+        self.spawn(dialog, true, Box::new(callback));
+    }
+
+    /// Whether a dialog is waiting on the user
+    pub fn is_open(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    /// Runs the callbacks of dialogs that closed since the last call. Apps call this before
+    /// every frame
+    pub fn deliver(&mut self) {
+        while let Ok((id, file)) = self.finished.try_recv() {
+            if let Some(index) = self.pending.iter().position(|(pending, _)| *pending == id) {
+                let (_, callback) = self.pending.swap_remove(index);
+                callback(file);
+            }
+        }
+    }
+
+    fn dialog(&self, filters: &[FileFilter]) -> AsyncFileDialog {
+        let mut dialog = AsyncFileDialog::new();
+        for filter in filters {
+            dialog = dialog.add_filter(&filter.name, &filter.extensions);
+        }
+        match &self.window {
+            Some(window) => dialog.set_parent(window.as_ref()),
+            None => dialog,
+        }
+    }
+
+    fn spawn(&mut self, dialog: AsyncFileDialog, save: bool, callback: DialogCallback) {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.pending.push((id, callback));
+        let (sender, window) = (self.sender.clone(), self.window.clone());
+
+        #[cfg(not(target_arch = "wasm32"))]
+        std::thread::spawn(move || {
+            let file = match save {
+                true => pollster::block_on(dialog.save_file()),
+                false => pollster::block_on(dialog.pick_file()),
+            };
+            let file = file.map(|file| FileHandle { path: file.into() });
+            finish(&sender, window, id, file);
+        });
+        #[cfg(target_arch = "wasm32")]
+        wasm_bindgen_futures::spawn_local(async move {
+            let file = match save {
+                true => dialog.save_file().await.map(|inner| FileHandle {
+                    inner,
+                    contents: None,
+                }),
+                false => match dialog.pick_file().await {
+                    Some(inner) => Some(FileHandle {
+                        contents: Some(inner.read().await),
+                        inner,
+                    }),
+                    None => None,
+                },
+            };
+            finish(&sender, window, id, file);
+        });
+    }
+}
+
+/// Hands `file` to the main thread & wakes it, in case it's waiting for events
+fn finish(
+    sender: &Sender<(u64, Option<FileHandle>)>,
+    window: Option<Arc<Window>>,
+    id: u64,
+    file: Option<FileHandle>,
+) {
+    let _ = sender.send((id, file));
+    if let Some(window) = window {
+        window.request_redraw();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{cell::RefCell, rc::Rc};
+
+    #[test]
+    fn callbacks_run_once_their_dialog_closes() {
+        let mut dialogs = Dialogs::default();
+        let picked = Rc::new(RefCell::new(Vec::new()));
+        for _ in 0..2 {
+            let picked = picked.clone();
+            dialogs.pending.push((
+                dialogs.next_id,
+                Box::new(move |file: Option<FileHandle>| {
+                    picked.borrow_mut().push(file.map(|f| f.name()));
+                }),
+            ));
+            dialogs.next_id += 1;
+        }
+
+        dialogs.deliver();
+        assert!(picked.borrow().is_empty() && dialogs.is_open());
+
+        let file = FileHandle {
+            path: "saves/slot.bin".into(),
+        };
+        finish(&dialogs.sender, None, 1, Some(file));
+        finish(&dialogs.sender, None, 0, None);
+        // a late duplicate is ignored
+        finish(&dialogs.sender, None, 1, None);
+        dialogs.deliver();
+        assert_eq!(*picked.borrow(), [Some("slot.bin".to_string()), None]);
+        assert!(!dialogs.is_open());
+    }
+}
@@ -1,31 +1,331 @@
-pub use winit::{event::MouseButton, keyboard::KeyCode};
+pub use winit::{
+    event::{Ime, MouseButton, TouchPhase},
+    keyboard::KeyCode,
+};
 
-use std::collections::HashMap;
+use std::{
+    cell::{Cell, RefCell},
+    collections::{HashMap, HashSet},
+};
 
 use winit::{
     dpi::PhysicalPosition,
-    event::{ElementState, KeyEvent},
+    event::{DeviceEvent, ElementState, MouseScrollDelta, WindowEvent},
     keyboard::PhysicalKey,
 };
 
-#[derive(Default)]
+/// One change to the input state, as fed to [`Input`] by the app runner.
+/// Recorded & replayed by the `record` module, with the `serde` feature
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum InputEvent {
+    Key {
+        key: KeyCode,
+        pressed: bool,
+    },
+    MouseButton {
+        button: MouseButton,
+        pressed: bool,
+    },
+    /// Cursor position in window coords
+    CursorMoved {
+        x: f64,
+        y: f64,
+    },
+    /// Raw mouse movement, only used while the pointer is locked
+    MouseMotion {
+        dx: f64,
+        dy: f64,
+    },
+    /// Mouse wheel lines scrolled
+    Scroll(f32),
+    /// A finger touching, moving on or leaving the screen, in window coords
+    Touch {
+        id: u64,
+        phase: TouchPhase,
+        x: f64,
+        y: f64,
+    },
+    /// Text typed with the keyboard, without control characters like backspace
+    Text(String),
+    /// Input method composition, see [`Input::ime_preedit`]
+    Ime(Ime),
+}
+
+impl InputEvent {
+    /// The input changes carried by a window event: a key press may also type text
+    pub fn from_window_event(event: &WindowEvent) -> impl Iterator<Item = Self> + use<> {
+        let text = match event {
+            WindowEvent::KeyboardInput { event, .. } if event.state.is_pressed() => {
+                let text = event.text.as_deref().unwrap_or_default();
+                let text: String = text.chars().filter(|c| !c.is_control()).collect();
+                (!text.is_empty()).then_some(Self::Text(text))
+            }
+            _ => None,
+        };
+        Self::from_window_change(event).into_iter().chain(text)
+    }
+
+    fn from_window_change(event: &WindowEvent) -> Option<Self> {
+        let pressed = |state: ElementState| state == ElementState::Pressed;
+        Some(match event {
+            WindowEvent::KeyboardInput { event, .. } => match event.physical_key {
+                PhysicalKey::Code(key) => Self::Key {
+                    key,
+                    pressed: pressed(event.state),
+                },
+                PhysicalKey::Unidentified(_) => return None,
+            },
+            WindowEvent::MouseInput { button, state, .. } => Self::MouseButton {
+                button: *button,
+                pressed: pressed(*state),
+            },
+            WindowEvent::CursorMoved { position, .. } => Self::CursorMoved {
+                x: position.x,
+                y: position.y,
+            },
+            WindowEvent::MouseWheel { delta, .. } => Self::Scroll(match delta {
+                MouseScrollDelta::LineDelta(_, y) => *y,
+                MouseScrollDelta::PixelDelta(pos) => pos.y as f32 / 100.0,
+            }),
+            WindowEvent::Touch(touch) => Self::Touch {
+                id: touch.id,
+                phase: touch.phase,
+                x: touch.location.x,
+                y: touch.location.y,
+            },
+            WindowEvent::Ime(ime) => Self::Ime(ime.clone()),
+            _ => return None,
+        })
+    }
+
+    /// The input change carried by a device event, if any
+    pub fn from_device_event(event: &DeviceEvent) -> Option<Self> {
+        match event {
+            DeviceEvent::MouseMotion { delta: (dx, dy) } => {
+                Some(Self::MouseMotion { dx: *dx, dy: *dy })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Longest gap in seconds between two clicks that still counts them as one double (or
+/// triple...) click, unless changed with [`Input::set_multi_click_interval`]
+pub const DEFAULT_MULTI_CLICK_INTERVAL: f64 = 0.5;
+
+/// Farthest in pixels the cursor may move between two clicks that still count as one double
+/// (or triple...) click, so quick clicks on different things aren't
+pub const MULTI_CLICK_DISTANCE: f32 = 4.0;
+
+/// A key going down or up, see [`Input::key_events`]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct KeyEventRecord {
+    pub key: KeyCode,
+    pub pressed: bool,
+    /// When the event arrived, in seconds on the [`crate::time::FrameTimer::now`] clock
+    pub time: f64,
+}
+
+/// A finger on a touch screen, see [`Input::touches`]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Touch {
+    /// Stays the same while the finger is down, may be reused after it's lifted
+    pub id: u64,
+    /// Position in window coords
+    pub position: (f32, f32),
+    /// Where the finger went down
+    pub start: (f32, f32),
+    pressed: bool,
+    released: bool,
+}
+
+impl Touch {
+    /// True if the finger went down this frame
+    pub fn pressed(&self) -> bool {
+        self.pressed
+    }
+
+    /// True while the finger is down
+    pub fn held(&self) -> bool {
+        !self.released
+    }
+
+    /// True if the finger was lifted (or the touch cancelled) this frame. A quick tap can
+    /// be pressed & released in the same frame
+    pub fn released(&self) -> bool {
+        self.released
+    }
+}
+
+/// The mouse or one touch, as claimed with [`Input::claim_pointer`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Pointer {
+    Mouse,
+    Touch(u64),
+}
+
+/// Complete input state for a single frame, used to drive or record frames deterministically
+///
+/// Feed snapshots to [`Input::apply_snapshot`] (e.g. through a headless app) to replay input,
+/// & capture them from live input with [`Input::snapshot`]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct InputSnapshot {
+    /// Keys held down during the frame
+    pub keys: HashSet<KeyCode>,
+    /// Mouse buttons held down during the frame
+    pub mouse_buttons: HashSet<MouseButton>,
+    /// Cursor position in window coords
+    pub mouse_position: (f32, f32),
+    /// Mouse wheel delta for the frame
+    pub mouse_scroll: f32,
+}
+
 pub struct Input {
     keyboard: HashMap<KeyCode, (ElementState, ElementState)>, // (current, previous) state
     mouse_buttons: HashMap<MouseButton, (ElementState, ElementState)>,
+    /// Key transitions of the current frame, in arrival order
+    key_events: Vec<KeyEventRecord>,
+    /// When held or just pressed keys & buttons went down
+    key_press_times: HashMap<KeyCode, f64>,
+    mouse_press_times: HashMap<MouseButton, f64>,
+    /// Clicks in a row & when & where the last one was pressed, per button
+    clicks: HashMap<MouseButton, (u32, f64, (f32, f32))>,
+    multi_click_interval: Cell<f64>,
     mouse_position: (f32, f32),
     mouse_delta: (f32, f32),
     mouse_wheel_delta: f32,
+    pointer_locked: bool,
+    /// Lock change asked for during the frame, applied to the window after it
+    pointer_lock_request: Cell<Option<bool>>,
+    /// Fingers down, & lifted this frame, in the order they went down
+    touches: Vec<Touch>,
+    /// Pointers taken by on-screen controls until they're let go
+    claimed: RefCell<HashSet<Pointer>>,
+    /// Keys the system repeated while held this frame
+    key_repeats: Vec<KeyCode>,
+    /// Text typed this frame, input method commits included
+    text: String,
+    /// Input method composition in progress & the cursor within it
+    preedit: (String, Option<(usize, usize)>),
+    /// Caret area text input was enabled for, & the one asked for this frame
+    text_input: Option<[f32; 4]>,
+    text_input_request: Cell<Option<[f32; 4]>>,
+}
+
+impl Default for Input {
+    fn default() -> Self {
+        Self {
+            keyboard: HashMap::new(),
+            mouse_buttons: HashMap::new(),
+            key_events: Vec::new(),
+            key_press_times: HashMap::new(),
+            mouse_press_times: HashMap::new(),
+            clicks: HashMap::new(),
+            multi_click_interval: Cell::new(DEFAULT_MULTI_CLICK_INTERVAL),
+            mouse_position: (0.0, 0.0),
+            mouse_delta: (0.0, 0.0),
+            mouse_wheel_delta: 0.0,
+            pointer_locked: false,
+            pointer_lock_request: Cell::new(None),
+            touches: Vec::new(),
+            claimed: RefCell::new(HashSet::new()),
+            key_repeats: Vec::new(),
+            text: String::new(),
+            preedit: (String::new(), None),
+            text_input: None,
+            text_input_request: Cell::new(None),
+        }
+    }
 }
 
 impl Input {
-    /// Update keyboard state from a `winit` KeyEvent
-    pub(crate) fn update_key(&mut self, event: KeyEvent) {
-        if let PhysicalKey::Code(key_code) = event.physical_key {
-            let prev = self
-                .keyboard
-                .get(&key_code)
-                .map_or(ElementState::Released, |(curr, _)| *curr);
-            self.keyboard.insert(key_code, (event.state, prev));
+    /// Update the state from one input event that arrived at `time` (seconds on the
+    /// [`crate::time::FrameTimer::now`] clock). The app runner feeds every event through
+    /// this; call it yourself to drive input from tests or another source
+    pub fn apply(&mut self, event: InputEvent, time: f64) {
+        let state = |pressed| match pressed {
+            true => ElementState::Pressed,
+            false => ElementState::Released,
+        };
+        match event {
+            InputEvent::Key { key, pressed } => {
+                // key repeats aren't transitions
+                if pressed && self.key_held(key) {
+                    self.key_repeats.push(key);
+                } else if pressed != self.key_held(key) {
+                    self.key_events.push(KeyEventRecord { key, pressed, time });
+                    if pressed {
+                        self.key_press_times.insert(key, time);
+                    }
+                }
+                self.set_key(key, state(pressed))
+            }
+            InputEvent::MouseButton { button, pressed } => {
+                if pressed && !self.mouse_held(button) {
+                    self.mouse_press_times.insert(button, time);
+                    let interval = self.multi_click_interval.get();
+                    let (x, y) = self.mouse_position;
+                    let (count, last, at) =
+                        (self.clicks.entry(button)).or_insert((0, f64::MIN, (x, y)));
+                    let still = (x - at.0).hypot(y - at.1) <= MULTI_CLICK_DISTANCE;
+                    *count = if time - *last <= interval && still {
+                        *count + 1
+                    } else {
+                        1
+                    };
+                    *last = time;
+                    *at = (x, y);
+                }
+                self.update_mouse_button(button, state(pressed))
+            }
+            InputEvent::CursorMoved { x, y } => self.update_cursor(PhysicalPosition::new(x, y)),
+            InputEvent::MouseMotion { dx, dy } => self.update_mouse_motion((dx, dy)),
+            InputEvent::Scroll(delta) => self.update_scroll(delta),
+            InputEvent::Touch { id, phase, x, y } => {
+                self.update_touch(id, phase, (x as f32, y as f32))
+            }
+            // keys pressed while composing go to the input method
+            InputEvent::Text(text) if self.preedit.0.is_empty() => self.text.push_str(&text),
+            InputEvent::Text(_) => {}
+            InputEvent::Ime(Ime::Commit(text)) => {
+                self.preedit = (String::new(), None);
+                self.text.push_str(&text);
+            }
+            InputEvent::Ime(Ime::Preedit(text, cursor)) => self.preedit = (text, cursor),
+            InputEvent::Ime(Ime::Disabled) => self.preedit = (String::new(), None),
+            InputEvent::Ime(Ime::Enabled) => {}
+        }
+    }
+
+    /// Start, move or end the touch `id`
+    pub(crate) fn update_touch(&mut self, id: u64, phase: TouchPhase, position: (f32, f32)) {
+        let held = self.touches.iter_mut().find(|t| t.id == id && !t.released);
+        match phase {
+            TouchPhase::Started => {
+                // a lost end would leave the id's last touch held forever
+                if let Some(stale) = held {
+                    stale.released = true;
+                }
+                self.touches.push(Touch {
+                    id,
+                    position,
+                    start: position,
+                    pressed: true,
+                    released: false,
+                });
+            }
+            TouchPhase::Moved => {
+                if let Some(touch) = held {
+                    touch.position = position;
+                }
+            }
+            TouchPhase::Ended | TouchPhase::Cancelled => {
+                if let Some(touch) = held {
+                    touch.position = position;
+                    touch.released = true;
+                }
+            }
         }
     }
 
@@ -42,17 +342,117 @@ impl Input {
     pub(crate) fn update_cursor(&mut self, position: PhysicalPosition<f64>) {
         let prev_pos = self.mouse_position;
         let pos: (f32, f32) = position.into();
-        self.mouse_delta = (pos.0 - prev_pos.0, pos.1 - prev_pos.1);
+        // a locked pointer reports movement through update_mouse_motion instead
+        if !self.pointer_locked {
+            self.mouse_delta = (pos.0 - prev_pos.0, pos.1 - prev_pos.1);
+        }
         self.mouse_position = pos;
     }
 
+    /// Accumulate raw mouse movement, used for [`Self::mouse_delta`] while the pointer is locked
+    pub(crate) fn update_mouse_motion(&mut self, (dx, dy): (f64, f64)) {
+        if self.pointer_locked {
+            self.mouse_delta.0 += dx as f32;
+            self.mouse_delta.1 += dy as f32;
+        }
+    }
+
+    /// Takes the lock state asked for with [`Self::set_pointer_lock`] this frame, if any
+    pub(crate) fn take_pointer_lock_request(&self) -> Option<bool> {
+        self.pointer_lock_request.take()
+    }
+
+    /// The caret area to enable text input for (`Some`) or to disable it (`None`), when
+    /// that changed with this frame's [`Self::request_text_input`]
+    pub(crate) fn take_text_input_change(&mut self) -> Option<Option<[f32; 4]>> {
+        let requested = self.text_input_request.take();
+        (requested != self.text_input).then(|| {
+            self.text_input = requested;
+            requested
+        })
+    }
+
+    /// Record whether the window actually holds the pointer
+    pub(crate) fn set_pointer_locked(&mut self, locked: bool) {
+        self.pointer_locked = locked;
+    }
+
     /// Update mouse wheel delta
     pub(crate) fn update_scroll(&mut self, delta: f32) {
         self.mouse_wheel_delta += delta;
     }
 
-    /// Update previous states & clean up released keys/buttons
-    pub(crate) fn end_frame(&mut self) {
+    /// Capture the current frame's input so it can be replayed later
+    pub fn snapshot(&self) -> InputSnapshot {
+        let held = |(curr, _): &(ElementState, ElementState)| *curr == ElementState::Pressed;
+        InputSnapshot {
+            keys: self
+                .keyboard
+                .iter()
+                .filter_map(|(k, s)| held(s).then_some(*k))
+                .collect(),
+            mouse_buttons: self
+                .mouse_buttons
+                .iter()
+                .filter_map(|(b, s)| held(s).then_some(*b))
+                .collect(),
+            mouse_position: self.mouse_position,
+            mouse_scroll: self.mouse_wheel_delta,
+        }
+    }
+
+    /// Advance to a new frame whose input is fully described by `snapshot`.
+    /// Keys & buttons missing from the snapshot are released, so pressed/released
+    /// edges behave the same as with live input
+    pub fn apply_snapshot(&mut self, snapshot: &InputSnapshot) {
+        self.end_frame();
+
+        let released: Vec<_> = self
+            .keyboard
+            .keys()
+            .filter(|k| !snapshot.keys.contains(k))
+            .copied()
+            .collect();
+        for key in released {
+            self.set_key(key, ElementState::Released);
+        }
+        for &key in &snapshot.keys {
+            if !self.key_held(key) {
+                self.set_key(key, ElementState::Pressed);
+            }
+        }
+
+        let released: Vec<_> = self
+            .mouse_buttons
+            .keys()
+            .filter(|b| !snapshot.mouse_buttons.contains(b))
+            .copied()
+            .collect();
+        for button in released {
+            self.update_mouse_button(button, ElementState::Released);
+        }
+        for &button in &snapshot.mouse_buttons {
+            if !self.mouse_held(button) {
+                self.update_mouse_button(button, ElementState::Pressed);
+            }
+        }
+
+        let (x, y) = snapshot.mouse_position;
+        self.update_cursor(PhysicalPosition::new(x as f64, y as f64));
+        self.update_scroll(snapshot.mouse_scroll);
+    }
+
+    fn set_key(&mut self, key: KeyCode, state: ElementState) {
+        let prev = self
+            .keyboard
+            .get(&key)
+            .map_or(ElementState::Released, |(curr, _)| *curr);
+        self.keyboard.insert(key, (state, prev));
+    }
+
+    /// Update previous states & clean up released keys, buttons & touches, ending this
+    /// frame's pressed & released edges. Called by the app runner after every frame
+    pub fn end_frame(&mut self) {
         for (curr, prev) in self.keyboard.values_mut() {
             *prev = *curr;
         }
@@ -65,6 +465,22 @@ impl Input {
             .retain(|_, (curr, _)| *curr != ElementState::Released);
         self.mouse_buttons
             .retain(|_, (curr, _)| *curr != ElementState::Released);
+        self.key_press_times
+            .retain(|key, _| self.keyboard.contains_key(key));
+        self.mouse_press_times
+            .retain(|button, _| self.mouse_buttons.contains_key(button));
+        self.key_events.clear();
+        self.key_repeats.clear();
+        self.text.clear();
+        self.touches.retain(|t| !t.released);
+        for touch in &mut self.touches {
+            touch.pressed = false;
+        }
+        // claims last until the pointer is let go
+        self.claimed.get_mut().retain(|pointer| match pointer {
+            Pointer::Mouse => !self.mouse_buttons.is_empty(),
+            Pointer::Touch(id) => self.touches.iter().any(|t| t.id == *id),
+        });
 
         self.mouse_delta = (0.0, 0.0);
         self.mouse_wheel_delta = 0.0;
@@ -91,6 +507,47 @@ impl Input {
             .is_some_and(|(curr, _)| *curr == ElementState::Released)
     }
 
+    /// When `key` went down, in seconds on the [`crate::time::FrameTimer::now`] clock, for
+    /// timing presses within a frame. `None` once it's released & the frame ends, & for
+    /// input driven by snapshots
+    pub fn key_press_time(&self, key: KeyCode) -> Option<f64> {
+        self.key_press_times.get(&key).copied()
+    }
+
+    /// Every key press & release of this frame in the order they arrived, so a key pressed
+    /// & released within one frame isn't lost. Key repeats aren't included
+    pub fn key_events(&self) -> &[KeyEventRecord] {
+        &self.key_events
+    }
+
+    /// True if the key went down this frame or the system repeated it while held, at the
+    /// rate set in the OS. For text editing & menus stepping while an arrow is held
+    pub fn key_pressed_repeating(&self, key: KeyCode) -> bool {
+        self.key_pressed(key) || self.key_repeats.contains(&key)
+    }
+
+    /// Text typed this frame, including text committed by an input method (IME). Doesn't
+    /// include control characters: check keys for Backspace, Enter & the like
+    pub fn text_typed(&self) -> &str {
+        &self.text
+    }
+
+    /// Text an input method (IME) is composing & the byte range of its cursor within it,
+    /// if it shows one. Drawn at the caret, usually underlined, until it's committed to
+    /// [`Self::text_typed`]. `None` when nothing is being composed
+    pub fn ime_preedit(&self) -> Option<(&str, Option<(usize, usize)>)> {
+        let (text, cursor) = &self.preedit;
+        (!text.is_empty()).then_some((text.as_str(), *cursor))
+    }
+
+    /// Ask for text input this frame with the caret at `area` (x, y, width & height in
+    /// window coords). Enables the input method, which places its candidate window by the
+    /// caret, & shows the soft keyboard on mobile. Call it every frame a text field has
+    /// focus; text input is disabled again after the first frame it isn't
+    pub fn request_text_input(&self, area: [f32; 4]) {
+        self.text_input_request.set(Some(area));
+    }
+
     /// True if any key in slice was just pressed
     pub fn keys_pressed(&self, keys: &[KeyCode]) -> bool {
         keys.iter().any(|&key| self.key_pressed(key))
@@ -142,20 +599,83 @@ impl Input {
             .is_some_and(|(curr, _)| *curr == ElementState::Released)
     }
 
+    /// When `button` went down, like [`Self::key_press_time`]
+    pub fn mouse_press_time(&self, button: MouseButton) -> Option<f64> {
+        self.mouse_press_times.get(&button).copied()
+    }
+
+    /// Clicks in a row ending with the latest press of `button`, each within the multi-click
+    /// interval & [`MULTI_CLICK_DISTANCE`] of the one before: 2 for a double click, 3 for a
+    /// triple click. 0 before the first press. Check it when [`Self::mouse_pressed`]
+    pub fn click_count(&self, button: MouseButton) -> u32 {
+        self.clicks.get(&button).map_or(0, |(count, ..)| *count)
+    }
+
+    /// Longest gap in seconds between clicks counted by [`Self::click_count`].
+    /// Defaults to [`DEFAULT_MULTI_CLICK_INTERVAL`]
+    pub fn set_multi_click_interval(&self, seconds: f64) {
+        self.multi_click_interval.set(seconds);
+    }
+
     /// Current mouse cursor position in window coords
     pub fn mouse_position(&self) -> (f32, f32) {
         self.mouse_position
     }
 
-    /// Delta mouse movement since last frame
+    /// Delta mouse movement since last frame.
+    /// While the pointer is locked this is the raw relative motion, since the cursor doesn't move
     pub fn mouse_delta(&self) -> (f32, f32) {
         self.mouse_delta
     }
 
+    /// Lock & hide the cursor for drag-look style controls, or release it.
+    /// Applied after the frame; check [`Self::pointer_locked`] for the result
+    ///
+    /// On the web this uses the Pointer Lock API, which browsers only grant shortly after a
+    /// click or key press & release on Escape. Platforms that can't lock fall back to
+    /// confining the cursor to the window
+    pub fn set_pointer_lock(&self, locked: bool) {
+        self.pointer_lock_request.set(Some(locked));
+    }
+
+    /// True while the cursor is locked by [`Self::set_pointer_lock`]
+    pub fn pointer_locked(&self) -> bool {
+        self.pointer_locked
+    }
+
     /// Mouse wheel delta this frame (positive = scroll up, negative = scroll down)
     pub fn mouse_scroll(&self) -> f32 {
         self.mouse_wheel_delta
     }
+
+    /// Fingers on the screen, & ones lifted this frame, oldest first
+    pub fn touches(&self) -> &[Touch] {
+        &self.touches
+    }
+
+    /// The finger with `id`, if it's down or was lifted this frame
+    pub fn touch(&self, id: u64) -> Option<&Touch> {
+        self.touches.iter().rev().find(|t| t.id == id)
+    }
+
+    /// Touches no on-screen control claimed, for gameplay taps & gestures
+    pub fn unclaimed_touches(&self) -> impl Iterator<Item = &Touch> {
+        // claims are looked up per touch, so controls can claim while this is iterated
+        (self.touches.iter()).filter(|t| !self.pointer_claimed(Pointer::Touch(t.id)))
+    }
+
+    /// Take `pointer` for an on-screen control until it's let go: the finger is lifted or,
+    /// for the mouse, every button is released. Like egui's pointer capture, gameplay code
+    /// checks [`Self::pointer_claimed`] (or uses [`Self::unclaimed_touches`]) so the same
+    /// press doesn't also count as a tap. Controls have to run before that code each frame
+    pub fn claim_pointer(&self, pointer: Pointer) {
+        self.claimed.borrow_mut().insert(pointer);
+    }
+
+    /// Whether an on-screen control holds `pointer`, see [`Self::claim_pointer`]
+    pub fn pointer_claimed(&self, pointer: Pointer) -> bool {
+        self.claimed.borrow().contains(&pointer)
+    }
 }
 
 #[cfg(test)]
@@ -214,6 +734,26 @@ mod tests {
         assert!(!input.key_released(KeyCode::Space));
     }
 
+    #[test]
+    fn locked_pointer_reports_relative_motion() {
+        let mut input = Input::default();
+        input.set_pointer_lock(true);
+        assert_eq!(input.take_pointer_lock_request(), Some(true));
+        assert_eq!(input.take_pointer_lock_request(), None);
+        input.set_pointer_locked(true);
+
+        // the cursor position no longer drives the delta, raw motion accumulates instead
+        input.update_cursor(PhysicalPosition::new(50.0, 50.0));
+        input.update_mouse_motion((3.0, -1.0));
+        input.update_mouse_motion((2.0, -1.0));
+        assert_eq!(input.mouse_delta(), (5.0, -2.0));
+
+        input.end_frame();
+        input.set_pointer_locked(false);
+        input.update_mouse_motion((3.0, 3.0));
+        assert_eq!(input.mouse_delta(), (0.0, 0.0));
+    }
+
     #[test]
     fn mouse_button_and_cursor() {
         // test mouse press & cursor movement/delta
@@ -302,4 +842,208 @@ mod tests {
         assert!(input.key_held(KeyCode::KeyX));
         assert!(!input.key_released(KeyCode::KeyX));
     }
+
+    #[test]
+    fn presses_within_a_frame_keep_their_order_and_time() {
+        let key = |key, pressed| InputEvent::Key { key, pressed };
+        let mut input = Input::default();
+        input.apply(key(KeyCode::KeyJ, true), 1.25);
+        input.apply(key(KeyCode::KeyJ, true), 1.3); // repeat
+        input.apply(key(KeyCode::KeyK, true), 1.5);
+        input.apply(key(KeyCode::KeyJ, false), 1.75);
+
+        let events: Vec<_> = input
+            .key_events()
+            .iter()
+            .map(|e| (e.key, e.pressed, e.time))
+            .collect();
+        assert_eq!(
+            events,
+            [
+                (KeyCode::KeyJ, true, 1.25),
+                (KeyCode::KeyK, true, 1.5),
+                (KeyCode::KeyJ, false, 1.75)
+            ]
+        );
+        assert_eq!(input.key_press_time(KeyCode::KeyJ), Some(1.25));
+
+        input.end_frame();
+        assert!(input.key_events().is_empty());
+        assert_eq!(input.key_press_time(KeyCode::KeyJ), None);
+        assert_eq!(input.key_press_time(KeyCode::KeyK), Some(1.5));
+    }
+
+    #[test]
+    fn quick_clicks_count_up() {
+        fn click(input: &mut Input, time: f64) -> u32 {
+            let button = |pressed| InputEvent::MouseButton {
+                button: MouseButton::Left,
+                pressed,
+            };
+            input.apply(button(true), time);
+            input.apply(button(false), time + 0.05);
+            input.end_frame();
+            input.click_count(MouseButton::Left)
+        }
+        let mut input = Input::default();
+        let counts = [0.0, 0.3, 0.6, 2.0, 2.2].map(|time| click(&mut input, time));
+        assert_eq!(counts, [1, 2, 3, 1, 2]);
+
+        input.set_multi_click_interval(0.1);
+        assert_eq!(click(&mut input, 2.4), 1);
+        assert_eq!(input.mouse_press_time(MouseButton::Left), None);
+    }
+
+    #[test]
+    fn clicks_far_apart_start_over() {
+        let mut input = Input::default();
+        let mut click = |x: f64, time: f64| {
+            input.apply(InputEvent::CursorMoved { x, y: 10.0 }, time);
+            let button = |pressed| InputEvent::MouseButton {
+                button: MouseButton::Left,
+                pressed,
+            };
+            input.apply(button(true), time);
+            input.apply(button(false), time);
+            input.click_count(MouseButton::Left)
+        };
+        // a small wobble still double clicks, a click elsewhere doesn't
+        let counts = [(10.0, 0.0), (13.0, 0.1), (40.0, 0.2), (41.0, 0.3)];
+        assert_eq!(counts.map(|(x, time)| click(x, time)), [1, 2, 1, 2]);
+    }
+
+    #[test]
+    fn typed_text_repeats_and_ime_composition_last_their_frame() {
+        let mut input = Input::default();
+        let key = |pressed| InputEvent::Key {
+            key: KeyCode::Backspace,
+            pressed,
+        };
+        input.apply(InputEvent::Text("hé".into()), 0.0);
+        input.apply(key(true), 0.0);
+        assert!(input.key_pressed_repeating(KeyCode::Backspace));
+        input.end_frame();
+        assert!(!input.key_pressed_repeating(KeyCode::Backspace));
+        input.apply(key(true), 0.5);
+        assert!(input.key_pressed_repeating(KeyCode::Backspace));
+        assert!(!input.key_pressed(KeyCode::Backspace));
+        assert_eq!(input.text_typed(), "");
+
+        // keys pressed while composing only reach the input method
+        let preedit = Ime::Preedit("ni".into(), Some((2, 2)));
+        input.apply(InputEvent::Ime(preedit), 0.6);
+        input.apply(InputEvent::Text("i".into()), 0.6);
+        assert_eq!(input.ime_preedit(), Some(("ni", Some((2, 2)))));
+        input.apply(InputEvent::Ime(Ime::Commit("你".into())), 0.7);
+        assert_eq!(input.ime_preedit(), None);
+        assert_eq!(input.text_typed(), "你");
+    }
+
+    #[test]
+    fn text_input_changes_only_when_the_requested_caret_does() {
+        let mut input = Input::default();
+        assert_eq!(input.take_text_input_change(), None);
+        input.request_text_input([1.0, 2.0, 1.0, 16.0]);
+        assert_eq!(
+            input.take_text_input_change(),
+            Some(Some([1.0, 2.0, 1.0, 16.0]))
+        );
+        input.request_text_input([1.0, 2.0, 1.0, 16.0]);
+        assert_eq!(input.take_text_input_change(), None);
+        assert_eq!(input.take_text_input_change(), Some(None));
+    }
+
+    #[test]
+    fn touches_have_edges_and_stay_claimed_until_lifted() {
+        let touch = |id, phase, x| InputEvent::Touch {
+            id,
+            phase,
+            x,
+            y: 0.0,
+        };
+        let mut input = Input::default();
+        input.apply(touch(1, TouchPhase::Started, 10.0), 0.0);
+        // a tap starting & ending within one frame
+        input.apply(touch(2, TouchPhase::Started, 50.0), 0.0);
+        input.apply(touch(2, TouchPhase::Ended, 50.0), 0.0);
+        input.claim_pointer(Pointer::Touch(1));
+
+        let tap = input.touch(2).unwrap();
+        assert!(tap.pressed() && tap.released() && !tap.held());
+        let ids: Vec<_> = input.unclaimed_touches().map(|t| t.id).collect();
+        assert_eq!(ids, [2]);
+
+        input.end_frame();
+        input.apply(touch(1, TouchPhase::Moved, 30.0), 0.0);
+        let stick = input.touch(1).unwrap();
+        assert!(!stick.pressed() && stick.held());
+        assert_eq!((stick.start, stick.position), ((10.0, 0.0), (30.0, 0.0)));
+        assert_eq!(input.touches().len(), 1);
+        assert!(input.pointer_claimed(Pointer::Touch(1)));
+
+        input.apply(touch(1, TouchPhase::Cancelled, 30.0), 0.0);
+        assert!(input.touch(1).unwrap().released());
+        assert!(input.pointer_claimed(Pointer::Touch(1)));
+        input.end_frame();
+        assert!(input.touches().is_empty());
+        assert!(!input.pointer_claimed(Pointer::Touch(1)));
+
+        // the mouse stays claimed while any button is held
+        input.inject_mouse_button(MouseButton::Left, Pressed);
+        input.claim_pointer(Pointer::Mouse);
+        input.end_frame();
+        assert!(input.pointer_claimed(Pointer::Mouse));
+        input.inject_mouse_button(MouseButton::Left, Released);
+        input.end_frame();
+        assert!(!input.pointer_claimed(Pointer::Mouse));
+    }
+
+    #[test]
+    fn snapshots_drive_press_hold_release_edges() {
+        let mut input = Input::default();
+        let mut snapshot = InputSnapshot {
+            keys: HashSet::from([KeyCode::KeyW]),
+            mouse_buttons: HashSet::from([MouseButton::Left]),
+            mouse_position: (10.0, 20.0),
+            mouse_scroll: 1.0,
+        };
+
+        input.apply_snapshot(&snapshot);
+        assert!(input.key_pressed(KeyCode::KeyW));
+        assert!(input.mouse_pressed(MouseButton::Left));
+        assert_eq!(input.mouse_delta(), (10.0, 20.0));
+        assert_eq!(input.mouse_scroll(), 1.0);
+
+        snapshot.mouse_scroll = 0.0;
+        input.apply_snapshot(&snapshot);
+        assert!(!input.key_pressed(KeyCode::KeyW));
+        assert!(input.key_held(KeyCode::KeyW));
+        assert_eq!(input.mouse_delta(), (0.0, 0.0));
+        assert_eq!(input.mouse_scroll(), 0.0);
+
+        input.apply_snapshot(&InputSnapshot::default());
+        assert!(input.key_released(KeyCode::KeyW));
+        assert!(input.mouse_released(MouseButton::Left));
+
+        input.apply_snapshot(&InputSnapshot::default());
+        assert!(!input.key_released(KeyCode::KeyW));
+    }
+
+    #[test]
+    fn snapshot_round_trips_live_input() {
+        let mut live = Input::default();
+        live.inject_key(KeyCode::Space, Pressed);
+        live.inject_key(KeyCode::KeyQ, Released);
+        live.inject_mouse_button(MouseButton::Right, Pressed);
+        live.inject_cursor(5.0, 6.0);
+
+        let snapshot = live.snapshot();
+        assert_eq!(snapshot.keys, HashSet::from([KeyCode::Space]));
+
+        let mut replay = Input::default();
+        replay.apply_snapshot(&snapshot);
+        assert_eq!(replay.snapshot(), snapshot);
+        assert!(replay.key_pressed(KeyCode::Space));
+        assert!(replay.mouse_pressed(MouseButton::Right));
+    }
 }
@@ -1,13 +1,29 @@
+pub mod clipboard;
+#[cfg(feature = "dialogs")]
+pub mod dialogs;
 pub mod input;
+#[cfg(feature = "log")]
+pub mod logging;
+#[cfg(feature = "serde")]
+pub mod record;
 pub mod time;
+#[cfg(target_arch = "wasm32")]
+mod web;
 
-use crate::{input::Input, time::FrameTimer};
-use std::sync::Arc;
+#[cfg(target_arch = "wasm32")]
+pub use web::show_html_over_canvas;
+
+use crate::{
+    input::{Input, InputEvent},
+    time::FrameTimer,
+};
+use std::{error::Error, sync::Arc};
 pub use winit::{
-    dpi::PhysicalSize,
+    dpi::{PhysicalPosition, PhysicalSize},
     event::WindowEvent,
     event_loop::ControlFlow,
-    window::{Fullscreen, Window},
+    monitor::MonitorHandle,
+    window::{Fullscreen, Window, WindowLevel},
 };
 
 #[cfg(target_os = "android")]
@@ -17,15 +33,110 @@ pub use winit::platform::android::activity::AndroidApp;
 #[cfg(target_os = "android")]
 pub static ANDROID_APP: OnceLock<AndroidApp> = OnceLock::new();
 
+use std::time::Duration;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+#[cfg(target_arch = "wasm32")]
+use web_time::Instant;
 use winit::{
     application::ApplicationHandler,
-    event::MouseScrollDelta,
+    event::{DeviceEvent, DeviceId},
     event_loop::{ActiveEventLoop, EventLoop, EventLoopProxy},
-    window::WindowId,
+    window::{CursorGrabMode, WindowId},
 };
 
+/// Largest [`FrameTimer::delta`] in [`RedrawMode::OnEvent`] when no max delta is set,
+/// so the first frame after idling doesn't step the whole idle time at once
+pub const ON_EVENT_MAX_DELTA: f32 = 0.1;
+
+/// Shortest time between two [`AppHandler::resize`] calls while the window is being
+/// resized. Frames in between are drawn at the old size & stretched by the compositor
+pub const RESIZE_DEBOUNCE: Duration = Duration::from_millis(50);
+
+/// Time between two [`AppHandler::skipped_frame`] calls while the window is minimized
+pub const SKIPPED_FRAME_INTERVAL: Duration = Duration::from_millis(16);
+
+/// Time between two [`AppHandler::unrendered_frame`] calls while the window is occluded, see
+/// [`OccludedBehavior::SkipRender`]
+pub const OCCLUDED_FRAME_INTERVAL: Duration = Duration::from_millis(100);
+
+/// When frames are drawn
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RedrawMode {
+    /// Redraw as fast as possible (or at the display rate with vsync), like a game loop
+    #[default]
+    Continuous,
+    /// Sleep until input arrives, a scheduled redraw is due or one is requested.
+    /// For tools & other apps that would otherwise burn a core on an unchanged scene
+    OnEvent,
+}
+
+/// What a web app does while its tab is hidden. Browsers stop animation frames in hidden
+/// tabs & throttle timers, usually to once a second, so no mode runs faster than that
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Background {
+    /// Stop running frames until the tab is visible again
+    #[default]
+    Pause,
+    /// Keep running at up to this many frames per second, e.g. for multiplayer games that
+    /// must keep up with a server
+    ThrottleTo(u32),
+    /// Keep running as often as the browser allows
+    Continue,
+}
+
+impl Background {
+    /// Time between frames while hidden, `None` for none at all
+    #[cfg_attr(not(target_arch = "wasm32"), allow(dead_code))]
+    fn interval(self) -> Option<Duration> {
+        match self {
+            Self::Pause => None,
+            Self::ThrottleTo(fps) => Some(Duration::from_secs(1) / fps.max(1)),
+            Self::Continue => Some(Duration::ZERO),
+        }
+    }
+}
+
+/// What a native app does while its window is fully covered or on another virtual desktop.
+/// Only Windows & macOS report that; elsewhere the window keeps drawing
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OccludedBehavior {
+    /// Stop drawing but keep calling [`AppHandler::unrendered_frame`] every
+    /// [`OCCLUDED_FRAME_INTERVAL`], so the simulation still advances
+    SkipRender,
+    /// Stop running frames until the window is uncovered
+    SkipFrame,
+    /// Keep drawing as if the window were visible
+    #[default]
+    Continue,
+}
+
+impl OccludedBehavior {
+    /// Time between skipped frames while occluded, `None` for none at all
+    fn interval(self) -> Option<Duration> {
+        match self {
+            Self::SkipRender => Some(OCCLUDED_FRAME_INTERVAL),
+            Self::SkipFrame | Self::Continue => None,
+        }
+    }
+}
+
+/// Window & loop settings. With the `serde` feature it (de)serializes with every field
+/// optional, e.g. for a settings file players can edit
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
 pub struct AppConfig {
+    /// Not saved, since it's how the app is built rather than a player setting
+    #[cfg_attr(feature = "serde", serde(skip))]
     pub control_flow: ControlFlow,
+    /// Not saved, for the same reason as `control_flow`. [`RedrawMode::OnEvent`] waits
+    /// between frames regardless of `control_flow`
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub redraw_mode: RedrawMode,
     pub title: String,
     pub width: Option<u32>,
     pub height: Option<u32>,
@@ -35,12 +146,40 @@ pub struct AppConfig {
     pub decorations: bool,
     pub min_size: Option<(u32, u32)>,
     pub max_size: Option<(u32, u32)>,
+    /// Outer window position in physical pixels, relative to the selected monitor if any
+    pub position: Option<(i32, i32)>,
+    /// Index into the available monitors; out of range falls back to the primary monitor
+    pub monitor: Option<usize>,
+    pub always_on_top: bool,
+    /// Let transparent pixels show what's behind the window. Not saved, since the app has to
+    /// draw for it
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub transparent: bool,
+    /// Sync presentation to the display's refresh rate
+    pub vsync: bool,
+    /// Number of recent frame times [`FrameTimer`] keeps for statistics
+    pub frame_history: usize,
+    /// Largest [`FrameTimer::delta`] reported in seconds, if clamped
+    pub max_delta: Option<f32>,
+    /// Web only: keep right-click from opening the browser context menu over the canvas
+    pub prevent_context_menu: bool,
+    /// Web only: keep the mouse wheel from scrolling the page while over the canvas
+    pub capture_scroll: bool,
+    /// Web only: what the app does while its tab is hidden. Not saved, since the game
+    /// decides what it can afford to miss
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub background: Background,
+    /// Native only: what the app does while its window is covered, for the same reason as
+    /// `background` not saved
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub occluded: OccludedBehavior,
 }
 
 impl Default for AppConfig {
     fn default() -> Self {
         Self {
             control_flow: ControlFlow::Poll,
+            redraw_mode: RedrawMode::Continuous,
             title: "Egor App".to_string(),
             width: None,
             height: None,
@@ -50,10 +189,42 @@ impl Default for AppConfig {
             decorations: true,
             min_size: None,
             max_size: None,
+            position: None,
+            monitor: None,
+            always_on_top: false,
+            transparent: false,
+            vsync: true,
+            frame_history: time::DEFAULT_FRAME_HISTORY,
+            max_delta: None,
+            prevent_context_menu: false,
+            capture_scroll: false,
+            background: Background::Pause,
+            occluded: OccludedBehavior::Continue,
         }
     }
 }
 
+/// Returns the monitor at `index`, falling back to the primary (or first) monitor when out of range
+pub fn select_monitor(
+    monitors: impl Iterator<Item = MonitorHandle>,
+    primary: Option<MonitorHandle>,
+    index: usize,
+) -> Option<MonitorHandle> {
+    let mut monitors = monitors.collect::<Vec<_>>();
+    if index < monitors.len() {
+        return Some(monitors.swap_remove(index));
+    }
+    primary.or_else(|| monitors.into_iter().next())
+}
+
+/// A change of the window's size in physical pixels, see [`AppHandler::resize`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Resize {
+    /// Size frames were drawn at until now
+    pub previous: (u32, u32),
+    pub size: (u32, u32),
+}
+
 /// Trait defining application behavior
 ///
 /// Implement this for your app logic. Hooks are called during window creation,
@@ -64,16 +235,58 @@ pub trait AppHandler<R> {
     fn resumed(&mut self, _window: Arc<Window>, _resource: &mut R) {}
     /// Called when app is suspended (happens for Android in background)
     fn suspended(&mut self) {}
+    /// Called with `false` when the page is hidden, e.g. by switching tabs, or the window is
+    /// covered (see [`OccludedBehavior`]), & with `true` once it's shown again. Frames in
+    /// between follow [`AppConfig::background`] or [`AppConfig::occluded`] & the first one
+    /// after is [`FrameTimer::resumed`]
+    fn visibility_changed(&mut self, _visible: bool) {}
     /// Called for every WindowEvent before default input handling
     fn on_window_event(&mut self, _window: &Window, _event: &WindowEvent) {}
-    /// Called once the window exists; should create & return the resource
-    async fn with_resource(&mut self, _window: Arc<Window>) -> R;
+    /// Called once the window exists; should create & return the resource.
+    /// An error is reported & ends the app (on the web the page stays, without frames)
+    async fn with_resource(&mut self, _window: Arc<Window>) -> Result<R, Box<dyn Error>>;
     /// Called after the resource is initialized & window is ready
     fn on_ready(&mut self, _window: &Window, _resource: &mut R) {}
+    /// Checked before every frame; returning `false` skips the redraw without advancing the
+    /// timer, e.g. while the resource is still busy with the last frame. The handler then
+    /// requests a redraw once it's ready
+    fn frame_ready(&mut self, _resource: &mut R) -> bool {
+        true
+    }
     /// Called every frame
     fn frame(&mut self, _window: &Window, _resource: &mut R, _input: &Input, _timer: &FrameTimer) {}
-    /// Called on window resize
-    fn resize(&mut self, _w: u32, _h: u32, _resource: &mut R) {}
+    /// Called instead of [`Self::frame`] every [`SKIPPED_FRAME_INTERVAL`] while the window is
+    /// minimized. For work that can't stall, like networking
+    fn skipped_frame(&mut self, _input: &Input, _timer: &FrameTimer) {}
+    /// Called instead of [`Self::frame`] every [`OCCLUDED_FRAME_INTERVAL`] while the window
+    /// is occluded with [`OccludedBehavior::SkipRender`]. Should advance the app without
+    /// drawing; runs [`Self::skipped_frame`] by default
+    fn unrendered_frame(
+        &mut self,
+        _window: &Window,
+        _resource: &mut R,
+        input: &Input,
+        timer: &FrameTimer,
+    ) {
+        self.skipped_frame(input, timer);
+    }
+    /// Called when the window's size changes, right before the first frame drawn at the new
+    /// size. The first change after a pause applies at once; a live resize at most every
+    /// [`RESIZE_DEBOUNCE`] & always with the final size. Never called with a zero size or an
+    /// unchanged one; frames are skipped while the window is minimized
+    fn resize(&mut self, _resize: Resize, _resource: &mut R) {}
+    /// Checked after every frame; returning an exit code ends the loop once the frame is done
+    fn exit_requested(&self) -> Option<i32> {
+        None
+    }
+    /// Checked after every frame when not redrawing continuously; returning a delay schedules
+    /// another frame after it, `Duration::ZERO` for as soon as possible
+    fn redraw_requested(&mut self) -> Option<Duration> {
+        None
+    }
+    /// Called once before the event loop exits, whether the window was closed or
+    /// [`Self::exit_requested`] returned a code
+    fn on_quit(&mut self) {}
 }
 
 /// Generic application entry point
@@ -89,6 +302,24 @@ pub struct AppRunner<R: 'static, H: AppHandler<R> + 'static> {
     input: Input,
     timer: FrameTimer,
     config: AppConfig,
+    exit_code: Option<i32>,
+    /// Earliest frame scheduled by [`AppHandler::redraw_requested`] while waiting
+    redraw_at: Option<Instant>,
+    resize: PendingResize,
+    /// Last [`AppHandler::skipped_frame`] call while minimized or occluded
+    skipped_at: Option<Instant>,
+    /// Whether the window is covered & frames are skipped, see [`OccludedBehavior`]
+    occluded: bool,
+    /// Whether the page is hidden, see [`Background`]
+    #[cfg(target_arch = "wasm32")]
+    hidden: bool,
+    /// Next frame while the page is hidden
+    #[cfg(target_arch = "wasm32")]
+    background_at: Option<Instant>,
+    #[cfg(feature = "serde")]
+    playback: Option<record::Playback>,
+    #[cfg(feature = "log")]
+    log: logging::LogConfig,
 }
 
 #[doc(hidden)]
@@ -99,6 +330,9 @@ impl<R, H: AppHandler<R> + 'static> ApplicationHandler<(R, H)> for AppRunner<R,
             self.resource.as_mut(),
             self.handler.as_mut(),
         ) {
+            // back from the background (Android), the time away isn't a frame
+            self.resize.applied = Some(window.inner_size());
+            self.timer.resume();
             handler.resumed(window, resource);
         }
 
@@ -107,10 +341,26 @@ impl<R, H: AppHandler<R> + 'static> ApplicationHandler<(R, H)> for AppRunner<R,
             return;
         };
 
+        // monitors & positions don't exist on the web, the canvas lives in the page
+        #[cfg(not(target_arch = "wasm32"))]
+        let monitor = self.config.monitor.and_then(|i| {
+            select_monitor(
+                event_loop.available_monitors(),
+                event_loop.primary_monitor(),
+                i,
+            )
+        });
+        #[cfg(target_arch = "wasm32")]
+        let monitor: Option<MonitorHandle> = None;
+
         let fullscreen = match self.config.fullscreen {
-            true => Some(Fullscreen::Borderless(None)),
+            true => Some(Fullscreen::Borderless(monitor.clone())),
             false => None,
         };
+        let level = match self.config.always_on_top {
+            true => WindowLevel::AlwaysOnTop,
+            false => WindowLevel::Normal,
+        };
 
         let mut win_attrs = Window::default_attributes()
             .with_visible(false)
@@ -118,19 +368,42 @@ impl<R, H: AppHandler<R> + 'static> ApplicationHandler<(R, H)> for AppRunner<R,
             .with_resizable(self.config.resizable)
             .with_maximized(self.config.maximized)
             .with_fullscreen(fullscreen)
-            .with_decorations(self.config.decorations);
+            .with_decorations(self.config.decorations)
+            .with_transparent(self.config.transparent)
+            .with_window_level(level);
 
         if let (Some(w), Some(h)) = (self.config.width, self.config.height) {
             win_attrs = win_attrs.with_inner_size(PhysicalSize::new(w, h));
         }
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let origin = monitor
+                .as_ref()
+                .map_or(PhysicalPosition::new(0, 0), |m| m.position());
+            match self.config.position {
+                Some((x, y)) => {
+                    win_attrs =
+                        win_attrs.with_position(PhysicalPosition::new(origin.x + x, origin.y + y));
+                }
+                None if monitor.is_some() => win_attrs = win_attrs.with_position(origin),
+                None => {}
+            }
+        }
         #[cfg(target_arch = "wasm32")]
         {
             use winit::platform::web::WindowAttributesExtWebSys;
-            win_attrs = win_attrs.with_append(true);
+            // web.rs keeps winit's page default suppression, minus wheel & context menu
+            win_attrs = win_attrs.with_append(true).with_prevent_default(false);
         }
 
         let window = Arc::new(event_loop.create_window(win_attrs).unwrap());
         self.window = Some(window.clone());
+        #[cfg(target_arch = "wasm32")]
+        web::capture_canvas_events(
+            &window,
+            self.config.prevent_context_menu,
+            self.config.capture_scroll,
+        );
 
         if let Some((w, h)) = self.config.min_size {
             window.set_min_inner_size(Some(PhysicalSize::new(w, h)));
@@ -143,14 +416,20 @@ impl<R, H: AppHandler<R> + 'static> ApplicationHandler<(R, H)> for AppRunner<R,
         #[cfg(target_arch = "wasm32")]
         {
             wasm_bindgen_futures::spawn_local(async move {
-                let resource = handler.with_resource(window).await;
-                _ = proxy.send_event((resource, handler));
+                match handler.with_resource(window).await {
+                    Ok(resource) => _ = proxy.send_event((resource, handler)),
+                    Err(e) => web::log_error(&format!("failed to initialize: {e}")),
+                }
             });
         }
         #[cfg(not(target_arch = "wasm32"))]
-        {
-            let resource = pollster::block_on(handler.with_resource(window));
-            _ = proxy.send_event((resource, handler));
+        match pollster::block_on(handler.with_resource(window)) {
+            Ok(resource) => _ = proxy.send_event((resource, handler)),
+            Err(e) => {
+                log::error!(target: "egor_app::init", "failed to initialize: {e}");
+                self.handler = Some(handler);
+                self.exit(event_loop, 1);
+            }
         }
     }
 
@@ -161,65 +440,238 @@ impl<R, H: AppHandler<R> + 'static> ApplicationHandler<(R, H)> for AppRunner<R,
     }
 
     fn window_event(&mut self, event_loop: &ActiveEventLoop, _: WindowId, event: WindowEvent) {
+        // egui & game input both see the corrected position
+        #[cfg(target_arch = "wasm32")]
+        let mut event = event;
+        #[cfg(target_arch = "wasm32")]
+        if let Some(window) = &self.window {
+            web::map_pointer_to_canvas(window, &mut event);
+        }
+        #[cfg(target_arch = "wasm32")]
+        if matches!(
+            event,
+            WindowEvent::Resized(_) | WindowEvent::ScaleFactorChanged { .. }
+        ) {
+            web::refresh_safe_area();
+        }
         if let Some(handler) = &mut self.handler {
             handler.on_window_event(self.window.as_ref().unwrap(), &event);
         }
+        if self.config.redraw_mode == RedrawMode::OnEvent
+            && wakes_redraw(&event)
+            && let Some(window) = &self.window
+        {
+            window.request_redraw();
+        }
 
         match event {
-            WindowEvent::CloseRequested => event_loop.exit(),
-            WindowEvent::RedrawRequested => {
-                let Some(window) = &self.window else { return };
-                let (Some(resource), Some(handler)) = (&mut self.resource, &mut self.handler)
-                else {
-                    return;
-                };
-
-                self.timer.update();
-                handler.frame(window, resource, &self.input, &self.timer);
-                self.input.end_frame();
-
-                if self.config.control_flow == ControlFlow::Poll {
+            WindowEvent::CloseRequested => self.exit(event_loop, 0),
+            WindowEvent::RedrawRequested => self.redraw(event_loop),
+            // the page was hidden or shown, or the canvas scrolled out of view
+            #[cfg(target_arch = "wasm32")]
+            WindowEvent::Occluded(_) => self.sync_visibility(),
+            #[cfg(not(target_arch = "wasm32"))]
+            WindowEvent::Occluded(occluded) => {
+                if self.set_occluded(occluded)
+                    && let Some(window) = &self.window
+                {
                     window.request_redraw();
                 }
             }
+            // applied on the next frame, so a live resize reconfigures the surface at most
+            // every `RESIZE_DEBOUNCE` instead of on every event
             WindowEvent::Resized(size) => {
-                if size.width == 0 || size.height == 0 {
-                    return;
+                self.resize.push(size);
+                if let Some(window) = self.window.as_ref().filter(|_| !self.resize.minimized) {
+                    window.request_redraw();
                 }
-
-                if let (Some(resource), Some(handler)) =
-                    (self.resource.as_mut(), self.handler.as_mut())
-                {
-                    handler.resize(size.width, size.height, resource);
+            }
+            _ => {
+                for input in InputEvent::from_window_event(&event) {
+                    self.handle_input(input);
                 }
             }
-            WindowEvent::KeyboardInput { event, .. } => self.input.update_key(event),
-            WindowEvent::MouseInput { button, state, .. } => {
-                self.input.update_mouse_button(button, state)
+        }
+    }
+
+    fn device_event(&mut self, _: &ActiveEventLoop, _: DeviceId, event: DeviceEvent) {
+        if let Some(input) = InputEvent::from_device_event(&event) {
+            self.handle_input(input);
+            // locked pointers only report motion here, not as cursor moves
+            if self.config.redraw_mode == RedrawMode::OnEvent
+                && self.input.pointer_locked()
+                && let Some(window) = &self.window
+            {
+                window.request_redraw();
             }
-            WindowEvent::CursorMoved { position, .. } => self.input.update_cursor(position),
-            WindowEvent::MouseWheel { delta, .. } => {
-                let wheel_delta = match delta {
-                    MouseScrollDelta::LineDelta(_, y) => y,
-                    MouseScrollDelta::PixelDelta(pos) => pos.y as f32 / 100.0,
-                };
-                self.input.update_scroll(wheel_delta);
+        }
+    }
+
+    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        #[cfg(target_arch = "wasm32")]
+        if self.hidden && self.exit_code.is_none() {
+            return self.background_frame(event_loop);
+        }
+        if self.resize.minimized && self.exit_code.is_none() {
+            return self.skip_frames_every(event_loop, SKIPPED_FRAME_INTERVAL);
+        }
+        if self.occluded && self.exit_code.is_none() {
+            return match self.config.occluded.interval() {
+                Some(interval) => self.skip_frames_every(event_loop, interval),
+                None => event_loop.set_control_flow(ControlFlow::Wait),
+            };
+        }
+        self.skipped_at = None;
+        if self.config.control_flow == ControlFlow::Poll {
+            // restores polling after minimizing switched to timed waits
+            event_loop.set_control_flow(self.poll_flow());
+            return;
+        }
+        if let Some(at) = self.redraw_at
+            && Instant::now() >= at
+        {
+            self.redraw_at = None;
+            if let Some(window) = &self.window {
+                window.request_redraw();
             }
-            _ => {}
         }
+        event_loop.set_control_flow(match self.redraw_at {
+            Some(at) => ControlFlow::WaitUntil(at),
+            None => self.config.control_flow,
+        });
     }
 
-    fn user_event(&mut self, _: &ActiveEventLoop, (mut resource, mut handler): (R, H)) {
+    fn user_event(&mut self, event_loop: &ActiveEventLoop, (mut resource, mut handler): (R, H)) {
         let Some(window) = &self.window else { return };
 
+        self.resize.applied = Some(window.inner_size());
         handler.on_ready(window, &mut resource);
         handler.frame(window, &mut resource, &self.input, &self.timer);
+        let exit_code = handler.exit_requested();
 
         window.set_visible(true);
         window.request_redraw();
 
         self.resource = Some(resource);
         self.handler = Some(handler);
+        if let Some(code) = exit_code {
+            self.exit(event_loop, code);
+        }
+    }
+}
+
+/// Whether `event` is input (or a window change) worth a new frame in [`RedrawMode::OnEvent`]
+fn wakes_redraw(event: &WindowEvent) -> bool {
+    matches!(
+        event,
+        WindowEvent::KeyboardInput { .. }
+            | WindowEvent::ModifiersChanged(_)
+            | WindowEvent::Ime(_)
+            | WindowEvent::MouseInput { .. }
+            | WindowEvent::MouseWheel { .. }
+            | WindowEvent::CursorMoved { .. }
+            | WindowEvent::CursorEntered { .. }
+            | WindowEvent::CursorLeft { .. }
+            | WindowEvent::Touch(_)
+            | WindowEvent::Focused(_)
+            | WindowEvent::Resized(_)
+            | WindowEvent::ScaleFactorChanged { .. }
+            | WindowEvent::Occluded(false)
+    )
+}
+
+/// Maps a pointer `position` in CSS pixels times `scale_factor`, as the browser reports it
+/// relative to the canvas, to pixels of the canvas' `backing` size. They differ when the
+/// canvas is stretched by CSS (e.g. `width: 100vw`) while rendering at another resolution.
+/// `css` is the canvas' size on the page in CSS pixels
+#[cfg_attr(not(target_arch = "wasm32"), allow(dead_code))]
+fn canvas_position(
+    position: PhysicalPosition<f64>,
+    scale_factor: f64,
+    css: (f64, f64),
+    backing: (u32, u32),
+) -> PhysicalPosition<f64> {
+    let axis = |reported: f64, css: f64, backing: u32| {
+        // hidden or unlaid-out canvases have no CSS size to map from
+        if css <= 0.0 || backing == 0 {
+            return reported;
+        }
+        reported / scale_factor * (backing as f64 / css)
+    };
+    PhysicalPosition::new(
+        axis(position.x, css.0, backing.0),
+        axis(position.y, css.1, backing.1),
+    )
+}
+
+/// Space the window's edges lose to notches, rounded corners & system bars, as
+/// `[top, right, bottom, left]` in physical pixels of the window. Browsers report it for
+/// pages with `viewport-fit=cover` (as `cargo xtask run --wasm` writes), Android from the
+/// activity's content area; elsewhere it's all zeros
+pub fn safe_area_insets(window: &Window) -> [f32; 4] {
+    platform_safe_area(window)
+}
+
+#[cfg(target_arch = "wasm32")]
+use web::safe_area_insets as platform_safe_area;
+
+#[cfg(target_os = "android")]
+fn platform_safe_area(window: &Window) -> [f32; 4] {
+    let Some(app) = ANDROID_APP.get() else {
+        return [0.0; 4];
+    };
+    let content = app.content_rect();
+    let rect = [content.left, content.top, content.right, content.bottom];
+    insets_around(rect, window.inner_size().into())
+}
+
+#[cfg(not(any(target_arch = "wasm32", target_os = "android")))]
+fn platform_safe_area(_: &Window) -> [f32; 4] {
+    [0.0; 4]
+}
+
+/// Insets of the `[left, top, right, bottom]` content rect within a window of `size`, none
+/// before the content area is laid out
+#[cfg_attr(not(target_os = "android"), allow(dead_code))]
+fn insets_around([left, top, right, bottom]: [i32; 4], (w, h): (u32, u32)) -> [f32; 4] {
+    if right <= left || bottom <= top {
+        return [0.0; 4];
+    }
+    let inset = |space: i64| space.max(0) as f32;
+    [
+        inset(top as i64),
+        inset(w as i64 - right as i64),
+        inset(h as i64 - bottom as i64),
+        inset(left as i64),
+    ]
+}
+
+/// Locks (or confines, where locking isn't supported) & hides the cursor, or releases it.
+/// Returns whether the cursor ended up grabbed
+fn set_pointer_lock(window: &Window, locked: bool) -> bool {
+    let grabbed = match locked {
+        true => window
+            .set_cursor_grab(CursorGrabMode::Locked)
+            .or_else(|_| window.set_cursor_grab(CursorGrabMode::Confined))
+            .is_ok(),
+        false => {
+            _ = window.set_cursor_grab(CursorGrabMode::None);
+            false
+        }
+    };
+    window.set_cursor_visible(!grabbed);
+    grabbed
+}
+
+/// Enables the input method with its candidate window by the `caret` area (x, y, width,
+/// height), or disables it
+fn set_text_input(window: &Window, caret: Option<[f32; 4]>) {
+    window.set_ime_allowed(caret.is_some());
+    if let Some([x, y, width, height]) = caret {
+        window.set_ime_cursor_area(
+            PhysicalPosition::new(x, y),
+            PhysicalSize::new(width, height),
+        );
     }
 }
 
@@ -232,38 +684,287 @@ impl<R, H: AppHandler<R> + 'static> AppRunner<R, H> {
             window: None,
             proxy: None,
             input: Input::default(),
-            timer: FrameTimer::default(),
+            timer: FrameTimer::with_history(config.frame_history)
+                .with_max_delta(max_delta(&config)),
             config,
+            exit_code: None,
+            redraw_at: None,
+            resize: PendingResize::default(),
+            skipped_at: None,
+            occluded: false,
+            #[cfg(target_arch = "wasm32")]
+            hidden: false,
+            #[cfg(target_arch = "wasm32")]
+            background_at: None,
+            #[cfg(feature = "serde")]
+            playback: None,
+            #[cfg(feature = "log")]
+            log: logging::LogConfig::default(),
+        }
+    }
+
+    /// Log levels & sinks of the logger installed by [`Self::run`]
+    #[cfg(feature = "log")]
+    pub fn logging(mut self, config: logging::LogConfig) -> Self {
+        self.log = config;
+        self
+    }
+
+    /// Record live input to a file or replay a recording instead of live input.
+    /// Events are tied to frame numbers, so replays are frame-accurate; pair them with a
+    /// fixed timestep to reproduce simulation bugs exactly
+    #[cfg(feature = "serde")]
+    pub fn input_playback(mut self, playback: record::InputPlayback) -> Self {
+        self.playback = Some(playback.into());
+        self
+    }
+
+    /// Feeds live input to [`Input`], recording it or dropping it during a replay
+    fn handle_input(&mut self, input: InputEvent) {
+        #[cfg(feature = "serde")]
+        match &mut self.playback {
+            #[cfg(not(target_arch = "wasm32"))]
+            Some(record::Playback::Recording { recording, .. }) => {
+                recording.events.push((self.timer.frame, input.clone()));
+            }
+            Some(record::Playback::Replaying { .. }) => {
+                if let InputEvent::Key { key, pressed: true } = input
+                    && key == record::REPLAY_ABORT_KEY
+                {
+                    log::info!(target: "egor_app::record", "input replay aborted");
+                    self.playback = None;
+                }
+                return;
+            }
+            None => {}
         }
+        self.input.apply(input, self.timer.now());
+    }
+
+    /// Runs a frame, on `RedrawRequested` or from a timer while the page is hidden
+    fn redraw(&mut self, event_loop: &ActiveEventLoop) {
+        #[cfg(target_arch = "wasm32")]
+        self.sync_visibility();
+        #[cfg(all(feature = "serde", not(target_arch = "wasm32")))]
+        self.save_recording(false);
+        let Some(window) = &self.window else { return };
+        let (Some(resource), Some(handler)) = (&mut self.resource, &mut self.handler) else {
+            return;
+        };
+        // no next frame is requested either, so the loop sleeps until uncovered
+        if self.exit_code.is_some() || self.resize.minimized || self.occluded {
+            return;
+        }
+        if !handler.frame_ready(resource) {
+            return;
+        }
+
+        #[cfg(feature = "serde")]
+        replay_due_input(&mut self.playback, &self.timer, &mut self.input);
+
+        // sizes the window has but no `Resized` reported yet still apply before drawing
+        self.resize.sync(window.inner_size());
+        let now = Instant::now();
+        if let Some(resize) = self.resize.take_due(now) {
+            handler.resize(resize, resource);
+        }
+        // a debounced size still needs a frame once it's due
+        if let Some(at) = self.resize.due_at()
+            && self.config.control_flow != ControlFlow::Poll
+        {
+            self.redraw_at = Some(self.redraw_at.map_or(at, |prev| prev.min(at)));
+        }
+
+        self.timer.update();
+        #[cfg(feature = "log")]
+        logging::set_frame(self.timer.frame);
+        // the browser drops the lock on Escape without telling winit
+        #[cfg(target_arch = "wasm32")]
+        self.input.set_pointer_locked(web::pointer_locked());
+        handler.frame(window, resource, &self.input, &self.timer);
+        if let Some(locked) = self.input.take_pointer_lock_request() {
+            self.input
+                .set_pointer_locked(set_pointer_lock(window, locked));
+        }
+        if let Some(caret) = self.input.take_text_input_change() {
+            set_text_input(window, caret);
+        }
+        self.input.end_frame();
+
+        if let Some(code) = handler.exit_requested() {
+            return self.exit(event_loop, code);
+        }
+        if self.config.control_flow == ControlFlow::Poll {
+            window.request_redraw();
+        } else if let Some(delay) = handler.redraw_requested() {
+            let at = Instant::now() + delay;
+            self.redraw_at = Some(self.redraw_at.map_or(at, |prev| prev.min(at)));
+        }
+    }
+
+    /// Writes an input recording in progress to its file, periodically or right away if
+    /// `force`, so a crash doesn't lose the whole session
+    #[cfg(all(feature = "serde", not(target_arch = "wasm32")))]
+    fn save_recording(&mut self, force: bool) {
+        if let Some(playback) = &mut self.playback
+            && let Err((path, e)) = playback.save(Instant::now(), force)
+        {
+            log::warn!(
+                target: "egor_app::record",
+                "failed to save input recording to {}: {e}",
+                path.display()
+            );
+        }
+    }
+
+    /// Control flow of a continuously redrawing app. On the web each frame requests the next
+    /// animation frame, so frames follow `requestAnimationFrame` & polling would only spin
+    fn poll_flow(&self) -> ControlFlow {
+        match cfg!(target_arch = "wasm32") {
+            true => ControlFlow::Wait,
+            false => ControlFlow::Poll,
+        }
+    }
+
+    /// Tells the handler when the page is hidden or shown again. The first frame after gets
+    /// a clamped delta, since no frame (or only throttled ones) ran while hidden
+    #[cfg(target_arch = "wasm32")]
+    fn sync_visibility(&mut self) {
+        let hidden = web::page_hidden();
+        if hidden == self.hidden {
+            return;
+        }
+        self.hidden = hidden;
+        if let Some(handler) = self.handler.as_mut() {
+            handler.visibility_changed(!hidden);
+        }
+        self.background_at = None;
+        if !hidden {
+            self.timer.resume();
+            if let Some(window) = &self.window {
+                window.request_redraw();
+            }
+        }
+    }
+
+    /// Runs frames from timers while the page is hidden, since animation frames stop
+    #[cfg(target_arch = "wasm32")]
+    fn background_frame(&mut self, event_loop: &ActiveEventLoop) {
+        let Some(interval) = self.config.background.interval() else {
+            return event_loop.set_control_flow(ControlFlow::Wait);
+        };
+        let now = Instant::now();
+        let at = match self.background_at {
+            Some(at) if now < at => at,
+            _ => {
+                self.redraw(event_loop);
+                now + interval
+            }
+        };
+        self.background_at = Some(at);
+        event_loop.set_control_flow(ControlFlow::WaitUntil(at));
+    }
+
+    /// Follows the window being covered or uncovered, unless [`OccludedBehavior::Continue`].
+    /// Frames stop requesting the next one while covered, so this returns `true` when
+    /// uncovering must request it, & the first frame back gets a clamped delta
+    #[cfg(not(target_arch = "wasm32"))]
+    fn set_occluded(&mut self, occluded: bool) -> bool {
+        if occluded == self.occluded || self.config.occluded == OccludedBehavior::Continue {
+            return false;
+        }
+        self.occluded = occluded;
+        self.skipped_at = None;
+        if let Some(handler) = self.handler.as_mut() {
+            handler.visibility_changed(!occluded);
+        }
+        if !occluded {
+            self.timer.resume();
+        }
+        !occluded
+    }
+
+    /// Runs [`Self::skip_frame`] every `interval` while the window isn't drawn to, sleeping
+    /// in between
+    fn skip_frames_every(&mut self, event_loop: &ActiveEventLoop, interval: Duration) {
+        let now = Instant::now();
+        let due = self.skipped_at.map(|at| at + interval);
+        if due.is_none_or(|at| now >= at) {
+            self.skipped_at = Some(now);
+            self.skip_frame(event_loop);
+        }
+        let next = self.skipped_at.unwrap_or(now) + interval;
+        event_loop.set_control_flow(ControlFlow::WaitUntil(next));
+    }
+
+    /// Runs [`AppHandler::skipped_frame`] in place of a frame the minimized window doesn't
+    /// draw, or [`AppHandler::unrendered_frame`] for the occluded one
+    fn skip_frame(&mut self, event_loop: &ActiveEventLoop) {
+        let (Some(window), Some(resource), Some(handler)) =
+            (&self.window, &mut self.resource, &mut self.handler)
+        else {
+            return;
+        };
+        #[cfg(feature = "serde")]
+        replay_due_input(&mut self.playback, &self.timer, &mut self.input);
+
+        self.timer.update();
+        #[cfg(feature = "log")]
+        logging::set_frame(self.timer.frame);
+        match self.occluded && !self.resize.minimized {
+            true => handler.unrendered_frame(window, resource, &self.input, &self.timer),
+            false => handler.skipped_frame(&self.input, &self.timer),
+        }
+        self.input.end_frame();
+        if let Some(code) = handler.exit_requested() {
+            self.exit(event_loop, code);
+        }
+    }
+
+    /// Calls [`AppHandler::on_quit`] once & stops the event loop. Frames stop immediately;
+    /// on the web this detaches the loop from the page instead of exiting a process
+    fn exit(&mut self, event_loop: &ActiveEventLoop, code: i32) {
+        if self.exit_code.is_some() {
+            return;
+        }
+        self.exit_code = Some(code);
+        if let Some(handler) = self.handler.as_mut() {
+            handler.on_quit();
+        }
+        #[cfg(all(feature = "serde", not(target_arch = "wasm32")))]
+        self.save_recording(true);
+        event_loop.exit();
     }
 
     /// Runs the app’s event loop on the current platform
     ///
-    /// Handles Android, WASM and native setups, plus logging and user events
+    /// Handles Android, WASM and native setups, plus logging and user events.
+    /// With the `log` feature it installs the logger set up with [`Self::logging`]
+    /// On native, a non-zero code from [`AppHandler::exit_requested`] becomes the process exit code
     pub fn run(mut self) {
+        #[cfg(feature = "log")]
+        std::mem::take(&mut self.log).install();
+
         let mut event_loop_builder = EventLoop::<(R, H)>::with_user_event();
         #[cfg(target_os = "android")]
         {
-            #[cfg(feature = "log")]
-            android_logger::init_once(Default::default().with_max_level(log::LevelFilter::Info));
-
             use winit::platform::android::EventLoopBuilderExtAndroid;
             let android_app = ANDROID_APP.get().unwrap().clone();
             event_loop_builder.with_android_app(android_app);
         }
 
         let event_loop = event_loop_builder.build().unwrap();
-        event_loop.set_control_flow(self.config.control_flow);
+        if self.config.redraw_mode == RedrawMode::OnEvent {
+            self.config.control_flow = ControlFlow::Wait;
+        }
+        event_loop.set_control_flow(match self.config.control_flow {
+            ControlFlow::Poll => self.poll_flow(),
+            flow => flow,
+        });
         self.proxy = Some(event_loop.create_proxy());
 
         #[cfg(target_arch = "wasm32")]
         {
-            #[cfg(feature = "log")]
-            {
-                std::panic::set_hook(Box::new(console_error_panic_hook::hook));
-                console_log::init_with_level(log::Level::Error).unwrap();
-            }
-
             use winit::platform::web::EventLoopExtWebSys;
             wasm_bindgen_futures::spawn_local(async move {
                 event_loop.spawn_app(self);
@@ -271,10 +972,268 @@ impl<R, H: AppHandler<R> + 'static> AppRunner<R, H> {
         }
         #[cfg(not(target_arch = "wasm32"))]
         {
-            #[cfg(all(feature = "log", not(target_os = "android")))]
-            env_logger::init_from_env(env_logger::Env::default().default_filter_or("error"));
-
             event_loop.run_app(&mut self).unwrap();
+
+            // a non-zero code is for the calling process (e.g. a CI script), so report it
+            if let Some(code) = self.exit_code.filter(|&c| c != 0) {
+                std::process::exit(code);
+            }
+        }
+    }
+}
+
+/// Feeds the recorded events due before frame `frame + 1` to `input`
+#[cfg(feature = "serde")]
+fn replay_due_input(
+    playback: &mut Option<record::Playback>,
+    timer: &FrameTimer,
+    input: &mut Input,
+) {
+    let Some(replay) = playback else { return };
+    for (_, event) in replay.due(timer.frame) {
+        input.apply(event.clone(), timer.now());
+    }
+    if replay.finished() {
+        log::info!(target: "egor_app::record", "input replay finished");
+        *playback = None;
+    }
+}
+
+/// Latest window size not yet passed to [`AppHandler::resize`]
+#[derive(Default)]
+struct PendingResize {
+    size: Option<PhysicalSize<u32>>,
+    /// Size the handler was last told about, or the one it started with
+    applied: Option<PhysicalSize<u32>>,
+    last_applied: Option<Instant>,
+    /// Set while the window is zero-sized, e.g. minimized
+    minimized: bool,
+}
+
+impl PendingResize {
+    fn push(&mut self, size: PhysicalSize<u32>) {
+        self.minimized = size.width == 0 || size.height == 0;
+        if !self.minimized {
+            self.size = Some(size);
+        }
+    }
+
+    /// Queues the window's current `size` if it changed without a pending resize
+    fn sync(&mut self, size: PhysicalSize<u32>) {
+        if self.size.is_none() && !self.minimized && Some(size) != self.applied {
+            self.push(size);
+        }
+    }
+
+    /// The pending size, if it differs from the applied one & [`RESIZE_DEBOUNCE`] passed
+    /// since the last one was applied
+    fn take_due(&mut self, now: Instant) -> Option<Resize> {
+        if self.minimized || self.due_at().is_some_and(|at| now < at) {
+            return None;
+        }
+        let size = self.size.take()?;
+        let previous = self.applied.replace(size).unwrap_or(size);
+        if previous == size {
+            return None;
+        }
+        self.last_applied = Some(now);
+        let resize = Resize {
+            previous: previous.into(),
+            size: size.into(),
+        };
+        Some(resize)
+    }
+
+    /// When the pending size can be applied, if it has to wait for the debounce
+    fn due_at(&self) -> Option<Instant> {
+        self.size
+            .and(self.last_applied)
+            .map(|at| at + RESIZE_DEBOUNCE)
+    }
+}
+
+/// The configured max delta, or [`ON_EVENT_MAX_DELTA`] for on-event redraws without one
+fn max_delta(config: &AppConfig) -> Option<f32> {
+    match config.redraw_mode {
+        RedrawMode::OnEvent => config.max_delta.or(Some(ON_EVENT_MAX_DELTA)),
+        RedrawMode::Continuous => config.max_delta,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn safe_area_is_what_the_content_rect_leaves_of_the_window() {
+        // a notch on top & a navigation bar on the right of a landscape phone
+        let insets = insets_around([0, 80, 2300, 1080], (2400, 1080));
+        assert_eq!(insets, [80.0, 100.0, 0.0, 0.0]);
+        // not laid out yet
+        assert_eq!(insets_around([0; 4], (2400, 1080)), [0.0; 4]);
+    }
+
+    #[test]
+    fn pointers_on_css_scaled_canvases_map_to_backing_pixels() {
+        let map = |x, y, scale, css, backing| {
+            let p = canvas_position(PhysicalPosition::new(x, y), scale, css, backing);
+            (p.x, p.y)
+        };
+        // unscaled: CSS pixels times the DPI scale are backing pixels
+        assert_eq!(
+            map(300.0, 150.0, 2.0, (400.0, 300.0), (800, 600)),
+            (300.0, 150.0)
+        );
+        // an 800x600 canvas stretched to 1600x900 CSS pixels on a 1x display, clicked in
+        // the bottom-right corner, is the backing's bottom-right corner
+        assert_eq!(
+            map(1600.0, 900.0, 1.0, (1600.0, 900.0), (800, 600)),
+            (800.0, 600.0)
+        );
+        assert_eq!(
+            map(400.0, 450.0, 1.0, (1600.0, 900.0), (800, 600)),
+            (200.0, 300.0)
+        );
+        // same on a 1.5x display, where positions arrive pre-multiplied
+        assert_eq!(
+            map(1200.0, 675.0, 1.5, (1600.0, 900.0), (800, 600)),
+            (400.0, 300.0)
+        );
+        // a hidden canvas leaves positions alone
+        assert_eq!(map(10.0, 20.0, 1.0, (0.0, 0.0), (800, 600)), (10.0, 20.0));
+    }
+
+    #[test]
+    fn live_resizes_are_debounced_and_minimizing_pauses() {
+        let resized = |previous, size| Some(Resize { previous, size });
+        let mut resize = PendingResize {
+            applied: Some(PhysicalSize::new(640, 480)),
+            ..Default::default()
+        };
+        let start = Instant::now();
+        resize.push(PhysicalSize::new(800, 600));
+        assert_eq!(resize.take_due(start), resized((640, 480), (800, 600)));
+
+        // dragging the window edge: only the latest size is applied, once it's due
+        resize.push(PhysicalSize::new(810, 600));
+        resize.push(PhysicalSize::new(820, 610));
+        assert_eq!(resize.take_due(start + RESIZE_DEBOUNCE / 2), None);
+        assert_eq!(resize.due_at(), Some(start + RESIZE_DEBOUNCE));
+        let later = start + RESIZE_DEBOUNCE;
+        assert_eq!(resize.take_due(later), resized((800, 600), (820, 610)));
+        assert_eq!(resize.due_at(), None);
+
+        // minimizing never reaches the handler & restoring resumes with the new size
+        resize.push(PhysicalSize::new(0, 0));
+        assert!(resize.minimized);
+        assert_eq!(resize.take_due(later + RESIZE_DEBOUNCE), None);
+        resize.push(PhysicalSize::new(1024, 768));
+        assert!(!resize.minimized);
+        let restored = later + RESIZE_DEBOUNCE;
+        assert_eq!(resize.take_due(restored), resized((820, 610), (1024, 768)));
+    }
+
+    #[test]
+    fn resizes_fire_on_changes_only_and_catch_unreported_sizes() {
+        let size = PhysicalSize::new(800, 600);
+        let mut resize = PendingResize {
+            applied: Some(size),
+            ..Default::default()
+        };
+        let start = Instant::now();
+        // the startup `Resized` repeats the size the handler started with
+        resize.push(size);
+        assert_eq!(resize.take_due(start), None);
+        resize.sync(size);
+        assert_eq!(resize.due_at(), None);
+
+        // toggling fullscreen long after the last resize applies before the next frame,
+        // even when the window reports its size before its `Resized` event
+        let fullscreen = PhysicalSize::new(1920, 1080);
+        resize.sync(fullscreen);
+        let resized = Resize {
+            previous: (800, 600),
+            size: (1920, 1080),
+        };
+        assert_eq!(resize.take_due(start), Some(resized));
+        resize.push(fullscreen);
+        assert_eq!(resize.take_due(start + RESIZE_DEBOUNCE), None);
+    }
+
+    #[test]
+    fn hidden_pages_run_frames_at_the_background_rate() {
+        assert_eq!(Background::default().interval(), None);
+        let throttled = Background::ThrottleTo(4).interval();
+        assert_eq!(throttled, Some(Duration::from_millis(250)));
+        assert_eq!(
+            Background::ThrottleTo(0).interval(),
+            Some(Duration::from_secs(1))
+        );
+        assert_eq!(Background::Continue.interval(), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn occluded_windows_tick_slowly_or_not_at_all() {
+        let config = AppConfig::default();
+        assert_eq!(config.occluded, OccludedBehavior::Continue);
+        assert_eq!(config.occluded.interval(), None);
+        let interval = OccludedBehavior::SkipRender.interval();
+        assert_eq!(interval, Some(OCCLUDED_FRAME_INTERVAL));
+        assert!(OCCLUDED_FRAME_INTERVAL > SKIPPED_FRAME_INTERVAL);
+        assert_eq!(OccludedBehavior::SkipFrame.interval(), None);
+    }
+
+    #[test]
+    fn uncovered_windows_request_a_redraw_and_clamp_the_first_delta() {
+        struct Visibility(Vec<bool>);
+        impl AppHandler<()> for Visibility {
+            async fn with_resource(&mut self, _window: Arc<Window>) -> Result<(), Box<dyn Error>> {
+                Ok(())
+            }
+            fn visibility_changed(&mut self, visible: bool) {
+                self.0.push(visible);
+            }
         }
+        let config = AppConfig {
+            occluded: OccludedBehavior::SkipRender,
+            ..Default::default()
+        };
+        let mut runner = AppRunner::new(Visibility(Vec::new()), config);
+        runner.timer.advance(0.016);
+
+        // covering stops frames without requesting one, repeats change nothing
+        assert!(!runner.set_occluded(true));
+        assert!(!runner.set_occluded(true));
+        assert!(runner.occluded);
+        assert!(runner.set_occluded(false));
+        assert!(!runner.occluded);
+        assert_eq!(runner.handler.as_ref().unwrap().0, [false, true]);
+        // the time spent covered isn't a frame time
+        runner.timer.advance(30.0);
+        assert!(runner.timer.resumed);
+        assert_eq!(runner.timer.delta, 0.016);
+
+        let mut runner = AppRunner::new(Visibility(Vec::new()), AppConfig::default());
+        assert!(!runner.set_occluded(true));
+        assert!(!runner.occluded);
+        assert!(runner.handler.as_ref().unwrap().0.is_empty());
+    }
+
+    #[test]
+    fn on_event_redraws_clamp_idle_gaps() {
+        let mut config = AppConfig {
+            redraw_mode: RedrawMode::OnEvent,
+            ..Default::default()
+        };
+        let mut timer = FrameTimer::default().with_max_delta(max_delta(&config));
+        // a minute without input passes between two frames
+        timer.advance(60.0);
+        assert_eq!((timer.delta, timer.delta_raw), (ON_EVENT_MAX_DELTA, 60.0));
+
+        config.max_delta = Some(0.5);
+        assert_eq!(max_delta(&config), Some(0.5));
+        config.redraw_mode = RedrawMode::Continuous;
+        config.max_delta = None;
+        assert_eq!(max_delta(&config), None);
     }
 }
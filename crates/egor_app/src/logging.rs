@@ -0,0 +1,219 @@
+//! One logger for every platform: messages are filtered per module & go to the platform's
+//! console (stderr on desktop, logcat on Android, the browser console on the web) & to any
+//! [`LogSink`]s, e.g. an in-game console or a crash reporter. See [`LogConfig`]
+//!
+//! Engine messages use these targets, so they can be filtered by subsystem:
+//!
+//! Target | Messages
+//! ---|---
+//! `egor_app::init` | apps that failed to start
+//! `egor_app::record` | input recordings & replays
+//! `egor_render::surface` | lost or outdated surfaces
+//! `egor_glue::app` | GPU & surface setup
+//! `egor_glue::batch` | draws with missing textures or UVs that can't be meant
+//! `egor_glue::shader` | custom shaders that failed to compile
+//! `egor_glue::memory` | textures over the app's texture memory budget
+//! `egor_glue::perf_warnings` | performance warnings
+
+use std::sync::{
+    Arc,
+    atomic::{AtomicU64, Ordering},
+};
+
+pub use log::{Level, LevelFilter};
+use log::{Log, Metadata, Record};
+
+/// Frame number of the running app, stamped on every [`LogRecord`]
+static FRAME: AtomicU64 = AtomicU64::new(0);
+
+/// Sets the frame number stamped on records from now on
+pub(crate) fn set_frame(frame: u64) {
+    FRAME.store(frame, Ordering::Relaxed);
+}
+
+/// A log message as [`LogSink`]s get it
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LogRecord<'a> {
+    pub level: Level,
+    /// Module path of the message, or the `target:` it was logged with
+    pub target: &'a str,
+    pub message: String,
+    /// [`crate::time::FrameTimer::frame`] when it was logged
+    pub frame: u64,
+}
+
+/// Callback getting every message that passes the filter. Called on whichever thread logged
+pub type LogSink = Arc<dyn Fn(&LogRecord) + Send + Sync>;
+
+/// Log levels & sinks, installed when the app runs. Levels set with `RUST_LOG` override
+/// these natively, e.g. `RUST_LOG=warn,egor_render=debug`
+#[derive(Clone)]
+pub struct LogConfig {
+    /// Level of messages from modules without their own
+    pub level: LevelFilter,
+    /// Levels of modules & everything in them; the longest matching path wins
+    pub modules: Vec<(String, LevelFilter)>,
+    pub sinks: Vec<LogSink>,
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        Self {
+            // Android has always logged info to logcat
+            #[cfg(target_os = "android")]
+            level: LevelFilter::Info,
+            #[cfg(not(target_os = "android"))]
+            level: LevelFilter::Error,
+            modules: Vec::new(),
+            sinks: Vec::new(),
+        }
+    }
+}
+
+impl LogConfig {
+    /// Level messages logged to `target` have to be at or above
+    pub fn level_for(&self, target: &str) -> LevelFilter {
+        self.modules
+            .iter()
+            .filter(|(module, _)| {
+                target
+                    .strip_prefix(module.as_str())
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+            })
+            .max_by_key(|(module, _)| module.len())
+            .map_or(self.level, |&(_, level)| level)
+    }
+
+    /// Applies comma separated `level` & `module=level` directives like `RUST_LOG`'s.
+    /// A module without a level logs everything
+    pub fn apply_directives(&mut self, directives: &str) {
+        for directive in directives.split(',').map(str::trim) {
+            match directive.split_once('=') {
+                Some((module, level)) => {
+                    if let Ok(level) = level.trim().parse() {
+                        self.set_module(module.trim(), level);
+                    }
+                }
+                None if directive.is_empty() => {}
+                None => match directive.parse() {
+                    Ok(level) => self.level = level,
+                    Err(_) => self.set_module(directive, LevelFilter::Trace),
+                },
+            }
+        }
+    }
+
+    /// Sets the level of `module`, replacing the one it had
+    pub fn set_module(&mut self, module: &str, level: LevelFilter) {
+        match self.modules.iter_mut().find(|(m, _)| m == module) {
+            Some((_, l)) => *l = level,
+            None => self.modules.push((module.to_owned(), level)),
+        }
+    }
+
+    /// Most verbose level any module logs at
+    fn max_level(&self) -> LevelFilter {
+        let modules = self.modules.iter().map(|&(_, level)| level);
+        modules.fold(self.level, Ord::max)
+    }
+
+    /// Installs the logger, unless the game installed its own
+    pub(crate) fn install(#[allow(unused_mut)] mut self) {
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Ok(directives) = std::env::var("RUST_LOG") {
+            self.apply_directives(&directives);
+        }
+        #[cfg(target_arch = "wasm32")]
+        std::panic::set_hook(Box::new(console_error_panic_hook::hook));
+
+        let max_level = self.max_level();
+        let logger = Logger {
+            config: self,
+            // filtering is done before records get here
+            #[cfg(not(any(target_arch = "wasm32", target_os = "android")))]
+            console: env_logger::Builder::new()
+                .filter_level(LevelFilter::Trace)
+                .build(),
+            // logcat, as stderr goes nowhere on Android. Filter with `adb logcat -s egor`
+            #[cfg(target_os = "android")]
+            console: android_logger::AndroidLogger::new(
+                android_logger::Config::default()
+                    .with_max_level(LevelFilter::Trace)
+                    .with_tag("egor"),
+            ),
+        };
+        if log::set_boxed_logger(Box::new(logger)).is_ok() {
+            log::set_max_level(max_level);
+        }
+    }
+}
+
+struct Logger {
+    config: LogConfig,
+    #[cfg(not(any(target_arch = "wasm32", target_os = "android")))]
+    console: env_logger::Logger,
+    #[cfg(target_os = "android")]
+    console: android_logger::AndroidLogger,
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.config.level_for(metadata.target())
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        if !self.config.sinks.is_empty() {
+            let record = LogRecord {
+                level: record.level(),
+                target: record.target(),
+                message: record.args().to_string(),
+                frame: FRAME.load(Ordering::Relaxed),
+            };
+            for sink in &self.config.sinks {
+                sink(&record);
+            }
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        self.console.log(record);
+        #[cfg(target_arch = "wasm32")]
+        console_log::log(record);
+    }
+
+    fn flush(&self) {
+        #[cfg(not(target_arch = "wasm32"))]
+        self.console.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_longest_matching_module_sets_the_level() {
+        let mut config = LogConfig {
+            level: LevelFilter::Warn,
+            ..Default::default()
+        };
+        config.set_module("egor_render", LevelFilter::Debug);
+        config.set_module("egor_render::surface", LevelFilter::Off);
+
+        assert_eq!(config.level_for("my_game"), LevelFilter::Warn);
+        assert_eq!(config.level_for("egor_render"), LevelFilter::Debug);
+        assert_eq!(config.level_for("egor_render::texture"), LevelFilter::Debug);
+        assert_eq!(config.level_for("egor_render::surface"), LevelFilter::Off);
+        // a module's name isn't a prefix of another's
+        assert_eq!(config.level_for("egor_renderer"), LevelFilter::Warn);
+        assert_eq!(config.max_level(), LevelFilter::Debug);
+
+        config.apply_directives("info, egor_render=error ,egor_glue::batch, nope=loud");
+        assert_eq!(config.level, LevelFilter::Info);
+        assert_eq!(config.level_for("egor_render::texture"), LevelFilter::Error);
+        assert_eq!(config.level_for("egor_glue::batch"), LevelFilter::Trace);
+        assert_eq!(config.level_for("nope"), LevelFilter::Info);
+        assert_eq!(config.modules.len(), 3);
+    }
+}
@@ -0,0 +1,281 @@
+use crate::input::{InputEvent, KeyCode};
+use bincode::error::{DecodeError, EncodeError};
+use std::{fmt, io};
+
+/// Bytes every recording starts with
+const MAGIC: [u8; 4] = *b"EGIR";
+/// Format version written by this egor version. Bumped whenever [`InputEvent`] changes
+pub const RECORDING_VERSION: u32 = 3;
+/// Key that stops a replay & hands input back to the player
+pub const REPLAY_ABORT_KEY: KeyCode = KeyCode::Escape;
+/// How often a recording in progress is written to its file, so a crash loses at most
+/// this much of the session
+#[cfg(not(target_arch = "wasm32"))]
+pub const RECORDING_SAVE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Errors from reading or writing an [`InputRecording`]
+#[derive(Debug)]
+pub enum RecordingError {
+    /// The file couldn't be read or written
+    Io(io::Error),
+    /// The data isn't an input recording
+    NotARecording,
+    /// The recording was made by an egor version with another format
+    UnsupportedVersion(u32),
+    /// The events couldn't be decoded
+    Decode(DecodeError),
+    /// The events couldn't be encoded
+    Encode(EncodeError),
+}
+
+impl fmt::Display for RecordingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "input recording file error: {e}"),
+            Self::NotARecording => write!(f, "not an input recording"),
+            Self::UnsupportedVersion(v) => write!(
+                f,
+                "input recording version {v} is unsupported (expected {RECORDING_VERSION})"
+            ),
+            Self::Decode(e) => write!(f, "invalid input recording: {e}"),
+            Self::Encode(e) => write!(f, "couldn't write input recording: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for RecordingError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            Self::Decode(e) => Some(e),
+            Self::Encode(e) => Some(e),
+            Self::NotARecording | Self::UnsupportedVersion(_) => None,
+        }
+    }
+}
+
+impl From<io::Error> for RecordingError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+/// Input events of a session, each tagged with the [`crate::time::FrameTimer::frame`] it
+/// arrived after. Replaying feeds them to the same frames, independent of wall-clock time
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct InputRecording {
+    pub events: Vec<(u64, InputEvent)>,
+}
+
+impl InputRecording {
+    /// Encode as a versioned binary blob
+    pub fn to_bytes(&self) -> Result<Vec<u8>, RecordingError> {
+        bincode::serde::encode_to_vec(
+            (MAGIC, RECORDING_VERSION, &self.events),
+            bincode::config::standard(),
+        )
+        .map_err(RecordingError::Encode)
+    }
+
+    /// Decode a blob written by [`Self::to_bytes`]
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, RecordingError> {
+        let config = bincode::config::standard();
+        let ((magic, version), read): (([u8; 4], u32), _) =
+            bincode::serde::decode_from_slice(bytes, config)
+                .map_err(|_| RecordingError::NotARecording)?;
+        if magic != MAGIC {
+            return Err(RecordingError::NotARecording);
+        }
+        if version != RECORDING_VERSION {
+            return Err(RecordingError::UnsupportedVersion(version));
+        }
+        let (events, _) = bincode::serde::decode_from_slice(&bytes[read..], config)
+            .map_err(RecordingError::Decode)?;
+        Ok(Self { events })
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load(path: impl AsRef<std::path::Path>) -> Result<Self, RecordingError> {
+        Self::from_bytes(&std::fs::read(path)?)
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn save(&self, path: impl AsRef<std::path::Path>) -> Result<(), RecordingError> {
+        std::fs::write(path, self.to_bytes()?)?;
+        Ok(())
+    }
+}
+
+/// What the runner does with input, see [`crate::AppRunner::input_playback`]
+pub enum InputPlayback {
+    /// Record live input & save it to the path every [`RECORDING_SAVE_INTERVAL`] & when
+    /// the app exits
+    #[cfg(not(target_arch = "wasm32"))]
+    Record(std::path::PathBuf),
+    /// Feed recorded input instead of live input until it runs out or
+    /// [`REPLAY_ABORT_KEY`] is pressed
+    Replay(InputRecording),
+}
+
+/// Recording or replay in progress
+pub(crate) enum Playback {
+    #[cfg(not(target_arch = "wasm32"))]
+    Recording {
+        path: std::path::PathBuf,
+        recording: InputRecording,
+        /// When & with how many events the file was last written
+        saved: (std::time::Instant, usize),
+    },
+    Replaying {
+        recording: InputRecording,
+        next: usize,
+    },
+}
+
+impl Playback {
+    /// Events recorded after `frame` or earlier that weren't replayed yet
+    pub(crate) fn due(&mut self, frame: u64) -> &[(u64, InputEvent)] {
+        let Self::Replaying { recording, next } = self else {
+            return &[];
+        };
+        let start = *next;
+        let pending = &recording.events[start..];
+        *next += pending.partition_point(|(f, _)| *f <= frame);
+        &recording.events[start..*next]
+    }
+
+    /// Writes a recording in progress to its file if it has unsaved events & `force` is set
+    /// or [`RECORDING_SAVE_INTERVAL`] passed since the last write. Returns the path on failure
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn save(
+        &mut self,
+        now: std::time::Instant,
+        force: bool,
+    ) -> Result<(), (&std::path::Path, RecordingError)> {
+        let Self::Recording {
+            path,
+            recording,
+            saved,
+        } = self
+        else {
+            return Ok(());
+        };
+        let due = force || now >= saved.0 + RECORDING_SAVE_INTERVAL;
+        if !due || recording.events.len() == saved.1 {
+            return Ok(());
+        }
+        *saved = (now, recording.events.len());
+        recording.save(&*path).map_err(|e| (path.as_path(), e))
+    }
+
+    /// Whether every recorded event was replayed
+    pub(crate) fn finished(&self) -> bool {
+        matches!(self, Self::Replaying { recording, next } if *next >= recording.events.len())
+    }
+}
+
+impl From<InputPlayback> for Playback {
+    fn from(playback: InputPlayback) -> Self {
+        match playback {
+            #[cfg(not(target_arch = "wasm32"))]
+            InputPlayback::Record(path) => Self::Recording {
+                path,
+                recording: InputRecording::default(),
+                saved: (std::time::Instant::now(), 0),
+            },
+            InputPlayback::Replay(recording) => Self::Replaying { recording, next: 0 },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::MouseButton;
+
+    fn recording() -> InputRecording {
+        InputRecording {
+            events: vec![
+                (0, InputEvent::CursorMoved { x: 12.5, y: 40.0 }),
+                (
+                    3,
+                    InputEvent::Key {
+                        key: KeyCode::KeyW,
+                        pressed: true,
+                    },
+                ),
+                (
+                    3,
+                    InputEvent::MouseButton {
+                        button: MouseButton::Left,
+                        pressed: true,
+                    },
+                ),
+                (7, InputEvent::Scroll(-1.0)),
+            ],
+        }
+    }
+
+    #[test]
+    fn recordings_round_trip_and_check_their_version() {
+        let bytes = recording().to_bytes().unwrap();
+        assert_eq!(InputRecording::from_bytes(&bytes).unwrap(), recording());
+
+        let future = bincode::serde::encode_to_vec(
+            (MAGIC, RECORDING_VERSION + 1, &recording().events),
+            bincode::config::standard(),
+        )
+        .unwrap();
+        assert!(matches!(
+            InputRecording::from_bytes(&future),
+            Err(RecordingError::UnsupportedVersion(v)) if v == RECORDING_VERSION + 1
+        ));
+        assert!(matches!(
+            InputRecording::from_bytes(b"PNG\x89 not input"),
+            Err(RecordingError::NotARecording)
+        ));
+    }
+
+    #[test]
+    fn replay_feeds_events_to_their_frames() {
+        let mut playback = Playback::from(InputPlayback::Replay(recording()));
+        assert_eq!(playback.due(0).len(), 1);
+        assert!(playback.due(2).is_empty());
+        // events from skipped frames still arrive, in order
+        let due: Vec<_> = playback.due(5).iter().map(|(f, _)| *f).collect();
+        assert_eq!(due, [3, 3]);
+        assert!(!playback.finished());
+        assert_eq!(playback.due(7).len(), 1);
+        assert!(playback.finished());
+    }
+
+    #[test]
+    fn recordings_in_progress_are_saved_periodically() {
+        let path = std::env::temp_dir().join(format!("egor_record_{}.bin", std::process::id()));
+        let mut playback = Playback::from(InputPlayback::Record(path.clone()));
+        let Playback::Recording {
+            recording: live,
+            saved,
+            ..
+        } = &mut playback
+        else {
+            unreachable!()
+        };
+        let start = saved.0;
+        live.events = recording().events;
+
+        playback.save(start, false).unwrap();
+        assert!(!path.exists());
+        playback
+            .save(start + RECORDING_SAVE_INTERVAL, false)
+            .unwrap();
+        assert_eq!(InputRecording::load(&path).unwrap(), recording());
+
+        // nothing new to write, even when forced
+        std::fs::remove_file(&path).unwrap();
+        playback
+            .save(start + RECORDING_SAVE_INTERVAL * 3, true)
+            .unwrap();
+        assert!(!path.exists());
+    }
+}
@@ -1,65 +1,129 @@
+use std::cell::RefCell;
 #[cfg(not(target_arch = "wasm32"))]
 use std::time::Instant;
 
 #[cfg(target_arch = "wasm32")]
-fn now() -> f32 {
-    (web_sys::window().unwrap().performance().unwrap().now() / 1000.0) as f32
+fn now() -> f64 {
+    web_sys::window().unwrap().performance().unwrap().now() / 1000.0
 }
 
 #[cfg(not(target_arch = "wasm32"))]
-fn now(start: Instant) -> f32 {
-    start.elapsed().as_secs_f32()
+fn now(start: Instant) -> f64 {
+    start.elapsed().as_secs_f64()
 }
 
+/// Number of frame times kept by default, 2 seconds at 60 FPS
+pub const DEFAULT_FRAME_HISTORY: usize = 120;
+
 pub struct FrameTimer {
     #[cfg(not(target_arch = "wasm32"))]
     start: Instant,
     last_time: f32,
     accumulator: f32,
     frame_count: u32,
-    /// Time in seconds since the last frame
+    /// Ring buffer of frame times in milliseconds, written twice (at `i` & `i + capacity`)
+    /// so the latest frames are always a contiguous slice
+    history: Vec<f32>,
+    history_next: usize,
+    history_len: usize,
+    /// Frame times partially sorted for percentiles, reused so they don't allocate
+    scratch: RefCell<Vec<f32>>,
+    max_delta: Option<f32>,
+    /// Set while the app is in the background, see [`Self::resumed`]
+    resuming: bool,
+    /// Time in seconds since the last frame, clamped to the max delta if one is set.
+    /// Use this to integrate game logic
     pub delta: f32,
+    /// Unclamped time in seconds since the last frame
+    pub delta_raw: f32,
     /// Frames per second, updated once per second
     pub fps: u32,
     /// Total number of frames rendered since start
     pub frame: u64,
+    /// True on the first frame after the app was in the background (a hidden browser tab, a
+    /// covered window or a suspended Android app). Its [`Self::delta`] is that of the last
+    /// frame before, rather than the whole time away, & it's left out of the statistics.
+    /// [`Self::delta_raw`] still has the real time
+    pub resumed: bool,
 }
 
 impl Default for FrameTimer {
     fn default() -> Self {
+        Self::with_history(DEFAULT_FRAME_HISTORY)
+    }
+}
+
+impl FrameTimer {
+    /// Create a timer keeping the last `len` frame times for statistics (at least 1)
+    pub fn with_history(len: usize) -> Self {
         Self {
             #[cfg(not(target_arch = "wasm32"))]
             start: Instant::now(),
             last_time: 0.0,
             accumulator: 0.0,
             frame_count: 0,
+            history: vec![0.0; len.max(1) * 2],
+            history_next: 0,
+            history_len: 0,
+            scratch: RefCell::new(Vec::with_capacity(len.max(1))),
+            max_delta: None,
+            resuming: false,
             delta: 0.0,
+            delta_raw: 0.0,
             fps: 0,
             frame: 0,
+            resumed: false,
         }
     }
-}
 
-impl FrameTimer {
+    /// Clamp [`Self::delta`] to at most `max_delta` seconds, so a long hitch doesn't make the
+    /// simulation take one huge step. [`Self::delta_raw`] & statistics still see the real time
+    pub fn with_max_delta(mut self, max_delta: Option<f32>) -> Self {
+        self.max_delta = max_delta;
+        self
+    }
+
+    /// Seconds on the timer's clock: since it was created on native, since the page loaded
+    /// on the web. Both are monotonic & keep counting while a browser tab is hidden.
+    /// Input timestamps like [`crate::input::Input::key_press_time`] use it too
+    pub fn now(&self) -> f64 {
+        #[cfg(not(target_arch = "wasm32"))]
+        return now(self.start);
+        #[cfg(target_arch = "wasm32")]
+        now()
+    }
+
     /// Updates delta time & calculates FPS
     pub(crate) fn update(&mut self) {
-        let cur_time = {
-            #[cfg(not(target_arch = "wasm32"))]
-            {
-                now(self.start)
-            }
-            #[cfg(target_arch = "wasm32")]
-            {
-                now()
-            }
-        };
-
-        self.delta = cur_time - self.last_time;
+        let cur_time = self.now() as f32;
+
+        self.advance(cur_time - self.last_time);
         self.last_time = cur_time;
+    }
 
-        self.accumulator += self.delta;
-        self.frame_count += 1;
+    /// Advance one frame by a fixed `delta` in seconds, ignoring the wall clock.
+    /// Used for deterministic stepping; FPS is derived from the simulated time
+    pub fn advance(&mut self, delta: f32) {
+        self.delta_raw = delta;
         self.frame += 1;
+        self.resumed = std::mem::take(&mut self.resuming);
+        if self.resumed {
+            // time spent in the background isn't a frame time
+            let last = self.frame_times().last().map_or(delta, |ms| ms / 1000.0);
+            self.delta = self.clamp(delta.min(last));
+            return;
+        }
+        self.delta = self.clamp(delta);
+
+        self.accumulator += delta;
+        self.frame_count += 1;
+
+        let capacity = self.history.len() / 2;
+        let ms = delta * 1000.0;
+        self.history[self.history_next] = ms;
+        self.history[self.history_next + capacity] = ms;
+        self.history_next = (self.history_next + 1) % capacity;
+        self.history_len = (self.history_len + 1).min(capacity);
 
         if self.accumulator >= 1.0 {
             self.fps = self.frame_count;
@@ -67,4 +131,139 @@ impl FrameTimer {
             self.accumulator = 0.0;
         }
     }
+
+    /// Marks the next frame as [`Self::resumed`]
+    pub(crate) fn resume(&mut self) {
+        self.resuming = true;
+    }
+
+    fn clamp(&self, delta: f32) -> f32 {
+        self.max_delta.map_or(delta, |max| delta.min(max))
+    }
+
+    /// Recent frame times in milliseconds, oldest first. Handy for drawing a frame time graph
+    pub fn frame_times(&self) -> &[f32] {
+        let end = self.history_next + self.history.len() / 2;
+        &self.history[end - self.history_len..end]
+    }
+
+    /// Number of frame times [`Self::frame_times`] holds once enough frames have passed
+    pub fn frame_history(&self) -> usize {
+        self.history.len() / 2
+    }
+
+    /// Average FPS over [`Self::frame_times`]; steadier than [`Self::fps`] for display
+    pub fn fps_avg(&self) -> f32 {
+        let times = self.frame_times();
+        let total: f32 = times.iter().sum();
+        match total > 0.0 {
+            true => times.len() as f32 * 1000.0 / total,
+            false => 0.0,
+        }
+    }
+
+    /// 95th percentile frame time in milliseconds; exposes stutters an average hides
+    pub fn frame_time_ms_p95(&self) -> f32 {
+        self.frame_time_percentile(0.95)
+    }
+
+    /// Longest recent frame time in milliseconds
+    pub fn frame_time_ms_max(&self) -> f32 {
+        self.frame_times().iter().copied().fold(0.0, f32::max)
+    }
+
+    /// Nearest-rank percentile of the recent frame times, `p` in [0..1]
+    fn frame_time_percentile(&self, p: f32) -> f32 {
+        let times = self.frame_times();
+        if times.is_empty() {
+            return 0.0;
+        }
+        let mut scratch = self.scratch.borrow_mut();
+        scratch.clear();
+        scratch.extend_from_slice(times);
+        let rank = ((p * times.len() as f32).ceil() as usize).clamp(1, times.len());
+        *scratch.select_nth_unstable_by(rank - 1, f32::total_cmp).1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timer_with(history: usize, frames_ms: &[f32]) -> FrameTimer {
+        let mut timer = FrameTimer::with_history(history);
+        for ms in frames_ms {
+            timer.advance(ms / 1000.0);
+        }
+        timer
+    }
+
+    #[test]
+    fn frame_times_keep_the_latest_in_order() {
+        let timer = timer_with(3, &[1.0, 2.0]);
+        assert_eq!(timer.frame_times(), &[1.0, 2.0]);
+
+        let timer = timer_with(3, &[1.0, 2.0, 3.0, 4.0, 5.0]);
+        assert_eq!(timer.frame_times(), &[3.0, 4.0, 5.0]);
+    }
+
+    #[test]
+    fn percentile_exposes_stutters_the_average_hides() {
+        // 1 second at ~60 FPS with one 200ms spike every 20 frames
+        let frames: Vec<f32> = (1..=60)
+            .map(|i| if i % 20 == 0 { 200.0 } else { 16.0 })
+            .collect();
+        let timer = timer_with(120, &frames);
+
+        assert_eq!(timer.frame_time_ms_max(), 200.0);
+        // 3 of 60 frames (5%) spiked, so the nearest-rank p95 is the last normal frame
+        assert_eq!(timer.frame_time_ms_p95(), 16.0);
+        let timer = timer_with(120, &[frames.as_slice(), &[200.0]].concat());
+        assert_eq!(timer.frame_time_ms_p95(), 200.0);
+
+        let avg_ms = (57.0 * 16.0 + 3.0 * 200.0) / 60.0;
+        assert!((timer_with(120, &frames).fps_avg() - 1000.0 / avg_ms).abs() < 1e-3);
+    }
+
+    #[test]
+    fn empty_history_reports_zero() {
+        let timer = FrameTimer::default();
+        assert!(timer.frame_times().is_empty());
+        assert_eq!(timer.frame_history(), DEFAULT_FRAME_HISTORY);
+        assert_eq!(timer.fps_avg(), 0.0);
+        assert_eq!(timer.frame_time_ms_p95(), 0.0);
+        assert_eq!(timer.frame_time_ms_max(), 0.0);
+    }
+
+    #[test]
+    fn max_delta_clamps_only_the_reported_delta() {
+        let mut timer = FrameTimer::default().with_max_delta(Some(0.1));
+        timer.advance(0.016);
+        assert_eq!((timer.delta, timer.delta_raw), (0.016, 0.016));
+
+        timer.advance(0.5);
+        assert_eq!((timer.delta, timer.delta_raw), (0.1, 0.5));
+        assert_eq!(timer.frame_time_ms_max(), 500.0);
+    }
+
+    #[test]
+    fn resumed_frames_step_like_the_last_one() {
+        let mut timer = timer_with(120, &[16.0, 20.0]);
+        timer.resume();
+        timer.advance(30.0);
+        assert!(timer.resumed);
+        assert!((timer.delta - 0.02).abs() < 1e-6);
+        assert_eq!(timer.delta_raw, 30.0);
+        assert_eq!(timer.frame_times().len(), 2);
+        assert_eq!(timer.frame, 3);
+
+        timer.advance(0.016);
+        assert!(!timer.resumed);
+        assert_eq!(timer.delta, 0.016);
+
+        // a short time away keeps its own delta
+        timer.resume();
+        timer.advance(0.005);
+        assert_eq!(timer.delta, 0.005);
+    }
 }
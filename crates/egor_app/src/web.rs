@@ -0,0 +1,203 @@
+use std::cell::RefCell;
+use wasm_bindgen::{JsCast, closure::Closure};
+use web_sys::{AddEventListenerOptions, Event, HtmlElement, MouseEvent};
+use winit::{
+    dpi::PhysicalPosition, event::WindowEvent, platform::web::WindowExtWebSys, window::Window,
+};
+
+use crate::canvas_position;
+
+/// Suppress the page's default handling of canvas events the way winit does with its
+/// `prevent_default` on (arrows & Space don't scroll, Tab doesn't move focus, presses don't
+/// select text), except right-clicks & the mouse wheel, which only the app's opt-ins capture.
+/// winit's own handling is off since its one flag covers all of these at once
+pub(crate) fn capture_canvas_events(window: &Window, context_menu: bool, scroll: bool) {
+    let Some(canvas) = window.canvas() else {
+        return;
+    };
+    let listen = |event_type: &str, handler: fn(&HtmlElement, Event)| {
+        let target: HtmlElement = canvas.clone().unchecked_into();
+        let listener =
+            Closure::<dyn FnMut(Event)>::new(move |event: Event| handler(&target, event));
+        // wheel listeners are passive by default in most browsers, which ignores preventDefault
+        let options = AddEventListenerOptions::new();
+        options.set_passive(false);
+        _ = canvas.add_event_listener_with_callback_and_add_event_listener_options(
+            event_type,
+            listener.as_ref().unchecked_ref(),
+            &options,
+        );
+        // lives as long as the canvas
+        listener.forget();
+    };
+    let prevent = |_: &HtmlElement, event: Event| event.prevent_default();
+    // keeps text from being selected while still focusing the canvas, as winit does
+    let press = |canvas: &HtmlElement, event: Event| {
+        event.prevent_default();
+        _ = canvas.focus();
+    };
+    // only chorded moves, i.e. with a button held
+    let drag = |canvas: &HtmlElement, event: Event| {
+        if event
+            .dyn_ref::<MouseEvent>()
+            .is_some_and(|e| e.buttons() != 0)
+        {
+            event.prevent_default();
+            _ = canvas.focus();
+        }
+    };
+    listen("keydown", prevent);
+    listen("keyup", prevent);
+    listen("touchstart", prevent);
+    listen("pointerdown", press);
+    listen("pointermove", drag);
+    if context_menu {
+        listen("contextmenu", prevent);
+    }
+    if scroll {
+        listen("wheel", prevent);
+    }
+}
+
+/// Moves pointer positions from scaled CSS pixels (offsets within the canvas' padding box,
+/// so wherever the canvas sits on the page) into the canvas' backing pixels, which egui
+/// & the renderer work in
+pub(crate) fn map_pointer_to_canvas(window: &Window, event: &mut WindowEvent) {
+    let position: &mut PhysicalPosition<f64> = match event {
+        WindowEvent::CursorMoved { position, .. } => position,
+        WindowEvent::Touch(touch) => &mut touch.location,
+        _ => return,
+    };
+    let Some(canvas) = window.canvas() else {
+        return;
+    };
+    let css = (canvas.client_width() as f64, canvas.client_height() as f64);
+    let backing = (canvas.width(), canvas.height());
+    *position = canvas_position(*position, window.scale_factor(), css, backing);
+}
+
+/// Hidden element padded by the page's safe area & the insets last read from it
+struct SafeArea {
+    probe: HtmlElement,
+    /// `None` until read & again after [`refresh_safe_area`]
+    insets: Option<[f32; 4]>,
+}
+
+thread_local! {
+    static SAFE_AREA: RefCell<Option<SafeArea>> = const { RefCell::new(None) };
+}
+
+/// Re-read the safe area on the next [`safe_area_insets`]. Called when the canvas is resized
+/// or its scale factor changes, which turning the phone also does
+pub(crate) fn refresh_safe_area() {
+    SAFE_AREA.with_borrow_mut(|area| {
+        if let Some(area) = area {
+            area.insets = None;
+        }
+    });
+}
+
+/// The page's `env(safe-area-inset-*)` as `[top, right, bottom, left]`, scaled from CSS
+/// pixels to the canvas' backing pixels like pointer positions. Computing the probe's style
+/// forces a layout, so it's only read again after [`refresh_safe_area`]
+pub(crate) fn safe_area_insets(window: &Window) -> [f32; 4] {
+    let Some(page) = web_sys::window() else {
+        return [0.0; 4];
+    };
+    SAFE_AREA.with_borrow_mut(|area| {
+        if area.is_none() {
+            *area = safe_area_probe(&page).map(|probe| SafeArea {
+                probe,
+                insets: None,
+            });
+        }
+        let Some(area) = area else {
+            return [0.0; 4];
+        };
+        *area
+            .insets
+            .get_or_insert_with(|| measure_safe_area(&page, window, &area.probe))
+    })
+}
+
+fn measure_safe_area(page: &web_sys::Window, window: &Window, probe: &HtmlElement) -> [f32; 4] {
+    let (Some(canvas), Ok(Some(style))) = (window.canvas(), page.get_computed_style(probe)) else {
+        return [0.0; 4];
+    };
+    let px = |property: &str| {
+        let value = style.get_property_value(property).unwrap_or_default();
+        value.trim_end_matches("px").parse::<f32>().unwrap_or(0.0)
+    };
+    // hidden or unlaid-out canvases have no CSS size to scale from
+    let scale = |backing: u32, css: i32| match css > 0 {
+        true => backing as f32 / css as f32,
+        false => window.scale_factor() as f32,
+    };
+    let (sx, sy) = (
+        scale(canvas.width(), canvas.client_width()),
+        scale(canvas.height(), canvas.client_height()),
+    );
+    [
+        px("padding-top") * sy,
+        px("padding-right") * sx,
+        px("padding-bottom") * sy,
+        px("padding-left") * sx,
+    ]
+}
+
+fn safe_area_probe(page: &web_sys::Window) -> Option<HtmlElement> {
+    let document = page.document()?;
+    let probe: HtmlElement = document.create_element("div").ok()?.dyn_into().ok()?;
+    probe
+        .set_attribute(
+            "style",
+            "position:fixed;top:0;left:0;width:0;height:0;visibility:hidden;pointer-events:none;\
+         padding:env(safe-area-inset-top) env(safe-area-inset-right) \
+         env(safe-area-inset-bottom) env(safe-area-inset-left)",
+        )
+        .ok()?;
+    document.body()?.append_child(&probe).ok()?;
+    Some(probe)
+}
+
+/// True if the page currently holds a pointer lock
+pub(crate) fn pointer_locked() -> bool {
+    web_sys::window()
+        .and_then(|w| w.document())
+        .is_some_and(|d| d.pointer_lock_element().is_some())
+}
+
+/// True while the page is hidden, e.g. in a background tab or a minimized browser
+pub(crate) fn page_hidden() -> bool {
+    web_sys::window()
+        .and_then(|w| w.document())
+        .is_some_and(|d| d.hidden())
+}
+
+/// Writes to the browser console whether or not the `log` feature set up a logger
+pub(crate) fn log_error(message: &str) {
+    web_sys::console::error_1(&message.into());
+}
+
+/// Hides the app's canvas & shows `html` in its place, e.g. to explain why the app can't run.
+/// Falls back to the page body if the canvas isn't in the page
+pub fn show_html_over_canvas(window: &Window, html: &str) {
+    let Some(document) = web_sys::window().and_then(|w| w.document()) else {
+        return;
+    };
+    let Ok(message) = document.create_element("div") else {
+        return;
+    };
+    message.set_inner_html(html);
+    let canvas = window.canvas();
+    let parent = canvas
+        .as_ref()
+        .and_then(|c| c.parent_element())
+        .or_else(|| document.body().map(Into::into));
+    if let Some(parent) = parent {
+        _ = parent.append_child(&message);
+    }
+    if let Some(canvas) = canvas {
+        canvas.set_hidden(true);
+    }
+}
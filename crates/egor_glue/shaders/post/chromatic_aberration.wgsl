@@ -0,0 +1,10 @@
+// params.x: how far red & blue split in pixels at the edges, growing from nothing at the center
+@fragment
+fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
+    let uv = input.tex_coords;
+    let shift = (uv - 0.5) * 2.0 * effect.params.x / effect.resolution.xy;
+    let color = scene(uv);
+    let red = scene(uv + shift).r;
+    let blue = scene(uv - shift).b;
+    return vec4<f32>(red, color.g, blue, color.a);
+}
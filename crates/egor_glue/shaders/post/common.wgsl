@@ -0,0 +1,20 @@
+//! egor:prelude
+struct PostEffect {
+    // the effect's own parameters
+    params: vec4<f32>,
+    // size of the target in pixels in xy
+    resolution: vec4<f32>,
+}
+@group(2) @binding(0) var<uniform> effect: PostEffect;
+
+// stretches the unit quad over the whole target, whatever the camera
+@vertex
+fn vs_main(vert: VertexInput, inst: InstanceInput) -> VertexOutput {
+    var out = egor_vertex(vert, inst);
+    out.position = vec4<f32>(vert.position * vec2<f32>(2.0, -2.0), 0.0, 1.0);
+    return out;
+}
+
+fn scene(uv: vec2<f32>) -> vec4<f32> {
+    return textureSample(texture_binding, texture_sampler, uv);
+}
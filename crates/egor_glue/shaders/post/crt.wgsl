@@ -0,0 +1,12 @@
+// params.x: screen curvature, params.y: how dark every other row of pixels gets
+@fragment
+fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
+    let centered = input.tex_coords * 2.0 - 1.0;
+    let warped = centered * (1.0 + effect.params.x * centered.yx * centered.yx);
+    let uv = warped * 0.5 + 0.5;
+    let color = scene(uv);
+    let scanline = 0.5 - 0.5 * sin(uv.y * effect.resolution.y * 3.14159265);
+    let shade = 1.0 - effect.params.y * scanline;
+    let inside = all(uv >= vec2<f32>(0.0)) && all(uv <= vec2<f32>(1.0));
+    return select(vec4<f32>(0.0, 0.0, 0.0, 1.0), vec4<f32>(color.rgb * shade, color.a), inside);
+}
@@ -0,0 +1,10 @@
+// params.x: block size in pixels
+@fragment
+fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
+    let resolution = effect.resolution.xy;
+    let block = max(effect.params.x, 1.0);
+    let start = floor(input.tex_coords * resolution / block) * block;
+    // blocks cut off by the right & bottom edges sample their own center, not past the edge
+    let end = min(start + block, resolution);
+    return scene((start + end) * 0.5 / resolution);
+}
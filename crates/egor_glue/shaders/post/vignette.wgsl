@@ -0,0 +1,8 @@
+// params.x: strength, 0 is off & 1 fades the corners to black
+@fragment
+fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
+    let color = scene(input.tex_coords);
+    let dist = length(input.tex_coords * 2.0 - 1.0);
+    let shade = 1.0 - effect.params.x * smoothstep(0.5, 1.42, dist);
+    return vec4<f32>(color.rgb * shade, color.a);
+}
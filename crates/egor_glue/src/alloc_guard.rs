@@ -0,0 +1,199 @@
+//! Catches heap allocations in egor's per-frame render path, see
+//! `Graphics::assert_no_frame_allocations`
+//!
+//! egor never installs a global allocator for you, since an app can only have one. To turn
+//! the guard on, enable the `alloc_guard` feature & add these lines to your binary:
+//!
+//! ```ignore
+//! #[global_allocator]
+//! static GLOBAL: egor::alloc_guard::CountingAlloc = egor::alloc_guard::CountingAlloc;
+//! ```
+//!
+//! Guarded code runs inside a scope; in debug builds the first allocation in it captures a
+//! backtrace & the scope panics with it once it ends. Without `CountingAlloc` installed
+//! scopes do nothing
+
+#[cfg(any(test, feature = "alloc_guard"))]
+mod tracking {
+    use std::{
+        alloc::{GlobalAlloc, Layout, System},
+        backtrace::Backtrace,
+        cell::{Cell, RefCell},
+    };
+
+    thread_local! {
+        /// Allocations made on this thread, so parallel tests don't skew each other
+        pub static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+        pub static ENABLED: Cell<bool> = const { Cell::new(false) };
+        /// Set while a [`super::scope`] is running
+        pub static ARMED: Cell<bool> = const { Cell::new(false) };
+        /// Where the first allocation inside a scope happened
+        pub static VIOLATION: RefCell<Option<Backtrace>> = const { RefCell::new(None) };
+    }
+
+    /// Global allocator that counts allocations per thread & records the first one made while
+    /// a guarded scope is armed. Everything else is passed through to [`System`]
+    pub struct CountingAlloc;
+
+    impl CountingAlloc {
+        fn record(&self) {
+            let _ = ALLOCATIONS.try_with(|a| a.set(a.get() + 1));
+            // disarm first, capturing the backtrace allocates too
+            if matches!(ARMED.try_with(|armed| armed.replace(false)), Ok(true)) {
+                let backtrace = Backtrace::force_capture();
+                let _ = VIOLATION.try_with(|v| {
+                    v.borrow_mut().get_or_insert(backtrace);
+                });
+            }
+        }
+    }
+
+    unsafe impl GlobalAlloc for CountingAlloc {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            self.record();
+            unsafe { System.alloc(layout) }
+        }
+
+        unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+            self.record();
+            unsafe { System.alloc_zeroed(layout) }
+        }
+
+        unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+            self.record();
+            unsafe { System.realloc(ptr, layout, new_size) }
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            unsafe { System.dealloc(ptr, layout) }
+        }
+    }
+
+    #[cfg(test)]
+    #[global_allocator]
+    static GLOBAL: CountingAlloc = CountingAlloc;
+}
+
+#[cfg(feature = "alloc_guard")]
+pub use tracking::CountingAlloc;
+
+/// Turn the guard on or off for this thread
+#[cfg(feature = "alloc_guard")]
+pub(crate) fn set_enabled(enabled: bool) {
+    tracking::ENABLED.set(enabled);
+}
+
+/// Allocations made on this thread so far
+#[cfg(test)]
+pub(crate) fn allocations() -> usize {
+    tracking::ALLOCATIONS.with(std::cell::Cell::get)
+}
+
+/// Guarded region, see [`scope`]
+#[must_use]
+pub(crate) struct Scope {
+    #[cfg(any(test, all(feature = "alloc_guard", debug_assertions)))]
+    armed: bool,
+}
+
+/// Guard the code until the returned scope drops. Nested scopes belong to the outermost one
+#[inline]
+pub(crate) fn scope() -> Scope {
+    #[cfg(any(test, all(feature = "alloc_guard", debug_assertions)))]
+    {
+        let armed = tracking::ENABLED.get() && !tracking::ARMED.get();
+        if armed {
+            tracking::ARMED.set(true);
+        }
+        Scope { armed }
+    }
+    #[cfg(not(any(test, all(feature = "alloc_guard", debug_assertions))))]
+    Scope {}
+}
+
+/// Unguarded region, see [`pause`]
+#[must_use]
+pub(crate) struct Pause {
+    #[cfg(any(test, all(feature = "alloc_guard", debug_assertions)))]
+    restore: (bool, bool),
+}
+
+/// Lift the guard until the returned pause drops, for work that's allowed to allocate.
+/// Scopes opened meanwhile don't guard anything
+#[inline]
+pub(crate) fn pause() -> Pause {
+    #[cfg(any(test, all(feature = "alloc_guard", debug_assertions)))]
+    {
+        let restore = (
+            tracking::ENABLED.replace(false),
+            tracking::ARMED.replace(false),
+        );
+        Pause { restore }
+    }
+    #[cfg(not(any(test, all(feature = "alloc_guard", debug_assertions))))]
+    Pause {}
+}
+
+#[cfg(any(test, all(feature = "alloc_guard", debug_assertions)))]
+impl Drop for Pause {
+    fn drop(&mut self) {
+        let (enabled, armed) = self.restore;
+        tracking::ENABLED.set(enabled);
+        // an allocation before the pause may have disarmed the scope already
+        tracking::ARMED.set(armed && tracking::VIOLATION.with_borrow(Option::is_none));
+    }
+}
+
+/// Run `f` unguarded, see [`pause`]
+#[inline]
+pub(crate) fn exempt<R>(f: impl FnOnce() -> R) -> R {
+    let _pause = pause();
+    f()
+}
+
+#[cfg(any(test, all(feature = "alloc_guard", debug_assertions)))]
+impl Drop for Scope {
+    fn drop(&mut self) {
+        if !self.armed {
+            return;
+        }
+        tracking::ARMED.set(false);
+        let violation = tracking::VIOLATION.take();
+        if let Some(backtrace) = violation
+            && !std::thread::panicking()
+        {
+            panic!(
+                "egor's render path allocated with assert_no_frame_allocations on:\n{backtrace}"
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scopes_panic_with_the_first_allocation() {
+        tracking::ENABLED.set(true);
+        let quiet = std::panic::catch_unwind(|| {
+            let _scope = scope();
+            let _exempt = exempt(|| vec![1u8; 16]);
+            let _pause = pause();
+            let _unguarded = scope();
+            std::hint::black_box(vec![1u8; 16]);
+        });
+        assert!(quiet.is_ok(), "exempt allocations don't count");
+
+        let caught = std::panic::catch_unwind(|| {
+            let _scope = scope();
+            let _nested = scope();
+            std::hint::black_box(vec![1u8; 16]);
+        });
+        tracking::ENABLED.set(false);
+        let message = caught.unwrap_err();
+        let message = message.downcast_ref::<String>().unwrap();
+        assert!(message.contains("scopes_panic_with_the_first_allocation"));
+        assert!(!tracking::ARMED.get());
+    }
+}
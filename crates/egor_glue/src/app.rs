@@ -1,44 +1,152 @@
-use std::sync::Arc;
+use std::{error::Error, sync::Arc, time::Duration};
 
-use crate::{graphics::Graphics, primitives::PrimitiveBatch, text::TextRenderer};
-
-#[cfg(feature = "ui")]
-use crate::ui::EguiRenderer;
+use crate::{
+    camera::{Camera, Origin},
+    coords::{Coords, Space},
+    frame::{FrameInput, FrameState},
+    gizmos::GizmoStore,
+    graphics::{Graphics, TextureId},
+    primitives::PrimitiveBatch,
+    profiler::ProfileReport,
+};
 
 use egor_app::{
-    AppConfig, AppHandler, AppRunner, ControlFlow, Fullscreen, PhysicalSize, Window, WindowEvent,
-    input::Input, time::FrameTimer,
+    AppConfig, AppHandler, AppRunner, Background, ControlFlow, Fullscreen, OccludedBehavior,
+    PhysicalPosition, PhysicalSize, RedrawMode, Resize, Window, WindowEvent, WindowLevel,
+    input::{Input, InputSnapshot},
+    select_monitor,
+    time::FrameTimer,
 };
 use egor_render::{
-    MemoryHints, Renderer,
+    AdapterPreference, CommandEncoder, Device, DeviceRequest, Features, GpuDowngrade, Limits,
+    MemoryHints, MemoryReport, PassResources, Queue, Renderer, SurfaceError, TextureFormat,
+    TextureView,
     target::{Backbuffer, RenderTarget},
 };
+use glam::Vec2;
+
+#[cfg(feature = "dialogs")]
+use egor_app::dialogs::Dialogs;
+#[cfg(feature = "log")]
+use egor_app::logging::{LevelFilter, LogConfig, LogRecord};
+#[cfg(feature = "serde")]
+use egor_app::record::InputPlayback;
+#[cfg(all(feature = "serde", not(target_arch = "wasm32")))]
+use egor_app::record::InputRecording;
+
+#[cfg(all(feature = "render_thread", not(target_arch = "wasm32")))]
+use crate::render_thread::RenderThread;
 
-type UpdateFn = dyn FnMut(&mut FrameContext);
+pub(crate) type UpdateFn = dyn FnMut(&mut FrameContext);
+type SurfaceErrorFn = dyn FnMut(SurfaceError);
+type HitchFn = dyn FnMut(f32);
+pub(crate) type ResizeFn = dyn FnMut(Resize, &mut Camera);
+type QuitFn = dyn FnOnce();
+type SuspendFn = dyn FnMut();
+#[cfg(not(all(feature = "render_thread", not(target_arch = "wasm32"))))]
+pub(crate) type CustomPassFn =
+    dyn FnMut(&Device, &Queue, &mut CommandEncoder, &TextureView, &PassResources);
+#[cfg(all(feature = "render_thread", not(target_arch = "wasm32")))]
+pub(crate) type CustomPassFn =
+    dyn FnMut(&Device, &Queue, &mut CommandEncoder, &TextureView, &PassResources) + Send;
+type GpuDowngradeFn = dyn FnOnce(&GpuDowngrade);
+type BeforeFrameFn = dyn FnMut(&Input, &FrameTimer);
+type AfterFrameFn = dyn FnMut(&Input, &FrameTimer, &FrameSummary);
+type InitFn<S> = dyn FnOnce(&mut S, &mut FrameContext);
 
+/// Bound on closures that travel with a frame to the render thread, like custom passes:
+/// `Send` with the `render_thread` feature on native & met by every type otherwise
+#[cfg(all(feature = "render_thread", not(target_arch = "wasm32")))]
+pub trait RenderThreadSend: Send {}
+#[cfg(all(feature = "render_thread", not(target_arch = "wasm32")))]
+impl<T: Send + ?Sized> RenderThreadSend for T {}
+/// Bound on closures that travel with a frame to the render thread, like custom passes:
+/// `Send` with the `render_thread` feature on native & met by every type otherwise
+#[cfg(not(all(feature = "render_thread", not(target_arch = "wasm32"))))]
+pub trait RenderThreadSend {}
+#[cfg(not(all(feature = "render_thread", not(target_arch = "wasm32"))))]
+impl<T: ?Sized> RenderThreadSend for T {}
+
+/// Shown in place of the canvas when the GPU can't be initialized on the web,
+/// see [`App::on_init_error_html`]
+const INIT_ERROR_HTML: &str = "<p>This game requires WebGL2 or WebGPU, which this browser \
+    doesn't support or has disabled.</p><pre>{error}</pre>";
+
+/// Window & presentation controls; window requests are ignored when running headless
 pub struct AppControl<'a> {
-    window: &'a Window,
-    requested_size: Option<(u32, u32)>,
-    requested_vsync: Option<bool>,
+    pub(crate) window: Option<&'a Window>,
+    pub(crate) requested_size: Option<(u32, u32)>,
+    pub(crate) requested_vsync: Option<bool>,
+    pub(crate) exit_code: Option<i32>,
+    pub(crate) redraw_in: Option<Duration>,
+    #[cfg(feature = "dialogs")]
+    pub(crate) dialogs: Option<&'a mut Dialogs>,
 }
 
 impl<'a> AppControl<'a> {
-    /// Request the window to redraw its contents on the next frame
-    pub fn request_redraw(&self) {
-        self.window.request_redraw();
+    /// Request another frame as soon as possible. Only needed with [`RedrawMode::OnEvent`]
+    /// or [`ControlFlow::Wait`], e.g. every frame while an animation plays
+    pub fn request_redraw(&mut self) {
+        self.request_redraw_in(Duration::ZERO);
+    }
+
+    /// Request another frame after `delay`, e.g. to blink a cursor while waiting for input.
+    /// The earliest request of the frame wins
+    pub fn request_redraw_in(&mut self, delay: Duration) {
+        self.redraw_in = Some(self.redraw_in.map_or(delay, |d| d.min(delay)));
     }
 
     /// Set the inner size of the window in physical pixels
     /// Returns the new size depending on platform
     pub fn set_size(&mut self, w: u32, h: u32) {
-        let _ = self.window.request_inner_size(PhysicalSize::new(w, h));
+        if let Some(window) = self.window {
+            let _ = window.request_inner_size(PhysicalSize::new(w, h));
+        }
         self.requested_size = Some((w, h));
     }
 
     /// Enable or disable borderless fullscreen mode
     pub fn set_fullscreen(&self, enabled: bool) {
-        self.window
-            .set_fullscreen(enabled.then_some(Fullscreen::Borderless(None)));
+        if let Some(window) = self.window {
+            window.set_fullscreen(enabled.then_some(Fullscreen::Borderless(None)));
+        }
+    }
+
+    /// Move the window's outer top-left corner to a position in physical desktop pixels.
+    /// Ignored on the web
+    pub fn set_position(&self, x: i32, y: i32) {
+        if let Some(window) = self.window {
+            window.set_outer_position(PhysicalPosition::new(x, y));
+        }
+    }
+
+    /// Move the window to the monitor at `index`, keeping fullscreen on that monitor if active.
+    /// Out of range indices fall back to the primary monitor. Ignored on the web
+    pub fn move_to_monitor(&self, index: usize) {
+        let Some(window) = self.window else {
+            return;
+        };
+        let Some(monitor) =
+            select_monitor(window.available_monitors(), window.primary_monitor(), index)
+        else {
+            return;
+        };
+
+        if window.fullscreen().is_some() {
+            window.set_fullscreen(Some(Fullscreen::Borderless(Some(monitor))));
+        } else {
+            window.set_outer_position(monitor.position());
+        }
+    }
+
+    /// Keep the window above all other windows
+    pub fn set_always_on_top(&self, enabled: bool) {
+        if let Some(window) = self.window {
+            window.set_window_level(match enabled {
+                true => WindowLevel::AlwaysOnTop,
+                false => WindowLevel::Normal,
+            });
+        }
     }
 
     /// Enable or disable vertical sync
@@ -47,16 +155,274 @@ impl<'a> AppControl<'a> {
     pub fn set_vsync(&mut self, on: bool) {
         self.requested_vsync = Some(on);
     }
+
+    /// Quit once the current frame finishes, calling [`App::on_quit`] first.
+    /// On the web the loop stops & detaches from the page instead
+    pub fn request_exit(&mut self) {
+        self.request_exit_with_code(0);
+    }
+
+    /// Like [`Self::request_exit`], but a non-zero `code` becomes the process exit code on
+    /// native, so scripts & CI can detect failures
+    pub fn request_exit_with_code(&mut self, code: i32) {
+        self.exit_code = Some(code);
+    }
+
+    /// Open & save file dialogs, `None` when running headless
+    ///
+    /// ```no_run
+    /// # use egor_glue::app::FrameContext;
+    /// # use egor_app::dialogs::FileFilter;
+    /// # fn f(ctx: &mut FrameContext) {
+    /// if let Some(dialogs) = ctx.app.dialogs() {
+    ///     dialogs.open_file(&[FileFilter::new("Images", &["png"])], |file| {
+    ///         let bytes = file.map(|file| file.read());
+    ///     });
+    /// }
+    /// # }
+    /// ```
+    #[cfg(feature = "dialogs")]
+    pub fn dialogs(&mut self) -> Option<&mut Dialogs> {
+        self.dialogs.as_deref_mut()
+    }
 }
 
+/// Everything the update closure gets each frame. Fields are added over time, so destructure
+/// with a trailing `..` (`|FrameContext { gfx, input, timer, .. }|`) or use the accessors.
+/// `gfx`, `input` & `timer` stay fields for good
+#[non_exhaustive]
 pub struct FrameContext<'a> {
+    /// Window events received since the last frame
     pub events: Vec<WindowEvent>,
     pub app: AppControl<'a>,
     pub gfx: Graphics<'a>,
     pub input: &'a Input,
     pub timer: &'a FrameTimer,
+    /// Mouse cursor position in window pixels
+    pub mouse_screen: Vec2,
+    /// Mouse cursor position in world space, using the camera as it was at the start of the frame.
+    /// If the camera moves during the frame, use [`Self::convert`] with
+    /// [`Self::mouse_screen`] for an up to date position
+    pub mouse_world: Vec2,
     #[cfg(feature = "ui")]
     pub egui_ctx: &'a egui::Context,
+    /// Physical pixels per UI point, see [`Space::Ui`]
+    pub(crate) ui_scale: f32,
+    /// The window resize applied right before this frame, if any
+    pub(crate) resized: Option<Resize>,
+}
+
+impl<'a> FrameContext<'a> {
+    /// Drawing for this frame
+    pub fn gfx(&mut self) -> &mut Graphics<'a> {
+        &mut self.gfx
+    }
+
+    /// Keyboard, mouse & touch state
+    pub fn input(&self) -> &'a Input {
+        self.input
+    }
+
+    /// Frame timing
+    pub fn timer(&self) -> &'a FrameTimer {
+        self.timer
+    }
+
+    /// Window & presentation controls
+    pub fn app(&mut self) -> &mut AppControl<'a> {
+        &mut self.app
+    }
+
+    /// The window resize applied right before this frame, with the previous & new size, e.g.
+    /// to move floating panels proportionally. Several resizes since the last frame are
+    /// merged into one
+    pub fn resized(&self) -> Option<Resize> {
+        self.resized
+    }
+
+    /// Window events received since the last frame
+    pub fn events(&self) -> &[WindowEvent] {
+        &self.events
+    }
+
+    /// See [`Self::mouse_screen`](#structfield.mouse_screen)
+    pub fn mouse_screen(&self) -> Vec2 {
+        self.mouse_screen
+    }
+
+    /// See [`Self::mouse_world`](#structfield.mouse_world)
+    pub fn mouse_world(&self) -> Vec2 {
+        self.mouse_world
+    }
+
+    /// egui context to build this frame's UI with
+    #[cfg(feature = "ui")]
+    pub fn ui(&self) -> &'a egui::Context {
+        self.egui_ctx
+    }
+
+    /// Physical pixels per UI point, e.g. to size on-screen controls for the display's density
+    pub fn ui_scale(&self) -> f32 {
+        self.ui_scale
+    }
+
+    /// Transforms between screen, UI & world space with the camera as it is now
+    pub fn coords(&self) -> Coords {
+        self.gfx.coords()
+    }
+
+    /// Converts `point` between spaces with the camera as it is now, e.g.
+    /// `ctx.convert(ctx.mouse_screen, Space::Screen, Space::World)`
+    pub fn convert(&self, point: Vec2, from: Space, to: Space) -> Vec2 {
+        self.coords().convert(point, from, to)
+    }
+}
+
+mod sealed {
+    pub trait Sealed<Marker> {}
+}
+
+/// Update functions [`App::run_update`] accepts, told apart by `Marker`:
+/// - `fn(&mut FrameContext)`, like [`App::run`]
+/// - `fn(&mut Graphics, &Input, &FrameTimer)`, the signature of older egor versions
+///
+/// Sealed, so signatures can be added without breaking anything
+pub trait FrameUpdate<Marker>: sealed::Sealed<Marker> + 'static {
+    #[doc(hidden)]
+    fn update(&mut self, ctx: &mut FrameContext);
+}
+
+impl<F: FnMut(&mut FrameContext) + 'static> sealed::Sealed<fn(&mut FrameContext)> for F {}
+impl<F: FnMut(&mut FrameContext) + 'static> FrameUpdate<fn(&mut FrameContext)> for F {
+    fn update(&mut self, ctx: &mut FrameContext) {
+        self(ctx)
+    }
+}
+
+type PartsMarker = fn(&mut Graphics, &Input, &FrameTimer);
+
+impl<F: FnMut(&mut Graphics, &Input, &FrameTimer) + 'static> sealed::Sealed<PartsMarker> for F {}
+impl<F: FnMut(&mut Graphics, &Input, &FrameTimer) + 'static> FrameUpdate<PartsMarker> for F {
+    fn update(&mut self, ctx: &mut FrameContext) {
+        self(&mut ctx.gfx, ctx.input, ctx.timer)
+    }
+}
+
+/// Most fixed ticks [`App::run_fixed`] runs per frame. Time beyond them is dropped, so a slow
+/// frame can't snowball into ever more ticks
+pub const MAX_TICKS_PER_FRAME: u32 = 8;
+
+/// Passed to the update closure of [`App::run_fixed`] on every fixed tick
+pub struct FixedContext<'a> {
+    /// Input as of this tick. A press or release shows up in exactly one tick, even when
+    /// a frame runs several ticks or none
+    pub input: &'a Input,
+    /// Fixed time step in seconds
+    pub dt: f32,
+    /// Number of ticks run before this one
+    pub tick: u64,
+}
+
+/// Turns variable frame times into fixed ticks for [`App::run_fixed`]
+pub(crate) struct FixedStep {
+    dt: f32,
+    accumulator: f32,
+    tick: u64,
+    /// Input the ticks see, advanced once per tick
+    input: Input,
+    /// Keys & buttons held, & scrolling, since the last tick, so taps between ticks still land
+    pending: InputSnapshot,
+}
+
+impl FixedStep {
+    pub(crate) fn new(update_hz: f32) -> Self {
+        assert!(update_hz > 0.0, "the update rate must be positive");
+        Self {
+            dt: 1.0 / update_hz,
+            accumulator: 0.0,
+            tick: 0,
+            input: Input::default(),
+            pending: InputSnapshot::default(),
+        }
+    }
+
+    /// Adds a frame of `delta` seconds with `input`, running `update` for every tick that's due.
+    /// Returns how far the leftover time is into the next tick, in `0..1`
+    pub(crate) fn frame(
+        &mut self,
+        delta: f32,
+        input: &Input,
+        mut update: impl FnMut(&mut FixedContext),
+    ) -> f32 {
+        let current = input.snapshot();
+        self.pending.keys.extend(&current.keys);
+        self.pending.mouse_buttons.extend(&current.mouse_buttons);
+        self.pending.mouse_position = current.mouse_position;
+        self.pending.mouse_scroll += current.mouse_scroll;
+
+        self.accumulator += delta;
+        let mut ticks = 0;
+        while self.accumulator >= self.dt {
+            if ticks == MAX_TICKS_PER_FRAME {
+                self.accumulator %= self.dt;
+                break;
+            }
+            // only the first tick sees what happened since the last one
+            let snapshot = match ticks {
+                0 => std::mem::take(&mut self.pending),
+                _ => InputSnapshot {
+                    mouse_scroll: 0.0,
+                    ..current.clone()
+                },
+            };
+            self.input.apply_snapshot(&snapshot);
+            update(&mut FixedContext {
+                input: &self.input,
+                dt: self.dt,
+                tick: self.tick,
+            });
+            self.accumulator -= self.dt;
+            self.tick += 1;
+            ticks += 1;
+        }
+        self.accumulator / self.dt
+    }
+}
+
+/// What happened during a frame, passed to [`App::after_frame`] callbacks
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FrameSummary<'a> {
+    /// Whether the frame was drawn. `false` while the window is minimized or occluded, or its
+    /// surface isn't available
+    pub rendered: bool,
+    /// Shape batches drawn, about one draw call each (text, egui & gizmos aside)
+    pub batches: usize,
+    /// Draws left out as they couldn't change the frame: fully transparent untextured
+    /// shapes drawn with the built-in shader, empty text & batches left empty
+    pub skipped_draws: usize,
+    /// Scope timings of the last complete frame, with the `profiling` feature
+    pub profile: Option<&'a ProfileReport>,
+}
+
+/// Callbacks registered with [`App::before_frame`] & [`App::after_frame`]
+#[derive(Default)]
+pub(crate) struct FrameHooks {
+    pub(crate) before: Vec<Box<BeforeFrameFn>>,
+    pub(crate) after: Vec<Box<AfterFrameFn>>,
+}
+
+impl FrameHooks {
+    pub(crate) fn before(&mut self, input: &Input, timer: &FrameTimer) {
+        for hook in &mut self.before {
+            hook(input, timer);
+        }
+    }
+
+    pub(crate) fn after(&mut self, input: &Input, timer: &FrameTimer, summary: &FrameSummary) {
+        for hook in &mut self.after {
+            hook(input, timer, summary);
+        }
+    }
 }
 
 pub struct App {
@@ -64,12 +430,41 @@ pub struct App {
     update: Option<Box<UpdateFn>>,
     config: Option<AppConfig>,
     vsync: bool,
-    text_renderer: Option<TextRenderer>,
-    #[cfg(feature = "ui")]
-    egui: Option<EguiRenderer>,
+    transparent: bool,
+    #[cfg(not(all(feature = "render_thread", not(target_arch = "wasm32"))))]
     backbuffer: Option<Backbuffer>,
-    primitive_batch: PrimitiveBatch,
+    /// Whether the window has no surface to draw into, until it's resumed
+    #[cfg(all(feature = "render_thread", not(target_arch = "wasm32")))]
+    suspended: bool,
+    state: FrameState,
+    /// Batches drawn & draws skipped of the last frame the render thread finished
+    #[cfg(all(feature = "render_thread", not(target_arch = "wasm32")))]
+    last_drawn: Option<(usize, usize)>,
+    #[cfg(all(feature = "render_thread", not(target_arch = "wasm32")))]
+    last_profile: Option<ProfileReport>,
     memory_hints: MemoryHints,
+    preferred_formats: Vec<TextureFormat>,
+    device_request: DeviceRequest,
+    on_gpu_downgrade: Option<Box<GpuDowngradeFn>>,
+    on_surface_error: Option<Box<SurfaceErrorFn>>,
+    on_hitch: Option<(f32, Box<HitchFn>)>,
+    on_resize: Option<Box<ResizeFn>>,
+    /// Resizes since the last frame, merged into one
+    resized: Option<Resize>,
+    on_quit: Option<Box<QuitFn>>,
+    on_suspend: Option<Box<SuspendFn>>,
+    on_resume: Option<Box<SuspendFn>>,
+    hooks: FrameHooks,
+    #[cfg_attr(not(target_arch = "wasm32"), allow(dead_code))]
+    init_error_html: String,
+    exit_code: Option<i32>,
+    redraw_in: Option<Duration>,
+    #[cfg(feature = "dialogs")]
+    dialogs: Dialogs,
+    #[cfg(feature = "serde")]
+    input_playback: Option<InputPlayback>,
+    #[cfg(feature = "log")]
+    log: LogConfig,
 }
 
 impl Default for App {
@@ -86,15 +481,68 @@ impl App {
             update: None,
             config: Some(AppConfig::default()),
             vsync: true,
-            text_renderer: None,
-            #[cfg(feature = "ui")]
-            egui: None,
+            transparent: false,
+            #[cfg(not(all(feature = "render_thread", not(target_arch = "wasm32"))))]
             backbuffer: None,
+            #[cfg(all(feature = "render_thread", not(target_arch = "wasm32")))]
+            suspended: false,
+            state: FrameState::default(),
+            #[cfg(all(feature = "render_thread", not(target_arch = "wasm32")))]
+            last_drawn: None,
+            #[cfg(all(feature = "render_thread", not(target_arch = "wasm32")))]
+            last_profile: None,
             memory_hints: MemoryHints::Performance,
-            primitive_batch: PrimitiveBatch::default(),
+            preferred_formats: Vec::new(),
+            device_request: DeviceRequest::default(),
+            on_gpu_downgrade: None,
+            on_surface_error: None,
+            on_hitch: None,
+            on_resize: None,
+            resized: None,
+            on_quit: None,
+            on_suspend: None,
+            on_resume: None,
+            hooks: FrameHooks::default(),
+            init_error_html: INIT_ERROR_HTML.to_string(),
+            exit_code: None,
+            redraw_in: None,
+            #[cfg(feature = "dialogs")]
+            dialogs: Dialogs::default(),
+            #[cfg(feature = "serde")]
+            input_playback: None,
+            #[cfg(feature = "log")]
+            log: LogConfig::default(),
+        }
+    }
+
+    /// Create a new [`App`] with all window & loop settings from `config`,
+    /// e.g. one loaded with `App::load_config`
+    pub fn from_config(config: AppConfig) -> Self {
+        Self {
+            config: Some(config),
+            ..Self::new()
         }
     }
 
+    /// Load an [`AppConfig`] from a TOML file such as a player-editable `settings.toml`.
+    /// See [`crate::config::from_toml`] for how missing & unknown settings are handled
+    #[cfg(all(feature = "serde", not(target_arch = "wasm32")))]
+    pub fn load_config(
+        path: impl AsRef<std::path::Path>,
+    ) -> Result<AppConfig, crate::config::ConfigError> {
+        crate::config::from_toml(&std::fs::read_to_string(path)?)
+    }
+
+    /// Save an [`AppConfig`] as a TOML file that [`Self::load_config`] can read back
+    #[cfg(all(feature = "serde", not(target_arch = "wasm32")))]
+    pub fn save_config(
+        config: &AppConfig,
+        path: impl AsRef<std::path::Path>,
+    ) -> Result<(), crate::config::ConfigError> {
+        std::fs::write(path, crate::config::to_toml(config)?)?;
+        Ok(())
+    }
+
     /// Set application title
     pub fn title(mut self, title: &str) -> Self {
         if let Some(c) = self.config.as_mut() {
@@ -162,9 +610,134 @@ impl App {
         self
     }
 
-    /// Enable or disable vsync
+    /// Set the initial outer window position in physical pixels.
+    /// When combined with [`Self::monitor`], the position is relative to that monitor.
+    /// Ignored on the web
+    pub fn position(mut self, x: i32, y: i32) -> Self {
+        if let Some(c) = self.config.as_mut() {
+            c.position = Some((x, y));
+        }
+        self
+    }
+
+    /// Open the window on the monitor at `index` (in the platform's enumeration order).
+    /// Out of range indices fall back to the primary monitor. Ignored on the web
+    pub fn monitor(mut self, index: usize) -> Self {
+        if let Some(c) = self.config.as_mut() {
+            c.monitor = Some(index);
+        }
+        self
+    }
+
+    /// Keep the window above all other windows (defaults to false)
+    pub fn always_on_top(mut self, always_on_top: bool) -> Self {
+        if let Some(c) = self.config.as_mut() {
+            c.always_on_top = always_on_top;
+        }
+        self
+    }
+
+    /// Make the window transparent & undecorated (defaults to false), so pixels cleared or
+    /// drawn with alpha show what's behind it, e.g. for an overlay with
+    /// `gfx.clear(Color::TRANSPARENT)`. Call [`Self::decorations`] after to keep decorations
+    ///
+    /// Where the surface composites premultiplied alpha, clear colors are premultiplied, text
+    /// is drawn through cached textures so glyph edges don't fringe & egui already outputs
+    /// premultiplied colors. Custom passes & shaders must output premultiplied colors too.
+    /// Surfaces that only composite postmultiplied or inherited alpha get straight clear
+    /// colors, though translucent shape edges still come out premultiplied. Falls back to an
+    /// opaque window with a warning where the surface can't composite alpha. Linux needs a
+    /// compositor on X11, & some Windows drivers only composite transparent windows with the
+    /// Vulkan or GL backends
+    pub fn transparent(mut self, transparent: bool) -> Self {
+        if let Some(c) = self.config.as_mut() {
+            c.transparent = transparent;
+            c.decorations = !transparent;
+        }
+        self
+    }
+
+    /// Web only: keep right-click from opening the browser context menu over the canvas,
+    /// for right-click gameplay (defaults to false)
+    pub fn prevent_context_menu(mut self, enabled: bool) -> Self {
+        if let Some(c) = self.config.as_mut() {
+            c.prevent_context_menu = enabled;
+        }
+        self
+    }
+
+    /// Web only: keep the mouse wheel from scrolling the page while over the canvas,
+    /// so it can zoom the camera instead (defaults to false)
+    pub fn capture_scroll(mut self, enabled: bool) -> Self {
+        if let Some(c) = self.config.as_mut() {
+            c.capture_scroll = enabled;
+        }
+        self
+    }
+
+    /// Web only: what the app does while its tab is hidden (defaults to [`Background::Pause`])
+    ///
+    /// - `Background::Pause`: no frames until the tab is visible again
+    /// - `Background::ThrottleTo(fps)`: frames at up to `fps`, e.g. to keep a network
+    ///   connection alive
+    /// - `Background::Continue`: frames as often as the browser allows
+    ///
+    /// Browsers throttle hidden tabs to about one frame a second whatever the mode. Either
+    /// way [`Self::on_suspend`] & [`Self::on_resume`] are called & the first frame back is
+    /// [`FrameTimer::resumed`], so a long absence isn't simulated in one step
+    pub fn background_behavior(mut self, background: Background) -> Self {
+        if let Some(c) = self.config.as_mut() {
+            c.background = background;
+        }
+        self
+    }
+
+    /// Native only: what the app does while its window is fully covered or on another
+    /// virtual desktop (defaults to [`OccludedBehavior::Continue`]). Windows & macOS report
+    /// this; other platforms keep drawing
+    ///
+    /// - `OccludedBehavior::SkipRender`: nothing is drawn, but the update closure (& fixed
+    ///   ticks) & frame hooks keep running every [`egor_app::OCCLUDED_FRAME_INTERVAL`] so the
+    ///   simulation advances. What they draw is dropped
+    /// - `OccludedBehavior::SkipFrame`: no frames until the window is uncovered
+    /// - `OccludedBehavior::Continue`: frames as if the window were visible
+    ///
+    /// Unless continuing, [`Self::on_suspend`] & [`Self::on_resume`] are called & the first
+    /// frame back is [`FrameTimer::resumed`]
+    pub fn occluded_behavior(mut self, occluded: OccludedBehavior) -> Self {
+        if let Some(c) = self.config.as_mut() {
+            c.occluded = occluded;
+        }
+        self
+    }
+
+    /// Number of recent frame times [`FrameTimer`] keeps for [`FrameTimer::fps_avg`] & friends
+    /// (defaults to 120)
+    pub fn frame_history(mut self, len: usize) -> Self {
+        if let Some(c) = self.config.as_mut() {
+            c.frame_history = len;
+        }
+        self
+    }
+
+    /// Enable or disable vsync (defaults to true)
     pub fn vsync(mut self, enabled: bool) -> Self {
-        self.vsync = enabled;
+        if let Some(c) = self.config.as_mut() {
+            c.vsync = enabled;
+        }
+        self
+    }
+
+    /// Set when frames are drawn (defaults to [`RedrawMode::Continuous`])
+    ///
+    /// - `RedrawMode::Continuous`: redraws every frame (game-style loop)
+    /// - `RedrawMode::OnEvent`: sleeps until input arrives, egui needs a repaint, or a frame is
+    ///   requested with [`AppControl::request_redraw`] or [`AppControl::request_redraw_in`].
+    ///   [`FrameTimer::delta`] is clamped after idling, see [`egor_app::ON_EVENT_MAX_DELTA`]
+    pub fn redraw_mode(mut self, mode: RedrawMode) -> Self {
+        if let Some(c) = self.config.as_mut() {
+            c.redraw_mode = mode;
+        }
         self
     }
 
@@ -174,7 +747,8 @@ impl App {
     /// - `ControlFlow::Wait`: no frames are produced unless
     ///   [`AppControl::request_redraw()`] is called
     ///
-    /// When using `Wait`, you are responsible for requesting redraws
+    /// When using `Wait`, you are responsible for requesting redraws.
+    /// [`Self::redraw_mode`] also redraws on input, which suits most apps better
     pub fn control_flow(mut self, control_flow: ControlFlow) -> Self {
         if let Some(c) = self.config.as_mut() {
             c.control_flow = control_flow;
@@ -182,6 +756,29 @@ impl App {
         self
     }
 
+    /// Set where world-space `(0, 0)` lands on screen (defaults to [`Origin::TopLeft`])
+    ///
+    /// - `Origin::TopLeft`: `(0, 0)` is the top-left corner of the window
+    /// - `Origin::Center`: `(0, 0)` is the center of the window, even after resizing
+    ///
+    /// Applies to the main camera & cameras of offscreen targets
+    pub fn origin(mut self, origin: Origin) -> Self {
+        let (position, zoom) = (self.state.camera.position(), self.state.camera.zoom());
+        self.state.camera = Camera::with_origin(origin);
+        self.state.camera.target(position);
+        self.state.camera.set_zoom(zoom);
+        self
+    }
+
+    /// Set the initial camera position & zoom, applied before the first frame renders
+    ///
+    /// `position` is interpreted according to [`Self::origin`] (see [`Camera::target`])
+    pub fn camera(mut self, position: Vec2, zoom: f32) -> Self {
+        self.state.camera.target(position);
+        self.state.camera.set_zoom(zoom);
+        self
+    }
+
     /// Configure wgpu device memory allocation strategy.
     /// Affects GPU sub-allocation block sizes, useful for mobile or low end devices.
     /// See [`MemoryHints`] for more
@@ -190,14 +787,337 @@ impl App {
         self
     }
 
+    /// Render to the first of `formats` the window surface supports, e.g.
+    /// `&[TextureFormat::Rgba16Float, TextureFormat::Bgra8UnormSrgb]` for HDR output where
+    /// available. Falls back to the surface's default format when none are supported.
+    /// The chosen format is available through [`Graphics::surface_format`]
+    pub fn preferred_surface_format(mut self, formats: &[TextureFormat]) -> Self {
+        self.preferred_formats = formats.to_vec();
+        self
+    }
+
+    /// Ask the GPU device for optional features, e.g. `Features::TEXTURE_BINDING_ARRAY`.
+    /// Features the adapter lacks are left out, see [`Self::on_gpu_downgrade`], so check
+    /// [`Graphics::active_features`] before relying on one
+    pub fn gpu_features(mut self, features: Features) -> Self {
+        self.device_request.features = features;
+        self
+    }
+
+    /// Ask the GPU device for other limits than the defaults (wgpu's natively, WebGL2's in
+    /// the browser), e.g. a higher `max_texture_dimension_2d` for 8k atlases. Limits beyond
+    /// the adapter are lowered to its best, see [`Self::on_gpu_downgrade`] &
+    /// [`Graphics::active_limits`]
+    pub fn gpu_limits(mut self, limits: Limits) -> Self {
+        self.device_request.limits = Some(limits);
+        self
+    }
+
+    /// Which GPU adapter to draw with (defaults to [`AdapterPreference::HighPerformance`]).
+    /// Any other adapter is tried before giving up, & with
+    /// [`AdapterPreference::SoftwareFallbackAllowed`] the fallback software renderer after
+    /// that, so VMs without a GPU can run the app. A software renderer listed like a GPU
+    /// (llvmpipe) may be picked by any preference. See [`Graphics::adapter_info`] for the one
+    /// picked
+    pub fn adapter_preference(mut self, preference: AdapterPreference) -> Self {
+        self.device_request.adapter = preference;
+        self
+    }
+
+    /// Record a wgpu API trace into the `dir` directory (created if missing), for bug
+    /// reports maintainers can replay with wgpu's `player`. Native only. wgpu logs that
+    /// tracing was removed, but the trace is still recorded. For captures see
+    /// [`egor_render::debug`]
+    #[cfg(feature = "gpu_debug")]
+    pub fn wgpu_trace(mut self, dir: impl Into<std::path::PathBuf>) -> Self {
+        self.device_request.trace = Some(dir.into());
+        self
+    }
+
+    /// Called once the device is created if the adapter lacks some of
+    /// [`Self::gpu_features`] or [`Self::gpu_limits`], with what's missing. Only limits set
+    /// to other than the defaults count. The app runs with everything else. Without a
+    /// handler a warning is logged
+    pub fn on_gpu_downgrade(mut self, handler: impl FnOnce(&GpuDowngrade) + 'static) -> Self {
+        self.on_gpu_downgrade = Some(Box::new(handler));
+        self
+    }
+
     /// Set the vertex and index buffer limits for the main frame batch.
     /// Defaults to [`egor_render::batch::GeometryBatch::DEFAULT_MAX_VERTICES`] and [`egor_render::batch::GeometryBatch::DEFAULT_MAX_INDICES`].
     /// Reduce these on memory-constrained platforms, or increase for scenes with dense geometry.
     pub fn batch_limits(mut self, max_verts: usize, max_indices: usize) -> Self {
-        self.primitive_batch = PrimitiveBatch::new(max_verts, max_indices);
+        self.state.primitive_batch = PrimitiveBatch::new(max_verts, max_indices);
+        self
+    }
+
+    /// Panic when a rectangle or raw mesh is drawn with, or a texture update targets, a
+    /// texture id that was never loaded, instead of drawing it white & logging a warning in
+    /// debug builds. Useful in tests & CI
+    pub fn strict_textures(mut self, strict: bool) -> Self {
+        self.state.strict_textures = strict;
+        self
+    }
+
+    /// Log rate limited warnings when a frame batches poorly: too many batches, few shapes
+    /// per batch, textures or shaders switching nearly every shape, or large texture uploads.
+    /// Each names the texture or shader id responsible. Off by default
+    pub fn performance_warnings(mut self, enabled: bool) -> Self {
+        self.state.perf_warnings.enabled = enabled;
+        self
+    }
+
+    /// Soft limit on the estimated bytes of textures, texture arrays & offscreen targets.
+    /// Going over it logs a warning naming the largest textures, or calls
+    /// [`Self::on_memory_budget`], once until usage drops back under. Nothing is unloaded
+    ///
+    /// ```no_run
+    /// # use egor_glue::app::App;
+    /// App::new().texture_memory_budget(256 * 1024 * 1024);
+    /// ```
+    pub fn texture_memory_budget(mut self, bytes: u64) -> Self {
+        self.state.memory_budget.bytes = Some(bytes);
+        self
+    }
+
+    /// Called instead of the warning when textures go over
+    /// [`Self::texture_memory_budget`], with where the memory went. Unload textures with
+    /// [`Graphics::unload_texture`] on the next frame to get back under
+    pub fn on_memory_budget(
+        mut self,
+        handler: impl FnMut(&MemoryReport<TextureId>) + RenderThreadSend + 'static,
+    ) -> Self {
+        self.state.memory_budget.on_exceeded = Some(Box::new(handler));
+        self
+    }
+
+    /// Record your own wgpu commands into every frame after shapes & text, before egui,
+    /// e.g. a pipeline drawing point primitives. The view is already cleared & holds the
+    /// frame so far, so begin passes with `LoadOp::Load`. [`PassResources`] has the main
+    /// camera's bind group for pipelines drawing in world space like egor's batches
+    pub fn custom_pass(
+        mut self,
+        pass: impl FnMut(&Device, &Queue, &mut CommandEncoder, &TextureView, &PassResources)
+        + RenderThreadSend
+        + 'static,
+    ) -> Self {
+        self.state.custom_pass = Some(Box::new(pass));
+        self
+    }
+
+    /// Like [`Self::custom_pass`], run before shapes are drawn, for backgrounds. The view is
+    /// cleared to the clear color first
+    pub fn background_pass(
+        mut self,
+        pass: impl FnMut(&Device, &Queue, &mut CommandEncoder, &TextureView, &PassResources)
+        + RenderThreadSend
+        + 'static,
+    ) -> Self {
+        self.state.background_pass = Some(Box::new(pass));
+        self
+    }
+
+    /// Handle unrecoverable surface errors (GPU out of memory) instead of panicking.
+    /// Lost or outdated surfaces are recovered automatically & never reach this handler
+    pub fn on_surface_error(mut self, handler: impl FnMut(SurfaceError) + 'static) -> Self {
+        self.on_surface_error = Some(Box::new(handler));
+        self
+    }
+
+    /// HTML shown in place of the canvas when no GPU can render the app on the web,
+    /// e.g. WebGPU is unavailable & WebGL2 is disabled. `{error}` is replaced with the
+    /// underlying error. The default explains that WebGL2 or WebGPU is required
+    pub fn on_init_error_html(mut self, html: impl Into<String>) -> Self {
+        self.init_error_html = html.into();
+        self
+    }
+
+    /// Enable or disable [`crate::gizmos::Gizmos`] (defaults to on in debug builds only).
+    /// Can also be toggled at runtime with [`crate::gizmos::Gizmos::set_enabled`]
+    pub fn gizmos(mut self, enabled: bool) -> Self {
+        self.state.gizmos = GizmoStore::new(enabled);
+        self
+    }
+
+    /// Set initial state & a closure that sets it up once the GPU surface exists, before the
+    /// first update. Loading textures & other GPU resources there works on every platform,
+    /// including Android where the surface is only created after the app resumes
+    ///
+    /// ```no_run
+    /// # use egor_glue::{app::App, graphics::TextureId};
+    /// let bytes = std::fs::read("player.png").unwrap();
+    /// App::new()
+    ///     .init(TextureId::default(), move |texture, ctx| {
+    ///         *texture = ctx.gfx.load_texture(&bytes)
+    ///     })
+    ///     .run(|texture, ctx| {
+    ///         ctx.gfx.rect().texture(*texture);
+    ///     });
+    /// ```
+    pub fn init<S: 'static>(
+        self,
+        state: S,
+        init: impl FnOnce(&mut S, &mut FrameContext) + 'static,
+    ) -> InitApp<S> {
+        InitApp {
+            app: self,
+            state,
+            init: Box::new(init),
+        }
+    }
+
+    /// Clamp [`FrameTimer::delta`] to at most `seconds`, so a hitch (shader compile, stall,
+    /// OS pause) doesn't blow up the simulation. The real value stays in [`FrameTimer::delta_raw`]
+    pub fn max_delta(mut self, seconds: f32) -> Self {
+        if let Some(c) = self.config.as_mut() {
+            c.max_delta = Some(seconds);
+        }
         self
     }
 
+    /// Call `handler` with the raw frame time in seconds whenever it exceeds `threshold` seconds.
+    /// Useful for logging where hitches happen in release builds
+    pub fn on_hitch(mut self, threshold: f32, handler: impl FnMut(f32) + 'static) -> Self {
+        self.on_hitch = Some((threshold, Box::new(handler)));
+        self
+    }
+
+    /// Call `handler` with the previous & new size whenever the window is resized, before the
+    /// first frame at the new size, so the camera never lags the surface. It's also called
+    /// once before the first frame with both sizes equal, so the same code sets the camera up:
+    ///
+    /// ```no_run
+    /// # use egor_glue::{app::App, math::vec2};
+    /// App::new()
+    ///     // 20 world units tall whatever the window, wider windows show more horizontally
+    ///     .on_resize(|resize, camera| {
+    ///         let (w, h) = resize.size;
+    ///         camera.keep_vertical_fov(20.0, vec2(w as f32, h as f32));
+    ///     })
+    ///     .run(|ctx| {
+    ///         ctx.gfx.rect().size(vec2(1.0, 1.0));
+    ///     });
+    /// ```
+    pub fn on_resize(mut self, handler: impl FnMut(Resize, &mut Camera) + 'static) -> Self {
+        self.on_resize = Some(Box::new(handler));
+        self
+    }
+
+    /// Call `handler` once before the app quits, whether the window was closed or
+    /// [`AppControl::request_exit`] was called. Handy for saving game state
+    pub fn on_quit(mut self, handler: impl FnOnce() + 'static) -> Self {
+        self.on_quit = Some(Box::new(handler));
+        self
+    }
+
+    /// Call `handler` when the app goes to the background: its browser tab is hidden, its
+    /// window is covered (see [`Self::occluded_behavior`]) or the app is suspended on Android.
+    /// Handy for pausing audio or saving progress
+    pub fn on_suspend(mut self, handler: impl FnMut() + 'static) -> Self {
+        self.on_suspend = Some(Box::new(handler));
+        self
+    }
+
+    /// Call `handler` when the app comes back from the background, see [`Self::on_suspend`]
+    pub fn on_resume(mut self, handler: impl FnMut() + 'static) -> Self {
+        self.on_resume = Some(Box::new(handler));
+        self
+    }
+
+    /// Call `hook` at the start of every frame, before the update closure. Hooks run in the
+    /// order they were added & keep running while the window is minimized & nothing is drawn,
+    /// e.g. to pump network sockets without stalling
+    pub fn before_frame(mut self, hook: impl FnMut(&Input, &FrameTimer) + 'static) -> Self {
+        self.hooks.before.push(Box::new(hook));
+        self
+    }
+
+    /// Call `hook` at the end of every frame, after it was rendered (or skipped, see
+    /// [`FrameSummary::rendered`]), e.g. to flush outgoing packets. Hooks run in the order
+    /// they were added, like [`Self::before_frame`]
+    pub fn after_frame(
+        mut self,
+        hook: impl FnMut(&Input, &FrameTimer, &FrameSummary) + 'static,
+    ) -> Self {
+        self.hooks.after.push(Box::new(hook));
+        self
+    }
+
+    /// Log messages at `level` & above (defaults to [`LevelFilter::Error`], or
+    /// [`LevelFilter::Info`] on Android), from modules without a level of their own.
+    /// `RUST_LOG` overrides it natively
+    #[cfg(feature = "log")]
+    pub fn log_level(mut self, level: LevelFilter) -> Self {
+        self.log.level = level;
+        self
+    }
+
+    /// Log messages of `module` & the modules in it at `level` & above, e.g.
+    /// `.log_module("egor_render", LevelFilter::Debug)`. See [`egor_app::logging`] for the
+    /// engine's targets
+    #[cfg(feature = "log")]
+    pub fn log_module(mut self, module: &str, level: LevelFilter) -> Self {
+        self.log.set_module(module, level);
+        self
+    }
+
+    /// Send every logged message that passes the levels to `sink` as well as the console,
+    /// e.g. to show engine logs in an in-game console. Sinks run in the order they were added
+    ///
+    /// ```no_run
+    /// # use egor_glue::app::App;
+    /// # use std::sync::{Arc, Mutex};
+    /// let lines = Arc::new(Mutex::new(Vec::new()));
+    /// let sink = lines.clone();
+    /// App::new().log_to(move |record| {
+    ///     let line = format!("[{}] {}: {}", record.frame, record.target, record.message);
+    ///     sink.lock().unwrap().push(line);
+    /// });
+    /// ```
+    #[cfg(feature = "log")]
+    pub fn log_to(mut self, sink: impl Fn(&LogRecord) + Send + Sync + 'static) -> Self {
+        self.log.sinks.push(Arc::new(sink));
+        self
+    }
+
+    /// Record every key, mouse button, cursor & scroll event with its frame number & save
+    /// them to `path` every few seconds & when the app exits, so a crash keeps most of the
+    /// session. Replay the file with [`Self::replay_input`]
+    #[cfg(all(feature = "serde", not(target_arch = "wasm32")))]
+    pub fn record_input(mut self, path: impl Into<std::path::PathBuf>) -> Self {
+        self.input_playback = Some(InputPlayback::Record(path.into()));
+        self
+    }
+
+    /// Replay input saved by [`Self::record_input`] on the same frames it was recorded on,
+    /// ignoring live input until it runs out or [`egor_app::record::REPLAY_ABORT_KEY`] is
+    /// pressed. Use a fixed timestep for simulations to play out exactly as recorded.
+    /// A missing or invalid file is logged & the app runs with live input
+    #[cfg(all(feature = "serde", not(target_arch = "wasm32")))]
+    pub fn replay_input(mut self, path: impl AsRef<std::path::Path>) -> Self {
+        match InputRecording::load(&path) {
+            Ok(recording) => self.input_playback = Some(InputPlayback::Replay(recording)),
+            Err(e) => log::error!("can't replay {}: {e}", path.as_ref().display()),
+        }
+        self
+    }
+
+    /// Run the app with an update function of any signature [`FrameUpdate`] supports, so
+    /// games written against older versions keep compiling. Closures need their parameter
+    /// types written out; [`Self::run`] infers them
+    ///
+    /// ```no_run
+    /// # use egor_glue::{app::App, graphics::Graphics};
+    /// # use egor_app::{input::Input, time::FrameTimer};
+    /// fn update(gfx: &mut Graphics, input: &Input, timer: &FrameTimer) {
+    ///     gfx.text(&format!("{} fps", timer.fps));
+    /// }
+    /// App::new().run_update(update);
+    /// ```
+    pub fn run_update<M>(self, mut update: impl FrameUpdate<M>) {
+        self.run(move |ctx| update.update(ctx));
+    }
+
     /// Run the app with a per-frame update closure
     pub fn run(mut self, #[allow(unused_mut)] mut update: impl FnMut(&mut FrameContext) + 'static) {
         #[cfg(all(feature = "hot_reload", not(target_arch = "wasm32")))]
@@ -211,181 +1131,679 @@ impl App {
         self.update = Some(Box::new(update));
 
         let config = self.config.take().unwrap();
-        AppRunner::new(self, config).run();
+        #[cfg(all(feature = "snapshot", not(target_arch = "wasm32")))]
+        if let Some(request) = crate::snapshot::SnapshotRequest::from_env() {
+            self.run_snapshot(&config, &request);
+            return;
+        }
+        self.vsync = config.vsync;
+        self.transparent = config.transparent;
+        #[cfg(feature = "serde")]
+        let playback = self.input_playback.take();
+        #[cfg(feature = "log")]
+        let log = std::mem::take(&mut self.log);
+        let runner = AppRunner::new(self, config);
+        #[cfg(feature = "log")]
+        let runner = runner.logging(log);
+        #[cfg(feature = "serde")]
+        let runner = match playback {
+            Some(playback) => runner.input_playback(playback),
+            None => runner,
+        };
+        runner.run();
     }
 }
 
-impl AppHandler<Renderer> for App {
-    fn on_window_event(&mut self, _window: &Window, event: &WindowEvent) {
+impl App {
+    /// Run the app with a simulation stepped `update_hz` times per second & a render closure
+    /// called once per frame. `update` runs zero or more times per frame with a fixed
+    /// [`FixedContext::dt`]; `render` then gets how far the frame is between the last tick &
+    /// the next (`0..1`) to interpolate positions with. [`Self::max_delta`] & at most
+    /// [`MAX_TICKS_PER_FRAME`] ticks a frame keep hitches from piling up ticks. Use
+    /// [`Self::init`] for state shared by both closures
+    ///
+    /// ```no_run
+    /// # use egor_glue::app::App;
+    /// # use glam::Vec2;
+    /// let velocity = Vec2::new(120.0, 0.0);
+    /// App::new()
+    ///     .init((Vec2::ZERO, Vec2::ZERO), |_, _| {})
+    ///     .run_fixed(
+    ///         60.0,
+    ///         move |(previous, current), tick| {
+    ///             *previous = *current;
+    ///             *current += velocity * tick.dt;
+    ///         },
+    ///         |(previous, current), ctx, alpha| {
+    ///             ctx.gfx.rect().at(previous.lerp(*current, alpha));
+    ///         },
+    ///     );
+    /// ```
+    pub fn run_fixed(
+        self,
+        update_hz: f32,
+        mut update: impl FnMut(&mut FixedContext) + 'static,
+        mut render: impl FnMut(&mut FrameContext, f32) + 'static,
+    ) {
+        let mut step = FixedStep::new(update_hz);
+        self.run(move |ctx| {
+            let alpha = step.frame(ctx.timer.delta, ctx.input, &mut update);
+            render(ctx, alpha);
+        });
+    }
+}
+
+/// An [`App`] with state that is initialized once the GPU surface exists. See [`App::init`]
+pub struct InitApp<S> {
+    app: App,
+    state: S,
+    init: Box<InitFn<S>>,
+}
+
+impl<S: 'static> InitApp<S> {
+    /// Run the app with a per-frame update closure receiving the state
+    pub fn run(self, mut update: impl FnMut(&mut S, &mut FrameContext) + 'static) {
+        let (mut state, mut init) = (self.state, Some(self.init));
+        self.app.run(move |ctx| {
+            if let Some(init) = init.take() {
+                init(&mut state, ctx);
+            }
+            update(&mut state, ctx);
+        });
+    }
+
+    /// Like [`App::run_fixed`], with both closures receiving the state
+    pub fn run_fixed(
+        self,
+        update_hz: f32,
+        mut update: impl FnMut(&mut S, &mut FixedContext) + 'static,
+        mut render: impl FnMut(&mut S, &mut FrameContext, f32) + 'static,
+    ) {
+        let mut step = FixedStep::new(update_hz);
+        self.run(move |state, ctx| {
+            let alpha = step.frame(ctx.timer.delta, ctx.input, |tick| update(state, tick));
+            render(state, ctx, alpha);
+        });
+    }
+}
+
+/// What the app draws with: the renderer, or with `render_thread` the thread it's handed to
+#[cfg(not(all(feature = "render_thread", not(target_arch = "wasm32"))))]
+type Gpu = Renderer;
+#[cfg(all(feature = "render_thread", not(target_arch = "wasm32")))]
+type Gpu = RenderThread;
+
+impl App {
+    /// Runs the update closure & draws the frame, or only runs it when not `drawn`. Returns
+    /// the number of batches drawn & draws skipped, `None` if the frame wasn't drawn
+    #[cfg(not(all(feature = "render_thread", not(target_arch = "wasm32"))))]
+    fn render(
+        &mut self,
+        window: &Window,
+        renderer: &mut Renderer,
+        input: &Input,
+        timer: &FrameTimer,
+        drawn: bool,
+    ) -> Option<(usize, usize)> {
+        let update = self.update.as_mut()?;
+        let backbuffer = self.backbuffer.as_mut()?;
+        let frame = match drawn {
+            true => match renderer.begin_frame(backbuffer) {
+                Ok(frame) => Some(frame?),
+                Err(e) => match &mut self.on_surface_error {
+                    Some(on_error) => {
+                        on_error(e);
+                        return None;
+                    }
+                    None => panic!("Unrecoverable surface error: {e}"),
+                },
+            },
+            false => None,
+        };
+
+        let app = AppControl {
+            window: Some(window),
+            requested_size: None,
+            requested_vsync: None,
+            exit_code: None,
+            redraw_in: None,
+            #[cfg(feature = "dialogs")]
+            dialogs: Some(&mut self.dialogs),
+        };
+        let frame_input = FrameInput {
+            input,
+            timer,
+            events: std::mem::take(&mut self.events),
+            resized: self.resized.take(),
+        };
+        let (drawn, requests) =
+            self.state
+                .run(update, renderer, backbuffer, frame, app, frame_input);
+        self.exit_code = self.exit_code.or(requests.exit_code);
+        self.redraw_in = requests.redraw_in;
+        self.apply_requests(renderer, requests.size, requests.vsync);
+        drawn
+    }
+
+    /// Runs the update closure & hands what it queued to the render thread, or drops it when
+    /// not `drawn`. Returns the number of batches drawn & draws skipped of the last frame the
+    /// render thread finished, `None` if that one wasn't drawn
+    #[cfg(all(feature = "render_thread", not(target_arch = "wasm32")))]
+    fn render(
+        &mut self,
+        window: &Window,
+        gpu: &mut RenderThread,
+        input: &Input,
+        timer: &FrameTimer,
+        drawn: bool,
+    ) -> Option<(usize, usize)> {
+        let (size, format) = (gpu.size(), gpu.format());
+        let renderer = self.renderer(gpu);
+        let update = self.update.as_mut()?;
+        if self.suspended {
+            return None;
+        }
+        // egui only ever sees events while its state is on this thread
         #[cfg(feature = "ui")]
-        if let Some(egui) = self.egui.as_mut() {
+        if let Some(egui) = self.state.egui.as_mut() {
+            for event in &self.events {
+                egui.handle_event(window, event);
+            }
+        }
+
+        let app = AppControl {
+            window: Some(window),
+            requested_size: None,
+            requested_vsync: None,
+            exit_code: None,
+            redraw_in: None,
+            #[cfg(feature = "dialogs")]
+            dialogs: Some(&mut self.dialogs),
+        };
+        let frame_input = FrameInput {
+            input,
+            timer,
+            events: std::mem::take(&mut self.events),
+            resized: self.resized.take(),
+        };
+        let (queued, requests) =
+            self.state
+                .update(update, renderer, size, format, drawn, app, frame_input);
+        if let Some(queued) = queued {
+            gpu.draw(std::mem::take(&mut self.state), queued);
+        }
+        self.exit_code = self.exit_code.or(requests.exit_code);
+        self.redraw_in = requests.redraw_in;
+        self.apply_requests(gpu, requests.size, requests.vsync);
+        self.last_drawn
+    }
+
+    /// Applies the window size & vsync the update closure asked for
+    fn apply_requests(
+        &mut self,
+        gpu: &mut Gpu,
+        requested_size: Option<(u32, u32)>,
+        requested_vsync: Option<bool>,
+    ) {
+        if let Some((rw, rh)) = requested_size {
+            self.resize_target(gpu, rw, rh);
+        }
+        if let Some(vsync) = requested_vsync {
+            self.set_target_vsync(gpu, vsync);
+            self.vsync = vsync;
+        }
+    }
+
+    #[cfg(not(all(feature = "render_thread", not(target_arch = "wasm32"))))]
+    fn renderer<'a>(&mut self, renderer: &'a mut Renderer) -> &'a mut Renderer {
+        renderer
+    }
+
+    /// The renderer, waiting for the render thread to hand it back
+    #[cfg(all(feature = "render_thread", not(target_arch = "wasm32")))]
+    fn renderer<'a>(&mut self, gpu: &'a mut RenderThread) -> &'a mut Renderer {
+        self.take_back(gpu, true);
+        gpu.renderer().unwrap()
+    }
+
+    #[cfg(not(all(feature = "render_thread", not(target_arch = "wasm32"))))]
+    fn resize_target(&mut self, renderer: &mut Renderer, w: u32, h: u32) {
+        let backbuffer = self.backbuffer.as_mut().unwrap();
+        backbuffer.resize(renderer.device(), w, h);
+    }
+
+    #[cfg(all(feature = "render_thread", not(target_arch = "wasm32")))]
+    fn resize_target(&mut self, gpu: &mut RenderThread, w: u32, h: u32) {
+        gpu.resize(w, h);
+    }
+
+    #[cfg(not(all(feature = "render_thread", not(target_arch = "wasm32"))))]
+    fn set_target_vsync(&mut self, renderer: &mut Renderer, vsync: bool) {
+        let backbuffer = self.backbuffer.as_mut().unwrap();
+        backbuffer.set_vsync(renderer.device(), vsync);
+    }
+
+    #[cfg(all(feature = "render_thread", not(target_arch = "wasm32")))]
+    fn set_target_vsync(&mut self, gpu: &mut RenderThread, vsync: bool) {
+        gpu.set_vsync(vsync);
+    }
+
+    /// Takes the renderer & frame state back from the render thread once it finished the
+    /// last frame, waiting for it if `wait`, & reports surface errors it ran into. Returns
+    /// whether they're back
+    #[cfg(all(feature = "render_thread", not(target_arch = "wasm32")))]
+    fn take_back(&mut self, gpu: &mut RenderThread, wait: bool) -> bool {
+        let Some((state, drawn)) = gpu.take_back(wait) else {
+            return gpu.renderer().is_some();
+        };
+        self.state = state;
+        self.last_profile = self.state.profiler.report().cloned();
+        self.last_drawn = drawn.unwrap_or_else(|e| match &mut self.on_surface_error {
+            Some(on_error) => {
+                on_error(e);
+                None
+            }
+            None => panic!("Unrecoverable surface error: {e}"),
+        });
+        true
+    }
+}
+
+impl AppHandler<Gpu> for App {
+    fn on_window_event(&mut self, _window: &Window, event: &WindowEvent) {
+        // with a render thread, egui gets the events at the start of the next frame
+        #[cfg(all(
+            feature = "ui",
+            not(all(feature = "render_thread", not(target_arch = "wasm32")))
+        ))]
+        if let Some(egui) = self.state.egui.as_mut() {
             egui.handle_event(_window, event);
         }
 
         self.events.push(event.clone());
     }
 
-    async fn with_resource(&mut self, window: Arc<Window>) -> Renderer {
+    async fn with_resource(&mut self, window: Arc<Window>) -> Result<Gpu, Box<dyn Error>> {
+        #[cfg(feature = "dialogs")]
+        self.dialogs.set_window(window.clone());
         // WebGPU throws error 'size is zero' if not set
         let size = window.inner_size();
         let (w, h) = (
             if size.width == 0 { 800 } else { size.width },
             if size.height == 0 { 600 } else { size.height },
         );
-        let renderer = Renderer::new(window.clone(), &self.memory_hints).await;
-        self.backbuffer = Some(Backbuffer::new(
-            renderer.instance(),
-            renderer.adapter(),
-            renderer.device(),
-            window,
-            w,
-            h,
+        let renderer = Renderer::with_device_request(
+            window.clone(),
+            &self.memory_hints,
+            &self.preferred_formats,
+            &self.device_request,
+        )
+        .await;
+        #[cfg(target_arch = "wasm32")]
+        if let Err(e) = &renderer {
+            let html = init_error_html(&self.init_error_html, e);
+            egor_app::show_html_over_canvas(&window, &html);
+        }
+        let mut renderer = renderer?;
+        if let Some(downgrade) = renderer.gpu_downgrade() {
+            match self.on_gpu_downgrade.take() {
+                Some(handler) => handler(downgrade),
+                None => {
+                    log::warn!("the GPU lacks some requested features or limits: {downgrade:?}")
+                }
+            }
+        }
+        let backbuffer = self.window_target(&renderer, window.clone(), w, h);
+        renderer.set_alpha_mode(backbuffer.alpha_mode());
+        #[cfg(all(feature = "render_thread", not(target_arch = "wasm32")))]
+        return Ok(RenderThread::spawn(
+            renderer,
+            Box::new(backbuffer),
+            move || window.request_redraw(),
         ));
-        renderer
+        #[cfg(not(all(feature = "render_thread", not(target_arch = "wasm32"))))]
+        {
+            self.backbuffer = Some(backbuffer);
+            Ok(renderer)
+        }
     }
 
-    fn on_ready(&mut self, window: &Window, renderer: &mut Renderer) {
-        let (device, format) = (
-            renderer.device(),
-            self.backbuffer.as_ref().unwrap().format(),
-        );
-        self.backbuffer
-            .as_mut()
-            .unwrap()
-            .set_vsync(device, self.vsync);
-        self.text_renderer = Some(TextRenderer::new(device, renderer.queue(), format));
-        #[cfg(feature = "ui")]
+    fn on_ready(&mut self, window: &Window, gpu: &mut Gpu) {
+        self.set_target_vsync(gpu, self.vsync);
+        let renderer = self.renderer(gpu);
+        let format = renderer.surface_format();
+        self.state.init(renderer, format, Some(window));
+
+        let size = window.inner_size().into();
+        let startup = Resize {
+            previous: size,
+            size,
+        };
+        self.resize(startup, gpu);
+    }
+
+    #[cfg(all(feature = "render_thread", not(target_arch = "wasm32")))]
+    fn frame_ready(&mut self, gpu: &mut RenderThread) -> bool {
+        self.take_back(gpu, false)
+    }
+
+    fn frame(&mut self, window: &Window, gpu: &mut Gpu, input: &Input, timer: &FrameTimer) {
+        if let Some((threshold, on_hitch)) = &mut self.on_hitch
+            && timer.delta_raw > *threshold
         {
-            self.egui = Some(EguiRenderer::new(device, format, window));
+            on_hitch(timer.delta_raw);
         }
 
-        self.resize(
-            window.inner_size().width,
-            window.inner_size().height,
-            renderer,
-        );
+        #[cfg(feature = "dialogs")]
+        self.dialogs.deliver();
+        self.hooks.before(input, timer);
+        let drawn = self.render(window, gpu, input, timer, true);
+        let (batches, skipped_draws) = drawn.unwrap_or_default();
+        let summary = FrameSummary {
+            rendered: drawn.is_some(),
+            batches,
+            skipped_draws,
+            #[cfg(not(all(feature = "render_thread", not(target_arch = "wasm32"))))]
+            profile: self.state.profiler.report(),
+            #[cfg(all(feature = "render_thread", not(target_arch = "wasm32")))]
+            profile: self.last_profile.as_ref(),
+        };
+        self.hooks.after(input, timer, &summary);
+    }
+
+    fn skipped_frame(&mut self, input: &Input, timer: &FrameTimer) {
+        self.hooks.before(input, timer);
+        self.hooks.after(input, timer, &FrameSummary::default());
     }
 
-    fn frame(
+    fn unrendered_frame(
         &mut self,
-        _window: &Window,
-        renderer: &mut Renderer,
+        window: &Window,
+        gpu: &mut Gpu,
         input: &Input,
         timer: &FrameTimer,
     ) {
-        let Some(update) = &mut self.update else {
-            return;
-        };
-        let Some(backbuffer) = &mut self.backbuffer else {
-            return;
-        };
-        let Some(mut frame) = renderer.begin_frame(backbuffer) else {
-            return;
+        #[cfg(feature = "dialogs")]
+        self.dialogs.deliver();
+        self.hooks.before(input, timer);
+        self.render(window, gpu, input, timer, false);
+        let summary = FrameSummary {
+            #[cfg(not(all(feature = "render_thread", not(target_arch = "wasm32"))))]
+            profile: self.state.profiler.report(),
+            #[cfg(all(feature = "render_thread", not(target_arch = "wasm32")))]
+            profile: self.last_profile.as_ref(),
+            ..Default::default()
         };
+        self.hooks.after(input, timer, &summary);
+    }
 
-        let (w, h) = backbuffer.size();
-        let (device, queue) = (renderer.device().clone(), renderer.queue().clone());
-        let format = backbuffer.format();
-        let text_renderer = self.text_renderer.as_mut().unwrap();
+    fn exit_requested(&self) -> Option<i32> {
+        self.exit_code
+    }
 
-        #[cfg(feature = "ui")]
-        let egui_ctx = self.egui.as_mut().unwrap().begin_frame(_window);
-        let mut ctx = FrameContext {
-            events: std::mem::take(&mut self.events),
-            app: AppControl {
-                window: _window,
-                requested_size: None,
-                requested_vsync: None,
-            },
-            gfx: Graphics::new(
-                renderer,
-                &mut self.primitive_batch,
-                text_renderer,
-                format,
-                w,
-                h,
-            ),
-            input,
-            timer,
-            #[cfg(feature = "ui")]
-            egui_ctx,
-        };
-        update(&mut ctx);
+    fn redraw_requested(&mut self) -> Option<Duration> {
+        self.redraw_in.take()
+    }
 
-        let requested_size = ctx.app.requested_size;
-        let requested_vsync = ctx.app.requested_vsync;
-        if let Some((pw, ph)) = requested_size {
-            ctx.gfx.set_target_size(pw, ph);
+    fn on_quit(&mut self) {
+        if let Some(on_quit) = self.on_quit.take() {
+            on_quit();
         }
+    }
 
-        ctx.gfx.upload_camera();
-
-        text_renderer.prepare(&device, &queue, w, h);
+    fn resize(&mut self, resize: Resize, gpu: &mut Gpu) {
+        let (w, h) = resize.size;
+        self.resize_target(gpu, w, h);
+        if let Some(on_resize) = &mut self.on_resize {
+            on_resize(resize, &mut self.state.camera);
+        }
+        if resize.previous != resize.size {
+            self.resized = merge_resizes(self.resized, resize);
+        }
+    }
 
+    fn suspended(&mut self) {
+        #[cfg(not(all(feature = "render_thread", not(target_arch = "wasm32"))))]
         {
-            let mut r_pass = renderer.begin_render_pass(&mut frame.encoder, &frame.view);
-
-            for (tex_id, shader_id, batch) in self.primitive_batch.iter_mut() {
-                renderer.draw_batch(&mut r_pass, batch, tex_id, shader_id);
-            }
+            self.backbuffer = None;
+        }
+        // the render thread keeps the old swapchain until `resumed` replaces it
+        #[cfg(all(feature = "render_thread", not(target_arch = "wasm32")))]
+        {
+            self.suspended = true;
+        }
+        self.visibility_changed(false);
+    }
 
-            text_renderer.render(&mut r_pass);
+    fn resumed(&mut self, window: Arc<Window>, gpu: &mut Gpu) {
+        let renderer = self.renderer(gpu);
+        let size = window.inner_size();
+        let mut backbuffer = self.window_target(renderer, window, size.width, size.height);
+        backbuffer.set_vsync(renderer.device(), self.vsync);
+        renderer.set_alpha_mode(backbuffer.alpha_mode());
+        if let Some(text_renderer) = &mut self.state.text_renderer {
+            text_renderer.premultiplied = renderer.premultiplied();
+        }
+        #[cfg(not(all(feature = "render_thread", not(target_arch = "wasm32"))))]
+        {
+            self.backbuffer = Some(backbuffer);
+        }
+        #[cfg(all(feature = "render_thread", not(target_arch = "wasm32")))]
+        {
+            gpu.set_target(Some(Box::new(backbuffer)));
+            self.suspended = false;
         }
+        self.visibility_changed(true);
+    }
 
-        self.primitive_batch.reset();
+    fn visibility_changed(&mut self, visible: bool) {
+        let handler = match visible {
+            true => &mut self.on_resume,
+            false => &mut self.on_suspend,
+        };
+        if let Some(handler) = handler {
+            handler();
+        }
+    }
+}
 
-        #[cfg(feature = "ui")]
+impl App {
+    /// Swapchain for `window`, transparent when [`Self::transparent`] is on
+    fn window_target(
+        &self,
+        renderer: &Renderer,
+        window: Arc<Window>,
+        w: u32,
+        h: u32,
+    ) -> Backbuffer {
+        let mut backbuffer = Backbuffer::new(
+            renderer.instance(),
+            renderer.adapter(),
+            renderer.device(),
+            window,
+            renderer.surface_format(),
+            w,
+            h,
+        );
+        if self.transparent
+            && !backbuffer.set_transparent(renderer.adapter(), renderer.device(), true)
         {
-            let render_data = self.egui.as_mut().unwrap().end_frame(_window);
-            self.egui.as_mut().unwrap().render(
-                &device,
-                &queue,
-                &mut frame.encoder,
-                &frame.view,
-                w,
-                h,
-                render_data,
-            );
+            log::warn!("the surface can't composite alpha, so the window stays opaque");
         }
+        backbuffer
+    }
 
-        renderer.end_frame(frame);
+    /// Run the update headless at the configured window size & save frames as PNGs instead
+    /// of opening a window. Frames are drawn like windowed ones, custom & background passes
+    /// included
+    #[cfg(all(feature = "snapshot", not(target_arch = "wasm32")))]
+    fn run_snapshot(mut self, config: &AppConfig, request: &crate::snapshot::SnapshotRequest) {
+        let (w, h) = (config.width.unwrap_or(800), config.height.unwrap_or(600));
+        let update = self.update.take().unwrap();
+        let state = std::mem::take(&mut self.state);
+        let hooks = std::mem::take(&mut self.hooks);
+        let mut app = crate::headless::HeadlessApp::with_state(w, h, update, state)
+            .with_app_state(hooks, self.on_resize.take());
+        request.run(&mut app);
+    }
+}
 
-        if let Some((rw, rh)) = requested_size {
-            self.backbuffer.as_mut().unwrap().resize(&device, rw, rh);
-        }
-        if let Some(vsync) = requested_vsync {
-            self.backbuffer.as_mut().unwrap().set_vsync(&device, vsync);
-            self.vsync = vsync;
+/// `next` on top of a resize no frame has seen yet, keeping the size frames last saw.
+/// `None` when it's back to that size
+pub(crate) fn merge_resizes(pending: Option<Resize>, next: Resize) -> Option<Resize> {
+    let previous = pending.map_or(next.previous, |p| p.previous);
+    (previous != next.size).then_some(Resize { previous, ..next })
+}
+
+/// Fills `{error}` in `template` with the HTML-escaped `error`
+#[cfg(any(target_arch = "wasm32", test))]
+fn init_error_html(template: &str, error: &dyn Error) -> String {
+    let mut escaped = String::new();
+    for c in error.to_string().chars() {
+        match c {
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '&' => escaped.push_str("&amp;"),
+            '"' => escaped.push_str("&quot;"),
+            c => escaped.push(c),
         }
     }
+    template.replace("{error}", &escaped)
+}
 
-    fn resize(&mut self, w: u32, h: u32, renderer: &mut Renderer) {
-        self.backbuffer
-            .as_mut()
-            .unwrap()
-            .resize(renderer.device(), w, h);
-        self.text_renderer
-            .as_mut()
-            .unwrap()
-            .resize(w, h, renderer.queue());
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use egor_app::input::KeyCode;
+    use egor_render::RendererError;
+    use std::{cell::RefCell, rc::Rc};
+
+    #[test]
+    fn both_update_signatures_are_accepted() {
+        fn accepts<M>(_: impl FrameUpdate<M>) {}
+        fn parts(gfx: &mut Graphics, input: &Input, timer: &FrameTimer) {
+            let _ = (gfx.screen_size(), input.mouse_position(), timer.delta);
+        }
+        accepts(parts);
+        accepts(|FrameContext { gfx, .. }: &mut FrameContext| {
+            gfx.screen_size();
+        });
+        accepts(|ctx: &mut FrameContext| {
+            let _ = (ctx.input(), ctx.timer(), ctx.mouse_world());
+        });
     }
 
-    fn suspended(&mut self) {
-        self.backbuffer = None;
+    #[test]
+    fn resizes_between_frames_merge() {
+        let resize = |previous, size| Resize { previous, size };
+        let first = merge_resizes(None, resize((800, 600), (1024, 768)));
+        assert_eq!(first, Some(resize((800, 600), (1024, 768))));
+        let second = merge_resizes(first, resize((1024, 768), (1920, 1080)));
+        assert_eq!(second, Some(resize((800, 600), (1920, 1080))));
+        // back where the last frame was
+        assert_eq!(
+            merge_resizes(second, resize((1920, 1080), (800, 600))),
+            None
+        );
     }
 
-    fn resumed(&mut self, window: Arc<Window>, renderer: &mut Renderer) {
-        let size = window.inner_size();
-        let device = renderer.device();
-        let mut backbuffer = Backbuffer::new(
-            renderer.instance(),
-            renderer.adapter(),
-            device,
-            window,
-            size.width,
-            size.height,
+    #[test]
+    fn init_error_html_escapes_the_error() {
+        let html = init_error_html("<b>oops</b> {error}", &RendererError::UnsupportedSurface);
+        assert_eq!(
+            html,
+            "<b>oops</b> the GPU adapter can't present to the window"
         );
-        backbuffer.set_vsync(device, self.vsync);
-        self.backbuffer = Some(backbuffer);
+        let error = std::io::Error::other("<canvas> & friends");
+        let html = init_error_html(INIT_ERROR_HTML, &error);
+        assert!(html.ends_with("<pre>&lt;canvas&gt; &amp; friends</pre>"));
+    }
+
+    /// Runs a frame of `delta` seconds, returning alpha & what each tick saw of `key`
+    fn fixed_frame(
+        step: &mut FixedStep,
+        delta: f32,
+        input: &Input,
+    ) -> (f32, Vec<(bool, bool, f32)>) {
+        let mut ticks = Vec::new();
+        let alpha = step.frame(delta, input, |tick| {
+            let key = KeyCode::Space;
+            let input = tick.input;
+            ticks.push((
+                input.key_pressed(key),
+                input.key_held(key),
+                input.mouse_scroll(),
+            ));
+        });
+        (alpha, ticks)
+    }
+
+    #[test]
+    fn fixed_steps_accumulate_and_clamp() {
+        let mut step = FixedStep::new(50.0);
+        let idle = Input::default();
+        let tick_counts: Vec<_> = (0..5)
+            .map(|_| fixed_frame(&mut step, 0.016, &idle).1.len())
+            .collect();
+        // 80ms hold four 20ms ticks
+        assert_eq!(tick_counts, [0, 1, 1, 1, 1]);
+        let (alpha, _) = fixed_frame(&mut step, 0.0, &idle);
+        assert!((alpha - 0.0).abs() < 1e-3, "{alpha}");
+        let (alpha, _) = fixed_frame(&mut step, 0.01, &idle);
+        assert!((alpha - 0.5).abs() < 1e-3, "{alpha}");
+
+        // a 10 second stall runs a bounded burst & forgets the rest
+        let (_, ticks) = fixed_frame(&mut step, 10.0, &idle);
+        assert_eq!(ticks.len(), MAX_TICKS_PER_FRAME as usize);
+        assert!(fixed_frame(&mut step, 0.0, &idle).1.is_empty());
+        assert_eq!(step.tick, 4 + MAX_TICKS_PER_FRAME as u64);
+    }
+
+    #[test]
+    fn fixed_ticks_see_each_press_once() {
+        let mut step = FixedStep::new(50.0);
+        let mut input = Input::default();
+        let held = |keys: &[KeyCode]| InputSnapshot {
+            keys: keys.iter().copied().collect(),
+            mouse_scroll: 1.0,
+            ..Default::default()
+        };
+
+        // two ticks in one frame: only the first sees the press & scroll
+        input.apply_snapshot(&held(&[KeyCode::Space]));
+        let (_, ticks) = fixed_frame(&mut step, 0.04, &input);
+        assert_eq!(ticks, [(true, true, 1.0), (false, true, 0.0)]);
+
+        // a tap during frames without a tick still reaches the next tick
+        input.apply_snapshot(&held(&[]));
+        fixed_frame(&mut step, 0.02, &input);
+        input.apply_snapshot(&held(&[KeyCode::Space]));
+        assert!(fixed_frame(&mut step, 0.005, &input).1.is_empty());
+        input.apply_snapshot(&held(&[]));
+        let (_, ticks) = fixed_frame(&mut step, 0.015, &input);
+        assert_eq!(ticks, [(true, true, 2.0)]);
+        let (_, ticks) = fixed_frame(&mut step, 0.02, &input);
+        assert_eq!(ticks, [(false, false, 1.0)]);
+    }
+
+    #[test]
+    fn frame_hooks_run_in_order_on_skipped_frames() {
+        let markers = Rc::new(RefCell::new(Vec::new()));
+        let marker = |name: &'static str| {
+            let markers = markers.clone();
+            move || markers.borrow_mut().push(name)
+        };
+        let (first, second, last) = (marker("first"), marker("second"), marker("last"));
+        let mut app = App::new()
+            .after_frame(move |_, _, summary| {
+                assert!(!summary.rendered);
+                last();
+            })
+            .before_frame(move |_, _| first())
+            .before_frame(move |_, _| second());
+
+        // a minimized window has no surface to draw to, so only the hooks run
+        app.skipped_frame(&Input::default(), &FrameTimer::default());
+        assert_eq!(*markers.borrow(), ["first", "second", "last"]);
     }
 }
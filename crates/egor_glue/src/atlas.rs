@@ -0,0 +1,275 @@
+use crate::graphics::TextureId;
+use glam::{Vec2, vec2};
+#[cfg(feature = "serde")]
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+/// Empty pixels kept between packed images so neighbours don't bleed when sampled
+const PADDING: u32 = 1;
+
+/// Region of a [`TextureAtlas`], usable anywhere a texture id & UVs are
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SubTexture {
+    /// Texture id of the atlas this region lives in
+    pub texture_id: usize,
+    /// UV coordinates of the region as (u0, v0, u1, v1)
+    pub uv: [f32; 4],
+    /// Size of the region in pixels
+    pub size: (u32, u32),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AtlasError {
+    /// No free region is large enough; create another atlas
+    OutOfSpace,
+    /// The pixel data doesn't match `width * height * 4` bytes, or an image can't be decoded
+    InvalidData,
+    /// An atlas manifest isn't valid JSON, or its pages don't match the images given
+    InvalidManifest,
+}
+
+impl fmt::Display for AtlasError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::OutOfSpace => write!(f, "texture atlas is full"),
+            Self::InvalidData => write!(f, "image data doesn't match its dimensions"),
+            Self::InvalidManifest => write!(f, "atlas manifest doesn't match its pages"),
+        }
+    }
+}
+
+impl std::error::Error for AtlasError {}
+
+/// A row of images sharing the same top edge
+struct Shelf {
+    y: u32,
+    height: u32,
+    next_x: u32,
+}
+
+/// A single texture that many small images are packed into, so they can be drawn
+/// in one batch. Created with [`crate::graphics::Graphics::create_atlas`]
+pub struct TextureAtlas {
+    texture_id: usize,
+    width: u32,
+    height: u32,
+    shelves: Vec<Shelf>,
+    next_y: u32,
+    white_pixel: Option<(u32, u32)>,
+}
+
+impl TextureAtlas {
+    pub(crate) fn new(texture_id: usize, width: u32, height: u32) -> Self {
+        Self {
+            texture_id,
+            width,
+            height,
+            shelves: Vec::new(),
+            next_y: 0,
+            white_pixel: None,
+        }
+    }
+
+    /// Texture id of the whole atlas
+    pub fn texture_id(&self) -> TextureId {
+        TextureId(self.texture_id)
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    /// UV of the center of the atlas' white pixel, for solid-color shapes.
+    /// `None` if the atlas was too small to reserve one
+    pub fn white_uv(&self) -> Option<Vec2> {
+        let (x, y) = self.white_pixel?;
+        Some(vec2(
+            (x as f32 + 0.5) / self.width as f32,
+            (y as f32 + 0.5) / self.height as f32,
+        ))
+    }
+
+    /// Packs a 1x1 region for a white pixel, returning its position to fill in
+    pub(crate) fn reserve_white_pixel(&mut self) -> Option<(u32, u32)> {
+        let position = self.pack(1, 1).ok()?;
+        self.white_pixel = Some(position);
+        Some(position)
+    }
+
+    /// Finds a free spot for a `w` x `h` image using shelf packing & reserves it
+    pub(crate) fn pack(&mut self, w: u32, h: u32) -> Result<(u32, u32), AtlasError> {
+        if w == 0 || h == 0 || w > self.width || h > self.height {
+            return Err(AtlasError::OutOfSpace);
+        }
+        let (padded_w, padded_h) = (w + PADDING, h + PADDING);
+
+        // the shortest shelf that fits wastes the least vertical space
+        let width = self.width;
+        let best = self
+            .shelves
+            .iter_mut()
+            .filter(|s| s.height >= padded_h && s.next_x + w <= width)
+            .min_by_key(|s| s.height);
+        if let Some(shelf) = best {
+            let position = (shelf.next_x, shelf.y);
+            shelf.next_x += padded_w;
+            return Ok(position);
+        }
+
+        if self.next_y + h > self.height {
+            return Err(AtlasError::OutOfSpace);
+        }
+        let position = (0, self.next_y);
+        self.shelves.push(Shelf {
+            y: self.next_y,
+            height: padded_h,
+            next_x: padded_w,
+        });
+        self.next_y += padded_h;
+        Ok(position)
+    }
+
+    pub(crate) fn sub_texture(&self, (x, y): (u32, u32), (w, h): (u32, u32)) -> SubTexture {
+        let (aw, ah) = (self.width as f32, self.height as f32);
+        SubTexture {
+            texture_id: self.texture_id,
+            uv: [
+                x as f32 / aw,
+                y as f32 / ah,
+                (x + w) as f32 / aw,
+                (y + h) as f32 / ah,
+            ],
+            size: (w, h),
+        }
+    }
+}
+
+/// Version of the manifest format written by `cargo xtask pack-atlas`
+#[cfg(feature = "serde")]
+const MANIFEST_VERSION: u32 = 1;
+
+/// Pre-packed atlas layout as written by `cargo xtask pack-atlas`:
+/// `{ "version": 1, "pages": [{ "image", "width", "height" }],
+/// "regions": { name: { "page", "x", "y", "width", "height" } } }`
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct AtlasManifest {
+    version: u32,
+    pages: Vec<ManifestPage>,
+    regions: BTreeMap<String, ManifestRegion>,
+}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct ManifestPage {
+    width: u32,
+    height: u32,
+}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct ManifestRegion {
+    page: usize,
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+}
+
+/// Named regions of a manifest whose pages were uploaded as `pages`, given as
+/// (texture id, width, height) in page order. Regions must lie inside their page
+#[cfg(feature = "serde")]
+pub(crate) fn manifest_regions(
+    json: &[u8],
+    pages: &[(usize, u32, u32)],
+) -> Result<HashMap<String, SubTexture>, AtlasError> {
+    let manifest: AtlasManifest =
+        serde_json::from_slice(json).map_err(|_| AtlasError::InvalidManifest)?;
+    let sizes_match = manifest.pages.len() == pages.len()
+        && (manifest.pages.iter().zip(pages)).all(|(m, &(_, w, h))| (m.width, m.height) == (w, h));
+    if manifest.version != MANIFEST_VERSION || !sizes_match {
+        return Err(AtlasError::InvalidManifest);
+    }
+
+    let mut regions = HashMap::with_capacity(manifest.regions.len());
+    for (name, r) in manifest.regions {
+        let &(texture_id, width, height) = pages.get(r.page).ok_or(AtlasError::InvalidManifest)?;
+        if r.x + r.width > width || r.y + r.height > height {
+            return Err(AtlasError::InvalidManifest);
+        }
+        let page = TextureAtlas::new(texture_id, width, height);
+        regions.insert(name, page.sub_texture((r.x, r.y), (r.width, r.height)));
+    }
+    Ok(regions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn packs_rows_onto_shelves() {
+        let mut atlas = TextureAtlas::new(0, 64, 64);
+        assert_eq!(atlas.pack(16, 16), Ok((0, 0)));
+        assert_eq!(atlas.pack(16, 8), Ok((17, 0)));
+        // too wide for the first shelf, so a new one starts below it
+        assert_eq!(atlas.pack(40, 16), Ok((0, 17)));
+        // short images reuse the tightest shelf with room
+        assert_eq!(atlas.pack(8, 8), Ok((34, 0)));
+    }
+
+    #[test]
+    fn reports_out_of_space() {
+        let mut atlas = TextureAtlas::new(0, 32, 32);
+        assert_eq!(atlas.pack(64, 1), Err(AtlasError::OutOfSpace));
+        assert_eq!(atlas.pack(32, 20), Ok((0, 0)));
+        assert_eq!(atlas.pack(32, 12), Err(AtlasError::OutOfSpace));
+        assert_eq!(atlas.pack(32, 11), Ok((0, 21)));
+        assert_eq!(atlas.pack(u32::MAX, u32::MAX), Err(AtlasError::OutOfSpace));
+    }
+
+    #[test]
+    fn sub_texture_uvs_cover_the_region() {
+        let atlas = TextureAtlas::new(3, 100, 50);
+        let sub = atlas.sub_texture((10, 5), (20, 10));
+        assert_eq!(sub.texture_id, 3);
+        assert_eq!(sub.uv, [0.1, 0.1, 0.3, 0.3]);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn manifest_regions_map_to_their_page_textures() {
+        let json = br#"{
+            "version": 1,
+            "pages": [
+                { "image": "sprites.png", "width": 64, "height": 32 },
+                { "image": "sprites_1.png", "width": 16, "height": 16 }
+            ],
+            "regions": {
+                "player": { "page": 0, "x": 32, "y": 0, "width": 16, "height": 16 },
+                "coin": { "page": 1, "x": 0, "y": 0, "width": 8, "height": 8 }
+            }
+        }"#;
+        let regions = manifest_regions(json, &[(4, 64, 32), (5, 16, 16)]).unwrap();
+        assert_eq!(regions["player"].texture_id, 4);
+        assert_eq!(regions["player"].uv, [0.5, 0.0, 0.75, 0.5]);
+        assert_eq!(regions["coin"].texture_id, 5);
+        assert_eq!(regions["coin"].size, (8, 8));
+
+        // a missing page or one with another size doesn't match this manifest
+        let invalid = Err(AtlasError::InvalidManifest);
+        assert_eq!(manifest_regions(json, &[(4, 64, 32)]), invalid);
+        assert_eq!(manifest_regions(json, &[(4, 64, 64), (5, 16, 16)]), invalid);
+        assert_eq!(manifest_regions(b"not json", &[]), invalid);
+    }
+
+    #[test]
+    fn white_pixel_uv_samples_its_center() {
+        let mut atlas = TextureAtlas::new(0, 64, 32);
+        assert_eq!(atlas.white_uv(), None);
+        assert_eq!(atlas.reserve_white_pixel(), Some((0, 0)));
+        assert_eq!(atlas.white_uv(), Some(vec2(0.5 / 64.0, 0.5 / 32.0)));
+        // the padding keeps images from bleeding into it
+        assert_eq!(atlas.pack(8, 8), Ok((0, 2)));
+    }
+}
@@ -0,0 +1,530 @@
+//! Fonts baked into a texture, drawn as one textured quad per glyph in the sprite batch.
+//! Cheaper than shaped text for numbers that change every frame & they follow the camera,
+//! rotate & sort like any other sprite
+
+use egor_render::{Renderer, instance::Instance};
+use glam::{Mat2, Vec2, vec2};
+#[cfg(feature = "serde")]
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+
+#[cfg(feature = "serde")]
+use crate::atlas::AtlasError;
+use crate::{
+    alloc_guard,
+    color::Color,
+    graphics::TextureId,
+    primitives::{PrimitiveBatch, debug_assert_finite},
+};
+
+/// Where a glyph is in a [`BitmapFont`]'s texture & how it's placed on a line
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Glyph {
+    /// UV coordinates of the glyph as (u0, v0, u1, v1)
+    pub uv: [f32; 4],
+    /// Size of the glyph in texture pixels
+    pub size: Vec2,
+    /// Offset of the glyph's top-left corner from the pen, in texture pixels
+    pub offset: Vec2,
+    /// How far the pen moves after the glyph, in texture pixels
+    pub advance: f32,
+}
+
+/// A font whose glyphs are regions of one texture. Load one with
+/// [`crate::graphics::Graphics::load_bitmap_font_grid`] (or `Graphics::load_bitmap_font` with
+/// the `serde` feature) & draw it with [`crate::graphics::Graphics::bitmap_text`]
+#[derive(Clone, Debug)]
+pub struct BitmapFont {
+    texture_id: usize,
+    glyphs: HashMap<char, Glyph>,
+    line_height: f32,
+}
+
+impl BitmapFont {
+    /// A font of equally sized `cell`s filling `texture_size` row by row, holding `chars`
+    /// in order. Each glyph covers & advances by its whole cell
+    pub fn grid(
+        texture_id: usize,
+        texture_size: (u32, u32),
+        cell: (u32, u32),
+        chars: &str,
+    ) -> Self {
+        let columns = (texture_size.0 / cell.0.max(1)).max(1);
+        let glyphs = chars.chars().enumerate().map(|(i, c)| {
+            let i = i as u32;
+            let position = ((i % columns) * cell.0, (i / columns) * cell.1);
+            (c, glyph_rect(texture_size, position, cell, cell.0))
+        });
+        Self {
+            texture_id,
+            glyphs: glyphs.collect(),
+            line_height: cell.1 as f32,
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    pub(crate) fn with_texture(mut self, texture_id: usize) -> Self {
+        self.texture_id = texture_id;
+        self
+    }
+
+    /// Texture id the glyphs are sampled from
+    pub fn texture_id(&self) -> TextureId {
+        TextureId(self.texture_id)
+    }
+
+    /// Distance between lines in texture pixels
+    pub fn line_height(&self) -> f32 {
+        self.line_height
+    }
+
+    /// The glyph drawn for `c`: the glyph itself, else its uppercase, else `None`
+    pub fn glyph(&self, c: char) -> Option<&Glyph> {
+        self.glyphs
+            .get(&c)
+            .or_else(|| self.glyphs.get(&c.to_ascii_uppercase()))
+    }
+
+    /// Size of `text` drawn `size` units tall per line
+    pub fn measure(&self, text: &str, size: f32) -> Vec2 {
+        let mut extent = Vec2::ZERO;
+        let mut lines = 0;
+        self.layout(text, |pen, glyph| {
+            let advance = glyph.map_or(self.line_height / 2.0, |g| g.advance);
+            extent.x = extent.x.max(pen.x + advance);
+            lines = lines.max((pen.y / self.line_height) as u32 + 1);
+        });
+        extent.y = lines as f32 * self.line_height;
+        extent * (size / self.line_height)
+    }
+
+    /// Calls `f` with the pen position of every character of `text` that isn't a line break
+    /// & its glyph, in texture pixels. Characters without a glyph advance half a line height
+    fn layout(&self, text: &str, mut f: impl FnMut(Vec2, Option<&Glyph>)) {
+        let mut pen = Vec2::ZERO;
+        for c in text.chars() {
+            if c == '\n' {
+                pen = vec2(0.0, pen.y + self.line_height);
+                continue;
+            }
+            let glyph = self.glyph(c);
+            f(pen, glyph);
+            pen.x += glyph.map_or(self.line_height / 2.0, |g| g.advance);
+        }
+    }
+}
+
+fn glyph_rect((tw, th): (u32, u32), (x, y): (u32, u32), (w, h): (u32, u32), advance: u32) -> Glyph {
+    let (tw, th) = (tw as f32, th as f32);
+    Glyph {
+        uv: [
+            x as f32 / tw,
+            y as f32 / th,
+            (x + w) as f32 / tw,
+            (y + h) as f32 / th,
+        ],
+        size: vec2(w as f32, h as f32),
+        offset: Vec2::ZERO,
+        advance: advance as f32,
+    }
+}
+
+/// Glyph map of a bitmap font: either a grid of `cell`-sized glyphs holding `chars`, or
+/// `glyphs` placed one by one
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct GlyphMap {
+    cell: Option<(u32, u32)>,
+    chars: Option<String>,
+    line_height: Option<u32>,
+    #[serde(default)]
+    glyphs: BTreeMap<String, GlyphEntry>,
+}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct GlyphEntry {
+    x: u32,
+    y: u32,
+    w: u32,
+    h: u32,
+    #[serde(default)]
+    offset: (f32, f32),
+    advance: Option<u32>,
+}
+
+/// Builds a font from a JSON glyph map for a `texture_size` texture, failing if the JSON is
+/// malformed or a glyph is outside the texture. Set its texture with
+/// [`BitmapFont::with_texture`] once it's uploaded
+#[cfg(feature = "serde")]
+pub(crate) fn from_glyph_map(
+    json: &[u8],
+    texture_size: (u32, u32),
+) -> Result<BitmapFont, AtlasError> {
+    let map: GlyphMap = serde_json::from_slice(json).map_err(|_| AtlasError::InvalidManifest)?;
+    let mut font = match (map.cell, &map.chars) {
+        (Some(cell), Some(chars)) => {
+            if cell.0 == 0 || cell.1 == 0 {
+                return Err(AtlasError::InvalidManifest);
+            }
+            let per_row = texture_size.0 / cell.0;
+            let rows = texture_size.1 / cell.1;
+            if (chars.chars().count() as u32).div_ceil(per_row.max(1)) > rows {
+                return Err(AtlasError::InvalidManifest);
+            }
+            BitmapFont::grid(0, texture_size, cell, chars)
+        }
+        (None, None) => BitmapFont {
+            texture_id: 0,
+            glyphs: HashMap::new(),
+            line_height: 0.0,
+        },
+        _ => return Err(AtlasError::InvalidManifest),
+    };
+
+    for (key, entry) in map.glyphs {
+        let mut chars = key.chars();
+        let (Some(c), None) = (chars.next(), chars.next()) else {
+            return Err(AtlasError::InvalidManifest);
+        };
+        let fits = entry
+            .x
+            .checked_add(entry.w)
+            .is_some_and(|r| r <= texture_size.0)
+            && entry
+                .y
+                .checked_add(entry.h)
+                .is_some_and(|b| b <= texture_size.1);
+        if !fits {
+            return Err(AtlasError::InvalidManifest);
+        }
+        let advance = entry.advance.unwrap_or(entry.w);
+        let mut glyph = glyph_rect(
+            texture_size,
+            (entry.x, entry.y),
+            (entry.w, entry.h),
+            advance,
+        );
+        glyph.offset = entry.offset.into();
+        font.glyphs.insert(c, glyph);
+    }
+
+    font.line_height = match map.line_height {
+        Some(height) => height as f32,
+        None if font.line_height > 0.0 => font.line_height,
+        None => font.glyphs.values().map(|g| g.size.y).fold(0.0, f32::max),
+    };
+    if font.line_height <= 0.0 {
+        return Err(AtlasError::InvalidManifest);
+    }
+    Ok(font)
+}
+
+/// Cell size of the built-in debug font, a 5x7 glyph with a pixel of spacing
+const DEBUG_CELL: (u32, u32) = (6, 8);
+const DEBUG_COLUMNS: u32 = 16;
+/// Rows of the printable ASCII characters from space to `_`, 5 bits per row with the
+/// leftmost pixel highest. Lowercase letters draw as uppercase
+#[rustfmt::skip]
+const DEBUG_GLYPHS: [[u8; 7]; 64] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // space
+    [0x04, 0x04, 0x04, 0x04, 0x04, 0x00, 0x04], // !
+    [0x0A, 0x0A, 0x00, 0x00, 0x00, 0x00, 0x00], // "
+    [0x0A, 0x0A, 0x1F, 0x0A, 0x1F, 0x0A, 0x0A], // #
+    [0x04, 0x0F, 0x14, 0x0E, 0x05, 0x1E, 0x04], // $
+    [0x18, 0x19, 0x02, 0x04, 0x08, 0x13, 0x03], // %
+    [0x0C, 0x12, 0x14, 0x08, 0x15, 0x12, 0x0D], // &
+    [0x04, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00], // '
+    [0x02, 0x04, 0x08, 0x08, 0x08, 0x04, 0x02], // (
+    [0x08, 0x04, 0x02, 0x02, 0x02, 0x04, 0x08], // )
+    [0x00, 0x04, 0x15, 0x0E, 0x15, 0x04, 0x00], // *
+    [0x00, 0x04, 0x04, 0x1F, 0x04, 0x04, 0x00], // +
+    [0x00, 0x00, 0x00, 0x00, 0x0C, 0x04, 0x08], // ,
+    [0x00, 0x00, 0x00, 0x1F, 0x00, 0x00, 0x00], // -
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x0C], // .
+    [0x00, 0x01, 0x02, 0x04, 0x08, 0x10, 0x00], // /
+    [0x0E, 0x11, 0x13, 0x15, 0x19, 0x11, 0x0E], // 0
+    [0x04, 0x0C, 0x04, 0x04, 0x04, 0x04, 0x0E], // 1
+    [0x0E, 0x11, 0x01, 0x02, 0x04, 0x08, 0x1F], // 2
+    [0x1F, 0x02, 0x04, 0x02, 0x01, 0x11, 0x0E], // 3
+    [0x02, 0x06, 0x0A, 0x12, 0x1F, 0x02, 0x02], // 4
+    [0x1F, 0x10, 0x1E, 0x01, 0x01, 0x11, 0x0E], // 5
+    [0x06, 0x08, 0x10, 0x1E, 0x11, 0x11, 0x0E], // 6
+    [0x1F, 0x01, 0x02, 0x04, 0x08, 0x08, 0x08], // 7
+    [0x0E, 0x11, 0x11, 0x0E, 0x11, 0x11, 0x0E], // 8
+    [0x0E, 0x11, 0x11, 0x0F, 0x01, 0x02, 0x0C], // 9
+    [0x00, 0x0C, 0x0C, 0x00, 0x0C, 0x0C, 0x00], // :
+    [0x00, 0x0C, 0x0C, 0x00, 0x0C, 0x04, 0x08], // ;
+    [0x02, 0x04, 0x08, 0x10, 0x08, 0x04, 0x02], // <
+    [0x00, 0x00, 0x1F, 0x00, 0x1F, 0x00, 0x00], // =
+    [0x08, 0x04, 0x02, 0x01, 0x02, 0x04, 0x08], // >
+    [0x0E, 0x11, 0x01, 0x02, 0x04, 0x00, 0x04], // ?
+    [0x0E, 0x11, 0x01, 0x0D, 0x15, 0x15, 0x0E], // @
+    [0x0E, 0x11, 0x11, 0x11, 0x1F, 0x11, 0x11], // A
+    [0x1E, 0x11, 0x11, 0x1E, 0x11, 0x11, 0x1E], // B
+    [0x0E, 0x11, 0x10, 0x10, 0x10, 0x11, 0x0E], // C
+    [0x1C, 0x12, 0x11, 0x11, 0x11, 0x12, 0x1C], // D
+    [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x1F], // E
+    [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x10], // F
+    [0x0E, 0x11, 0x10, 0x17, 0x11, 0x11, 0x0F], // G
+    [0x11, 0x11, 0x11, 0x1F, 0x11, 0x11, 0x11], // H
+    [0x0E, 0x04, 0x04, 0x04, 0x04, 0x04, 0x0E], // I
+    [0x07, 0x02, 0x02, 0x02, 0x02, 0x12, 0x0C], // J
+    [0x11, 0x12, 0x14, 0x18, 0x14, 0x12, 0x11], // K
+    [0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x1F], // L
+    [0x11, 0x1B, 0x15, 0x15, 0x11, 0x11, 0x11], // M
+    [0x11, 0x11, 0x19, 0x15, 0x13, 0x11, 0x11], // N
+    [0x0E, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E], // O
+    [0x1E, 0x11, 0x11, 0x1E, 0x10, 0x10, 0x10], // P
+    [0x0E, 0x11, 0x11, 0x11, 0x15, 0x12, 0x0D], // Q
+    [0x1E, 0x11, 0x11, 0x1E, 0x14, 0x12, 0x11], // R
+    [0x0F, 0x10, 0x10, 0x0E, 0x01, 0x01, 0x1E], // S
+    [0x1F, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04], // T
+    [0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E], // U
+    [0x11, 0x11, 0x11, 0x11, 0x11, 0x0A, 0x04], // V
+    [0x11, 0x11, 0x11, 0x15, 0x15, 0x15, 0x0A], // W
+    [0x11, 0x11, 0x0A, 0x04, 0x0A, 0x11, 0x11], // X
+    [0x11, 0x11, 0x11, 0x0A, 0x04, 0x04, 0x04], // Y
+    [0x1F, 0x01, 0x02, 0x04, 0x08, 0x10, 0x1F], // Z
+    [0x0E, 0x08, 0x08, 0x08, 0x08, 0x08, 0x0E], // [
+    [0x00, 0x10, 0x08, 0x04, 0x02, 0x01, 0x00], // \
+    [0x0E, 0x02, 0x02, 0x02, 0x02, 0x02, 0x0E], // ]
+    [0x04, 0x0A, 0x11, 0x00, 0x00, 0x00, 0x00], // ^
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x1F], // _
+];
+
+fn debug_font_size() -> (u32, u32) {
+    let rows = (DEBUG_GLYPHS.len() as u32).div_ceil(DEBUG_COLUMNS);
+    (DEBUG_COLUMNS * DEBUG_CELL.0, rows * DEBUG_CELL.1)
+}
+
+/// RGBA pixels of the built-in debug font: white glyphs on transparent cells
+fn debug_font_pixels() -> Vec<u8> {
+    let (w, h) = debug_font_size();
+    let mut pixels = vec![0; (w * h * 4) as usize];
+    for (i, rows) in DEBUG_GLYPHS.iter().enumerate() {
+        let i = i as u32;
+        let (cx, cy) = (
+            (i % DEBUG_COLUMNS) * DEBUG_CELL.0,
+            (i / DEBUG_COLUMNS) * DEBUG_CELL.1,
+        );
+        for (y, bits) in rows.iter().enumerate() {
+            for x in (0..5).filter(|x| bits & (0x10 >> x) != 0) {
+                let start = (((cy + y as u32) * w + cx + x) * 4) as usize;
+                pixels[start..start + 4].fill(255);
+            }
+        }
+    }
+    pixels
+}
+
+/// Uploads the built-in debug font for [`crate::graphics::Graphics::debug_text`]
+pub(crate) fn load_debug_font(renderer: &mut Renderer) -> BitmapFont {
+    let (w, h) = debug_font_size();
+    let texture_id = renderer.add_texture_raw(w, h, &debug_font_pixels());
+    debug_font(texture_id)
+}
+
+fn debug_font(texture_id: usize) -> BitmapFont {
+    let chars: String = (' '..='_').collect();
+    BitmapFont::grid(texture_id, debug_font_size(), DEBUG_CELL, &chars)
+}
+
+/// Draws a line of [`BitmapFont`] text when dropped, one quad per glyph
+pub struct BitmapTextBuilder<'a> {
+    batch: &'a mut PrimitiveBatch,
+    shader_id: Option<usize>,
+    /// `None` for the built-in debug font kept in the batch
+    font: Option<&'a BitmapFont>,
+    text: &'a str,
+    position: Vec2,
+    size: f32,
+    rotation: f32,
+    color: Color,
+}
+
+/// Builds bitmap text with configurable position, size, color & rotation
+impl<'a> BitmapTextBuilder<'a> {
+    pub(crate) fn new(
+        batch: &'a mut PrimitiveBatch,
+        shader_id: Option<usize>,
+        font: Option<&'a BitmapFont>,
+        text: &'a str,
+    ) -> Self {
+        let size = font.map_or(DEBUG_CELL.1 as f32, |f| f.line_height);
+        Self {
+            batch,
+            shader_id,
+            font,
+            text,
+            position: Vec2::ZERO,
+            size,
+            rotation: 0.0,
+            color: Color::WHITE,
+        }
+    }
+    /// Sets the world-space position of the text's top-left corner
+    pub fn at(mut self, position: impl Into<Vec2>) -> Self {
+        self.position = position.into();
+        self
+    }
+    /// Sets the height of a line in world units. Defaults to the font's line height in
+    /// texture pixels
+    pub fn size(mut self, size: f32) -> Self {
+        self.size = size;
+        self
+    }
+    /// Moves the text's place in a layer sorted by y, see
+    /// [`crate::primitives::RectangleBuilder::sort_bias`]
+    pub fn sort_bias(self, bias: f32) -> Self {
+        self.batch.sort_bias = bias;
+        self
+    }
+    /// Sets the color the glyphs are tinted with
+    pub fn color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+    /// Sets rotation in radians around the text's top-left corner, from +X toward +Y
+    pub fn rotate(mut self, angle: f32) -> Self {
+        self.rotation = angle;
+        self
+    }
+}
+
+impl BitmapTextBuilder<'_> {
+    fn submit(&mut self) {
+        let _guard = alloc_guard::scope();
+        if self.batch.skips_area("bitmap text", self.size * self.size) {
+            return;
+        }
+        let debug = self.font.is_none().then(|| self.batch.debug_font.take());
+        let Some(font) = self.font.or(debug.as_ref().and_then(Option::as_ref)) else {
+            return;
+        };
+
+        let scale = self.size / font.line_height;
+        let rot = Mat2::from_angle(self.rotation);
+        let (texture_id, color) = (font.texture_id, self.batch.tinted(self.color));
+        font.layout(self.text, |pen, glyph| {
+            let Some(glyph) = glyph.filter(|g| g.size.x > 0.0 && g.size.y > 0.0) else {
+                return;
+            };
+            let size = glyph.size * scale;
+            let center = (pen + glyph.offset) * scale + size / 2.0;
+            let center = self.position + rot * center;
+            let (col0, col1) = (rot.x_axis * size.x, rot.y_axis * size.y);
+            let instance = Instance::new(
+                [col0.x, col0.y, col1.x, col1.y],
+                [center.x, center.y],
+                color,
+                glyph.uv,
+            );
+            debug_assert_finite(
+                "bitmap text",
+                instance.affine.iter().chain(&instance.translate),
+            );
+            self.batch
+                .push_instance(instance, Some(texture_id), self.shader_id);
+        });
+
+        if let Some(font) = debug {
+            self.batch.debug_font = font;
+        }
+    }
+}
+
+impl Drop for BitmapTextBuilder<'_> {
+    fn drop(&mut self) {
+        self.submit();
+        self.batch.close_draw();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grid_glyphs_fill_rows_left_to_right() {
+        let font = BitmapFont::grid(7, (32, 16), (8, 8), "0123456");
+        assert_eq!(font.texture_id(), TextureId(7));
+        assert_eq!(font.line_height(), 8.0);
+
+        let five = font.glyph('5').unwrap();
+        assert_eq!(five.uv, [0.25, 0.5, 0.5, 1.0]);
+        assert_eq!(five.size, vec2(8.0, 8.0));
+        assert_eq!(five.advance, 8.0);
+        assert!(font.glyph('7').is_none());
+    }
+
+    #[test]
+    fn layout_breaks_lines_and_measures_the_widest() {
+        let font = BitmapFont::grid(0, (32, 16), (8, 8), "0123456");
+        let mut pens = Vec::new();
+        font.layout("12\n3", |pen, glyph| pens.push((pen, glyph.is_some())));
+        assert_eq!(
+            pens,
+            [
+                (vec2(0.0, 0.0), true),
+                (vec2(8.0, 0.0), true),
+                (vec2(0.0, 8.0), true),
+            ]
+        );
+        // unknown characters advance half a line
+        assert_eq!(font.measure("12\n3x", 16.0), vec2(32.0, 32.0));
+        assert_eq!(font.measure("", 16.0), Vec2::ZERO);
+    }
+
+    #[test]
+    fn the_debug_font_covers_digits_and_draws_lowercase_as_uppercase() {
+        let font = debug_font(0);
+        let (w, h) = debug_font_size();
+        assert_eq!(debug_font_pixels().len(), (w * h * 4) as usize);
+        for c in ('0'..='9').chain('A'..='Z').chain(" .:-+%/".chars()) {
+            assert!(font.glyph(c).is_some(), "{c:?}");
+        }
+        assert_eq!(font.glyph('q'), font.glyph('Q'));
+
+        // '1' is the 18th glyph, on the second row
+        let one = font.glyph('1').unwrap();
+        assert_eq!(one.uv[0] * w as f32, 6.0);
+        assert_eq!(one.uv[1] * h as f32, 8.0);
+        let pixels = debug_font_pixels();
+        let alpha = |x: u32, y: u32| pixels[((y * w + x) * 4 + 3) as usize];
+        // the stem of the 1 & the blank spacing column beside it
+        assert_eq!(alpha(6 + 2, 8 + 3), 255);
+        assert_eq!(alpha(6 + 5, 8 + 3), 0);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn glyph_maps_place_grids_and_single_glyphs() {
+        let grid = br#"{ "cell": [8, 8], "chars": "0123" }"#;
+        let font = from_glyph_map(grid, (16, 16)).unwrap();
+        assert_eq!(font.glyph('3').unwrap().uv, [0.5, 0.5, 1.0, 1.0]);
+
+        let glyphs = br#"{ "line_height": 12, "glyphs": {
+            "A": { "x": 0, "y": 0, "w": 6, "h": 10, "offset": [0, 2], "advance": 7 }
+        } }"#;
+        let font = from_glyph_map(glyphs, (16, 16)).unwrap();
+        let a = font.glyph('a').unwrap();
+        assert_eq!(
+            (a.offset, a.advance, font.line_height()),
+            (vec2(0.0, 2.0), 7.0, 12.0)
+        );
+
+        for bad in [
+            &br#"{ "cell": [8, 8], "chars": "012345678" }"#[..],
+            br#"{ "glyphs": { "A": { "x": 12, "y": 0, "w": 6, "h": 10 } } }"#,
+            br#"{ "glyphs": { "AB": { "x": 0, "y": 0, "w": 6, "h": 10 } } }"#,
+            br#"{ "cell": [8, 8] }"#,
+            b"{}",
+            b"not json",
+        ] {
+            assert!(from_glyph_map(bad, (16, 16)).is_err());
+        }
+    }
+}
@@ -1,46 +1,214 @@
-use glam::{Mat4, Vec2};
+use glam::{Mat2, Mat4, Vec2};
 
 use crate::math::Rect;
 
+/// Where world-space `(0, 0)` lands on screen for an untouched camera
+///
+/// - [`Origin::TopLeft`]: `(0, 0)` is the top-left corner of the screen (default)
+/// - [`Origin::Center`]: `(0, 0)` is the center of the screen
+///
+/// In both modes +X points right & +Y points down
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Origin {
+    #[default]
+    TopLeft,
+    Center,
+}
+
+/// Everything placing a [`Camera`]'s view, for saves & replays. Take it with
+/// [`Camera::snapshot`] & put it back with [`Camera::restore`]
+///
+/// With the `serde` feature it's what a camera (de)serializes as. Fields missing from older
+/// saves keep their defaults & unknown ones are ignored, so saves stay loadable as fields are
+/// added
+///
+/// Only the camera's own view is kept. Following a target or shaking the screen is done by
+/// moving the camera each frame, so save that state (the target, shake time left, etc.)
+/// next to this one
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct CameraState {
+    /// Format version, [`Self::VERSION`] for states taken by this egor version
+    pub version: u32,
+    pub origin: Origin,
+    /// See [`Camera::position`]
+    pub position: Vec2,
+    pub zoom: f32,
+    /// Radians, see [`Camera::set_rotation`]
+    pub rotation: f32,
+}
+
+impl CameraState {
+    /// Bumped whenever a field changes meaning, so [`Camera::restore`] can convert older states
+    pub const VERSION: u32 = 1;
+}
+
+impl Default for CameraState {
+    fn default() -> Self {
+        Camera::default().snapshot()
+    }
+}
+
 /// A basic camera for controlling view & projection
 ///
 /// Useful for culling & rendering transformations
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(from = "CameraState", into = "CameraState")
+)]
 pub struct Camera {
+    origin: Origin,
     position: Vec2,
     zoom: f32,
+    rotation: f32,
+    screen_size: Vec2,
 }
 
 impl Default for Camera {
     fn default() -> Self {
+        Self::with_origin(Origin::TopLeft)
+    }
+}
+
+impl Camera {
+    /// Create a camera using the given [`Origin`] convention
+    pub fn with_origin(origin: Origin) -> Self {
         Self {
+            origin,
             position: Vec2::ZERO,
             zoom: 1.0,
+            rotation: 0.0,
+            screen_size: Vec2::ZERO,
         }
     }
-}
 
-impl Camera {
+    /// Set the screen size used by [`Self::world_to_screen`] & [`Self::screen_to_world`]
+    pub(crate) fn set_screen_size(&mut self, screen_size: Vec2) {
+        self.screen_size = screen_size;
+    }
+
+    pub(crate) fn screen_size(&self) -> Vec2 {
+        self.screen_size
+    }
+
+    /// World position of the top-left corner of the view
+    fn top_left(&self, screen_size: Vec2) -> Vec2 {
+        match self.origin {
+            Origin::TopLeft => self.position,
+            Origin::Center => self.position - screen_size / (2.0 * self.zoom),
+        }
+    }
+
+    /// World position at the center of the view, which rotation pivots around
+    fn center_in(&self, screen_size: Vec2) -> Vec2 {
+        self.top_left(screen_size) + screen_size / (2.0 * self.zoom)
+    }
+
     /// Returns the orthographic view-projection matrix for the current camera state
     pub(crate) fn view_proj(&self, screen_size: Vec2) -> Mat4 {
         let width = screen_size.x / self.zoom;
         let height = screen_size.y / self.zoom;
 
-        let left = self.position.x;
-        let right = self.position.x + width;
-        let top = self.position.y;
-        let bottom = self.position.y + height;
+        let top_left = self.top_left(screen_size);
+        let left = top_left.x;
+        let right = top_left.x + width;
+        let top = top_left.y;
+        let bottom = top_left.y + height;
 
-        Mat4::orthographic_lh(left, right, bottom, top, -1.0, 1.0)
+        let proj = Mat4::orthographic_lh(left, right, bottom, top, -1.0, 1.0);
+        if self.rotation == 0.0 {
+            return proj;
+        }
+
+        // turn the world the opposite way around the view center
+        let center = self.center_in(screen_size).extend(0.0);
+        proj * Mat4::from_translation(center)
+            * Mat4::from_rotation_z(-self.rotation)
+            * Mat4::from_translation(-center)
+    }
+
+    fn screen_to_world_in(&self, screen: Vec2, screen_size: Vec2) -> Vec2 {
+        let local = (screen - screen_size / 2.0) / self.zoom;
+        self.center_in(screen_size) + Mat2::from_angle(self.rotation) * local
+    }
+
+    /// Returns the [`Origin`] convention of this camera
+    pub fn origin(&self) -> Origin {
+        self.origin
+    }
+
+    /// Returns the camera position (see [`Self::target`])
+    pub fn position(&self) -> Vec2 {
+        self.position
     }
 
-    /// Set the camera's position (top-left corner of view)
+    /// Returns the current zoom level
+    pub fn zoom(&self) -> f32 {
+        self.zoom
+    }
+
+    /// Returns the camera rotation in radians (see [`Self::set_rotation`])
+    pub fn rotation(&self) -> f32 {
+        self.rotation
+    }
+
+    /// World position at the center of the view as of the last frame, whatever the
+    /// [`Origin`] (see [`Self::center`] to set it)
+    pub fn view_center(&self) -> Vec2 {
+        self.center_in(self.screen_size)
+    }
+
+    /// Everything placing the view, to save & later [`Self::restore`]
+    pub fn snapshot(&self) -> CameraState {
+        CameraState {
+            version: CameraState::VERSION,
+            origin: self.origin,
+            position: self.position,
+            zoom: self.zoom,
+            rotation: self.rotation,
+        }
+    }
+
+    /// Puts the view back where [`Self::snapshot`] found it. The screen size stays as is.
+    /// Zero, negative & non-finite zooms are ignored like in [`Self::keep_vertical_fov`].
+    /// States from a newer egor are restored as far as this version understands them, with
+    /// a warning
+    pub fn restore(&mut self, state: CameraState) {
+        match state.version {
+            // version 1 is the first format, older states will be converted here
+            ..=CameraState::VERSION => {}
+            newer => log::warn!(
+                "camera state version {newer} is newer than {}, later fields are ignored",
+                CameraState::VERSION
+            ),
+        }
+        self.origin = state.origin;
+        self.position = state.position;
+        self.fit_zoom(state.zoom);
+        self.rotation = state.rotation;
+    }
+
+    /// Set the camera's position
+    ///
+    /// This is the world point placed at the camera [`Origin`]: the top-left
+    /// corner of the view for [`Origin::TopLeft`], its center for [`Origin::Center`]
     pub fn target(&mut self, position: Vec2) {
         self.position = position;
     }
 
     /// Center the camera on a position
     pub fn center(&mut self, position: Vec2, screen_size: Vec2) {
-        self.position = position - screen_size / (2.0 * self.zoom);
+        self.position = match self.origin {
+            Origin::TopLeft => position - screen_size / (2.0 * self.zoom),
+            Origin::Center => position,
+        };
     }
 
     /// Set zoom level, clamped between 0.1 & 10.0 to avoid insanity
@@ -48,23 +216,93 @@ impl Camera {
         self.zoom = zoom.clamp(0.1, 10.0);
     }
 
-    /// Returns the viewport rectangle in world coordinates, factoring in zoom  
+    /// Zoom so the view is `world_height` world units tall on a `screen_size` screen, whatever
+    /// its width. Call it on resize (see [`crate::app::App::on_resize`]) so a wider window
+    /// shows more of the world instead of stretching it. Not clamped like [`Self::set_zoom`]
+    pub fn keep_vertical_fov(&mut self, world_height: f32, screen_size: Vec2) {
+        self.fit_zoom(screen_size.y / world_height);
+    }
+
+    /// Zoom so the view is `world_width` world units wide on a `screen_size` screen, whatever
+    /// its height. The horizontal twin of [`Self::keep_vertical_fov`]
+    pub fn keep_horizontal_fov(&mut self, world_width: f32, screen_size: Vec2) {
+        self.fit_zoom(screen_size.x / world_width);
+    }
+
+    /// Sets a zoom computed from sizes, ignoring zero or negative ones
+    fn fit_zoom(&mut self, zoom: f32) {
+        if zoom.is_finite() && zoom > 0.0 {
+            self.zoom = zoom;
+        }
+    }
+
+    /// Rotate the camera clockwise by `radians` around the center of the view.
+    /// The world appears to turn the opposite way, so following a heading keeps it pointing up
+    pub fn set_rotation(&mut self, radians: f32) {
+        self.rotation = radians;
+    }
+
+    /// This camera with its position scaled by `factor` & the same zoom & rotation, see
+    /// [`crate::graphics::Graphics::with_parallax`]
+    pub(crate) fn parallax(mut self, factor: Vec2) -> Self {
+        self.position *= factor;
+        self
+    }
+
+    /// Returns the viewport rectangle in world coordinates, factoring in zoom & rotation.
+    /// When rotated, this is the axis-aligned bounds of the visible area.
     /// Useful for culling or visibility checks
     pub fn viewport(&self, screen_size: Vec2) -> Rect {
-        let size = screen_size / self.zoom;
-        Rect::new(self.position, size)
+        if self.rotation == 0.0 {
+            let size = screen_size / self.zoom;
+            return Rect::new(self.top_left(screen_size), size);
+        }
+
+        let corners = [
+            Vec2::ZERO,
+            Vec2::new(screen_size.x, 0.0),
+            screen_size,
+            Vec2::new(0.0, screen_size.y),
+        ]
+        .map(|c| self.screen_to_world_in(c, screen_size));
+        let min = corners.iter().copied().reduce(Vec2::min).unwrap();
+        let max = corners.iter().copied().reduce(Vec2::max).unwrap();
+        Rect::new(min, max - min)
     }
     /// Converts a point from world space to screen space (pixels)
     pub fn world_to_screen(&self, world: Vec2) -> Vec2 {
-        (world - self.position) * self.zoom
+        let local = Mat2::from_angle(-self.rotation) * (world - self.center_in(self.screen_size));
+        local * self.zoom + self.screen_size / 2.0
     }
 
     /// Converts a point from screen space back to world space
     pub fn screen_to_world(&self, screen: Vec2) -> Vec2 {
-        screen / self.zoom + self.position
+        self.screen_to_world_in(screen, self.screen_size)
+    }
+}
+
+impl From<CameraState> for Camera {
+    fn from(state: CameraState) -> Self {
+        let mut camera = Self::with_origin(state.origin);
+        camera.restore(state);
+        camera
     }
 }
 
+impl From<Camera> for CameraState {
+    fn from(camera: Camera) -> Self {
+        camera.snapshot()
+    }
+}
+
+/// A camera drawing into a pixel rect of the target, see [`crate::graphics::Graphics::viewport`]
+pub(crate) struct Viewport {
+    /// Pixel rect of the target, already clipped to it
+    pub rect: Rect,
+    pub camera: Camera,
+    pub camera_slot: usize,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -82,6 +320,24 @@ mod tests {
         assert_eq!(mat, expected);
     }
 
+    #[test]
+    fn parallax_scales_only_the_translation() {
+        let mut cam = Camera::with_origin(Origin::Center);
+        cam.set_screen_size(vec2(800.0, 600.0));
+        cam.set_zoom(2.0);
+        cam.set_rotation(0.3);
+        let start = cam.world_to_screen(Vec2::ZERO);
+        cam.target(vec2(100.0, 40.0));
+
+        let far = cam.parallax(Vec2::ZERO);
+        assert!((far.world_to_screen(Vec2::ZERO) - start).length() < 0.001);
+        let half = cam.parallax(Vec2::splat(0.5));
+        assert_eq!(half.position(), vec2(50.0, 20.0));
+        assert_eq!((half.zoom(), half.rotation()), (2.0, 0.3));
+        // a point at the camera's scaled position is what half speed layers center on
+        assert!((half.world_to_screen(vec2(50.0, 20.0)) - vec2(400.0, 300.0)).length() < 0.001);
+    }
+
     #[test]
     fn viewport_rect() {
         // check that viewport is centered on camera & scales correctly with zoom
@@ -95,6 +351,23 @@ mod tests {
         assert!((rect.size - vec2(100.0, 50.0)).length() < 0.001); // allow for float fuzz
     }
 
+    #[test]
+    fn fov_helpers_keep_one_axis_across_aspects() {
+        let mut cam = Camera::with_origin(Origin::Center);
+        cam.keep_vertical_fov(20.0, vec2(800.0, 600.0));
+        assert_eq!(cam.viewport(vec2(800.0, 600.0)).size.y, 20.0);
+        // going widescreen shows more horizontally & never squishes
+        cam.keep_vertical_fov(20.0, vec2(1920.0, 1080.0));
+        let view = cam.viewport(vec2(1920.0, 1080.0)).size;
+        assert!((view - vec2(20.0 * 16.0 / 9.0, 20.0)).length() < 0.001);
+
+        cam.keep_horizontal_fov(32.0, vec2(1920.0, 1080.0));
+        assert_eq!(cam.viewport(vec2(1920.0, 1080.0)).size.x, 32.0);
+        // a zero-sized world keeps the last zoom
+        cam.keep_horizontal_fov(0.0, vec2(1920.0, 1080.0));
+        assert_eq!(cam.zoom(), 60.0);
+    }
+
     #[test]
     fn world_screen_round_trip() {
         // converting world -> screen -> world should come back to where we started
@@ -108,4 +381,139 @@ mod tests {
 
         assert!((world - world2).length() < 0.001);
     }
+
+    #[test]
+    fn origin_placement() {
+        // (0, 0) lands at the top-left corner or the screen center depending on origin
+        let screen = vec2(800.0, 600.0);
+
+        let mut top_left = Camera::with_origin(Origin::TopLeft);
+        top_left.set_screen_size(screen);
+        assert_eq!(top_left.world_to_screen(Vec2::ZERO), Vec2::ZERO);
+        assert_eq!(top_left.screen_to_world(Vec2::ZERO), Vec2::ZERO);
+
+        let mut center = Camera::with_origin(Origin::Center);
+        center.set_screen_size(screen);
+        assert_eq!(center.world_to_screen(Vec2::ZERO), screen / 2.0);
+        assert_eq!(center.screen_to_world(screen / 2.0), Vec2::ZERO);
+
+        let expected = Mat4::orthographic_lh(-400.0, 400.0, 300.0, -300.0, -1.0, 1.0);
+        assert_eq!(center.view_proj(screen), expected);
+    }
+
+    #[test]
+    fn center_agrees_across_origins() {
+        // centering on a point produces the same view regardless of origin
+        let screen = vec2(800.0, 600.0);
+        let target = vec2(120.0, -40.0);
+
+        for origin in [Origin::TopLeft, Origin::Center] {
+            let mut cam = Camera::with_origin(origin);
+            cam.set_screen_size(screen);
+            cam.set_zoom(2.0);
+            cam.center(target, screen);
+
+            assert!((cam.world_to_screen(target) - screen / 2.0).length() < 0.001);
+            assert!((cam.viewport(screen).center() - target).length() < 0.001);
+        }
+    }
+
+    #[test]
+    fn rotated_round_trips_match_the_matrix() {
+        // world -> screen -> world returns the original point for any zoom, rotation & center,
+        // & world_to_screen agrees with the GPU matrix
+        let screen = vec2(800.0, 600.0);
+        for origin in [Origin::TopLeft, Origin::Center] {
+            for zoom in [0.25, 1.0, 3.5] {
+                for rotation in [-2.0, -0.3, 0.0, 0.7, std::f32::consts::PI] {
+                    for target in [Vec2::ZERO, vec2(-250.0, 40.0), vec2(1e3, -7e2)] {
+                        let mut cam = Camera::with_origin(origin);
+                        cam.set_screen_size(screen);
+                        cam.set_zoom(zoom);
+                        cam.set_rotation(rotation);
+                        cam.center(target, screen);
+
+                        let world = target + vec2(37.0, -81.0);
+                        let px = cam.world_to_screen(world);
+                        assert!((cam.screen_to_world(px) - world).length() < 1e-2);
+                        assert!((cam.world_to_screen(target) - screen / 2.0).length() < 1e-2);
+
+                        let ndc = cam.view_proj(screen) * world.extend(0.0).extend(1.0);
+                        let from_ndc = vec2(ndc.x + 1.0, 1.0 - ndc.y) * screen / 2.0;
+                        assert!((from_ndc - px).length() < 1e-2);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn rotated_viewport_bounds_the_visible_area() {
+        let screen = vec2(200.0, 100.0);
+        let mut cam = Camera::with_origin(Origin::Center);
+        cam.set_screen_size(screen);
+        cam.set_rotation(std::f32::consts::FRAC_PI_2);
+
+        // a quarter turn swaps the visible width & height
+        let view = cam.viewport(screen);
+        assert!((view.size - vec2(100.0, 200.0)).length() < 1e-3);
+        assert!(view.center().length() < 1e-3);
+
+        for corner in [Vec2::ZERO, screen, vec2(200.0, 0.0)] {
+            let world = cam.screen_to_world(corner);
+            assert!(view.contains(world + (view.center() - world) * 1e-3));
+        }
+    }
+
+    /// A camera moved, zoomed & turned away from every default
+    fn moved_camera() -> Camera {
+        let mut cam = Camera::with_origin(Origin::Center);
+        cam.target(vec2(-312.5, 48.25));
+        cam.set_zoom(2.75);
+        cam.set_rotation(0.4);
+        cam
+    }
+
+    #[test]
+    fn snapshots_restore_the_same_view() {
+        let screen = vec2(800.0, 600.0);
+        let cam = moved_camera();
+        let mut restored = Camera::default();
+        restored.set_screen_size(screen);
+        restored.restore(cam.snapshot());
+
+        assert_eq!(restored.view_proj(screen), cam.view_proj(screen));
+        assert_eq!(restored.view_center(), vec2(-312.5, 48.25));
+        assert_eq!(restored.snapshot().version, CameraState::VERSION);
+    }
+
+    #[test]
+    fn newer_states_restore_the_fields_they_share() {
+        let screen = vec2(800.0, 600.0);
+        let cam = moved_camera();
+        let mut state = cam.snapshot();
+        state.version = CameraState::VERSION + 1;
+        let mut restored = Camera::default();
+        restored.restore(state);
+
+        assert_eq!(restored.view_proj(screen), cam.view_proj(screen));
+        assert_eq!(restored.snapshot().version, CameraState::VERSION);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn cameras_round_trip_through_json() {
+        let screen = vec2(800.0, 600.0);
+        let cam = moved_camera();
+        let json = serde_json::to_string(&cam).unwrap();
+        let loaded: Camera = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.view_proj(screen), cam.view_proj(screen));
+
+        // a save from before rotation existed, with a field from a later version
+        let old =
+            r#"{"version":1,"origin":"Center","position":[10.0,20.0],"zoom":2.0,"shake":0.5}"#;
+        let loaded: Camera = serde_json::from_str(old).unwrap();
+        assert_eq!(loaded.snapshot().position, vec2(10.0, 20.0));
+        assert_eq!((loaded.zoom(), loaded.rotation()), (2.0, 0.0));
+    }
 }
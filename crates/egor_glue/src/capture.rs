@@ -0,0 +1,235 @@
+//! What a frame submitted, draw by draw, for debugging shapes that don't show up where or
+//! how they should. See [`crate::graphics::Graphics::capture_next_frame`]
+
+use std::{collections::HashMap, fmt, panic::Location};
+
+use egor_render::batch::GeometryBatch;
+use glam::{Vec2, vec2};
+
+use crate::math::Rect;
+
+/// The builder a [`DrawRecord`] came from
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DrawKind {
+    Rect,
+    Polygon,
+    Polyline,
+    Path,
+    Mesh,
+    RawMesh,
+    BitmapText,
+    Static,
+    /// Shaped text, drawn by the text renderer after all batches
+    Text,
+}
+
+impl fmt::Display for DrawKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+/// One shape as submitted by a builder
+#[derive(Clone, Debug)]
+pub struct DrawRecord {
+    pub kind: DrawKind,
+    /// Where the builder was started, e.g. the `gfx.rect()` call
+    pub location: &'static Location<'static>,
+    /// Name of the layer it was drawn on
+    pub layer: String,
+    /// Texture & shader of the first part of the shape (a rectangle's shadow comes before
+    /// its fill). `None` is the default
+    pub texture: Option<usize>,
+    pub shader: Option<usize>,
+    /// Vertices written to batches, 4 per instance. 0 means nothing was drawn, e.g. a fully
+    /// transparent shape or one too big for a batch
+    pub vertices: usize,
+    pub instances: usize,
+    /// Area covered in the layer's space. Mesh instances only count their position, text &
+    /// baked geometry have none
+    pub bounds: Option<Rect>,
+    /// Indices into [`FrameCapture::batches`] of the batches it landed in. Empty if it was
+    /// skipped, baked by [`crate::graphics::Graphics::bake_static`] or on a hidden layer
+    pub batches: Vec<usize>,
+}
+
+/// One batch as handed to the renderer, about one draw call
+#[derive(Clone, Debug)]
+pub struct BatchRecord {
+    pub layer: String,
+    pub texture: Option<usize>,
+    pub shader: Option<usize>,
+    /// Mesh its instances repeat, `None` for the unit quad
+    pub mesh: Option<usize>,
+    /// Pixel rect it's clipped to when drawn in a viewport
+    pub viewport: Option<Rect>,
+    pub vertices: usize,
+    pub indices: usize,
+    pub instances: usize,
+    /// Baked batches drawn in its place by [`crate::graphics::Graphics::draw_static`]
+    pub baked: usize,
+    /// Indices into [`FrameCapture::draws`] of the draws it holds
+    pub draws: Vec<usize>,
+}
+
+/// Every draw of a frame & the batches they ended up in, in draw order. Taken with
+/// [`crate::graphics::Graphics::take_capture`]
+#[derive(Clone, Debug, Default)]
+pub struct FrameCapture {
+    /// In submission order
+    pub draws: Vec<DrawRecord>,
+    /// In draw order, after layers are sorted & empty batches dropped
+    pub batches: Vec<BatchRecord>,
+}
+
+/// Where part of a shape went, by index into the frame's batch list at the time
+pub(crate) enum Piece {
+    Vertices {
+        entry: usize,
+        start: usize,
+        count: usize,
+    },
+    Instance {
+        entry: usize,
+        index: usize,
+        quad: bool,
+    },
+    Static {
+        entry: usize,
+        vertices: usize,
+    },
+}
+
+/// Records draws while a capture is running. Only exists during a captured frame, so other
+/// frames pay for a `None` check per shape
+#[derive(Default)]
+pub(crate) struct Recorder {
+    draws: Vec<DrawRecord>,
+    /// Submission order of the batch entries each draw landed in, mapped to batch indices
+    /// when the frame is flushed
+    entry_orders: Vec<Vec<usize>>,
+    /// Id the batch gave each draw, naming it in the moves of layers sorted by y
+    ids: Vec<usize>,
+    open: Option<DrawRecord>,
+    pieces: Vec<Piece>,
+}
+
+impl Recorder {
+    /// Starts recording a shape
+    pub fn open(&mut self, kind: DrawKind, location: &'static Location<'static>, layer: &str) {
+        self.open = Some(DrawRecord {
+            kind,
+            location,
+            layer: layer.to_string(),
+            texture: None,
+            shader: None,
+            vertices: 0,
+            instances: 0,
+            bounds: None,
+            batches: Vec::new(),
+        });
+        self.pieces.clear();
+    }
+
+    /// Adds part of the open shape, the first setting its texture & shader. Shapes egor
+    /// draws itself outside a builder, like text backgrounds, aren't recorded
+    pub fn piece(&mut self, piece: Piece, texture: Option<usize>, shader: Option<usize>) {
+        let Some(draw) = self.open.as_mut() else {
+            return;
+        };
+        if self.pieces.is_empty() {
+            (draw.texture, draw.shader) = (texture, shader);
+        }
+        self.pieces.push(piece);
+    }
+
+    /// Finishes the open shape with id `id`, measuring it from the geometry its pieces
+    /// point at. `entry` gives the submission order & geometry of a batch entry by index
+    pub fn close<'g>(&mut self, id: usize, entry: impl Fn(usize) -> (usize, &'g GeometryBatch)) {
+        let Some(mut draw) = self.open.take() else {
+            return;
+        };
+        let mut orders = Vec::new();
+        let mut points = Vec::new();
+        for piece in self.pieces.drain(..) {
+            let (order, geometry) = match piece {
+                Piece::Vertices { entry: i, .. }
+                | Piece::Instance { entry: i, .. }
+                | Piece::Static { entry: i, .. } => entry(i),
+            };
+            if !orders.contains(&order) {
+                orders.push(order);
+            }
+            match piece {
+                Piece::Vertices { start, count, .. } => {
+                    draw.vertices += count;
+                    let vertices = &geometry.vertices()[start..start + count];
+                    points.extend(vertices.iter().map(|v| Vec2::from(v.position)));
+                }
+                Piece::Instance { index, quad, .. } => {
+                    draw.instances += 1;
+                    draw.vertices += 4;
+                    let instance = &geometry.instances()[index];
+                    let [ax, ay, bx, by] = instance.affine;
+                    let center = Vec2::from(instance.translate);
+                    let (half0, half1) = (vec2(ax, ay) / 2.0, vec2(bx, by) / 2.0);
+                    match quad {
+                        true => points.extend([
+                            center - half0 - half1,
+                            center + half0 - half1,
+                            center + half0 + half1,
+                            center - half0 + half1,
+                        ]),
+                        false => points.push(center),
+                    }
+                }
+                Piece::Static { vertices, .. } => draw.vertices += vertices,
+            }
+        }
+        draw.bounds = bounds(&points);
+        self.draws.push(draw);
+        self.entry_orders.push(orders);
+        self.ids.push(id);
+    }
+
+    /// The capture, with `batches` (each with the submission order of its entry) in draw
+    /// order & the (draw id, submission order) of shapes moved into the rebuilt batches of
+    /// layers sorted by y
+    pub fn finish(
+        mut self,
+        mut batches: Vec<(usize, BatchRecord)>,
+        moved: &[(usize, usize)],
+    ) -> FrameCapture {
+        let index: HashMap<usize, usize> = batches
+            .iter()
+            .enumerate()
+            .map(|(i, (order, _))| (*order, i))
+            .collect();
+        let mut moved_orders: HashMap<usize, Vec<usize>> = HashMap::new();
+        for &(draw, order) in moved {
+            moved_orders.entry(draw).or_default().push(order);
+        }
+        let draws = self.draws.iter_mut().zip(&self.entry_orders).zip(&self.ids);
+        for (i, ((draw, orders), id)) in draws.enumerate() {
+            let moved = moved_orders.get(id).into_iter().flatten();
+            for batch in orders.iter().chain(moved).filter_map(|o| index.get(o)) {
+                if !draw.batches.contains(batch) {
+                    draw.batches.push(*batch);
+                }
+            }
+            for &batch in &draw.batches {
+                batches[batch].1.draws.push(i);
+            }
+        }
+        FrameCapture {
+            draws: self.draws,
+            batches: batches.into_iter().map(|(_, batch)| batch).collect(),
+        }
+    }
+}
+
+fn bounds(points: &[Vec2]) -> Option<Rect> {
+    let min = points.iter().copied().reduce(Vec2::min)?;
+    let max = points.iter().copied().reduce(Vec2::max)?;
+    Some(Rect::new(min, max - min))
+}
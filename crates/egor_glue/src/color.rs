@@ -2,6 +2,11 @@ use color::{AlphaColor, LinearSrgb};
 use glyphon::cosmic_text;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(from = "[f32; 4]", into = "[f32; 4]")
+)]
 pub struct Color {
     inner: AlphaColor<LinearSrgb>,
 }
@@ -14,6 +19,39 @@ impl Color {
         }
     }
 
+    /// Create an opaque Color from 8-bit RGB components, each mapped to [0..1]
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Self {
+        Self::from_rgba8(r, g, b, 255)
+    }
+
+    /// Create a Color from 8-bit RGBA components, each mapped to [0..1]
+    pub const fn from_rgba8(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Self::new([
+            r as f32 / 255.0,
+            g as f32 / 255.0,
+            b as f32 / 255.0,
+            a as f32 / 255.0,
+        ])
+    }
+
+    /// Create a Color from a packed `0xRRGGBBAA` value
+    pub const fn from_hex(rgba: u32) -> Self {
+        let [r, g, b, a] = rgba.to_be_bytes();
+        Self::from_rgba8(r, g, b, a)
+    }
+
+    /// Same color with alpha replaced by `alpha` in [0..1]
+    pub const fn with_alpha(self, alpha: f32) -> Self {
+        let [r, g, b, _] = self.inner.components;
+        Self::new([r, g, b, alpha])
+    }
+
+    /// Component-wise product with `other`, e.g. to tint or fade a color
+    pub const fn multiply(self, other: Color) -> Self {
+        let ([r, g, b, a], [or, og, ob, oa]) = (self.inner.components, other.inner.components);
+        Self::new([r * or, g * og, b * ob, a * oa])
+    }
+
     /// Get raw RGBA components
     pub fn components(&self) -> [f32; 4] {
         self.inner.components
@@ -39,6 +77,46 @@ impl Color {
     pub const BLUE: Color = Self {
         inner: AlphaColor::new([0., 0., 1., 1.]),
     };
+    pub const YELLOW: Color = Self::new([1., 1., 0., 1.]);
+    pub const CYAN: Color = Self::new([0., 1., 1., 1.]);
+    pub const MAGENTA: Color = Self::new([1., 0., 1., 1.]);
+    pub const ORANGE: Color = Self::new([1., 0.5, 0., 1.]);
+    pub const PURPLE: Color = Self::new([0.5, 0., 0.5, 1.]);
+    pub const PINK: Color = Self::new([1., 0.75, 0.8, 1.]);
+    pub const BROWN: Color = Self::new([0.6, 0.3, 0.1, 1.]);
+    pub const LIME: Color = Self::new([0.5, 1., 0., 1.]);
+    pub const TEAL: Color = Self::new([0., 0.5, 0.5, 1.]);
+    pub const NAVY: Color = Self::new([0., 0., 0.5, 1.]);
+    pub const MAROON: Color = Self::new([0.5, 0., 0., 1.]);
+    pub const OLIVE: Color = Self::new([0.5, 0.5, 0., 1.]);
+    pub const GRAY: Color = Self::new([0.5, 0.5, 0.5, 1.]);
+    pub const LIGHT_GRAY: Color = Self::new([0.75, 0.75, 0.75, 1.]);
+    pub const DARK_GRAY: Color = Self::new([0.25, 0.25, 0.25, 1.]);
+}
+
+impl From<[f32; 4]> for Color {
+    fn from(components: [f32; 4]) -> Self {
+        Self::new(components)
+    }
+}
+
+impl From<Color> for [f32; 4] {
+    fn from(value: Color) -> Self {
+        value.components()
+    }
+}
+
+impl From<[u8; 4]> for Color {
+    fn from([r, g, b, a]: [u8; 4]) -> Self {
+        Self::from_rgba8(r, g, b, a)
+    }
+}
+
+/// Packed as `0xRRGGBBAA`
+impl From<u32> for Color {
+    fn from(rgba: u32) -> Self {
+        Self::from_hex(rgba)
+    }
 }
 
 // Convert Color to an array of f64s
@@ -56,3 +134,32 @@ impl From<Color> for cosmic_text::Color {
         cosmic_text::Color::rgba(r, g, b, a)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PALETTE: [Color; 3] = [
+        Color::rgb(255, 0, 0),
+        Color::from_hex(0x00ff0080),
+        Color::TRANSPARENT,
+    ];
+
+    #[test]
+    fn const_palettes_match_constants() {
+        assert_eq!(PALETTE[0], Color::RED);
+        assert_eq!(PALETTE[1].components(), [0.0, 1.0, 0.0, 128.0 / 255.0]);
+        assert_eq!(PALETTE[2].components()[3], 0.0);
+    }
+
+    #[test]
+    fn converts_from_bytes_and_packed_hex() {
+        assert_eq!(Color::from([0, 0, 255, 255]), Color::BLUE);
+        assert_eq!(Color::from(0xffffffff), Color::WHITE);
+        assert_eq!(Color::from(0x000000ff), Color::BLACK);
+        assert_eq!(
+            Color::RED.with_alpha(0.5).components(),
+            [1.0, 0.0, 0.0, 0.5]
+        );
+    }
+}
@@ -0,0 +1,110 @@
+use egor_app::AppConfig;
+use serde::Deserialize;
+use std::{collections::BTreeMap, fmt, io};
+
+/// Errors from reading or writing an [`AppConfig`] file
+#[derive(Debug)]
+pub enum ConfigError {
+    /// The file couldn't be read or written
+    Io(io::Error),
+    /// The file isn't valid TOML or a setting has the wrong type
+    Parse(toml::de::Error),
+    /// The config couldn't be written as TOML
+    Serialize(toml::ser::Error),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "config file error: {e}"),
+            Self::Parse(e) => write!(f, "invalid config: {e}"),
+            Self::Serialize(e) => write!(f, "couldn't write config: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            Self::Parse(e) => Some(e),
+            Self::Serialize(e) => Some(e),
+        }
+    }
+}
+
+impl From<io::Error> for ConfigError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<toml::de::Error> for ConfigError {
+    fn from(e: toml::de::Error) -> Self {
+        Self::Parse(e)
+    }
+}
+
+impl From<toml::ser::Error> for ConfigError {
+    fn from(e: toml::ser::Error) -> Self {
+        Self::Serialize(e)
+    }
+}
+
+/// The settings this egor version knows, plus whatever else is in the file
+#[derive(Deserialize)]
+struct ConfigFile {
+    #[serde(flatten)]
+    config: AppConfig,
+    #[serde(flatten)]
+    unknown: BTreeMap<String, toml::Value>,
+}
+
+/// Parse an [`AppConfig`] from TOML. Missing settings keep their defaults & unknown ones
+/// are skipped with a logged warning, so files stay valid across egor versions
+pub fn from_toml(text: &str) -> Result<AppConfig, ConfigError> {
+    let file: ConfigFile = toml::from_str(text)?;
+    for key in file.unknown.keys() {
+        log::warn!("ignoring unknown config setting `{key}`");
+    }
+    Ok(file.config)
+}
+
+/// Write an [`AppConfig`] as TOML. Unset optional settings are left out
+pub fn to_toml(config: &AppConfig) -> Result<String, ConfigError> {
+    Ok(toml::to_string_pretty(config)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_config_round_trips() {
+        let text = to_toml(&AppConfig::default()).unwrap();
+        assert_eq!(from_toml(&text).unwrap(), AppConfig::default());
+
+        let config = AppConfig {
+            title: "Settings".into(),
+            width: Some(1280),
+            height: Some(720),
+            min_size: Some((320, 240)),
+            max_delta: Some(0.25),
+            vsync: false,
+            ..Default::default()
+        };
+        assert_eq!(from_toml(&to_toml(&config).unwrap()).unwrap(), config);
+    }
+
+    #[test]
+    fn unknown_and_missing_settings_are_tolerated() {
+        let config = from_toml("fullscreen = true\nmsaa = 4\n[future]\nkey = 1").unwrap();
+        assert!(config.fullscreen);
+        assert_eq!(config.title, AppConfig::default().title);
+
+        assert!(matches!(
+            from_toml("fullscreen = \"yes\""),
+            Err(ConfigError::Parse(_))
+        ));
+    }
+}
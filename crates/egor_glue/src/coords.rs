@@ -0,0 +1,132 @@
+//! One place that converts points between screen, UI & world space, so nothing converting
+//! them can disagree
+
+use glam::Vec2;
+
+use crate::camera::Camera;
+
+/// A coordinate space a point can be in
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Space {
+    /// Physical window pixels from the top-left corner, like screen-space drawing &
+    /// [`crate::app::FrameContext::mouse_screen`]
+    Screen,
+    /// Logical points from the top-left corner, as egui lays out its widgets. Screen pixels
+    /// divided by the DPI scale (& egui's zoom)
+    Ui,
+    /// World space, as seen through the camera
+    World,
+}
+
+/// Every transform between [`Space`]s as of one moment. Get one from
+/// [`crate::app::FrameContext::coords`]
+#[derive(Clone, Copy, Debug)]
+pub struct Coords {
+    camera: Camera,
+    ui_scale: f32,
+}
+
+impl Coords {
+    /// `ui_scale` is physical pixels per UI point
+    pub(crate) fn new(camera: Camera, ui_scale: f32) -> Self {
+        Self { camera, ui_scale }
+    }
+
+    /// Physical pixels per UI point
+    pub fn ui_scale(&self) -> f32 {
+        self.ui_scale
+    }
+
+    /// Camera the world space is seen through
+    pub fn camera(&self) -> &Camera {
+        &self.camera
+    }
+
+    /// The same transforms with the world seen through `camera` instead, e.g. a layer's own
+    pub(crate) fn seen_through(&self, mut camera: Camera) -> Self {
+        camera.set_screen_size(self.camera.screen_size());
+        Self { camera, ..*self }
+    }
+
+    /// Converts `point` from the `from` space to the `to` space
+    pub fn convert(&self, point: Vec2, from: Space, to: Space) -> Vec2 {
+        let screen = match from {
+            Space::Screen => point,
+            Space::Ui => point * self.ui_scale,
+            Space::World => self.camera.world_to_screen(point),
+        };
+        match to {
+            Space::Screen => screen,
+            Space::Ui => screen / self.ui_scale,
+            Space::World => self.camera.screen_to_world(screen),
+        }
+    }
+}
+
+/// Physical pixels per UI point for a frame: egui's pixels per point (DPI scale & zoom) with
+/// the `ui` feature, else the display's `scale_factor`. Windows & headless frames both get
+/// theirs here, headless ones passing 1.0 as they have no display
+pub(crate) fn frame_ui_scale(
+    #[cfg(feature = "ui")] egui_ctx: &egui::Context,
+    scale_factor: f32,
+) -> f32 {
+    #[cfg(feature = "ui")]
+    let _ = scale_factor;
+    #[cfg(feature = "ui")]
+    return egui_ctx.pixels_per_point();
+    #[cfg(not(feature = "ui"))]
+    scale_factor
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::camera::Origin;
+
+    #[test]
+    fn conversions_round_trip_and_match_the_camera() {
+        let screen_size = Vec2::new(1280.0, 720.0);
+        let spaces = [Space::Screen, Space::Ui, Space::World];
+        let points = [
+            Vec2::ZERO,
+            Vec2::new(640.0, 360.0),
+            Vec2::new(-37.5, 901.25),
+        ];
+        for origin in [Origin::TopLeft, Origin::Center] {
+            for ui_scale in [1.0, 1.25, 1.5, 2.0, 3.0] {
+                for zoom in [0.25, 1.0, 2.5] {
+                    for rotation in [0.0, 0.7, -2.0] {
+                        let mut camera = Camera::with_origin(origin);
+                        camera.set_screen_size(screen_size);
+                        camera.target(Vec2::new(120.0, -80.0));
+                        camera.set_zoom(zoom);
+                        camera.set_rotation(rotation);
+                        let coords = Coords::new(camera, ui_scale);
+
+                        for point in points {
+                            assert_eq!(
+                                coords.convert(point, Space::Screen, Space::World),
+                                camera.screen_to_world(point)
+                            );
+                            let ui = coords.convert(point, Space::Screen, Space::Ui);
+                            assert_eq!(ui * ui_scale, point);
+                            // through ui points lands where going straight does
+                            let world = coords.convert(ui, Space::Ui, Space::World);
+                            let direct = coords.convert(point, Space::Screen, Space::World);
+                            assert!((world - direct).length() < 1e-3);
+
+                            for from in spaces {
+                                for to in spaces {
+                                    let there = coords.convert(point, from, to);
+                                    let back = coords.convert(there, to, from);
+                                    let error = (back - point).length();
+                                    assert!(error < 1e-2, "{from:?} -> {to:?}: {point} vs {back}");
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,146 @@
+//! Drop-in egui panels for developing with egor
+
+use egui::{Color32, Context, RichText, ScrollArea, Window};
+
+use crate::{capture::FrameCapture, color::Color, graphics::Graphics};
+
+/// A window listing [`Graphics::shader_errors`] with the source line each points at, shown
+/// only while there are errors. Call it every frame with the [`crate::app::FrameContext`]'s
+/// egui context:
+///
+/// ```no_run
+/// # use egor_glue::{app::FrameContext, dev};
+/// # fn f(ctx: &mut FrameContext) {
+/// dev::shader_errors_window(ctx.egui_ctx, &ctx.gfx);
+/// # }
+/// ```
+pub fn shader_errors_window(ctx: &Context, gfx: &Graphics) {
+    let errors = gfx.shader_errors();
+    if errors.is_empty() {
+        return;
+    }
+    Window::new(format!("Shader errors ({})", errors.len()))
+        .default_width(480.0)
+        .show(ctx, |ui| {
+            for (i, error) in errors.iter().enumerate() {
+                if i > 0 {
+                    ui.separator();
+                }
+                let location = match (error.line, error.col) {
+                    (Some(line), Some(col)) => {
+                        format!("shader {} at {line}:{col}", error.shader_id)
+                    }
+                    _ => format!("shader {}", error.shader_id),
+                };
+                ui.label(RichText::new(location).strong());
+                ui.label(RichText::new(&error.message).color(Color32::LIGHT_RED));
+                if let (Some(line), Some(excerpt)) = (error.line, &error.excerpt) {
+                    ui.label(RichText::new(format!("{line} | {excerpt}")).monospace());
+                    // caret under the column the error points at
+                    if let Some(col) = error.col {
+                        let gutter = line.to_string().len() + 3;
+                        let caret = format!("{}^", " ".repeat(gutter + col as usize - 1));
+                        ui.label(RichText::new(caret).monospace().color(Color32::LIGHT_RED));
+                    }
+                }
+            }
+        });
+}
+
+/// State of a [`capture_inspector`] window: the last capture & the draw picked in it
+#[derive(Default)]
+pub struct CaptureInspector {
+    capture: Option<FrameCapture>,
+    selected: Option<usize>,
+}
+
+/// A window capturing a frame on demand with [`Graphics::capture_next_frame`], listing
+/// every draw with where it came from. The selected draw's bounds are outlined in the scene
+/// & its batch shown below. Keep the [`CaptureInspector`] between frames:
+///
+/// ```no_run
+/// # use egor_glue::{app::FrameContext, dev};
+/// # fn f(ctx: &mut FrameContext, inspector: &mut dev::CaptureInspector) {
+/// dev::capture_inspector(ctx.egui_ctx, &mut ctx.gfx, inspector);
+/// # }
+/// ```
+pub fn capture_inspector(ctx: &Context, gfx: &mut Graphics, inspector: &mut CaptureInspector) {
+    if let Some(capture) = gfx.take_capture() {
+        inspector.capture = Some(capture);
+        inspector.selected = None;
+    }
+    Window::new("Frame capture")
+        .default_width(420.0)
+        .show(ctx, |ui| {
+            if ui.button("Capture next frame").clicked() {
+                gfx.capture_next_frame();
+            }
+            let Some(capture) = &inspector.capture else {
+                return;
+            };
+            ui.label(format!(
+                "{} draws in {} batches",
+                capture.draws.len(),
+                capture.batches.len()
+            ));
+            ui.separator();
+
+            ScrollArea::vertical().max_height(240.0).show(ui, |ui| {
+                for (i, draw) in capture.draws.iter().enumerate() {
+                    let location = draw.location;
+                    let text = format!(
+                        "#{i} {} {}:{}, {} verts",
+                        draw.kind,
+                        location.file(),
+                        location.line(),
+                        draw.vertices
+                    );
+                    let selected = inspector.selected == Some(i);
+                    let text = match draw.vertices {
+                        0 => RichText::new(text).monospace().weak(),
+                        _ => RichText::new(text).monospace(),
+                    };
+                    if ui.selectable_label(selected, text).clicked() {
+                        inspector.selected = (!selected).then_some(i);
+                    }
+                }
+            });
+
+            let Some(draw) = inspector.selected.and_then(|i| capture.draws.get(i)) else {
+                return;
+            };
+            ui.separator();
+            ui.label(RichText::new(format!("{} on layer \"{}\"", draw.kind, draw.layer)).strong());
+            ui.label(format!(
+                "texture {:?}, shader {:?}, {} vertices, {} instances",
+                draw.texture, draw.shader, draw.vertices, draw.instances
+            ));
+            if let Some(bounds) = draw.bounds {
+                ui.label(format!(
+                    "bounds {:.1}, {:.1} size {:.1} x {:.1}",
+                    bounds.position.x, bounds.position.y, bounds.size.x, bounds.size.y
+                ));
+                gfx.on_layer(&draw.layer, |gfx| gfx.debug_rect(&bounds, Color::MAGENTA));
+            }
+            if draw.batches.is_empty() {
+                ui.label(RichText::new("not in any batch").color(Color32::YELLOW));
+            }
+            for &i in &draw.batches {
+                let batch = &capture.batches[i];
+                ui.label(format!(
+                    "batch #{i}: texture {:?}, shader {:?}, mesh {:?}, {} vertices, {} indices, \
+                     {} instances, {} draws",
+                    batch.texture,
+                    batch.shader,
+                    batch.mesh,
+                    batch.vertices,
+                    batch.indices,
+                    batch.instances,
+                    batch.draws.len()
+                ));
+                if let Some(viewport) = batch.viewport {
+                    ui.label(format!("clipped to viewport {viewport:?}"));
+                }
+            }
+        });
+}
@@ -0,0 +1,391 @@
+//! The per-frame work both [`crate::app::App`] & [`crate::headless::HeadlessApp`] run, so
+//! a headless frame is drawn exactly like a windowed one
+
+use std::time::Duration;
+
+use crate::{
+    app::{AppControl, CustomPassFn, FrameContext, UpdateFn},
+    camera::Camera,
+    coords::{Coords, Space, frame_ui_scale},
+    frame_graph::GraphScale,
+    gizmos::GizmoStore,
+    graphics::Graphics,
+    memory_budget::MemoryBudget,
+    perf_warnings::PerfWarnings,
+    post::PostChain,
+    primitives::{PrimitiveBatch, draw_entries},
+    profiler::Profiler,
+    text::TextRenderer,
+    widgets::{WidgetInput, WidgetStore},
+};
+
+#[cfg(feature = "ui")]
+use crate::ui::{EguiFrame, EguiRenderer};
+
+use egor_app::{Resize, Window, WindowEvent, input::Input, time::FrameTimer};
+use egor_render::{Renderer, TextureFormat, debug, frame::Frame, target::RenderTarget};
+use glam::Vec2;
+
+/// Everything a frame is drawn with, apart from the renderer & the target it draws to
+pub(crate) struct FrameState {
+    /// Created once the renderer exists, see [`Self::init`]
+    pub text_renderer: Option<TextRenderer>,
+    #[cfg(feature = "ui")]
+    pub egui: Option<EguiRenderer>,
+    pub primitive_batch: PrimitiveBatch,
+    pub strict_textures: bool,
+    pub camera: Camera,
+    pub background_pass: Option<Box<CustomPassFn>>,
+    pub custom_pass: Option<Box<CustomPassFn>>,
+    pub gizmos: GizmoStore,
+    pub post_chain: PostChain,
+    pub widgets: WidgetStore,
+    pub frame_graph: GraphScale,
+    pub perf_warnings: PerfWarnings,
+    pub memory_budget: MemoryBudget,
+    pub profiler: Profiler,
+}
+
+impl Default for FrameState {
+    fn default() -> Self {
+        Self {
+            text_renderer: None,
+            #[cfg(feature = "ui")]
+            egui: None,
+            primitive_batch: PrimitiveBatch::default(),
+            strict_textures: false,
+            camera: Camera::default(),
+            background_pass: None,
+            custom_pass: None,
+            gizmos: GizmoStore::new(cfg!(debug_assertions)),
+            post_chain: PostChain::default(),
+            widgets: WidgetStore::default(),
+            frame_graph: GraphScale::default(),
+            perf_warnings: PerfWarnings::default(),
+            memory_budget: MemoryBudget::default(),
+            profiler: Profiler::default(),
+        }
+    }
+}
+
+/// What a frame gets besides its state: input, timing & what happened since the last one
+pub(crate) struct FrameInput<'a> {
+    pub input: &'a Input,
+    pub timer: &'a FrameTimer,
+    /// Window events received since the last frame
+    pub events: Vec<WindowEvent>,
+    /// The resize applied right before this frame, if any
+    pub resized: Option<Resize>,
+}
+
+/// What an update queued, for [`FrameState::draw`] to draw
+pub(crate) struct Queued {
+    size: (u32, u32),
+    format: TextureFormat,
+    #[cfg(feature = "ui")]
+    egui: EguiFrame,
+}
+
+/// What the update closure asked for, for the caller to apply once the frame is done
+pub(crate) struct FrameRequests {
+    pub size: Option<(u32, u32)>,
+    pub vsync: Option<bool>,
+    pub exit_code: Option<i32>,
+    pub redraw_in: Option<Duration>,
+}
+
+impl FrameState {
+    /// Create the GPU resources frames are drawn with, once `renderer` exists. egui takes
+    /// its input from `window`, or none when headless
+    pub fn init(
+        &mut self,
+        renderer: &mut Renderer,
+        format: TextureFormat,
+        window: Option<&Window>,
+    ) {
+        self.primitive_batch.load_builtin_textures(renderer);
+        self.primitive_batch.texture_check.strict = self.strict_textures;
+        let device = renderer.device();
+        let mut text_renderer = TextRenderer::new(device, renderer.queue(), format);
+        text_renderer.premultiplied = renderer.premultiplied();
+        self.text_renderer = Some(text_renderer);
+        #[cfg(feature = "ui")]
+        {
+            self.egui = Some(match window {
+                Some(window) => EguiRenderer::new(device, format, window),
+                None => EguiRenderer::headless(device, format),
+            });
+        }
+        #[cfg(not(feature = "ui"))]
+        let _ = window;
+    }
+
+    /// Run `update` & draw what it queued into `frame`, sized & formatted like `target`.
+    /// Without a frame the update still runs & what it queued is dropped. Returns the number
+    /// of batches drawn & draws skipped (`None` if the frame wasn't drawn) & the requests
+    /// the update made
+    pub fn run(
+        &mut self,
+        update: &mut UpdateFn,
+        renderer: &mut Renderer,
+        target: &dyn RenderTarget,
+        frame: Option<Frame>,
+        app: AppControl,
+        frame_input: FrameInput,
+    ) -> (Option<(usize, usize)>, FrameRequests) {
+        let (size, format) = (target.size(), target.format());
+        let (queued, requests) = self.update(
+            update,
+            renderer,
+            size,
+            format,
+            frame.is_some(),
+            app,
+            frame_input,
+        );
+        let drawn = frame
+            .zip(queued)
+            .map(|(frame, queued)| self.draw(renderer, frame, queued));
+        (drawn, requests)
+    }
+
+    /// Run `update` for a target of `size` & `format` & get what it queued ready for
+    /// [`Self::draw`], or drop it unless `drawn`. Returns the requests the update made too
+    #[allow(clippy::too_many_arguments)]
+    pub fn update(
+        &mut self,
+        update: &mut UpdateFn,
+        renderer: &mut Renderer,
+        (w, h): (u32, u32),
+        format: TextureFormat,
+        drawn: bool,
+        app: AppControl,
+        frame_input: FrameInput,
+    ) -> (Option<Queued>, FrameRequests) {
+        let FrameInput {
+            input,
+            timer,
+            events,
+            resized,
+        } = frame_input;
+        let window = app.window;
+        self.profiler.begin_frame();
+        let text_renderer = self.text_renderer.as_mut().unwrap();
+
+        #[cfg(feature = "ui")]
+        // cloned so the renderer stays borrowable by `Graphics` for native textures
+        let egui_ctx = &self
+            .egui
+            .as_mut()
+            .unwrap()
+            .begin_any_frame(window, (w, h), timer.delta)
+            .clone();
+        let scale_factor = window.map_or(1.0, |window| window.scale_factor() as f32);
+        let ui_scale = frame_ui_scale(
+            #[cfg(feature = "ui")]
+            egui_ctx,
+            scale_factor,
+        );
+        text_renderer.set_target(w, h, scale_factor);
+        // sampled before user code so it matches the camera at the start of the frame
+        self.camera.set_screen_size((w as f32, h as f32).into());
+        let mouse_screen = Vec2::from(input.mouse_position());
+        let mouse_world =
+            Coords::new(self.camera, ui_scale).convert(mouse_screen, Space::Screen, Space::World);
+        self.widgets.begin_frame(WidgetInput::new(input));
+        let gfx = Graphics::new(
+            renderer,
+            &mut self.primitive_batch,
+            &mut self.camera,
+            text_renderer,
+            format,
+            w,
+            h,
+        );
+        let gfx = gfx
+            .with_safe_area(window.map_or([0.0; 4], egor_app::safe_area_insets))
+            .with_ui_scale(ui_scale)
+            .with_gizmos(&mut self.gizmos)
+            .with_post_chain(&mut self.post_chain)
+            .with_widgets(&mut self.widgets)
+            .with_profiler(&mut self.profiler)
+            .with_frame_graph(timer, &mut self.frame_graph);
+        #[cfg(feature = "ui")]
+        let gfx = gfx.with_egui(self.egui.as_mut().unwrap());
+        let mut ctx = FrameContext {
+            events,
+            app,
+            gfx,
+            input,
+            timer,
+            mouse_screen,
+            mouse_world,
+            #[cfg(feature = "ui")]
+            egui_ctx,
+            ui_scale,
+            resized,
+        };
+        ctx.gfx.enter_scope("update");
+        update(&mut ctx);
+        ctx.gfx.exit_scope();
+
+        #[cfg_attr(not(feature = "ui"), allow(unused_mut))]
+        let mut requests = FrameRequests {
+            size: ctx.app.requested_size,
+            vsync: ctx.app.requested_vsync,
+            exit_code: ctx.app.exit_code,
+            redraw_in: ctx.app.redraw_in,
+        };
+        if let Some((pw, ph)) = requests.size {
+            ctx.gfx.set_target_size(pw, ph);
+        }
+
+        if !drawn {
+            self.discard(timer.delta);
+            #[cfg(feature = "ui")]
+            self.egui.as_mut().unwrap().end_any_frame(window);
+            self.profiler.end_frame();
+            return (None, requests);
+        }
+
+        ctx.gfx.profile_scope("flush", |gfx| {
+            gfx.draw_transformed_text();
+            gfx.draw_gizmos(timer.delta);
+        });
+        ctx.gfx.profile_scope("upload", |gfx| gfx.upload_camera());
+        self.perf_warnings.check(&self.primitive_batch, renderer);
+        self.memory_budget.check(renderer);
+
+        #[cfg(feature = "ui")]
+        let egui = {
+            let egui = self.egui.as_mut().unwrap();
+            let egui = self
+                .profiler
+                .scope("egui tessellate", || egui.end_any_frame(window));
+            if let Some(delay) = egui.repaint_delay {
+                requests.redraw_in = Some(requests.redraw_in.map_or(delay, |d| d.min(delay)));
+            }
+            egui
+        };
+        let queued = Queued {
+            size: (w, h),
+            format,
+            #[cfg(feature = "ui")]
+            egui,
+        };
+        (Some(queued), requests)
+    }
+
+    /// Draw what [`Self::update`] queued into `frame` & submit it. Returns the number of
+    /// batches drawn & draws skipped
+    pub fn draw(
+        &mut self,
+        renderer: &mut Renderer,
+        mut frame: Frame,
+        queued: Queued,
+    ) -> (usize, usize) {
+        let Queued {
+            size: (w, h),
+            format,
+            #[cfg(feature = "ui")]
+                egui: render_data,
+        } = queued;
+        let (device, queue) = (renderer.device().clone(), renderer.queue().clone());
+        let text_renderer = self.text_renderer.as_mut().unwrap();
+        let profiler = &mut self.profiler;
+        profiler.scope("text prepare", || {
+            text_renderer.prepare(&device, &queue, w, h)
+        });
+
+        // with post effects the scene is drawn offscreen & they draw it into the frame
+        let post_view = profiler.scope("post prepare", || {
+            self.post_chain.prepare(renderer, format, (w, h))
+        });
+        let post_effects = post_view.is_some();
+        let scene_view = post_view.unwrap_or(&frame.view);
+
+        if let Some(pass) = &mut self.background_pass {
+            drop(renderer.begin_render_pass(&mut frame.encoder, scene_view));
+            let resources = renderer.pass_resources(format, (w, h));
+            profiler.scope("background pass", || {
+                debug::group(&mut frame.encoder, format_args!("background pass"), |e| {
+                    pass(&device, &queue, e, scene_view, &resources)
+                })
+            });
+        }
+
+        let batches = profiler.scope("render pass encode", || {
+            debug::group(&mut frame.encoder, format_args!("scene"), |encoder| {
+                // a background pass already cleared the view
+                let mut r_pass = match self.background_pass.is_some() {
+                    true => renderer.begin_overlay_pass(encoder, scene_view),
+                    false => renderer.begin_render_pass(encoder, scene_view),
+                };
+
+                let entries = self.primitive_batch.iter_mut();
+                let batches = debug::group(&mut r_pass, format_args!("geometry"), |r_pass| {
+                    draw_entries(renderer, r_pass, entries, (w, h))
+                });
+
+                debug::group(&mut r_pass, format_args!("text"), |r_pass| {
+                    text_renderer.render(r_pass)
+                });
+                batches
+            })
+        });
+        let skipped = self.primitive_batch.take_skipped() + text_renderer.take_skipped();
+
+        if let Some(pass) = &mut self.custom_pass {
+            let resources = renderer.pass_resources(format, (w, h));
+            profiler.scope("custom pass", || {
+                debug::group(&mut frame.encoder, format_args!("custom pass"), |e| {
+                    pass(&device, &queue, e, scene_view, &resources)
+                })
+            });
+        }
+
+        if post_effects {
+            profiler.scope("post effects", || {
+                debug::group(&mut frame.encoder, format_args!("post effects"), |e| {
+                    self.post_chain.apply(renderer, e, &frame.view)
+                })
+            });
+        }
+
+        self.primitive_batch.reset();
+
+        #[cfg(feature = "ui")]
+        {
+            let egui = self.egui.as_mut().unwrap();
+            profiler.scope("egui render", || {
+                debug::group(&mut frame.encoder, format_args!("egui"), |e| {
+                    egui.render(&device, &queue, e, &frame.view, w, h, render_data)
+                })
+            });
+        }
+
+        self.gizmos.render(renderer, &mut frame);
+        profiler.scope("submit", || renderer.end_frame(frame));
+        profiler.end_frame();
+
+        (batches, skipped)
+    }
+
+    /// Drops what [`Self::update`] queued when there's no frame to draw it into after all
+    #[cfg(all(feature = "render_thread", not(target_arch = "wasm32")))]
+    pub fn drop_queued(&mut self) {
+        // the update already aged what's kept between frames
+        self.discard(0.0);
+        self.profiler.end_frame();
+    }
+
+    /// Drops what an unrendered frame queued; what's kept between frames still ages by `delta`
+    fn discard(&mut self, delta: f32) {
+        if let Some(text_renderer) = &mut self.text_renderer {
+            text_renderer.discard();
+        }
+        self.primitive_batch.take_skipped();
+        self.primitive_batch.reset();
+        self.gizmos.discard(delta);
+    }
+}
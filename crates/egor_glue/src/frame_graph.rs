@@ -0,0 +1,175 @@
+use egor_app::time::FrameTimer;
+use glam::vec2;
+
+use crate::{color::Color, graphics::Graphics, math::Rect, text::Align};
+
+/// Screen-space layer the frame graph is drawn to
+const LAYER: &str = "egor_frame_graph";
+/// Frame time budgets of 60 & 30 FPS in milliseconds, drawn as reference lines
+const BUDGETS: [f32; 2] = [1000.0 / 60.0, 1000.0 / 30.0];
+/// Vertical ranges the graph snaps to in milliseconds, doubling past the last one
+const RANGES: [f32; 5] = [20.0, 40.0, 60.0, 100.0, 250.0];
+/// Frames the graph must fit a smaller range before shrinking to it
+const SHRINK_DELAY: u32 = 60;
+const FONT_SIZE: f32 = 12.0;
+
+/// Smallest range from [`RANGES`] (or a doubling of the last) holding `peak` milliseconds
+fn snap_range(peak: f32) -> f32 {
+    if let Some(&range) = RANGES.iter().find(|&&range| range >= peak) {
+        return range;
+    }
+    let mut range = RANGES[RANGES.len() - 1];
+    while range < peak {
+        range *= 2.0;
+    }
+    range
+}
+
+/// Bars within the 60 FPS budget are green, within 30 FPS yellow & slower ones red
+fn bar_color(ms: f32) -> Color {
+    match ms {
+        ms if ms <= BUDGETS[0] => Color::GREEN,
+        ms if ms <= BUDGETS[1] => Color::YELLOW,
+        _ => Color::RED,
+    }
+}
+
+/// Vertical range of the frame graph, kept across frames so it doesn't jump around: it grows
+/// at once to fit a spike, but only shrinks once a smaller range has fit for a while
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct GraphScale {
+    range: f32,
+    /// Frames in a row a smaller range would have fit
+    smaller_for: u32,
+    /// [`FrameTimer::frame`] last counted, so drawing twice in a frame doesn't count it twice
+    counted: Option<u64>,
+}
+
+impl Default for GraphScale {
+    fn default() -> Self {
+        Self {
+            range: RANGES[0],
+            smaller_for: 0,
+            counted: None,
+        }
+    }
+}
+
+impl GraphScale {
+    /// Range in milliseconds to draw `frame`, whose slowest shown frame took `peak`. Only the
+    /// first call per frame moves the shrink wait along
+    pub fn update(&mut self, peak: f32, frame: u64) -> f32 {
+        let fit = snap_range(peak);
+        if fit >= self.range {
+            self.range = fit;
+            self.smaller_for = 0;
+        } else if self.counted != Some(frame) {
+            self.smaller_for += 1;
+            if self.smaller_for >= SHRINK_DELAY {
+                self.range = fit;
+                self.smaller_for = 0;
+            }
+        }
+        self.counted = Some(frame);
+        self.range
+    }
+}
+
+/// Draws `timer`'s frame times as bars in `rect`, in screen pixels, see [`Graphics::frame_graph`]
+pub(crate) fn draw(gfx: &mut Graphics, rect: Rect, timer: &FrameTimer, scale: &mut GraphScale) {
+    let times = timer.frame_times();
+    let min = times.iter().copied().fold(f32::MAX, f32::min);
+    let max = timer.frame_time_ms_max();
+    let range = scale.update(max, timer.frame);
+    let bar_width = rect.size.x / timer.frame_history() as f32;
+    let bottom = rect.max().y;
+
+    gfx.layer(LAYER).screen_space(true);
+    gfx.on_layer(LAYER, |gfx| {
+        gfx.rect()
+            .at(rect.position)
+            .size(rect.size)
+            .color(Color::BLACK.with_alpha(0.6));
+        // newest on the right, so a history that isn't full yet grows in from there
+        let left = rect.max().x - bar_width * times.len() as f32;
+        for (i, &ms) in times.iter().enumerate() {
+            let height = rect.size.y * (ms / range).min(1.0);
+            gfx.rect()
+                .at(vec2(left + bar_width * i as f32, bottom - height))
+                .size(vec2(bar_width, height))
+                .color(bar_color(ms));
+        }
+        for budget in BUDGETS.into_iter().filter(|&budget| budget < range) {
+            gfx.rect()
+                .at(vec2(rect.position.x, bottom - rect.size.y * budget / range))
+                .size(vec2(rect.size.x, 1.0))
+                .color(Color::WHITE.with_alpha(0.5));
+        }
+    });
+
+    if !times.is_empty() {
+        let inset = Rect::new(rect.position + 4.0, rect.size - 8.0);
+        gfx.text(&format!("max {max:.1} ms"))
+            .in_rect(inset, Align::TopLeft)
+            .size(FONT_SIZE);
+        gfx.text(&format!("min {min:.1} ms"))
+            .in_rect(inset, Align::BottomLeft)
+            .size(FONT_SIZE);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ranges_snap_to_steps_that_fit_the_peak() {
+        assert_eq!(snap_range(0.0), 20.0);
+        assert_eq!(snap_range(16.7), 20.0);
+        // 30 FPS frames get the 33.3ms line on screen
+        assert_eq!(snap_range(33.4), 40.0);
+        assert_eq!(snap_range(250.0), 250.0);
+        assert_eq!(snap_range(900.0), 1000.0);
+    }
+
+    #[test]
+    fn scale_grows_at_once_but_shrinks_only_after_a_while() {
+        let mut scale = GraphScale::default();
+        let mut frame = 0;
+        let mut update = |peak| {
+            frame += 1;
+            scale.update(peak, frame)
+        };
+        assert_eq!(update(15.0), 20.0);
+        assert_eq!(update(90.0), 100.0);
+
+        // a frame fitting the current range restarts the wait
+        for _ in 0..SHRINK_DELAY - 1 {
+            assert_eq!(update(30.0), 100.0);
+        }
+        assert_eq!(update(95.0), 100.0);
+        for _ in 0..SHRINK_DELAY - 1 {
+            assert_eq!(update(30.0), 100.0);
+        }
+        assert_eq!(update(30.0), 40.0);
+        assert_eq!(update(16.0), 40.0);
+    }
+
+    #[test]
+    fn drawing_twice_in_a_frame_counts_it_once() {
+        let mut scale = GraphScale::default();
+        scale.update(90.0, 0);
+        for frame in 1..SHRINK_DELAY as u64 {
+            assert_eq!(scale.update(30.0, frame), 100.0);
+            assert_eq!(scale.update(30.0, frame), 100.0);
+        }
+        assert_eq!(scale.update(30.0, SHRINK_DELAY as u64), 40.0);
+    }
+
+    #[test]
+    fn bars_are_colored_by_budget() {
+        assert_eq!(bar_color(16.0), Color::GREEN);
+        assert_eq!(bar_color(20.0), Color::YELLOW);
+        assert_eq!(bar_color(34.0), Color::RED);
+    }
+}
@@ -0,0 +1,269 @@
+use egor_render::{Renderer, debug, frame::Frame};
+use glam::{Vec2, vec2};
+
+use crate::{
+    color::Color,
+    coords::{Coords, Space},
+    graphics::push_text_quads,
+    math::Rect,
+    primitives::{PolygonBuilder, PolylineBuilder, PrimitiveBatch, RectangleBuilder},
+    text::{TextBuilder, TextRenderer},
+};
+
+/// Thickness of gizmo lines in screen pixels, regardless of zoom
+const THICKNESS: f32 = 1.0;
+const CIRCLE_SEGMENTS: usize = 32;
+
+enum Shape {
+    Line(Vec2, Vec2),
+    Circle(Vec2, f32),
+    Cross(Vec2, f32),
+    Rect(Rect),
+    Text(Vec2, String),
+}
+
+struct Gizmo {
+    shape: Shape,
+    color: Color,
+    /// Seconds left before the gizmo expires; drawn at least once
+    remaining: f32,
+}
+
+/// Gizmos kept across frames, drawn in their own pass on top of everything else
+pub(crate) struct GizmoStore {
+    gizmos: Vec<Gizmo>,
+    batch: PrimitiveBatch,
+    enabled: bool,
+}
+
+impl GizmoStore {
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            gizmos: Vec::new(),
+            batch: PrimitiveBatch::default(),
+            enabled,
+        }
+    }
+
+    /// Builds geometry for every live gizmo, then ages them by `delta` seconds.
+    /// Text is rasterized through `text_renderer`'s cached path so it can share the batch
+    pub fn emit(
+        &mut self,
+        renderer: &mut Renderer,
+        text_renderer: &mut TextRenderer,
+        coords: &Coords,
+        delta: f32,
+    ) {
+        let thickness = THICKNESS / coords.camera().zoom();
+        for gizmo in &self.gizmos {
+            let (batch, color) = (&mut self.batch, gizmo.color);
+            match &gizmo.shape {
+                Shape::Line(a, b) => {
+                    PolylineBuilder::new(batch, None)
+                        .points(&[*a, *b])
+                        .thickness(thickness)
+                        .color(color);
+                }
+                Shape::Circle(center, radius) => {
+                    PolygonBuilder::new(batch, None)
+                        .at(*center)
+                        .radius(*radius)
+                        .segments(CIRCLE_SEGMENTS)
+                        .fill(false)
+                        .stroke(thickness)
+                        .color(color);
+                }
+                Shape::Cross(p, size) => {
+                    let half = size * 0.5;
+                    for offset in [vec2(half, half), vec2(half, -half)] {
+                        PolylineBuilder::new(batch, None)
+                            .points(&[*p - offset, *p + offset])
+                            .thickness(thickness)
+                            .color(color);
+                    }
+                }
+                Shape::Rect(rect) => {
+                    RectangleBuilder::new(batch, None)
+                        .with(rect)
+                        .fill(false)
+                        .stroke(thickness)
+                        .color(color);
+                }
+                Shape::Text(p, text) => {
+                    TextBuilder::new(text_renderer, text.clone())
+                        .at(coords.convert(*p, Space::World, Space::Screen))
+                        .color(color)
+                        .cached();
+                }
+            }
+        }
+        let quads = text_renderer.resolve_transformed(renderer);
+        push_text_quads(&mut self.batch, coords, quads);
+        self.age(delta);
+    }
+
+    /// Ages gizmos by `delta` seconds without drawing them & drops any built this frame, for
+    /// frames that aren't rendered
+    pub fn discard(&mut self, delta: f32) {
+        self.batch.reset();
+        self.age(delta);
+    }
+
+    fn age(&mut self, delta: f32) {
+        self.gizmos.retain_mut(|g| {
+            g.remaining -= delta;
+            g.remaining > 0.0
+        });
+    }
+
+    /// Draws this frame's gizmos on top of `frame` & clears them for the next frame
+    pub fn render(&mut self, renderer: &Renderer, frame: &mut Frame) {
+        if !self.batch.is_empty() {
+            debug::group(&mut frame.encoder, format_args!("gizmos"), |encoder| {
+                let mut r_pass = renderer.begin_overlay_pass(encoder, &frame.view);
+                for (tex_id, shader_id, camera, .., batch) in self.batch.iter_mut() {
+                    renderer.draw_batch_with_camera(&mut r_pass, batch, tex_id, shader_id, camera);
+                }
+            });
+        }
+        self.batch.reset();
+    }
+}
+
+/// Debug shapes drawn on top of everything (including egui) with a constant 1 pixel thickness,
+/// without affecting the game's batches. Get it with [`crate::graphics::Graphics::gizmos`]
+///
+/// Gizmos last a single frame unless given a duration, which keeps e.g. a collision visible:
+/// ```no_run
+/// # use egor_glue::{color::Color, graphics::Graphics};
+/// # fn f(gfx: &mut Graphics, hit: glam::Vec2) {
+/// gfx.gizmos().with_duration(2.0).cross(hit, 10.0, Color::RED);
+/// # }
+/// ```
+///
+/// Gizmos are enabled in debug builds only by default; see [`crate::app::App::gizmos`].
+/// While disabled, every call returns immediately
+pub struct Gizmos<'a> {
+    store: Option<&'a mut GizmoStore>,
+    duration: f32,
+}
+
+impl<'a> Gizmos<'a> {
+    pub(crate) fn new(store: Option<&'a mut GizmoStore>) -> Self {
+        Self {
+            store,
+            duration: 0.0,
+        }
+    }
+
+    /// Keep gizmos added through this handle for `seconds` instead of a single frame
+    pub fn with_duration(mut self, seconds: f32) -> Self {
+        self.duration = seconds;
+        self
+    }
+
+    /// Turn gizmo drawing on or off at runtime. Disabling drops all live gizmos
+    pub fn set_enabled(&mut self, enabled: bool) {
+        if let Some(store) = &mut self.store {
+            store.enabled = enabled;
+            if !enabled {
+                store.gizmos.clear();
+            }
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.store.as_ref().is_some_and(|s| s.enabled)
+    }
+
+    /// Line from `a` to `b` in world space
+    pub fn line(&mut self, a: Vec2, b: Vec2, color: Color) -> &mut Self {
+        self.push(Shape::Line(a, b), color)
+    }
+
+    /// Circle outline around `center` with a world space radius
+    pub fn circle(&mut self, center: Vec2, radius: f32, color: Color) -> &mut Self {
+        self.push(Shape::Circle(center, radius), color)
+    }
+
+    /// Diagonal cross centered on `p`, `size` world units across
+    pub fn cross(&mut self, p: Vec2, size: f32, color: Color) -> &mut Self {
+        self.push(Shape::Cross(p, size), color)
+    }
+
+    /// Outline of a world space rectangle
+    pub fn rect(&mut self, rect: &Rect, color: Color) -> &mut Self {
+        self.push(Shape::Rect(*rect), color)
+    }
+
+    /// Label with its top-left corner at world position `p`, drawn at a constant size
+    pub fn text(&mut self, p: Vec2, text: &str) -> &mut Self {
+        self.push(Shape::Text(p, text.into()), Color::WHITE)
+    }
+
+    fn push(&mut self, shape: Shape, color: Color) -> &mut Self {
+        if let Some(store) = self.store.as_mut().filter(|s| s.enabled) {
+            store.gizmos.push(Gizmo {
+                shape,
+                color,
+                remaining: self.duration,
+            });
+        }
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn live(store: &GizmoStore) -> usize {
+        store.gizmos.len()
+    }
+
+    #[test]
+    fn gizmos_expire_after_their_duration() {
+        let mut store = GizmoStore::new(true);
+        Gizmos::new(Some(&mut store)).line(Vec2::ZERO, Vec2::ONE, Color::RED);
+        Gizmos::new(Some(&mut store))
+            .with_duration(0.05)
+            .circle(Vec2::ZERO, 4.0, Color::RED);
+        assert_eq!(live(&store), 2);
+
+        store.age(0.02);
+        assert_eq!(live(&store), 1);
+        store.age(0.02);
+        assert_eq!(live(&store), 1);
+        store.age(0.02);
+        assert_eq!(live(&store), 0);
+    }
+
+    #[test]
+    fn disabled_gizmos_are_dropped() {
+        let mut store = GizmoStore::new(false);
+        let mut gizmos = Gizmos::new(Some(&mut store));
+        gizmos.cross(Vec2::ZERO, 5.0, Color::RED);
+        assert!(!gizmos.is_enabled());
+        gizmos.set_enabled(true);
+        gizmos.with_duration(1.0).text(Vec2::ZERO, "hit");
+        assert_eq!(live(&store), 1);
+
+        Gizmos::new(Some(&mut store)).set_enabled(false);
+        assert_eq!(live(&store), 0);
+    }
+
+    #[test]
+    fn discarded_frames_age_gizmos() {
+        let mut store = GizmoStore::new(true);
+        Gizmos::new(Some(&mut store))
+            .with_duration(0.25)
+            .circle(Vec2::ZERO, 1.0, Color::RED);
+
+        // an occluded window still updates, queuing a one-frame gizmo each time
+        for frames in 1..=5 {
+            Gizmos::new(Some(&mut store)).line(Vec2::ZERO, Vec2::ONE, Color::RED);
+            store.discard(0.1);
+            assert_eq!(live(&store), usize::from(frames < 3));
+        }
+    }
+}
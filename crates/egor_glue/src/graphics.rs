@@ -1,50 +1,191 @@
+use egor_app::time::FrameTimer;
 use egor_render::{
-    Renderer, TextureFormat,
+    AdapterInfo, Features, Limits, MemoryReport, Renderer, ShaderError, TextureFormat,
+    TextureRegionError,
     batch::GeometryBatch,
-    target::{OffscreenTarget, RenderTarget},
+    debug,
+    instance::Instance,
+    target::{OffscreenTarget, RenderTarget, RgbaImage},
 };
-use glam::Vec2;
+use glam::{Affine2, Mat2, Vec2, vec2};
+#[cfg(not(target_arch = "wasm32"))]
+use image::{
+    ImageError, ImageFormat, ImageResult,
+    error::{UnsupportedError, UnsupportedErrorKind},
+};
+#[cfg(feature = "serde")]
+use std::collections::HashMap;
+use std::{io, path::Path};
 
-use crate::primitives::PathBuilder;
+use crate::primitives::{BorderRadii, PathBuilder};
+#[cfg(feature = "ui")]
+use crate::ui::{EguiRenderer, NativeTextureSource, egui};
 use crate::{
+    alloc_guard,
+    atlas::{AtlasError, SubTexture, TextureAtlas},
+    bitmap_font::{BitmapFont, BitmapTextBuilder},
     camera::Camera,
+    capture::{DrawKind, FrameCapture},
     color::Color,
-    primitives::{PolygonBuilder, PolylineBuilder, PrimitiveBatch, RectangleBuilder},
-    text::{TextBuilder, TextRenderer},
+    coords::{Coords, Space},
+    frame_graph::{self, GraphScale},
+    gizmos::{GizmoStore, Gizmos},
+    layer::Layer,
+    math::{Rect, Transform},
+    mesh::{MeshBaker, MeshBuilder, RawMeshBuilder},
+    picking::AlphaMask,
+    post::{PostChain, PostEffects},
+    primitives::{
+        Anchor, ParallelShapes, PolygonBuilder, PolylineBuilder, PrimitiveBatch, QualityMode,
+        RectangleBuilder, draw_entries,
+    },
+    profiler::{ProfileReport, Profiler},
+    text::{
+        SortedText, TextBackground, TextBuilder, TextDecoration, TextRenderer, TransformedText,
+    },
+    widgets::{WidgetStore, Widgets},
 };
 
+/// Id of a texture loaded through [`Graphics`], so it can't be mixed up with shader, uniform
+/// or mesh ids. Converts from & into a bare `usize` for code written before it existed
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TextureId(pub usize);
+
+impl From<usize> for TextureId {
+    fn from(id: usize) -> Self {
+        Self(id)
+    }
+}
+
+impl From<TextureId> for usize {
+    fn from(id: TextureId) -> Self {
+        id.0
+    }
+}
+
+/// Id of geometry baked with [`Graphics::bake_static`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct StaticGeometryId(usize);
+
 /// High-level 2D drawing interface that simplifies the [`Renderer`]
 pub struct Graphics<'a> {
     renderer: &'a mut Renderer,
     batch: &'a mut PrimitiveBatch,
-    camera: Camera,
+    camera: &'a mut Camera,
     text_renderer: &'a mut TextRenderer,
     target_format: TextureFormat,
     target_size: (u32, u32),
+    safe_area: [f32; 4],
+    ui_scale: f32,
     current_shader: Option<usize>,
+    gizmos: Option<&'a mut GizmoStore>,
+    post_chain: Option<&'a mut PostChain>,
+    widgets: Option<&'a mut WidgetStore>,
+    profiler: Option<&'a mut Profiler>,
+    frame_graph: Option<(&'a FrameTimer, &'a mut GraphScale)>,
+    #[cfg(feature = "ui")]
+    egui: Option<&'a mut EguiRenderer>,
 }
 
 impl<'a> Graphics<'a> {
-    /// Create `Graphics` with [`Renderer`], [`TextRenderer`] & `TextureFormat`
+    /// Create `Graphics` with [`Renderer`], [`Camera`], [`TextRenderer`] & `TextureFormat`
     pub fn new(
         renderer: &'a mut Renderer,
         batch: &'a mut PrimitiveBatch,
+        camera: &'a mut Camera,
         text_renderer: &'a mut TextRenderer,
         format: TextureFormat,
         w: u32,
         h: u32,
     ) -> Self {
+        camera.set_screen_size((w as f32, h as f32).into());
         Self {
             renderer,
             batch,
-            camera: Camera::default(),
+            camera,
             text_renderer,
             target_format: format,
             target_size: (w, h),
+            safe_area: [0.0; 4],
+            ui_scale: 1.0,
             current_shader: None,
+            gizmos: None,
+            post_chain: None,
+            widgets: None,
+            profiler: None,
+            frame_graph: None,
+            #[cfg(feature = "ui")]
+            egui: None,
         }
     }
 
+    pub(crate) fn with_gizmos(mut self, gizmos: &'a mut GizmoStore) -> Self {
+        self.gizmos = Some(gizmos);
+        self
+    }
+
+    pub(crate) fn with_post_chain(mut self, post_chain: &'a mut PostChain) -> Self {
+        self.post_chain = Some(post_chain);
+        self
+    }
+
+    pub(crate) fn with_safe_area(mut self, insets: [f32; 4]) -> Self {
+        self.safe_area = insets;
+        self
+    }
+
+    pub(crate) fn with_ui_scale(mut self, ui_scale: f32) -> Self {
+        self.ui_scale = ui_scale;
+        self
+    }
+
+    pub(crate) fn with_widgets(mut self, widgets: &'a mut WidgetStore) -> Self {
+        self.widgets = Some(widgets);
+        self
+    }
+
+    pub(crate) fn with_profiler(mut self, profiler: &'a mut Profiler) -> Self {
+        self.profiler = Some(profiler);
+        self
+    }
+
+    pub(crate) fn with_frame_graph(
+        mut self,
+        timer: &'a FrameTimer,
+        scale: &'a mut GraphScale,
+    ) -> Self {
+        self.frame_graph = Some((timer, scale));
+        self
+    }
+
+    #[cfg(feature = "ui")]
+    pub(crate) fn with_egui(mut self, egui: &'a mut EguiRenderer) -> Self {
+        self.egui = Some(egui);
+        self
+    }
+
+    /// Features of the GPU device, those of [`crate::app::App::gpu_features`] the adapter
+    /// supports
+    pub fn active_features(&self) -> Features {
+        self.renderer.features()
+    }
+    /// Limits of the GPU device, see [`crate::app::App::gpu_limits`]
+    pub fn active_limits(&self) -> Limits {
+        self.renderer.limits()
+    }
+    /// The adapter drawing the app, picked by [`crate::app::App::adapter_preference`], e.g. to
+    /// lower quality on software renderers
+    pub fn adapter_info(&self) -> AdapterInfo {
+        self.renderer.adapter_info()
+    }
+
+    /// Format the window surface & all pipelines render in, negotiated from
+    /// [`crate::app::App::preferred_surface_format`]. Useful for user shaders that
+    /// need to know e.g. whether output is HDR (`Rgba16Float`) or sRGB
+    pub fn surface_format(&self) -> TextureFormat {
+        self.renderer.surface_format()
+    }
+
     /// Create a new offscreen render target
     pub fn create_offscreen(&self, width: u32, height: u32) -> OffscreenTarget {
         self.renderer
@@ -76,22 +217,57 @@ impl<'a> Graphics<'a> {
         max_indices: usize,
         mut render_fn: impl FnMut(&mut Graphics),
     ) {
+        let _unguarded = alloc_guard::pause();
         let (w, h) = target.size();
         let format = target.format();
 
         let mut offscreen_batch = PrimitiveBatch::new(max_verts, max_indices);
+        offscreen_batch.solid = self.batch.solid.clone();
+        offscreen_batch.shadow_texture = self.batch.shadow_texture;
+        offscreen_batch.debug_font = self.batch.debug_font.clone();
+        offscreen_batch.texture_check = std::mem::take(&mut self.batch.texture_check);
+        offscreen_batch.curve_quality = self.batch.curve_quality;
+        offscreen_batch.statics = std::mem::take(&mut self.batch.statics);
+        // offscreen cameras start fresh but follow the app's origin convention
+        let mut offscreen_camera = Camera::with_origin(self.camera.origin());
+        offscreen_camera.set_screen_size((w as f32, h as f32).into());
         let mut offscreen_gfx = Graphics {
             renderer: self.renderer,
             batch: &mut offscreen_batch,
-            camera: Camera::default(),
+            camera: &mut offscreen_camera,
             text_renderer: self.text_renderer,
             target_size: (w, h),
             target_format: format,
+            // nothing covers an offscreen target
+            safe_area: [0.0; 4],
+            ui_scale: self.ui_scale,
             current_shader: None,
+            gizmos: self.gizmos.as_deref_mut(),
+            post_chain: self.post_chain.as_deref_mut(),
+            // the mouse is over the screen, not the offscreen target
+            widgets: None,
+            profiler: self.profiler.as_deref_mut(),
+            frame_graph: self
+                .frame_graph
+                .as_mut()
+                .map(|(timer, scale)| (*timer, &mut **scale)),
+            #[cfg(feature = "ui")]
+            egui: self.egui.as_deref_mut(),
         };
 
+        // tints & transforms apply to the offscreen result when it's drawn, not to its contents
+        let text_tint = std::mem::replace(&mut offscreen_gfx.text_renderer.tint, Color::WHITE);
+        let text_transform = std::mem::replace(
+            &mut offscreen_gfx.text_renderer.transform,
+            Affine2::IDENTITY,
+        );
         render_fn(&mut offscreen_gfx);
+        offscreen_gfx.text_renderer.tint = text_tint;
+        offscreen_gfx.text_renderer.transform = text_transform;
+        offscreen_gfx.draw_transformed_text();
         offscreen_gfx.upload_camera();
+        self.batch.texture_check = std::mem::take(&mut offscreen_batch.texture_check);
+        self.batch.statics = std::mem::take(&mut offscreen_batch.statics);
         let mut geometry = offscreen_batch.take();
 
         let mut encoder = self
@@ -99,40 +275,331 @@ impl<'a> Graphics<'a> {
             .device()
             .create_command_encoder(&Default::default());
 
-        {
+        let label = format_args!("offscreen {w}x{h}");
+        debug::group(&mut encoder, label, |encoder| {
             let mut r_pass = self
                 .renderer
-                .begin_render_pass(&mut encoder, target.render_view());
+                .begin_render_pass(encoder, target.render_view());
 
-            for (tex_id, shader_id, batch) in &mut geometry {
-                self.renderer
-                    .draw_batch(&mut r_pass, batch, *tex_id, *shader_id);
-            }
-        }
+            let entries =
+                geometry
+                    .iter_mut()
+                    .map(|(tex_id, shader_id, camera, viewport, baked, batch)| {
+                        (*tex_id, *shader_id, *camera, *viewport, baked.take(), batch)
+                    });
+            draw_entries(self.renderer, &mut r_pass, entries, (w, h));
+        });
 
         target.copy_to_sample(&mut encoder);
 
         let _ = self.renderer.queue().submit(Some(encoder.finish()));
     }
 
+    /// Render everything drawn so far this frame into `target` & start over with an empty
+    /// batch, so later draws form a new layer, e.g. drawing the world, flushing it, then
+    /// drawing it through a post-process shader under an untouched HUD. The camera is uploaded
+    /// as it is at the time of the flush, sized to `target`. Like [`Self::render_offscreen`]
+    /// this covers shapes & transformed text; plain text is still drawn at the end of the frame.
+    /// Layers & viewports keep routing later draws, but their camera is re-uploaded on flush
+    ///
+    /// ```no_run
+    /// # use egor_render::target::OffscreenTarget;
+    /// # fn f(gfx: &mut egor_glue::graphics::Graphics, world: &mut OffscreenTarget, world_tex: usize, crt: usize) {
+    /// // `world_tex` comes from `gfx.offscreen_as_texture(world)` at startup
+    /// let screen = gfx.screen_size();
+    /// gfx.rect().size(screen);
+    /// gfx.flush_to_target(world);
+    /// gfx.with_shader(crt, |gfx| {
+    ///     gfx.rect().size(screen).texture(world_tex);
+    /// });
+    /// gfx.text("HUD").at((8.0, 8.0));
+    /// # }
+    /// ```
+    pub fn flush_to_target(&mut self, target: &mut OffscreenTarget) {
+        let _unguarded = alloc_guard::pause();
+        let size = target.size();
+        let screen_size = std::mem::replace(&mut self.target_size, size);
+        self.draw_transformed_text();
+        self.upload_camera();
+        self.target_size = screen_size;
+
+        let mut encoder = self
+            .renderer
+            .device()
+            .create_command_encoder(&Default::default());
+        let label = format_args!("flush to {}x{}", size.0, size.1);
+        debug::group(&mut encoder, label, |encoder| {
+            let mut r_pass = self
+                .renderer
+                .begin_render_pass(encoder, target.render_view());
+            draw_entries(self.renderer, &mut r_pass, self.batch.iter_mut(), size);
+        });
+        target.copy_to_sample(&mut encoder);
+        // submitted now so the camera uploads above land before the next flush's
+        let _ = self.renderer.queue().submit(Some(encoder.finish()));
+        self.batch.clear_batches();
+    }
+
+    /// Create an empty `width` x `height` texture atlas for packing images added at runtime.
+    /// Everything drawn from one atlas shares a texture, so it batches into a single draw call.
+    /// A white pixel is reserved for solid-color shapes, see [`Self::set_primary_atlas`]
+    pub fn create_atlas(&mut self, width: u32, height: u32) -> TextureAtlas {
+        let blank = vec![0; width as usize * height as usize * 4];
+        let texture_id = self.renderer.add_texture_raw(width, height, &blank);
+        let mut atlas = TextureAtlas::new(texture_id, width, height);
+        if let Some((x, y)) = atlas.reserve_white_pixel() {
+            // the pixel is inside the texture just created
+            let white = [255; 4];
+            let _ = self
+                .renderer
+                .update_texture_region(texture_id, x, y, 1, 1, &white);
+            self.register_white_pixel(texture_id, atlas.white_uv().unwrap());
+        }
+        atlas
+    }
+
+    /// Marks `uv` as the center of an opaque white pixel in texture `texture_id`,
+    /// so it can be used with [`Self::set_primary_atlas`]. Atlases register theirs
+    pub fn register_white_pixel(&mut self, texture_id: impl Into<TextureId>, uv: Vec2) {
+        self.batch
+            .solid
+            .white_pixels
+            .insert(texture_id.into().0, uv.to_array());
+    }
+
+    /// Draws solid-color shapes with the white pixel of texture `texture_id` instead of
+    /// the default white texture, so they batch with sprites from it. Applies to everything
+    /// drawn afterwards, across frames; `None` goes back to the default.
+    /// Ignored for textures without a white pixel, see [`Self::register_white_pixel`]
+    pub fn set_primary_atlas(&mut self, texture_id: Option<TextureId>) {
+        self.batch.solid.primary = texture_id.map(usize::from);
+    }
+
+    /// Pack a `width` x `height` RGBA image into `atlas`.
+    /// Returns [`AtlasError::OutOfSpace`] when full, so another atlas can be created
+    pub fn atlas_add(
+        &mut self,
+        atlas: &mut TextureAtlas,
+        width: u32,
+        height: u32,
+        rgba: &[u8],
+    ) -> Result<SubTexture, AtlasError> {
+        // in usize, as the byte count of a large image overflows a u32
+        if rgba.len() != width as usize * height as usize * 4 {
+            return Err(AtlasError::InvalidData);
+        }
+        let position = atlas.pack(width, height)?;
+        let ((x, y), id) = (position, atlas.texture_id().0);
+        // packed regions fit the atlas, which only rejects them once it's unloaded
+        let _ = self
+            .renderer
+            .update_texture_region(id, x, y, width, height, rgba);
+        Ok(atlas.sub_texture(position, (width, height)))
+    }
+
+    /// Load an atlas packed by `cargo xtask pack-atlas` from its PNG & JSON manifest,
+    /// returning its regions by name. For multi-page atlases see [`Self::load_atlas_pages`]
+    #[cfg(feature = "serde")]
+    pub fn load_atlas_manifest(
+        &mut self,
+        png: &[u8],
+        json: &[u8],
+    ) -> Result<HashMap<String, SubTexture>, AtlasError> {
+        self.load_atlas_pages(&[png], json)
+    }
+
+    /// Load an atlas packed by `cargo xtask pack-atlas` from the PNG of every page, in the
+    /// manifest's page order, & the JSON manifest. Regions of each page share a texture
+    #[cfg(feature = "serde")]
+    pub fn load_atlas_pages(
+        &mut self,
+        pages: &[&[u8]],
+        json: &[u8],
+    ) -> Result<HashMap<String, SubTexture>, AtlasError> {
+        let mut uploaded = Vec::with_capacity(pages.len());
+        for png in pages {
+            let image = image::load_from_memory(png)
+                .map_err(|_| AtlasError::InvalidData)?
+                .into_rgba8();
+            let (w, h) = image.dimensions();
+            uploaded.push((self.renderer.add_texture_raw(w, h, &image), w, h));
+        }
+        crate::atlas::manifest_regions(json, &uploaded)
+    }
+
+    /// Load a bitmap font from its PNG & a JSON glyph map. The map is either a grid of
+    /// equally sized cells filled row by row, `{ "cell": [8, 8], "chars": "0123456789" }`,
+    /// or glyphs placed one by one, `{ "line_height": 12, "glyphs": { "A": { "x": 0, "y": 0,
+    /// "w": 6, "h": 10, "offset": [0, 2], "advance": 7 } } }`. `offset` & `advance` are
+    /// optional & both forms can be combined. For grids without the `serde` feature, see
+    /// [`Self::load_bitmap_font_grid`]
+    #[cfg(feature = "serde")]
+    pub fn load_bitmap_font(&mut self, png: &[u8], json: &[u8]) -> Result<BitmapFont, AtlasError> {
+        let image = image::load_from_memory(png)
+            .map_err(|_| AtlasError::InvalidData)?
+            .into_rgba8();
+        let (w, h) = image.dimensions();
+        let font = crate::bitmap_font::from_glyph_map(json, (w, h))?;
+        let texture_id = self.renderer.add_texture_raw(w, h, &image);
+        Ok(font.with_texture(texture_id))
+    }
+
+    /// Load a bitmap font from a PNG of equally sized `cell`s filled row by row with `chars`
+    pub fn load_bitmap_font_grid(
+        &mut self,
+        png: &[u8],
+        cell: (u32, u32),
+        chars: &str,
+    ) -> Result<BitmapFont, AtlasError> {
+        let image = image::load_from_memory(png)
+            .map_err(|_| AtlasError::InvalidData)?
+            .into_rgba8();
+        let (w, h) = image.dimensions();
+        let texture_id = self.renderer.add_texture_raw(w, h, &image);
+        Ok(BitmapFont::grid(texture_id, (w, h), cell, chars))
+    }
+
     /// Use an offscreen target as a texture
-    pub fn offscreen_as_texture(&mut self, target: &mut OffscreenTarget) -> usize {
-        self.renderer.add_offscreen_texture(target)
+    pub fn offscreen_as_texture(&mut self, target: &mut OffscreenTarget) -> TextureId {
+        TextureId(self.renderer.add_offscreen_texture(target))
+    }
+
+    /// Read an offscreen target back as an image, blocking until the GPU is done.
+    /// `None` if the target isn't an 8-bit RGBA or BGRA format, e.g. an HDR one
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn read_offscreen(&self, target: &OffscreenTarget) -> Option<RgbaImage> {
+        target.read_image(self.renderer.device(), self.renderer.queue())
+    }
+
+    /// Like [`Self::read_offscreen`] without blocking, for wasm. The copy is queued right away,
+    /// so the returned future can outlive the frame, e.g. with `wasm_bindgen_futures::spawn_local`
+    pub fn read_offscreen_async(
+        &self,
+        target: &OffscreenTarget,
+    ) -> impl Future<Output = Option<RgbaImage>> + 'static {
+        target.read_image_async(self.renderer.device(), self.renderer.queue())
+    }
+
+    /// Read an offscreen target back & encode it to a PNG file, e.g. for thumbnails
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn save_offscreen_png(
+        &self,
+        target: &OffscreenTarget,
+        path: impl AsRef<Path>,
+    ) -> ImageResult<()> {
+        let image = self.read_offscreen(target).ok_or_else(|| {
+            ImageError::Unsupported(UnsupportedError::from_format_and_kind(
+                ImageFormat::Png.into(),
+                UnsupportedErrorKind::GenericFeature(format!(
+                    "saving {:?} targets",
+                    target.format()
+                )),
+            ))
+        })?;
+        image.save_with_format(path, ImageFormat::Png)
+    }
+
+    /// Get an egui texture id showing an offscreen target, for use with `egui::Image`.
+    /// The id stays the same across calls; after the target is resized,
+    /// calling this again points the id at the recreated texture
+    #[cfg(feature = "ui")]
+    pub fn egui_texture(&mut self, target: &OffscreenTarget) -> egui::TextureId {
+        let egui = self.egui.as_deref_mut().expect("egui is not initialized");
+        egui.native_texture(
+            self.renderer.device(),
+            NativeTextureSource::Offscreen(target.id()),
+            target.view(),
+        )
+    }
+
+    /// Get an egui texture id for a texture loaded with [`Self::load_texture`].
+    /// Returns `None` if no texture with that id exists
+    #[cfg(feature = "ui")]
+    pub fn egui_texture_from_id(
+        &mut self,
+        texture_id: impl Into<TextureId>,
+    ) -> Option<egui::TextureId> {
+        let TextureId(texture_id) = texture_id.into();
+        let view = self.renderer.texture_view(texture_id)?;
+        let egui = self.egui.as_deref_mut().expect("egui is not initialized");
+        Some(egui.native_texture(
+            self.renderer.device(),
+            NativeTextureSource::Texture(texture_id),
+            view,
+        ))
+    }
+
+    /// Unregister an egui texture id obtained from [`Self::egui_texture`] or
+    /// [`Self::egui_texture_from_id`]. The underlying egor texture is left untouched
+    #[cfg(feature = "ui")]
+    pub fn free_egui_texture(&mut self, id: egui::TextureId) {
+        if let Some(egui) = self.egui.as_deref_mut() {
+            egui.free_native_texture(id);
+        }
     }
 
     pub(crate) fn set_target_size(&mut self, w: u32, h: u32) {
         self.target_size = (w, h);
+        self.camera.set_screen_size((w as f32, h as f32).into());
+    }
+
+    /// Resolve rotated/scaled text queued this frame into textured quads.
+    /// Call after user drawing is complete, so text lands on top like regular text
+    pub(crate) fn draw_transformed_text(&mut self) {
+        let _guard = alloc_guard::scope();
+        // text was placed by the transform when queued, one left pushed mustn't move it again
+        let transform = self.batch.set_transform(Affine2::IDENTITY);
+        // backgrounds go first so they end up under both cached & regular text
+        let coords = self.coords();
+        let backgrounds = self.text_renderer.drain_backgrounds();
+        push_text_backgrounds(self.batch, &coords, backgrounds);
+        let quads = self.text_renderer.resolve_transformed(self.renderer);
+        push_text_quads(self.batch, &coords, quads);
+        let decorations = self.text_renderer.drain_decorations();
+        push_text_decorations(self.batch, &coords, decorations);
+        self.batch.set_transform(transform);
+    }
+
+    /// Build this frame's gizmos, aging them by `delta` seconds.
+    /// Call after [`Self::draw_transformed_text`] so user text isn't mixed into the gizmo pass
+    pub(crate) fn draw_gizmos(&mut self, delta: f32) {
+        let coords = self.coords();
+        if let Some(gizmos) = self.gizmos.as_deref_mut() {
+            let _unguarded = alloc_guard::pause();
+            gizmos.emit(self.renderer, self.text_renderer, &coords, delta);
+        }
     }
 
     /// Upload camera matrix to the GPU.
     /// Call after user drawing is complete and before the render pass
     pub(crate) fn upload_camera(&mut self) {
         let (w, h) = self.target_size;
-        self.renderer.upload_camera_matrix(
-            self.camera
-                .view_proj((w as f32, h as f32).into())
-                .to_cols_array_2d(),
-        );
+        let screen_size = (w as f32, h as f32).into();
+        self.renderer
+            .upload_camera_matrix(self.camera.view_proj(screen_size).to_cols_array_2d());
+
+        // layers with their own camera each get an extra slot
+        let mut slots = 0;
+        for layer in self.batch.layers.iter_mut() {
+            layer.camera_slot = match layer.camera_override() {
+                Some(camera) => {
+                    slots += 1;
+                    self.renderer.upload_camera_matrix_to(
+                        slots,
+                        camera.view_proj(screen_size).to_cols_array_2d(),
+                    );
+                    slots
+                }
+                None => 0,
+            };
+        }
+        for viewport in &mut self.batch.viewports {
+            slots += 1;
+            let view_proj = viewport.camera.view_proj(viewport.rect.size);
+            self.renderer
+                .upload_camera_matrix_to(slots, view_proj.to_cols_array_2d());
+            viewport.camera_slot = slots;
+        }
     }
 
     /// Clear the screen to a color
@@ -144,55 +611,611 @@ impl<'a> Graphics<'a> {
         let (w, h) = self.target_size;
         (w as f32, h as f32).into()
     }
+    /// Pixels along each edge of the screen hidden by notches, rounded corners or system
+    /// bars, as `[top, right, bottom, left]`. Keep HUD elements inside them; all zeros on
+    /// desktops & in offscreen targets
+    pub fn safe_area_insets(&self) -> [f32; 4] {
+        self.safe_area
+    }
+    /// Transforms between screen, UI & world space with the frame's camera as it is now,
+    /// ignoring any [`Self::viewport`] being drawn
+    pub fn coords(&self) -> Coords {
+        Coords::new(*self.camera, self.ui_scale)
+    }
+
     /// Mutable access to [`Camera`]
+    ///
+    /// The camera persists across frames, so changes carry over until overwritten
     pub fn camera(&mut self) -> &mut Camera {
-        &mut self.camera
+        self.camera
+    }
+
+    /// Configure the layer called `name`, creating it if needed.
+    /// Settings persist across frames
+    ///
+    /// ```no_run
+    /// # fn f(gfx: &mut egor_glue::graphics::Graphics) {
+    /// gfx.layer("hud").screen_space(true);
+    /// gfx.layer("debug").enabled(false);
+    /// # }
+    /// ```
+    pub fn layer(&mut self, name: &str) -> &mut Layer {
+        let index = self.batch.layers.index(name);
+        self.batch.layers.get_mut(index)
+    }
+    /// Draw everything in `draw_fn` to the layer called `name`, creating it if needed.
+    /// Text is not affected by layers & is drawn on top, unless the layer sorts by y
+    pub fn on_layer(&mut self, name: &str, draw_fn: impl FnOnce(&mut Self)) {
+        let index = self.batch.layers.index(name);
+        let previous = self.batch.set_layer(index);
+        draw_fn(self);
+        self.batch.set_layer(previous);
+    }
+    /// Like [`Self::on_layer`], but the layer draws shapes lower on screen in front, like
+    /// characters walking past each other in a top-down game, whatever order they're drawn in:
+    ///
+    /// ```no_run
+    /// # use egor_glue::graphics::Graphics;
+    /// # use glam::vec2;
+    /// # fn f(gfx: &mut Graphics, hero: usize, tree: usize) {
+    /// gfx.on_layer_sorted_y("entities", |gfx| {
+    ///     gfx.rect().at(vec2(100.0, 80.0)).size(vec2(32.0, 48.0)).texture(tree);
+    ///     // behind the tree while its feet are above the tree's base
+    ///     gfx.rect().at(vec2(110.0, 60.0)).size(vec2(16.0, 24.0)).texture(hero);
+    /// });
+    /// # }
+    /// ```
+    ///
+    /// Shapes are ordered by their bottom edge (a rectangle's `position.y + size.y`), moved
+    /// with `sort_bias` on their builder; ties keep drawing order. Neighbours that share a
+    /// texture are still batched together after sorting. Text is sorted too: bitmap text like
+    /// any shape & [`Self::text`] by the bottom of its box, drawn as a cached texture with
+    /// its background & decorations. The layer stays sorted, see [`Layer::sort_by_y`]
+    pub fn on_layer_sorted_y(&mut self, name: &str, draw_fn: impl FnOnce(&mut Self)) {
+        self.layer(name).sort_by_y(true);
+        self.on_layer(name, draw_fn);
     }
+    /// Draw everything in `draw_fn` into `rect` of the target (in pixels) as seen by `camera`,
+    /// e.g. once per player for split screen. `rect` is clipped to the target, & nested
+    /// viewports are relative to the enclosing one
+    ///
+    /// Inside, [`Self::camera`] & [`Self::screen_size`] refer to the viewport; camera changes
+    /// last until `draw_fn` returns. Layer cameras don't apply inside a viewport, & text
+    /// isn't clipped to it
+    pub fn viewport(&mut self, rect: Rect, camera: Camera, draw_fn: impl FnOnce(&mut Self)) {
+        let (w, h) = self.target_size;
+        let min = rect
+            .min()
+            .clamp(Vec2::ZERO, vec2(w as f32, h as f32))
+            .round();
+        let max = rect.max().clamp(min, vec2(w as f32, h as f32)).round();
+        let rect = Rect::new(self.batch.viewport_origin() + min, max - min);
 
+        let mut camera = camera;
+        camera.set_screen_size(rect.size);
+        let outer_camera = std::mem::replace(self.camera, camera);
+        let outer_size = std::mem::replace(
+            &mut self.target_size,
+            (rect.size.x as u32, rect.size.y as u32),
+        );
+        let previous = self.batch.begin_viewport(rect, camera);
+        draw_fn(self);
+        self.batch.end_viewport(previous, *self.camera);
+        *self.camera = outer_camera;
+        self.target_size = outer_size;
+    }
+    /// Draw everything in `draw_fn` with the camera's translation scaled by `factor`, keeping
+    /// its zoom & rotation, for parallax scrolling: `0.0` stays put like a far background,
+    /// `1.0` moves with the world & past `1.0` moves faster, like a near foreground
+    ///
+    /// ```no_run
+    /// # use egor_glue::graphics::Graphics;
+    /// # use glam::{Vec2, vec2};
+    /// # fn f(gfx: &mut Graphics) {
+    /// gfx.with_parallax(Vec2::ZERO, |gfx| {
+    ///     gfx.rect().size(vec2(4.0, 4.0)); // a star
+    /// });
+    /// gfx.with_parallax(vec2(0.3, 1.0), |gfx| {
+    ///     gfx.rect().at(vec2(0.0, 300.0)).size(vec2(200.0, 100.0)); // a hill
+    /// });
+    /// # }
+    /// ```
+    ///
+    /// The scope's camera is taken from the camera as it is when this is called, so move the
+    /// camera first. It's drawn like a [`Self::viewport`] covering the current one: inside,
+    /// [`Self::camera`] is the scaled camera (e.g. for culling) & layer cameras don't apply.
+    /// Text is placed in screen pixels, so it doesn't scroll with the scope
+    pub fn with_parallax(&mut self, factor: Vec2, draw_fn: impl FnOnce(&mut Self)) {
+        let camera = self.camera.parallax(factor);
+        let rect = Rect::new(Vec2::ZERO, self.screen_size());
+        self.viewport(rect, camera, draw_fn);
+    }
+    /// Set the order layers are drawn in, back to front.
+    /// [`Layer::DEFAULT`] is drawn first unless listed; other unlisted layers are drawn last
+    pub fn set_layer_order(&mut self, names: &[&str]) {
+        self.batch.layers.set_order(names);
+    }
     /// Start building a rectangle primitive
+    #[track_caller]
     pub fn rect(&mut self) -> RectangleBuilder<'_> {
+        self.sync_texture_check();
+        self.batch.open_draw(DrawKind::Rect);
         RectangleBuilder::new(self.batch, self.current_shader)
     }
+    /// Cover the screen with a texture scrolled by `offset` (in texture widths & heights),
+    /// wherever the camera is. Draw it first & stack a few at different speeds for parallax.
+    /// Use a repeating texture so it wraps, & chain [`RectangleBuilder::uv_scale`] to tile it:
+    ///
+    /// ```no_run
+    /// # use egor_glue::graphics::Graphics;
+    /// # fn f(gfx: &mut Graphics, stars: egor_glue::graphics::TextureId, time: f32) {
+    /// let tiles = gfx.screen_size() / 256.0;
+    /// gfx.scrolling_background(stars, glam::vec2(time * 0.1, 0.0))
+    ///     .uv_scale(tiles);
+    /// # }
+    /// ```
+    #[track_caller]
+    pub fn scrolling_background(
+        &mut self,
+        texture_id: impl Into<TextureId>,
+        offset: Vec2,
+    ) -> RectangleBuilder<'_> {
+        let screen = self.screen_size();
+        let coords = self.coords();
+        let camera = coords.camera();
+        self.rect()
+            .anchor(Anchor::Center)
+            .at(coords.convert(screen / 2.0, Space::Screen, Space::World))
+            .size(screen / camera.zoom())
+            .rotate(camera.rotation())
+            .texture(texture_id)
+            .uv_offset(offset)
+    }
+    /// Draw a 1 pixel wide outline around `rect`, regardless of camera zoom.
+    /// Handy for hitboxes & physics debugging
+    #[track_caller]
+    pub fn debug_rect(&mut self, rect: &Rect, color: Color) {
+        let thickness = 1.0 / self.camera.zoom();
+        self.rect()
+            .with(rect)
+            .fill(false)
+            .stroke(thickness)
+            .color(color);
+    }
+    /// Debug shapes drawn on top of everything, see [`Gizmos`]
+    pub fn gizmos(&mut self) -> Gizmos<'_> {
+        Gizmos::new(self.gizmos.as_deref_mut())
+    }
+
+    /// Built-in screen-space effects like vignette & CRT, applied to the whole frame
+    pub fn post_effects(&mut self) -> PostEffects<'_> {
+        PostEffects::new(self.post_chain.as_deref_mut())
+    }
+
+    /// Buttons, checkboxes, sliders & labels drawn in screen space, see [`Widgets`]
+    pub fn ui(&mut self) -> Widgets<'_, 'a> {
+        Widgets::new(self)
+    }
+
+    pub(crate) fn widget_store(&mut self) -> Option<&mut WidgetStore> {
+        self.widgets.as_deref_mut()
+    }
+
+    /// Where a caret can go in one line of `text` drawn at `size`, see
+    /// [`crate::text::caret_stops`]
+    pub(crate) fn caret_stops(&mut self, text: &str, size: f32, stops: &mut Vec<(usize, f32)>) {
+        self.text_renderer.caret_stops(text, size, stops);
+    }
+
+    /// Id of the topmost rectangle under `point` of those drawn with
+    /// [`RectangleBuilder::pick_id`] so far this frame. `point` is in screen pixels, e.g. the
+    /// mouse position, & is seen through each rectangle's layer: the main camera, the layer's
+    /// own camera or none for screen space layers. Rotation, flips & atlas regions are
+    /// respected, as is [`RectangleBuilder::pick_alpha_threshold`]. Rectangles drawn in
+    /// [`Self::viewport`] are picked as if drawn outside it
+    ///
+    /// ```no_run
+    /// # use egor_glue::{graphics::{Graphics, TextureId}, math::{Vec2, vec2}};
+    /// # fn f(gfx: &mut Graphics, card: TextureId, mouse: Vec2) {
+    /// for (i, x) in [0.0, 60.0].into_iter().enumerate() {
+    ///     gfx.rect()
+    ///         .at(vec2(x, 0.0))
+    ///         .texture(card)
+    ///         .pick_alpha_threshold(0.5)
+    ///         .pick_id(i as u64);
+    /// }
+    /// let hovered = gfx.pick(mouse);
+    /// # }
+    /// ```
+    pub fn pick(&self, point: Vec2) -> Option<u64> {
+        self.batch
+            .picking
+            .pick(point, &self.batch.layers, &self.coords())
+    }
+
+    /// Draw the last [`FrameTimer::frame_history`] frame times as bars in `rect`, in screen
+    /// pixels. Bars are green within 16.6ms, yellow within 33.3ms & red past it, with lines
+    /// at both budgets. The vertical scale grows with spikes & eases back down after a while
+    pub fn frame_graph(&mut self, rect: Rect) {
+        if let Some((timer, scale)) = self.frame_graph.take() {
+            frame_graph::draw(self, rect, timer, scale);
+            self.frame_graph = Some((timer, scale));
+        }
+    }
     /// Start building an arbitrary polygon primitive, capable of triangles, circles, n-gons
+    #[track_caller]
     pub fn polygon(&mut self) -> PolygonBuilder<'_> {
+        self.batch.open_draw(DrawKind::Polygon);
         PolygonBuilder::new(self.batch, self.current_shader)
     }
     /// Start building a polyline (stroked path) primitive
+    #[track_caller]
     pub fn polyline(&mut self) -> PolylineBuilder<'_> {
+        self.batch.open_draw(DrawKind::Polyline);
         PolylineBuilder::new(self.batch, self.current_shader)
     }
     /// Start building a vector path (lines + curves) to be filled or stroked
+    #[track_caller]
     pub fn path(&mut self) -> PathBuilder<'_> {
-        PathBuilder::new(self.batch, self.current_shader)
+        self.batch.open_draw(DrawKind::Path);
+        let zoom = self.curve_zoom();
+        PathBuilder::new(self.batch, self.current_shader).with_zoom(zoom)
+    }
+    /// Set how finely path curves are tessellated when a path doesn't set its own
+    /// [`PathBuilder::tolerance`]. Lasts until changed
+    ///
+    /// ```no_run
+    /// # use egor_glue::{graphics::Graphics, primitives::QualityMode};
+    /// # fn f(gfx: &mut Graphics) {
+    /// // smooth circles however far the camera zooms in
+    /// gfx.set_curve_quality(QualityMode::ZoomAdaptive);
+    /// # }
+    /// ```
+    pub fn set_curve_quality(&mut self, mode: QualityMode) {
+        self.batch.curve_quality = mode;
+    }
+    /// Zoom of the camera the next primitive is drawn with
+    fn curve_zoom(&self) -> f32 {
+        self.batch
+            .camera_override()
+            .map_or(self.camera.zoom(), |camera| camera.zoom())
+    }
+    /// Build many paths at once; they are tessellated in parallel (on native with the
+    /// `parallel` feature) when `build_fn` returns, instead of one by one as each path is dropped.
+    /// Worth it for scenes with hundreds of curve-heavy paths
+    ///
+    /// ```no_run
+    /// # use egor_glue::{color::Color, graphics::Graphics};
+    /// # fn f(gfx: &mut Graphics) {
+    /// gfx.parallel_shapes(|shapes| {
+    ///     for i in 0..500 {
+    ///         shapes.path().at(glam::vec2(i as f32, 0.0)).circle(8.0).fill_color(Color::RED);
+    ///     }
+    /// });
+    /// # }
+    /// ```
+    #[track_caller]
+    pub fn parallel_shapes(&mut self, build_fn: impl FnOnce(&mut ParallelShapes)) {
+        let _unguarded = alloc_guard::pause();
+        let zoom = self.curve_zoom();
+        // recorded as one path, since the shapes are only written once they're all built
+        self.batch.open_draw(DrawKind::Path);
+        build_fn(&mut ParallelShapes::new(
+            self.batch,
+            self.current_shader,
+            zoom,
+        ));
+        self.batch.close_draw();
+    }
+    /// Tessellate shapes once into a mesh kept on the GPU & return its id. Draw it any number
+    /// of times with [`Self::mesh`], which only costs an instance per copy. Meshes live until
+    /// [`Self::free_mesh`], so bake them once (e.g. on the first frame)
+    ///
+    /// ```no_run
+    /// # use egor_glue::{color::Color, graphics::Graphics};
+    /// # fn f(gfx: &mut Graphics, star: &[glam::Vec2], stars: &[(glam::Vec2, f32)]) {
+    /// let star_mesh = gfx.bake_mesh(|b| {
+    ///     b.polygon().points(star).color(Color::WHITE);
+    /// });
+    /// for &(pos, angle) in stars {
+    ///     gfx.mesh(star_mesh).at(pos).rotate(angle).color(Color::YELLOW);
+    /// }
+    /// # }
+    /// ```
+    pub fn bake_mesh(&mut self, bake_fn: impl FnOnce(&mut MeshBaker)) -> usize {
+        let _unguarded = alloc_guard::pause();
+        let mut baker = MeshBaker::new();
+        bake_fn(&mut baker);
+        let (vertices, indices) = baker.finish();
+        self.renderer.add_mesh(&vertices, &indices)
+    }
+    /// Draw an instance of a mesh created by [`Self::bake_mesh`]
+    #[track_caller]
+    pub fn mesh(&mut self, id: usize) -> MeshBuilder<'_> {
+        self.batch.open_draw(DrawKind::Mesh);
+        MeshBuilder::new(self.batch, self.current_shader, id)
+    }
+    /// Draw triangles of vertices given every frame, textured with `texture` (or a solid
+    /// color) & batched, layered & blended like any other shape. For geometry that changes
+    /// every frame, like cloth or soft bodies; [`Self::bake_mesh`] suits geometry that doesn't
+    ///
+    /// ```no_run
+    /// # use egor_glue::{color::Color, graphics::{Graphics, TextureId}, mesh::MeshVertex};
+    /// # fn f(gfx: &mut Graphics, flag: TextureId) {
+    /// let corner = |x: f32, y: f32| MeshVertex::new(glam::vec2(x, y) * 64.0, glam::vec2(x, y), Color::WHITE);
+    /// let quad = [corner(0.0, 0.0), corner(1.0, 0.0), corner(1.0, 1.0), corner(0.0, 1.0)];
+    /// gfx.mesh_raw(Some(flag)).vertices(&quad).indices(&[0, 1, 2, 0, 2, 3]);
+    /// # }
+    /// ```
+    #[track_caller]
+    pub fn mesh_raw(&mut self, texture: Option<TextureId>) -> RawMeshBuilder<'_> {
+        self.sync_texture_check();
+        self.batch.open_draw(DrawKind::RawMesh);
+        let texture = texture.map(usize::from);
+        RawMeshBuilder::new(self.batch, self.current_shader, texture)
+    }
+    /// Release a baked mesh. Its id may be handed out again by [`Self::bake_mesh`]
+    pub fn free_mesh(&mut self, id: usize) {
+        self.renderer.free_mesh(id);
+    }
+    /// Capture the shapes, sprites & meshes drawn in `draw_fn` into GPU buffers uploaded once,
+    /// instead of drawing them this frame. [`Self::draw_static`] then draws them every frame
+    /// with no CPU vertex work or upload, e.g. for a tilemap that never changes. Cameras still
+    /// apply, but tints, transforms & positions are baked in; text isn't captured. Neither are
+    /// chunks drawn with [`Self::draw_static`] inside it, which log a warning; draw them next
+    /// to the new chunk instead
+    ///
+    /// ```no_run
+    /// # use egor_glue::graphics::{Graphics, StaticGeometryId};
+    /// # fn f(gfx: &mut Graphics, tiles: &[(glam::Vec2, [f32; 4])], ground: &mut Option<StaticGeometryId>) {
+    /// let ground = *ground.get_or_insert_with(|| {
+    ///     gfx.bake_static(|gfx| {
+    ///         for &(pos, uv) in tiles {
+    ///             gfx.rect().at(pos).size(glam::Vec2::splat(16.0)).sprite(0, uv);
+    ///         }
+    ///     })
+    /// });
+    /// gfx.draw_static(ground);
+    /// # }
+    /// ```
+    pub fn bake_static(&mut self, draw_fn: impl FnOnce(&mut Self)) -> StaticGeometryId {
+        let _unguarded = alloc_guard::pause();
+        let previous = self.batch.begin_capture();
+        draw_fn(self);
+        let mut batches = self.batch.end_capture(previous);
+        for baked in &mut batches {
+            self.renderer.upload_batch(&mut baked.geometry);
+        }
+        StaticGeometryId(self.batch.statics.insert(batches))
     }
-    /// Load a font from disk into the text system.
+    /// Draw geometry baked by [`Self::bake_static`] on the current layer, in order with
+    /// everything else. Freed ids draw nothing
+    #[track_caller]
+    pub fn draw_static(&mut self, id: StaticGeometryId) {
+        self.batch.open_draw(DrawKind::Static);
+        self.batch.push_static(id.0);
+        self.batch.close_draw();
+    }
+    /// Release baked geometry, e.g. to bake it again after it changed. Its id may be handed
+    /// out again by [`Self::bake_static`]
+    pub fn free_static(&mut self, id: StaticGeometryId) {
+        self.batch.statics.remove(id.0);
+    }
+    /// Load a font from disk into the text system. Clears the text cache, since cached text
+    /// may have fallen back to another font
     pub fn load_font(&mut self, bytes: &[u8]) -> Option<String> {
         self.text_renderer.load_font_bytes(bytes)
     }
-    /// Draw a line of text
+    /// Font families tried in order for glyphs the text's own font lacks, ahead of the
+    /// platform's fallbacks, e.g. a color emoji font registered with [`Self::load_font`]:
+    ///
+    /// ```no_run
+    /// # fn f(gfx: &mut egor_glue::graphics::Graphics) {
+    /// let emoji = gfx.load_font(&std::fs::read("NotoColorEmoji.ttf").unwrap()).unwrap();
+    /// let arabic = gfx.load_font(&std::fs::read("NotoSansArabic.ttf").unwrap()).unwrap();
+    /// gfx.set_font_fallback_chain(&[emoji, arabic]);
+    /// # }
+    /// ```
+    ///
+    /// Replaces the platform's per-script picks, fonts outside the chain are still searched
+    /// last. Clears the text cache
+    pub fn set_font_fallback_chain(&mut self, families: &[impl AsRef<str>]) {
+        self.text_renderer.set_fallback_chain(families);
+    }
+    /// Draw a line of text. Text drawn before with the same style reuses its layout instead
+    /// of being shaped again; the least recently drawn layouts are dropped as the cache fills.
+    /// It's drawn on top of shapes, except in a layer sorted by y, see
+    /// [`Self::on_layer_sorted_y`]
+    #[track_caller]
     pub fn text(&mut self, text: &str) -> TextBuilder<'_> {
-        TextBuilder::new(self.text_renderer, text.to_string())
+        // text isn't batched, so only where it was drawn is recorded
+        self.batch.open_draw(DrawKind::Text);
+        self.batch.close_draw();
+        let text = self.text_renderer.pooled_string(text);
+        let layer = self.batch.layer();
+        let builder = TextBuilder::new(self.text_renderer, text);
+        match self.batch.layers.get(layer).sorts_by_y() {
+            true => builder.sorted_in(layer),
+            false => builder,
+        }
+    }
+
+    /// Draw `text` with a [`BitmapFont`], one quad per glyph in the sprite batch. Unlike
+    /// [`Self::text`] nothing is shaped, so it's the cheap way to draw counters & other
+    /// numbers that change every frame. It's positioned in world space like a sprite
+    #[track_caller]
+    pub fn bitmap_text<'t>(
+        &'t mut self,
+        font: &'t BitmapFont,
+        text: &'t str,
+    ) -> BitmapTextBuilder<'t> {
+        self.batch.open_draw(DrawKind::BitmapText);
+        BitmapTextBuilder::new(self.batch, self.current_shader, Some(font), text)
+    }
+
+    /// Like [`Self::bitmap_text`] with a built-in 5x7 pixel font of ASCII from space to `_`,
+    /// drawing lowercase as uppercase. Meant for debug readouts
+    #[track_caller]
+    pub fn debug_text<'t>(&'t mut self, text: &'t str) -> BitmapTextBuilder<'t> {
+        self.batch.open_draw(DrawKind::BitmapText);
+        BitmapTextBuilder::new(self.batch, self.current_shader, None, text)
+    }
+
+    /// Drop every cached text layout, e.g. after a scene change, so text is shaped again
+    /// when next drawn
+    pub fn clear_text_cache(&mut self) {
+        self.text_renderer.clear_shaped();
     }
 
     /// Load a texture from raw image data (e.g., PNG bytes)
     ///
     /// Returns a texture ID that can be used with `.texture(id)` on primitives.
     /// Typically called once during initialization (when `timer.frame == 0`).
-    pub fn load_texture(&mut self, data: &[u8]) -> usize {
-        self.renderer.add_texture(data)
+    pub fn load_texture(&mut self, data: &[u8]) -> TextureId {
+        TextureId(self.renderer.add_texture(data))
+    }
+    /// Like [`Self::load_texture`], also keeping the texture's alpha on the CPU so picks can
+    /// fall through its transparent texels, see [`RectangleBuilder::pick_alpha_threshold`].
+    /// Updating the texture doesn't update that copy
+    ///
+    /// # Panics
+    /// If the data isn't an image
+    pub fn load_texture_pickable(&mut self, data: &[u8]) -> TextureId {
+        let image = image::load_from_memory(data)
+            .expect("failed to decode pickable texture")
+            .into_rgba8();
+        let (w, h) = image.dimensions();
+        let id = self.renderer.add_texture_raw(w, h, &image);
+        let mask = AlphaMask::from_rgba(w, h, &image);
+        self.batch.picking.insert_mask(id, mask);
+        TextureId(id)
+    }
+    /// Load a KTX2 texture, keeping BC, ETC2 or ASTC data compressed on GPUs that sample it
+    /// & decoding it to RGBA8 elsewhere. Mip levels are kept & the container picks sRGB or
+    /// linear. Convert PNGs with `cargo xtask ktx2`.
+    /// Basis Universal (ETC1S or UASTC) & zstd or zlib supercompressed containers aren't
+    /// transcoded, so write them without `toktx --encode` or `--zcmp`
+    ///
+    /// # Panics
+    /// If the data isn't a 2D KTX2 texture in a supported format or is supercompressed, see
+    /// [`egor_render::CompressedTextureError`]
+    #[cfg(feature = "ktx2")]
+    pub fn load_texture_compressed(&mut self, ktx2: &[u8]) -> TextureId {
+        match self.renderer.add_texture_compressed(ktx2) {
+            Ok(id) => TextureId(id),
+            Err(e) => panic!("failed to load KTX2 texture: {e}"),
+        }
+    }
+    /// Format a texture is stored in on the GPU, e.g. to check whether a compressed one
+    /// had to be decoded
+    pub fn texture_format(&self, id: impl Into<TextureId>) -> Option<TextureFormat> {
+        self.renderer.texture_format(id.into().0)
+    }
+    /// Whether `id` is a loaded texture. Rectangles & raw meshes drawn with one that isn't
+    /// fall back to white & log a warning in debug builds (or panic, see
+    /// [`crate::app::App::strict_textures`])
+    pub fn texture_exists(&self, id: impl Into<TextureId>) -> bool {
+        self.renderer.texture_exists(id.into().0)
+    }
+    /// Load a texture as a layer of the shared texture array, returning its id & layer for
+    /// [`RectangleBuilder::texture_layer`]. Sprites from the array draw in one batch instead
+    /// of one per texture. Only images the size of the first one join the array, others (&
+    /// everything on devices without texture arrays) load as standalone textures at layer 0.
+    /// Layers share the array's id, so [`Self::unload_texture`] drops the whole array.
+    /// Arrays ignore custom shaders; paths & polygons using one sample layer 0
+    pub fn load_texture_into_array(&mut self, data: &[u8]) -> (TextureId, u32) {
+        let (id, layer) = self.renderer.add_texture_into_array(data);
+        (TextureId(id), layer)
+    }
+    /// Like [`Self::load_texture_into_array`], from raw RGBA bytes
+    pub fn load_texture_raw_into_array(&mut self, w: u32, h: u32, data: &[u8]) -> (TextureId, u32) {
+        let (id, layer) = self.renderer.add_texture_raw_into_array(w, h, data);
+        (TextureId(id), layer)
+    }
+    /// Load a texture whose UVs tile outside `0..1` instead of clamping to the edge,
+    /// for [`RectangleBuilder::uv_scale`] & [`Self::scrolling_background`]
+    pub fn load_texture_repeating(&mut self, data: &[u8]) -> TextureId {
+        TextureId(self.renderer.add_texture_repeating(data))
+    }
+    /// Like [`Self::load_texture_repeating`], from raw RGBA bytes
+    pub fn load_texture_raw_repeating(&mut self, w: u32, h: u32, data: &[u8]) -> TextureId {
+        TextureId(self.renderer.add_texture_raw_repeating(w, h, data))
+    }
+    /// Drop a texture, freeing its GPU memory. The id isn't reused & draws white afterwards,
+    /// reported like one that was never loaded. Every layer of the shared texture array has
+    /// the array's id, so unloading one layer's id drops all of them
+    pub fn unload_texture(&mut self, id: impl Into<TextureId>) {
+        let id = id.into().0;
+        self.renderer.unload_texture(id);
+        self.batch.texture_check.unloaded.insert(id);
+        self.batch.picking.remove_mask(id);
+    }
+    /// Estimated GPU memory of loaded textures, offscreen targets & buffers, with the
+    /// largest textures. See [`crate::app::App::texture_memory_budget`] to be warned
+    /// when textures grow past a limit
+    pub fn gpu_memory_report(&self) -> MemoryReport<TextureId> {
+        self.renderer.memory_report().map_textures(TextureId)
     }
-    /// Update texture data by index
-    pub fn update_texture(&mut self, index: usize, data: &[u8]) {
+    /// Update texture data by index. Ids that were never loaded are reported like drawing
+    /// them, see [`crate::app::App::strict_textures`]
+    pub fn update_texture(&mut self, index: impl Into<TextureId>, data: &[u8]) {
+        let index = index.into().0;
+        self.check_texture(index, "texture update");
         self.renderer.update_texture(index, data);
     }
-    /// Update texture data by index with raw width/height
-    pub fn update_texture_raw(&mut self, index: usize, w: u32, h: u32, data: &[u8]) {
+    /// Update texture data by index with raw width/height. Same-sized data is written into
+    /// the existing texture
+    pub fn update_texture_raw(&mut self, index: impl Into<TextureId>, w: u32, h: u32, data: &[u8]) {
+        let index = index.into().0;
+        self.check_texture(index, "texture update");
         self.renderer.update_texture_raw(index, w, h, data);
     }
+    /// Reports texture `id` used by `what` if it was never loaded
+    fn check_texture(&mut self, id: usize, what: &str) {
+        self.sync_texture_check();
+        self.batch.texture_check.check(id, what);
+    }
+    /// Catches the texture check up with textures loaded since it last ran. Every entry point
+    /// whose textures are checked calls it first
+    fn sync_texture_check(&mut self) {
+        self.batch.texture_check.loaded = self.renderer.texture_count();
+    }
+    /// Overwrite a `w` x `h` region at (`x`, `y`) of a texture with raw RGBA bytes, uploading
+    /// only that region. Much cheaper than [`Self::update_texture_raw`] for large streamed
+    /// textures that change a little per frame
+    pub fn update_texture_region(
+        &mut self,
+        index: impl Into<TextureId>,
+        x: u32,
+        y: u32,
+        w: u32,
+        h: u32,
+        data: &[u8],
+    ) -> Result<(), TextureRegionError> {
+        self.renderer
+            .update_texture_region(index.into().0, x, y, w, h, data)
+    }
 
-    /// Load a custom shader from WGSL source code
+    /// Load a custom shader from WGSL source code. A `//! egor:prelude` line
+    /// ([`egor_render::PRELUDE_DIRECTIVE`]) is replaced with egor's bindings & structs,
+    /// see [`Self::shader_prelude`], so the shader only needs its entry points:
+    ///
+    /// ```wgsl
+    /// //! egor:prelude
+    /// @fragment
+    /// fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
+    ///     let tex = textureSample(texture_binding, texture_sampler, input.tex_coords);
+    ///     return vec4<f32>(1.0 - tex.rgb, tex.a) * input.color;
+    /// }
+    /// ```
+    ///
+    /// `input.params` holds what [`crate::primitives::RectangleBuilder::params`] set, for
+    /// small per-sprite values that don't need a uniform. Shaders that fail to compile draw
+    /// like the built-in one & show up in [`Self::shader_errors`]
     pub fn load_shader(&mut self, wgsl_source: &str) -> usize {
-        self.renderer.add_shader(wgsl_source)
+        let shader_id = self.renderer.add_shader(wgsl_source);
+        self.log_shader_errors(shader_id);
+        shader_id
     }
 
     /// Create a uniform buffer from raw bytes, returns a uniform id
@@ -205,10 +1228,187 @@ impl<'a> Graphics<'a> {
         self.renderer.update_uniform(id, data);
     }
 
-    /// Load a custom shader with associated uniform buffers
+    /// WGSL the `//! egor:prelude` line of custom shaders expands to. Declarations match the
+    /// built-in pipeline's layout; without their own `vs_main` shaders get one calling
+    /// `egor_vertex`, the built-in vertex transform
+    pub fn shader_prelude(&self) -> String {
+        self.renderer.shader_prelude()
+    }
+
+    /// Load a custom shader with associated uniform buffers, bound from group 2 on.
+    /// Supports the prelude like [`Self::load_shader`]
     pub fn load_shader_with_uniforms(&mut self, wgsl_source: &str, uniform_ids: &[usize]) -> usize {
-        self.renderer
-            .add_shader_with_uniforms(wgsl_source, uniform_ids)
+        let shader_id = self
+            .renderer
+            .add_shader_with_uniforms(wgsl_source, uniform_ids);
+        self.log_shader_errors(shader_id);
+        shader_id
+    }
+
+    /// Recompile a shader from new WGSL source, e.g. when its file changes. A broken version
+    /// shows up in [`Self::shader_errors`] while the last one that compiled keeps drawing
+    pub fn reload_shader(&mut self, shader_id: usize, wgsl_source: &str) {
+        self.renderer.replace_shader(shader_id, wgsl_source);
+        self.log_shader_errors(shader_id);
+    }
+
+    /// Errors of shaders whose latest source failed to compile, with the line & column they
+    /// point at. A shader's errors clear once it compiles again. See
+    /// `egor::dev::shader_errors_window` for a panel listing them
+    pub fn shader_errors(&self) -> &[ShaderError] {
+        self.renderer.shader_errors()
+    }
+
+    /// Record every draw of the next frame: what was drawn, from where, with which texture
+    /// & shader, & the batches it ended up in. Take it with [`Self::take_capture`] on the
+    /// frame after. Frames not captured record nothing. See `egor::dev::capture_inspector`
+    /// for a panel browsing it
+    pub fn capture_next_frame(&mut self) {
+        self.batch.request_capture();
+    }
+
+    /// The frame recorded after [`Self::capture_next_frame`], once it has been drawn
+    pub fn take_capture(&mut self) -> Option<FrameCapture> {
+        self.batch.take_capture()
+    }
+
+    fn log_shader_errors(&self, shader_id: usize) {
+        for error in self.shader_errors() {
+            if error.shader_id == shader_id {
+                log::error!(target: "egor_glue::shader", "{error}");
+            }
+        }
+    }
+
+    /// Multiply the color of everything drawn in `draw_fn` (shapes, sprites & text) by `tint`.
+    /// Nested tints multiply, so a scene-wide fade is one call:
+    ///
+    /// ```no_run
+    /// # use egor_glue::{color::Color, graphics::Graphics};
+    /// # fn f(gfx: &mut Graphics, fade: f32) {
+    /// gfx.with_tint(Color::WHITE.with_alpha(fade), |gfx| {
+    ///     gfx.rect().color(Color::RED);
+    ///     gfx.text("Paused");
+    /// });
+    /// # }
+    /// ```
+    pub fn with_tint(&mut self, tint: Color, draw_fn: impl FnOnce(&mut Self)) {
+        let previous = self.batch.push_tint(tint);
+        let previous_text = self.text_renderer.tint;
+        self.text_renderer.tint = previous_text.multiply(tint);
+        draw_fn(self);
+        self.batch.set_tint(previous);
+        self.text_renderer.tint = previous_text;
+    }
+
+    /// Draw everything after relative to `transform` within the current transform, until
+    /// [`Self::pop_transform`]. Positions, rotations & scales given to shapes, sprites, meshes
+    /// & text are then in the transform's space, e.g. a turret's angle relative to its tank.
+    /// Pushes nest to any depth. Text takes the transform's horizontal scale & is placed in
+    /// screen space like always, so it only lines up with shapes under the default camera.
+    /// Geometry baked by [`Self::bake_static`] isn't moved
+    pub fn push_transform(&mut self, transform: Transform) {
+        self.batch.push_transform(transform.to_affine());
+        self.text_renderer.transform = self.batch.transform();
+    }
+
+    /// Return to the transform before the last [`Self::push_transform`]. Popping more than
+    /// was pushed does nothing. Transforms left pushed are dropped at the end of the frame
+    pub fn pop_transform(&mut self) {
+        if !self.batch.pop_transform() {
+            log::warn!(target: "egor_glue::graphics", "pop_transform without a push_transform");
+        }
+        self.text_renderer.transform = self.batch.transform();
+    }
+
+    /// Draw everything in `draw_fn` relative to `transform`, see [`Self::push_transform`]:
+    ///
+    /// ```no_run
+    /// # use egor_glue::{graphics::Graphics, math::{Transform, vec2}};
+    /// # fn f(gfx: &mut Graphics, hull: f32, turret: f32) {
+    /// let tank = Transform::from_translation(vec2(200.0, 150.0)).rotated(hull);
+    /// gfx.with_transform(tank, |gfx| {
+    ///     gfx.rect().size(vec2(60.0, 40.0)).at(vec2(-30.0, -20.0));
+    ///     gfx.with_transform(Transform::from_rotation(turret), |gfx| {
+    ///         // the gun points along the turret, wherever the tank is headed
+    ///         gfx.rect().size(vec2(40.0, 6.0)).at(vec2(0.0, -3.0));
+    ///     });
+    /// });
+    /// # }
+    /// ```
+    pub fn with_transform(&mut self, transform: Transform, draw_fn: impl FnOnce(&mut Self)) {
+        self.push_transform(transform);
+        draw_fn(self);
+        self.pop_transform();
+    }
+
+    /// Panic with a backtrace whenever egor's per-frame render path allocates on this thread.
+    /// Turn it on once the first frames have warmed the pools up, e.g. at `timer.frame == 2`
+    ///
+    /// Guarded: shape, sprite & mesh builders, text drawn with the same style last frame &
+    /// the end of frame batching. Exempt, since they allocate by design: loading textures,
+    /// atlases, fonts & shaders, [`Self::render_offscreen`], [`Self::bake_mesh`],
+    /// [`Self::parallel_shapes`], shaping & rasterizing text not seen last frame, gizmos,
+    /// egui & wgpu's own submission. Allocations in your own code are never checked
+    ///
+    /// Needs the `alloc_guard` feature & [`crate::alloc_guard::CountingAlloc`] installed as the
+    /// app's `#[global_allocator]`, see [`crate::alloc_guard`]. Does nothing in release builds
+    #[cfg(feature = "alloc_guard")]
+    pub fn assert_no_frame_allocations(&mut self, enabled: bool) {
+        alloc_guard::set_enabled(enabled);
+    }
+
+    /// Time `f` as a scope called `name` in this frame's [`ProfileReport`]. Scopes opened inside
+    /// `f` nest under it. Without the `profiling` feature this is just a call to `f`
+    ///
+    /// ```no_run
+    /// # use egor_glue::graphics::Graphics;
+    /// # fn f(gfx: &mut Graphics) {
+    /// gfx.profile_scope("enemy rendering", |gfx| {
+    ///     gfx.rect().size(glam::Vec2::splat(16.0));
+    /// });
+    /// # }
+    /// ```
+    #[inline(always)]
+    pub fn profile_scope<R>(&mut self, name: &'static str, f: impl FnOnce(&mut Self) -> R) -> R {
+        self.enter_scope(name);
+        let result = f(self);
+        self.exit_scope();
+        result
+    }
+
+    /// Opens a profiling scope that [`Self::exit_scope`] closes, for spans a closure can't wrap
+    #[inline(always)]
+    pub(crate) fn enter_scope(&mut self, name: &'static str) {
+        #[cfg(feature = "profiling")]
+        if let Some(profiler) = self.profiler.as_deref_mut() {
+            profiler.enter(name);
+        }
+        #[cfg(not(feature = "profiling"))]
+        let _ = name;
+    }
+
+    #[inline(always)]
+    pub(crate) fn exit_scope(&mut self) {
+        #[cfg(feature = "profiling")]
+        if let Some(profiler) = self.profiler.as_deref_mut() {
+            profiler.exit();
+        }
+    }
+
+    /// Scope timings of the last complete frame, including the engine's own phases.
+    /// `None` before the first frame finishes or without the `profiling` feature
+    pub fn profiling_report(&self) -> Option<&ProfileReport> {
+        self.profiler.as_deref()?.report()
+    }
+
+    /// Write the last couple seconds of scope timings to `path` as Chrome trace-event JSON,
+    /// for inspecting in Perfetto
+    pub fn dump_trace(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        match self.profiler.as_deref() {
+            Some(profiler) => profiler.dump_trace(path),
+            None => Ok(()),
+        }
     }
 
     /// Execute drawing commands with a custom shader
@@ -221,3 +1421,247 @@ impl<'a> Graphics<'a> {
         self.current_shader = previous_shader;
     }
 }
+
+/// Pushes resolved text quads into `batch`, undoing the camera since text is placed in
+/// screen space
+pub(crate) fn push_text_quads(
+    batch: &mut PrimitiveBatch,
+    coords: &Coords,
+    quads: impl IntoIterator<Item = TransformedText>,
+) {
+    let layer = batch.layer();
+    for text in quads {
+        let coords = &place_text(batch, coords, layer, text.sorted);
+        let camera = coords.camera();
+        let zoom = camera.zoom();
+        let center = coords.convert(text.center, Space::Screen, Space::World);
+        // counter the camera rotation so text stays upright on screen
+        let rot = Mat2::from_angle(text.rotation + camera.rotation());
+        let (col0, col1) = (
+            rot.x_axis * text.size.x / zoom,
+            rot.y_axis * text.size.y / zoom,
+        );
+
+        batch.push_instance(
+            Instance::new(
+                [col0.x, col0.y, col1.x, col1.y],
+                center.into(),
+                [1.0; 4],
+                [0.0, 0.0, 1.0, 1.0],
+            ),
+            Some(text.texture_id),
+            None,
+        );
+    }
+    place_text(batch, coords, layer, None);
+}
+
+/// Pushes text backgrounds as solid quads, or rounded rect paths when they have a radius,
+/// placed like [`push_text_quads`]. Colors are already tinted when the text was queued
+fn push_text_backgrounds(
+    batch: &mut PrimitiveBatch,
+    coords: &Coords,
+    backgrounds: impl IntoIterator<Item = TextBackground>,
+) {
+    let layer = batch.layer();
+    let tint = batch.set_tint(Color::WHITE);
+    for background in backgrounds {
+        let coords = &place_text(batch, coords, layer, background.sorted);
+        let camera = coords.camera();
+        let zoom = camera.zoom();
+        let center = coords.convert(background.center, Space::Screen, Space::World);
+        let angle = background.rotation + camera.rotation();
+        let size = background.size / zoom;
+
+        if background.radius > 0.0 {
+            let radius = (background.radius / zoom).min(size.min_element() * 0.5);
+            PathBuilder::new(batch, None)
+                .at(center - Mat2::from_angle(angle) * size * 0.5)
+                .rotate(angle)
+                .fill_color(background.color)
+                .round_rect(
+                    size,
+                    Some(BorderRadii {
+                        top_left: radius,
+                        top_right: radius,
+                        bottom_left: radius,
+                        bottom_right: radius,
+                    }),
+                );
+            continue;
+        }
+
+        let rot = Mat2::from_angle(angle);
+        let (col0, col1) = (rot.x_axis * size.x, rot.y_axis * size.y);
+        let (tex_id, [u, v]) = batch.solid_texture();
+        batch.push_instance(
+            Instance::new(
+                [col0.x, col0.y, col1.x, col1.y],
+                center.into(),
+                background.color.components(),
+                [u, v, u, v],
+            ),
+            tex_id,
+            None,
+        );
+    }
+    place_text(batch, coords, layer, None);
+    batch.set_tint(tint);
+}
+
+/// Pushes underlines & strikethroughs as solid quads, placed like [`push_text_quads`]
+fn push_text_decorations(
+    batch: &mut PrimitiveBatch,
+    coords: &Coords,
+    decorations: impl IntoIterator<Item = TextDecoration>,
+) {
+    let layer = batch.layer();
+    for line in decorations {
+        let coords = &place_text(batch, coords, layer, line.sorted);
+        let camera = coords.camera();
+        let zoom = camera.zoom();
+        let center = coords.convert(line.center, Space::Screen, Space::World);
+        let rot = Mat2::from_angle(line.rotation + camera.rotation());
+        let (col0, col1) = (
+            rot.x_axis * line.size.x / zoom,
+            rot.y_axis * line.size.y / zoom,
+        );
+
+        batch.push_instance(
+            Instance::new(
+                [col0.x, col0.y, col1.x, col1.y],
+                center.into(),
+                line.color.components(),
+                [0.0, 0.0, 1.0, 1.0],
+            ),
+            None,
+            None,
+        );
+    }
+    place_text(batch, coords, layer, None);
+}
+
+/// Moves `batch` onto the layer of text drawn in a layer sorted by y & keys what's pushed
+/// next by the text's bottom, or back onto `layer` for other text. Returns the coords to
+/// place the text with, through its layer's camera
+fn place_text(
+    batch: &mut PrimitiveBatch,
+    coords: &Coords,
+    layer: usize,
+    sorted: Option<SortedText>,
+) -> Coords {
+    let Some(sorted) = sorted else {
+        batch.set_layer(layer);
+        batch.sort_key = None;
+        return *coords;
+    };
+    batch.set_layer(sorted.layer);
+    let coords = match batch.camera_override() {
+        Some(camera) => coords.seen_through(camera),
+        None => *coords,
+    };
+    let bottom = coords.convert(sorted.bottom, Space::Screen, Space::World);
+    batch.sort_key = Some(bottom.y);
+    coords
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_backgrounds_skip_the_tint_and_round_their_corners() {
+        let mut batch = PrimitiveBatch::default();
+        let tint = Color::new([0.5, 0.5, 0.5, 1.0]);
+        batch.set_tint(tint);
+        let background = |radius| TextBackground {
+            center: vec2(50.0, 20.0),
+            size: vec2(80.0, 24.0),
+            rotation: 0.0,
+            radius,
+            color: Color::RED,
+            sorted: None,
+        };
+        push_text_backgrounds(
+            &mut batch,
+            &Coords::new(Camera::default(), 1.0),
+            vec![background(0.0), background(6.0)],
+        );
+        assert_eq!(batch.set_tint(Color::WHITE), tint);
+
+        let (instances, vertices): (Vec<_>, Vec<_>) = batch
+            .iter_mut()
+            .map(|(.., g)| (g.instances().to_vec(), g.vertices().to_vec()))
+            .unzip();
+        let quad = instances.concat()[0];
+        assert_eq!(quad.affine, [80.0, 0.0, 0.0, 24.0]);
+        assert_eq!(quad.translate, [50.0, 20.0]);
+        assert_eq!(quad.color, Color::RED.components());
+        // the rounded box is a tessellated path spanning the same bounds
+        let vertices = vertices.concat();
+        let xs = vertices.iter().map(|v| v.position[0]);
+        let (min, max) = xs.fold((f32::MAX, f32::MIN), |(lo, hi), x| (lo.min(x), hi.max(x)));
+        assert_eq!((min, max), (10.0, 90.0));
+        assert!(vertices.iter().all(|v| v.color == Color::RED.components()));
+    }
+
+    #[test]
+    fn text_in_y_sorted_layers_sorts_with_its_background() {
+        let mut batch = PrimitiveBatch::default();
+        batch.texture_check.loaded = 3;
+        let entities = batch.layers.index("entities");
+        batch.layers.get_mut(entities).sort_by_y(true);
+        batch.set_layer(entities);
+        // the text's bottom at y = 50 falls between the rectangles'
+        RectangleBuilder::new(&mut batch, None)
+            .at(vec2(0.0, 60.0))
+            .size(Vec2::splat(40.0))
+            .texture(1);
+        RectangleBuilder::new(&mut batch, None)
+            .size(Vec2::splat(20.0))
+            .texture(2);
+        batch.set_layer(0);
+
+        let sorted = Some(SortedText {
+            layer: entities,
+            bottom: vec2(20.0, 50.0),
+        });
+        let coords = Coords::new(Camera::default(), 1.0);
+        let background = TextBackground {
+            center: vec2(20.0, 40.0),
+            size: vec2(48.0, 28.0),
+            rotation: 0.0,
+            radius: 0.0,
+            color: Color::BLACK,
+            sorted,
+        };
+        push_text_backgrounds(&mut batch, &coords, vec![background]);
+        let text = TransformedText {
+            texture_id: 0,
+            center: vec2(20.0, 40.0),
+            size: vec2(40.0, 20.0),
+            rotation: 0.0,
+            sorted,
+        };
+        push_text_quads(&mut batch, &coords, vec![text]);
+        assert_eq!(batch.layer(), 0);
+        assert_eq!(batch.sort_key, None);
+
+        let drawn: Vec<_> = batch
+            .iter_mut()
+            .flat_map(|(texture, _, _, _, _, g)| {
+                g.instances().iter().map(move |i| (texture, i.color))
+            })
+            .collect();
+        let (solid, _) = batch.solid_texture();
+        assert_eq!(
+            drawn,
+            [
+                (Some(2), [1.0; 4]),
+                (solid, Color::BLACK.components()),
+                (Some(0), [1.0; 4]),
+                (Some(1), [1.0; 4]),
+            ]
+        );
+    }
+}
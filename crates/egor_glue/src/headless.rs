@@ -0,0 +1,703 @@
+use crate::{
+    app::{
+        AppControl, FrameContext, FrameHooks, FrameSummary, RenderThreadSend, ResizeFn, UpdateFn,
+        merge_resizes,
+    },
+    camera::{Camera, Origin},
+    frame::{FrameInput, FrameState},
+    gizmos::GizmoStore,
+};
+
+use egor_app::{
+    Resize,
+    input::{Input, InputSnapshot},
+    time::FrameTimer,
+};
+use egor_render::{
+    CommandEncoder, Device, MemoryHints, PassResources, Queue, Renderer, TextureFormat,
+    TextureView,
+    target::{OffscreenTarget, RenderTarget},
+};
+use glam::Vec2;
+
+/// Result of a single [`HeadlessApp::step`]
+pub struct FrameOutput {
+    /// Frame number, matching [`FrameTimer::frame`] during the step
+    pub frame: u64,
+    /// Size of the rendered image in pixels
+    pub size: (u32, u32),
+    /// Rendered image as tightly packed RGBA8 rows, if capturing is enabled & the frame was
+    /// drawn. Frames whose target fails to give a view to draw into are logged & skipped
+    pub pixels: Option<Vec<u8>>,
+    /// Exit code if the step called [`AppControl::request_exit`]
+    pub exit_code: Option<i32>,
+}
+
+/// Runs the same per-frame closure as [`crate::app::App::run`] without a window
+///
+/// Every step uses a fixed delta & scripted input, so game logic runs deterministically.
+/// Useful for testing gameplay in CI & replaying recorded [`InputSnapshot`]s
+///
+/// ```no_run
+/// # use egor_glue::{app::FrameContext, headless::HeadlessApp};
+/// # use egor_app::input::InputSnapshot;
+/// let mut app = HeadlessApp::new(320, 240, |FrameContext { gfx, .. }| {
+///     gfx.rect();
+/// });
+/// let output = app.step(&InputSnapshot::default());
+/// assert_eq!(output.pixels.unwrap().len(), 320 * 240 * 4);
+/// ```
+pub struct HeadlessApp {
+    update: Box<UpdateFn>,
+    hooks: FrameHooks,
+    renderer: Renderer,
+    target: OffscreenTarget,
+    state: FrameState,
+    input: Input,
+    timer: FrameTimer,
+    fixed_delta: f32,
+    capture: bool,
+    /// Size change requested by the last frame, for the next one
+    resized: Option<Resize>,
+    on_resize: Option<Box<ResizeFn>>,
+    /// Whether the first step ran. Like a window's first frame it's frame 0, with no time
+    /// passed, & calls `on_resize` with its size
+    started: bool,
+}
+
+impl HeadlessApp {
+    /// Image format of headless frames
+    pub const FORMAT: TextureFormat = TextureFormat::Rgba8UnormSrgb;
+
+    /// Create a headless app rendering `width` x `height` frames with a per-frame update closure.
+    /// Blocks while the GPU is initialized & panics if there's no GPU adapter
+    pub fn new(width: u32, height: u32, update: impl FnMut(&mut FrameContext) + 'static) -> Self {
+        Self::with_state(width, height, Box::new(update), FrameState::default())
+    }
+
+    /// Like [`Self::new`], drawing with `state` as configured by an [`crate::app::App`]
+    pub(crate) fn with_state(
+        width: u32,
+        height: u32,
+        update: Box<UpdateFn>,
+        mut state: FrameState,
+    ) -> Self {
+        let mut renderer =
+            pollster::block_on(Renderer::headless(Self::FORMAT, &MemoryHints::Performance))
+                .expect("headless rendering needs a GPU adapter");
+        state.init(&mut renderer, Self::FORMAT, None);
+        let target = renderer.create_offscreen_target(width, height, Self::FORMAT);
+
+        Self {
+            update,
+            hooks: FrameHooks::default(),
+            renderer,
+            target,
+            state,
+            input: Input::default(),
+            timer: FrameTimer::default(),
+            fixed_delta: 1.0 / 60.0,
+            capture: true,
+            resized: None,
+            on_resize: None,
+            started: false,
+        }
+    }
+
+    /// Set the delta time reported for every step after the first in seconds (defaults to 1/60)
+    pub fn fixed_delta(mut self, delta: f32) -> Self {
+        self.fixed_delta = delta;
+        self
+    }
+
+    /// Enable or disable reading back each rendered frame into [`FrameOutput::pixels`]
+    /// (defaults to true). Disable when only game state is asserted on
+    pub fn capture(mut self, enabled: bool) -> Self {
+        self.capture = enabled;
+        self
+    }
+
+    /// Set where world-space `(0, 0)` lands on screen (see [`crate::app::App::origin`])
+    pub fn origin(mut self, origin: Origin) -> Self {
+        let camera = &mut self.state.camera;
+        let (position, zoom) = (camera.position(), camera.zoom());
+        *camera = Camera::with_origin(origin);
+        camera.target(position);
+        camera.set_zoom(zoom);
+        self
+    }
+
+    /// Set the initial camera position & zoom (see [`crate::app::App::camera`])
+    pub fn camera(mut self, position: Vec2, zoom: f32) -> Self {
+        self.state.camera.target(position);
+        self.state.camera.set_zoom(zoom);
+        self
+    }
+
+    /// Number of recent frame times [`FrameTimer`] keeps (see [`crate::app::App::frame_history`])
+    pub fn frame_history(mut self, len: usize) -> Self {
+        self.timer = FrameTimer::with_history(len);
+        self
+    }
+
+    /// Enable or disable gizmos (see [`crate::app::App::gizmos`])
+    pub fn gizmos(mut self, enabled: bool) -> Self {
+        self.state.gizmos = GizmoStore::new(enabled);
+        self
+    }
+
+    /// Record your own wgpu commands into every step after shapes & text (see
+    /// [`crate::app::App::custom_pass`])
+    pub fn custom_pass(
+        mut self,
+        pass: impl FnMut(&Device, &Queue, &mut CommandEncoder, &TextureView, &PassResources)
+        + RenderThreadSend
+        + 'static,
+    ) -> Self {
+        self.state.custom_pass = Some(Box::new(pass));
+        self
+    }
+
+    /// Record your own wgpu commands into every step before shapes are drawn (see
+    /// [`crate::app::App::background_pass`])
+    pub fn background_pass(
+        mut self,
+        pass: impl FnMut(&Device, &Queue, &mut CommandEncoder, &TextureView, &PassResources)
+        + RenderThreadSend
+        + 'static,
+    ) -> Self {
+        self.state.background_pass = Some(Box::new(pass));
+        self
+    }
+
+    /// Log warnings when a step batches poorly (see [`crate::app::App::performance_warnings`])
+    pub fn performance_warnings(mut self, enabled: bool) -> Self {
+        self.state.perf_warnings.enabled = enabled;
+        self
+    }
+
+    /// Soft limit on the estimated bytes of textures (see
+    /// [`crate::app::App::texture_memory_budget`])
+    pub fn texture_memory_budget(mut self, bytes: u64) -> Self {
+        self.state.memory_budget.bytes = Some(bytes);
+        self
+    }
+
+    /// Call `hook` at the start of every step (see [`crate::app::App::before_frame`])
+    pub fn before_frame(mut self, hook: impl FnMut(&Input, &FrameTimer) + 'static) -> Self {
+        self.hooks.before.push(Box::new(hook));
+        self
+    }
+
+    /// Call `hook` at the end of every step (see [`crate::app::App::after_frame`])
+    pub fn after_frame(
+        mut self,
+        hook: impl FnMut(&Input, &FrameTimer, &FrameSummary) + 'static,
+    ) -> Self {
+        self.hooks.after.push(Box::new(hook));
+        self
+    }
+
+    /// Call `handler` with the previous & new size whenever a step resizes the frames (see
+    /// [`crate::app::App::on_resize`]), before the first step at the new size. It's also
+    /// called once before the first step with both sizes equal, so the same code sets the
+    /// camera up
+    pub fn on_resize(mut self, handler: impl FnMut(Resize, &mut Camera) + 'static) -> Self {
+        self.on_resize = Some(Box::new(handler));
+        self
+    }
+
+    /// Start from an [`crate::app::App`]'s frame hooks & resize handler
+    #[cfg(feature = "snapshot")]
+    pub(crate) fn with_app_state(
+        mut self,
+        hooks: FrameHooks,
+        on_resize: Option<Box<ResizeFn>>,
+    ) -> Self {
+        self.hooks = hooks;
+        self.on_resize = on_resize;
+        self
+    }
+
+    /// Access the underlying [`Renderer`]
+    pub fn renderer(&mut self) -> &mut Renderer {
+        &mut self.renderer
+    }
+
+    /// Run a single frame with `input` as the complete input state for that frame
+    pub fn step(&mut self, input: &InputSnapshot) -> FrameOutput {
+        self.input.apply_snapshot(input);
+        if self.started {
+            self.timer.advance(self.fixed_delta);
+        }
+        self.hooks.before(&self.input, &self.timer);
+
+        let (w, h) = self.target.size();
+        if !self.started {
+            self.started = true;
+            if let Some(on_resize) = &mut self.on_resize {
+                let size = (w, h);
+                on_resize(
+                    Resize {
+                        previous: size,
+                        size,
+                    },
+                    &mut self.state.camera,
+                );
+            }
+        }
+
+        // the update still runs without a frame, like a window's skipped frames
+        let frame = self
+            .renderer
+            .begin_frame(&mut self.target)
+            .unwrap_or_else(|e| {
+                log::error!("skipping headless frame {}: {e}", self.timer.frame);
+                None
+            });
+        let app = AppControl {
+            window: None,
+            requested_size: None,
+            requested_vsync: None,
+            exit_code: None,
+            redraw_in: None,
+            #[cfg(feature = "dialogs")]
+            dialogs: None,
+        };
+        let frame_input = FrameInput {
+            input: &self.input,
+            timer: &self.timer,
+            events: Vec::new(),
+            resized: self.resized.take(),
+        };
+        let (drawn, requests) = self.state.run(
+            &mut self.update,
+            &mut self.renderer,
+            &self.target,
+            frame,
+            app,
+            frame_input,
+        );
+        let (batches, skipped_draws) = drawn.unwrap_or_default();
+
+        let (device, queue) = (self.renderer.device(), self.renderer.queue());
+        let pixels =
+            (self.capture && drawn.is_some()).then(|| self.target.read_pixels(device, queue));
+
+        if let Some((rw, rh)) = requests.size {
+            self.target.resize(device, rw, rh);
+            let resize = Resize {
+                previous: (w, h),
+                size: self.target.size(),
+            };
+            if let Some(on_resize) = &mut self.on_resize
+                && resize.previous != resize.size
+            {
+                on_resize(resize, &mut self.state.camera);
+            }
+            self.resized = merge_resizes(self.resized, resize);
+        }
+
+        let summary = FrameSummary {
+            rendered: drawn.is_some(),
+            batches,
+            skipped_draws,
+            profile: self.state.profiler.report(),
+        };
+        self.hooks.after(&self.input, &self.timer, &summary);
+
+        FrameOutput {
+            frame: self.timer.frame,
+            size: (w, h),
+            pixels,
+            exit_code: requests.exit_code,
+        }
+    }
+
+    /// Run one step per snapshot, e.g. to replay recorded input.
+    /// Stops early at a step that requests an exit.
+    /// Returns the output of the last step run, if any
+    pub fn replay<'s>(
+        &mut self,
+        inputs: impl IntoIterator<Item = &'s InputSnapshot>,
+    ) -> Option<FrameOutput> {
+        let mut last = None;
+        for input in inputs {
+            let output = self.step(input);
+            let exit = output.exit_code.is_some();
+            last = Some(output);
+            if exit {
+                break;
+            }
+        }
+        last
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{camera::Camera, color::Color, math::Rect};
+    use glam::vec2;
+    use std::{
+        cell::{Cell, RefCell},
+        rc::Rc,
+    };
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn replay_stops_at_requested_exit() {
+        let mut app = HeadlessApp::new(8, 8, |ctx| {
+            if ctx.timer.frame == 3 {
+                ctx.app.request_exit_with_code(2);
+            }
+        })
+        .capture(false);
+        let inputs = vec![InputSnapshot::default(); 10];
+        let output = app.replay(&inputs).unwrap();
+        assert_eq!((output.frame, output.exit_code), (3, Some(2)));
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn frame_hooks_wrap_the_update_in_order() {
+        let markers = Rc::new(RefCell::new(Vec::new()));
+        let marker = |name: &'static str| {
+            let markers = markers.clone();
+            move || markers.borrow_mut().push(name)
+        };
+        let (update, net_in, input_in) = (marker("update"), marker("net in"), marker("input in"));
+        let (net_out, stats) = (marker("net out"), marker("stats"));
+        let drawn = Rc::new(Cell::new(0));
+        let batches = drawn.clone();
+        let mut app = HeadlessApp::new(8, 8, move |ctx| {
+            ctx.gfx.rect();
+            update();
+        })
+        .capture(false)
+        .before_frame(move |_, _| net_in())
+        .before_frame(move |_, _| input_in())
+        .after_frame(move |_, _, _| net_out())
+        .after_frame(move |_, timer, summary| {
+            assert!(summary.rendered);
+            batches.set(summary.batches);
+            assert_eq!(timer.frame, 0);
+            stats();
+        });
+
+        app.step(&InputSnapshot::default());
+        assert_eq!(
+            *markers.borrow(),
+            ["net in", "input in", "update", "net out", "stats"]
+        );
+        assert_eq!(drawn.get(), 1);
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn invisible_frames_issue_no_draws() {
+        let counts = Rc::new(Cell::new((usize::MAX, 0)));
+        let summary_counts = counts.clone();
+        let mut app = HeadlessApp::new(8, 8, |ctx| {
+            ctx.gfx.rect().color(Color::TRANSPARENT);
+            ctx.gfx
+                .polygon()
+                .radius(4.0)
+                .color(Color::WHITE.with_alpha(0.0));
+            ctx.gfx.text("");
+        })
+        .capture(false)
+        .after_frame(move |_, _, summary| {
+            summary_counts.set((summary.batches, summary.skipped_draws));
+        });
+
+        app.step(&InputSnapshot::default());
+        assert_eq!(counts.get(), (0, 3));
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn pixelated_edge_blocks_keep_their_own_color() {
+        // 10 pixels wide, so 4 pixel blocks leave a 2 pixel block at the right edge
+        let mut app = HeadlessApp::new(10, 4, |ctx| {
+            ctx.gfx.clear(Color::BLACK);
+            ctx.gfx
+                .rect()
+                .at(vec2(8.0, 0.0))
+                .size(vec2(2.0, 4.0))
+                .color(Color::RED);
+            ctx.gfx.post_effects().pixelate(4.0);
+        });
+
+        let pixels = app.step(&InputSnapshot::default()).pixels.unwrap();
+        let pixel = |x: usize, y: usize| &pixels[(y * 10 + x) * 4..][..4];
+        for y in 0..4 {
+            assert_eq!(pixel(7, y), [0, 0, 0, 255]);
+            assert_eq!(pixel(8, y), [255, 0, 0, 255]);
+            assert_eq!(pixel(9, y), [255, 0, 0, 255]);
+        }
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn flushed_draws_stay_out_of_later_layers() {
+        let flushed = Rc::new(RefCell::new(None));
+        let image = flushed.clone();
+        let mut app = HeadlessApp::new(8, 8, move |ctx| {
+            let mut world = ctx.gfx.create_offscreen(8, 8);
+            ctx.gfx.rect().size(Vec2::splat(8.0)).color(Color::RED);
+            ctx.gfx.flush_to_target(&mut world);
+            ctx.gfx.rect().size(vec2(4.0, 8.0)).color(Color::GREEN);
+            *image.borrow_mut() = ctx.gfx.read_offscreen(&world);
+        });
+
+        let pixels = app.step(&InputSnapshot::default()).pixels.unwrap();
+        let pixel = |x: usize, y: usize| &pixels[(y * 8 + x) * 4..][..4];
+        assert_eq!(pixel(1, 4), [0, 255, 0, 255]);
+        assert_eq!(pixel(6, 4)[..3], [0, 0, 0]);
+
+        let world = flushed.borrow_mut().take().unwrap();
+        assert!(world.pixels().all(|p| p.0 == [255, 0, 0, 255]));
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn concave_polygons_cover_their_area() {
+        let mut app = HeadlessApp::new(16, 16, |ctx| {
+            let l_shape = [
+                (16.0, 0.0),
+                (16.0, 8.0),
+                (8.0, 8.0),
+                (8.0, 16.0),
+                (0.0, 16.0),
+            ];
+            let points: Vec<_> = l_shape.into_iter().map(Vec2::from).collect();
+            ctx.gfx
+                .polygon()
+                .points_iter(points.into_iter().chain([Vec2::ZERO]))
+                .color(Color::RED);
+        });
+
+        let pixels = app.step(&InputSnapshot::default()).pixels.unwrap();
+        let covered = pixels.chunks(4).filter(|p| p[0] == 255).count();
+        // 16x16 minus the 8x8 notch
+        assert_eq!(covered, 192);
+        assert_eq!(pixels[(12 * 16 + 12) * 4], 0);
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn interleaved_textures_keep_draw_order() {
+        let textures = Rc::new(Cell::new((0, 0)));
+        let ids = textures.clone();
+        let mut app = HeadlessApp::new(8, 8, move |ctx| {
+            let (red, green) = ids.get();
+            let full = Vec2::splat(8.0);
+            ctx.gfx.rect().size(full).texture(red);
+            ctx.gfx.rect().size(full).texture(green);
+            // reuses the first texture but must still land on top of the green rect
+            ctx.gfx.rect().size(vec2(4.0, 8.0)).texture(red);
+        });
+        let red = app.renderer().add_texture_raw(1, 1, &[255, 0, 0, 255]);
+        let green = app.renderer().add_texture_raw(1, 1, &[0, 255, 0, 255]);
+        textures.set((red, green));
+
+        let pixels = app.step(&InputSnapshot::default()).pixels.unwrap();
+        let pixel = |x: usize, y: usize| &pixels[(y * 8 + x) * 4..][..4];
+        assert_eq!(pixel(1, 4), [255, 0, 0, 255]);
+        assert_eq!(pixel(6, 4), [0, 255, 0, 255]);
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn viewports_draw_their_own_camera() {
+        let mut app = HeadlessApp::new(8, 8, |ctx| {
+            let gfx = &mut ctx.gfx;
+            gfx.clear(Color::BLACK);
+            let half = vec2(4.0, 8.0);
+            let mut far = Camera::default();
+            far.target(vec2(1000.0, 0.0));
+            for (x, camera) in [(0.0, Camera::default()), (4.0, far)] {
+                gfx.viewport(Rect::new(vec2(x, 0.0), half), camera, |gfx| {
+                    assert_eq!(gfx.screen_size(), half);
+                    gfx.rect().size(Vec2::splat(8.0)).color(Color::RED);
+                });
+            }
+        });
+        let pixels = app.step(&InputSnapshot::default()).pixels.unwrap();
+        let pixel = |x: usize, y: usize| &pixels[(y * 8 + x) * 4..][..4];
+        assert_eq!(pixel(1, 4), [255, 0, 0, 255]);
+        // the right camera looks elsewhere & the left rect is clipped to its half
+        assert_eq!(pixel(6, 4), [0, 0, 0, 255]);
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn offscreen_readback_handles_unaligned_rows() {
+        let images = Rc::new(RefCell::new(Vec::new()));
+        let read = images.clone();
+        let mut app = HeadlessApp::new(8, 8, move |ctx| {
+            let gfx = &mut ctx.gfx;
+            // 300 pixels wide isn't a multiple of the 256 byte row alignment
+            let mut target = gfx.create_offscreen(300, 200);
+            gfx.render_offscreen(&mut target, |gfx| {
+                gfx.clear(Color::BLUE);
+                gfx.rect().size(vec2(150.0, 100.0)).color(Color::RED);
+                gfx.rect()
+                    .at(vec2(150.0, 0.0))
+                    .size(vec2(150.0, 100.0))
+                    .color(Color::GREEN);
+            });
+            let image = gfx.read_offscreen(&target).unwrap();
+            let async_image = pollster::block_on(gfx.read_offscreen_async(&target));
+            read.borrow_mut().extend([image, async_image.unwrap()]);
+        })
+        .capture(false);
+        app.step(&InputSnapshot::default());
+
+        for image in images.borrow().iter() {
+            assert_eq!(image.dimensions(), (300, 200));
+            assert_eq!(image.get_pixel(0, 0).0, [255, 0, 0, 255]);
+            assert_eq!(image.get_pixel(149, 99).0, [255, 0, 0, 255]);
+            assert_eq!(image.get_pixel(150, 0).0, [0, 255, 0, 255]);
+            assert_eq!(image.get_pixel(299, 99).0, [0, 255, 0, 255]);
+            assert_eq!(image.get_pixel(0, 100).0, [0, 0, 255, 255]);
+            assert_eq!(image.get_pixel(299, 199).0, [0, 0, 255, 255]);
+        }
+    }
+
+    /// Width & height of the lit pixels in tightly packed RGBA rows `width` pixels wide
+    fn lit_extent(pixels: &[u8], width: usize) -> (usize, usize) {
+        let (mut min, mut max) = ((usize::MAX, usize::MAX), (0, 0));
+        for (i, pixel) in pixels.chunks_exact(4).enumerate() {
+            if pixel[0] > 128 {
+                let (x, y) = (i % width, i / width);
+                min = (min.0.min(x), min.1.min(y));
+                max = (max.0.max(x), max.1.max(y));
+            }
+        }
+        (max.0 + 1 - min.0, max.1 + 1 - min.1)
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn text_keeps_its_shape_after_a_resize() {
+        let mut app = HeadlessApp::new(800, 600, |ctx| {
+            ctx.gfx.clear(Color::BLACK);
+            ctx.gfx
+                .text("H")
+                .at(vec2(20.0, 20.0))
+                .size(64.0)
+                .color(Color::WHITE);
+            if ctx.timer.frame == 0 {
+                ctx.app.set_size(1600, 600);
+            }
+        });
+
+        let before = app.step(&InputSnapshot::default());
+        let after = app.step(&InputSnapshot::default());
+        assert_eq!(after.size, (1600, 600));
+        let before = lit_extent(&before.pixels.unwrap(), 800);
+        let after = lit_extent(&after.pixels.unwrap(), 1600);
+        // drawn for a stale viewport, the glyph would come out twice as wide
+        assert!(before.0 > 10 && before.1 > 10);
+        assert!(after.0.abs_diff(before.0) <= 1, "{before:?} -> {after:?}");
+        assert!(after.1.abs_diff(before.1) <= 1, "{before:?} -> {after:?}");
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn background_passes_draw_under_the_scene() {
+        use egor_render::wgpu::{
+            self, LoadOp, Operations, RenderPassColorAttachment, RenderPassDescriptor, StoreOp,
+        };
+
+        let mut app = HeadlessApp::new(4, 4, |ctx| {
+            ctx.gfx.rect().size(vec2(2.0, 4.0)).color(Color::BLUE);
+        })
+        .background_pass(|_, _, encoder, view, _| {
+            encoder.begin_render_pass(&RenderPassDescriptor {
+                label: None,
+                color_attachments: &[Some(RenderPassColorAttachment {
+                    view,
+                    resolve_target: None,
+                    ops: Operations {
+                        load: LoadOp::Clear(wgpu::Color::RED),
+                        store: StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+        });
+
+        let pixels = app.step(&InputSnapshot::default()).pixels.unwrap();
+        assert_eq!(pixels[..4], [0, 0, 255, 255]);
+        assert_eq!(pixels[12..16], [255, 0, 0, 255]);
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn resize_handlers_see_the_first_size_and_requested_ones() {
+        let seen = Rc::new(RefCell::new(Vec::new()));
+        let handler_seen = seen.clone();
+        let resized = Rc::new(Cell::new(None));
+        let frame_resized = resized.clone();
+        let mut app = HeadlessApp::new(8, 8, move |ctx| {
+            frame_resized.set(ctx.resized());
+            if ctx.timer.frame == 0 {
+                ctx.app.set_size(16, 8);
+            }
+        })
+        .capture(false)
+        .on_resize(move |resize, _| handler_seen.borrow_mut().push(resize));
+
+        app.step(&InputSnapshot::default());
+        app.step(&InputSnapshot::default());
+        let grown = Resize {
+            previous: (8, 8),
+            size: (16, 8),
+        };
+        let first = Resize {
+            previous: (8, 8),
+            size: (8, 8),
+        };
+        assert_eq!(*seen.borrow(), [first, grown]);
+        assert_eq!(resized.get(), Some(grown));
+    }
+
+    /// Run with `cargo test -p egor_glue --release -- --ignored --nocapture bench_`
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn bench_texture_region_uploads() {
+        use std::time::Instant;
+
+        const SIZE: u32 = 2048;
+        const REGION: u32 = 64;
+        let mut app = HeadlessApp::new(8, 8, |_| {}).capture(false);
+        let full = vec![128; (SIZE * SIZE * 4) as usize];
+        let region = vec![255; (REGION * REGION * 4) as usize];
+        let id = app.renderer().add_texture_raw(SIZE, SIZE, &full);
+        for regional in [false, true] {
+            let start = Instant::now();
+            for frame in 0..60 {
+                let renderer = app.renderer();
+                match regional {
+                    true => {
+                        let x = frame * REGION % SIZE;
+                        let updated =
+                            renderer.update_texture_region(id, x, 0, REGION, REGION, &region);
+                        updated.unwrap();
+                    }
+                    false => renderer.update_texture_raw(id, SIZE, SIZE, &full),
+                }
+                app.step(&InputSnapshot::default());
+            }
+            let device = app.renderer().device();
+            let _ = device.poll(egor_render::wgpu::PollType::Wait);
+            let mode = if regional { "region" } else { "full" };
+            println!("{mode}: {:.2?} per frame", start.elapsed() / 60);
+        }
+    }
+}
@@ -0,0 +1,142 @@
+use crate::camera::Camera;
+
+/// A named group of draw calls, drawn in a declared order
+///
+/// Configure layers through [`crate::graphics::Graphics::layer`] & draw into them with
+/// [`crate::graphics::Graphics::on_layer`]. Settings persist across frames
+pub struct Layer {
+    name: String,
+    enabled: bool,
+    screen_space: bool,
+    camera: Option<Camera>,
+    sort_by_y: bool,
+    sort_key: (u8, usize),
+    pub(crate) camera_slot: usize,
+}
+
+impl Layer {
+    /// Name of the layer everything is drawn to outside of `on_layer`
+    pub const DEFAULT: &'static str = "default";
+
+    pub(crate) fn new(name: &str) -> Self {
+        Self {
+            name: name.into(),
+            enabled: true,
+            screen_space: false,
+            camera: None,
+            sort_by_y: false,
+            sort_key: (0, 0),
+            camera_slot: 0,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Show or hide everything drawn to this layer (defaults to true)
+    pub fn enabled(&mut self, enabled: bool) -> &mut Self {
+        self.enabled = enabled;
+        self
+    }
+
+    /// Draw this layer in screen pixels with `(0, 0)` at the top-left, ignoring the camera.
+    /// Takes precedence over [`Self::camera`]
+    pub fn screen_space(&mut self, screen_space: bool) -> &mut Self {
+        self.screen_space = screen_space;
+        self
+    }
+
+    /// Draw this layer with its own camera instead of the main one. `None` restores the main camera
+    pub fn camera(&mut self, camera: Option<Camera>) -> &mut Self {
+        self.camera = camera;
+        self
+    }
+
+    /// Draw shapes in this layer in order of their bottom edge instead of the order they were
+    /// drawn in (defaults to false), see [`crate::graphics::Graphics::on_layer_sorted_y`]
+    pub fn sort_by_y(&mut self, sort: bool) -> &mut Self {
+        self.sort_by_y = sort;
+        self
+    }
+
+    pub fn sorts_by_y(&self) -> bool {
+        self.sort_by_y
+    }
+
+    /// The camera this layer overrides the main camera with, if any
+    pub(crate) fn camera_override(&self) -> Option<Camera> {
+        match self.screen_space {
+            true => Some(Camera::default()),
+            false => self.camera,
+        }
+    }
+}
+
+/// Named layers & their draw order. Index 0 is always the default layer
+pub(crate) struct Layers {
+    layers: Vec<Layer>,
+    order: Vec<String>,
+}
+
+impl Default for Layers {
+    fn default() -> Self {
+        Self {
+            layers: vec![Layer::new(Layer::DEFAULT)],
+            order: Vec::new(),
+        }
+    }
+}
+
+impl Layers {
+    /// Returns the index of the layer called `name`, creating it if needed
+    pub fn index(&mut self, name: &str) -> usize {
+        if let Some(i) = self.layers.iter().position(|l| l.name == name) {
+            return i;
+        }
+        self.layers.push(Layer::new(name));
+        self.update_sort_keys();
+        self.layers.len() - 1
+    }
+
+    pub fn get(&self, index: usize) -> &Layer {
+        &self.layers[index]
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> &mut Layer {
+        &mut self.layers[index]
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut Layer> {
+        self.layers.iter_mut()
+    }
+
+    /// Layers in `names` are drawn in that order. The default layer is drawn first unless
+    /// listed; other unlisted layers are drawn last, in the order they were created
+    pub fn set_order(&mut self, names: &[&str]) {
+        self.order = names.iter().map(|n| n.to_string()).collect();
+        for name in names {
+            self.index(name);
+        }
+        self.update_sort_keys();
+    }
+
+    /// Sort key of a layer, lower is drawn first
+    pub fn sort_key(&self, index: usize) -> (u8, usize) {
+        self.layers[index].sort_key
+    }
+
+    fn update_sort_keys(&mut self) {
+        for (i, layer) in self.layers.iter_mut().enumerate() {
+            layer.sort_key = match self.order.iter().position(|n| *n == layer.name) {
+                Some(pos) => (1, pos),
+                None if i == 0 => (0, 0),
+                None => (2, i),
+            };
+        }
+    }
+}
@@ -1,10 +1,42 @@
+pub mod alloc_guard;
 pub mod app;
+pub mod atlas;
+pub mod bitmap_font;
 pub mod camera;
+pub mod capture;
 pub mod color;
+#[cfg(feature = "serde")]
+pub mod config;
+pub mod coords;
+#[cfg(feature = "ui")]
+pub mod dev;
+mod frame;
+mod frame_graph;
+pub mod gizmos;
 pub mod graphics;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod headless;
+pub mod layer;
 pub mod math;
+mod memory_budget;
+pub mod mesh;
+mod perf_warnings;
+pub mod physics;
+mod picking;
+pub mod post;
 pub mod primitives;
+pub mod profiler;
+pub mod random;
+#[cfg(all(feature = "render_thread", not(target_arch = "wasm32")))]
+mod render_thread;
+#[cfg(all(feature = "snapshot", not(target_arch = "wasm32")))]
+mod snapshot;
+mod static_geometry;
 pub mod text;
+pub mod text_edit;
+pub mod touch_controls;
+pub mod tween;
+pub mod widgets;
 
 #[cfg(feature = "ui")]
 pub mod ui;
@@ -1,4 +1,4 @@
-pub use glam::{IVec2, Mat2, Mat4, Vec2, ivec2, vec2};
+pub use glam::{Affine2, IVec2, Mat2, Mat4, Vec2, ivec2, vec2};
 
 /// Axis-aligned rectangle defined by position (top-left corner) & size
 #[derive(Copy, Clone, PartialEq, Debug)]
@@ -48,6 +48,63 @@ impl Rect {
     }
 }
 
+/// Scale, then rotation (radians, clockwise on screen), then translation, e.g. of a parent
+/// part that everything drawn in [`crate::graphics::Graphics::with_transform`] hangs off
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Transform {
+    pub translation: Vec2,
+    pub rotation: f32,
+    pub scale: Vec2,
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl Transform {
+    /// Leaves everything where it is
+    pub const IDENTITY: Self = Self {
+        translation: Vec2::ZERO,
+        rotation: 0.0,
+        scale: Vec2::ONE,
+    };
+
+    /// Moves by `translation`
+    pub fn from_translation(translation: Vec2) -> Self {
+        Self {
+            translation,
+            ..Self::IDENTITY
+        }
+    }
+
+    /// Rotates by `rotation` radians around the origin
+    pub fn from_rotation(rotation: f32) -> Self {
+        Self {
+            rotation,
+            ..Self::IDENTITY
+        }
+    }
+
+    /// Also rotates by `rotation` radians, before translating
+    pub fn rotated(mut self, rotation: f32) -> Self {
+        self.rotation += rotation;
+        self
+    }
+
+    /// Also scales by `scale`, before rotating & translating
+    pub fn scaled(mut self, scale: Vec2) -> Self {
+        self.scale *= scale;
+        self
+    }
+
+    /// The same transform as a matrix
+    pub fn to_affine(self) -> Affine2 {
+        Affine2::from_scale_angle_translation(self.scale, self.rotation, self.translation)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,78 @@
+//! A soft budget on texture memory, see [`crate::app::App::texture_memory_budget`]
+
+use egor_render::{MemoryReport, Renderer};
+
+use crate::graphics::TextureId;
+
+#[cfg(not(all(feature = "render_thread", not(target_arch = "wasm32"))))]
+pub(crate) type MemoryBudgetFn = dyn FnMut(&MemoryReport<TextureId>);
+// the budget travels with the frame state to the render thread
+#[cfg(all(feature = "render_thread", not(target_arch = "wasm32")))]
+pub(crate) type MemoryBudgetFn = dyn FnMut(&MemoryReport<TextureId>) + Send;
+
+fn mib(bytes: u64) -> f32 {
+    bytes as f32 / (1 << 20) as f32
+}
+
+/// Reports texture memory going over budget once per crossing, checked after every frame
+#[derive(Default)]
+pub(crate) struct MemoryBudget {
+    pub bytes: Option<u64>,
+    pub on_exceeded: Option<Box<MemoryBudgetFn>>,
+    /// Whether the last check was over budget, so staying over isn't reported every frame
+    exceeded: bool,
+}
+
+impl MemoryBudget {
+    pub fn check(&mut self, renderer: &Renderer) {
+        let Some(budget) = self.bytes else {
+            return;
+        };
+        if !self.crossed(renderer.texture_memory(), budget) {
+            return;
+        }
+        let report = renderer.memory_report().map_textures(TextureId);
+        match &mut self.on_exceeded {
+            Some(on_exceeded) => on_exceeded(&report),
+            None => {
+                let largest: Vec<_> = report
+                    .largest
+                    .iter()
+                    .take(3)
+                    .map(|(id, bytes)| format!("texture {} ({:.1} MiB)", id.0, mib(*bytes)))
+                    .collect();
+                log::warn!(
+                    target: "egor_glue::memory",
+                    "{:.1} MiB of textures exceed the {:.1} MiB budget, the largest being {}; \
+                     unload unused ones with `unload_texture`",
+                    mib(report.textures_bytes),
+                    mib(budget),
+                    largest.join(", ")
+                );
+            }
+        }
+    }
+
+    /// Whether `used` just went over `budget`. Dropping back under re-arms the report
+    fn crossed(&mut self, used: u64, budget: u64) -> bool {
+        let over = used > budget;
+        let crossed = over && !self.exceeded;
+        self.exceeded = over;
+        crossed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn going_over_budget_is_reported_once_per_crossing() {
+        let mut budget = MemoryBudget::default();
+        let crossings: Vec<_> = [10, 120, 150, 90, 101, 101]
+            .into_iter()
+            .map(|used| budget.crossed(used, 100))
+            .collect();
+        assert_eq!(crossings, [false, true, false, false, true, false]);
+    }
+}
@@ -0,0 +1,512 @@
+use egor_render::{instance::Instance, vertex::Vertex};
+use glam::{Mat2, Vec2};
+use std::cell::RefCell;
+
+use crate::{
+    alloc_guard,
+    color::Color,
+    primitives::{
+        PathBuilder, PolygonBuilder, PolylineBuilder, PrimitiveBatch, debug_assert_finite,
+    },
+};
+
+/// Collects shapes into a single mesh. Passed to [`crate::graphics::Graphics::bake_mesh`]
+///
+/// Shapes are baked in the mesh's local space, so `(0, 0)` is the point placed by
+/// [`MeshBuilder::at`] & rotated around by [`MeshBuilder::rotate`]
+pub struct MeshBaker {
+    batch: PrimitiveBatch,
+}
+
+impl MeshBaker {
+    pub(crate) fn new() -> Self {
+        Self {
+            batch: PrimitiveBatch::default(),
+        }
+    }
+
+    /// Add a polygon to the mesh, see [`crate::graphics::Graphics::polygon`]
+    pub fn polygon(&mut self) -> PolygonBuilder<'_> {
+        PolygonBuilder::new(&mut self.batch, None)
+    }
+
+    /// Add a polyline to the mesh, see [`crate::graphics::Graphics::polyline`]
+    pub fn polyline(&mut self) -> PolylineBuilder<'_> {
+        PolylineBuilder::new(&mut self.batch, None)
+    }
+
+    /// Add a vector path to the mesh, see [`crate::graphics::Graphics::path`]
+    pub fn path(&mut self) -> PathBuilder<'_> {
+        PathBuilder::new(&mut self.batch, None)
+    }
+
+    /// Merges everything added into one vertex & index list. Meshes use `u16` indices,
+    /// so shapes past the first `u16::MAX` vertices are left out
+    pub(crate) fn finish(mut self) -> (Vec<Vertex>, Vec<u16>) {
+        let (mut vertices, mut indices) = (Vec::new(), Vec::new());
+        for (.., geometry) in self.batch.take() {
+            if vertices.len() + geometry.vertex_count() > u16::MAX as usize {
+                break;
+            }
+            let base = vertices.len() as u16;
+            vertices.extend_from_slice(geometry.vertices());
+            indices.extend(geometry.indices().iter().map(|i| base + i));
+        }
+        (vertices, indices)
+    }
+}
+
+/// Builder for one instance of a baked mesh, drawn on `Drop`.
+/// Created by [`crate::graphics::Graphics::mesh`]
+pub struct MeshBuilder<'a> {
+    batch: &'a mut PrimitiveBatch,
+    shader_id: Option<usize>,
+    mesh: usize,
+    position: Vec2,
+    rotation: f32,
+    scale: Vec2,
+    color: Color,
+    params: [f32; 4],
+}
+
+impl<'a> MeshBuilder<'a> {
+    pub(crate) fn new(
+        batch: &'a mut PrimitiveBatch,
+        shader_id: Option<usize>,
+        mesh: usize,
+    ) -> Self {
+        Self {
+            batch,
+            shader_id,
+            mesh,
+            position: Vec2::ZERO,
+            rotation: 0.0,
+            scale: Vec2::ONE,
+            color: Color::WHITE,
+            params: [0.0; 4],
+        }
+    }
+
+    pub fn at(mut self, position: impl Into<Vec2>) -> Self {
+        self.position = position.into();
+        self
+    }
+
+    /// Rotation in radians around the mesh origin, from +X toward +Y (clockwise on screen)
+    pub fn rotate(mut self, angle: f32) -> Self {
+        self.rotation = angle;
+        self
+    }
+    /// Same as [`Self::rotate`] in degrees
+    pub fn rotate_deg(self, degrees: f32) -> Self {
+        self.rotate(degrees.to_radians())
+    }
+
+    pub fn scale(mut self, scale: Vec2) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    /// Moves the mesh's place in a layer sorted by y, where it's sorted by its position
+    /// rather than its bottom edge, see [`crate::primitives::RectangleBuilder::sort_bias`]
+    pub fn sort_bias(self, bias: f32) -> Self {
+        self.batch.sort_bias = bias;
+        self
+    }
+
+    /// Multiplies the colors the mesh was baked with
+    pub fn color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Four numbers for a custom shader to read as `input.params`, per instance like
+    /// [`crate::primitives::RectangleBuilder::params`]
+    pub fn params(mut self, params: [f32; 4]) -> Self {
+        self.params = params;
+        self
+    }
+}
+
+impl MeshBuilder<'_> {
+    fn submit(&mut self) {
+        let _guard = alloc_guard::scope();
+        if self.batch.skips(self.color, None, self.shader_id)
+            || self.batch.skips_area("mesh", self.scale.x * self.scale.y)
+        {
+            return;
+        }
+        let rot = Mat2::from_angle(self.rotation);
+        let (col0, col1) = (rot.x_axis * self.scale.x, rot.y_axis * self.scale.y);
+        // baked shapes have zero texture coords, so the instance UVs pick the solid texel
+        let (tex_id, [u, v]) = self.batch.solid_texture();
+        let instance = Instance::new(
+            [col0.x, col0.y, col1.x, col1.y],
+            self.position.to_array(),
+            self.batch.tinted(self.color),
+            [u, v, u, v],
+        )
+        .with_params(self.params);
+        debug_assert_finite("mesh", instance.affine.iter().chain(&instance.translate));
+        self.batch
+            .push_mesh_instance(instance, Some(self.mesh), tex_id, self.shader_id);
+    }
+}
+
+impl Drop for MeshBuilder<'_> {
+    fn drop(&mut self) {
+        self.submit();
+        self.batch.close_draw();
+    }
+}
+
+/// Marks vertices not yet copied into the current batch while a raw mesh spills
+const UNMAPPED: u16 = u16::MAX;
+
+thread_local! {
+    /// Batch-local index of each raw mesh vertex while it spills over several batches,
+    /// reused so steady frames don't allocate
+    static REMAP_SCRATCH: RefCell<Vec<u16>> = const { RefCell::new(Vec::new()) };
+}
+
+/// One vertex of a mesh drawn with [`crate::graphics::Graphics::mesh_raw`]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MeshVertex {
+    /// Position in world space, or screen space in screen-space layers
+    pub pos: Vec2,
+    /// Texture coordinates, `(0, 0)` top-left to `(1, 1)` bottom-right
+    pub uv: Vec2,
+    pub color: Color,
+}
+
+impl MeshVertex {
+    pub fn new(pos: Vec2, uv: Vec2, color: Color) -> Self {
+        Self { pos, uv, color }
+    }
+}
+
+/// Builder for a mesh of caller-provided vertices, drawn on `Drop` like any other shape.
+/// Created by [`crate::graphics::Graphics::mesh_raw`]
+///
+/// Without [`Self::indices`] every three vertices make a triangle. Meshes too large for
+/// one batch are split by triangle over as many batches as they need
+pub struct RawMeshBuilder<'a> {
+    batch: &'a mut PrimitiveBatch,
+    shader_id: Option<usize>,
+    texture_id: Option<usize>,
+    vertices: &'a [MeshVertex],
+    indices: Option<&'a [u16]>,
+}
+
+impl<'a> RawMeshBuilder<'a> {
+    pub(crate) fn new(
+        batch: &'a mut PrimitiveBatch,
+        shader_id: Option<usize>,
+        texture_id: Option<usize>,
+    ) -> Self {
+        Self {
+            batch,
+            shader_id,
+            texture_id,
+            vertices: &[],
+            indices: None,
+        }
+    }
+
+    pub fn vertices(mut self, vertices: &'a [MeshVertex]) -> Self {
+        self.vertices = vertices;
+        self
+    }
+
+    /// Triangle list indexing [`Self::vertices`]. Meshes with a count that isn't a multiple
+    /// of 3 or an index past the last vertex aren't drawn, with a warning in debug builds
+    pub fn indices(mut self, indices: &'a [u16]) -> Self {
+        self.indices = Some(indices);
+        self
+    }
+
+    /// Moves the mesh's place in a layer sorted by y, see
+    /// [`crate::primitives::RectangleBuilder::sort_bias`]
+    pub fn sort_bias(self, bias: f32) -> Self {
+        self.batch.sort_bias = bias;
+        self
+    }
+
+    /// Draw with a custom shader instead of the one set by
+    /// [`crate::graphics::Graphics::with_shader`]
+    pub fn shader(mut self, shader_id: usize) -> Self {
+        self.shader_id = Some(shader_id);
+        self
+    }
+
+    fn index_count(&self) -> usize {
+        self.indices.map_or(self.vertices.len(), <[u16]>::len)
+    }
+
+    /// Reports why the mesh can't be drawn, if it can't
+    fn validate(&mut self) -> bool {
+        let (count, vertices) = (self.index_count(), self.vertices.len());
+        let check = &mut self.batch.texture_check;
+        if count % 3 != 0 {
+            check.report_invalid_mesh(format_args!("{count} indices don't make whole triangles"));
+            return false;
+        }
+        let past = self
+            .indices
+            .and_then(|indices| indices.iter().find(|&&i| i as usize >= vertices));
+        if let Some(index) = past {
+            check.report_invalid_mesh(format_args!(
+                "index {index} is past its {vertices} vertices"
+            ));
+            return false;
+        }
+        true
+    }
+
+    /// Copies every vertex & index into one batch allocation
+    fn push_whole(&mut self, texture_id: Option<usize>, vertex: impl Fn(&MeshVertex) -> Vertex) {
+        let count = self.index_count();
+        let Some((verts, indices, base)) =
+            self.batch
+                .allocate(self.vertices.len(), count, texture_id, self.shader_id)
+        else {
+            return;
+        };
+        for (out, v) in verts.iter_mut().zip(self.vertices) {
+            *out = vertex(v);
+        }
+        for (i, out) in indices.iter_mut().enumerate() {
+            *out = base + self.indices.map_or(i as u16, |indices| indices[i]);
+        }
+    }
+
+    /// Copies the mesh a run of triangles at a time, each run taking as many triangles as
+    /// fit in an empty batch along with the vertices they use
+    fn spill(
+        &mut self,
+        texture_id: Option<usize>,
+        (max_vertices, max_indices): (usize, usize),
+        vertex: impl Fn(&MeshVertex) -> Vertex,
+    ) {
+        let max_vertices = max_vertices.min(UNMAPPED as usize);
+        let triangles = self.index_count() / 3;
+        let (vertices, indices) = (self.vertices, self.indices);
+        let index = |i: usize| indices.map_or(i, |indices| indices[i] as usize);
+        let corners = |t: usize| [index(t * 3), index(t * 3 + 1), index(t * 3 + 2)];
+        REMAP_SCRATCH.with_borrow_mut(|remap| {
+            remap.clear();
+            remap.resize(vertices.len(), UNMAPPED);
+            let mut start = 0;
+            while start < triangles {
+                // number the vertices of as many triangles as fit
+                let (mut end, mut used) = (start, 0);
+                while end < triangles && (end - start + 1) * 3 <= max_indices {
+                    let tri = corners(end);
+                    let new = (0..3)
+                        .filter(|&c| remap[tri[c]] == UNMAPPED && !tri[..c].contains(&tri[c]))
+                        .count();
+                    if used + new > max_vertices {
+                        break;
+                    }
+                    for i in tri {
+                        if remap[i] == UNMAPPED {
+                            remap[i] = used as u16;
+                            used += 1;
+                        }
+                    }
+                    end += 1;
+                }
+                if end == start {
+                    // batches too small for a single triangle
+                    return;
+                }
+
+                let run = start..end;
+                let allocation =
+                    self.batch
+                        .allocate(used, run.len() * 3, texture_id, self.shader_id);
+                if let Some((verts, indices, base)) = allocation {
+                    for (t, out) in run.clone().zip(indices.chunks_exact_mut(3)) {
+                        for (i, out) in corners(t).into_iter().zip(out) {
+                            verts[remap[i] as usize] = vertex(&vertices[i]);
+                            *out = base + remap[i];
+                        }
+                    }
+                }
+                for t in run {
+                    for i in corners(t) {
+                        remap[i] = UNMAPPED;
+                    }
+                }
+                start = end;
+            }
+        });
+    }
+}
+
+impl RawMeshBuilder<'_> {
+    fn submit(&mut self) {
+        if self.index_count() == 0 || !self.validate() {
+            return;
+        }
+        let _guard = alloc_guard::scope();
+        if let Some(id) = self.texture_id {
+            self.batch.texture_check.check(id, "raw mesh");
+        }
+        // untextured meshes sample the solid texel, like the other shapes
+        let (texture_id, solid_uv) = match self.texture_id {
+            Some(id) => (Some(id), None),
+            None => {
+                let (id, uv) = self.batch.solid_texture();
+                (id, Some(uv))
+            }
+        };
+        let tint = self.batch.tint();
+        let vertex = |v: &MeshVertex| {
+            let uv = solid_uv.unwrap_or(v.uv.to_array());
+            Vertex::new(v.pos.to_array(), v.color.multiply(tint).components(), uv)
+        };
+        debug_assert_finite(
+            "raw mesh",
+            self.vertices.iter().flat_map(|v| v.pos.as_ref()),
+        );
+
+        let (max_vertices, max_indices) = self.batch.limits();
+        let fits = self.vertices.len() <= max_vertices.min(UNMAPPED as usize);
+        if fits && self.index_count() <= max_indices {
+            self.push_whole(texture_id, vertex);
+        } else {
+            self.spill(texture_id, (max_vertices, max_indices), vertex);
+        }
+    }
+}
+
+impl Drop for RawMeshBuilder<'_> {
+    fn drop(&mut self) {
+        self.submit();
+        self.batch.close_draw();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glam::vec2;
+
+    #[test]
+    fn baking_merges_shapes_into_one_mesh() {
+        let mut baker = MeshBaker::new();
+        baker.polygon().radius(1.0).segments(6).color(Color::RED);
+        baker
+            .polyline()
+            .points(&[vec2(0.0, 0.0), vec2(4.0, 0.0)])
+            .thickness(1.0);
+        let (vertices, indices) = baker.finish();
+
+        assert!(!indices.is_empty());
+        assert!(indices.iter().all(|&i| (i as usize) < vertices.len()));
+        assert!(vertices.iter().any(|v| v.color == Color::RED.components()));
+    }
+
+    fn grid(columns: usize, rows: usize) -> (Vec<MeshVertex>, Vec<u16>) {
+        let vertices = (0..=rows)
+            .flat_map(|y| (0..=columns).map(move |x| vec2(x as f32, y as f32)))
+            .map(|p| MeshVertex::new(p, p, Color::WHITE))
+            .collect();
+        let corner = |x: usize, y: usize| (y * (columns + 1) + x) as u16;
+        let indices = (0..rows)
+            .flat_map(|y| (0..columns).map(move |x| (x, y)))
+            .flat_map(|(x, y)| {
+                let (a, b) = (corner(x, y), corner(x + 1, y));
+                let (c, d) = (corner(x, y + 1), corner(x + 1, y + 1));
+                [a, b, d, a, d, c]
+            })
+            .collect();
+        (vertices, indices)
+    }
+
+    /// Triangles drawn by every batch, as world positions
+    fn triangles(batch: &mut PrimitiveBatch) -> Vec<[[f32; 2]; 3]> {
+        let mut triangles = Vec::new();
+        for (.., g) in batch.iter_mut() {
+            let position = |i: u16| g.vertices()[i as usize].position;
+            for t in g.indices().chunks_exact(3) {
+                triangles.push([position(t[0]), position(t[1]), position(t[2])]);
+            }
+        }
+        triangles
+    }
+
+    #[test]
+    fn raw_meshes_batch_with_their_texture_and_tint() {
+        let mut batch = PrimitiveBatch::default();
+        batch.texture_check.loaded = 1;
+        let (vertices, indices) = grid(2, 1);
+        batch.set_tint(Color::RED);
+        RawMeshBuilder::new(&mut batch, None, Some(0))
+            .vertices(&vertices)
+            .indices(&indices);
+        RawMeshBuilder::new(&mut batch, None, Some(0)).vertices(&vertices[..3]);
+
+        let entries: Vec<_> = batch.iter_mut().map(|(tex, ..)| tex).collect();
+        assert_eq!(entries, [Some(0)]);
+        let (.., geometry) = batch.iter_mut().next().unwrap();
+        assert_eq!((geometry.vertex_count(), geometry.index_count()), (9, 15));
+        // the second mesh's indices start past the first's vertices
+        assert_eq!(&geometry.indices()[12..], [6, 7, 8]);
+        assert_eq!(geometry.vertices()[4].tex_coords, [1.0, 1.0]);
+        assert!(
+            geometry
+                .vertices()
+                .iter()
+                .all(|v| v.color == Color::RED.components())
+        );
+    }
+
+    #[test]
+    fn raw_meshes_with_bad_indices_are_skipped() {
+        let mut batch = PrimitiveBatch::default();
+        let (vertices, _) = grid(1, 1);
+        RawMeshBuilder::new(&mut batch, None, None)
+            .vertices(&vertices)
+            .indices(&[0, 1, 4]);
+        RawMeshBuilder::new(&mut batch, None, None)
+            .vertices(&vertices)
+            .indices(&[0, 1]);
+        assert!(batch.is_empty());
+    }
+
+    #[test]
+    fn raw_meshes_past_the_batch_limits_spill_by_triangle() {
+        let (vertices, indices) = grid(8, 8);
+        let mut whole = PrimitiveBatch::default();
+        RawMeshBuilder::new(&mut whole, None, None)
+            .vertices(&vertices)
+            .indices(&indices);
+        let mut split = PrimitiveBatch::new(16, 24);
+        RawMeshBuilder::new(&mut split, None, None)
+            .vertices(&vertices)
+            .indices(&indices);
+
+        let sizes: Vec<_> = split
+            .iter_mut()
+            .map(|(.., g)| (g.vertex_count(), g.index_count()))
+            .collect();
+        assert!(sizes.len() > 1);
+        assert!(sizes.iter().all(|&(v, i)| v <= 16 && i <= 24));
+        assert_eq!(triangles(&mut split), triangles(&mut whole));
+    }
+
+    #[test]
+    fn mesh_instances_batch_per_mesh() {
+        let mut batch = PrimitiveBatch::default();
+        for mesh in [0, 0, 1] {
+            MeshBuilder::new(&mut batch, None, mesh).at(vec2(1.0, 2.0));
+        }
+        let counts: Vec<_> = batch
+            .iter_mut()
+            .map(|(.., g)| (g.mesh(), g.instance_count()))
+            .collect();
+        assert_eq!(counts, [(Some(0), 2), (Some(1), 1)]);
+    }
+}
@@ -0,0 +1,348 @@
+use std::fmt;
+
+use egor_render::Renderer;
+
+use crate::primitives::PrimitiveBatch;
+
+/// Batches in a frame past which drawing is likely CPU bound on draw calls
+const MAX_BATCHES: usize = 500;
+/// Frames with fewer batches are cheap enough not to warn about their shape
+const MIN_BATCHES: usize = 64;
+/// Average shapes per batch below which batching isn't doing much
+const MIN_SHAPES_PER_BATCH: f32 = 4.0;
+/// Texture bytes uploaded in a frame past which uploads eat into the frame budget
+const MAX_UPLOAD_BYTES: usize = 8 << 20;
+/// Frames between two warnings of the same kind
+const WARN_INTERVAL: u64 = 600;
+
+/// A smell in how a frame was batched, see [`crate::app::App::performance_warnings`]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum PerfWarning {
+    TooManyBatches {
+        batches: usize,
+        /// Texture bound the most & how often
+        texture: (Option<usize>, usize),
+    },
+    SmallBatches {
+        batches: usize,
+        shapes: usize,
+    },
+    /// Switching to `texture` `binds` times for `shapes` shapes in all
+    TextureThrash {
+        texture: Option<usize>,
+        binds: usize,
+        shapes: usize,
+    },
+    ShaderThrash {
+        shader: Option<usize>,
+        binds: usize,
+        shapes: usize,
+    },
+    LargeUploads {
+        bytes: usize,
+        /// Texture with the most bytes uploaded & how many
+        texture: (usize, usize),
+    },
+}
+
+impl PerfWarning {
+    const KINDS: usize = 5;
+
+    fn kind(&self) -> usize {
+        match self {
+            Self::TooManyBatches { .. } => 0,
+            Self::SmallBatches { .. } => 1,
+            Self::TextureThrash { .. } => 2,
+            Self::ShaderThrash { .. } => 3,
+            Self::LargeUploads { .. } => 4,
+        }
+    }
+}
+
+struct Id(Option<usize>, &'static str);
+
+impl fmt::Display for Id {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.0 {
+            Some(id) => write!(f, "{} {id}", self.1),
+            None => write!(f, "the default {}", self.1),
+        }
+    }
+}
+
+fn mib(bytes: usize) -> f32 {
+    bytes as f32 / (1 << 20) as f32
+}
+
+impl fmt::Display for PerfWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Self::TooManyBatches {
+                batches,
+                texture: (texture, binds),
+            } => write!(
+                f,
+                "{batches} batches this frame, {binds} of them for {}; draw shapes sharing a \
+                 texture & shader together or use an atlas to merge them",
+                Id(texture, "texture")
+            ),
+            Self::SmallBatches { batches, shapes } => write!(
+                f,
+                "{shapes} shapes took {batches} batches ({:.1} per batch); shapes only batch \
+                 while consecutive ones share a texture, shader & layer",
+                shapes as f32 / batches as f32
+            ),
+            Self::TextureThrash {
+                texture,
+                binds,
+                shapes,
+            } => write!(
+                f,
+                "{} was bound {binds} times for {:.1} shapes each; consider an atlas or \
+                 drawing everything with it in one go",
+                Id(texture, "texture"),
+                shapes as f32 / binds as f32
+            ),
+            Self::ShaderThrash {
+                shader,
+                binds,
+                shapes,
+            } => write!(
+                f,
+                "{} was bound {binds} times for {:.1} shapes each; group shapes by shader \
+                 with `with_shader`",
+                Id(shader, "shader"),
+                shapes as f32 / binds as f32
+            ),
+            Self::LargeUploads {
+                bytes,
+                texture: (texture, texture_bytes),
+            } => write!(
+                f,
+                "{:.1} MiB of textures uploaded this frame, {:.1} MiB to texture {texture}; \
+                 update only changed regions with `update_texture_region`",
+                mib(bytes),
+                mib(texture_bytes)
+            ),
+        }
+    }
+}
+
+/// Binds of a texture or shader & the shapes drawn with it in a frame
+#[derive(Clone, Copy)]
+struct Binds {
+    id: Option<usize>,
+    binds: usize,
+    shapes: usize,
+}
+
+fn bind(binds: &mut Vec<Binds>, id: Option<usize>, shapes: usize) {
+    match binds.iter_mut().find(|b| b.id == id) {
+        Some(b) => {
+            b.binds += 1;
+            b.shapes += shapes;
+        }
+        None => binds.push(Binds {
+            id,
+            binds: 1,
+            shapes,
+        }),
+    }
+}
+
+fn most_bound(binds: &[Binds]) -> Binds {
+    // the first bound on ties
+    let most = binds.iter().copied();
+    most.reduce(|a, b| if b.binds > a.binds { b } else { a })
+        .unwrap()
+}
+
+/// Looks for batching smells at flush time & logs rate limited warnings about them.
+/// Does nothing but clear the renderer's upload counts while disabled
+#[derive(Default)]
+pub(crate) struct PerfWarnings {
+    pub enabled: bool,
+    frame: u64,
+    /// Frame each kind of warning was last logged on
+    last_warned: [Option<u64>; PerfWarning::KINDS],
+    /// Reused across frames
+    textures: Vec<Binds>,
+    shaders: Vec<Binds>,
+    warnings: Vec<PerfWarning>,
+}
+
+impl PerfWarnings {
+    /// Logs the smells of the frame about to be drawn, at most once per kind every
+    /// [`WARN_INTERVAL`] frames
+    pub fn check(&mut self, batch: &PrimitiveBatch, renderer: &mut Renderer) {
+        if self.enabled {
+            self.frame += 1;
+            self.analyze(batch, renderer.texture_uploads());
+            for warning in &self.warnings {
+                let last = &mut self.last_warned[warning.kind()];
+                if last.is_none_or(|frame| self.frame - frame >= WARN_INTERVAL) {
+                    *last = Some(self.frame);
+                    log::warn!("{warning}");
+                }
+            }
+        }
+        renderer.clear_texture_uploads();
+    }
+
+    /// Finds the smells of a frame, reusing the warnings of the last one
+    fn analyze(&mut self, batch: &PrimitiveBatch, uploads: &[(usize, usize)]) -> &[PerfWarning] {
+        let warnings = &mut self.warnings;
+        warnings.clear();
+        self.textures.clear();
+        self.shaders.clear();
+        let (mut batches, mut shapes) = (0, 0);
+        let (mut texture_switches, mut shader_switches) = (0, 0);
+        let mut previous = None;
+        for (texture, shader, count) in batch.batch_sizes() {
+            batches += 1;
+            shapes += count;
+            if let Some((previous_texture, previous_shader)) = previous {
+                texture_switches += (texture != previous_texture) as usize;
+                shader_switches += (shader != previous_shader) as usize;
+            }
+            // a batch after one with the same texture still binds it again
+            bind(&mut self.textures, texture, count);
+            if previous.is_none_or(|(_, s)| s != shader) {
+                bind(&mut self.shaders, shader, count);
+            }
+            previous = Some((texture, shader));
+        }
+
+        if batches > MAX_BATCHES {
+            let top = most_bound(&self.textures);
+            warnings.push(PerfWarning::TooManyBatches {
+                batches,
+                texture: (top.id, top.binds),
+            });
+        }
+        if batches >= MIN_BATCHES {
+            if (shapes as f32) < batches as f32 * MIN_SHAPES_PER_BATCH {
+                warnings.push(PerfWarning::SmallBatches { batches, shapes });
+            }
+            if texture_switches > shapes / 2 {
+                let top = most_bound(&self.textures);
+                warnings.push(PerfWarning::TextureThrash {
+                    texture: top.id,
+                    binds: top.binds,
+                    shapes: top.shapes,
+                });
+            }
+            if shader_switches > shapes / 2 {
+                let top = most_bound(&self.shaders);
+                warnings.push(PerfWarning::ShaderThrash {
+                    shader: top.id,
+                    binds: top.binds,
+                    shapes: top.shapes,
+                });
+            }
+        }
+
+        let bytes = uploads.iter().map(|(_, bytes)| bytes).sum();
+        if bytes > MAX_UPLOAD_BYTES {
+            let texture = *uploads.iter().max_by_key(|(_, bytes)| bytes).unwrap();
+            warnings.push(PerfWarning::LargeUploads { bytes, texture });
+        }
+        warnings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alloc_guard::allocations;
+    use egor_render::instance::Instance;
+
+    fn quad(batch: &mut PrimitiveBatch, texture: Option<usize>, shader: Option<usize>) {
+        let instance = Instance::new([1.0, 0.0, 0.0, 1.0], [0.0; 2], [1.0; 4], [0.0; 4]);
+        batch.push_instance(instance, texture, shader);
+    }
+
+    #[test]
+    fn interleaved_textures_warn_with_the_worst_offender() {
+        let mut warnings = PerfWarnings::default();
+        let mut batch = PrimitiveBatch::default();
+        for _ in 0..450 {
+            quad(&mut batch, Some(7), None);
+            quad(&mut batch, Some(3), None);
+        }
+        quad(&mut batch, Some(7), None);
+        let found = warnings.analyze(&batch, &[]);
+        assert_eq!(
+            found,
+            [
+                PerfWarning::TooManyBatches {
+                    batches: 901,
+                    texture: (Some(7), 451),
+                },
+                PerfWarning::SmallBatches {
+                    batches: 901,
+                    shapes: 901,
+                },
+                PerfWarning::TextureThrash {
+                    texture: Some(7),
+                    binds: 451,
+                    shapes: 451,
+                },
+            ]
+        );
+        assert_eq!(
+            found[2].to_string(),
+            "texture 7 was bound 451 times for 1.0 shapes each; consider an atlas or drawing \
+             everything with it in one go"
+        );
+
+        // the same smells next frame reuse the buffers
+        let before = allocations();
+        warnings.analyze(&batch, &[]);
+        assert_eq!(allocations() - before, 0);
+    }
+
+    #[test]
+    fn well_batched_frames_only_warn_about_uploads() {
+        let mut warnings = PerfWarnings::default();
+        let mut batch = PrimitiveBatch::default();
+        // few batches of many shapes, & a handful of small batches stay quiet
+        for texture in 0..100 {
+            for _ in 0..50 {
+                quad(&mut batch, Some(texture), None);
+            }
+        }
+        for i in 0..10 {
+            quad(&mut batch, Some(i), Some(i % 2));
+        }
+        let uploads = [(2, 6 << 20), (5, 4 << 20)];
+        assert_eq!(
+            warnings.analyze(&batch, &uploads),
+            [PerfWarning::LargeUploads {
+                bytes: 10 << 20,
+                texture: (2, 6 << 20),
+            }]
+        );
+
+        // alternating shaders on one texture
+        let mut batch = PrimitiveBatch::default();
+        for i in 0..100 {
+            quad(&mut batch, None, Some(i % 2));
+        }
+        let found = warnings.analyze(&batch, &[(1, 1024)]);
+        assert_eq!(
+            found,
+            [
+                PerfWarning::SmallBatches {
+                    batches: 100,
+                    shapes: 100,
+                },
+                PerfWarning::ShaderThrash {
+                    shader: Some(0),
+                    binds: 50,
+                    shapes: 50,
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,328 @@
+//! Collision helpers for axis-aligned rects: sliding movement & ray casts. There's no
+//! broadphase, so pass only the solids near the mover (e.g. the tiles around it)
+
+use crate::math::{Rect, Vec2};
+
+/// How far a rect may already overlap a solid & still be blocked by it. Absorbs the rounding
+/// error of stopping flush against a surface, so a resting rect can't slip into it next frame
+const CONTACT_SLOP: f32 = 1e-3;
+
+/// Outcome of [`move_and_collide`]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MoveResult {
+    /// How far the rect actually moved
+    pub actual_delta: Vec2,
+    /// A solid stopped the horizontal movement
+    pub hit_x: bool,
+    /// A solid stopped the vertical movement
+    pub hit_y: bool,
+}
+
+/// Where [`raycast_rects`] hit
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RayHit {
+    /// Index of the rect hit
+    pub index: usize,
+    /// Distance along the ray
+    pub distance: f32,
+    pub point: Vec2,
+    /// Unit normal of the side hit, or zero when the ray starts inside the rect
+    pub normal: Vec2,
+}
+
+/// Moves `rect` by up to `delta`, stopping flush against `solids` & sliding along them.
+/// X moves first, then Y, so a rect heading into a corner slides along the wall it meets.
+///
+/// Touching a solid only blocks movement into it: a rect resting on the floor moves along
+/// it freely. Solids the rect already overlaps don't block it, so it can always get out
+pub fn move_and_collide(rect: &Rect, delta: Vec2, solids: &[Rect]) -> MoveResult {
+    let (dx, hit_x) = sweep_axis(rect, delta.x, 0, solids);
+    let moved = Rect::new(rect.position + Vec2::new(dx, 0.0), rect.size);
+    let (dy, hit_y) = sweep_axis(&moved, delta.y, 1, solids);
+    MoveResult {
+        actual_delta: Vec2::new(dx, dy),
+        hit_x,
+        hit_y,
+    }
+}
+
+/// How far `rect` gets moving `delta` along `axis`, & whether a solid stopped it
+fn sweep_axis(rect: &Rect, delta: f32, axis: usize, solids: &[Rect]) -> (f32, bool) {
+    if delta == 0.0 {
+        return (0.0, false);
+    }
+    let other = 1 - axis;
+    let (min, max) = (rect.min(), rect.max());
+    let mut allowed = delta.abs();
+    let mut hit = false;
+    for solid in solids {
+        let (solid_min, solid_max) = (solid.min(), solid.max());
+        // only touching across the movement, e.g. a wall the rect slides past
+        if solid_max[other] <= min[other] || solid_min[other] >= max[other] {
+            continue;
+        }
+        let gap = match delta > 0.0 {
+            true => solid_min[axis] - max[axis],
+            false => min[axis] - solid_max[axis],
+        };
+        // behind the rect or overlapping it
+        if gap < -CONTACT_SLOP {
+            continue;
+        }
+        let gap = gap.max(0.0);
+        if gap <= allowed {
+            allowed = gap;
+            hit = true;
+        }
+    }
+    (allowed.copysign(delta), hit)
+}
+
+/// Casts a ray from `origin` along `dir` & returns the nearest of `rects` it hits within
+/// `max_dist`. `dir` needn't be normalized; a zero `dir` hits nothing.
+///
+/// Grazing an edge or only clipping a corner isn't a hit, matching [`move_and_collide`]
+/// letting rects slide past them. A ray starting inside a rect hits it at distance 0
+pub fn raycast_rects(origin: Vec2, dir: Vec2, max_dist: f32, rects: &[Rect]) -> Option<RayHit> {
+    let dir = dir.try_normalize()?;
+    let mut nearest: Option<RayHit> = None;
+    for (index, rect) in rects.iter().enumerate() {
+        let Some((distance, normal)) = ray_rect(origin, dir, rect) else {
+            continue;
+        };
+        if distance > max_dist || nearest.is_some_and(|hit| hit.distance <= distance) {
+            continue;
+        }
+        nearest = Some(RayHit {
+            index,
+            distance,
+            point: origin + dir * distance,
+            normal,
+        });
+    }
+    nearest
+}
+
+/// Distance along the unit `dir` where the ray enters `rect` & the normal of that side
+fn ray_rect(origin: Vec2, dir: Vec2, rect: &Rect) -> Option<(f32, Vec2)> {
+    let (min, max) = (rect.min(), rect.max());
+    let (mut enter, mut exit) = (f32::NEG_INFINITY, f32::INFINITY);
+    let mut normal = Vec2::ZERO;
+    for axis in 0..2 {
+        if dir[axis] == 0.0 {
+            // parallel to this axis' sides, so it must run strictly between them
+            if origin[axis] <= min[axis] || origin[axis] >= max[axis] {
+                return None;
+            }
+            continue;
+        }
+        let a = (min[axis] - origin[axis]) / dir[axis];
+        let b = (max[axis] - origin[axis]) / dir[axis];
+        let (near, far) = (a.min(b), a.max(b));
+        if near > enter {
+            enter = near;
+            normal = Vec2::ZERO;
+            normal[axis] = -dir[axis].signum();
+        }
+        exit = exit.min(far);
+    }
+    if enter >= exit || exit <= 0.0 {
+        return None;
+    }
+    match enter < 0.0 {
+        true => Some((0.0, Vec2::ZERO)),
+        false => Some((enter, normal)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::vec2;
+
+    fn square(x: f32, y: f32, size: f32) -> Rect {
+        Rect::new(vec2(x, y), Vec2::splat(size))
+    }
+
+    fn moved(rect: &Rect, delta: Vec2, solids: &[Rect]) -> (Vec2, bool, bool) {
+        let result = move_and_collide(rect, delta, solids);
+        (result.actual_delta, result.hit_x, result.hit_y)
+    }
+
+    #[test]
+    fn free_movement_and_zero_deltas() {
+        let player = square(0.0, 0.0, 10.0);
+        let far = [square(100.0, 100.0, 10.0)];
+        assert_eq!(
+            moved(&player, vec2(5.0, -3.0), &far),
+            (vec2(5.0, -3.0), false, false)
+        );
+        assert_eq!(moved(&player, Vec2::ZERO, &far), (Vec2::ZERO, false, false));
+        assert_eq!(
+            moved(&player, vec2(5.0, 0.0), &[]),
+            (vec2(5.0, 0.0), false, false)
+        );
+
+        // a zero delta against a touching wall isn't a hit
+        let wall = [square(10.0, 0.0, 10.0)];
+        assert_eq!(
+            moved(&player, Vec2::ZERO, &wall),
+            (Vec2::ZERO, false, false)
+        );
+    }
+
+    #[test]
+    fn stops_flush_against_walls_in_every_direction() {
+        let player = square(0.0, 0.0, 10.0);
+        let cases = [
+            (square(15.0, 0.0, 10.0), vec2(20.0, 0.0), vec2(5.0, 0.0)),
+            (square(-15.0, 0.0, 10.0), vec2(-20.0, 0.0), vec2(-5.0, 0.0)),
+            (square(0.0, 15.0, 10.0), vec2(0.0, 20.0), vec2(0.0, 5.0)),
+            (square(0.0, -15.0, 10.0), vec2(0.0, -20.0), vec2(0.0, -5.0)),
+        ];
+        for (wall, delta, expected) in cases {
+            let (actual, hit_x, hit_y) = moved(&player, delta, &[wall]);
+            assert_eq!(actual, expected);
+            assert_eq!((hit_x, hit_y), (delta.x != 0.0, delta.y != 0.0));
+        }
+
+        // exactly reaching the wall counts as hitting it
+        assert_eq!(
+            moved(&player, vec2(5.0, 0.0), &[square(15.0, 0.0, 10.0)]),
+            (vec2(5.0, 0.0), true, false)
+        );
+        // the nearest of several walls wins
+        let walls = [square(40.0, 0.0, 10.0), square(12.0, 5.0, 4.0)];
+        assert_eq!(
+            moved(&player, vec2(50.0, 0.0), &walls),
+            (vec2(2.0, 0.0), true, false)
+        );
+    }
+
+    #[test]
+    fn touching_edges_block_only_movement_into_them() {
+        // standing on the floor
+        let player = square(0.0, 0.0, 10.0);
+        let floor = [Rect::new(vec2(-100.0, 10.0), vec2(200.0, 10.0))];
+        assert_eq!(
+            moved(&player, vec2(7.0, 3.0), &floor),
+            (vec2(7.0, 0.0), false, true)
+        );
+        assert_eq!(
+            moved(&player, vec2(7.0, -3.0), &floor),
+            (vec2(7.0, -3.0), false, false)
+        );
+
+        // sliding past a wall that only shares an edge across the movement
+        let beside = [square(10.0, 10.0, 10.0)];
+        assert_eq!(
+            moved(&player, vec2(20.0, 0.0), &beside),
+            (vec2(20.0, 0.0), false, false)
+        );
+        assert_eq!(
+            moved(&player, vec2(0.0, 20.0), &beside),
+            (vec2(0.0, 20.0), false, false)
+        );
+    }
+
+    #[test]
+    fn slides_along_walls_and_into_corners() {
+        let player = square(0.0, 0.0, 10.0);
+        let wall = [Rect::new(vec2(12.0, -100.0), vec2(10.0, 200.0))];
+        assert_eq!(
+            moved(&player, vec2(5.0, 8.0), &wall),
+            (vec2(2.0, 8.0), true, false)
+        );
+
+        // diagonally at a block's corner: X is clear, then Y lands on the block's top
+        let block = [square(12.0, 12.0, 10.0)];
+        assert_eq!(
+            moved(&player, vec2(6.0, 6.0), &block),
+            (vec2(6.0, 2.0), false, true)
+        );
+        // exactly corner to corner only touches, so it slides past
+        assert_eq!(
+            moved(&player, vec2(2.0, 5.0), &block),
+            (vec2(2.0, 5.0), false, false)
+        );
+
+        // into an inside corner, both axes stop
+        let corner = [
+            Rect::new(vec2(15.0, -50.0), vec2(10.0, 100.0)),
+            Rect::new(vec2(-50.0, 14.0), vec2(100.0, 10.0)),
+        ];
+        assert_eq!(
+            moved(&player, vec2(10.0, 10.0), &corner),
+            (vec2(5.0, 4.0), true, true)
+        );
+    }
+
+    #[test]
+    fn resting_contact_survives_rounding_and_overlaps_can_escape() {
+        // flush after a move with rounding error, still blocked next frame
+        let mut player = square(0.1, 0.0, 0.2);
+        let wall = [square(1.0, 0.0, 1.0)];
+        for _ in 0..3 {
+            let result = move_and_collide(&player, vec2(0.7, 0.0), &wall);
+            player.translate(result.actual_delta);
+        }
+        assert!(player.max().x <= 1.0 + CONTACT_SLOP);
+        assert!(move_and_collide(&player, vec2(0.7, 0.0), &wall).hit_x);
+
+        // spawned inside a solid, movement isn't blocked by it
+        let inside = square(5.0, 5.0, 2.0);
+        let solid = [square(0.0, 0.0, 10.0)];
+        assert_eq!(
+            moved(&inside, vec2(-8.0, 0.0), &solid),
+            (vec2(-8.0, 0.0), false, false)
+        );
+    }
+
+    #[test]
+    fn rays_hit_the_nearest_rect_with_its_normal() {
+        let rects = [
+            square(20.0, -5.0, 10.0),
+            Rect::new(vec2(10.0, -5.0), vec2(5.0, 10.0)),
+        ];
+        let hit = raycast_rects(Vec2::ZERO, vec2(1.0, 0.0), 100.0, &rects).unwrap();
+        assert_eq!(hit.index, 1);
+        assert_eq!(hit.distance, 10.0);
+        assert_eq!(hit.point, vec2(10.0, 0.0));
+        assert_eq!(hit.normal, vec2(-1.0, 0.0));
+
+        // dir is normalized & the normal faces the ray
+        let hit = raycast_rects(vec2(12.0, 40.0), vec2(0.0, -3.0), 100.0, &rects).unwrap();
+        assert_eq!((hit.index, hit.distance), (1, 35.0));
+        assert_eq!(hit.normal, vec2(0.0, 1.0));
+
+        let hit = raycast_rects(vec2(0.0, 10.0), vec2(1.0, -1.0), 100.0, &rects).unwrap();
+        assert_eq!((hit.index, hit.normal), (1, vec2(-1.0, 0.0)));
+        assert!((hit.point - vec2(10.0, 0.0)).length() < 1e-4);
+    }
+
+    #[test]
+    fn rays_miss_past_max_dist_behind_and_along_edges() {
+        let rects = [square(10.0, -5.0, 10.0)];
+        assert!(raycast_rects(Vec2::ZERO, vec2(1.0, 0.0), 9.0, &rects).is_none());
+        assert!(raycast_rects(Vec2::ZERO, vec2(1.0, 0.0), 10.0, &rects).is_some());
+        assert!(raycast_rects(Vec2::ZERO, vec2(-1.0, 0.0), 100.0, &rects).is_none());
+        assert!(raycast_rects(Vec2::ZERO, Vec2::ZERO, 100.0, &rects).is_none());
+        assert!(raycast_rects(Vec2::ZERO, vec2(1.0, 0.0), 100.0, &[]).is_none());
+        // grazing the top edge & clipping only the corner
+        assert!(raycast_rects(vec2(0.0, -5.0), vec2(1.0, 0.0), 100.0, &rects).is_none());
+        assert!(raycast_rects(vec2(10.0, -15.0), vec2(1.0, 1.0), 100.0, &rects).is_none());
+        // starting on the far side, moving away
+        assert!(raycast_rects(vec2(20.0, 0.0), vec2(1.0, 0.0), 100.0, &rects).is_none());
+    }
+
+    #[test]
+    fn rays_starting_inside_or_on_an_edge_hit_immediately() {
+        let rects = [square(10.0, -5.0, 10.0)];
+        let inside = raycast_rects(vec2(15.0, 0.0), vec2(1.0, 0.0), 100.0, &rects).unwrap();
+        assert_eq!((inside.distance, inside.normal), (0.0, Vec2::ZERO));
+        assert_eq!(inside.point, vec2(15.0, 0.0));
+
+        let on_edge = raycast_rects(vec2(10.0, 0.0), vec2(1.0, 0.0), 100.0, &rects).unwrap();
+        assert_eq!((on_edge.distance, on_edge.normal), (0.0, vec2(-1.0, 0.0)));
+    }
+}
@@ -0,0 +1,318 @@
+//! Finding the rectangle under a point, see [`crate::graphics::Graphics::pick`]
+
+use std::collections::HashMap;
+
+use glam::{Mat2, Vec2, vec2};
+
+use crate::{
+    coords::{Coords, Space},
+    graphics::TextureId,
+    layer::Layers,
+};
+
+/// Alpha channel of a texture kept on the CPU, see
+/// [`crate::graphics::Graphics::load_texture_pickable`]
+pub(crate) struct AlphaMask {
+    width: u32,
+    height: u32,
+    alpha: Vec<u8>,
+}
+
+impl AlphaMask {
+    pub fn from_rgba(width: u32, height: u32, rgba: &[u8]) -> Self {
+        Self {
+            width,
+            height,
+            alpha: rgba.chunks_exact(4).map(|pixel| pixel[3]).collect(),
+        }
+    }
+
+    /// Alpha in 0..1 of the texel at `uv`, wrapped like a repeating texture so offset &
+    /// scaled UVs past 0..1 sample the tile they show
+    fn sample(&self, uv: Vec2) -> f32 {
+        let texel = |t: f32, size: u32| {
+            ((t.rem_euclid(1.0) * size as f32) as i64).clamp(0, size as i64 - 1)
+        };
+        let (x, y) = (texel(uv.x, self.width), texel(uv.y, self.height));
+        self.alpha[(y * self.width as i64 + x) as usize] as f32 / 255.0
+    }
+}
+
+/// A rectangle drawn with [`crate::primitives::RectangleBuilder::pick_id`]
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct PickTarget {
+    pub id: u64,
+    pub center: Vec2,
+    /// Maps offsets from the center to the rectangle's -0.5..0.5 square, undoing rotation
+    pub to_local: Mat2,
+    /// UV rect after flips, atlas regions, offsets & scales
+    pub uvs: [f32; 4],
+    pub texture: Option<usize>,
+    pub alpha_threshold: Option<f32>,
+    pub layer: usize,
+}
+
+/// Rectangles to pick from this frame & the alpha masks of pickable textures
+#[derive(Default)]
+pub(crate) struct Picking {
+    masks: HashMap<usize, AlphaMask>,
+    /// In the order they were drawn
+    targets: Vec<PickTarget>,
+}
+
+impl Picking {
+    pub fn insert_mask(&mut self, texture: impl Into<TextureId>, mask: AlphaMask) {
+        self.masks.insert(texture.into().0, mask);
+    }
+
+    /// Forgets the mask of an unloaded texture, so a texture reusing its id isn't hit through it
+    pub fn remove_mask(&mut self, texture: impl Into<TextureId>) {
+        self.masks.remove(&texture.into().0);
+    }
+
+    pub fn push(&mut self, target: PickTarget) {
+        self.targets.push(target);
+    }
+
+    /// Forgets this frame's rectangles, keeping the masks
+    pub fn clear(&mut self) {
+        self.targets.clear();
+    }
+
+    /// Id of the topmost rectangle under the screen pixel `point`, seen through the camera of
+    /// `coords` or the camera of the rectangle's layer. Later layers & later rectangles
+    /// within a layer are on top
+    pub fn pick(&self, point: Vec2, layers: &Layers, coords: &Coords) -> Option<u64> {
+        let in_layer = |layer: usize| {
+            let coords = match layers.get(layer).camera_override() {
+                Some(own) => coords.seen_through(own),
+                None => *coords,
+            };
+            coords.convert(point, Space::Screen, Space::World)
+        };
+        let hits = self
+            .targets
+            .iter()
+            .enumerate()
+            .filter(|(_, t)| self.hits(t, in_layer(t.layer)));
+        hits.max_by_key(|&(order, t)| (layers.sort_key(t.layer), order))
+            .map(|(_, t)| t.id)
+    }
+
+    fn hits(&self, target: &PickTarget, point: Vec2) -> bool {
+        let local = target.to_local * (point - target.center) + 0.5;
+        if !(0.0..=1.0).contains(&local.x) || !(0.0..=1.0).contains(&local.y) {
+            return false;
+        }
+        let (Some(threshold), Some(texture)) = (target.alpha_threshold, target.texture) else {
+            return true;
+        };
+        // textures without a CPU copy hit on their whole quad
+        let Some(mask) = self.masks.get(&texture) else {
+            return true;
+        };
+        let [u0, v0, u1, v1] = target.uvs;
+        let uv = vec2(u0, v0) + local * vec2(u1 - u0, v1 - v0);
+        mask.sample(uv) >= threshold
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        atlas::SubTexture,
+        camera::Camera,
+        primitives::{PrimitiveBatch, RectangleBuilder},
+    };
+    use std::f32::consts::FRAC_PI_2;
+
+    const SIZE: u32 = 8;
+    const TEXTURE: usize = 0;
+    const CARD: u64 = 1;
+    const BEHIND: u64 = 2;
+
+    /// Opaque above the diagonal from the top left to the bottom right corner & transparent
+    /// below it. With more `regions` side by side, only the last one has the diagonal
+    fn diagonal(regions: u32) -> AlphaMask {
+        let last = SIZE * (regions - 1);
+        let rgba: Vec<u8> = (0..SIZE)
+            .flat_map(|y| (0..SIZE * regions).map(move |x| (x, y)))
+            .flat_map(|(x, y)| {
+                [
+                    255,
+                    255,
+                    255,
+                    if x >= last && x - last > y { 255 } else { 0 },
+                ]
+            })
+            .collect();
+        AlphaMask::from_rgba(SIZE * regions, SIZE, &rgba)
+    }
+
+    fn rect(batch: &mut PrimitiveBatch) -> RectangleBuilder<'_> {
+        RectangleBuilder::new(batch, None)
+    }
+
+    /// A 100x100 card at the origin over an opaque card covering it, drawn by `card`
+    fn pick_at(
+        mask: AlphaMask,
+        card: impl FnOnce(&mut PrimitiveBatch),
+        points: &[(f32, f32)],
+    ) -> Vec<Option<u64>> {
+        let mut batch = PrimitiveBatch::new(1024, 1024);
+        batch.texture_check.loaded = 1;
+        batch.picking.insert_mask(TEXTURE, mask);
+        rect(&mut batch)
+            .at(vec2(-50.0, -50.0))
+            .size(vec2(200.0, 200.0))
+            .pick_id(BEHIND);
+        card(&mut batch);
+        let pick = |&(x, y): &(f32, f32)| {
+            batch.picking.pick(
+                vec2(x, y),
+                &batch.layers,
+                &Coords::new(Camera::default(), 1.0),
+            )
+        };
+        points.iter().map(pick).collect()
+    }
+
+    #[test]
+    fn transparent_texels_fall_through() {
+        let card = |batch: &mut PrimitiveBatch| {
+            rect(batch)
+                .size(vec2(100.0, 100.0))
+                .texture(TEXTURE)
+                .pick_alpha_threshold(0.5)
+                .pick_id(CARD);
+        };
+        // top right triangle, bottom left triangle & outside the card
+        let points = [(80.0, 20.0), (20.0, 80.0), (120.0, 50.0), (500.0, 0.0)];
+        let picks = pick_at(diagonal(1), card, &points);
+        assert_eq!(picks, [Some(CARD), Some(BEHIND), Some(BEHIND), None]);
+
+        // without a threshold the whole quad is the card
+        let card = |batch: &mut PrimitiveBatch| {
+            rect(batch)
+                .size(vec2(100.0, 100.0))
+                .texture(TEXTURE)
+                .pick_id(CARD);
+        };
+        let picks = pick_at(diagonal(1), card, &points[..2]);
+        assert_eq!(picks, [Some(CARD), Some(CARD)]);
+    }
+
+    #[test]
+    fn rotation_turns_the_opaque_triangle() {
+        // a quarter turn clockwise on screen moves the top right triangle to the bottom right,
+        // so the opaque half is below the other diagonal
+        let card = |batch: &mut PrimitiveBatch| {
+            rect(batch)
+                .size(vec2(100.0, 100.0))
+                .rotate(FRAC_PI_2)
+                .texture(TEXTURE)
+                .pick_alpha_threshold(0.5)
+                .pick_id(CARD);
+        };
+        let points = [(40.0, 90.0), (60.0, 10.0)];
+        let picks = pick_at(diagonal(1), card, &points);
+        assert_eq!(picks, [Some(CARD), Some(BEHIND)]);
+    }
+
+    #[test]
+    fn flips_mirror_the_opaque_triangle() {
+        let card = |batch: &mut PrimitiveBatch| {
+            rect(batch)
+                .size(vec2(100.0, 100.0))
+                .texture(TEXTURE)
+                .uv([1.0, 0.0, 0.0, 1.0])
+                .pick_alpha_threshold(0.5)
+                .pick_id(CARD);
+        };
+        let points = [(20.0, 20.0), (80.0, 80.0)];
+        let picks = pick_at(diagonal(1), card, &points);
+        assert_eq!(picks, [Some(CARD), Some(BEHIND)]);
+    }
+
+    #[test]
+    fn atlas_regions_sample_their_own_texels() {
+        // the right region of a 2 region atlas, vertically flipped
+        let region = SubTexture {
+            texture_id: TEXTURE,
+            uv: [0.5, 1.0, 1.0, 0.0],
+            size: (SIZE, SIZE),
+        };
+        let card = |batch: &mut PrimitiveBatch| {
+            rect(batch)
+                .size(vec2(100.0, 100.0))
+                .texture_region(region)
+                .pick_alpha_threshold(0.5)
+                .pick_id(CARD);
+        };
+        let points = [(80.0, 80.0), (60.0, 60.0), (20.0, 20.0)];
+        let picks = pick_at(diagonal(2), card, &points);
+        assert_eq!(picks, [Some(CARD), Some(CARD), Some(BEHIND)]);
+    }
+
+    #[test]
+    fn uvs_past_one_wrap_around() {
+        // the second tile of a repeating texture has the same opaque triangle
+        let card = |batch: &mut PrimitiveBatch| {
+            rect(batch)
+                .size(vec2(100.0, 100.0))
+                .texture(TEXTURE)
+                .uv([1.0, 0.0, 2.0, 1.0])
+                .pick_alpha_threshold(0.5)
+                .pick_id(CARD);
+        };
+        let points = [(80.0, 20.0), (20.0, 80.0)];
+        let picks = pick_at(diagonal(1), card, &points);
+        assert_eq!(picks, [Some(CARD), Some(BEHIND)]);
+    }
+
+    #[test]
+    fn points_are_seen_through_each_layers_camera() {
+        let mut batch = PrimitiveBatch::new(1024, 1024);
+        let mut camera = Camera::default();
+        camera.set_screen_size(vec2(800.0, 600.0));
+        camera.set_zoom(4.0);
+        rect(&mut batch).size(vec2(10.0, 10.0)).pick_id(1);
+
+        let hud = batch.layers.index("hud");
+        batch.layers.get_mut(hud).screen_space(true);
+        batch.set_layer(hud);
+        rect(&mut batch)
+            .at(vec2(100.0, 100.0))
+            .size(vec2(10.0, 10.0))
+            .pick_id(2);
+
+        let map = batch.layers.index("map");
+        let mut map_camera = Camera::default();
+        map_camera.target(vec2(1000.0, 0.0));
+        batch.layers.get_mut(map).camera(Some(map_camera));
+        batch.set_layer(map);
+        rect(&mut batch)
+            .at(vec2(1200.0, 200.0))
+            .size(vec2(10.0, 10.0))
+            .pick_id(3);
+
+        let pick = |x, y| {
+            batch
+                .picking
+                .pick(vec2(x, y), &batch.layers, &Coords::new(camera, 1.0))
+        };
+        assert_eq!(pick(20.0, 20.0), Some(1));
+        assert_eq!(pick(105.0, 105.0), Some(2));
+        assert_eq!(pick(205.0, 205.0), Some(3));
+        assert_eq!(pick(60.0, 60.0), None);
+    }
+
+    #[test]
+    fn unloaded_textures_lose_their_mask() {
+        let mut picking = Picking::default();
+        picking.insert_mask(TEXTURE, diagonal(1));
+        picking.remove_mask(TEXTURE);
+        assert!(picking.masks.is_empty());
+    }
+}
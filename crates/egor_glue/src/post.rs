@@ -0,0 +1,310 @@
+use egor_render::{
+    CommandEncoder, Renderer, TextureFormat, TextureView,
+    batch::GeometryBatch,
+    debug,
+    instance::Instance,
+    target::{OffscreenTarget, RenderTarget},
+};
+
+const COMMON_WGSL: &str = include_str!("../shaders/post/common.wgsl");
+
+/// A built-in screen-space effect, see [`PostEffects`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PostEffect {
+    Vignette,
+    ChromaticAberration,
+    Pixelate,
+    Crt,
+}
+
+impl PostEffect {
+    pub const ALL: [Self; 4] = [
+        Self::Vignette,
+        Self::ChromaticAberration,
+        Self::Pixelate,
+        Self::Crt,
+    ];
+
+    fn fragment_wgsl(self) -> &'static str {
+        match self {
+            Self::Vignette => include_str!("../shaders/post/vignette.wgsl"),
+            Self::ChromaticAberration => include_str!("../shaders/post/chromatic_aberration.wgsl"),
+            Self::Pixelate => include_str!("../shaders/post/pixelate.wgsl"),
+            Self::Crt => include_str!("../shaders/post/crt.wgsl"),
+        }
+    }
+}
+
+struct Stage {
+    effect: PostEffect,
+    params: [f32; 4],
+    enabled: bool,
+}
+
+/// Post effects kept across frames, run in order over the frame before egui & gizmos.
+/// The scene is drawn into an offscreen target & each effect ping-pongs between two of
+/// them, the last one writing to the frame
+pub(crate) struct PostChain {
+    stages: Vec<Stage>,
+    /// Shader & uniform ids per [`PostEffect`], created the first time it's enabled
+    programs: [Option<(usize, usize)>; PostEffect::ALL.len()],
+    /// Ping-pong targets sized to the frame & their texture ids
+    targets: Vec<(OffscreenTarget, usize)>,
+    quad: GeometryBatch,
+}
+
+impl Default for PostChain {
+    fn default() -> Self {
+        Self {
+            stages: Vec::new(),
+            programs: Default::default(),
+            targets: Vec::new(),
+            // effects draw a single instanced quad
+            quad: GeometryBatch::new(0, 0),
+        }
+    }
+}
+
+impl PostChain {
+    fn enabled(&self) -> impl Iterator<Item = &Stage> {
+        self.stages.iter().filter(|s| s.enabled)
+    }
+
+    /// Creates what the enabled effects need & uploads their parameters for a `size` frame.
+    /// Returns the view to draw the scene into, `None` when no effect is enabled
+    pub fn prepare(
+        &mut self,
+        renderer: &mut Renderer,
+        format: TextureFormat,
+        (w, h): (u32, u32),
+    ) -> Option<&TextureView> {
+        let count = self.enabled().count();
+        if count == 0 {
+            return None;
+        }
+
+        for stage in self.stages.iter().filter(|s| s.enabled) {
+            let mut uniform = [0; 32];
+            let values = stage
+                .params
+                .into_iter()
+                .chain([w as f32, h as f32, 0.0, 0.0]);
+            for (bytes, value) in uniform.chunks_exact_mut(4).zip(values) {
+                bytes.copy_from_slice(&value.to_ne_bytes());
+            }
+            let (_, uniform_id) = *self.programs[stage.effect as usize].get_or_insert_with(|| {
+                let uniform_id = renderer.add_uniform(&uniform);
+                let wgsl = format!("{COMMON_WGSL}{}", stage.effect.fragment_wgsl());
+                (
+                    renderer.add_shader_with_uniforms(&wgsl, &[uniform_id]),
+                    uniform_id,
+                )
+            });
+            renderer.update_uniform(uniform_id, &uniform);
+        }
+
+        // one target holds the scene, a second is needed once effects feed each other
+        let needed = count.min(2);
+        for (target, id) in &mut self.targets {
+            if target.size() != (w, h) {
+                target.resize(renderer.device(), w, h);
+                renderer.update_offscreen_texture(*id, target);
+            }
+        }
+        while self.targets.len() < needed {
+            let mut target = renderer.create_offscreen_target(w, h, format);
+            let id = renderer.add_offscreen_texture(&mut target);
+            self.targets.push((target, id));
+        }
+        Some(self.targets[0].0.render_view())
+    }
+
+    /// Runs the enabled effects over the scene drawn into [`Self::prepare`]'s view, the last
+    /// one writing to `view`
+    pub fn apply(&mut self, renderer: &Renderer, encoder: &mut CommandEncoder, view: &TextureView) {
+        let count = self.enabled().count();
+        let stages = self.stages.iter().filter(|s| s.enabled);
+        for (i, stage) in stages.enumerate() {
+            let (source, texture_id) = &self.targets[i % 2];
+            source.copy_to_sample(encoder);
+            let output = match i + 1 == count {
+                true => view,
+                false => self.targets[(i + 1) % 2].0.render_view(),
+            };
+            let (shader_id, _) = self.programs[stage.effect as usize].unwrap();
+            let effect = stage.effect;
+            let label = format_args!("post effect {i}: {effect:?}, shader {shader_id}");
+            debug::group(encoder, label, |encoder| {
+                let mut r_pass = renderer.begin_render_pass(encoder, output);
+                self.quad.push_instance(Instance::new(
+                    [1.0, 0.0, 0.0, 1.0],
+                    [0.0, 0.0],
+                    [1.0; 4],
+                    [0.0, 0.0, 1.0, 1.0],
+                ));
+                renderer.draw_batch(
+                    &mut r_pass,
+                    &mut self.quad,
+                    Some(*texture_id),
+                    Some(shader_id),
+                );
+            });
+        }
+    }
+
+    fn set(&mut self, effect: PostEffect, params: [f32; 4]) {
+        match self.stages.iter_mut().find(|s| s.effect == effect) {
+            Some(stage) => {
+                stage.params = params;
+                stage.enabled = true;
+            }
+            None => self.stages.push(Stage {
+                effect,
+                params,
+                enabled: true,
+            }),
+        }
+    }
+}
+
+/// Built-in screen-space effects over everything drawn, below egui & gizmos. Get it with
+/// [`crate::graphics::Graphics::post_effects`]. Effects stay on across frames & run in the
+/// order they were first added; changing parameters each frame only updates uniforms
+///
+/// ```no_run
+/// # fn f(gfx: &mut egor_glue::graphics::Graphics, time: f32) {
+/// gfx.post_effects()
+///     .vignette(0.6)
+///     .chromatic_aberration(2.0 + time.sin())
+///     .crt(0.1, 0.3);
+/// # }
+/// ```
+pub struct PostEffects<'a> {
+    chain: Option<&'a mut PostChain>,
+}
+
+impl<'a> PostEffects<'a> {
+    pub(crate) fn new(chain: Option<&'a mut PostChain>) -> Self {
+        Self { chain }
+    }
+
+    fn set(mut self, effect: PostEffect, params: [f32; 4]) -> Self {
+        if let Some(chain) = &mut self.chain {
+            chain.set(effect, params);
+        }
+        self
+    }
+
+    /// Darken toward the corners, from 0 (off) to 1 (black corners)
+    pub fn vignette(self, strength: f32) -> Self {
+        self.set(PostEffect::Vignette, [strength, 0.0, 0.0, 0.0])
+    }
+
+    /// Split red & blue apart by up to `offset` pixels toward the edges of the screen
+    pub fn chromatic_aberration(self, offset: f32) -> Self {
+        self.set(PostEffect::ChromaticAberration, [offset, 0.0, 0.0, 0.0])
+    }
+
+    /// Draw the screen as `block_size` pixel blocks. Blocks cut off by the screen's edges
+    /// keep their color instead of sampling past it
+    pub fn pixelate(self, block_size: f32) -> Self {
+        self.set(PostEffect::Pixelate, [block_size, 0.0, 0.0, 0.0])
+    }
+
+    /// Bulge the screen like a CRT by `curvature` (around 0.1) & darken every other row of
+    /// pixels by `scanline_intensity`, from 0 to 1
+    pub fn crt(self, curvature: f32, scanline_intensity: f32) -> Self {
+        self.set(PostEffect::Crt, [curvature, scanline_intensity, 0.0, 0.0])
+    }
+
+    /// Turn an added effect on or off, keeping its parameters & place in the order
+    pub fn set_enabled(mut self, effect: PostEffect, enabled: bool) -> Self {
+        if let Some(chain) = &mut self.chain {
+            let stage = chain.stages.iter_mut().find(|s| s.effect == effect);
+            if let Some(stage) = stage {
+                stage.enabled = enabled;
+            }
+        }
+        self
+    }
+
+    /// Remove an effect, so adding it again puts it last
+    pub fn remove(mut self, effect: PostEffect) -> Self {
+        if let Some(chain) = &mut self.chain {
+            chain.stages.retain(|s| s.effect != effect);
+        }
+        self
+    }
+
+    /// Remove every effect
+    pub fn clear(mut self) -> Self {
+        if let Some(chain) = &mut self.chain {
+            chain.stages.clear();
+        }
+        self
+    }
+
+    /// Run the given effects first, in this order, followed by the rest as they were
+    pub fn order(mut self, effects: &[PostEffect]) -> Self {
+        if let Some(chain) = &mut self.chain {
+            chain.stages.sort_by_key(|s| {
+                let position = effects.iter().position(|&e| e == s.effect);
+                position.unwrap_or(effects.len())
+            });
+        }
+        self
+    }
+
+    pub fn is_enabled(&self, effect: PostEffect) -> bool {
+        let chain = self.chain.as_deref();
+        chain.is_some_and(|c| c.enabled().any(|s| s.effect == effect))
+    }
+
+    /// Enabled effects in the order they run
+    pub fn enabled(&self) -> Vec<PostEffect> {
+        let chain = self.chain.as_deref();
+        let stages = chain.into_iter().flat_map(|c| c.enabled());
+        stages.map(|s| s.effect).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn effects_toggle_and_reorder_in_place() {
+        let mut chain = PostChain::default();
+        PostEffects::new(Some(&mut chain))
+            .vignette(0.5)
+            .pixelate(4.0)
+            .crt(0.1, 0.3)
+            .set_enabled(PostEffect::Pixelate, false)
+            .vignette(0.8);
+        let effects = PostEffects::new(Some(&mut chain));
+        assert_eq!(effects.enabled(), [PostEffect::Vignette, PostEffect::Crt]);
+        assert!(!effects.is_enabled(PostEffect::Pixelate));
+        assert_eq!(chain.stages[0].params[0], 0.8);
+
+        let effects = PostEffects::new(Some(&mut chain))
+            .set_enabled(PostEffect::Pixelate, true)
+            .order(&[PostEffect::Crt, PostEffect::Pixelate]);
+        assert_eq!(
+            effects.enabled(),
+            [PostEffect::Crt, PostEffect::Pixelate, PostEffect::Vignette]
+        );
+        let effects = effects.remove(PostEffect::Crt).chromatic_aberration(2.0);
+        assert_eq!(
+            effects.enabled(),
+            [
+                PostEffect::Pixelate,
+                PostEffect::Vignette,
+                PostEffect::ChromaticAberration
+            ]
+        );
+        assert!(effects.clear().enabled().is_empty());
+
+        // graphics without a chain ignore effects
+        assert!(PostEffects::new(None).vignette(1.0).enabled().is_empty());
+    }
+}
@@ -1,32 +1,453 @@
-use crate::{color::Color, math::Rect};
-use egor_render::{batch::GeometryBatch, instance::Instance, vertex::Vertex};
-use glam::{Mat2, Vec2, vec2};
+use crate::{
+    alloc_guard,
+    atlas::SubTexture,
+    bitmap_font::{BitmapFont, load_debug_font},
+    camera::{Camera, Viewport},
+    capture::{BatchRecord, DrawKind, FrameCapture, Piece, Recorder},
+    color::Color,
+    graphics::TextureId,
+    layer::Layers,
+    math::Rect,
+    picking::{PickTarget, Picking},
+    static_geometry::{StaticBatch, StaticStore},
+};
+use egor_render::{
+    RenderPass, Renderer,
+    batch::GeometryBatch,
+    instance::{ColorMode, Instance},
+    vertex::Vertex,
+};
+use glam::{Affine2, Mat2, Vec2, vec2};
 use lyon::{
     geom::euclid::Point2D,
     math::{Box2D, Point, point},
-    path::{Builder, Path, Winding},
+    path::{
+        Attributes, EndpointId, PathEvent, Winding,
+        builder::{NoAttributes, PathBuilder as LyonPathBuilder},
+    },
     tessellation::{
-        FillTessellator, FillVertex, StrokeOptions, StrokeTessellator, StrokeVertex,
+        FillOptions, FillTessellator, FillVertex, StrokeOptions, StrokeTessellator, StrokeVertex,
         geometry_builder::{BuffersBuilder, VertexBuffers},
     },
 };
 
 pub use lyon::path::builder::BorderRadii;
 
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    panic::Location,
+    sync::Arc,
+};
+
 const MIN_THICKNESS: f32 = 0.001;
+/// Most a tessellated curve may stray from the true one, in local units, unless overridden
+pub const DEFAULT_TOLERANCE: f32 = 0.1;
+/// Finest tolerance [`QualityMode::ZoomAdaptive`] goes down to, so huge zooms stay bounded
+const MIN_TOLERANCE: f32 = 0.001;
+/// Longest a mitered outline corner may extend, as a multiple of half the thickness
+const MITER_LIMIT: f32 = 4.0;
+/// Width & height of the built-in drop shadow texture, see [`shadow_texture_pixels`]
+pub(crate) const SHADOW_TEXTURE_SIZE: u32 = 32;
+/// Fraction of the shadow texture's radius that stays fully opaque
+const SHADOW_PLATEAU: f32 = 0.1;
+/// Most cleared batches kept around for reuse by the next frame
+const MAX_SPARE_BATCHES: usize = 256;
+
+thread_local! {
+    /// Scratch point storage shared by builders. Only one builder can be alive
+    /// per [`PrimitiveBatch`] borrow, so a single buffer is enough to make
+    /// steady-state drawing allocation free
+    static POINT_SCRATCH: RefCell<Vec<Vec2>> = const { RefCell::new(Vec::new()) };
+    /// Path events of the [`PathBuilder`] being built, reused the same way
+    static PATH_SCRATCH: RefCell<Vec<PathEvent>> = const { RefCell::new(Vec::new()) };
+    /// Tessellators & their output, reused by every path tessellated on this thread
+    static TESSELLATION: RefCell<Tessellation> = RefCell::new(Tessellation::new());
+}
+
+/// Reusable tessellation state. lyon's tessellators keep their internal buffers between paths
+struct Tessellation {
+    fill: FillTessellator,
+    stroke: StrokeTessellator,
+    geometry: VertexBuffers<Vertex, u16>,
+}
+
+impl Tessellation {
+    fn new() -> Self {
+        Self {
+            fill: FillTessellator::new(),
+            stroke: StrokeTessellator::new(),
+            geometry: VertexBuffers::new(),
+        }
+    }
+}
+
+/// Records path commands as events to tessellate straight from, since building a lyon `Path`
+/// allocates for every shape
+#[derive(Default)]
+struct PathRecorder {
+    events: Vec<PathEvent>,
+    first: Point,
+    current: Point,
+}
+
+impl PathRecorder {
+    fn pooled() -> Self {
+        let mut events = PATH_SCRATCH.with_borrow_mut(std::mem::take);
+        events.clear();
+        Self {
+            events,
+            ..Default::default()
+        }
+    }
+
+    fn to(&mut self, to: Point, event: PathEvent) -> EndpointId {
+        self.events.push(event);
+        self.current = to;
+        EndpointId(self.events.len() as u32)
+    }
+}
+
+/// Hands recorded events back to [`PATH_SCRATCH`] if they have more room than what's there
+fn recycle_path_events(mut events: Vec<PathEvent>) {
+    events.clear();
+    PATH_SCRATCH.with_borrow_mut(|scratch| {
+        if events.capacity() > scratch.capacity() {
+            *scratch = events;
+        }
+    });
+}
+
+impl LyonPathBuilder for PathRecorder {
+    fn num_attributes(&self) -> usize {
+        0
+    }
+
+    fn begin(&mut self, at: Point, _: Attributes) -> EndpointId {
+        self.first = at;
+        self.to(at, PathEvent::Begin { at })
+    }
+
+    fn end(&mut self, close: bool) {
+        self.events.push(PathEvent::End {
+            last: self.current,
+            first: self.first,
+            close,
+        });
+        self.current = self.first;
+    }
+
+    fn line_to(&mut self, to: Point, _: Attributes) -> EndpointId {
+        let from = self.current;
+        self.to(to, PathEvent::Line { from, to })
+    }
+
+    fn quadratic_bezier_to(&mut self, ctrl: Point, to: Point, _: Attributes) -> EndpointId {
+        let from = self.current;
+        self.to(to, PathEvent::Quadratic { from, ctrl, to })
+    }
+
+    fn cubic_bezier_to(
+        &mut self,
+        ctrl1: Point,
+        ctrl2: Point,
+        to: Point,
+        _: Attributes,
+    ) -> EndpointId {
+        let from = self.current;
+        let event = PathEvent::Cubic {
+            from,
+            ctrl1,
+            ctrl2,
+            to,
+        };
+        self.to(to, event)
+    }
+
+    fn reserve(&mut self, endpoints: usize, _: usize) {
+        self.events.reserve(endpoints);
+    }
+}
+
+/// Point list for builders, either borrowed from [`POINT_SCRATCH`] or owned by the caller
+struct PointBuf {
+    points: Vec<Vec2>,
+    pooled: bool,
+}
+
+impl PointBuf {
+    fn from_iter(points: impl IntoIterator<Item = Vec2>) -> Self {
+        let mut buf = Self {
+            points: POINT_SCRATCH.with_borrow_mut(std::mem::take),
+            pooled: true,
+        };
+        buf.points.clear();
+        buf.points.extend(points);
+        buf
+    }
+
+    fn from_vec(points: Vec<Vec2>) -> Self {
+        Self {
+            points,
+            pooled: false,
+        }
+    }
+}
+
+impl Drop for PointBuf {
+    fn drop(&mut self) {
+        if !self.pooled {
+            return;
+        }
+        let mut points = std::mem::take(&mut self.points);
+        points.clear();
+        POINT_SCRATCH.with_borrow_mut(|scratch| {
+            if points.capacity() > scratch.capacity() {
+                *scratch = points;
+            }
+        });
+    }
+}
 
-struct BatchEntry {
+pub(crate) struct BatchEntry {
+    layer: usize,
     texture_id: Option<usize>,
     shader_id: Option<usize>,
+    mesh: Option<usize>,
+    viewport: Option<usize>,
+    /// Submission order, keeps draw order stable when sorting by layer
+    order: usize,
     geometry: GeometryBatch,
+    /// Baked batches drawn in place of `geometry`, see [`PrimitiveBatch::push_static`]
+    baked: Option<Arc<[StaticBatch]>>,
+    /// Whether it holds shapes of a layer sorted by y, which are moved out when flushed
+    sorted: bool,
+}
+
+/// Part of a shape drawn to a layer sorted by y, copied into place when batches are flushed
+struct SortPiece {
+    /// Batch entry it was written to
+    entry: usize,
+    part: SortPart,
+    /// Shape it belongs to, see [`PrimitiveBatch::draws`]
+    draw: usize,
+    /// Bottom of its shape plus the shape's sort bias, lowest drawn first. NaN until known
+    key: f32,
+}
+
+#[derive(Clone, Copy)]
+enum SortPart {
+    Vertices {
+        start: usize,
+        count: usize,
+        indices: usize,
+        index_count: usize,
+    },
+    Instance(usize),
+    Static,
 }
 
+/// Textures with a reserved white pixel & which one solid-color shapes sample from,
+/// so they can share a batch with sprites from that texture
+#[derive(Clone, Default)]
+pub(crate) struct SolidTexture {
+    pub white_pixels: HashMap<usize, [f32; 2]>,
+    pub primary: Option<usize>,
+}
+
+/// Geometry ready to draw: (texture_id, shader_id, camera_slot, viewport rect, baked batches
+/// drawn instead of the geometry, geometry)
+pub(crate) type DrawEntry<G> = (
+    Option<usize>,
+    Option<usize>,
+    usize,
+    Option<Rect>,
+    Option<Arc<[StaticBatch]>>,
+    G,
+);
+
+/// Collects a frame's primitives into draw-ready batches
+///
+/// Shapes draw in the order they were submitted (painter's order), within each layer.
+/// Only consecutive shapes sharing a texture, shader, mesh & layer are merged, and only if
+/// they take the same draw path (instanced quads/meshes or baked vertices). Any switch
+/// starts a new batch, so interleaving textures costs draw calls but never reorders shapes
+///
+/// Layers sorted by y are the exception: their shapes are put in order of their bottom edge
+/// when flushed & only then merged, again only with neighbours
 pub struct PrimitiveBatch {
     batches: Vec<BatchEntry>,
+    /// Cleared geometry of past frames, reused so steady frames don't allocate
+    spare: Vec<GeometryBatch>,
+    next_order: usize,
+    /// Counts shapes opened & closed, telling apart the pieces of each in [`Self::sort_pieces`]
+    draws: usize,
+    /// Added to the sort key of the next shape closed, see [`RectangleBuilder::sort_bias`]
+    pub(crate) sort_bias: f32,
+    /// Sort key of everything pushed while set, instead of its bottom. Keeps text & its
+    /// background & decorations together, see [`crate::graphics::Graphics::on_layer_sorted_y`]
+    pub(crate) sort_key: Option<f32>,
+    /// Everything drawn to layers sorted by y this frame, in submission order
+    sort_pieces: Vec<SortPiece>,
+    /// Batches of layers sorted by y rebuilt in order, kept for their capacity
+    sorted: Vec<BatchEntry>,
+    /// Batches being baked by [`Self::begin_capture`], whose shapes aren't sorted
+    baking: usize,
+    /// (shape, batch order) of every piece moved into a sorted batch. Only kept while
+    /// capturing, so the capture knows where shapes went
+    moved: Vec<(usize, usize)>,
+    pub(crate) layers: Layers,
+    current_layer: usize,
+    pub(crate) viewports: Vec<Viewport>,
+    current_viewport: Option<usize>,
+    pub(crate) solid: SolidTexture,
+    /// Texture for [`RectangleBuilder::shadow`], created once the renderer exists
+    pub(crate) shadow_texture: Option<usize>,
+    /// Catches rectangles textured with ids that were never loaded
+    pub(crate) texture_check: TextureCheck,
+    /// Font for [`crate::graphics::Graphics::debug_text`], created once the renderer exists
+    pub(crate) debug_font: Option<BitmapFont>,
+    /// Kept across frames, see [`QualityMode`]
+    pub(crate) curve_quality: QualityMode,
+    /// Rectangles drawn with [`RectangleBuilder::pick_id`] this frame
+    pub(crate) picking: Picking,
+    /// Geometry baked by [`crate::graphics::Graphics::bake_static`], kept across frames
+    pub(crate) statics: StaticStore,
+    tint: Color,
+    /// Shapes & batches left out this frame as they couldn't change it, see [`Self::skips`]
+    skipped: usize,
+    /// Applied to everything drawn, see [`Self::push_transform`]
+    transform: Affine2,
+    /// Transforms to return to, innermost last
+    transforms: Vec<Affine2>,
+    /// Vertices written under [`Self::transform`] but not moved by it yet, as (entry, start,
+    /// count). Builders write vertices after allocating, so they're moved once it's done
+    untransformed: Vec<(usize, usize, usize)>,
+    /// Set by [`Self::request_capture`], so the next frame is recorded
+    capture_requested: bool,
+    /// Records draws during a captured frame only
+    recorder: Option<Box<Recorder>>,
+    /// The last captured frame, until taken
+    captured: Option<FrameCapture>,
     max_vertices: usize,
     max_indices: usize,
 }
 
+/// Reports textures drawn by id that were never loaded, which would otherwise draw white
+/// & hide the mistake, see [`crate::graphics::Graphics::texture_exists`]
+#[derive(Default)]
+pub(crate) struct TextureCheck {
+    /// How many texture ids were handed out as of the last check through
+    /// [`crate::graphics::Graphics`], unloaded ones included
+    pub loaded: usize,
+    /// Ids freed by [`crate::graphics::Graphics::unload_texture`]
+    pub unloaded: HashSet<usize>,
+    /// Panic instead of warning, see [`crate::app::App::strict_textures`]
+    pub strict: bool,
+    /// Ids already warned about, so each is reported once
+    reported: HashSet<usize>,
+    /// Whether custom UVs on an untextured primitive were warned about
+    reported_untextured_uvs: bool,
+    /// Whether zero-area UVs were warned about
+    reported_empty_uvs: bool,
+    /// Whether a raw mesh with bad indices was warned about
+    reported_invalid_mesh: bool,
+}
+
+impl TextureCheck {
+    /// Warns (in debug builds) or panics (when strict) if texture `id` used by a `primitive`
+    /// or update was never loaded or has been unloaded
+    pub(crate) fn check(&mut self, id: usize, primitive: &str) {
+        let unloaded = self.unloaded.contains(&id);
+        if id < self.loaded && !unloaded {
+            return;
+        }
+        // each id is warned about once, so only build the message when it's reported
+        let report = self.strict || (cfg!(debug_assertions) && self.reported.insert(id));
+        if !report {
+            return;
+        }
+        let problem = match unloaded {
+            true => format!("{primitive} uses texture {id}, which was unloaded"),
+            false => format!(
+                "{primitive} uses texture {id}, which was never loaded ({} are)",
+                self.loaded
+            ),
+        };
+        if self.strict {
+            panic!("{problem}");
+        }
+        log::warn!(target: "egor_glue::batch", "{problem}; drawing it with the default white texture");
+    }
+
+    /// Warns (in debug builds, once each) about custom `uvs` of a `primitive` that can't be
+    /// what was meant: set without a texture or custom shader to read them, so they sample
+    /// the default white texture, or spanning no area, so a single row or column of texels
+    /// stretches over it. Swapped corners aren't reported, since they're how textures get
+    /// flipped
+    pub(crate) fn check_uvs(&mut self, [u0, v0, u1, v1]: [f32; 4], read: bool, primitive: &str) {
+        if !cfg!(debug_assertions) {
+            return;
+        }
+        if !read && !self.reported_untextured_uvs {
+            self.reported_untextured_uvs = true;
+            log::warn!(
+                target: "egor_glue::batch",
+                "{primitive} given UVs without a texture samples the default white texture; \
+                 set one with `.texture(id)` or use `.sprite(id, uv)`"
+            );
+        }
+        if (u0 == u1 || v0 == v1) && !self.reported_empty_uvs {
+            self.reported_empty_uvs = true;
+            log::warn!(
+                target: "egor_glue::batch",
+                "{primitive} given UVs {:?} spanning no area; they're (u0, v0, u1, v1), not a \
+                 position & size",
+                [u0, v0, u1, v1]
+            );
+        }
+    }
+
+    /// Warns (in debug builds, once) that a raw mesh was skipped because of `problem`, see
+    /// [`crate::graphics::Graphics::mesh_raw`]
+    pub(crate) fn report_invalid_mesh(&mut self, problem: std::fmt::Arguments) {
+        if cfg!(debug_assertions) && !self.reported_invalid_mesh {
+            self.reported_invalid_mesh = true;
+            log::warn!(target: "egor_glue::batch", "raw mesh not drawn: {problem}");
+        }
+    }
+}
+
+/// How finely [`PathBuilder`] curves are tessellated when a path doesn't set
+/// [`PathBuilder::tolerance`], see [`crate::graphics::Graphics::set_curve_quality`]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum QualityMode {
+    /// Same tolerance (in local path units) at every zoom
+    FixedTolerance(f32),
+    /// [`DEFAULT_TOLERANCE`] in screen pixels: curves get more segments as the camera
+    /// (or the path's scale) zooms in & fewer as it zooms out
+    ZoomAdaptive,
+}
+
+impl Default for QualityMode {
+    fn default() -> Self {
+        Self::FixedTolerance(DEFAULT_TOLERANCE)
+    }
+}
+
+impl QualityMode {
+    /// Tolerance for a path drawn with `scale` under a camera at `zoom`
+    pub(crate) fn tolerance(self, zoom: f32, scale: Vec2) -> f32 {
+        match self {
+            Self::FixedTolerance(tolerance) => tolerance.max(MIN_TOLERANCE),
+            Self::ZoomAdaptive => {
+                let magnification = zoom * scale.abs().max_element();
+                (DEFAULT_TOLERANCE / magnification).max(MIN_TOLERANCE)
+            }
+        }
+    }
+}
+
 impl Default for PrimitiveBatch {
     fn default() -> Self {
         Self::new(
@@ -40,21 +461,369 @@ impl PrimitiveBatch {
     pub fn new(max_vertices: usize, max_indices: usize) -> Self {
         Self {
             batches: Vec::new(),
+            spare: Vec::new(),
+            next_order: 0,
+            draws: 0,
+            sort_bias: 0.0,
+            sort_key: None,
+            sort_pieces: Vec::new(),
+            sorted: Vec::new(),
+            baking: 0,
+            moved: Vec::new(),
+            layers: Layers::default(),
+            current_layer: 0,
+            viewports: Vec::new(),
+            current_viewport: None,
+            solid: SolidTexture::default(),
+            shadow_texture: None,
+            debug_font: None,
+            texture_check: TextureCheck::default(),
+            curve_quality: QualityMode::default(),
+            picking: Picking::default(),
+            statics: StaticStore::default(),
+            tint: Color::WHITE,
+            skipped: 0,
+            transform: Affine2::IDENTITY,
+            transforms: Vec::new(),
+            untransformed: Vec::new(),
+            capture_requested: false,
+            recorder: None,
+            captured: None,
             max_vertices,
             max_indices,
         }
     }
 
-    fn new_entry(&self, texture_id: Option<usize>, shader_id: Option<usize>) -> BatchEntry {
+    /// Loads the built-in drop shadow texture & debug font, in that order, so both app kinds
+    /// hand out the same texture ids to user textures
+    pub(crate) fn load_builtin_textures(&mut self, renderer: &mut Renderer) {
+        self.shadow_texture = Some(renderer.add_texture_raw_linear(
+            SHADOW_TEXTURE_SIZE,
+            SHADOW_TEXTURE_SIZE,
+            &shadow_texture_pixels(),
+        ));
+        self.debug_font = Some(load_debug_font(renderer));
+    }
+
+    fn new_entry(
+        &mut self,
+        texture_id: Option<usize>,
+        shader_id: Option<usize>,
+        mesh: Option<usize>,
+    ) -> BatchEntry {
+        let mut geometry = self
+            .spare
+            .pop()
+            .unwrap_or_else(|| GeometryBatch::new(self.max_vertices, self.max_indices));
+        geometry.set_mesh(mesh);
+        self.next_order += 1;
         BatchEntry {
+            layer: self.current_layer,
             texture_id,
             shader_id,
-            geometry: GeometryBatch::new(self.max_vertices, self.max_indices),
+            mesh,
+            viewport: self.current_viewport,
+            order: self.next_order,
+            geometry,
+            baked: None,
+            sorted: false,
+        }
+    }
+
+    /// Keeps a batch's buffers for the next frame
+    fn recycle(spare: &mut Vec<GeometryBatch>, mut geometry: GeometryBatch) {
+        if spare.len() < MAX_SPARE_BATCHES {
+            geometry.clear();
+            spare.push(geometry);
+        }
+    }
+
+    /// Routes subsequent primitives to the layer at `index`, returning the previous layer
+    pub(crate) fn set_layer(&mut self, index: usize) -> usize {
+        std::mem::replace(&mut self.current_layer, index)
+    }
+
+    /// Index of the layer primitives are drawn to
+    pub(crate) fn layer(&self) -> usize {
+        self.current_layer
+    }
+
+    /// Remembers `part` of the last batch entry for sorting if it's in a layer sorted by y
+    fn push_sort_piece(&mut self, part: SortPart) {
+        if self.baking > 0 || !self.layers.get(self.current_layer).sorts_by_y() {
+            return;
+        }
+        let entry = self.batches.len() - 1;
+        self.batches[entry].sorted = true;
+        let piece = SortPiece {
+            entry,
+            part,
+            draw: self.draws,
+            key: self.sort_key.unwrap_or(f32::NAN),
+        };
+        self.sort_pieces.push(piece);
+    }
+
+    /// Routes subsequent primitives to a new viewport covering `rect` (in target pixels),
+    /// returning the previous viewport to restore with [`Self::end_viewport`]
+    pub(crate) fn begin_viewport(&mut self, rect: Rect, camera: Camera) -> Option<usize> {
+        self.viewports.push(Viewport {
+            rect,
+            camera,
+            camera_slot: 0,
+        });
+        self.current_viewport.replace(self.viewports.len() - 1)
+    }
+
+    /// Closes the current viewport, drawing it with `camera` as it was left
+    pub(crate) fn end_viewport(&mut self, previous: Option<usize>, camera: Camera) {
+        if let Some(viewport) = self.current_viewport.map(|i| &mut self.viewports[i]) {
+            viewport.camera = camera;
+        }
+        self.current_viewport = previous;
+    }
+
+    /// Camera the current layer draws with instead of the main one. Viewports ignore layer cameras
+    pub(crate) fn camera_override(&self) -> Option<Camera> {
+        match self.current_viewport {
+            Some(_) => None,
+            None => self.layers.get(self.current_layer).camera_override(),
+        }
+    }
+
+    /// Top-left of the current viewport in target pixels, nested viewports are relative to it
+    pub(crate) fn viewport_origin(&self) -> Vec2 {
+        self.current_viewport
+            .map_or(Vec2::ZERO, |i| self.viewports[i].rect.position)
+    }
+
+    /// Multiplies the color of subsequent primitives by `tint` on top of the current tint,
+    /// returning the previous tint to restore with [`Self::set_tint`]
+    pub(crate) fn push_tint(&mut self, tint: Color) -> Color {
+        let combined = self.tint.multiply(tint);
+        self.set_tint(combined)
+    }
+
+    pub(crate) fn set_tint(&mut self, tint: Color) -> Color {
+        std::mem::replace(&mut self.tint, tint)
+    }
+
+    pub(crate) fn tint(&self) -> Color {
+        self.tint
+    }
+
+    /// Draws subsequent primitives relative to `transform` within the current transform,
+    /// until [`Self::pop_transform`]
+    pub(crate) fn push_transform(&mut self, transform: Affine2) {
+        self.transforms.push(self.transform);
+        self.transform *= transform;
+    }
+
+    /// Returns to the transform before the last [`Self::push_transform`], `false` if there
+    /// was none to pop
+    pub(crate) fn pop_transform(&mut self) -> bool {
+        let Some(previous) = self.transforms.pop() else {
+            return false;
+        };
+        self.transform = previous;
+        true
+    }
+
+    pub(crate) fn transform(&self) -> Affine2 {
+        self.transform
+    }
+
+    /// Replaces the current transform without touching the stack, returning the previous one
+    pub(crate) fn set_transform(&mut self, transform: Affine2) -> Affine2 {
+        std::mem::replace(&mut self.transform, transform)
+    }
+
+    /// Columns & center of an instance (or pick area) moved by the current transform
+    fn transform_frame(&self, [ax, ay, bx, by]: [f32; 4], center: [f32; 2]) -> (Vec2, Vec2, Vec2) {
+        let matrix = self.transform.matrix2;
+        let center = self.transform.transform_point2(center.into());
+        (matrix * vec2(ax, ay), matrix * vec2(bx, by), center)
+    }
+
+    /// Moves the vertices written since the last call by the transform they were drawn under
+    fn apply_transform(&mut self) {
+        for (entry, start, count) in self.untransformed.drain(..) {
+            let vertices = self.batches[entry].geometry.vertices_mut();
+            for vertex in &mut vertices[start..start + count] {
+                let position = Vec2::from(vertex.position);
+                vertex.position = self.transform.transform_point2(position).into();
+            }
+        }
+    }
+
+    /// Most vertices & indices one batch holds
+    pub(crate) fn limits(&self) -> (usize, usize) {
+        (self.max_vertices, self.max_indices)
+    }
+
+    /// Applies the current tint to `color`. Builders call this last, after all other color logic
+    pub(crate) fn tinted(&self, color: Color) -> [f32; 4] {
+        color.multiply(self.tint).components()
+    }
+
+    /// Whether a shape in `color` (before the tint) can be left out, counting it as skipped
+    /// if so. Only untextured shapes drawn by the built-in shader are: its alpha blending
+    /// leaves the target untouched at zero alpha, while a custom shader may blend
+    /// additively or not read the color at all
+    pub(crate) fn skips(
+        &mut self,
+        color: Color,
+        texture_id: Option<usize>,
+        shader_id: Option<usize>,
+    ) -> bool {
+        let invisible = self.tinted(color)[3] == 0.0;
+        let skip = invisible && texture_id.is_none() && shader_id.is_none();
+        self.skipped += skip as usize;
+        skip
+    }
+
+    /// Whether a shape spanning `area` square units before the current transform (e.g. a
+    /// rectangle's width times height) covers none on screen, counting it as skipped if so.
+    /// Negative areas are mirrored shapes & still drawn. A NaN area, from a NaN size or
+    /// radius, is skipped & logged at debug level
+    pub(crate) fn skips_area(&mut self, primitive: &str, area: f32) -> bool {
+        let area = area * self.transform.matrix2.determinant();
+        self.skips_degenerate(primitive, area.is_nan(), area == 0.0)
+    }
+
+    /// Like [`Self::skips_area`] for a line `thickness` wide, skipped when thinner than
+    /// [`MIN_THICKNESS`] on screen
+    pub(crate) fn skips_thickness(&mut self, primitive: &str, thickness: f32) -> bool {
+        let thickness = thickness * self.transform.matrix2.determinant().abs().sqrt();
+        self.skips_degenerate(primitive, thickness.is_nan(), thickness < MIN_THICKNESS)
+    }
+
+    fn skips_degenerate(&mut self, primitive: &str, nan: bool, empty: bool) -> bool {
+        if nan {
+            alloc_guard::exempt(|| {
+                log::debug!(target: "egor_glue::batch", "{primitive} with a NaN size skipped");
+            });
+        }
+        let skip = nan || empty;
+        self.skipped += skip as usize;
+        skip
+    }
+
+    /// Shapes & empty batches skipped since the last call or [`Self::reset`]
+    pub(crate) fn take_skipped(&mut self) -> usize {
+        std::mem::take(&mut self.skipped)
+    }
+
+    /// Records every draw of the next frame, see
+    /// [`crate::graphics::Graphics::capture_next_frame`]
+    pub(crate) fn request_capture(&mut self) {
+        self.capture_requested = true;
+    }
+
+    pub(crate) fn take_capture(&mut self) -> Option<FrameCapture> {
+        self.captured.take()
+    }
+
+    /// Starts recording a shape drawn from the caller's location while capturing. Every
+    /// builder opened here must [`Self::close_draw`] when dropped
+    #[track_caller]
+    pub(crate) fn open_draw(&mut self, kind: DrawKind) {
+        self.draws += 1;
+        if let Some(recorder) = self.recorder.as_deref_mut() {
+            let location = Location::caller();
+            let layer = self.layers.get(self.current_layer).name();
+            alloc_guard::exempt(|| recorder.open(kind, location, layer));
+        }
+    }
+
+    /// Finishes a shape: applies the transform to the vertices it wrote, keys it for sorting
+    /// & records it if it was opened by [`Self::open_draw`]
+    pub(crate) fn close_draw(&mut self) {
+        self.apply_transform();
+        self.key_draw();
+        if let Some(recorder) = self.recorder.as_deref_mut() {
+            let (batches, draw) = (&self.batches, self.draws);
+            let entry = |i: usize| (batches[i].order, &batches[i].geometry);
+            alloc_guard::exempt(|| recorder.close(draw, entry));
+        }
+        self.draws += 1;
+    }
+
+    /// Gives every piece of the shape being closed the same sort key, so e.g. a shadow stays
+    /// under its rectangle
+    fn key_draw(&mut self) {
+        let bias = std::mem::take(&mut self.sort_bias);
+        let draw = self.draws;
+        let pieces = &mut self.sort_pieces;
+        let start = pieces
+            .iter()
+            .rposition(|p| p.draw != draw)
+            .map_or(0, |i| i + 1);
+        let batches = &self.batches;
+        let bottom = (pieces[start..].iter())
+            .map(|p| p.bottom(batches))
+            .fold(f32::NEG_INFINITY, f32::max);
+        for piece in &mut pieces[start..] {
+            piece.key = self.sort_key.unwrap_or(bottom + bias);
+        }
+    }
+
+    /// Adds part of the shape being recorded, made of the last batch entry's geometry
+    fn record(
+        &mut self,
+        piece: impl FnOnce(usize) -> Piece,
+        texture_id: Option<usize>,
+        shader_id: Option<usize>,
+    ) {
+        if let Some(recorder) = self.recorder.as_deref_mut() {
+            let piece = piece(self.batches.len() - 1);
+            alloc_guard::exempt(|| recorder.piece(piece, texture_id, shader_id));
+        }
+    }
+
+    /// Turns the recording into a [`FrameCapture`] of the flushed batches
+    fn finish_capture(&mut self) {
+        let Some(recorder) = self.recorder.take() else {
+            return;
+        };
+        let _unguarded = alloc_guard::pause();
+        let batches = self
+            .batches
+            .iter()
+            .map(|e| {
+                let batch = BatchRecord {
+                    layer: self.layers.get(e.layer).name().to_string(),
+                    texture: e.texture_id,
+                    shader: e.shader_id,
+                    mesh: e.mesh,
+                    viewport: e.viewport.map(|i| self.viewports[i].rect),
+                    vertices: e.geometry.vertices().len(),
+                    indices: e.geometry.index_count(),
+                    instances: e.geometry.instance_count(),
+                    baked: e.baked.as_ref().map_or(0, |baked| baked.len()),
+                    draws: Vec::new(),
+                };
+                (e.order, batch)
+            })
+            .collect();
+        self.captured = Some(recorder.finish(batches, &self.moved));
+    }
+
+    /// Texture & UV solid-color shapes sample: the primary atlas' white pixel if one is set,
+    /// otherwise no texture (the default white one) & the origin
+    pub(crate) fn solid_texture(&self) -> (Option<usize>, [f32; 2]) {
+        let solid = &self.solid;
+        match solid
+            .primary
+            .and_then(|id| Some((id, *solid.white_pixels.get(&id)?)))
+        {
+            Some((id, uv)) => (Some(id), uv),
+            None => (None, [0.0, 0.0]),
         }
     }
 
     /// Allocates space for vertices & indices in the current batch if it matches
-    /// `texture_id` + `shader_id`, otherwise starts a new batch.
+    /// `texture_id` + `shader_id` (and layer) & holds no instances, otherwise starts a new batch.
     /// Used by paths, polygons, and other baked geometry primitives
     pub(crate) fn allocate(
         &mut self,
@@ -63,21 +832,45 @@ impl PrimitiveBatch {
         texture_id: Option<usize>,
         shader_id: Option<usize>,
     ) -> Option<(&mut [Vertex], &mut [u16], u16)> {
-        // only reuse last batch if it matches and won't overflow
-        if let Some(last) = self.batches.last()
-            && last.texture_id == texture_id
-            && last.shader_id == shader_id
-            && !last.geometry.would_overflow(vert_count, idx_count)
-        {
-            return self
-                .batches
-                .last_mut()
-                .unwrap()
-                .geometry
-                .try_allocate(vert_count, idx_count);
+        // only reuse last batch if it matches and won't overflow. Instances draw before baked
+        // geometry within a batch, so mixing them would move earlier shapes on top
+        let reuse = self.batches.last().is_some_and(|last| {
+            last.layer == self.current_layer
+                && last.texture_id == texture_id
+                && last.shader_id == shader_id
+                && last.viewport == self.current_viewport
+                && last.baked.is_none()
+                && last.geometry.instance_count() == 0
+                && !last.geometry.would_overflow(vert_count, idx_count)
+        });
+        if !reuse {
+            let entry = self.new_entry(texture_id, shader_id, None);
+            self.batches.push(entry);
         }
 
-        self.batches.push(self.new_entry(texture_id, shader_id));
+        let geometry = &self.batches.last().unwrap().geometry;
+        let (start, geometry_indices) = (geometry.vertices().len(), geometry.index_count());
+        let fits = !geometry.would_overflow(vert_count, idx_count);
+        if fits && self.transform != Affine2::IDENTITY {
+            let range = (self.batches.len() - 1, start, vert_count);
+            self.untransformed.push(range);
+        }
+        if fits && self.recorder.is_some() {
+            let piece = |entry| Piece::Vertices {
+                entry,
+                start,
+                count: vert_count,
+            };
+            self.record(piece, texture_id, shader_id);
+        }
+        if fits {
+            self.push_sort_piece(SortPart::Vertices {
+                start,
+                count: vert_count,
+                indices: geometry_indices,
+                index_count: idx_count,
+            });
+        }
         self.batches
             .last_mut()
             .unwrap()
@@ -85,57 +878,432 @@ impl PrimitiveBatch {
             .try_allocate(vert_count, idx_count)
     }
 
-    /// Pushes an instance into the current batch if it matches `texture_id` + `shader_id`,
-    /// otherwise starts a new batch. Preserves insertion order for correct draw ordering.
+    /// Pushes an instance into the current batch if it matches `texture_id` + `shader_id`
+    /// (and layer) & holds no baked geometry, otherwise starts a new batch.
+    /// Preserves insertion order for correct draw ordering.
     pub(crate) fn push_instance(
         &mut self,
         instance: Instance,
         texture_id: Option<usize>,
         shader_id: Option<usize>,
     ) {
-        if let Some(last) = self.batches.last_mut()
-            && last.texture_id == texture_id
-            && last.shader_id == shader_id
-        {
-            last.geometry.push_instance(instance);
-            return;
+        self.push_mesh_instance(instance, None, texture_id, shader_id);
+    }
+
+    /// Like [`Self::push_instance`], but the instance repeats a retained mesh instead of
+    /// the unit quad. Instances of different meshes never share a batch
+    pub(crate) fn push_mesh_instance(
+        &mut self,
+        mut instance: Instance,
+        mesh: Option<usize>,
+        texture_id: Option<usize>,
+        shader_id: Option<usize>,
+    ) {
+        if self.transform != Affine2::IDENTITY {
+            let (col0, col1, center) = self.transform_frame(instance.affine, instance.translate);
+            instance.affine = [col0.x, col0.y, col1.x, col1.y];
+            instance.translate = center.into();
+        }
+        let reuse = self.batches.last().is_some_and(|last| {
+            last.layer == self.current_layer
+                && last.texture_id == texture_id
+                && last.shader_id == shader_id
+                && last.mesh == mesh
+                && last.viewport == self.current_viewport
+                && last.baked.is_none()
+                && last.geometry.index_count() == 0
+        });
+        if !reuse {
+            let entry = self.new_entry(texture_id, shader_id, mesh);
+            self.batches.push(entry);
         }
 
-        let mut entry = self.new_entry(texture_id, shader_id);
-        entry.geometry.push_instance(instance);
+        let geometry = &mut self.batches.last_mut().unwrap().geometry;
+        let index = geometry.instance_count();
+        geometry.push_instance(instance);
+        let piece = |entry| Piece::Instance {
+            entry,
+            index,
+            quad: mesh.is_none(),
+        };
+        self.record(piece, texture_id, shader_id);
+        self.push_sort_piece(SortPart::Instance(index));
+    }
+
+    /// Draws the batches baked as `id` at this point in the current layer & viewport. Freed
+    /// ids draw nothing
+    pub(crate) fn push_static(&mut self, id: usize) {
+        let Some(baked) = self.statics.get(id).cloned() else {
+            return;
+        };
+        let vertices = baked.iter().map(|b| b.geometry.vertices().len()).sum();
+        let mut entry = self.new_entry(None, None, None);
+        entry.baked = Some(baked);
         self.batches.push(entry);
+        self.record(|entry| Piece::Static { entry, vertices }, None, None);
+        self.push_sort_piece(SortPart::Static);
     }
 
-    /// Moves all batch entries out, consuming their geometry.
-    /// Used for ephemeral paths (offscreen rendering) where batch reuse isn't needed
-    pub(crate) fn take(&mut self) -> Vec<(Option<usize>, Option<usize>, GeometryBatch)> {
+    /// Routes primitives into a batch list of their own until [`Self::end_capture`], which
+    /// returns them. Captured primitives never merge into batches from before
+    pub(crate) fn begin_capture(&mut self) -> Vec<BatchEntry> {
+        self.baking += 1;
         std::mem::take(&mut self.batches)
+    }
+
+    /// Texture, shader & geometry of the batches pushed since [`Self::begin_capture`] in
+    /// draw order, restoring the batches from before. Baked chunks drawn meanwhile are left
+    /// out, their buffers can't be moved into another chunk
+    pub(crate) fn end_capture(&mut self, previous: Vec<BatchEntry>) -> Vec<StaticBatch> {
+        self.baking -= 1;
+        let captured = std::mem::replace(&mut self.batches, previous);
+        captured
             .into_iter()
-            .map(|entry| (entry.texture_id, entry.shader_id, entry.geometry))
+            .flat_map(|entry| match entry.baked {
+                Some(_) => {
+                    log::warn!(
+                        "a static chunk drawn while baking another isn't baked into it, \
+                         draw it next to the new chunk instead"
+                    );
+                    None
+                }
+                None => Some(StaticBatch {
+                    texture_id: entry.texture_id,
+                    shader_id: entry.shader_id,
+                    geometry: entry.geometry,
+                }),
+            })
             .collect()
     }
 
-    /// Iterates over active batch entries for drawing.
-    /// Returns (texture_id, shader_id, &mut GeometryBatch) for each entry
-    pub(crate) fn iter_mut(
-        &mut self,
-    ) -> impl Iterator<Item = (Option<usize>, Option<usize>, &mut GeometryBatch)> {
-        self.batches
-            .iter_mut()
-            .map(|e| (e.texture_id, e.shader_id, &mut e.geometry))
+    /// Orders batches by layer (keeping submission order within a layer)
+    /// & recycles batches of hidden layers & batches left empty, e.g. by shapes too big to
+    /// fit one
+    fn flush_layers(&mut self) {
+        let _guard = alloc_guard::scope();
+        self.sort_by_y();
+        let empty = |e: &mut BatchEntry| e.baked.is_none() && e.geometry.is_empty();
+        for entry in self.batches.extract_if(.., empty) {
+            Self::recycle(&mut self.spare, entry.geometry);
+            self.skipped += 1;
+        }
+        let layers = &self.layers;
+        // unstable sorts don't allocate, `order` keeps equal layers in place
+        self.batches.sort_unstable_by_key(|e| {
+            let layer = layers.get(e.layer);
+            (!layer.is_enabled(), layers.sort_key(e.layer), e.order)
+        });
+        while let Some(hidden) = self.batches.pop_if(|e| !layers.get(e.layer).is_enabled()) {
+            Self::recycle(&mut self.spare, hidden.geometry);
+        }
     }
 
-    /// Clears all batches, dropping their geometry. Called at the end of each frame
-    pub(crate) fn reset(&mut self) {
-        self.batches.clear();
+    /// Rebuilds the batches of layers sorted by y with their shapes in order of their keys,
+    /// merging neighbours that can share a batch. The batches they were drawn to are recycled
+    fn sort_by_y(&mut self) {
+        if self.sort_pieces.is_empty() {
+            return;
+        }
+        let batches = &self.batches;
+        // shapes egor draws outside a builder aren't keyed when closed
+        for piece in &mut self.sort_pieces {
+            if piece.key.is_nan() {
+                piece.key = piece.bottom(batches);
+            }
+        }
+        // unstable sorts don't allocate. Equal keys keep shapes in submission order & each
+        // shape's pieces together, in the order `place` wrote them
+        self.sort_pieces.sort_unstable_by(|a, b| {
+            let layer = |p: &SortPiece| batches[p.entry].layer;
+            (layer(a).cmp(&layer(b)))
+                .then(a.key.total_cmp(&b.key))
+                .then(a.draw.cmp(&b.draw))
+                .then(a.place().cmp(&b.place()))
+        });
+
+        let mut sorted = std::mem::take(&mut self.sorted);
+        for piece in &self.sort_pieces {
+            let source = &batches[piece.entry];
+            if !sorted.last().is_some_and(|last| piece.fits(source, last)) {
+                let mut geometry = self
+                    .spare
+                    .pop()
+                    .unwrap_or_else(|| GeometryBatch::new(self.max_vertices, self.max_indices));
+                geometry.set_mesh(source.mesh);
+                self.next_order += 1;
+                let entry = BatchEntry {
+                    order: self.next_order,
+                    geometry,
+                    baked: None,
+                    sorted: false,
+                    ..*source
+                };
+                sorted.push(entry);
+            }
+            let batch = sorted.last_mut().unwrap();
+            piece.copy(source, batch);
+            if self.recorder.is_some() {
+                alloc_guard::exempt(|| self.moved.push((piece.draw, batch.order)));
+            }
+        }
+        for entry in self.batches.extract_if(.., |e| e.sorted) {
+            Self::recycle(&mut self.spare, entry.geometry);
+        }
+        self.batches.append(&mut sorted);
+        self.sorted = sorted;
+        self.sort_pieces.clear();
     }
-}
 
-/// Common anchor options
-pub enum Anchor {
-    Center,
-    TopLeft,
-}
+    /// Moves all batch entries out in draw order, consuming their geometry.
+    /// Used for ephemeral paths (offscreen rendering) where batch reuse isn't needed
+    pub(crate) fn take(&mut self) -> Vec<DrawEntry<GeometryBatch>> {
+        self.flush_layers();
+        self.finish_capture();
+        let (layers, viewports) = (&self.layers, &self.viewports);
+        std::mem::take(&mut self.batches)
+            .into_iter()
+            .map(|e| {
+                let (slot, rect) = Self::camera_in(layers, viewports, &e);
+                (e.texture_id, e.shader_id, slot, rect, e.baked, e.geometry)
+            })
+            .collect()
+    }
+
+    /// Iterates over active batch entries in draw order
+    pub(crate) fn iter_mut(&mut self) -> impl Iterator<Item = DrawEntry<&mut GeometryBatch>> {
+        self.flush_layers();
+        self.finish_capture();
+        let (layers, viewports) = (&self.layers, &self.viewports);
+        self.batches.iter_mut().map(|e| {
+            let (slot, rect) = Self::camera_in(layers, viewports, e);
+            let baked = e.baked.clone();
+            (
+                e.texture_id,
+                e.shader_id,
+                slot,
+                rect,
+                baked,
+                &mut e.geometry,
+            )
+        })
+    }
+
+    /// Camera slot & viewport rect of an entry. A viewport's camera replaces the layer's
+    fn camera_in(
+        layers: &Layers,
+        viewports: &[Viewport],
+        entry: &BatchEntry,
+    ) -> (usize, Option<Rect>) {
+        match entry.viewport.map(|i| &viewports[i]) {
+            Some(viewport) => (viewport.camera_slot, Some(viewport.rect)),
+            None => (layers.get(entry.layer).camera_slot, None),
+        }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.batches.is_empty()
+    }
+
+    /// Texture, shader & number of shapes of each batch, in submission order, counting each
+    /// batch of a [`Self::push_static`] chunk. Baked geometry counts a shape per 2 triangles.
+    /// Empty batches are left out as they never draw
+    pub(crate) fn batch_sizes(
+        &self,
+    ) -> impl Iterator<Item = (Option<usize>, Option<usize>, usize)> {
+        self.batches
+            .iter()
+            .filter(|e| e.baked.is_some() || !e.geometry.is_empty())
+            .flat_map(|e| {
+                let own = e
+                    .baked
+                    .is_none()
+                    .then_some((e.texture_id, e.shader_id, &e.geometry));
+                let baked = e.baked.iter().flat_map(|baked| baked.iter());
+                own.into_iter()
+                    .chain(baked.map(|b| (b.texture_id, b.shader_id, &b.geometry)))
+            })
+            .map(|(texture, shader, geometry)| {
+                let shapes = geometry.instance_count().max(geometry.index_count() / 6);
+                (texture, shader, shapes.max(1))
+            })
+    }
+
+    /// Clears all batches, keeping their buffers for reuse. Unlike [`Self::reset`] later
+    /// primitives still go to the current layer & viewport
+    pub(crate) fn clear_batches(&mut self) {
+        // newest first, so next frame's batches pop the buffers they grew into this frame
+        self.batches
+            .sort_unstable_by_key(|e| std::cmp::Reverse(e.order));
+        for entry in self.batches.drain(..) {
+            Self::recycle(&mut self.spare, entry.geometry);
+        }
+        self.next_order = 0;
+        self.sort_pieces.clear();
+        self.moved.clear();
+    }
+
+    /// Clears all batches, keeping their buffers for reuse. Called at the end of each frame.
+    /// Layer settings are kept
+    pub(crate) fn reset(&mut self) {
+        let _guard = alloc_guard::scope();
+        self.clear_batches();
+        self.picking.clear();
+        self.skipped = 0;
+        // a frame that never flushed (e.g. a minimized window) isn't captured
+        if std::mem::take(&mut self.capture_requested) {
+            self.recorder = Some(alloc_guard::exempt(Box::default));
+        }
+        self.current_layer = 0;
+        self.viewports.clear();
+        self.current_viewport = None;
+        self.tint = Color::WHITE;
+        self.transform = Affine2::IDENTITY;
+        self.transforms.clear();
+        self.untransformed.clear();
+        self.sort_bias = 0.0;
+        self.sort_key = None;
+    }
+}
+
+impl SortPiece {
+    /// Lowest point of what it draws. Mesh instances only count their position
+    fn bottom(&self, batches: &[BatchEntry]) -> f32 {
+        let entry = &batches[self.entry];
+        let geometry = &entry.geometry;
+        let lowest = |vertices: &[Vertex]| {
+            (vertices.iter())
+                .map(|v| v.position[1])
+                .fold(f32::NEG_INFINITY, f32::max)
+        };
+        match self.part {
+            SortPart::Vertices { start, count, .. } => {
+                lowest(&geometry.vertices()[start..start + count])
+            }
+            SortPart::Instance(index) => {
+                let instance = &geometry.instances()[index];
+                let [_, ay, _, by] = instance.affine;
+                match entry.mesh {
+                    None => instance.translate[1] + (ay.abs() + by.abs()) / 2.0,
+                    Some(_) => instance.translate[1],
+                }
+            }
+            SortPart::Static => (entry.baked.iter().flat_map(|baked| baked.iter()))
+                .map(|b| lowest(b.geometry.vertices()))
+                .fold(f32::NEG_INFINITY, f32::max),
+        }
+    }
+
+    /// Submission order within its batch, breaking ties between equal keys
+    fn place(&self) -> (usize, usize) {
+        match self.part {
+            SortPart::Vertices { start, .. } => (self.entry, start),
+            SortPart::Instance(index) => (self.entry, index),
+            SortPart::Static => (self.entry, 0),
+        }
+    }
+
+    /// Whether it can be added to `batch`, holding what was drawn right before it
+    fn fits(&self, source: &BatchEntry, batch: &BatchEntry) -> bool {
+        let geometry = &batch.geometry;
+        let same_path = match self.part {
+            SortPart::Vertices {
+                count, index_count, ..
+            } => geometry.instance_count() == 0 && !geometry.would_overflow(count, index_count),
+            SortPart::Instance(_) => geometry.index_count() == 0,
+            SortPart::Static => false,
+        };
+        same_path
+            && batch.baked.is_none()
+            && batch.layer == source.layer
+            && batch.texture_id == source.texture_id
+            && batch.shader_id == source.shader_id
+            && batch.mesh == source.mesh
+            && batch.viewport == source.viewport
+    }
+
+    /// Copies it from `source` to the end of `batch`
+    fn copy(&self, source: &BatchEntry, batch: &mut BatchEntry) {
+        let from = &source.geometry;
+        match self.part {
+            SortPart::Vertices {
+                start,
+                count,
+                indices,
+                index_count,
+            } => {
+                let Some((vertices, to, base)) = batch.geometry.try_allocate(count, index_count)
+                else {
+                    return;
+                };
+                vertices.copy_from_slice(&from.vertices()[start..start + count]);
+                let offset = base as i32 - start as i32;
+                for (to, index) in to.iter_mut().zip(&from.indices()[indices..]) {
+                    *to = (*index as i32 + offset) as u16;
+                }
+            }
+            SortPart::Instance(index) => batch.geometry.push_instance(from.instances()[index]),
+            SortPart::Static => batch.baked = source.baked.clone(),
+        }
+    }
+}
+
+/// Draws `entries` in order on a `target_size` target, limiting entries inside a viewport to
+/// its rect. Viewports that fall outside the target (e.g. after a resize) draw nothing.
+/// Returns how many entries were drawn
+pub(crate) fn draw_entries<'g>(
+    renderer: &Renderer,
+    r_pass: &mut RenderPass<'_>,
+    entries: impl IntoIterator<Item = DrawEntry<&'g mut GeometryBatch>>,
+    (w, h): (u32, u32),
+) -> usize {
+    let full = Rect::new(Vec2::ZERO, vec2(w as f32, h as f32));
+    let mut current = None;
+    let mut drawn = 0;
+    for (tex_id, shader_id, camera, viewport, baked, batch) in entries {
+        if viewport != current {
+            let rect = viewport.unwrap_or(full);
+            let min = rect.min().clamp(Vec2::ZERO, full.size);
+            let size = (rect.max().min(full.size) - min).max(Vec2::ZERO);
+            if size.x < 1.0 || size.y < 1.0 {
+                batch.clear();
+                continue;
+            }
+            r_pass.set_viewport(min.x, min.y, size.x, size.y, 0.0, 1.0);
+            r_pass.set_scissor_rect(min.x as u32, min.y as u32, size.x as u32, size.y as u32);
+            current = viewport;
+        }
+        match baked {
+            Some(baked) => {
+                for b in baked.iter() {
+                    renderer.draw_uploaded_batch(
+                        r_pass,
+                        &b.geometry,
+                        b.texture_id,
+                        b.shader_id,
+                        camera,
+                    );
+                }
+                drawn += baked.len();
+            }
+            None => {
+                renderer.draw_batch_with_camera(r_pass, batch, tex_id, shader_id, camera);
+                drawn += 1;
+            }
+        }
+    }
+    // anything drawn after (text) covers the whole target again
+    if current.is_some() {
+        r_pass.set_viewport(0.0, 0.0, full.size.x, full.size.y, 0.0, 1.0);
+        r_pass.set_scissor_rect(0, 0, w, h);
+    }
+    drawn
+}
+
+/// Common anchor options
+pub enum Anchor {
+    Center,
+    TopLeft,
+}
 
 /// Builder for (textured) rectangles, drawn on `Drop`
 pub struct RectangleBuilder<'a> {
@@ -146,8 +1314,28 @@ pub struct RectangleBuilder<'a> {
     size: Vec2,
     rotation: f32,
     color: Color,
+    color_mode: ColorMode,
     uvs: [f32; 4],
+    /// Set by [`Self::uv`] & [`Self::uv_rect`], which need a texture to mean anything
+    custom_uvs: bool,
+    uv_offset: Vec2,
+    uv_scale: Vec2,
     tex_id: Option<usize>,
+    layer: u32,
+    params: [f32; 4],
+    fill: bool,
+    stroke: Option<f32>,
+    stroke_color: Option<Color>,
+    shadow: Option<Shadow>,
+    pick_id: Option<u64>,
+    pick_alpha_threshold: Option<f32>,
+}
+
+#[derive(Clone, Copy)]
+struct Shadow {
+    offset: Vec2,
+    softness: f32,
+    color: Color,
 }
 
 /// Builds a rectangle with configurable position, size, color, anchor, rotation, & texture
@@ -161,8 +1349,20 @@ impl<'a> RectangleBuilder<'a> {
             size: vec2(64.0, 64.0),
             rotation: 0.0,
             color: Color::WHITE,
+            color_mode: ColorMode::Multiply,
             uvs: [0.0, 0.0, 1.0, 1.0],
+            custom_uvs: false,
+            uv_offset: Vec2::ZERO,
+            uv_scale: Vec2::ONE,
             tex_id: None,
+            layer: 0,
+            params: [0.0; 4],
+            fill: true,
+            stroke: None,
+            stroke_color: None,
+            shadow: None,
+            pick_id: None,
+            pick_alpha_threshold: None,
         }
     }
     /// Sets the position & size from a [`Rect`].
@@ -182,37 +1382,155 @@ impl<'a> RectangleBuilder<'a> {
         self.position = position.into();
         self
     }
-    /// Sets the size of the rectangle
+    /// Sets the size of the rectangle. A negative width or height mirrors it (texture
+    /// included) across its position, so a size lerped through zero flips over. Rectangles
+    /// with a zero width or height draw nothing, not even their outline or shadow
     pub fn size(mut self, size: Vec2) -> Self {
         self.size = size;
         self
     }
+    /// Moves the rectangle's place in a layer sorted by y by `bias` units, e.g. a negative
+    /// bias for a shadow or a rug that's always stepped on, see
+    /// [`crate::graphics::Graphics::on_layer_sorted_y`]. Ignored elsewhere
+    pub fn sort_bias(self, bias: f32) -> Self {
+        self.batch.sort_bias = bias;
+        self
+    }
     /// Sets the color of the rectangle
     pub fn color(mut self, color: Color) -> Self {
         self.color = color;
         self
     }
-    /// Sets rotation (in radians) around the rectangle's center
-    /// 0 radians points up (positive Y), increasing clockwise
+    /// Sets how the color combines with the texture. Defaults to [`ColorMode::Multiply`];
+    /// [`ColorMode::Replace`] flashes a sprite a solid color without touching its texture
+    pub fn color_mode(mut self, mode: ColorMode) -> Self {
+        self.color_mode = mode;
+        self
+    }
+    /// Sets rotation in radians around the rectangle's center, from +X toward +Y
+    /// (clockwise on screen). Textures stay upright at 0
     pub fn rotate(mut self, angle: f32) -> Self {
-        self.rotation = angle + std::f32::consts::FRAC_PI_2;
+        self.rotation = angle;
         self
     }
+    /// Same as [`Self::rotate`] in degrees
+    pub fn rotate_deg(self, degrees: f32) -> Self {
+        self.rotate(degrees.to_radians())
+    }
     /// Sets the texture ID for the rectangle
-    pub fn texture(mut self, id: usize) -> Self {
-        self.tex_id = Some(id);
+    pub fn texture(mut self, id: impl Into<TextureId>) -> Self {
+        self.tex_id = Some(id.into().0);
+        self
+    }
+    /// Uses a layer of a texture array, as returned by
+    /// [`crate::graphics::Graphics::load_texture_into_array`]. Rectangles sharing the array
+    /// draw in one batch whatever their layer
+    pub fn texture_layer(mut self, (id, layer): (impl Into<TextureId>, u32)) -> Self {
+        self.tex_id = Some(id.into().0);
+        self.layer = layer;
+        self
+    }
+    /// Uses a region of a texture atlas, setting both the texture & UVs
+    pub fn texture_region(mut self, sub: SubTexture) -> Self {
+        self.tex_id = Some(sub.texture_id);
+        self.uvs = sub.uv;
+        self
+    }
+    /// Draws the `uv` area of a texture, e.g. an animation frame. Same as
+    /// [`Self::texture`] & [`Self::uv`], which do nothing visible without each other
+    pub fn sprite(mut self, id: impl Into<TextureId>, uv: [f32; 4]) -> Self {
+        self.tex_id = Some(id.into().0);
+        self.uvs = uv;
+        self.custom_uvs = true;
         self
     }
-    /// Custom UV coordinates as (u0, v0, u1, v1).
-    /// Defaults to full texture coverage [0, 0, 1, 1]
+    /// Custom UV coordinates as (u0, v0, u1, v1), needing a [`Self::texture`].
+    /// Defaults to full texture coverage [0, 0, 1, 1]; swap the corners to flip it.
+    /// Debug builds warn when there's no texture or the coordinates span no area
     pub fn uv(mut self, coords: [f32; 4]) -> Self {
         self.uvs = coords;
+        self.custom_uvs = true;
+        self
+    }
+    /// Same as [`Self::uv`] with the area as a [`Rect`] in UV space, from its min to its
+    /// max corner
+    pub fn uv_rect(self, rect: Rect) -> Self {
+        let (min, max) = (rect.min(), rect.max());
+        self.uv([min.x, min.y, max.x, max.y])
+    }
+    /// Shifts the sampled area by `offset`, measured in widths & heights of the UV rect
+    /// (the whole texture or an atlas region). Animate it to scroll a texture
+    pub fn uv_offset(mut self, offset: Vec2) -> Self {
+        self.uv_offset = offset;
+        self
+    }
+    /// Samples `scale` times the UV rect, e.g. `vec2(4.0, 1.0)` shows it 4 times across.
+    /// Tiling needs a repeating texture (see
+    /// [`crate::graphics::Graphics::load_texture_repeating`]); it repeats the whole texture,
+    /// so atlas regions only scale down cleanly
+    pub fn uv_scale(mut self, scale: Vec2) -> Self {
+        self.uv_scale = scale;
+        self
+    }
+    /// Four numbers for a custom shader to read as `input.params`, e.g. a dissolve amount
+    /// per sprite. They travel with the rectangle's instance, so rectangles with different
+    /// params still draw in one batch. Only the fill carries them; the built-in shader
+    /// ignores them
+    pub fn params(mut self, params: [f32; 4]) -> Self {
+        self.params = params;
+        self
+    }
+    /// Draws an outline of `thickness` world units centered on the rectangle's edges.
+    /// Outlines thinner than 0.001 world units aren't drawn
+    pub fn stroke(mut self, thickness: f32) -> Self {
+        self.stroke = Some(thickness);
+        self
+    }
+    /// Sets the outline color. Defaults to the fill color
+    pub fn stroke_color(mut self, color: Color) -> Self {
+        self.stroke_color = Some(color);
+        self
+    }
+    /// Enable or disable the fill (defaults to true). Use with [`Self::stroke`] for outlines
+    pub fn fill(mut self, fill: bool) -> Self {
+        self.fill = fill;
+        self
+    }
+    /// Draws a soft drop shadow under the rectangle, moved by `offset` world units. It fades
+    /// out over `softness` units on both sides of the edges & corners stay round.
+    /// Shadows share one built-in texture, so a grid of shadowed cards stays in one batch
+    pub fn shadow(mut self, offset: Vec2, softness: f32, color: Color) -> Self {
+        self.shadow = Some(Shadow {
+            offset,
+            softness: softness.max(0.0),
+            color,
+        });
+        self
+    }
+    /// Makes the rectangle pickable as `id` for the rest of the frame, see
+    /// [`crate::graphics::Graphics::pick`]
+    pub fn pick_id(mut self, id: u64) -> Self {
+        self.pick_id = Some(id);
+        self
+    }
+    /// Picks fall through texels with an alpha below `threshold`, e.g. the transparent
+    /// corners of a card. Needs a texture loaded with
+    /// [`crate::graphics::Graphics::load_texture_pickable`]; others pick on their whole quad
+    pub fn pick_alpha_threshold(mut self, threshold: f32) -> Self {
+        self.pick_alpha_threshold = Some(threshold);
         self
     }
 }
 
-impl Drop for RectangleBuilder<'_> {
-    fn drop(&mut self) {
+impl RectangleBuilder<'_> {
+    fn submit(&mut self) {
+        let _guard = alloc_guard::scope();
+        if self
+            .batch
+            .skips_area("rectangle", self.size.x * self.size.y)
+        {
+            return;
+        }
         let offset = match self.anchor {
             Anchor::TopLeft => Vec2::ZERO,
             Anchor::Center => -self.size / 2.0,
@@ -220,19 +1538,233 @@ impl Drop for RectangleBuilder<'_> {
         let center = self.position + offset + self.size / 2.0;
         let rot = Mat2::from_angle(self.rotation);
         let (col0, col1) = (rot.x_axis * self.size.x, rot.y_axis * self.size.y);
-        let color = self.color.components();
 
-        self.batch.push_instance(
-            Instance::new(
+        let shadow_texture = self.shadow.and(self.batch.shadow_texture);
+        if let (Some(shadow), Some(texture)) = (self.shadow, shadow_texture)
+            && !self.batch.skips(shadow.color, None, self.shader_id)
+        {
+            push_shadow(
+                self.batch,
+                self.shader_id,
+                texture,
+                (center + shadow.offset, rot, self.size),
+                shadow,
+            );
+        }
+
+        if let Some(id) = self.tex_id {
+            self.batch.texture_check.check(id, "rectangle");
+        }
+        if self.custom_uvs {
+            // custom shaders may use UVs for something else than sampling a texture
+            let read = self.tex_id.is_some() || self.shader_id.is_some();
+            let check = &mut self.batch.texture_check;
+            check.check_uvs(self.uvs, read, "rectangle");
+        }
+        if self.fill && !self.batch.skips(self.color, self.tex_id, self.shader_id) {
+            let (tex_id, uvs) = match (self.tex_id, shadow_texture) {
+                (Some(id), _) => (
+                    Some(id),
+                    transform_uvs(self.uvs, self.uv_offset, self.uv_scale),
+                ),
+                // the shadow texture's opaque center keeps the fill in the shadow's batch
+                (None, Some(texture)) => (Some(texture), [0.5; 4]),
+                // custom shaders may read the UVs, so only plain fills move to the white pixel
+                (None, None) => match self.batch.solid_texture() {
+                    (Some(id), [u, v]) if self.shader_id.is_none() => (Some(id), [u, v, u, v]),
+                    _ => (None, transform_uvs(self.uvs, self.uv_offset, self.uv_scale)),
+                },
+            };
+            let instance = Instance::new(
                 [col0.x, col0.y, col1.x, col1.y],
                 [center.x, center.y],
+                self.batch.tinted(self.color),
+                uvs,
+            )
+            .with_color_mode(self.color_mode)
+            .with_layer(self.layer)
+            .with_params(self.params);
+            debug_assert_finite(
+                "rectangle",
+                instance.affine.iter().chain(&instance.translate),
+            );
+            self.batch.push_instance(instance, tex_id, self.shader_id);
+        }
+
+        // hit areas follow the transform like the fill does
+        let affine = [col0.x, col0.y, col1.x, col1.y];
+        let (pick0, pick1, pick_center) = self.batch.transform_frame(affine, center.into());
+        let affine = Mat2::from_cols(pick0, pick1);
+        if let Some(id) = self.pick_id
+            && affine.determinant() != 0.0
+        {
+            self.batch.picking.push(PickTarget {
+                id,
+                center: pick_center,
+                to_local: affine.inverse(),
+                uvs: transform_uvs(self.uvs, self.uv_offset, self.uv_scale),
+                texture: self.tex_id,
+                alpha_threshold: self.pick_alpha_threshold,
+                layer: self.batch.current_layer,
+            });
+        }
+
+        if let Some(thickness) = self.stroke {
+            let (half0, half1) = (col0 / 2.0, col1 / 2.0);
+            let corners = [
+                center - half0 - half1,
+                center + half0 - half1,
+                center + half0 + half1,
+                center - half0 + half1,
+            ];
+            push_outline(
+                "rectangle outline",
+                self.batch,
+                self.shader_id,
+                corners.len(),
+                |i| corners[i],
+                thickness,
+                self.stroke_color.unwrap_or(self.color),
+            );
+        }
+    }
+}
+
+impl Drop for RectangleBuilder<'_> {
+    fn drop(&mut self) {
+        self.submit();
+        self.batch.close_draw();
+    }
+}
+
+/// Applies [`RectangleBuilder::uv_offset`] & [`RectangleBuilder::uv_scale`] to a UV rect
+fn transform_uvs([u0, v0, u1, v1]: [f32; 4], offset: Vec2, scale: Vec2) -> [f32; 4] {
+    let (min, span) = (vec2(u0, v0), vec2(u1 - u0, v1 - v0));
+    let start = min + offset * span;
+    let end = start + scale * span;
+    [start.x, start.y, end.x, end.y]
+}
+
+/// Draws a nine-slice drop shadow behind a `size` rect at `center` rotated by `rot`.
+/// Each corner samples a quarter of the shadow texture & the edges & middle stretch its
+/// center texels, fading from the shadow's outer edge to `2 * softness` inside it
+fn push_shadow(
+    batch: &mut PrimitiveBatch,
+    shader_id: Option<usize>,
+    texture: usize,
+    (center, rot, size): (Vec2, Mat2, Vec2),
+    shadow: Shadow,
+) {
+    let slices = |half: f32| {
+        let (outer, inner) = (half + shadow.softness, (half - shadow.softness).max(0.0));
+        [-outer, -inner, inner, outer]
+    };
+    let half = size.abs() / 2.0;
+    let (xs, ys) = (slices(half.x), slices(half.y));
+    let uvs = [0.0, 0.5, 0.5, 1.0];
+    let color = batch.tinted(shadow.color);
+
+    for j in 0..3 {
+        for i in 0..3 {
+            let slice = vec2(xs[i + 1] - xs[i], ys[j + 1] - ys[j]);
+            if slice.x <= 0.0 || slice.y <= 0.0 {
+                continue;
+            }
+            let local = vec2(xs[i] + xs[i + 1], ys[j] + ys[j + 1]) / 2.0;
+            let (col0, col1) = (rot.x_axis * slice.x, rot.y_axis * slice.y);
+            let instance = Instance::new(
+                [col0.x, col0.y, col1.x, col1.y],
+                (center + rot * local).to_array(),
                 color,
-                self.uvs,
-            ),
-            self.tex_id,
-            self.shader_id,
+                [uvs[i], uvs[j], uvs[i + 1], uvs[j + 1]],
+            );
+            debug_assert_finite("shadow", instance.affine.iter().chain(&instance.translate));
+            batch.push_instance(instance, Some(texture), shader_id);
+        }
+    }
+}
+
+/// RGBA pixels of the drop shadow texture: white, with alpha falling off smoothly from an
+/// opaque center to transparent edges. The center texels double as solid white for fills
+pub(crate) fn shadow_texture_pixels() -> Vec<u8> {
+    let half = SHADOW_TEXTURE_SIZE as f32 / 2.0;
+    let mut pixels = Vec::with_capacity((SHADOW_TEXTURE_SIZE * SHADOW_TEXTURE_SIZE * 4) as usize);
+    for y in 0..SHADOW_TEXTURE_SIZE {
+        for x in 0..SHADOW_TEXTURE_SIZE {
+            let distance = (vec2(x as f32, y as f32) + 0.5 - half).length() / half;
+            let t = ((distance - SHADOW_PLATEAU) / (1.0 - SHADOW_PLATEAU)).clamp(0.0, 1.0);
+            let alpha = 1.0 - t * t * (3.0 - 2.0 * t);
+            pixels.extend_from_slice(&[255, 255, 255, (alpha * 255.0).round() as u8]);
+        }
+    }
+    pixels
+}
+
+/// Writes a closed outline through `count` corners as a mitered quad strip,
+/// 2 vertices per corner. Cheaper than tessellating a stroke for simple outlines
+fn push_outline(
+    primitive: &str,
+    batch: &mut PrimitiveBatch,
+    shader_id: Option<usize>,
+    count: usize,
+    corner: impl Fn(usize) -> Vec2,
+    thickness: f32,
+    color: Color,
+) {
+    if count < 2
+        || batch.skips(color, None, shader_id)
+        || batch.skips_thickness(primitive, thickness)
+    {
+        return;
+    }
+    let color = batch.tinted(color);
+    let (tex_id, uv) = batch.solid_texture();
+    let Some((verts, indices, base)) = batch.allocate(count * 2, count * 6, tex_id, shader_id)
+    else {
+        return;
+    };
+
+    let half = thickness / 2.0;
+
+    for i in 0..count {
+        let curr = corner(i);
+        let offset = miter_offset(
+            corner((i + count - 1) % count),
+            curr,
+            corner((i + 1) % count),
+            half,
         );
+
+        verts[i * 2] = Vertex::new((curr + offset).into(), color, uv);
+        verts[i * 2 + 1] = Vertex::new((curr - offset).into(), color, uv);
+
+        let (outer, inner) = (base + i as u16 * 2, base + i as u16 * 2 + 1);
+        let next = (i + 1) % count;
+        let (next_outer, next_inner) = (base + next as u16 * 2, base + next as u16 * 2 + 1);
+        indices[i * 6..i * 6 + 6]
+            .copy_from_slice(&[outer, inner, next_inner, next_inner, next_outer, outer]);
     }
+    debug_assert_finite(primitive, verts.iter().flat_map(|v| &v.position));
+}
+
+/// Catches NaN & infinite positions as they enter a batch. On the GPU a single one can make
+/// everything drawn after it vanish for the frame, far from the primitive that caused it
+#[track_caller]
+pub(crate) fn debug_assert_finite<'a>(primitive: &str, values: impl IntoIterator<Item = &'a f32>) {
+    debug_assert!(
+        values.into_iter().all(|v| v.is_finite()),
+        "{primitive} produced a NaN or infinite vertex position; check its inputs"
+    );
+}
+
+/// Offset from `curr` to the outer edge of an outline of `half` thickness, mitered
+/// between the edges `prev -> curr` & `curr -> next`
+fn miter_offset(prev: Vec2, curr: Vec2, next: Vec2, half: f32) -> Vec2 {
+    let normal = |a: Vec2, b: Vec2| (b - a).perp().normalize_or_zero();
+    let (n0, n1) = (normal(prev, curr), normal(curr, next));
+    let miter = (n0 + n1).normalize_or(n1);
+    // extend along the miter so both edges keep their full thickness
+    miter * (half / miter.dot(n1).max(1.0 / MITER_LIMIT))
 }
 
 /// Builder for polygons, triangles, circles, n-gons. Drawn on `Drop`
@@ -243,7 +1775,11 @@ pub struct PolygonBuilder<'a> {
     rotation: f32,
     radius: f32,
     segments: usize,
+    points: Option<PointBuf>,
     color: Color,
+    fill: bool,
+    stroke: Option<f32>,
+    stroke_color: Option<Color>,
 }
 
 impl<'a> PolygonBuilder<'a> {
@@ -255,7 +1791,11 @@ impl<'a> PolygonBuilder<'a> {
             rotation: 0.0,
             radius: 10.0,
             segments: 3,
+            points: None,
             color: Color::WHITE,
+            fill: true,
+            stroke: None,
+            stroke_color: None,
         }
     }
     /// Sets the world-space position of the polygon
@@ -263,12 +1803,17 @@ impl<'a> PolygonBuilder<'a> {
         self.position = pos;
         self
     }
-    /// Sets rotation in radians around the polygon's origin (default center)
+    /// Sets rotation in radians around the polygon's origin (default center), from +X
+    /// toward +Y (clockwise on screen)
     pub fn rotate(mut self, angle: f32) -> Self {
         self.rotation = angle;
         self
     }
-    /// Set radius for a circle or regular n-gon
+    /// Same as [`Self::rotate`] in degrees
+    pub fn rotate_deg(self, degrees: f32) -> Self {
+        self.rotate(degrees.to_radians())
+    }
+    /// Set radius for a circle or regular n-gon. A radius of zero draws nothing
     pub fn radius(mut self, r: f32) -> Self {
         self.radius = r;
         self
@@ -278,46 +1823,204 @@ impl<'a> PolygonBuilder<'a> {
         self.segments = segments.max(3);
         self
     }
+    /// Sets custom points relative to the polygon origin, overriding radius & segments.
+    /// Convex shapes are triangulated as a cheap fan from the first point; concave or
+    /// self-intersecting ones are tessellated by lyon, filling overlaps (non-zero rule)
+    pub fn points(self, pts: &[Vec2]) -> Self {
+        self.points_iter(pts.iter().copied())
+    }
+    /// Like [`Self::points`] but takes ownership of the points without copying
+    pub fn points_owned(mut self, pts: Vec<Vec2>) -> Self {
+        self.points = Some(PointBuf::from_vec(pts));
+        self
+    }
+    /// Like [`Self::points`] but collects from an iterator into reused scratch storage
+    pub fn points_iter(mut self, pts: impl IntoIterator<Item = Vec2>) -> Self {
+        // release any current (scratch) points first so they can be reused
+        self.points = None;
+        self.points = Some(PointBuf::from_iter(pts));
+        self
+    }
+    /// Moves the polygon's place in a layer sorted by y, see [`RectangleBuilder::sort_bias`]
+    pub fn sort_bias(self, bias: f32) -> Self {
+        self.batch.sort_bias = bias;
+        self
+    }
     /// Sets the color of the polygon
     pub fn color(mut self, color: Color) -> Self {
         self.color = color;
         self
     }
+    /// Draws an outline of `thickness` world units centered on the polygon's edges.
+    /// Outlines thinner than 0.001 world units aren't drawn
+    pub fn stroke(mut self, thickness: f32) -> Self {
+        self.stroke = Some(thickness);
+        self
+    }
+    /// Sets the outline color. Defaults to the fill color
+    pub fn stroke_color(mut self, color: Color) -> Self {
+        self.stroke_color = Some(color);
+        self
+    }
+    /// Enable or disable the fill (defaults to true). Use with [`Self::stroke`] for outlines
+    pub fn fill(mut self, fill: bool) -> Self {
+        self.fill = fill;
+        self
+    }
 }
 
-impl Drop for PolygonBuilder<'_> {
-    fn drop(&mut self) {
-        let r = self.radius;
-        let points: Vec<Vec2> = (0..self.segments)
-            .map(|i| {
-                let t = i as f32 / self.segments as f32 * std::f32::consts::TAU;
-                Vec2::new(t.cos(), t.sin()) * r
-            })
-            .collect();
+impl PolygonBuilder<'_> {
+    fn submit(&mut self) {
+        let _guard = alloc_guard::scope();
+        let (r, segments) = (self.radius, self.segments);
+        let vert_count = self.points.as_ref().map_or(segments, |p| p.points.len());
+        // custom points cover whatever they outline, as long as there are enough of them
+        let area = match self.points {
+            Some(_) if vert_count < 3 => 0.0,
+            Some(_) => 1.0,
+            None => r * r,
+        };
+        if self.batch.skips_area("polygon", area) {
+            return;
+        }
 
         let rot = Mat2::from_angle(self.rotation);
         let center = self.position;
-        let color = self.color.components();
-        let vert_count = points.len();
-        let idx_count = (points.len().saturating_sub(2)) * 3;
+        let corner = |i: usize| {
+            let local = match &self.points {
+                Some(points) => points.points[i],
+                None => {
+                    let t = i as f32 / segments as f32 * std::f32::consts::TAU;
+                    Vec2::new(t.cos(), t.sin()) * r
+                }
+            };
+            rot * local + center
+        };
 
-        if let Some((verts, indices, base)) =
-            self.batch
-                .allocate(vert_count, idx_count, None, self.shader_id)
-        {
-            for (i, p) in points.iter().enumerate() {
-                let world = rot * *p + center;
-                verts[i] = Vertex::new(world.into(), color, [0.0, 0.0]);
-            }
+        let concave = self.points.as_ref().is_some_and(|p| !is_convex(&p.points));
+        let fill = self.fill && !self.batch.skips(self.color, None, self.shader_id);
+        if fill && concave {
+            let points = &self.points.as_ref().unwrap().points;
+            fill_concave(self.batch, self.shader_id, points, self.color, |p| {
+                rot * p + center
+            });
+        } else if fill {
+            let idx_count = vert_count.saturating_sub(2) * 3;
+            let color = self.batch.tinted(self.color);
+            let (tex_id, uv) = self.batch.solid_texture();
+            if let Some((verts, indices, base)) =
+                self.batch
+                    .allocate(vert_count, idx_count, tex_id, self.shader_id)
+            {
+                for (i, vert) in verts.iter_mut().enumerate() {
+                    *vert = Vertex::new(corner(i).into(), color, uv);
+                }
 
-            for i in 0..points.len().saturating_sub(2) {
-                let offset = i * 3;
-                indices[offset] = base;
-                indices[offset + 1] = base + (i as u16 + 1);
-                indices[offset + 2] = base + (i as u16 + 2);
+                for i in 0..vert_count.saturating_sub(2) {
+                    let offset = i * 3;
+                    indices[offset] = base;
+                    indices[offset + 1] = base + (i as u16 + 1);
+                    indices[offset + 2] = base + (i as u16 + 2);
+                }
+                debug_assert_finite("polygon", verts.iter().flat_map(|v| &v.position));
             }
         }
+
+        if let Some(thickness) = self.stroke {
+            push_outline(
+                "polygon outline",
+                self.batch,
+                self.shader_id,
+                vert_count,
+                corner,
+                thickness,
+                self.stroke_color.unwrap_or(self.color),
+            );
+        }
+    }
+}
+
+impl Drop for PolygonBuilder<'_> {
+    fn drop(&mut self) {
+        self.submit();
+        self.batch.close_draw();
+    }
+}
+
+/// Whether `points` outline a convex polygon, which a triangle fan from the first point covers.
+/// Repeated & collinear points are allowed; outlines winding more than once (a pentagram)
+/// aren't convex even though they only turn one way
+fn is_convex(points: &[Vec2]) -> bool {
+    let n = points.len();
+    if n < 4 {
+        return true;
+    }
+    let (mut turn, mut total) = (0.0f32, 0.0);
+    for i in 0..n {
+        let edge = points[(i + 1) % n] - points[i];
+        let next = points[(i + 2) % n] - points[(i + 1) % n];
+        let cross = edge.perp_dot(next);
+        if cross * turn < 0.0 {
+            return false;
+        }
+        if cross != 0.0 {
+            turn = cross;
+        }
+        total += cross.atan2(edge.dot(next));
     }
+    total.abs() < std::f32::consts::TAU + 1e-3
+}
+
+/// Fills a concave polygon through lyon's fill tessellator, placing each point with `place`
+fn fill_concave(
+    batch: &mut PrimitiveBatch,
+    shader_id: Option<usize>,
+    points: &[Vec2],
+    color: Color,
+    place: impl Fn(Vec2) -> Vec2,
+) {
+    let at = |p: Vec2| point(p.x, p.y);
+    let (first, last) = (at(points[0]), at(points[points.len() - 1]));
+    let lines = points.windows(2).map(|w| PathEvent::Line {
+        from: at(w[0]),
+        to: at(w[1]),
+    });
+    let events = std::iter::once(PathEvent::Begin { at: first })
+        .chain(lines)
+        .chain(std::iter::once(PathEvent::End {
+            last,
+            first,
+            close: true,
+        }));
+
+    let color = batch.tinted(color);
+    let (tex_id, uv) = batch.solid_texture();
+    TESSELLATION.with_borrow_mut(|t| {
+        let geometry = &mut t.geometry;
+        geometry.vertices.clear();
+        geometry.indices.clear();
+        let tessellated = t.fill.tessellate(
+            events,
+            &FillOptions::non_zero(),
+            &mut BuffersBuilder::new(geometry, |vertex: FillVertex| {
+                let p = place(Vec2::from(vertex.position().to_array()));
+                Vertex::new(p.into(), color, uv)
+            }),
+        );
+        if tessellated.is_err() {
+            return;
+        }
+        let (vert_count, idx_count) = (geometry.vertices.len(), geometry.indices.len());
+        if let Some((verts, indices, base)) =
+            batch.allocate(vert_count, idx_count, tex_id, shader_id)
+        {
+            verts.copy_from_slice(&geometry.vertices);
+            for (idx, src) in indices.iter_mut().zip(&geometry.indices) {
+                *idx = base + src;
+            }
+            debug_assert_finite("polygon", verts.iter().flat_map(|v| &v.position));
+        }
+    });
 }
 
 /// Builder for stroked paths (polylines)
@@ -328,7 +2031,7 @@ pub struct PolylineBuilder<'a> {
     shader_id: Option<usize>,
     position: Vec2,
     rotation: f32,
-    points: Vec<Vec2>,
+    points: PointBuf,
     thickness: f32,
     color: Color,
     closed: bool,
@@ -341,7 +2044,7 @@ impl<'a> PolylineBuilder<'a> {
             shader_id,
             position: Vec2::ZERO,
             rotation: 0.0,
-            points: vec![vec2(0.0, 0.0), vec2(10.0, 0.0)],
+            points: PointBuf::from_iter([vec2(0.0, 0.0), vec2(10.0, 0.0)]),
             thickness: 1.0,
             color: Color::WHITE,
             closed: false,
@@ -352,21 +2055,42 @@ impl<'a> PolylineBuilder<'a> {
         self.position = pos;
         self
     }
-    /// Sets rotation in radians around the polyline origin
+    /// Sets rotation in radians around the polyline origin, from +X toward +Y
+    /// (clockwise on screen)
     pub fn rotate(mut self, angle: f32) -> Self {
         self.rotation = angle;
         self
     }
+    /// Same as [`Self::rotate`] in degrees
+    pub fn rotate_deg(self, degrees: f32) -> Self {
+        self.rotate(degrees.to_radians())
+    }
     /// Sets the points of the polyline
     /// At least two points are required to generate geometry
-    pub fn points(mut self, pts: &[Vec2]) -> Self {
-        self.points.clear();
-        self.points.extend_from_slice(pts);
+    pub fn points(self, pts: &[Vec2]) -> Self {
+        self.points_iter(pts.iter().copied())
+    }
+    /// Like [`Self::points`] but takes ownership of the points without copying
+    pub fn points_owned(mut self, pts: Vec<Vec2>) -> Self {
+        self.points = PointBuf::from_vec(pts);
         self
     }
-    /// Sets the stroke thickness in world units
+    /// Like [`Self::points`] but collects from an iterator into reused scratch storage
+    pub fn points_iter(mut self, pts: impl IntoIterator<Item = Vec2>) -> Self {
+        // release the current (scratch) points first so they can be reused
+        self.points = PointBuf::from_vec(Vec::new());
+        self.points = PointBuf::from_iter(pts);
+        self
+    }
+    /// Sets the stroke thickness in world units. Lines thinner than 0.001 world units
+    /// aren't drawn
     pub fn thickness(mut self, t: f32) -> Self {
-        self.thickness = t.max(MIN_THICKNESS);
+        self.thickness = t;
+        self
+    }
+    /// Moves the polyline's place in a layer sorted by y, see [`RectangleBuilder::sort_bias`]
+    pub fn sort_bias(self, bias: f32) -> Self {
+        self.batch.sort_bias = bias;
         self
     }
     /// Sets the color of the polyline
@@ -381,30 +2105,43 @@ impl<'a> PolylineBuilder<'a> {
     }
 }
 
-impl Drop for PolylineBuilder<'_> {
-    fn drop(&mut self) {
-        let n = self.points.len();
-        if n < 2 {
+impl PolylineBuilder<'_> {
+    fn submit(&mut self) {
+        let _guard = alloc_guard::scope();
+        let points = &self.points.points;
+        let n = points.len();
+        if n < 2
+            || self.batch.skips(self.color, None, self.shader_id)
+            || self.batch.skips_thickness("polyline", self.thickness)
+        {
             return;
         }
 
         let rot = Mat2::from_angle(self.rotation);
-        let color = self.color.components();
-        let segments = if self.closed { n } else { n - 1 };
-        let vert_count = segments * 4;
-        let idx_count = segments * 6;
+        let color = self.batch.tinted(self.color);
+        let segment_count = if self.closed { n } else { n - 1 };
+        // repeated points (e.g. a mouse path sampled every frame) have no direction to extrude
+        let segments = || {
+            (0..segment_count)
+                .map(|s| (points[s], points[(s + 1) % n])) // wraps if closed
+                .filter(|(a, b)| a.distance_squared(*b) > f32::EPSILON * f32::EPSILON)
+        };
+        let segments_drawn = segments().count();
+        if segments_drawn == 0 {
+            return;
+        }
+        let vert_count = segments_drawn * 4;
+        let idx_count = segments_drawn * 6;
 
+        let (tex_id, uv) = self.batch.solid_texture();
         if let Some((verts, indices, mut base)) =
             self.batch
-                .allocate(vert_count, idx_count, None, self.shader_id)
+                .allocate(vert_count, idx_count, tex_id, self.shader_id)
         {
             let mut vi = 0;
             let mut ii = 0;
 
-            for s in 0..segments {
-                let a = self.points[s];
-                let b = self.points[(s + 1) % n]; // wraps if closed
-
+            for (a, b) in segments() {
                 let dir = (b - a).normalize();
                 let nrm = vec2(-dir.y, dir.x) * (self.thickness * 0.5);
 
@@ -416,7 +2153,7 @@ impl Drop for PolylineBuilder<'_> {
                 ];
 
                 for &pos in &p {
-                    verts[vi] = Vertex::new(pos.into(), color, [0.0, 0.0]);
+                    verts[vi] = Vertex::new(pos.into(), color, uv);
                     vi += 1;
                 }
 
@@ -431,13 +2168,21 @@ impl Drop for PolylineBuilder<'_> {
                 ii += 6;
                 base += 4;
             }
+            debug_assert_finite("polyline", verts.iter().flat_map(|v| &v.position));
         }
     }
 }
 
+impl Drop for PolylineBuilder<'_> {
+    fn drop(&mut self) {
+        self.submit();
+        self.batch.close_draw();
+    }
+}
+
 /// Builder for constructing and submitting a vector path
 ///
-/// Internally this wraps a `lyon::path::Builder` and records path commands
+/// Internally this wraps a lyon path builder and records path commands
 /// (`begin`, `line_to`, `quad_to`, etc). On drop:
 ///
 /// - Any open subpath is automatically ended (non-closed).
@@ -448,6 +2193,8 @@ impl Drop for PolylineBuilder<'_> {
 /// Users must call `begin()` before issuing path commands
 pub struct PathBuilder<'a> {
     batch: &'a mut PrimitiveBatch,
+    /// Collects the path for [`ParallelShapes`] instead of tessellating on drop
+    deferred: Option<&'a mut Vec<PathJob>>,
     shader_id: Option<usize>,
     position: Vec2,
     rotation: f32,
@@ -455,14 +2202,19 @@ pub struct PathBuilder<'a> {
     thickness: f32,
     stroke_color: Option<Color>,
     fill_color: Option<Color>,
+    /// Overrides the batch's [`QualityMode`]
+    tolerance: Option<f32>,
+    /// Camera zoom the path is drawn at, for [`QualityMode::ZoomAdaptive`]
+    zoom: f32,
     path_open: bool,
-    builder: Builder,
+    builder: NoAttributes<PathRecorder>,
 }
 
 impl<'a> PathBuilder<'a> {
     pub(crate) fn new(batch: &'a mut PrimitiveBatch, shader_id: Option<usize>) -> Self {
         Self {
             batch,
+            deferred: None,
             shader_id,
             position: Vec2::ZERO,
             rotation: 0.0,
@@ -470,21 +2222,33 @@ impl<'a> PathBuilder<'a> {
             thickness: 1.0,
             stroke_color: None,
             fill_color: None,
+            tolerance: None,
+            zoom: 1.0,
             path_open: false,
-            builder: Path::builder(),
+            builder: NoAttributes::wrap(PathRecorder::pooled()),
         }
     }
 
+    pub(crate) fn with_zoom(mut self, zoom: f32) -> Self {
+        self.zoom = zoom;
+        self
+    }
+
     /// Sets the world-space translation of the path
     pub fn at(mut self, pos: Vec2) -> Self {
         self.position = pos;
         self
     }
-    /// Sets rotation in radians around the local origin (0,0)
+    /// Sets rotation in radians around the local origin (0,0), from +X toward +Y
+    /// (clockwise on screen)
     pub fn rotate(mut self, angle: f32) -> Self {
         self.rotation = angle;
         self
     }
+    /// Same as [`Self::rotate`] in degrees
+    pub fn rotate_deg(self, degrees: f32) -> Self {
+        self.rotate(degrees.to_radians())
+    }
     /// Sets the scale of the path
     pub fn scale(mut self, scale: Vec2) -> Self {
         self.scale = scale;
@@ -500,11 +2264,23 @@ impl<'a> PathBuilder<'a> {
         self.stroke_color = Some(color);
         self
     }
+    /// Moves the path's place in a layer sorted by y, see [`RectangleBuilder::sort_bias`]
+    pub fn sort_bias(self, bias: f32) -> Self {
+        self.batch.sort_bias = bias;
+        self
+    }
     /// Sets the fill color for the path
     pub fn fill_color(mut self, color: Color) -> Self {
         self.fill_color = Some(color);
         self
     }
+    /// Sets how far (in local units) tessellated curves may stray from the true curve,
+    /// overriding [`crate::graphics::Graphics::set_curve_quality`]. Lower is smoother
+    /// but costs more vertices
+    pub fn tolerance(mut self, tolerance: f32) -> Self {
+        self.tolerance = Some(tolerance.max(MIN_TOLERANCE));
+        self
+    }
 
     /// Begins a new subpath at the given local coordinate.
     /// Must be called before any `line_to`/`quad_to`/`cubic_to` commands.
@@ -574,59 +2350,1386 @@ impl<'a> PathBuilder<'a> {
             .add_circle(Point::new(0.0, 0.0), radius, Winding::Positive);
         self
     }
+
+    /// `color` tinted, or `None` if the fill or stroke it's for is skipped
+    fn visible(&mut self, color: Color) -> Option<[f32; 4]> {
+        let skip = self.batch.skips(color, None, self.shader_id);
+        (!skip).then(|| self.batch.tinted(color))
+    }
 }
 
 impl Drop for PathBuilder<'_> {
     fn drop(&mut self) {
+        // parallel shapes collect their jobs & tessellate on other threads, which allocates
+        let _guard = self.deferred.is_none().then(alloc_guard::scope);
         if self.path_open {
             self.builder.end(false);
         }
-        let path = std::mem::take(&mut self.builder).build();
-        let mut geometry: VertexBuffers<Vertex, u16> = VertexBuffers::new();
+        let job = PathJob {
+            events: std::mem::take(&mut self.builder.inner_mut().events),
+            shader_id: self.shader_id,
+            position: self.position,
+            rotation: self.rotation,
+            scale: self.scale,
+            thickness: self.thickness,
+            tolerance: self
+                .tolerance
+                .unwrap_or_else(|| self.batch.curve_quality.tolerance(self.zoom, self.scale)),
+            fill_color: self.fill_color.and_then(|c| self.visible(c)),
+            stroke_color: self.stroke_color.and_then(|c| self.visible(c)),
+        };
+
+        match self.deferred.as_mut() {
+            Some(jobs) => jobs.push(job),
+            None => {
+                TESSELLATION.with_borrow_mut(|t| {
+                    job.tessellate_into(&mut t.fill, &mut t.stroke, &mut t.geometry);
+                    job.write(self.batch, &t.geometry);
+                });
+                recycle_path_events(job.events);
+                self.batch.close_draw();
+            }
+        }
+    }
+}
+
+/// A recorded path with its final colors, ready to tessellate on any thread
+pub(crate) struct PathJob {
+    events: Vec<PathEvent>,
+    shader_id: Option<usize>,
+    position: Vec2,
+    rotation: f32,
+    scale: Vec2,
+    thickness: f32,
+    tolerance: f32,
+    fill_color: Option<[f32; 4]>,
+    stroke_color: Option<[f32; 4]>,
+}
+
+impl PathJob {
+    /// Tessellates with fresh tessellators, for jobs spread over threads
+    fn tessellate(&self) -> VertexBuffers<Vertex, u16> {
+        let mut geometry = VertexBuffers::new();
+        let (mut fill, mut stroke) = (FillTessellator::new(), StrokeTessellator::new());
+        self.tessellate_into(&mut fill, &mut stroke, &mut geometry);
+        geometry
+    }
+
+    /// Tessellates the fill and/or stroke into `geometry` & applies the world transform
+    fn tessellate_into(
+        &self,
+        fill: &mut FillTessellator,
+        stroke: &mut StrokeTessellator,
+        geometry: &mut VertexBuffers<Vertex, u16>,
+    ) {
+        geometry.vertices.clear();
+        geometry.indices.clear();
 
         if let Some(fill_color) = self.fill_color {
-            FillTessellator::new()
-                .tessellate_path(
-                    &path,
-                    &Default::default(),
-                    &mut BuffersBuilder::new(&mut geometry, |vertex: FillVertex| {
+            fill.tessellate(
+                self.events.iter().copied(),
+                &FillOptions::tolerance(self.tolerance),
+                &mut BuffersBuilder::new(geometry, |vertex: FillVertex| {
+                    let [x, y] = vertex.position().to_array();
+                    Vertex::new([x, y], fill_color, [0.0, 0.0])
+                }),
+            )
+            .unwrap();
+        }
+
+        if let Some(stroke_color) = self.stroke_color {
+            stroke
+                .tessellate(
+                    self.events.iter().copied(),
+                    &StrokeOptions::tolerance(self.tolerance).with_line_width(self.thickness),
+                    &mut BuffersBuilder::new(geometry, |vertex: StrokeVertex| {
                         let [x, y] = vertex.position().to_array();
-                        Vertex::new([x, y], fill_color.components(), [0.0, 0.0])
-                    }),
-                )
-                .unwrap();
-        }
-
-        if let Some(stroke_color) = self.stroke_color {
-            StrokeTessellator::new()
-                .tessellate_path(
-                    &path,
-                    &StrokeOptions::default().with_line_width(self.thickness),
-                    &mut BuffersBuilder::new(&mut geometry, |vertex: StrokeVertex| {
-                        let [x, y] = vertex.position().to_array();
-                        Vertex::new([x, y], stroke_color.components(), [0.0, 0.0])
+                        Vertex::new([x, y], stroke_color, [0.0, 0.0])
                     }),
                 )
                 .unwrap();
         }
 
         let rot = Mat2::from_angle(self.rotation);
-        let vert_count = geometry.vertices.len();
-        let idx_count = geometry.indices.len();
+        for vertex in &mut geometry.vertices {
+            let p = rot * (self.scale * Vec2::from(vertex.position)) + self.position;
+            vertex.position = p.to_array();
+        }
+    }
 
+    /// Copies tessellated geometry into the batch
+    fn write(&self, batch: &mut PrimitiveBatch, geometry: &VertexBuffers<Vertex, u16>) {
+        let (vert_count, idx_count) = (geometry.vertices.len(), geometry.indices.len());
+        if idx_count == 0 {
+            return;
+        }
+        let (tex_id, uv) = batch.solid_texture();
         if let Some((verts, indices, base)) =
-            self.batch
-                .allocate(vert_count, idx_count, None, self.shader_id)
+            batch.allocate(vert_count, idx_count, tex_id, self.shader_id)
         {
-            for (vi, mut vo) in geometry.vertices.into_iter().enumerate() {
-                let mut p: Vec2 = vo.position.into();
-                p = rot * (self.scale * p) + self.position;
-                vo.position = p.to_array();
-                verts[vi] = vo;
+            verts.copy_from_slice(&geometry.vertices);
+            verts.iter_mut().for_each(|v| v.tex_coords = uv);
+            for (idx, src) in indices.iter_mut().zip(&geometry.indices) {
+                *idx = base + src;
+            }
+            debug_assert_finite("path", verts.iter().flat_map(|v| &v.position));
+        }
+    }
+}
+
+/// Collects paths & tessellates them together when dropped: in parallel on native with the
+/// `parallel` feature & more than one core, sequentially otherwise. Geometry is added in
+/// submission order, so draw order is kept. Created by
+/// [`crate::graphics::Graphics::parallel_shapes`]
+pub struct ParallelShapes<'a> {
+    batch: &'a mut PrimitiveBatch,
+    shader_id: Option<usize>,
+    zoom: f32,
+    jobs: Vec<PathJob>,
+}
+
+impl<'a> ParallelShapes<'a> {
+    pub(crate) fn new(batch: &'a mut PrimitiveBatch, shader_id: Option<usize>, zoom: f32) -> Self {
+        Self {
+            batch,
+            shader_id,
+            zoom,
+            jobs: Vec::new(),
+        }
+    }
+
+    /// Start building a vector path, same as [`crate::graphics::Graphics::path`]
+    /// but tessellated with the rest of the collected shapes
+    pub fn path(&mut self) -> PathBuilder<'_> {
+        let mut builder = PathBuilder::new(self.batch, self.shader_id).with_zoom(self.zoom);
+        builder.deferred = Some(&mut self.jobs);
+        builder
+    }
+}
+
+/// Whether only one thread can run at a time, where a thread pool only adds overhead.
+/// Checked once, since `available_parallelism` reads cgroup files on Linux
+#[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+fn single_core() -> bool {
+    static SINGLE: std::sync::OnceLock<bool> = std::sync::OnceLock::new();
+    *SINGLE.get_or_init(|| std::thread::available_parallelism().is_ok_and(|n| n.get() == 1))
+}
+
+impl Drop for ParallelShapes<'_> {
+    fn drop(&mut self) {
+        #[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+        let geometry: Vec<_> = if single_core() {
+            self.jobs.iter().map(PathJob::tessellate).collect()
+        } else {
+            use rayon::prelude::*;
+            self.jobs.par_iter().map(PathJob::tessellate).collect()
+        };
+        #[cfg(not(all(feature = "parallel", not(target_arch = "wasm32"))))]
+        let geometry: Vec<_> = self.jobs.iter().map(PathJob::tessellate).collect();
+
+        for (job, geometry) in self.jobs.iter().zip(&geometry) {
+            job.write(self.batch, geometry);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{alloc_guard::allocations, coords::Coords, math::Transform};
+    use std::f32::consts::FRAC_PI_2;
+
+    fn allocations_during(f: impl FnOnce()) -> usize {
+        let before = allocations();
+        f();
+        allocations() - before
+    }
+
+    fn draw_polylines(batch: &mut PrimitiveBatch) {
+        for i in 0..1_000 {
+            let x = i as f32;
+            PolylineBuilder::new(batch, None)
+                .points_iter((0..8).map(|j| vec2(x, j as f32 * 10.0)))
+                .thickness(2.0);
+        }
+    }
+
+    #[test]
+    fn polylines_reuse_scratch_points() {
+        // 1k polylines per frame shouldn't allocate once the scratch buffer is warm
+        let mut batch = PrimitiveBatch::default();
+        draw_polylines(&mut batch);
+
+        assert_eq!(allocations_during(|| draw_polylines(&mut batch)), 0);
+
+        let pts = [vec2(0.0, 0.0), vec2(5.0, 5.0), vec2(10.0, 0.0)];
+        let slices = allocations_during(|| {
+            for _ in 0..1_000 {
+                PolylineBuilder::new(&mut batch, None).points(&pts);
+            }
+        });
+        assert_eq!(slices, 0);
+    }
+
+    /// Area covered by the triangles in `batch`, counting overlaps twice
+    fn triangle_area(batch: &mut PrimitiveBatch) -> f32 {
+        let (.., geometry) = batch.iter_mut().next().unwrap();
+        let position = |i: &u16| Vec2::from(geometry.vertices()[*i as usize].position);
+        geometry
+            .indices()
+            .chunks(3)
+            .map(|t| {
+                let [a, b, c] = [&t[0], &t[1], &t[2]].map(position);
+                (b - a).perp_dot(c - a).abs() / 2.0
+            })
+            .sum()
+    }
+
+    #[test]
+    fn concave_polygons_cover_exactly_their_area() {
+        // the fan from (16, 0) would reach across the notch
+        let l_shape = [
+            vec2(16.0, 0.0),
+            vec2(16.0, 8.0),
+            vec2(8.0, 8.0),
+            vec2(8.0, 16.0),
+            vec2(0.0, 16.0),
+            vec2(0.0, 0.0),
+        ];
+        let star: Vec<_> = (0..10)
+            .map(|i| {
+                let radius = if i % 2 == 0 { 10.0 } else { 4.0 };
+                Vec2::from_angle(i as f32 * std::f32::consts::TAU / 10.0) * radius
+            })
+            .collect();
+        let square = [Vec2::ZERO, Vec2::X, Vec2::ONE, Vec2::Y].map(|p| p * 4.0);
+        assert!(!is_convex(&l_shape) && !is_convex(&star) && is_convex(&square));
+
+        let shoelace = |pts: &[Vec2]| {
+            let n = pts.len();
+            (0..n)
+                .map(|i| pts[i].perp_dot(pts[(i + 1) % n]))
+                .sum::<f32>()
+                .abs()
+                / 2.0
+        };
+        for points in [&l_shape[..], &star, &square] {
+            let mut batch = PrimitiveBatch::default();
+            PolygonBuilder::new(&mut batch, None)
+                .at(vec2(50.0, 20.0))
+                .points(points);
+            let area = triangle_area(&mut batch);
+            assert!(
+                (area - shoelace(points)).abs() < 1e-3,
+                "{area} for {points:?}"
+            );
+        }
+
+        // a pentagram turns one way but winds twice
+        let pentagram: Vec<_> = (0..5)
+            .map(|i| Vec2::from_angle(i as f32 * 2.0 * std::f32::consts::TAU / 5.0))
+            .collect();
+        assert!(!is_convex(&pentagram));
+    }
+
+    #[test]
+    fn polygons_do_not_allocate() {
+        // regular n-gons, custom & concave points all draw without per-shape allocations
+        let mut batch = PrimitiveBatch::default();
+        let notched = [Vec2::ZERO, Vec2::X, vec2(0.2, 0.2), Vec2::Y];
+        PolygonBuilder::new(&mut batch, None).points_iter([Vec2::ZERO, Vec2::X, Vec2::Y]);
+        PolygonBuilder::new(&mut batch, None).points(&notched);
+
+        let allocs = allocations_during(|| {
+            for _ in 0..1_000 {
+                PolygonBuilder::new(&mut batch, None).segments(16);
+                PolygonBuilder::new(&mut batch, None).points(&[Vec2::ZERO, Vec2::X, Vec2::Y]);
+                PolygonBuilder::new(&mut batch, None).points(&notched);
+            }
+        });
+        assert_eq!(allocs, 0);
+    }
+
+    #[test]
+    fn owned_points_are_not_copied() {
+        // handing over a Vec moves it into the builder instead of cloning
+        let mut batch = PrimitiveBatch::default();
+        PolylineBuilder::new(&mut batch, None);
+
+        let pts = vec![Vec2::ZERO, Vec2::X, Vec2::ONE];
+        let allocs = allocations_during(|| {
+            PolylineBuilder::new(&mut batch, None).points_owned(pts);
+        });
+        assert_eq!(allocs, 0);
+    }
+
+    fn counts(batch: &mut PrimitiveBatch) -> (usize, usize, usize) {
+        batch.iter_mut().fold((0, 0, 0), |(v, i, n), (.., g)| {
+            (
+                v + g.vertex_count(),
+                i + g.index_count(),
+                n + g.instance_count(),
+            )
+        })
+    }
+
+    #[test]
+    fn only_consecutive_draws_share_a_batch() {
+        let mut batch = PrimitiveBatch::default();
+        let (a, b) = (Some(1), Some(2));
+        RectangleBuilder::new(&mut batch, None).texture(1);
+        RectangleBuilder::new(&mut batch, None).texture(1);
+        RectangleBuilder::new(&mut batch, None).texture(2);
+        RectangleBuilder::new(&mut batch, None).texture(1);
+        // an untextured rect, polygon, rect would otherwise share a batch & draw both rects first
+        RectangleBuilder::new(&mut batch, None);
+        PolygonBuilder::new(&mut batch, None).segments(4);
+        RectangleBuilder::new(&mut batch, None);
+
+        let entries: Vec<_> = batch
+            .iter_mut()
+            .map(|(tex, .., g)| (tex, g.instance_count(), g.index_count() > 0))
+            .collect();
+        assert_eq!(
+            entries,
+            [
+                (a, 2, false),
+                (b, 1, false),
+                (a, 1, false),
+                (None, 1, false),
+                (None, 0, true),
+                (None, 1, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn solid_shapes_share_the_primary_atlas_batch() {
+        let mut batch = PrimitiveBatch::default();
+        batch.solid.white_pixels.insert(4, [0.25, 0.5]);
+        let draw_ui = |batch: &mut PrimitiveBatch| {
+            for _ in 0..10 {
+                RectangleBuilder::new(batch, None).texture(4);
+                RectangleBuilder::new(batch, None).color(Color::RED);
+            }
+            batch.iter_mut().count()
+        };
+        assert_eq!(draw_ui(&mut batch), 20);
+
+        batch.reset();
+        batch.solid.primary = Some(4);
+        assert_eq!(draw_ui(&mut batch), 1);
+        let (.., geometry) = batch.iter_mut().next().unwrap();
+        assert_eq!(geometry.instances()[1].uv, [0.25, 0.5, 0.25, 0.5]);
+
+        PolygonBuilder::new(&mut batch, None).segments(4);
+        let (tex, .., geometry) = batch.iter_mut().last().unwrap();
+        assert_eq!(tex, Some(4));
+        assert!(
+            geometry
+                .vertices()
+                .iter()
+                .all(|v| v.tex_coords == [0.25, 0.5])
+        );
+
+        // custom shaders may read the UVs, so their fills keep them
+        RectangleBuilder::new(&mut batch, Some(2));
+        let (tex, .., geometry) = batch.iter_mut().last().unwrap();
+        let uv = geometry.instances()[0].uv;
+        assert_eq!((tex, uv), (None, [0.0, 0.0, 1.0, 1.0]));
+
+        // textures without a registered white pixel fall back to the default
+        batch.solid.primary = Some(7);
+        assert_eq!(batch.solid_texture(), (None, [0.0, 0.0]));
+    }
+
+    #[test]
+    fn uv_transforms_compose_with_atlas_regions() {
+        let mut batch = PrimitiveBatch::default();
+        RectangleBuilder::new(&mut batch, None)
+            .texture(1)
+            .uv_offset(vec2(0.5, -1.0))
+            .uv_scale(vec2(3.0, 1.0));
+        let region = SubTexture {
+            texture_id: 2,
+            uv: [0.25, 0.5, 0.75, 1.0],
+            size: (32, 32),
+        };
+        RectangleBuilder::new(&mut batch, None)
+            .texture_region(region)
+            .uv_offset(vec2(0.5, 0.0))
+            .uv_scale(vec2(0.5, 0.5));
+
+        let uvs: Vec<_> = batch
+            .iter_mut()
+            .map(|(tex, .., g)| (tex, g.instances()[0].uv))
+            .collect();
+        assert_eq!(
+            uvs,
+            [
+                (Some(1), [0.5, -1.0, 3.5, 0.0]),
+                (Some(2), [0.5, 0.5, 0.75, 0.75])
+            ]
+        );
+    }
+
+    #[test]
+    fn shadowed_cards_share_one_batch() {
+        let mut batch = PrimitiveBatch {
+            shadow_texture: Some(3),
+            ..Default::default()
+        };
+        for i in 0..12 {
+            let position = vec2((i % 4) as f32 * 120.0, (i / 4) as f32 * 90.0);
+            RectangleBuilder::new(&mut batch, None)
+                .at(position)
+                .size(vec2(100.0, 70.0))
+                .shadow(vec2(4.0, 6.0), 8.0, Color::BLACK);
+        }
+        let entries: Vec<_> = batch
+            .iter_mut()
+            .map(|(tex, .., g)| (tex, g.instance_count()))
+            .collect();
+        assert_eq!(entries, [(Some(3), 12 * 10)]);
+
+        // nine slices, the first a corner reaching `softness` past the offset rect
+        let (.., geometry) = batch.iter_mut().next().unwrap();
+        let corner = geometry.instances()[0];
+        assert_eq!(corner.uv, [0.0, 0.0, 0.5, 0.5]);
+        assert_eq!(corner.affine, [16.0, 0.0, 0.0, 16.0]);
+        assert_eq!(corner.translate, [4.0, 6.0]);
+        assert_eq!(geometry.instances()[9].uv, [0.5; 4]);
+    }
+
+    #[test]
+    fn shadow_texture_fades_from_an_opaque_center() {
+        let pixels = shadow_texture_pixels();
+        let size = SHADOW_TEXTURE_SIZE as usize;
+        let alpha = |x: usize, y: usize| pixels[(y * size + x) * 4 + 3];
+        let mid = size / 2;
+        assert_eq!(alpha(mid, mid), 255);
+        assert_eq!(alpha(mid - 1, mid - 1), 255);
+        assert_eq!(alpha(0, 0), 0);
+        assert!(alpha(0, mid) < 10);
+        assert!((100..200).contains(&alpha(mid / 2, mid)));
+    }
+
+    #[test]
+    fn rectangles_carry_their_color_mode() {
+        let mut batch = PrimitiveBatch::default();
+        RectangleBuilder::new(&mut batch, None);
+        RectangleBuilder::new(&mut batch, None).color_mode(ColorMode::Replace);
+        let (.., geometry) = batch.iter_mut().next().unwrap();
+        let modes: Vec<_> = geometry.instances().iter().map(|i| i.color_mode).collect();
+        assert_eq!(
+            modes,
+            [ColorMode::Multiply as u32, ColorMode::Replace as u32]
+        );
+    }
+
+    #[test]
+    fn params_vary_per_rectangle_in_one_batch() {
+        let mut batch = PrimitiveBatch::default();
+        for i in 0..3 {
+            RectangleBuilder::new(&mut batch, Some(0)).params([i as f32 * 0.5, 0.0, 0.0, 1.0]);
+        }
+        assert_eq!(batch.iter_mut().count(), 1);
+        let (.., geometry) = batch.iter_mut().next().unwrap();
+        let thresholds: Vec<_> = geometry.instances().iter().map(|i| i.params[0]).collect();
+        assert_eq!(thresholds, [0.0, 0.5, 1.0]);
+    }
+
+    #[test]
+    fn array_layers_share_one_batch() {
+        let draw = |batch: &mut PrimitiveBatch, sprite: &dyn Fn(usize) -> (usize, u32)| {
+            for i in 0..40 {
+                RectangleBuilder::new(batch, None).texture_layer(sprite(i));
+            }
+            batch.iter_mut().count()
+        };
+        // 40 textures drawn interleaved switch bind groups 40 times
+        let mut batch = PrimitiveBatch::default();
+        assert_eq!(draw(&mut batch, &|i| (i % 2 * 20 + i / 2, 0)), 40);
+
+        batch.reset();
+        assert_eq!(draw(&mut batch, &|i| (7, i as u32)), 1);
+        let (.., geometry) = batch.iter_mut().next().unwrap();
+        let layers: Vec<_> = geometry.instances().iter().map(|i| i.layer).collect();
+        assert_eq!(layers, (0..40).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn polylines_skip_repeated_points() {
+        let mut batch = PrimitiveBatch::default();
+        let (a, b) = (vec2(1.0, 1.0), vec2(4.0, 5.0));
+        PolylineBuilder::new(&mut batch, None)
+            .points(&[a, a, b, b, b])
+            .thickness(2.0);
+        // only a -> b has a direction
+        assert_eq!(counts(&mut batch), (4, 6, 0));
+        let (.., geometry) = batch.iter_mut().next().unwrap();
+        assert!(
+            geometry
+                .vertices()
+                .iter()
+                .all(|v| v.position.iter().all(|c| c.is_finite()))
+        );
+
+        batch.reset();
+        PolylineBuilder::new(&mut batch, None)
+            .points(&[a, a, a])
+            .closed(true);
+        assert_eq!(counts(&mut batch), (0, 0, 0));
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "polygon produced a NaN")]
+    fn non_finite_vertices_name_the_primitive() {
+        let mut batch = PrimitiveBatch::default();
+        PolygonBuilder::new(&mut batch, None).points(&[Vec2::ZERO, Vec2::X, Vec2::NAN]);
+    }
+
+    #[test]
+    fn outlines_are_cheap_quad_strips() {
+        let mut batch = PrimitiveBatch::default();
+        RectangleBuilder::new(&mut batch, None)
+            .fill(false)
+            .stroke(1.0);
+        assert_eq!(counts(&mut batch), (8, 24, 0));
+
+        batch.reset();
+        PolygonBuilder::new(&mut batch, None)
+            .segments(6)
+            .fill(false)
+            .stroke(2.0);
+        assert_eq!(counts(&mut batch), (12, 36, 0));
+
+        // fill & stroke together keep the instanced fill
+        batch.reset();
+        RectangleBuilder::new(&mut batch, None).stroke(1.0);
+        assert_eq!(counts(&mut batch), (8, 24, 1));
+    }
+
+    #[test]
+    fn outline_corners_are_mitered() {
+        // a square corner extends by half the thickness along both edges
+        let offset = miter_offset(vec2(0.0, 10.0), Vec2::ZERO, vec2(10.0, 0.0), 1.0);
+        assert!((offset.abs() - Vec2::ONE).length() < 1e-5);
+
+        // collinear points offset straight out by half the thickness
+        let offset = miter_offset(vec2(-1.0, 0.0), Vec2::ZERO, vec2(1.0, 0.0), 1.0);
+        assert!((offset.length() - 1.0).abs() < 1e-5);
+
+        // spikes on very sharp corners are capped
+        let offset = miter_offset(vec2(10.0, 0.1), Vec2::ZERO, vec2(10.0, -0.1), 1.0);
+        assert!(offset.length() <= MITER_LIMIT + 1e-5);
+    }
+
+    /// Vertex positions & instance quad corners of everything drawn since the last reset
+    fn drawn_points(batch: &mut PrimitiveBatch) -> Vec<Vec2> {
+        let mut points = Vec::new();
+        for (.., g) in batch.iter_mut() {
+            points.extend(g.vertices().iter().map(|v| Vec2::from(v.position)));
+            for instance in g.instances() {
+                let [ax, ay, bx, by] = instance.affine;
+                let (col0, col1) = (vec2(ax, ay), vec2(bx, by));
+                points.extend(
+                    [(-0.5, -0.5), (0.5, -0.5), (0.5, 0.5), (-0.5, 0.5)]
+                        .map(|(x, y)| col0 * x + col1 * y + Vec2::from(instance.translate)),
+                );
+            }
+        }
+        batch.reset();
+        points
+    }
+
+    #[test]
+    fn every_primitive_rotates_the_same_way() {
+        let mut batch = PrimitiveBatch::default();
+        let corners = [
+            vec2(-10.0, -5.0),
+            vec2(10.0, -5.0),
+            vec2(10.0, 5.0),
+            vec2(-10.0, 5.0),
+        ];
+        type Draw<'a> = &'a dyn Fn(&mut PrimitiveBatch, f32);
+        let draws: [Draw; 5] = [
+            &|b, angle| {
+                RectangleBuilder::new(b, None)
+                    .anchor(Anchor::Center)
+                    .size(vec2(20.0, 10.0))
+                    .rotate(angle);
+            },
+            &|b, angle| {
+                PolygonBuilder::new(b, None).points(&corners).rotate(angle);
+            },
+            &|b, angle| {
+                PolylineBuilder::new(b, None).points(&corners).rotate(angle);
+            },
+            &|b, angle| {
+                PathBuilder::new(b, None)
+                    .at(corners[0])
+                    .rect(vec2(20.0, 10.0))
+                    .fill_color(Color::WHITE)
+                    .rotate(angle);
+            },
+            &|b, angle| {
+                crate::mesh::MeshBuilder::new(b, None, 0)
+                    .scale(vec2(20.0, 10.0))
+                    .rotate(angle);
+            },
+        ];
+
+        let quarter = Mat2::from_angle(std::f32::consts::FRAC_PI_2);
+        for (i, draw) in draws.iter().enumerate() {
+            draw(&mut batch, 0.0);
+            let upright = drawn_points(&mut batch);
+            draw(&mut batch, std::f32::consts::FRAC_PI_2);
+            let turned = drawn_points(&mut batch);
+            assert!(!upright.is_empty() && upright.len() == turned.len());
+            // the path pivots on its own origin, the top-left corner
+            let pivot = if i == 3 { corners[0] } else { Vec2::ZERO };
+            for (a, b) in upright.iter().zip(&turned) {
+                let expected = quarter * (*a - pivot) + pivot;
+                assert!(
+                    expected.distance(*b) < 1e-3,
+                    "primitive {i}: {b} != {expected}"
+                );
             }
-            for (i, idx) in indices.iter_mut().enumerate().take(idx_count) {
-                *idx = base + geometry.indices[i];
+        }
+    }
+
+    #[test]
+    fn curve_tolerance_sets_the_vertex_count() {
+        let circle_vertices = |batch: &mut PrimitiveBatch, build: &dyn Fn(PathBuilder)| {
+            batch.reset();
+            build(
+                PathBuilder::new(batch, None)
+                    .circle(50.0)
+                    .fill_color(Color::RED),
+            );
+            batch
+                .iter_mut()
+                .map(|(.., g)| g.vertex_count())
+                .sum::<usize>()
+        };
+        let mut batch = PrimitiveBatch::default();
+        let coarse = circle_vertices(&mut batch, &|p| drop(p.tolerance(1.0)));
+        let fine = circle_vertices(&mut batch, &|p| drop(p.tolerance(0.01)));
+        assert!(coarse < fine, "{coarse} vs {fine} vertices");
+
+        batch.curve_quality = QualityMode::ZoomAdaptive;
+        let zoomed: Vec<_> = [0.25, 1.0, 8.0]
+            .map(|zoom| circle_vertices(&mut batch, &|p| drop(p.with_zoom(zoom))))
+            .into();
+        assert!(zoomed.is_sorted() && zoomed[0] < zoomed[2], "{zoomed:?}");
+        // scaling a path up counts like zooming in
+        let scaled = circle_vertices(&mut batch, &|p| drop(p.scale(Vec2::splat(8.0))));
+        assert_eq!(scaled, zoomed[2]);
+
+        // an explicit tolerance ignores the zoom
+        let pinned = circle_vertices(&mut batch, &|p| drop(p.with_zoom(8.0).tolerance(1.0)));
+        assert_eq!(pinned, coarse);
+    }
+
+    #[test]
+    fn layers_draw_in_declared_order_and_can_be_hidden() {
+        let mut batch = PrimitiveBatch::default();
+        batch.layers.set_order(&["bg", "hud"]);
+        let (bg, hud) = (batch.layers.index("bg"), batch.layers.index("hud"));
+        batch.layers.get_mut(bg).camera_slot = 1;
+        batch.layers.get_mut(hud).camera_slot = 2;
+
+        batch.set_layer(hud);
+        RectangleBuilder::new(&mut batch, None);
+        batch.set_layer(0);
+        RectangleBuilder::new(&mut batch, None);
+        batch.set_layer(bg);
+        RectangleBuilder::new(&mut batch, None);
+
+        let slots: Vec<_> = batch.iter_mut().map(|(_, _, slot, ..)| slot).collect();
+        assert_eq!(slots, [0, 1, 2]);
+
+        batch.layers.get_mut(hud).enabled(false);
+        let slots: Vec<_> = batch.iter_mut().map(|(_, _, slot, ..)| slot).collect();
+        assert_eq!(slots, [0, 1]);
+    }
+
+    /// A frame of rects, paths & layered shapes, flushed & reset like the renderer does
+    fn draw_frame(batch: &mut PrimitiveBatch, hud: usize) {
+        for i in 0..100 {
+            let x = i as f32;
+            RectangleBuilder::new(batch, None).at(vec2(x, 0.0));
+            PathBuilder::new(batch, None)
+                .begin(vec2(x, 0.0))
+                .quad_to(vec2(x + 5.0, 10.0), vec2(x + 10.0, 0.0))
+                .close()
+                .fill_color(Color::RED)
+                .thickness(2.0);
+        }
+        let previous = batch.set_layer(hud);
+        PathBuilder::new(batch, None).circle(8.0);
+        batch.set_layer(previous);
+        RectangleBuilder::new(batch, None);
+        assert!(batch.iter_mut().count() >= 3);
+        batch.reset();
+    }
+
+    #[test]
+    fn steady_frames_reuse_batches_and_paths() {
+        let mut batch = PrimitiveBatch::default();
+        batch.layers.set_order(&["hud"]);
+        let hud = batch.layers.index("hud");
+        draw_frame(&mut batch, hud);
+
+        assert_eq!(allocations_during(|| draw_frame(&mut batch, hud)), 0);
+    }
+
+    #[test]
+    fn uv_mistakes_are_reported_once() {
+        let mut batch = PrimitiveBatch::default();
+        batch.texture_check.loaded = 1;
+        RectangleBuilder::new(&mut batch, None).sprite(0, [0.0, 0.0, 0.5, 0.5]);
+        RectangleBuilder::new(&mut batch, None)
+            .texture(0)
+            .uv([1.0, 0.0, 0.0, 1.0]);
+        let check = &batch.texture_check;
+        assert!(!check.reported_untextured_uvs && !check.reported_empty_uvs);
+
+        RectangleBuilder::new(&mut batch, None).uv([0.0, 0.0, 0.5, 0.5]);
+        RectangleBuilder::new(&mut batch, None).uv([0.5, 0.0, 0.5, 0.5]);
+        let check = &batch.texture_check;
+        assert_eq!(check.reported_untextured_uvs, cfg!(debug_assertions));
+        assert_eq!(check.reported_empty_uvs, cfg!(debug_assertions));
+    }
+
+    #[test]
+    fn sprite_uvs_are_checked_but_shader_uvs_need_no_texture() {
+        let mut batch = PrimitiveBatch::default();
+        batch.texture_check.loaded = 1;
+        RectangleBuilder::new(&mut batch, Some(1)).uv([0.0, 0.0, 0.5, 0.5]);
+        assert!(!batch.texture_check.reported_untextured_uvs);
+
+        RectangleBuilder::new(&mut batch, None).sprite(0, [0.5, 0.0, 0.5, 0.5]);
+        let check = &batch.texture_check;
+        assert!(!check.reported_untextured_uvs);
+        assert_eq!(check.reported_empty_uvs, cfg!(debug_assertions));
+    }
+
+    #[test]
+    fn uv_rects_map_to_the_same_corners_as_arrays() {
+        let mut batch = PrimitiveBatch::default();
+        let uv = [0.25, 0.5, 0.75, 1.0];
+        RectangleBuilder::new(&mut batch, None).texture(1).uv(uv);
+        RectangleBuilder::new(&mut batch, None)
+            .texture(1)
+            .uv_rect(Rect::new(vec2(0.25, 0.5), vec2(0.5, 0.5)));
+        RectangleBuilder::new(&mut batch, None).sprite(1, uv);
+
+        let (.., geometry) = batch.iter_mut().next().unwrap();
+        let uvs: Vec<_> = geometry.instances().iter().map(|i| i.uv).collect();
+        assert_eq!(uvs, [uv; 3]);
+    }
+
+    #[test]
+    fn unloaded_texture_ids_are_reported_once() {
+        let mut batch = PrimitiveBatch::default();
+        batch.texture_check.loaded = 2;
+        RectangleBuilder::new(&mut batch, None).texture(1);
+        RectangleBuilder::new(&mut batch, None).texture(TextureId(5));
+        RectangleBuilder::new(&mut batch, None).texture_layer((5, 0));
+        assert_eq!(
+            batch.texture_check.reported.contains(&5),
+            cfg!(debug_assertions)
+        );
+        assert_eq!(
+            batch.texture_check.reported.len(),
+            cfg!(debug_assertions) as usize
+        );
+    }
+
+    #[test]
+    fn unloaded_texture_ids_are_reported() {
+        let mut batch = PrimitiveBatch::default();
+        batch.texture_check.loaded = 2;
+        batch.texture_check.unloaded.insert(1);
+        RectangleBuilder::new(&mut batch, None).texture(0);
+        RectangleBuilder::new(&mut batch, None).texture(1);
+        assert_eq!(
+            batch.texture_check.reported.contains(&1),
+            cfg!(debug_assertions)
+        );
+        assert!(!batch.texture_check.reported.contains(&0));
+    }
+
+    #[test]
+    #[should_panic(expected = "texture 1, which was unloaded")]
+    fn strict_texture_checks_panic_on_unloaded_ids() {
+        let mut batch = PrimitiveBatch::default();
+        (batch.texture_check.loaded, batch.texture_check.strict) = (2, true);
+        batch.texture_check.unloaded.insert(1);
+        RectangleBuilder::new(&mut batch, None).texture(1);
+    }
+
+    #[test]
+    #[should_panic(expected = "texture 3, which was never loaded")]
+    fn strict_texture_checks_panic() {
+        let mut batch = PrimitiveBatch::default();
+        batch.texture_check.strict = true;
+        RectangleBuilder::new(&mut batch, None).texture(3);
+    }
+
+    #[test]
+    fn viewports_split_batches_and_nest() {
+        let mut batch = PrimitiveBatch::default();
+        let left = Rect::new(Vec2::ZERO, vec2(50.0, 100.0));
+        RectangleBuilder::new(&mut batch, None);
+        let outer = batch.begin_viewport(left, Camera::default());
+        RectangleBuilder::new(&mut batch, None);
+        assert_eq!(batch.viewport_origin(), Vec2::ZERO);
+        batch.end_viewport(outer, Camera::default());
+        let right = Rect::new(vec2(50.0, 0.0), vec2(50.0, 100.0));
+        let outer = batch.begin_viewport(right, Camera::default());
+        assert_eq!(batch.viewport_origin(), vec2(50.0, 0.0));
+        RectangleBuilder::new(&mut batch, None);
+        batch.end_viewport(outer, Camera::default());
+        RectangleBuilder::new(&mut batch, None);
+        batch.viewports[1].camera_slot = 2;
+
+        let entries: Vec<_> = batch
+            .iter_mut()
+            .map(|(_, _, slot, rect, _, g)| (slot, rect, g.instance_count()))
+            .collect();
+        assert_eq!(
+            entries,
+            [
+                (0, None, 1),
+                (0, Some(left), 1),
+                (2, Some(right), 1),
+                (0, None, 1)
+            ]
+        );
+        batch.reset();
+        assert!(batch.viewports.is_empty());
+    }
+
+    #[test]
+    fn nested_tints_multiply_and_restore() {
+        let mut batch = PrimitiveBatch::default();
+        let outer = batch.push_tint(Color::WHITE.with_alpha(0.5));
+        let inner = batch.push_tint(Color::new([0.5, 1.0, 1.0, 0.5]));
+        assert_eq!(batch.tinted(Color::RED), [0.5, 0.0, 0.0, 0.25]);
+
+        batch.set_tint(inner);
+        assert_eq!(batch.tinted(Color::RED), [1.0, 0.0, 0.0, 0.5]);
+        batch.set_tint(outer);
+        assert_eq!(batch.tinted(Color::RED), Color::RED.components());
+    }
+
+    /// A curve-heavy flower path: `petals` cubic petals, filled & stroked
+    fn flower(builder: PathBuilder, center: Vec2, petals: usize) -> PathBuilder {
+        let mut builder = builder
+            .at(center)
+            .fill_color(Color::RED)
+            .stroke_color(Color::WHITE)
+            .thickness(1.5)
+            .begin(vec2(20.0, 0.0));
+        for i in 0..petals {
+            let angle = |t: f32| (i as f32 + t) / petals as f32 * std::f32::consts::TAU;
+            let point = |t: f32, r: f32| Vec2::from_angle(angle(t)) * r;
+            builder = builder.cubic_to(point(0.2, 60.0), point(0.8, 60.0), point(1.0, 20.0));
+        }
+        builder.close()
+    }
+
+    fn draw_flowers(batch: &mut PrimitiveBatch, count: usize, parallel: bool) {
+        let center = |i: usize| vec2((i % 25) as f32 * 40.0, (i / 25) as f32 * 40.0);
+        if parallel {
+            let mut shapes = ParallelShapes::new(batch, None, 1.0);
+            for i in 0..count {
+                flower(shapes.path(), center(i), 8 + i % 5);
+            }
+        } else {
+            for i in 0..count {
+                flower(PathBuilder::new(batch, None), center(i), 8 + i % 5);
+            }
+        }
+    }
+
+    #[test]
+    fn parallel_shapes_match_immediate_paths() {
+        let (mut immediate, mut parallel) = (PrimitiveBatch::default(), PrimitiveBatch::default());
+        draw_flowers(&mut immediate, 60, false);
+        draw_flowers(&mut parallel, 60, true);
+
+        let sizes = |batch: &mut PrimitiveBatch| -> Vec<_> {
+            batch
+                .iter_mut()
+                .map(|(.., g)| (g.vertex_count(), g.index_count()))
+                .collect()
+        };
+        assert_eq!(sizes(&mut immediate), sizes(&mut parallel));
+        assert_ne!(counts(&mut parallel), (0, 0, 0));
+    }
+
+    /// Run with `cargo test -p egor_glue --release -- --ignored --nocapture bench_`
+    #[test]
+    #[ignore]
+    fn bench_parallel_shapes() {
+        use std::time::Instant;
+
+        let mut batch = PrimitiveBatch::default();
+        for parallel in [false, true] {
+            // warm up, then time a few frames of 500 paths
+            draw_flowers(&mut batch, 500, parallel);
+            batch.reset();
+            let start = Instant::now();
+            for _ in 0..10 {
+                draw_flowers(&mut batch, 500, parallel);
+                batch.reset();
+            }
+            let mode = if parallel { "parallel" } else { "immediate" };
+            println!("{mode}: {:.2?} per frame", start.elapsed() / 10);
+        }
+    }
+
+    #[test]
+    fn static_chunks_draw_their_batches_every_frame() {
+        let mut batch = PrimitiveBatch::default();
+        let quad = |batch: &mut PrimitiveBatch| {
+            RectangleBuilder::new(batch, None).size(Vec2::ONE);
+        };
+        quad(&mut batch);
+        let previous = batch.begin_capture();
+        for _ in 0..3 {
+            quad(&mut batch);
+        }
+        let baked = batch.end_capture(previous);
+        // captured quads don't merge into the batch from before the capture
+        assert_eq!(baked.len(), 1);
+        assert_eq!(baked[0].geometry.instance_count(), 3);
+        assert_eq!(batch.batch_sizes().count(), 1);
+        let id = batch.statics.insert(baked);
+
+        batch.reset();
+        for _ in 0..2 {
+            batch.push_static(id);
+            // later quads start a batch of their own
+            quad(&mut batch);
+            let sizes: Vec<_> = batch.batch_sizes().map(|(.., shapes)| shapes).collect();
+            assert_eq!(sizes, [3, 1]);
+            batch.reset();
+        }
+
+        batch.statics.remove(id);
+        batch.push_static(id);
+        assert!(batch.is_empty());
+    }
+
+    #[test]
+    fn static_chunks_drawn_while_baking_are_left_out() {
+        let mut batch = PrimitiveBatch::default();
+        let previous = batch.begin_capture();
+        RectangleBuilder::new(&mut batch, None).size(Vec2::ONE);
+        let inner = batch.end_capture(previous);
+        let inner = batch.statics.insert(inner);
+
+        let previous = batch.begin_capture();
+        batch.push_static(inner);
+        RectangleBuilder::new(&mut batch, None).size(Vec2::ONE);
+        let outer = batch.end_capture(previous);
+        // only the rect's batch
+        assert_eq!(outer.len(), 1);
+        assert_eq!(outer[0].geometry.instance_count(), 1);
+    }
+
+    #[test]
+    fn invisible_shapes_record_no_draws() {
+        // room for a 16 sided polygon, not a 64 sided one
+        let mut batch = PrimitiveBatch::new(16, 48);
+        batch.shadow_texture = Some(0);
+        let clear = Color::TRANSPARENT;
+        RectangleBuilder::new(&mut batch, None)
+            .color(clear)
+            .stroke(2.0)
+            .shadow(Vec2::ONE, 4.0, clear)
+            .pick_id(7);
+        PolygonBuilder::new(&mut batch, None)
+            .radius(8.0)
+            .color(clear);
+        PolylineBuilder::new(&mut batch, None)
+            .points(&[Vec2::ZERO, vec2(8.0, 8.0)])
+            .color(Color::RED.with_alpha(0.0));
+        PathBuilder::new(&mut batch, None)
+            .fill_color(clear)
+            .stroke_color(clear)
+            .begin(Vec2::ZERO)
+            .line_to(vec2(8.0, 0.0))
+            .line_to(vec2(8.0, 8.0));
+        PolygonBuilder::new(&mut batch, None)
+            .radius(8.0)
+            .segments(64);
+        // a parent tint fades out what's drawn in it
+        let previous = batch.push_tint(clear);
+        RectangleBuilder::new(&mut batch, None);
+        batch.set_tint(previous);
+
+        assert_eq!(batch.batch_sizes().count(), 0);
+        assert_eq!(batch.iter_mut().count(), 0);
+        // fill, outline & shadow, polygon, polyline, path fill & stroke, tinted rect & the
+        // batch the oversized polygon left empty
+        assert_eq!(batch.take_skipped(), 9);
+        // invisible rectangles still make hit areas
+        assert_eq!(
+            batch.picking.pick(
+                vec2(32.0, 32.0),
+                &batch.layers,
+                &Coords::new(Camera::default(), 1.0)
+            ),
+            Some(7)
+        );
+    }
+
+    #[test]
+    fn invisible_shapes_still_draw_textured_or_with_custom_shaders() {
+        let mut batch = PrimitiveBatch::default();
+        let clear = Color::TRANSPARENT;
+        // a custom shader may blend additively or ignore the color
+        RectangleBuilder::new(&mut batch, Some(0)).color(clear);
+        RectangleBuilder::new(&mut batch, None)
+            .texture(1)
+            .color(clear);
+        PolygonBuilder::new(&mut batch, Some(0))
+            .radius(8.0)
+            .color(clear);
+
+        assert_eq!(batch.iter_mut().count(), 3);
+        assert_eq!(batch.take_skipped(), 0);
+    }
+
+    #[test]
+    fn captures_record_each_draw_and_its_batch() {
+        let mut batch = PrimitiveBatch::default();
+        RectangleBuilder::new(&mut batch, None);
+        batch.iter_mut().count();
+        assert!(batch.take_capture().is_none());
+
+        batch.request_capture();
+        batch.reset();
+        let line = line!() + 1;
+        batch.open_draw(DrawKind::Rect);
+        RectangleBuilder::new(&mut batch, None)
+            .at(vec2(10.0, 20.0))
+            .size(vec2(4.0, 2.0))
+            .texture(3);
+        batch.close_draw();
+        batch.open_draw(DrawKind::Polygon);
+        PolygonBuilder::new(&mut batch, None).points(&[Vec2::ZERO, Vec2::X, Vec2::Y]);
+        batch.close_draw();
+        // shapes drawn by egor itself aren't recorded
+        RectangleBuilder::new(&mut batch, None);
+        assert_eq!(batch.iter_mut().count(), 3);
+
+        let capture = batch.take_capture().unwrap();
+        let [rect, polygon] = &capture.draws[..] else {
+            panic!("expected 2 draws, got {:?}", capture.draws);
+        };
+        assert_eq!(rect.kind, DrawKind::Rect);
+        assert!(rect.location.file().ends_with("primitives.rs"));
+        assert_eq!(rect.location.line(), line);
+        assert_eq!((rect.texture, rect.layer.as_str()), (Some(3), "default"));
+        assert_eq!((rect.vertices, rect.instances), (4, 1));
+        assert_eq!(
+            rect.bounds,
+            Some(Rect::new(vec2(10.0, 20.0), vec2(4.0, 2.0)))
+        );
+        assert_eq!((polygon.vertices, polygon.instances), (3, 0));
+        assert_eq!(polygon.bounds, Some(Rect::new(Vec2::ZERO, Vec2::ONE)));
+
+        assert_eq!(
+            (&rect.batches[..], &polygon.batches[..]),
+            (&[0][..], &[1][..])
+        );
+        assert_eq!(capture.batches.len(), 3);
+        assert_eq!(capture.batches[0].texture, Some(3));
+        assert_eq!(capture.batches[1].draws, [1]);
+        assert!(capture.batches[2].draws.is_empty());
+
+        // only the requested frame is recorded
+        batch.reset();
+        RectangleBuilder::new(&mut batch, None);
+        batch.iter_mut().count();
+        assert!(batch.take_capture().is_none());
+    }
+
+    #[test]
+    fn transforms_nest_moving_shapes_and_hit_areas() {
+        let mut batch = PrimitiveBatch::default();
+        let hub = Transform::from_translation(vec2(100.0, 0.0));
+        batch.push_transform(hub.to_affine());
+        batch.push_transform(Transform::from_rotation(FRAC_PI_2).to_affine());
+        // a blade along local x ends up pointing down from the hub
+        RectangleBuilder::new(&mut batch, None)
+            .at(vec2(10.0, -1.0))
+            .size(vec2(10.0, 2.0))
+            .pick_id(3);
+        PolygonBuilder::new(&mut batch, None).points(&[Vec2::ZERO, Vec2::X, Vec2::Y]);
+        assert!(batch.pop_transform());
+        RectangleBuilder::new(&mut batch, None).size(Vec2::ONE);
+        assert!(batch.pop_transform());
+        assert!(!batch.pop_transform());
+        assert_eq!(batch.transform(), Affine2::IDENTITY);
+
+        let close = |a: Vec2, b: Vec2| a.abs_diff_eq(b, 1e-4);
+        let mut instances = Vec::new();
+        let mut vertices = Vec::new();
+        for (.., geometry) in batch.iter_mut() {
+            instances.extend(geometry.instances().iter().map(|i| (i.affine, i.translate)));
+            vertices.extend(geometry.vertices().iter().map(|v| Vec2::from(v.position)));
+        }
+        let [(blade, blade_center), (_, hub_center)] = instances[..] else {
+            panic!("expected 2 instances, got {instances:?}");
+        };
+        assert!(close(blade_center.into(), vec2(100.0, 15.0)));
+        assert!(close(vec2(blade[0], blade[1]), vec2(0.0, 10.0)));
+        assert!(close(vec2(blade[2], blade[3]), vec2(-2.0, 0.0)));
+        assert!(close(hub_center.into(), vec2(100.5, 0.5)));
+        let expected = [vec2(100.0, 0.0), vec2(100.0, 1.0), vec2(99.0, 0.0)];
+        assert!(vertices.iter().zip(expected).all(|(&v, e)| close(v, e)));
+
+        let pick = |batch: &PrimitiveBatch, at| {
+            batch
+                .picking
+                .pick(at, &batch.layers, &Coords::new(Camera::default(), 1.0))
+        };
+        assert_eq!(pick(&batch, vec2(100.0, 18.0)), Some(3));
+        assert_eq!(pick(&batch, vec2(15.0, 0.0)), None);
+
+        // a transform left pushed doesn't outlive the frame
+        batch.push_transform(hub.to_affine());
+        batch.reset();
+        assert_eq!(batch.transform(), Affine2::IDENTITY);
+        assert!(!batch.pop_transform());
+    }
+
+    #[test]
+    fn transformed_frames_reuse_their_stacks() {
+        let mut batch = PrimitiveBatch::default();
+        let frame = |batch: &mut PrimitiveBatch| {
+            for i in 0..100 {
+                let spin = Transform::from_rotation(i as f32).to_affine();
+                batch.push_transform(spin);
+                RectangleBuilder::new(batch, None).size(Vec2::ONE);
+                PolygonBuilder::new(batch, None).points(&[Vec2::ZERO, Vec2::X, Vec2::Y]);
+                batch.pop_transform();
+            }
+            batch.iter_mut().count();
+            batch.reset();
+        };
+        frame(&mut batch);
+
+        assert_eq!(allocations_during(|| frame(&mut batch)), 0);
+    }
+
+    #[test]
+    fn zero_sized_shapes_take_no_batch_space() {
+        let mut batch = PrimitiveBatch::default();
+        for i in 0..10_000 {
+            // particles squashed to nothing along either axis
+            let (x, y) = (i as f32, 0.0);
+            RectangleBuilder::new(&mut batch, None)
+                .size(if i % 2 == 0 { vec2(x, y) } else { vec2(y, x) })
+                .stroke(2.0)
+                .shadow(Vec2::ONE, 4.0, Color::BLACK)
+                .pick_id(i);
+        }
+        assert_eq!(counts(&mut batch), (0, 0, 0));
+        assert_eq!(batch.take_skipped(), 10_000);
+
+        PolygonBuilder::new(&mut batch, None)
+            .radius(0.0)
+            .stroke(1.0);
+        PolygonBuilder::new(&mut batch, None).points(&[Vec2::ZERO, Vec2::X]);
+        PolylineBuilder::new(&mut batch, None).thickness(0.0005);
+        RectangleBuilder::new(&mut batch, None).size(vec2(f32::NAN, 4.0));
+        PolygonBuilder::new(&mut batch, None).radius(f32::NAN);
+        RectangleBuilder::new(&mut batch, None)
+            .fill(false)
+            .stroke(0.0);
+        // squashed flat by a parent transform
+        batch.push_transform(Affine2::from_scale(vec2(1.0, 0.0)));
+        RectangleBuilder::new(&mut batch, None);
+        batch.pop_transform();
+        assert_eq!(counts(&mut batch), (0, 0, 0));
+        // the stroke-only rectangle is drawn, just not its outline
+        assert_eq!(batch.take_skipped(), 7);
+    }
+
+    #[test]
+    fn negative_sizes_mirror_rectangles() {
+        let mut batch = PrimitiveBatch::default();
+        RectangleBuilder::new(&mut batch, None)
+            .at(vec2(10.0, 10.0))
+            .size(vec2(-4.0, 2.0))
+            .pick_id(1);
+        let instances: Vec<_> = batch
+            .iter_mut()
+            .flat_map(|(.., g)| g.instances().to_vec())
+            .collect();
+        // spans x 6..10, flipped horizontally
+        assert_eq!(instances[0].translate, [8.0, 11.0]);
+        assert_eq!(instances[0].affine, [-4.0, 0.0, 0.0, 2.0]);
+        assert_eq!(
+            batch.picking.pick(
+                vec2(7.0, 11.0),
+                &batch.layers,
+                &Coords::new(Camera::default(), 1.0)
+            ),
+            Some(1)
+        );
+        assert_eq!(batch.take_skipped(), 0);
+    }
+
+    /// Textures of the instances drawn, back to front
+    fn drawn_textures(batch: &mut PrimitiveBatch) -> Vec<Option<usize>> {
+        (batch.iter_mut())
+            .flat_map(|(texture, .., g)| std::iter::repeat_n(texture, g.instance_count()))
+            .collect()
+    }
+
+    #[test]
+    fn y_sorted_characters_swap_order_as_they_pass() {
+        let mut batch = PrimitiveBatch::default();
+        let entities = batch.layers.index("entities");
+        batch.layers.get_mut(entities).sort_by_y(true);
+        let (hero, villager) = (1, 2);
+        let draw = |batch: &mut PrimitiveBatch, texture, y| {
+            RectangleBuilder::new(batch, None)
+                .at(vec2(0.0, y))
+                .size(vec2(16.0, 24.0))
+                .texture(texture);
+        };
+
+        // the villager stands at y 50, the hero walks down past them
+        for y in [20.0, 40.0, 60.0, 80.0] {
+            for hero_first in [true, false] {
+                batch.set_layer(entities);
+                match hero_first {
+                    true => (draw(&mut batch, hero, y), draw(&mut batch, villager, 50.0)),
+                    false => (draw(&mut batch, villager, 50.0), draw(&mut batch, hero, y)),
+                };
+                let front = match y > 50.0 {
+                    true => Some(hero),
+                    false => Some(villager),
+                };
+                assert_eq!(
+                    drawn_textures(&mut batch).last(),
+                    Some(&front),
+                    "hero at {y}"
+                );
+                batch.reset();
             }
         }
     }
+
+    #[test]
+    fn y_sorted_neighbours_share_batches() {
+        let mut batch = PrimitiveBatch::default();
+        let entities = batch.layers.index("entities");
+        batch.layers.get_mut(entities).sort_by_y(true);
+        RectangleBuilder::new(&mut batch, None).at(vec2(0.0, -100.0));
+        batch.set_layer(entities);
+        // alternating textures, but sorted they're two runs of the same texture
+        for (y, texture) in [
+            (0.0, 1),
+            (30.0, 2),
+            (10.0, 2),
+            (40.0, 1),
+            (20.0, 2),
+            (50.0, 1),
+        ] {
+            RectangleBuilder::new(&mut batch, None)
+                .at(vec2(0.0, y))
+                .size(vec2(8.0, 8.0))
+                .texture(texture);
+        }
+        let polygon = [vec2(0.0, 44.0), vec2(1.0, 44.0), vec2(0.0, 45.0)];
+        PolygonBuilder::new(&mut batch, None).points(&polygon);
+        // a rug stepped on by everything
+        RectangleBuilder::new(&mut batch, None)
+            .at(vec2(0.0, 60.0))
+            .size(vec2(8.0, 8.0))
+            .texture(3)
+            .sort_bias(-100.0);
+        batch.set_layer(0);
+        RectangleBuilder::new(&mut batch, None).at(vec2(0.0, 100.0));
+
+        let batches: Vec<_> = (batch.iter_mut())
+            .map(|(texture, .., g)| (texture, g.instance_count(), g.vertices().len()))
+            .collect();
+        assert_eq!(
+            batches,
+            [
+                (None, 1, 0),
+                (None, 1, 0),
+                (Some(3), 1, 0),
+                (Some(1), 1, 0),
+                (Some(2), 3, 0),
+                (None, 0, 3),
+                (Some(1), 2, 0),
+            ]
+        );
+        assert_eq!(batch.take_skipped(), 0);
+    }
+
+    #[test]
+    fn y_sorted_pieces_of_one_shape_stay_together_on_equal_keys() {
+        let mut batch = PrimitiveBatch::default();
+        let entities = batch.layers.index("entities");
+        batch.layers.get_mut(entities).sort_by_y(true);
+        batch.set_layer(entities);
+        for texture in [1, 2, 1] {
+            RectangleBuilder::new(&mut batch, None).texture(texture);
+        }
+        // the last piece belongs to the first shape, like a fill written after another
+        // shape's batch
+        let first = batch.sort_pieces[0].draw;
+        batch.sort_pieces[2].draw = first;
+
+        assert_eq!(drawn_textures(&mut batch), [Some(1), Some(1), Some(2)]);
+    }
+
+    #[test]
+    fn y_sorted_layers_reuse_batches_and_track_captured_shapes() {
+        let mut batch = PrimitiveBatch::default();
+        let entities = batch.layers.index("entities");
+        batch.layers.get_mut(entities).sort_by_y(true);
+        let frame = |batch: &mut PrimitiveBatch| {
+            batch.set_layer(entities);
+            for i in 0..100 {
+                let y = (i * 37 % 100) as f32;
+                batch.open_draw(DrawKind::Rect);
+                RectangleBuilder::new(batch, None)
+                    .at(vec2(0.0, y))
+                    .texture(i % 2);
+            }
+            batch.iter_mut().count()
+        };
+        frame(&mut batch);
+        batch.reset();
+        assert_eq!(allocations_during(|| assert_eq!(frame(&mut batch), 100)), 0);
+
+        batch.request_capture();
+        batch.reset();
+        frame(&mut batch);
+        let capture = batch.take_capture().unwrap();
+        for (i, draw) in capture.draws.iter().enumerate() {
+            let [index] = draw.batches[..] else {
+                panic!("draw {i} landed in {:?}", draw.batches);
+            };
+            assert_eq!(capture.batches[index].texture, Some(i % 2));
+            assert!(capture.batches[index].draws.contains(&i));
+        }
+    }
 }
@@ -0,0 +1,248 @@
+use std::{collections::VecDeque, fmt, io, path::Path};
+
+/// Frames kept for [`Profiler::trace_json`], 2 seconds at 60 FPS
+#[cfg(feature = "profiling")]
+const TRACE_FRAMES: usize = 120;
+
+/// A named scope timed during one frame
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProfileScope {
+    pub name: &'static str,
+    /// Index of the enclosing scope in [`ProfileReport::scopes`], `None` at the top level
+    pub parent: Option<usize>,
+    /// Nesting depth, 0 at the top level
+    pub depth: usize,
+    /// Milliseconds from the start of the frame to the start of the scope
+    pub start_ms: f64,
+    pub duration_ms: f64,
+}
+
+/// CPU timings of every scope in a frame, engine & user scopes alike.
+/// Get the last complete frame's with [`crate::graphics::Graphics::profiling_report`]
+///
+/// Printing a report shows the scopes as an indented tree
+#[derive(Clone, Debug, Default)]
+pub struct ProfileReport {
+    scopes: Vec<ProfileScope>,
+    /// Milliseconds since the profiler started, used to place frames on a trace timeline
+    frame_start_ms: f64,
+}
+
+impl ProfileReport {
+    /// All scopes in the order they started; parents always come before their children
+    pub fn scopes(&self) -> &[ProfileScope] {
+        &self.scopes
+    }
+
+    /// Scopes directly inside the scope at `index`, or top level scopes for `None`
+    pub fn children(&self, index: Option<usize>) -> impl Iterator<Item = &ProfileScope> {
+        self.scopes.iter().filter(move |s| s.parent == index)
+    }
+
+    /// Total milliseconds spent in scopes called `name`, summed if it ran more than once
+    pub fn total_ms(&self, name: &str) -> Option<f64> {
+        self.scopes
+            .iter()
+            .filter(|s| s.name == name)
+            .map(|s| s.duration_ms)
+            .reduce(|a, b| a + b)
+    }
+}
+
+impl fmt::Display for ProfileReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for scope in &self.scopes {
+            let indent = scope.depth * 2;
+            writeln!(f, "{:indent$}{} {:.3}ms", "", scope.name, scope.duration_ms)?;
+        }
+        Ok(())
+    }
+}
+
+/// Records [`ProfileScope`]s per frame. Only does anything with the `profiling` feature;
+/// without it scopes are plain calls & reports stay empty
+#[derive(Default)]
+pub(crate) struct Profiler {
+    #[cfg(feature = "profiling")]
+    current: ProfileReport,
+    #[cfg(feature = "profiling")]
+    stack: Vec<usize>,
+    #[cfg(feature = "profiling")]
+    clock: clock::Clock,
+    frames: VecDeque<ProfileReport>,
+}
+
+impl Profiler {
+    /// Starts recording a new frame
+    pub fn begin_frame(&mut self) {
+        #[cfg(feature = "profiling")]
+        {
+            self.stack.clear();
+            self.current = ProfileReport {
+                scopes: Vec::new(),
+                frame_start_ms: self.clock.now_ms(),
+            };
+        }
+    }
+
+    /// Finishes the frame, making it the latest [`Self::report`]
+    pub fn end_frame(&mut self) {
+        #[cfg(feature = "profiling")]
+        {
+            if self.frames.len() == TRACE_FRAMES {
+                self.frames.pop_front();
+            }
+            self.frames.push_back(std::mem::take(&mut self.current));
+        }
+    }
+
+    /// Runs `f` inside a scope called `name`, nested in any scope that's already open
+    #[inline(always)]
+    pub fn scope<R>(&mut self, name: &'static str, f: impl FnOnce() -> R) -> R {
+        #[cfg(feature = "profiling")]
+        self.enter(name);
+        #[cfg(not(feature = "profiling"))]
+        let _ = name;
+        let result = f();
+        #[cfg(feature = "profiling")]
+        self.exit();
+        result
+    }
+
+    #[cfg(feature = "profiling")]
+    pub fn enter(&mut self, name: &'static str) {
+        let scopes = &mut self.current.scopes;
+        scopes.push(ProfileScope {
+            name,
+            parent: self.stack.last().copied(),
+            depth: self.stack.len(),
+            start_ms: self.clock.now_ms() - self.current.frame_start_ms,
+            duration_ms: 0.0,
+        });
+        self.stack.push(scopes.len() - 1);
+    }
+
+    #[cfg(feature = "profiling")]
+    pub fn exit(&mut self) {
+        let elapsed = self.clock.now_ms() - self.current.frame_start_ms;
+        if let Some(scope) = self.stack.pop().map(|i| &mut self.current.scopes[i]) {
+            scope.duration_ms = elapsed - scope.start_ms;
+        }
+    }
+
+    /// The last complete frame
+    pub fn report(&self) -> Option<&ProfileReport> {
+        self.frames.back()
+    }
+
+    /// Recent frames as Chrome trace-event JSON, viewable in Perfetto or `chrome://tracing`
+    pub fn trace_json(&self) -> String {
+        let mut json = String::from("{\"traceEvents\":[");
+        let events = self
+            .frames
+            .iter()
+            .flat_map(|frame| frame.scopes.iter().map(move |s| (frame.frame_start_ms, s)));
+        for (i, (frame_start_ms, scope)) in events.enumerate() {
+            if i > 0 {
+                json.push(',');
+            }
+            json.push_str("{\"name\":\"");
+            push_json_escaped(&mut json, scope.name);
+            json.push_str(&format!(
+                "\",\"ph\":\"X\",\"ts\":{:.3},\"dur\":{:.3},\"pid\":0,\"tid\":0}}",
+                (frame_start_ms + scope.start_ms) * 1000.0,
+                scope.duration_ms * 1000.0,
+            ));
+        }
+        json.push_str("]}");
+        json
+    }
+
+    pub fn dump_trace(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        std::fs::write(path, self.trace_json())
+    }
+}
+
+fn push_json_escaped(json: &mut String, s: &str) {
+    for c in s.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            c if c.is_control() => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+}
+
+#[cfg(feature = "profiling")]
+mod clock {
+    #[cfg(not(target_arch = "wasm32"))]
+    pub struct Clock(std::time::Instant);
+
+    #[cfg(not(target_arch = "wasm32"))]
+    impl Default for Clock {
+        fn default() -> Self {
+            Self(std::time::Instant::now())
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    impl Clock {
+        pub fn now_ms(&self) -> f64 {
+            self.0.elapsed().as_secs_f64() * 1000.0
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    #[derive(Default)]
+    pub struct Clock;
+
+    #[cfg(target_arch = "wasm32")]
+    impl Clock {
+        pub fn now_ms(&self) -> f64 {
+            web_sys::window().unwrap().performance().unwrap().now()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn disabled_scopes_still_run() {
+        let mut profiler = Profiler::default();
+        profiler.begin_frame();
+        assert_eq!(profiler.scope("work", || 7), 7);
+        profiler.end_frame();
+        let scopes = profiler.report().map_or(0, |r| r.scopes().len());
+        assert_eq!(scopes, if cfg!(feature = "profiling") { 1 } else { 0 });
+    }
+
+    #[cfg(feature = "profiling")]
+    #[test]
+    fn nested_scopes_form_a_tree() {
+        let mut profiler = Profiler::default();
+        profiler.begin_frame();
+        profiler.enter("update");
+        profiler.scope("enemies", || {});
+        profiler.scope("enemies", || {});
+        profiler.exit();
+        profiler.scope("render", || {});
+        profiler.end_frame();
+
+        let report = profiler.report().unwrap();
+        let top: Vec<_> = report.children(None).map(|s| s.name).collect();
+        assert_eq!(top, ["update", "render"]);
+        let nested: Vec<_> = report.children(Some(0)).map(|s| s.depth).collect();
+        assert_eq!(nested, [1, 1]);
+        assert!(report.total_ms("enemies").unwrap() <= report.scopes()[0].duration_ms);
+        assert_eq!(report.total_ms("missing"), None);
+
+        let printed = report.to_string();
+        assert!(printed.contains("\n  enemies "));
+        let trace = profiler.trace_json();
+        assert_eq!(trace.matches("\"ph\":\"X\"").count(), 4);
+        assert!(trace.starts_with("{\"traceEvents\":[{\"name\":\"update\""));
+    }
+}
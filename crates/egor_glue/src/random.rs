@@ -0,0 +1,407 @@
+//! Seedable random numbers & value noise for jitter, particles, screen shake & terrain,
+//! without `rand` or `getrandom` (which needs extra setup on the web). Both only use integer
+//! math, `floor`, `+` & `*`, so a seed gives the same values on every platform
+
+use std::ops::Range;
+
+use glam::{Vec2, vec2};
+
+const MULTIPLIER: u64 = 6_364_136_223_846_793_005;
+const DEFAULT_STREAM: u64 = 1_442_695_040_888_963_407 >> 1;
+
+/// PCG32 random number generator, see <https://www.pcg-random.org>. Small & fast, not for
+/// anything that has to be unpredictable
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Rng {
+    state: u64,
+    increment: u64,
+}
+
+impl Rng {
+    /// A generator whose sequence only depends on `seed`
+    pub fn new(seed: u64) -> Self {
+        Self::with_stream(seed, DEFAULT_STREAM)
+    }
+
+    /// A generator on one of 2^63 independent sequences, e.g. one per system so adding
+    /// draws to one doesn't shift what another gets
+    pub fn with_stream(seed: u64, stream: u64) -> Self {
+        let mut rng = Self {
+            state: 0,
+            increment: (stream << 1) | 1,
+        };
+        rng.next_u32();
+        rng.state = rng.state.wrapping_add(seed);
+        rng.next_u32();
+        rng
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        let old = self.state;
+        self.state = old.wrapping_mul(MULTIPLIER).wrapping_add(self.increment);
+        let xorshifted = (((old >> 18) ^ old) >> 27) as u32;
+        xorshifted.rotate_right((old >> 59) as u32)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        (u64::from(self.next_u32()) << 32) | u64::from(self.next_u32())
+    }
+
+    /// Uniform in `0.0..1.0`
+    pub fn next_f32(&mut self) -> f32 {
+        // 24 bits, as many as an f32 holds below 1
+        (self.next_u32() >> 8) as f32 / (1 << 24) as f32
+    }
+
+    /// Uniform in `range`, e.g. `rng.range(0..3)` or `rng.range(-0.5..0.5)`. Panics if it's
+    /// empty
+    pub fn range<T: Uniform>(&mut self, range: Range<T>) -> T {
+        assert!(range.start < range.end, "cannot sample an empty range");
+        T::sample(self, range)
+    }
+
+    /// `true` with probability `p`, e.g. `0.25` for one in four
+    pub fn chance(&mut self, p: f32) -> bool {
+        self.next_f32() < p
+    }
+
+    /// Uniform inside the unit circle
+    pub fn vec2_in_circle(&mut self) -> Vec2 {
+        // rejection sampling keeps to `*` & `+`, where sin & cos differ between platforms
+        loop {
+            let v = vec2(self.range(-1.0..1.0), self.range(-1.0..1.0));
+            if v.length_squared() <= 1.0 {
+                return v;
+            }
+        }
+    }
+
+    /// Uniform below `bound`, without the bias of a plain `%`
+    fn below(&mut self, bound: u64) -> u64 {
+        if let Ok(bound) = u32::try_from(bound) {
+            let threshold = bound.wrapping_neg() % bound;
+            loop {
+                let r = self.next_u32();
+                if r >= threshold {
+                    return u64::from(r % bound);
+                }
+            }
+        }
+        let threshold = bound.wrapping_neg() % bound;
+        loop {
+            let r = self.next_u64();
+            if r >= threshold {
+                return r % bound;
+            }
+        }
+    }
+}
+
+/// Numbers [`Rng::range`] can draw
+pub trait Uniform: Copy + PartialOrd {
+    fn sample(rng: &mut Rng, range: Range<Self>) -> Self;
+}
+
+impl Uniform for f32 {
+    fn sample(rng: &mut Rng, range: Range<Self>) -> Self {
+        let value = range.start + (range.end - range.start) * rng.next_f32();
+        // rounding can land on the end, which is excluded
+        if value < range.end {
+            value
+        } else {
+            range.start
+        }
+    }
+}
+
+macro_rules! uniform_int {
+    ($($int:ty => $wide:ty),*) => {$(
+        impl Uniform for $int {
+            fn sample(rng: &mut Rng, range: Range<Self>) -> Self {
+                let width = (range.end as $wide).wrapping_sub(range.start as $wide) as u64;
+                (range.start as $wide).wrapping_add(rng.below(width) as $wide) as $int
+            }
+        }
+    )*};
+}
+
+uniform_int!(i32 => i64, i64 => i64, u32 => u64, u64 => u64, usize => u64);
+
+/// Smooth value noise, in `-1.0..=1.0` with features about 1 unit apart: scale the input to
+/// stretch it, e.g. `noise.noise1(time * 8.0)` for shake or `noise.fbm2(p / 64.0, 5)` for
+/// terrain. The same seed always gives the same noise
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Noise {
+    seed: u32,
+}
+
+impl Noise {
+    pub fn new(seed: u32) -> Self {
+        Self { seed }
+    }
+
+    pub fn noise1(&self, x: f32) -> f32 {
+        let (x0, tx) = lattice(x);
+        let (a, b) = (self.value(x0, 0), self.value(x0.wrapping_add(1), 0));
+        lerp(a, b, fade(tx))
+    }
+
+    pub fn noise2(&self, p: Vec2) -> f32 {
+        self.noise2_wrapped(p, None)
+    }
+
+    /// [`Self::noise2`] repeating every `period` units, for textures that tile seamlessly
+    pub fn tileable2(&self, p: Vec2, period: (u32, u32)) -> f32 {
+        self.noise2_wrapped(p, Some(period))
+    }
+
+    /// Fractal (fBm) noise: `octaves` layers of [`Self::noise1`], each twice as detailed &
+    /// half as strong as the one before
+    pub fn fbm1(&self, x: f32, octaves: u32) -> f32 {
+        fbm(octaves, |octave, frequency| {
+            self.octave(octave).noise1(x * frequency)
+        })
+    }
+
+    /// Fractal (fBm) noise: `octaves` layers of [`Self::noise2`], each twice as detailed &
+    /// half as strong as the one before
+    pub fn fbm2(&self, p: Vec2, octaves: u32) -> f32 {
+        fbm(octaves, |octave, frequency| {
+            self.octave(octave).noise2(p * frequency)
+        })
+    }
+
+    /// [`Self::fbm2`] repeating every `period` units
+    pub fn tileable_fbm2(&self, p: Vec2, octaves: u32, period: (u32, u32)) -> f32 {
+        fbm(octaves, |octave, frequency| {
+            let scale = frequency as u32;
+            let period = (
+                period.0.saturating_mul(scale),
+                period.1.saturating_mul(scale),
+            );
+            self.octave(octave).tileable2(p * frequency, period)
+        })
+    }
+
+    /// Each octave gets its own noise so their features don't line up at the origin
+    fn octave(&self, octave: u32) -> Self {
+        Self::new(self.seed.wrapping_add(octave.wrapping_mul(0x9e37_79b9)))
+    }
+
+    fn noise2_wrapped(&self, p: Vec2, period: Option<(u32, u32)>) -> f32 {
+        let ((x0, tx), (y0, ty)) = (lattice(p.x), lattice(p.y));
+        let (x1, y1) = (x0.wrapping_add(1), y0.wrapping_add(1));
+        let corner = |x: i32, y: i32| match period {
+            Some((w, h)) => {
+                let x = x.rem_euclid(w.max(1) as i32);
+                let y = y.rem_euclid(h.max(1) as i32);
+                self.value(x, y)
+            }
+            None => self.value(x, y),
+        };
+        let (fx, fy) = (fade(tx), fade(ty));
+        let top = lerp(corner(x0, y0), corner(x1, y0), fx);
+        let bottom = lerp(corner(x0, y1), corner(x1, y1), fx);
+        lerp(top, bottom, fy)
+    }
+
+    /// Random value in `-1.0..=1.0` at a lattice point
+    fn value(&self, x: i32, y: i32) -> f32 {
+        let mut h = self.seed ^ (x as u32).wrapping_mul(0x27d4_eb2d);
+        h ^= (y as u32).wrapping_mul(0x1656_67b1);
+        // PCG's output hash, see Jarzynski & Olano "Hash Functions for GPU Rendering"
+        h = h.wrapping_mul(747_796_405).wrapping_add(2_891_336_453);
+        h = ((h >> ((h >> 28) + 4)) ^ h).wrapping_mul(277_803_737);
+        h ^= h >> 22;
+        (h >> 8) as f32 / ((1 << 23) as f32) - 1.0
+    }
+}
+
+/// [`Noise::noise1`] with seed 0
+pub fn noise1(x: f32) -> f32 {
+    Noise::new(0).noise1(x)
+}
+
+/// [`Noise::noise2`] with seed 0
+pub fn noise2(x: f32, y: f32) -> f32 {
+    Noise::new(0).noise2(vec2(x, y))
+}
+
+/// Lattice cell `x` is in & how far into it
+fn lattice(x: f32) -> (i32, f32) {
+    let floor = x.floor();
+    (floor as i32, x - floor)
+}
+
+/// Quintic fade, so the noise & its slope are continuous across cells
+fn fade(t: f32) -> f32 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+/// Sums `octaves` layers, normalized back into `-1.0..=1.0`
+fn fbm(octaves: u32, mut layer: impl FnMut(u32, f32) -> f32) -> f32 {
+    let (mut sum, mut total) = (0.0, 0.0);
+    let (mut amplitude, mut frequency) = (1.0, 1.0);
+    for octave in 0..octaves {
+        sum += layer(octave, frequency) * amplitude;
+        total += amplitude;
+        amplitude *= 0.5;
+        frequency *= 2.0;
+    }
+    if total > 0.0 { sum / total } else { 0.0 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLES: usize = 100_000;
+
+    fn mean_variance(values: impl Iterator<Item = f32>) -> (f32, f32) {
+        let values: Vec<f64> = values.map(f64::from).collect();
+        let mean = values.iter().sum::<f64>() / values.len() as f64;
+        let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / values.len() as f64;
+        (mean as f32, variance as f32)
+    }
+
+    #[test]
+    fn matches_the_pcg32_reference_sequence() {
+        // first outputs of pcg32-demo seeded with 42 on stream 54
+        let mut rng = Rng::with_stream(42, 54);
+        let outputs: Vec<u32> = (0..6).map(|_| rng.next_u32()).collect();
+        assert_eq!(
+            outputs,
+            [
+                0xa15c02b7, 0x7b47f409, 0xba1d3330, 0x83d2f293, 0xbfa4784b, 0xcbed606e
+            ]
+        );
+
+        let (mut a, mut b) = (Rng::new(7), Rng::new(7));
+        assert!((0..100).all(|_| a.next_u32() == b.next_u32()));
+        assert_ne!(Rng::new(7).next_u32(), Rng::new(8).next_u32());
+    }
+
+    #[test]
+    fn floats_are_uniform_within_their_range() {
+        let mut rng = Rng::new(1);
+        let (mean, variance) = mean_variance((0..SAMPLES).map(|_| rng.next_f32()));
+        assert!((mean - 0.5).abs() < 0.01, "mean {mean}");
+        assert!((variance - 1.0 / 12.0).abs() < 0.005, "variance {variance}");
+
+        let values: Vec<f32> = (0..SAMPLES).map(|_| rng.range(-4.0..-2.0)).collect();
+        assert!(values.iter().all(|v| (-4.0..-2.0).contains(v)));
+        let (mean, variance) = mean_variance(values.into_iter());
+        assert!((mean + 3.0).abs() < 0.02, "mean {mean}");
+        assert!((variance - 4.0 / 12.0).abs() < 0.02, "variance {variance}");
+    }
+
+    #[test]
+    fn integers_hit_every_value_evenly() {
+        let mut rng = Rng::new(2);
+        let mut counts = [0usize; 10];
+        for _ in 0..SAMPLES {
+            counts[(rng.range(-5..5) + 5) as usize] += 1;
+        }
+        let expected = SAMPLES / counts.len();
+        for count in counts {
+            assert!(count.abs_diff(expected) < expected / 20, "{counts:?}");
+        }
+        assert!((0..1000).all(|_| rng.range(3usize..4) == 3));
+        assert!((0..1000).all(|_| rng.range(0..u64::MAX) < u64::MAX));
+        assert!((0..1000).all(|_| (i32::MIN..i32::MAX).contains(&rng.range(i32::MIN..i32::MAX))));
+    }
+
+    #[test]
+    fn chances_and_circles_are_fair() {
+        let mut rng = Rng::new(3);
+        let hits = (0..SAMPLES).filter(|_| rng.chance(0.25)).count();
+        assert!((hits as f32 / SAMPLES as f32 - 0.25).abs() < 0.01, "{hits}");
+        assert!(!(0..1000).any(|_| rng.chance(0.0)));
+        assert!((0..1000).all(|_| rng.chance(1.0)));
+
+        let points: Vec<Vec2> = (0..SAMPLES).map(|_| rng.vec2_in_circle()).collect();
+        assert!(points.iter().all(|p| p.length_squared() <= 1.0));
+        let (mean_x, _) = mean_variance(points.iter().map(|p| p.x));
+        let (mean_y, _) = mean_variance(points.iter().map(|p| p.y));
+        assert!(
+            mean_x.abs() < 0.01 && mean_y.abs() < 0.01,
+            "{mean_x} {mean_y}"
+        );
+        // uniform over the area, so half the points lie within radius 1/sqrt(2)
+        let (mean_r2, _) = mean_variance(points.iter().map(|p| p.length_squared()));
+        assert!((mean_r2 - 0.5).abs() < 0.01, "{mean_r2}");
+    }
+
+    #[test]
+    fn noise_matches_golden_values() {
+        // any change to these shifts every seeded world & replay
+        let noise = Noise::new(1234);
+        let golden = [
+            (
+                vec2(0.0, 0.0),
+                [-0.15250564, -0.15250564, -0.06149343, -0.017336573],
+            ),
+            (
+                vec2(0.5, 0.25),
+                [-0.5286901, -0.40479758, -0.543597, -0.53019893],
+            ),
+            (
+                vec2(3.7, -1.2),
+                [0.30753875, -0.7634708, -0.4347737, 0.4625534],
+            ),
+            (
+                vec2(-12.3, 45.6),
+                [0.23204353, -0.40238205, -0.13098167, 0.25603467],
+            ),
+        ];
+        for (p, expected) in golden {
+            let values = [
+                noise.noise1(p.x),
+                noise.noise2(p),
+                noise.fbm2(p, 4),
+                noise.tileable_fbm2(p, 3, (4, 4)),
+            ];
+            for (value, expected) in values.into_iter().zip(expected) {
+                assert!((value - expected).abs() < 1e-6, "{p}: {values:?}");
+            }
+        }
+        assert!((noise1(0.5) + 0.41751343).abs() < 1e-6);
+        assert!((noise2(0.5, 0.5) - 0.06063342).abs() < 1e-6);
+    }
+
+    #[test]
+    fn noise_is_smooth_bounded_and_tiles() {
+        let noise = Noise::new(5);
+        let mut rng = Rng::new(5);
+        for _ in 0..10_000 {
+            let p = vec2(rng.range(-100.0..100.0), rng.range(-100.0..100.0));
+            let value = noise.noise2(p);
+            assert!((-1.0..=1.0).contains(&value));
+            assert!((-1.0..=1.0).contains(&noise.fbm2(p, 6)));
+            assert!((-1.0..=1.0).contains(&noise.fbm1(p.x, 6)));
+            // no jumps between nearby points, even across cell edges
+            assert!((noise.noise2(p + Vec2::splat(0.001)) - value).abs() < 0.02);
+
+            let period = (8, 3);
+            let tiled = noise.tileable_fbm2(p, 4, period);
+            let wrapped = p + vec2(8.0, 3.0) * vec2(rng.range(-3..3) as f32, 1.0);
+            assert!((noise.tileable_fbm2(wrapped, 4, period) - tiled).abs() < 1e-3);
+        }
+        let (mean, _) = mean_variance((0..SAMPLES).map(|i| noise.noise1(i as f32 * 0.37)));
+        assert!(mean.abs() < 0.05, "mean {mean}");
+        assert_ne!(
+            Noise::new(6).noise2(vec2(0.5, 0.5)),
+            noise.noise2(vec2(0.5, 0.5))
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "empty range")]
+    fn empty_ranges_panic() {
+        Rng::new(0).range(1..1);
+    }
+}
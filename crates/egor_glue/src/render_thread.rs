@@ -0,0 +1,318 @@
+//! Drawing on a dedicated thread with the `render_thread` feature. Updates stay on the main
+//! thread, which hands the renderer & the frame's state (its batches included) to the render
+//! thread to upload, encode & present. Waiting on the GPU or vsync happens there, so the
+//! event loop keeps handling input meanwhile
+//!
+//! The renderer is only ever in one place, so at most one frame is in flight: the next update
+//! waits until the render thread hands it back, & the window's redraws are skipped until then
+
+use std::{
+    panic,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, Sender, TryRecvError},
+    },
+    thread::{self, JoinHandle},
+};
+
+use egor_render::{Device, Renderer, SurfaceError, TextureFormat, target::RenderTarget};
+
+use crate::frame::{FrameState, Queued};
+
+/// How drawing a frame went: batches drawn & draws skipped, `None` if there was no frame to
+/// draw into
+pub(crate) type Drawn = Result<Option<(usize, usize)>, SurfaceError>;
+
+/// Where the render thread draws frames, the window's swapchain outside of tests
+pub(crate) type Target = Box<dyn RenderTarget + Send>;
+
+/// What the main thread sends the render thread, handled in order
+enum Message {
+    /// Draw what an update queued, then hand the renderer & state back
+    Draw(Box<Lent>, Queued),
+    Resize(u32, u32),
+    Vsync(bool),
+    /// Swap the target frames are drawn into, `None` to draw none
+    Target(Option<Target>),
+}
+
+/// What a frame is drawn with, handed to the render thread & back
+struct Lent {
+    renderer: Renderer,
+    state: FrameState,
+    drawn: Drawn,
+}
+
+/// The main thread's side of the render thread, keeping the renderer between frames
+pub(crate) struct RenderThread {
+    /// `None` while the render thread draws with it
+    renderer: Option<Renderer>,
+    messages: Option<Sender<Message>>,
+    returned: Receiver<Box<Lent>>,
+    /// Set when a redraw was skipped waiting for the renderer, for the render thread to
+    /// request another once it hands the renderer back
+    redraw_skipped: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+    /// The swapchain's size & format, for updates to lay frames out with
+    size: (u32, u32),
+    format: TextureFormat,
+}
+
+impl RenderThread {
+    /// Starts the render thread drawing into `target`. It calls `wake` (which should request
+    /// a redraw) when it hands back the renderer a redraw was skipped for
+    pub fn spawn(renderer: Renderer, target: Target, wake: impl Fn() + Send + 'static) -> Self {
+        let (size, format) = (target.size(), target.format());
+        let (messages, received) = mpsc::channel();
+        let (hand_back, returned) = mpsc::channel();
+        let redraw_skipped = Arc::new(AtomicBool::new(false));
+        let hand_back = HandBack {
+            lent: hand_back,
+            redraw_skipped: redraw_skipped.clone(),
+            wake: Box::new(wake),
+        };
+        let device = renderer.device().clone();
+        let thread = thread::Builder::new()
+            .name("egor render".into())
+            .spawn(move || run(received, hand_back, device, Some(target)))
+            .expect("couldn't spawn the render thread");
+        Self {
+            renderer: Some(renderer),
+            messages: Some(messages),
+            returned,
+            redraw_skipped,
+            thread: Some(thread),
+            size,
+            format,
+        }
+    }
+
+    /// The renderer, `None` while the render thread draws with it
+    pub fn renderer(&mut self) -> Option<&mut Renderer> {
+        self.renderer.as_mut()
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        self.size
+    }
+
+    pub fn format(&self) -> TextureFormat {
+        self.format
+    }
+
+    /// Hands the renderer & `state` to the render thread to draw what the update queued
+    pub fn draw(&mut self, state: FrameState, queued: Queued) {
+        let renderer = self
+            .renderer
+            .take()
+            .expect("the renderer is already drawing");
+        let lent = Box::new(Lent {
+            renderer,
+            state,
+            drawn: Ok(None),
+        });
+        self.send(Message::Draw(lent, queued));
+    }
+
+    /// Takes the renderer back once the render thread finished the frame, waiting for it if
+    /// `wait`. Returns the frame's state & how drawing went, `None` if the renderer wasn't
+    /// away or (without `wait`) the frame isn't done yet, in which case the render thread
+    /// requests a redraw when it is
+    pub fn take_back(&mut self, wait: bool) -> Option<(FrameState, Drawn)> {
+        if self.renderer.is_some() {
+            return None;
+        }
+        let lent = match wait {
+            true => self.returned.recv().ok(),
+            false => {
+                // set before looking, so a frame finishing in between still requests the redraw
+                self.redraw_skipped.store(true, Ordering::SeqCst);
+                match self.returned.try_recv() {
+                    Ok(lent) => {
+                        self.redraw_skipped.store(false, Ordering::SeqCst);
+                        Some(lent)
+                    }
+                    Err(TryRecvError::Empty) => return None,
+                    Err(TryRecvError::Disconnected) => None,
+                }
+            }
+        };
+        // the render thread only hangs up by panicking, which goes on here
+        let Some(lent) = lent else {
+            let thread = self.thread.take().unwrap();
+            panic::resume_unwind(thread.join().unwrap_err());
+        };
+        let Lent {
+            renderer,
+            state,
+            drawn,
+        } = *lent;
+        self.renderer = Some(renderer);
+        Some((state, drawn))
+    }
+
+    /// Zero-sized requests (e.g. a minimized window) are ignored & keep the current size
+    pub fn resize(&mut self, w: u32, h: u32) {
+        if w > 0 && h > 0 {
+            self.size = (w, h);
+        }
+        self.send(Message::Resize(w, h));
+    }
+
+    pub fn set_vsync(&mut self, on: bool) {
+        self.send(Message::Vsync(on));
+    }
+
+    /// Replaces the target frames are drawn into, `None` to draw none
+    pub fn set_target(&mut self, target: Option<Target>) {
+        if let Some(target) = &target {
+            (self.size, self.format) = (target.size(), target.format());
+        }
+        self.send(Message::Target(target));
+    }
+
+    fn send(&self, message: Message) {
+        // a render thread that's gone panicked, which the next `take_back` reports
+        let _ = self.messages.as_ref().unwrap().send(message);
+    }
+}
+
+impl Drop for RenderThread {
+    /// Hangs up & waits for the render thread to finish the frame it's drawing
+    fn drop(&mut self) {
+        self.messages = None;
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// How the render thread returns what it drew with
+struct HandBack {
+    lent: Sender<Box<Lent>>,
+    redraw_skipped: Arc<AtomicBool>,
+    wake: Box<dyn Fn() + Send>,
+}
+
+impl HandBack {
+    /// Returns `lent` to the main thread, requesting the redraw it skipped waiting for it.
+    /// Returns `false` once the main thread hung up
+    fn send(&self, lent: Box<Lent>) -> bool {
+        if self.lent.send(lent).is_err() {
+            return false;
+        }
+        if self.redraw_skipped.swap(false, Ordering::SeqCst) {
+            (self.wake)();
+        }
+        true
+    }
+}
+
+/// The render thread's loop, until the main thread hangs up
+fn run(
+    messages: Receiver<Message>,
+    hand_back: HandBack,
+    device: Device,
+    mut target: Option<Target>,
+) {
+    for message in messages {
+        match message {
+            Message::Draw(mut lent, queued) => {
+                lent.drawn = draw(&mut lent, target.as_mut(), queued);
+                if !hand_back.send(lent) {
+                    return;
+                }
+            }
+            Message::Resize(w, h) => {
+                if let Some(target) = &mut target {
+                    target.resize(&device, w, h);
+                }
+            }
+            Message::Vsync(on) => {
+                if let Some(target) = &mut target {
+                    target.set_vsync(&device, on);
+                }
+            }
+            Message::Target(swapped) => target = swapped,
+        }
+    }
+}
+
+/// Draws into the target's next frame, dropping what was queued without one
+fn draw(lent: &mut Lent, target: Option<&mut Target>, queued: Queued) -> Drawn {
+    let Lent {
+        renderer, state, ..
+    } = lent;
+    let frame = match target {
+        Some(target) => renderer.begin_frame(target.as_mut()),
+        None => Ok(None),
+    };
+    match frame {
+        Ok(Some(frame)) => Ok(Some(state.draw(renderer, frame, queued))),
+        skipped => {
+            state.drop_queued();
+            skipped.map(|_| None)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{app::AppControl, frame::FrameInput};
+    use egor_app::{input::Input, time::FrameTimer};
+    use egor_render::{MemoryHints, target::OffscreenTarget};
+    use glam::vec2;
+    use std::sync::atomic::AtomicUsize;
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn frames_drawn_on_the_render_thread_come_back_with_their_counts() {
+        let format = TextureFormat::Rgba8UnormSrgb;
+        let renderer = Renderer::headless(format, &MemoryHints::Performance);
+        let mut renderer = pollster::block_on(renderer).unwrap();
+        let target = OffscreenTarget::new(renderer.device(), 8, 8, format);
+        let mut state = FrameState::default();
+        state.init(&mut renderer, format, None);
+        let wakes = Arc::new(AtomicUsize::new(0));
+        let woken = wakes.clone();
+        let mut thread = RenderThread::spawn(renderer, Box::new(target), move || {
+            woken.fetch_add(1, Ordering::SeqCst);
+        });
+
+        let (input, timer) = (Input::default(), FrameTimer::default());
+        let mut update = |ctx: &mut crate::app::FrameContext| {
+            ctx.gfx.rect().size(vec2(4.0, 4.0));
+        };
+        for _ in 0..3 {
+            let app = AppControl {
+                window: None,
+                requested_size: None,
+                requested_vsync: None,
+                exit_code: None,
+                redraw_in: None,
+                #[cfg(feature = "dialogs")]
+                dialogs: None,
+            };
+            let frame_input = FrameInput {
+                input: &input,
+                timer: &timer,
+                events: Vec::new(),
+                resized: None,
+            };
+            let (size, format) = (thread.size(), thread.format());
+            let renderer = thread.renderer().unwrap();
+            let (queued, _) =
+                state.update(&mut update, renderer, size, format, true, app, frame_input);
+            thread.draw(state, queued.unwrap());
+            // one frame in flight: the renderer is away until it's taken back
+            assert!(thread.renderer().is_none());
+            let (back, drawn) = thread.take_back(true).unwrap();
+            state = back;
+            assert_eq!(drawn.unwrap(), Some((1, 0)));
+        }
+        // nothing was skipped waiting for a frame, so no redraw was requested
+        assert_eq!(wakes.load(Ordering::SeqCst), 0);
+    }
+}
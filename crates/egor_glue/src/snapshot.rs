@@ -0,0 +1,87 @@
+use crate::headless::HeadlessApp;
+use egor_app::input::InputSnapshot;
+use image::ColorType;
+use std::{env, path::PathBuf};
+
+/// Frames to write when an app runs as a snapshot, read from the environment
+/// `cargo xtask snapshot` sets:
+///
+/// - `EGOR_SNAPSHOT_DIR`: folder the PNGs go to, snapshots are off without it
+/// - `EGOR_SNAPSHOT_FRAMES`: frames to run (defaults to 10), numbered from 0 like a window's
+/// - `EGOR_SNAPSHOT_CAPTURE`: comma separated frames to save (defaults to the last)
+#[derive(Debug, PartialEq)]
+pub(crate) struct SnapshotRequest {
+    dir: PathBuf,
+    frames: u64,
+    capture: Vec<u64>,
+}
+
+impl SnapshotRequest {
+    pub fn from_env() -> Option<Self> {
+        let dir = env::var_os("EGOR_SNAPSHOT_DIR")?;
+        let frames = env::var("EGOR_SNAPSHOT_FRAMES").ok();
+        let capture = env::var("EGOR_SNAPSHOT_CAPTURE").ok();
+        Some(Self::parse(
+            dir.into(),
+            frames.as_deref(),
+            capture.as_deref(),
+        ))
+    }
+
+    fn parse(dir: PathBuf, frames: Option<&str>, capture: Option<&str>) -> Self {
+        let frames = frames
+            .and_then(|f| f.trim().parse().ok())
+            .unwrap_or(10u64)
+            .max(1);
+        let mut capture: Vec<u64> = capture
+            .unwrap_or_default()
+            .split(',')
+            .filter_map(|f| f.trim().parse().ok())
+            .filter(|f| *f < frames)
+            .collect();
+        if capture.is_empty() {
+            capture.push(frames - 1);
+        }
+        Self {
+            dir,
+            frames,
+            capture,
+        }
+    }
+
+    /// Step `app` with no input, saving the requested frames as `frame_NNNN.png`. Stops
+    /// early if the app requests an exit
+    pub fn run(&self, app: &mut HeadlessApp) {
+        std::fs::create_dir_all(&self.dir)
+            .unwrap_or_else(|e| panic!("failed to create {}: {e}", self.dir.display()));
+        for _ in 0..self.frames {
+            let output = app.step(&InputSnapshot::default());
+            if let (true, Some(pixels)) = (self.capture.contains(&output.frame), &output.pixels) {
+                let path = self.dir.join(format!("frame_{:04}.png", output.frame));
+                let (w, h) = output.size;
+                image::save_buffer(&path, pixels, w, h, ColorType::Rgba8)
+                    .unwrap_or_else(|e| panic!("failed to write {}: {e}", path.display()));
+                log::info!("snapshot written to {}", path.display());
+            }
+            if output.exit_code.is_some() {
+                break;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn capture_defaults_to_the_last_frame() {
+        let request = SnapshotRequest::parse("out".into(), Some("5"), Some("0, 3,5,x"));
+        assert_eq!((request.frames, request.capture), (5, vec![0, 3]));
+
+        let request = SnapshotRequest::parse("out".into(), None, Some("10,42"));
+        assert_eq!((request.frames, request.capture), (10, vec![9]));
+        let request = SnapshotRequest::parse("out".into(), Some("0"), None);
+        assert_eq!((request.frames, request.capture), (1, vec![0]));
+    }
+}
@@ -0,0 +1,47 @@
+//! Geometry baked once & drawn every frame without rebuilding it, see
+//! [`crate::graphics::Graphics::bake_static`]
+
+use std::sync::Arc;
+
+use egor_render::batch::GeometryBatch;
+
+/// One batch of baked geometry, uploaded when it was baked
+pub(crate) struct StaticBatch {
+    pub texture_id: Option<usize>,
+    pub shader_id: Option<usize>,
+    pub geometry: GeometryBatch,
+}
+
+/// Baked batches by id, shared with the frame's draw entries that reference them. Freed
+/// slots are reused by the next insert
+#[derive(Default)]
+pub(crate) struct StaticStore {
+    store: Vec<Option<Arc<[StaticBatch]>>>,
+}
+
+impl StaticStore {
+    pub fn insert(&mut self, batches: Vec<StaticBatch>) -> usize {
+        let batches = Some(batches.into());
+        match self.store.iter().position(Option::is_none) {
+            Some(id) => {
+                self.store[id] = batches;
+                id
+            }
+            None => {
+                self.store.push(batches);
+                self.store.len() - 1
+            }
+        }
+    }
+
+    pub fn get(&self, id: usize) -> Option<&Arc<[StaticBatch]>> {
+        self.store.get(id)?.as_ref()
+    }
+
+    /// Frees the batches' buffers once no draw entry of this frame references them
+    pub fn remove(&mut self, id: usize) {
+        if let Some(slot) = self.store.get_mut(id) {
+            *slot = None;
+        }
+    }
+}
@@ -1,29 +1,240 @@
-use egor_render::{Device, Queue, RenderPass, TextureFormat};
-use glam::Vec2;
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    vec::Drain,
+};
+
+use egor_render::{Device, Queue, RenderPass, Renderer, TextureFormat};
+use glam::{Affine2, Mat2, Vec2, vec2};
 use glyphon::{
     Attrs, Buffer, Cache, Color as GlyphonColor, Family, FontSystem, Metrics, Resolution, Shaping,
     Style, SwashCache, TextArea, TextAtlas, TextBounds, TextRenderer as GlyphonRenderer, Viewport,
     Weight,
+    cosmic_text::{Fallback, PlatformFallback},
 };
+use unicode_script::Script;
 
-use crate::{color::Color, math::Rect};
+use crate::{alloc_guard, color::Color, math::Rect};
 
 struct TextEntry {
+    key: TextKey,
     buffer: Buffer,
+    glyphs: usize,
     position: Vec2,
+    /// Screen rect glyphs outside of are cut off, see [`TextBuilder::clip`]
+    clip: Option<Rect>,
+}
+
+/// Everything that affects how a piece of text rasterizes
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct TextKey {
+    text: String,
+    size: u32,
+    line_height: u32,
+    color: u32,
+    family: Cow<'static, str>,
+    weight: Weight,
+    style: Style,
+    text_align: TextAlign,
+    direction: TextDirection,
+    wrap_width: Option<u32>,
+    /// How & to what width in pixels the text is cut down, see [`TextBuilder::truncate`]
+    truncate: Option<(TextTruncate, u32)>,
+}
+
+struct ShapedText {
+    buffer: Buffer,
+    glyphs: usize,
+    last_used: u64,
+}
+
+/// Shaped buffers kept across frames, so text drawn again isn't shaped again. The least
+/// recently drawn text is evicted past [`MAX_SHAPED_TEXTS`] entries or [`MAX_SHAPED_GLYPHS`]
+/// glyphs in total
+#[derive(Default)]
+struct ShapedCache {
+    entries: HashMap<TextKey, ShapedText>,
+    glyphs: usize,
+    /// Target size & scale factor the kept text was laid out for
+    layout: Option<((u32, u32), f32)>,
+}
+
+impl ShapedCache {
+    /// Takes the buffer & glyph count of `key` out, to be put back once drawn
+    fn take(&mut self, key: &TextKey) -> Option<(Buffer, usize)> {
+        let shaped = self.entries.remove(key)?;
+        self.glyphs -= shaped.glyphs;
+        Some((shaped.buffer, shaped.glyphs))
+    }
+
+    /// Keeps `buffer` as drawn in `frame`. Returns the buffer it replaces when the same text
+    /// was drawn twice
+    fn insert(
+        &mut self,
+        key: TextKey,
+        buffer: Buffer,
+        glyphs: usize,
+        frame: u64,
+    ) -> Option<Buffer> {
+        self.glyphs += glyphs;
+        let shaped = ShapedText {
+            buffer,
+            glyphs,
+            last_used: frame,
+        };
+        let replaced = self.entries.insert(key, shaped)?;
+        self.glyphs -= replaced.glyphs;
+        Some(replaced.buffer)
+    }
+
+    /// Evicts the least recently drawn text until the cache is within its limits, keeping
+    /// text drawn in `frame`
+    fn evict(&mut self, frame: u64, mut evicted: impl FnMut(TextKey, Buffer)) {
+        let (mut len, mut glyphs) = (self.entries.len(), self.glyphs);
+        while len > MAX_SHAPED_TEXTS || glyphs > MAX_SHAPED_GLYPHS {
+            let oldest = self.entries.values().map(|s| s.last_used).min();
+            let Some(oldest) = oldest.filter(|&oldest| oldest < frame) else {
+                break;
+            };
+            let mut over_limit = |shaped: &ShapedText| {
+                let evict = shaped.last_used == oldest
+                    && (len > MAX_SHAPED_TEXTS || glyphs > MAX_SHAPED_GLYPHS);
+                if evict {
+                    len -= 1;
+                    glyphs -= shaped.glyphs;
+                }
+                evict
+            };
+            for (key, shaped) in self.entries.extract_if(|_, shaped| over_limit(shaped)) {
+                evicted(key, shaped.buffer);
+            }
+        }
+        self.glyphs = glyphs;
+    }
+
+    /// Drops all kept text when it was laid out for another target size or scale factor, so
+    /// it's laid out again when next drawn
+    fn relayout_for(
+        &mut self,
+        size: (u32, u32),
+        scale_factor: f32,
+        evicted: impl FnMut(TextKey, Buffer),
+    ) {
+        let layout = Some((size, scale_factor));
+        if self.layout != layout {
+            self.layout = layout;
+            self.clear(evicted);
+        }
+    }
+
+    fn clear(&mut self, mut evicted: impl FnMut(TextKey, Buffer)) {
+        self.glyphs = 0;
+        for (key, shaped) in self.entries.drain() {
+            evicted(key, shaped.buffer);
+        }
+    }
+}
+
+/// Text rasterized into its own texture so it can be drawn as a transformed quad
+struct CachedText {
+    texture_id: usize,
+    size: Vec2,
+    last_used: u64,
+}
+
+/// Rotated/scaled text queued this frame, resolved into textured quads by [`crate::graphics::Graphics`]
+struct TransformedEntry {
+    key: TextKey,
+    /// Shaped buffer, only present when `key` wasn't cached yet
+    buffer: Option<Buffer>,
+    center: Vec2,
+    size: Vec2,
+    rotation: f32,
+    scale: f32,
+    sorted: Option<SortedText>,
+}
+
+/// Where text drawn in a layer sorted by y is drawn, see
+/// [`crate::graphics::Graphics::on_layer_sorted_y`]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct SortedText {
+    pub layer: usize,
+    /// Middle of the bottom edge of the text's box in screen space, which it's sorted by
+    pub bottom: Vec2,
+}
+
+/// A cached text texture ready to be drawn as a quad in screen space
+pub(crate) struct TransformedText {
+    pub texture_id: usize,
+    pub center: Vec2,
+    pub size: Vec2,
+    pub rotation: f32,
+    pub sorted: Option<SortedText>,
+}
+
+/// Underline or strikethrough of a laid out line, drawn as a solid quad in screen space
+pub(crate) struct TextDecoration {
+    pub center: Vec2,
+    pub size: Vec2,
+    pub rotation: f32,
+    pub color: Color,
+    pub sorted: Option<SortedText>,
+}
+
+/// Padded box behind a piece of text, drawn as a solid quad (or rounded rect) in screen space
+pub(crate) struct TextBackground {
+    pub center: Vec2,
+    pub size: Vec2,
+    pub rotation: f32,
+    pub radius: f32,
+    pub color: Color,
+    pub sorted: Option<SortedText>,
 }
 
 pub struct TextRenderer {
     font_system: FontSystem,
     swash_cache: SwashCache,
+    glyph_cache: Cache,
+    format: TextureFormat,
     atlas: TextAtlas,
     renderer: GlyphonRenderer,
     viewport: Viewport,
     entries: Vec<TextEntry>,
+    /// Buffers drawn in earlier frames, reused as is when the same text is drawn again
+    shaped: ShapedCache,
     buffer_pool: Vec<Buffer>,
+    /// Spare strings for [`Self::pooled_string`]
+    strings: Vec<String>,
+    transformed: Vec<TransformedEntry>,
+    quads: Vec<TransformedText>,
+    decorations: Vec<TextDecoration>,
+    backgrounds: Vec<TextBackground>,
+    texture_cache: HashMap<TextKey, CachedText>,
+    free_texture_ids: Vec<usize>,
+    offscreen: Option<(GlyphonRenderer, Viewport)>,
+    /// Family names of every fallback chain set, leaked once each as cosmic-text's fallback
+    /// lists hold `&'static str`s
+    fallback_names: HashSet<&'static str>,
+    frame: u64,
+    /// Empty texts left out since the last [`Self::take_skipped`]
+    skipped: usize,
+    /// Multiplied into the color of queued text, see [`crate::graphics::Graphics::with_tint`]
+    pub(crate) tint: Color,
+    /// Places queued text, see [`crate::graphics::Graphics::push_transform`]. Reset each frame
+    pub(crate) transform: Affine2,
+    /// Draw all text through cached textures, whose glyph edges blend into a premultiplied
+    /// target without fringes unlike glyphon's direct rendering
+    pub(crate) premultiplied: bool,
 }
 
 const MAX_POOLED_BUFFERS: usize = 64;
+const MAX_POOLED_STRINGS: usize = 256;
+/// Most pieces of shaped text kept between frames
+const MAX_SHAPED_TEXTS: usize = 512;
+/// Most glyphs of shaped text kept between frames, so a few long texts can't hog memory
+const MAX_SHAPED_GLYPHS: usize = 32_768;
+/// Soft cap on cached text textures; least recently used entries are evicted past this
+const MAX_CACHED_TEXTURES: usize = 64;
 
 impl TextRenderer {
     pub(crate) fn new(device: &Device, queue: &Queue, format: TextureFormat) -> Self {
@@ -33,48 +244,99 @@ impl TextRenderer {
             .db_mut()
             .load_font_data(include_bytes!("../inter-v19-latin-regular.ttf").to_vec());
         let swash_cache = SwashCache::new();
-        let cache = Cache::new(device);
-        let viewport = Viewport::new(device, &cache);
-        let mut atlas = TextAtlas::new(device, queue, &cache, format);
+        let glyph_cache = Cache::new(device);
+        let viewport = Viewport::new(device, &glyph_cache);
+        let mut atlas = TextAtlas::new(device, queue, &glyph_cache, format);
         let renderer = GlyphonRenderer::new(&mut atlas, device, Default::default(), None);
 
         Self {
             font_system,
             swash_cache,
+            glyph_cache,
+            format,
             atlas,
             renderer,
             viewport,
             entries: Vec::new(),
+            shaped: ShapedCache::default(),
             buffer_pool: Vec::new(),
+            strings: Vec::new(),
+            transformed: Vec::new(),
+            quads: Vec::new(),
+            decorations: Vec::new(),
+            backgrounds: Vec::new(),
+            texture_cache: HashMap::new(),
+            free_texture_ids: Vec::new(),
+            offscreen: None,
+            fallback_names: HashSet::new(),
+            frame: 0,
+            skipped: 0,
+            tint: Color::WHITE,
+            transform: Affine2::IDENTITY,
+            premultiplied: false,
         }
     }
 
     pub fn load_font_bytes(&mut self, bytes: &[u8]) -> Option<String> {
         self.font_system.db_mut().load_font_data(bytes.to_vec());
+        // cached text may have fallen back to another font before this one existed
+        self.clear_shaped();
         let face = self.font_system.db().faces().last()?;
         let family = face.families.first()?.0.clone();
         Some(family)
     }
 
-    /// Prepare the text renderer for drawing
-    pub(crate) fn prepare(&mut self, device: &Device, queue: &Queue, width: u32, height: u32) {
-        let text_areas: Vec<TextArea> = self
-            .entries
+    /// Tries `families` in order for glyphs missing from the text's font, before the
+    /// platform's fallbacks
+    pub fn set_fallback_chain(&mut self, families: &[impl AsRef<str>]) {
+        let families = families
             .iter()
-            .map(|entry| TextArea {
-                buffer: &entry.buffer,
-                left: entry.position.x,
-                top: entry.position.y,
-                bounds: TextBounds {
+            .map(|family| {
+                let family = family.as_ref();
+                match self.fallback_names.get(family) {
+                    Some(name) => *name,
+                    None => {
+                        let name: &'static str = Box::leak(family.into());
+                        self.fallback_names.insert(name);
+                        name
+                    }
+                }
+            })
+            .collect();
+        let empty = FontSystem::new_with_locale_and_db(String::new(), Default::default());
+        let (locale, db) = std::mem::replace(&mut self.font_system, empty).into_locale_and_db();
+        self.font_system =
+            FontSystem::new_with_locale_and_db_and_fallback(locale, db, FallbackChain(families));
+        self.clear_shaped();
+    }
+
+    /// Prepare the text renderer for drawing into a `width` x `height` target. The viewport
+    /// follows the target's size here, so text is never laid out for a stale resolution after
+    /// a resize or scale factor change
+    pub(crate) fn prepare(&mut self, device: &Device, queue: &Queue, width: u32, height: u32) {
+        // glyphon only writes the resolution when it changed
+        self.viewport.update(queue, Resolution { width, height });
+        let text_areas = self.entries.iter().map(|entry| TextArea {
+            buffer: &entry.buffer,
+            left: entry.position.x,
+            top: entry.position.y,
+            bounds: match entry.clip {
+                Some(clip) => TextBounds {
+                    left: clip.min().x.floor() as i32,
+                    top: clip.min().y.floor() as i32,
+                    right: (clip.max().x.ceil() as i32).min(width as i32),
+                    bottom: (clip.max().y.ceil() as i32).min(height as i32),
+                },
+                None => TextBounds {
                     right: width as i32,
                     bottom: height as i32,
                     ..Default::default()
                 },
-                scale: 1.0,
-                default_color: GlyphonColor::rgb(255, 255, 255),
-                custom_glyphs: &[],
-            })
-            .collect();
+            },
+            scale: 1.0,
+            default_color: GlyphonColor::rgb(255, 255, 255),
+            custom_glyphs: &[],
+        });
         self.renderer
             .prepare(
                 device,
@@ -86,13 +348,221 @@ impl TextRenderer {
                 &mut self.swash_cache,
             )
             .unwrap();
+        self.end_frame();
+    }
+
+    /// Drops the text queued this frame without drawing it, for frames that aren't rendered.
+    /// Its layouts are still kept for the next frame
+    pub(crate) fn discard(&mut self) {
+        for entry in self.transformed.drain(..) {
+            if let Some(buffer) = entry.buffer
+                && self.buffer_pool.len() < MAX_POOLED_BUFFERS
+            {
+                self.buffer_pool.push(buffer);
+            }
+            recycle_string(&mut self.strings, entry.key.text);
+        }
+        self.decorations.clear();
+        self.backgrounds.clear();
+        self.end_frame();
+    }
+
+    /// Keeps this frame's layouts, evicts those undrawn for too long & starts the next frame
+    fn end_frame(&mut self) {
+        // keep this frame's buffers for text drawn again later, pool what gets evicted
+        for TextEntry {
+            key,
+            buffer,
+            glyphs,
+            ..
+        } in self.entries.drain(..)
+        {
+            // the same text drawn twice keeps one buffer
+            if let Some(duplicate) = self.shaped.insert(key, buffer, glyphs, self.frame)
+                && self.buffer_pool.len() < MAX_POOLED_BUFFERS
+            {
+                self.buffer_pool.push(duplicate);
+            }
+        }
+        let (pool, strings) = (&mut self.buffer_pool, &mut self.strings);
+        self.shaped.evict(self.frame, |key, buffer| {
+            recycle(pool, strings, key, buffer)
+        });
+        self.transform = Affine2::IDENTITY;
+        self.frame += 1;
+    }
+
+    /// Lays text out for a `width` x `height` target at `scale_factor` from this frame on.
+    /// Shaped text kept from earlier frames is laid out again after a resize or scale factor
+    /// change, rather than reused as it was
+    pub(crate) fn set_target(&mut self, width: u32, height: u32, scale_factor: f32) {
+        let (pool, strings) = (&mut self.buffer_pool, &mut self.strings);
+        self.shaped
+            .relayout_for((width, height), scale_factor, |key, buffer| {
+                recycle(pool, strings, key, buffer)
+            });
+    }
+
+    /// Drops all shaped text kept between frames, so it's shaped again when next drawn
+    pub(crate) fn clear_shaped(&mut self) {
+        let (pool, strings) = (&mut self.buffer_pool, &mut self.strings);
+        self.shaped
+            .clear(|key, buffer| recycle(pool, strings, key, buffer));
+    }
+
+    /// Rasterizes queued rotated/scaled text into cached textures
+    ///
+    /// Text already in the cache is reused as is, new text is rendered into
+    /// its own offscreen target. Yields one quad per queued text
+    pub(crate) fn resolve_transformed(
+        &mut self,
+        renderer: &mut Renderer,
+    ) -> Drain<'_, TransformedText> {
+        let mut queued = std::mem::take(&mut self.transformed);
+
+        for entry in queued.drain(..) {
+            if let Some(buffer) = entry.buffer {
+                if !self.texture_cache.contains_key(&entry.key) {
+                    alloc_guard::exempt(|| {
+                        self.rasterize(renderer, entry.key.clone(), &buffer, entry.size)
+                    });
+                }
+                if self.buffer_pool.len() < MAX_POOLED_BUFFERS {
+                    self.buffer_pool.push(buffer);
+                }
+            }
+            if let Some(cached) = self.texture_cache.get_mut(&entry.key) {
+                cached.last_used = self.frame;
+                self.quads.push(TransformedText {
+                    texture_id: cached.texture_id,
+                    center: entry.center,
+                    size: entry.size * entry.scale,
+                    rotation: entry.rotation,
+                    sorted: entry.sorted,
+                });
+            }
+            recycle_string(&mut self.strings, entry.key.text);
+        }
+
+        // hand the emptied queue back so its capacity is reused
+        self.transformed = queued;
+        self.quads.drain(..)
+    }
+
+    /// Drains the underlines & strikethroughs queued this frame
+    pub(crate) fn drain_decorations(&mut self) -> Drain<'_, TextDecoration> {
+        self.decorations.drain(..)
+    }
+
+    /// Drains the text backgrounds queued this frame
+    pub(crate) fn drain_backgrounds(&mut self) -> Drain<'_, TextBackground> {
+        self.backgrounds.drain(..)
+    }
+
+    /// Empty texts skipped since the last call, which never reach glyphon
+    pub(crate) fn take_skipped(&mut self) -> usize {
+        std::mem::take(&mut self.skipped)
+    }
+
+    /// A copy of `text` in a string reused from text drawn in earlier frames
+    pub(crate) fn pooled_string(&mut self, text: &str) -> String {
+        let mut string = match self.strings.iter().position(|s| s.capacity() >= text.len()) {
+            Some(i) => self.strings.swap_remove(i),
+            None => String::new(),
+        };
+        string.push_str(text);
+        string
+    }
+
+    /// Renders a shaped buffer into a new cached texture, evicting the least recently used one if full
+    fn rasterize(&mut self, renderer: &mut Renderer, key: TextKey, buffer: &Buffer, size: Vec2) {
+        let (w, h) = (size.x.ceil().max(1.0) as u32, size.y.ceil().max(1.0) as u32);
 
-        // Return buffers to the pool for reuse next frame
-        for entry in self.entries.drain(..) {
-            if self.buffer_pool.len() < MAX_POOLED_BUFFERS {
-                self.buffer_pool.push(entry.buffer);
+        if self.texture_cache.len() >= MAX_CACHED_TEXTURES {
+            // never evict text that is already queued for drawing this frame
+            let frame = self.frame;
+            let lru = self
+                .texture_cache
+                .iter()
+                .filter(|(_, cached)| cached.last_used < frame)
+                .min_by_key(|(_, cached)| cached.last_used)
+                .map(|(key, _)| key.clone());
+            if let Some(cached) = lru.and_then(|key| self.texture_cache.remove(&key)) {
+                self.free_texture_ids.push(cached.texture_id);
             }
         }
+
+        let mut target = renderer.create_offscreen_target(w, h, self.format);
+        let texture_id = match self.free_texture_ids.pop() {
+            Some(id) => {
+                renderer.update_offscreen_texture(id, &mut target);
+                id
+            }
+            None => renderer.add_offscreen_texture(&mut target),
+        };
+
+        let (device, queue) = (renderer.device(), renderer.queue());
+        let (text_renderer, viewport) = self.offscreen.get_or_insert_with(|| {
+            (
+                GlyphonRenderer::new(&mut self.atlas, device, Default::default(), None),
+                Viewport::new(device, &self.glyph_cache),
+            )
+        });
+        viewport.update(
+            queue,
+            Resolution {
+                width: w,
+                height: h,
+            },
+        );
+        text_renderer
+            .prepare(
+                device,
+                queue,
+                &mut self.font_system,
+                &mut self.atlas,
+                viewport,
+                [TextArea {
+                    buffer,
+                    left: 0.0,
+                    top: 0.0,
+                    bounds: TextBounds {
+                        right: w as i32,
+                        bottom: h as i32,
+                        ..Default::default()
+                    },
+                    scale: 1.0,
+                    default_color: GlyphonColor::rgb(255, 255, 255),
+                    custom_glyphs: &[],
+                }],
+                &mut self.swash_cache,
+            )
+            .unwrap();
+
+        // clear to the text color at zero alpha so blended glyph edges don't darken
+        let [r, g, b, _] = GlyphonColor(key.color).as_rgba().map(|c| c as f64 / 255.0);
+        let mut encoder = device.create_command_encoder(&Default::default());
+        {
+            let mut r_pass = renderer.begin_render_pass_with_clear(
+                &mut encoder,
+                target.render_view(),
+                [r, g, b, 0.0],
+            );
+            text_renderer
+                .render(&self.atlas, viewport, &mut r_pass)
+                .unwrap();
+        }
+        target.copy_to_sample(&mut encoder);
+        queue.submit(Some(encoder.finish()));
+
+        self.texture_cache.insert(
+            key,
+            CachedText {
+                texture_id,
+                size,
+                last_used: self.frame,
+            },
+        );
     }
 
     pub(crate) fn render<'a>(&'a self, pass: &mut RenderPass<'a>) {
@@ -101,19 +571,87 @@ impl TextRenderer {
             .unwrap();
     }
 
-    pub(crate) fn resize(&mut self, width: u32, height: u32, queue: &Queue) {
-        self.viewport.update(queue, Resolution { width, height });
+    /// Lays out one line of `text` like [`TextBuilder`] draws it at `size` in the default
+    /// font, filling `stops` with where a caret can go in it, see [`caret_stops`]
+    pub(crate) fn caret_stops(&mut self, text: &str, size: f32, stops: &mut Vec<(usize, f32)>) {
+        let mut buffer = self.take_buffer(Metrics::new(size, size * 1.2), None);
+        let attrs = Attrs::new().family(Family::Name("Inter"));
+        set_text(
+            &mut buffer,
+            &mut self.font_system,
+            text,
+            &attrs,
+            TextDirection::Auto,
+        );
+        buffer.shape_until_scroll(&mut self.font_system, false);
+        caret_stops(&buffer, stops);
+        if self.buffer_pool.len() < MAX_POOLED_BUFFERS {
+            self.buffer_pool.push(buffer);
+        }
     }
 
-    /// Takes a buffer from the pool, or creates a new one with the given metrics
-    fn take_buffer(&mut self, metrics: Metrics) -> Buffer {
-        if let Some(mut buf) = self.buffer_pool.pop() {
-            buf.set_metrics(&mut self.font_system, metrics);
-            buf
-        } else {
-            Buffer::new(&mut self.font_system, metrics)
+    /// Returns the measured size of cached text & marks it as used this frame
+    fn touch_cached(&mut self, key: &TextKey) -> Option<Vec2> {
+        let cached = self.texture_cache.get_mut(key)?;
+        cached.last_used = self.frame;
+        Some(cached.size)
+    }
+
+    /// Takes a buffer from the pool, or creates a new one, with the given metrics & width
+    fn take_buffer(&mut self, metrics: Metrics, width: Option<f32>) -> Buffer {
+        let mut buf = match self.buffer_pool.pop() {
+            Some(buf) => buf,
+            None => Buffer::new(&mut self.font_system, metrics),
+        };
+        buf.set_metrics_and_size(&mut self.font_system, metrics, width, None);
+        buf
+    }
+}
+
+/// Fills `stops` with every place a caret can go in the first line of a shaped `buffer`: the
+/// byte offset & x of each glyph cluster's edges, by offset. A cluster (a letter with its
+/// accents, a ligature) is never split. Clusters of right to left text start at their right
+/// edge. Where bidirectional runs meet, an offset keeps the first edge it was seen at
+pub(crate) fn caret_stops(buffer: &Buffer, stops: &mut Vec<(usize, f32)>) {
+    stops.clear();
+    if let Some(run) = buffer.layout_runs().next() {
+        let mut glyphs = run.glyphs.iter().peekable();
+        while let Some(first) = glyphs.next() {
+            // the glyphs of one cluster share its byte range
+            let (mut left, mut right) = (first.x, first.x + first.w);
+            while let Some(glyph) = glyphs.next_if(|g| (g.start, g.end) == (first.start, first.end))
+            {
+                left = left.min(glyph.x);
+                right = right.max(glyph.x + glyph.w);
+            }
+            let (leading, trailing) = match first.level.is_rtl() {
+                true => (right, left),
+                false => (left, right),
+            };
+            stops.push((first.start, leading));
+            stops.push((first.end, trailing));
         }
     }
+    stops.sort_by_key(|&(offset, _)| offset);
+    stops.dedup_by_key(|&mut (offset, _)| offset);
+    if stops.first().is_none_or(|&(offset, _)| offset > 0) {
+        stops.insert(0, (0, 0.0));
+    }
+}
+
+/// Returns a buffer & the strings of its key to their pools
+fn recycle(pool: &mut Vec<Buffer>, strings: &mut Vec<String>, key: TextKey, buffer: Buffer) {
+    if pool.len() < MAX_POOLED_BUFFERS {
+        pool.push(buffer);
+    }
+    recycle_string(strings, key.text);
+}
+
+fn recycle_string(strings: &mut Vec<String>, mut string: String) {
+    if strings.len() < MAX_POOLED_STRINGS && string.capacity() > 0 {
+        string.clear();
+        strings.push(string);
+    }
 }
 
 /// Alignment of text (for use with and) relative to a rectangle
@@ -129,6 +667,71 @@ pub enum Align {
     BottomRight,
 }
 
+/// Alignment of each line within a block of text, see [`TextBuilder::text_align`].
+/// Where the block itself goes is up to [`TextBuilder::at`] or [`TextBuilder::in_rect`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum TextAlign {
+    #[default]
+    Left,
+    Center,
+    Right,
+    /// Stretch the spaces of every line but the last of each paragraph to the widest line
+    Justify,
+}
+
+/// Base direction of each paragraph of text, see [`TextBuilder::direction`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum TextDirection {
+    /// Right to left when a paragraph's first letter is from a right to left script
+    #[default]
+    Auto,
+    Ltr,
+    Rtl,
+}
+
+/// Where text too wide for its width loses clusters to an ellipsis, see
+/// [`TextBuilder::truncate`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TextTruncate {
+    /// Keep the start, e.g. `Leaderboard na…`
+    End,
+    /// Keep both ends, e.g. `assets/…/player.png`
+    Middle,
+}
+
+/// Platform font fallback with the families of [`TextRenderer::set_fallback_chain`] first
+struct FallbackChain(Vec<&'static str>);
+
+impl Fallback for FallbackChain {
+    fn common_fallback(&self) -> &[&'static str] {
+        PlatformFallback.common_fallback()
+    }
+
+    fn forbidden_fallback(&self) -> &[&'static str] {
+        PlatformFallback.forbidden_fallback()
+    }
+
+    // script fallbacks are tried before the common ones, so the chain goes here. Fonts
+    // outside of it are still searched after the common fallbacks
+    fn script_fallback(&self, script: Script, locale: &str) -> &[&'static str] {
+        if self.0.is_empty() {
+            return PlatformFallback.script_fallback(script, locale);
+        }
+        &self.0
+    }
+}
+
+impl From<TextAlign> for glyphon::cosmic_text::Align {
+    fn from(align: TextAlign) -> Self {
+        match align {
+            TextAlign::Left => Self::Left,
+            TextAlign::Center => Self::Center,
+            TextAlign::Right => Self::Right,
+            TextAlign::Justify => Self::Justified,
+        }
+    }
+}
+
 /// A builder for queuing a single line of text to the [`TextRenderer`].
 /// The text is uploaded and rendered on the next frame
 ///
@@ -150,10 +753,30 @@ pub struct TextBuilder<'a> {
     size: f32,
     color: Color,
     /// Font family name used for matching
-    family: String,
+    family: Cow<'static, str>,
     weight: Weight,
     style: Style,
     align: Align,
+    text_align: TextAlign,
+    direction: TextDirection,
+    /// Width in pixels lines wrap at
+    wrap_width: Option<f32>,
+    /// Rotation in radians around the text center
+    rotation: f32,
+    scale: f32,
+    /// Always use the cached texture path, even without a transform
+    cached: bool,
+    /// Layer sorted by y the text was drawn in, see [`Self::sorted_in`]
+    sorted_layer: Option<usize>,
+    underline: bool,
+    strikethrough: bool,
+    background: Option<Color>,
+    padding: f32,
+    background_radius: f32,
+    clip: Option<Rect>,
+    truncate: Option<TextTruncate>,
+    /// Width in pixels truncated text must fit, the [`Self::in_rect`] width by default
+    max_width: Option<f32>,
 }
 
 impl<'a> TextBuilder<'a> {
@@ -169,10 +792,25 @@ impl<'a> TextBuilder<'a> {
             size: 16.0,
             line_height: None,
             color: Color::BLACK,
-            family: "Inter".into(),
+            family: Cow::Borrowed("Inter"),
             weight: Weight::NORMAL,
             style: Style::Normal,
             align: Align::TopLeft,
+            text_align: TextAlign::Left,
+            direction: TextDirection::Auto,
+            wrap_width: None,
+            rotation: 0.0,
+            scale: 1.0,
+            cached: false,
+            sorted_layer: None,
+            underline: false,
+            strikethrough: false,
+            background: None,
+            padding: 0.0,
+            background_radius: 0.0,
+            clip: None,
+            truncate: None,
+            max_width: None,
         }
     }
 
@@ -181,7 +819,7 @@ impl<'a> TextBuilder<'a> {
     /// The family must match a font that has been loaded into the renderer.
     /// If the family cannot be found, a fallback font will be used (Inter)
     pub fn font(mut self, family: String) -> Self {
-        self.family = family;
+        self.family = Cow::Owned(family);
         self
     }
 
@@ -203,6 +841,42 @@ impl<'a> TextBuilder<'a> {
         self
     }
 
+    /// Align every line within the text block (defaults to [`TextAlign::Left`]).
+    /// Composes with [`Self::in_rect`], which places the block as a whole
+    pub fn text_align(mut self, align: TextAlign) -> Self {
+        self.text_align = align;
+        self
+    }
+
+    /// Set the base direction of every paragraph (defaults to [`TextDirection::Auto`]),
+    /// e.g. [`TextDirection::Rtl`] for Arabic chat lines that start with a Latin name
+    pub fn direction(mut self, direction: TextDirection) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    /// Wrap lines longer than `width` pixels at word boundaries
+    pub fn wrap(mut self, width: f32) -> Self {
+        self.wrap_width = Some(width.max(0.0));
+        self
+    }
+
+    /// Cut text wider than [`Self::max_width`] (or the [`Self::in_rect`] width) down to the
+    /// clusters that fit with an ellipsis, e.g. for names & file paths in fixed-width
+    /// labels. Only the first line is kept & it isn't wrapped. Clusters come from shaping, so
+    /// emoji & accented letters are never split
+    pub fn truncate(mut self, mode: TextTruncate) -> Self {
+        self.truncate = Some(mode);
+        self
+    }
+
+    /// Width in pixels [`Self::truncate`] cuts text down to, instead of the
+    /// [`Self::in_rect`] width
+    pub fn max_width(mut self, px: f32) -> Self {
+        self.max_width = Some(px.max(0.0));
+        self
+    }
+
     /// Set the font size in points
     pub fn size(mut self, size: f32) -> Self {
         self.size = size;
@@ -242,58 +916,784 @@ impl<'a> TextBuilder<'a> {
         self.weight = Weight(weight);
         self
     }
-}
 
-impl Drop for TextBuilder<'_> {
-    fn drop(&mut self) {
-        let line_height = self.line_height.unwrap_or(self.size * 1.2);
+    /// Draw a line under each line of text, in the text color
+    pub fn underline(mut self) -> Self {
+        self.underline = true;
+        self
+    }
+
+    /// Draw a line through the middle of each line of text, in the text color
+    pub fn strikethrough(mut self) -> Self {
+        self.strikethrough = true;
+        self
+    }
+
+    /// Draw a box of `color` behind the text, sized to its bounds plus [`Self::padding`].
+    /// The box always renders under the text, whatever else was drawn this frame
+    pub fn background(mut self, color: Color) -> Self {
+        self.background = Some(color);
+        self
+    }
+
+    /// Space in pixels between the text & the edges of its [`Self::background`].
+    /// Doesn't move the text, the box grows outwards
+    pub fn padding(mut self, px: f32) -> Self {
+        self.padding = px.max(0.0);
+        self
+    }
+
+    /// Round the corners of the [`Self::background`] by `radius` pixels
+    pub fn background_radius(mut self, radius: f32) -> Self {
+        self.background_radius = radius.max(0.0);
+        self
+    }
+
+    /// Cut off glyphs outside `rect`, in screen pixels, e.g. text scrolled inside a field.
+    /// Rotated & scaled text isn't clipped
+    pub fn clip(mut self, rect: Rect) -> Self {
+        self.clip = Some(rect);
+        self
+    }
+
+    /// Rotate the text (in radians, clockwise on screen) around its center
+    ///
+    /// Transformed text is rasterized once into a cached texture (keyed by
+    /// content, font, size & color) and drawn as a textured quad, so it costs
+    /// an offscreen render the first time a string is seen & a texture bind
+    /// per draw afterwards. Frequently changing strings (timers, counters)
+    /// churn the cache; prefer unrotated text for those
+    pub fn rotate(mut self, angle: f32) -> Self {
+        self.rotation = angle;
+        self
+    }
+
+    /// Same as [`Self::rotate`] in degrees
+    pub fn rotate_deg(self, degrees: f32) -> Self {
+        self.rotate(degrees.to_radians())
+    }
+
+    /// Scale the text around its center
+    ///
+    /// Uses the same cached texture path as [`Self::rotate`]; the text is
+    /// rasterized at [`Self::size`] & stretched, so large scales look soft.
+    /// Prefer a bigger font size for crisp static scaling
+    pub fn scale(mut self, scale: f32) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    /// Draw through the cached texture path so the text can be drawn as a quad in any batch
+    pub(crate) fn cached(mut self) -> Self {
+        self.cached = true;
+        self
+    }
+
+    /// Draw as a quad in the layer at `layer`, sorted by y with its shapes
+    pub(crate) fn sorted_in(mut self, layer: usize) -> Self {
+        self.sorted_layer = Some(layer);
+        self.cached()
+    }
+
+    /// Top-left position of a block of text with the given size, honoring [`Self::in_rect`]
+    fn aligned_position(&self, text_w: f32, text_h: f32) -> Vec2 {
+        let Some(rect) = self.rect else {
+            return self.position;
+        };
+
+        let x = match self.align {
+            Align::TopLeft | Align::MiddleLeft | Align::BottomLeft => rect.position.x,
+            Align::TopCenter | Align::MiddleCenter | Align::BottomCenter => {
+                rect.position.x + (rect.size.x - text_w) * 0.5
+            }
+            Align::TopRight | Align::MiddleRight | Align::BottomRight => {
+                rect.position.x + rect.size.x - text_w
+            }
+        };
+        let y = match self.align {
+            Align::TopLeft | Align::TopCenter | Align::TopRight => rect.position.y,
+            Align::MiddleLeft | Align::MiddleCenter | Align::MiddleRight => {
+                rect.position.y + (rect.size.y - text_h) * 0.5
+            }
+            Align::BottomLeft | Align::BottomCenter | Align::BottomRight => {
+                rect.position.y + rect.size.y - text_h
+            }
+        };
+
+        Vec2::new(x, y)
+    }
+
+    /// Truncation mode & the width it cuts to, if the text is truncated at all
+    fn truncation(&self) -> Option<(TextTruncate, f32)> {
+        let width = self.max_width.or(self.rect.map(|rect| rect.size.x))?;
+        Some((self.truncate?, width))
+    }
+
+    /// Moves the text & family out of the builder into the key of its current style
+    fn key(&mut self, line_height: f32) -> TextKey {
+        TextKey {
+            text: std::mem::take(&mut self.text),
+            size: self.size.to_bits(),
+            line_height: line_height.to_bits(),
+            color: GlyphonColor::from(self.color).0,
+            family: std::mem::take(&mut self.family),
+            weight: self.weight,
+            style: self.style,
+            text_align: self.text_align,
+            direction: self.direction,
+            wrap_width: self.wrap_width.map(f32::to_bits),
+            truncate: self
+                .truncation()
+                .map(|(mode, width)| (mode, width.to_bits())),
+        }
+    }
+
+    /// Shapes the text of `key` into a pooled buffer, wrapped & with its lines aligned
+    fn shape(&mut self, key: &TextKey, line_height: f32) -> Buffer {
+        // truncated text stays on one line
+        let truncation = self.truncation();
+        let wrap_width = self.wrap_width.filter(|_| truncation.is_none());
         let mut buffer = self
             .renderer
-            .take_buffer(Metrics::new(self.size, line_height));
-        buffer.set_text(
-            &mut self.renderer.font_system,
-            &self.text,
-            &Attrs::new()
-                .family(Family::Name(&self.family))
-                .color(self.color.into())
-                .weight(self.weight)
-                .style(self.style),
-            Shaping::Basic,
-        );
+            .take_buffer(Metrics::new(self.size, line_height), wrap_width);
+        let font_system = &mut self.renderer.font_system;
+        let attrs = Attrs::new()
+            .family(Family::Name(&key.family))
+            .color(self.color.into())
+            .weight(self.weight)
+            .style(self.style);
+        set_text(&mut buffer, font_system, &key.text, &attrs, self.direction);
+        if let Some((mode, width)) = truncation {
+            truncate(
+                &mut buffer,
+                font_system,
+                &key.text,
+                &attrs,
+                self.direction,
+                mode,
+                width,
+            );
+        }
+        align_lines(&mut buffer, font_system, self.text_align);
+        buffer.shape_until_scroll(font_system, false);
+        buffer
+    }
 
-        // compute final position, applying alignment within rect if set
-        let position = if let Some(rect) = self.rect {
-            buffer.shape_until_scroll(&mut self.renderer.font_system, false);
-            let text_w = buffer
-                .layout_runs()
-                .map(|r| r.line_w)
-                .fold(0.0_f32, f32::max);
-            let text_h = buffer.layout_runs().count() as f32 * line_height;
-
-            let x = match self.align {
-                Align::TopLeft | Align::MiddleLeft | Align::BottomLeft => rect.position.x,
-                Align::TopCenter | Align::MiddleCenter | Align::BottomCenter => {
-                    rect.position.x + (rect.size.x - text_w) * 0.5
-                }
-                Align::TopRight | Align::MiddleRight | Align::BottomRight => {
-                    rect.position.x + rect.size.x - text_w
-                }
+    /// Measures shaped text as (widest line, total height)
+    fn measure(&self, buffer: &Buffer, line_height: f32) -> (f32, f32) {
+        let text_w = buffer
+            .layout_runs()
+            .map(|r| r.line_w)
+            .fold(0.0_f32, f32::max);
+        let text_h = buffer.layout_runs().count() as f32 * line_height;
+        (text_w, text_h)
+    }
+
+    /// Queues underlines & strikethroughs for every laid out line of a measured `buffer`
+    /// drawn at `top_left` with the given size, following the text's rotation & scale
+    fn decorate(
+        &mut self,
+        buffer: &Buffer,
+        top_left: Vec2,
+        text_size: Vec2,
+        sorted: Option<SortedText>,
+    ) {
+        // fonts don't expose their decoration metrics through cosmic-text, so
+        // approximate them from the font size
+        let underline = self.underline.then_some(self.size * 0.12);
+        let strikethrough = self.strikethrough.then_some(self.size * -0.28);
+        let thickness = (self.size / 14.0).max(1.0);
+
+        let rot = Mat2::from_angle(self.rotation);
+        let text_center = top_left + text_size * 0.5;
+        for run in buffer.layout_runs() {
+            let Some(x0) = run.glyphs.iter().map(|g| g.x).reduce(f32::min) else {
+                continue;
             };
-            let y = match self.align {
-                Align::TopLeft | Align::TopCenter | Align::TopRight => rect.position.y,
-                Align::MiddleLeft | Align::MiddleCenter | Align::MiddleRight => {
-                    rect.position.y + (rect.size.y - text_h) * 0.5
-                }
-                Align::BottomLeft | Align::BottomCenter | Align::BottomRight => {
-                    rect.position.y + rect.size.y - text_h
+            let x1 = run.glyphs.iter().map(|g| g.x + g.w).fold(x0, f32::max);
+            for offset in underline.into_iter().chain(strikethrough) {
+                let local = vec2((x0 + x1) * 0.5, run.line_y + offset) - text_size * 0.5;
+                self.renderer.decorations.push(TextDecoration {
+                    center: text_center + rot * (local * self.scale),
+                    size: vec2(x1 - x0, thickness) * self.scale,
+                    rotation: self.rotation,
+                    color: self.color,
+                    sorted,
+                });
+            }
+        }
+    }
+
+    /// Queues the [`Self::background`] box for text drawn at `top_left` with the given size,
+    /// following the text's rotation & scale
+    fn queue_background(&mut self, top_left: Vec2, text_size: Vec2, sorted: Option<SortedText>) {
+        let Some(color) = self.background else {
+            return;
+        };
+        self.renderer.backgrounds.push(TextBackground {
+            center: top_left + text_size * 0.5,
+            size: (text_size + self.padding * 2.0) * self.scale,
+            rotation: self.rotation,
+            radius: self.background_radius * self.scale,
+            color: color.multiply(self.renderer.tint),
+            sorted,
+        });
+    }
+
+    /// Queues the text to be drawn through the cached texture path
+    fn queue_transformed(&mut self, key: TextKey, line_height: f32) {
+        let (buffer, size) = match self.renderer.touch_cached(&key) {
+            Some(size) => (None, size),
+            None => {
+                let buffer = alloc_guard::exempt(|| self.shape(&key, line_height));
+                let (w, h) = self.measure(&buffer, line_height);
+                // whole pixels so the cached texture maps 1:1 onto the quad
+                (Some(buffer), Vec2::new(w, h).ceil())
+            }
+        };
+        let center = self.aligned_position(size.x, size.y) + size * 0.5;
+        let center = self.renderer.transform.transform_point2(center);
+        let sorted = self.sorted_layer.map(|layer| SortedText {
+            layer,
+            bottom: center + Mat2::from_angle(self.rotation) * vec2(0.0, size.y * self.scale * 0.5),
+        });
+        self.queue_background(center - size * 0.5, size, sorted);
+
+        if self.underline || self.strikethrough {
+            let top_left = center - size * 0.5;
+            match &buffer {
+                Some(buffer) => self.decorate(buffer, top_left, size, sorted),
+                None => {
+                    // cached text skips shaping, but decorations need the line layout
+                    let shaped = alloc_guard::exempt(|| self.shape(&key, line_height));
+                    self.decorate(&shaped, top_left, size, sorted);
+                    if self.renderer.buffer_pool.len() < MAX_POOLED_BUFFERS {
+                        self.renderer.buffer_pool.push(shaped);
+                    }
                 }
+            }
+        }
+
+        self.renderer.transformed.push(TransformedEntry {
+            key,
+            buffer,
+            center,
+            size,
+            rotation: self.rotation,
+            scale: self.scale,
+            sorted,
+        });
+    }
+}
+
+/// Sets the text of a buffer. Plain ASCII takes cosmic-text's cheap shaping, anything else
+/// gets complex scripts & font fallback
+fn set_text(
+    buffer: &mut Buffer,
+    font_system: &mut FontSystem,
+    text: &str,
+    attrs: &Attrs,
+    direction: TextDirection,
+) {
+    // a paragraph's direction follows its first strong character, so lead every paragraph
+    // with an invisible left or right to left mark
+    let mark = match direction {
+        TextDirection::Auto => {
+            let shaping = if text.is_ascii() {
+                Shaping::Basic
+            } else {
+                Shaping::Advanced
             };
+            buffer.set_text(font_system, text, attrs, shaping);
+            return;
+        }
+        TextDirection::Ltr => "\u{200E}",
+        TextDirection::Rtl => "\u{200F}",
+    };
+    let marked = format!("{mark}{}", text.replace('\n', &format!("\n{mark}")));
+    buffer.set_text(font_system, &marked, attrs, Shaping::Advanced);
+}
+
+/// Cuts the first line of `text`, already set in `buffer`, down to as many shaped clusters
+/// as fit `width` with an ellipsis, & sets that instead. Returns the text set, or `None` if
+/// the line fits as is. When not even the ellipsis fits, it's all that's left
+fn truncate(
+    buffer: &mut Buffer,
+    font_system: &mut FontSystem,
+    text: &str,
+    attrs: &Attrs,
+    direction: TextDirection,
+    mode: TextTruncate,
+    width: f32,
+) -> Option<String> {
+    let line_width = |buffer: &mut Buffer, font_system: &mut FontSystem| {
+        buffer.shape_until_scroll(font_system, false);
+        buffer.layout_runs().next().map_or(0.0, |run| run.line_w)
+    };
+    let line = text.lines().next().unwrap_or_default();
+    if line.len() == text.len() && line_width(buffer, font_system) <= width {
+        return None;
+    }
 
-            Vec2::new(x, y)
+    // cluster edges within the first line, without the direction mark set_text leads with
+    let mark = match direction {
+        TextDirection::Auto => 0,
+        TextDirection::Ltr | TextDirection::Rtl => 3,
+    };
+    let mut stops = Vec::new();
+    caret_stops(buffer, &mut stops);
+    let mut edges: Vec<_> = stops
+        .iter()
+        .filter_map(|&(offset, _)| offset.checked_sub(mark))
+        .filter(|&offset| offset <= line.len() && line.is_char_boundary(offset))
+        .collect();
+    edges.sort_unstable();
+    edges.dedup();
+    if edges.first() != Some(&0) {
+        edges.insert(0, 0);
+    }
+    if edges.last() != Some(&line.len()) {
+        edges.push(line.len());
+    }
+
+    // the text keeping `kept` clusters around the ellipsis
+    let clusters = edges.len() - 1;
+    let cut = |kept: usize| match mode {
+        TextTruncate::End => format!("{}…", &line[..edges[kept]]),
+        TextTruncate::Middle => {
+            let (head, tail) = (kept.div_ceil(2), kept / 2);
+            let tail = &line[edges[clusters - tail]..];
+            format!("{}…{tail}", &line[..edges[head]])
+        }
+    };
+    let mut fits = |kept: usize| {
+        set_text(buffer, font_system, &cut(kept), attrs, direction);
+        line_width(buffer, font_system) <= width
+    };
+
+    // a first line that fits keeps all its clusters, the ellipsis standing for the rest
+    let most = match line.len() < text.len() {
+        true => clusters,
+        false => clusters.saturating_sub(1),
+    };
+    let (mut lo, mut hi) = (0, most);
+    while lo < hi {
+        let mid = (lo + hi).div_ceil(2);
+        match fits(mid) {
+            true => lo = mid,
+            false => hi = mid - 1,
+        }
+    }
+    let truncated = cut(lo);
+    set_text(buffer, font_system, &truncated, attrs, direction);
+    Some(truncated)
+}
+
+/// Aligns every line of a buffer that has its text set
+fn align_lines(buffer: &mut Buffer, font_system: &mut FontSystem, align: TextAlign) {
+    if align == TextAlign::Left {
+        return;
+    }
+    for line in &mut buffer.lines {
+        line.set_align(Some(align.into()));
+    }
+    // align within the widest line rather than the wrap width, so the measured block
+    // hugs the text. No line gets longer, so the wrapping stays the same
+    buffer.shape_until_scroll(font_system, false);
+    let widest = buffer.layout_runs().map(|r| r.line_w).fold(0.0, f32::max);
+    buffer.set_size(font_system, Some(widest), None);
+}
+
+impl Drop for TextBuilder<'_> {
+    fn drop(&mut self) {
+        let _guard = alloc_guard::scope();
+        if self.text.is_empty() && self.background.is_none() {
+            self.renderer.skipped += 1;
+            recycle_string(&mut self.renderer.strings, std::mem::take(&mut self.text));
+            return;
+        }
+        self.color = self.color.multiply(self.renderer.tint);
+        let line_height = self.line_height.unwrap_or(self.size * 1.2);
+        let key = self.key(line_height);
+        let transform = self.renderer.transform;
+        // text keeps its aspect, so only the transform's horizontal scale applies
+        let transformed = transform.matrix2 != Mat2::IDENTITY;
+        if transformed {
+            let (scale, angle, _) = transform.to_scale_angle_translation();
+            self.rotation += angle;
+            self.scale *= scale.x;
+        }
+        if transformed
+            || self.cached
+            || self.renderer.premultiplied
+            || self.rotation != 0.0
+            || self.scale != 1.0
+        {
+            self.queue_transformed(key, line_height);
+            return;
+        }
+        // only moved by the transform
+        self.position += transform.translation;
+        if let Some(rect) = self.rect.as_mut() {
+            rect.position += transform.translation;
+        }
+
+        let (buffer, glyphs) = match self.renderer.shaped.take(&key) {
+            Some(shaped) => shaped,
+            None => alloc_guard::exempt(|| {
+                let buffer = self.shape(&key, line_height);
+                let glyphs = buffer.layout_runs().map(|run| run.glyphs.len()).sum();
+                (buffer, glyphs)
+            }),
+        };
+
+        // compute final position, applying alignment within rect if set
+        let decorated = self.underline || self.strikethrough;
+        let position = if self.rect.is_some() || decorated || self.background.is_some() {
+            let (text_w, text_h) = self.measure(&buffer, line_height);
+            let position = self.aligned_position(text_w, text_h);
+            self.queue_background(position, vec2(text_w, text_h), None);
+            if decorated {
+                self.decorate(&buffer, position, vec2(text_w, text_h), None);
+            }
+            position
         } else {
             self.position
         };
 
-        self.renderer.entries.push(TextEntry { buffer, position });
+        self.renderer.entries.push(TextEntry {
+            key,
+            buffer,
+            glyphs,
+            position,
+            clip: self.clip,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glyphon::fontdb;
+
+    const PARAGRAPH: &str = "The quick brown fox jumps over the lazy dog while egor draws it";
+
+    /// Lays out the paragraph wrapped at 200px & returns (left edge, width) of each line
+    fn aligned_lines(align: TextAlign) -> Vec<(f32, f32)> {
+        let mut db = fontdb::Database::new();
+        db.load_font_data(include_bytes!("../inter-v19-latin-regular.ttf").to_vec());
+        let mut font_system = FontSystem::new_with_locale_and_db("en-US".into(), db);
+
+        let mut buffer = Buffer::new(&mut font_system, Metrics::new(16.0, 20.0));
+        buffer.set_size(&mut font_system, Some(200.0), None);
+        buffer.set_text(&mut font_system, PARAGRAPH, &Attrs::new(), Shaping::Basic);
+        align_lines(&mut buffer, &mut font_system, align);
+        buffer.shape_until_scroll(&mut font_system, false);
+        buffer
+            .layout_runs()
+            .map(|run| (run.glyphs[0].x, run.line_w))
+            .collect()
+    }
+
+    fn inter() -> FontSystem {
+        let mut db = fontdb::Database::new();
+        db.load_font_data(include_bytes!("../inter-v19-latin-regular.ttf").to_vec());
+        FontSystem::new_with_locale_and_db("en-US".into(), db)
+    }
+
+    /// Shapes `text` on one line & returns the byte offset of each glyph, left to right
+    fn visual_order(text: &str, direction: TextDirection) -> Vec<usize> {
+        let mut font_system = inter();
+        let mut buffer = Buffer::new(&mut font_system, Metrics::new(16.0, 20.0));
+        set_text(
+            &mut buffer,
+            &mut font_system,
+            text,
+            &Attrs::new(),
+            direction,
+        );
+        buffer.shape_until_scroll(&mut font_system, false);
+        let run = buffer.layout_runs().next().unwrap();
+        let mut glyphs: Vec<_> = run.glyphs.iter().filter(|g| g.w > 0.0).collect();
+        glyphs.sort_by(|a, b| a.x.total_cmp(&b.x));
+        // without the direction mark, so offsets index into `text`
+        let mark = if direction == TextDirection::Auto {
+            0
+        } else {
+            3
+        };
+        glyphs.iter().map(|g| g.start - mark).collect()
+    }
+
+    #[test]
+    fn right_to_left_text_is_laid_out_in_visual_order() {
+        // Hebrew on its own reads right to left, whatever glyphs the font has for it
+        let (alef, bet, gimel) = (0, 2, 4);
+        assert_eq!(visual_order("אבג", TextDirection::Auto), [gimel, bet, alef]);
+
+        // a Latin word first makes the paragraph left to right unless told otherwise
+        let text = "abc אבג";
+        let (a, b, c, space) = (0, 1, 2, 3);
+        let (alef, bet, gimel) = (4, 6, 8);
+        let ltr = [a, b, c, space, gimel, bet, alef];
+        let rtl = [gimel, bet, alef, space, a, b, c];
+        assert_eq!(visual_order(text, TextDirection::Auto), ltr);
+        assert_eq!(visual_order(text, TextDirection::Ltr), ltr);
+        assert_eq!(visual_order(text, TextDirection::Rtl), rtl);
+    }
+
+    #[test]
+    fn caret_stops_cover_every_cluster_edge_once() {
+        let mut font_system = inter();
+        let mut buffer = Buffer::new(&mut font_system, Metrics::new(16.0, 20.0));
+        let text = "ab é";
+        set_text(
+            &mut buffer,
+            &mut font_system,
+            text,
+            &Attrs::new(),
+            TextDirection::Auto,
+        );
+        buffer.shape_until_scroll(&mut font_system, false);
+        let mut stops = Vec::new();
+        caret_stops(&buffer, &mut stops);
+
+        let offsets: Vec<_> = stops.iter().map(|&(offset, _)| offset).collect();
+        assert_eq!(offsets, [0, 1, 2, 3, text.len()]);
+        assert_eq!(stops[0].1, 0.0);
+        assert!(stops.windows(2).all(|pair| pair[0].1 < pair[1].1));
+    }
+
+    #[test]
+    #[ignore = "needs a color emoji font installed"]
+    fn emoji_resolve_through_the_fallback_chain() {
+        let mut db = fontdb::Database::new();
+        db.load_font_data(include_bytes!("../inter-v19-latin-regular.ttf").to_vec());
+        db.load_system_fonts();
+        let emoji = ["Noto Color Emoji", "Apple Color Emoji", "Segoe UI Emoji"];
+        let mut font_system = FontSystem::new_with_locale_and_db_and_fallback(
+            "en-US".into(),
+            db,
+            FallbackChain(emoji.to_vec()),
+        );
+
+        let mut buffer = Buffer::new(&mut font_system, Metrics::new(16.0, 20.0));
+        set_text(
+            &mut buffer,
+            &mut font_system,
+            "hi 😀",
+            &Attrs::new().family(Family::Name("Inter")),
+            TextDirection::Auto,
+        );
+        buffer.shape_until_scroll(&mut font_system, false);
+        let run = buffer.layout_runs().next().unwrap();
+        let glyph = run.glyphs.iter().find(|g| g.start == 3).unwrap();
+        assert_ne!(glyph.glyph_id, 0, "the emoji fell back to notdef");
+        let face = font_system.db().face(glyph.font_id).unwrap();
+        assert!(
+            face.families
+                .iter()
+                .any(|(family, _)| emoji.contains(&family.as_str())),
+            "the emoji resolved to {:?}",
+            face.families
+        );
+    }
+
+    #[test]
+    fn shaped_text_is_evicted_least_recently_drawn_first() {
+        let mut font_system =
+            FontSystem::new_with_locale_and_db("en-US".into(), Default::default());
+        let key = |text: String| TextKey {
+            text,
+            size: 16,
+            line_height: 20,
+            color: 0,
+            family: Cow::Borrowed("Inter"),
+            weight: Weight::NORMAL,
+            style: Style::Normal,
+            text_align: TextAlign::Left,
+            direction: TextDirection::Auto,
+            wrap_width: None,
+            truncate: None,
+        };
+        let mut cache = ShapedCache::default();
+        let mut insert = |cache: &mut ShapedCache, text: String, glyphs, frame| {
+            let buffer = Buffer::new(&mut font_system, Metrics::new(16.0, 20.0));
+            cache.insert(key(text), buffer, glyphs, frame)
+        };
+
+        // a full cache of frame 0 text, then 10 more texts drawn in frame 1
+        for i in 0..MAX_SHAPED_TEXTS {
+            insert(&mut cache, format!("hud {i}"), 1, 0);
+        }
+        assert!(insert(&mut cache, "hud 0".into(), 1, 0).is_some());
+        for i in 0..10 {
+            insert(&mut cache, format!("new {i}"), 1, 1);
+        }
+        let mut evicted = Vec::new();
+        cache.evict(1, |key, _| evicted.push(key.text));
+        assert_eq!(evicted.len(), 10);
+        assert!(evicted.iter().all(|text| text.starts_with("hud")));
+        assert_eq!(
+            (cache.entries.len(), cache.glyphs),
+            (MAX_SHAPED_TEXTS, MAX_SHAPED_TEXTS)
+        );
+        assert!(cache.take(&key("new 3".into())).is_some());
+
+        // one huge text goes over the glyph limit, but text drawn this frame is kept
+        insert(&mut cache, "log".into(), MAX_SHAPED_GLYPHS + 1, 2);
+        cache.evict(2, |key, _| evicted.push(key.text));
+        assert!(cache.glyphs > MAX_SHAPED_GLYPHS);
+        assert!(cache.take(&key("log".into())).is_some());
+        insert(&mut cache, "log".into(), MAX_SHAPED_GLYPHS + 1, 2);
+        insert(&mut cache, "fps".into(), 3, 3);
+        cache.evict(3, |key, _| evicted.push(key.text));
+        assert!(cache.glyphs <= MAX_SHAPED_GLYPHS);
+        assert!(cache.take(&key("log".into())).is_none());
+
+        assert_eq!((cache.entries.len(), cache.glyphs), (1, 3));
+
+        let mut cleared = Vec::new();
+        cache.clear(|key, _| cleared.push(key.text));
+        assert_eq!((cleared, cache.glyphs), (vec!["fps".to_string()], 0));
+    }
+
+    #[test]
+    fn shaped_text_is_laid_out_again_after_resizes_and_scale_changes() {
+        let mut font_system =
+            FontSystem::new_with_locale_and_db("en-US".into(), Default::default());
+        let key = TextKey {
+            text: "hud".into(),
+            size: 16,
+            line_height: 20,
+            color: 0,
+            family: Cow::Borrowed("Inter"),
+            weight: Weight::NORMAL,
+            style: Style::Normal,
+            text_align: TextAlign::Left,
+            direction: TextDirection::Auto,
+            wrap_width: None,
+            truncate: None,
+        };
+        let mut cache = ShapedCache::default();
+        let mut kept_after = |size, scale_factor| {
+            let buffer = Buffer::new(&mut font_system, Metrics::new(16.0, 20.0));
+            cache.insert(key.clone(), buffer, 3, 0);
+            let mut evicted = 0;
+            cache.relayout_for(size, scale_factor, |_, _| evicted += 1);
+            evicted == 0 && cache.take(&key).is_some()
+        };
+
+        assert!(!kept_after((800, 600), 1.0), "nothing was laid out yet");
+        assert!(kept_after((800, 600), 1.0));
+        assert!(!kept_after((1600, 600), 1.0));
+        assert!(!kept_after((1600, 600), 2.0));
+        assert!(kept_after((1600, 600), 2.0));
+    }
+
+    #[test]
+    fn wrapped_lines_align_within_the_widest_line() {
+        let left = aligned_lines(TextAlign::Left);
+        assert!(left.len() >= 3, "the paragraph should wrap");
+        assert!(left.iter().all(|&(x, w)| x == 0.0 && w <= 200.0));
+        let widest = left.iter().map(|&(_, w)| w).fold(0.0, f32::max);
+
+        // same wrapping, every line shifted inside the widest one
+        for (align, shift) in [(TextAlign::Center, 0.5), (TextAlign::Right, 1.0)] {
+            let lines = aligned_lines(align);
+            assert_eq!(lines.len(), left.len());
+            for (&(x, w), &(_, left_w)) in lines.iter().zip(&left) {
+                assert_eq!(w, left_w);
+                assert!(
+                    (x - (widest - w) * shift).abs() < 0.01,
+                    "{align:?} line at {x}"
+                );
+            }
+        }
+    }
+
+    /// Truncates `text` in Inter to `width`, returning what's drawn & its width
+    fn truncated(text: &str, mode: TextTruncate, width: f32) -> (String, f32) {
+        let mut font_system = inter();
+        let mut buffer = Buffer::new(&mut font_system, Metrics::new(16.0, 20.0));
+        let attrs = Attrs::new().family(Family::Name("Inter"));
+        let direction = TextDirection::Auto;
+        set_text(&mut buffer, &mut font_system, text, &attrs, direction);
+        let drawn = truncate(
+            &mut buffer,
+            &mut font_system,
+            text,
+            &attrs,
+            direction,
+            mode,
+            width,
+        );
+        buffer.shape_until_scroll(&mut font_system, false);
+        let line_w = buffer.layout_runs().next().map_or(0.0, |run| run.line_w);
+        (drawn.unwrap_or_else(|| text.to_string()), line_w)
+    }
+
+    #[test]
+    fn truncation_keeps_whole_clusters_as_the_width_shrinks() {
+        // an accent, a ZWJ sequence & a skin tone modifier, each one cluster
+        let graphemes = [
+            "Z",
+            "o",
+            "e\u{301}",
+            " ",
+            "👩\u{200D}🚀",
+            " ",
+            "A",
+            "c",
+            "e",
+            "👍🏽",
+            "!",
+        ];
+        let text = graphemes.concat();
+        let edges: Vec<_> = (0..=graphemes.len())
+            .map(|i| graphemes[..i].concat().len())
+            .collect();
+
+        for mode in [TextTruncate::End, TextTruncate::Middle] {
+            let (full, full_w) = truncated(&text, mode, 1000.0);
+            assert_eq!(full, text);
+
+            let mut kept = text.len();
+            let mut width = full_w - 1.0;
+            while width >= 0.0 {
+                let (drawn, drawn_w) = truncated(&text, mode, width);
+                let (head, tail) = drawn
+                    .split_once('…')
+                    .unwrap_or_else(|| panic!("{mode:?} at {width}px drew {drawn:?}"));
+                assert!(text.starts_with(head) && text.ends_with(tail));
+                assert!(edges.contains(&head.len()), "{mode:?} split {drawn:?}");
+                assert!(
+                    edges.contains(&(text.len() - tail.len())),
+                    "{mode:?} split {drawn:?}"
+                );
+                if mode == TextTruncate::End {
+                    assert!(tail.is_empty());
+                }
+                assert!(
+                    drawn_w <= width || drawn == "…",
+                    "{drawn:?} overflows {width}px"
+                );
+                assert!(
+                    head.len() + tail.len() <= kept,
+                    "{mode:?} grew at {width}px"
+                );
+                kept = head.len() + tail.len();
+                width -= 3.0;
+            }
+            assert_eq!(kept, 0);
+        }
+    }
+
+    #[test]
+    fn truncation_keeps_the_first_line_only() {
+        let (drawn, _) = truncated("score\nhigh score", TextTruncate::End, 1000.0);
+        assert_eq!(drawn, "score…");
+        let (drawn, _) = truncated("", TextTruncate::Middle, 0.0);
+        assert_eq!(drawn, "");
     }
 }
@@ -0,0 +1,649 @@
+//! A single line text field: typing, selecting with the keyboard or mouse, copy & paste &
+//! input method composition
+//!
+//! ```no_run
+//! # use egor_glue::{app::FrameContext, math::Rect, text_edit::{TextEditState, TextEditStyle}};
+//! # use glam::vec2;
+//! # fn f(ctx: &mut FrameContext, name: &mut TextEditState) {
+//! if name.update(ctx.input, ctx.timer) {
+//!     println!("name: {}", name.text());
+//! }
+//! let field = Rect::new(vec2(20.0, 20.0), vec2(240.0, 36.0));
+//! name.draw(&mut ctx.gfx, field, &TextEditStyle::default());
+//! # }
+//! ```
+
+use egor_app::{
+    clipboard::Clipboard,
+    input::{Input, KeyCode, MouseButton, Pointer},
+    time::FrameTimer,
+};
+use glam::{Vec2, vec2};
+use std::ops::Range;
+
+use crate::{color::Color, graphics::Graphics, math::Rect};
+
+/// Screen-space layer fields are drawn to
+const LAYER: &str = "egor_text_edit";
+/// Seconds the caret stays shown, then hidden, while blinking
+const BLINK_INTERVAL: f32 = 0.53;
+/// Line height of the field's text, as a multiple of the font size like [`Graphics::text`]
+const LINE_HEIGHT: f32 = 1.2;
+
+/// Colors & sizes of a field drawn with [`TextEditState::draw`]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TextEditStyle {
+    pub font_size: f32,
+    pub text: Color,
+    pub background: Color,
+    /// Outline of the field while it has focus
+    pub focus: Color,
+    pub selection: Color,
+    pub caret: Color,
+    /// Space between the field's edges & its text, in pixels
+    pub padding: f32,
+    pub caret_width: f32,
+}
+
+impl Default for TextEditStyle {
+    fn default() -> Self {
+        Self {
+            font_size: 18.0,
+            text: Color::WHITE,
+            background: Color::new([0.2, 0.22, 0.27, 1.0]),
+            focus: Color::new([0.95, 0.8, 0.3, 1.0]),
+            selection: Color::new([0.33, 0.6, 0.95, 0.6]),
+            caret: Color::WHITE,
+            padding: 8.0,
+            caret_width: 2.0,
+        }
+    }
+}
+
+/// The text, caret & selection of a single line text field, kept across frames
+///
+/// [`Self::update`] takes the frame's typing, keys & clicks: arrows, Home & End move the
+/// caret (with Shift to select), Ctrl+arrows jump words (Option on macOS), Backspace &
+/// Delete erase, Ctrl+A, C, X & V select all, copy, cut & paste (Cmd on macOS) & clicking,
+/// dragging or double clicking select with the mouse. [`Self::draw`] draws the field in a
+/// screen rect, scrolled to keep the caret in view. Clicks are hit tested against what was
+/// drawn the frame before
+#[derive(Default)]
+pub struct TextEditState {
+    text: String,
+    /// Byte offsets of the caret & the other end of the selection, equal without one
+    caret: usize,
+    anchor: usize,
+    focused: bool,
+    /// The mouse was pressed in the field & is still held
+    dragging: bool,
+    /// Seconds since the caret last moved, so it shows while typing
+    blink: f32,
+    /// Pixels the text is scrolled left by
+    scroll: f32,
+    /// Input method composition at the caret & its cursor
+    preedit: (String, Option<(usize, usize)>),
+    clipboard: Clipboard,
+    /// Field drawn last, its text (composition included) & where a caret can go in it
+    rect: Option<Rect>,
+    laid_out: String,
+    layout_size: f32,
+    stops: Vec<(usize, f32)>,
+    /// Left edge of the text & caret area drawn last, in screen pixels
+    text_x: f32,
+    caret_area: [f32; 4],
+    /// Scratch for the displayed text & selection spans
+    display: String,
+    spans: Vec<(f32, f32)>,
+}
+
+impl TextEditState {
+    pub fn new(text: impl Into<String>) -> Self {
+        let text = text.into();
+        Self {
+            caret: text.len(),
+            anchor: text.len(),
+            text,
+            ..Default::default()
+        }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Replace the text, moving the caret to its end
+    pub fn set_text(&mut self, text: impl Into<String>) {
+        self.text = text.into();
+        self.caret = self.text.len();
+        self.anchor = self.caret;
+    }
+
+    /// Byte offset of the caret in [`Self::text`]
+    pub fn caret(&self) -> usize {
+        self.caret
+    }
+
+    /// Selected bytes of [`Self::text`], empty without a selection
+    pub fn selection(&self) -> Range<usize> {
+        self.caret.min(self.anchor)..self.caret.max(self.anchor)
+    }
+
+    /// Select `range` of [`Self::text`], with the caret at its end. Offsets are clamped &
+    /// rounded down to character boundaries
+    pub fn select(&mut self, range: Range<usize>) {
+        let boundary = |mut i: usize| {
+            i = i.min(self.text.len());
+            while !self.text.is_char_boundary(i) {
+                i -= 1;
+            }
+            i
+        };
+        self.anchor = boundary(range.start);
+        self.caret = boundary(range.end);
+    }
+
+    /// Whether keys & typing go to this field. Given by clicking it & taken by clicking
+    /// anywhere else
+    pub fn focused(&self) -> bool {
+        self.focused
+    }
+
+    pub fn set_focus(&mut self, focused: bool) {
+        self.focused = focused;
+        self.blink = 0.0;
+    }
+
+    /// Apply this frame's clicks, keys & typing. Returns true if the text changed. While
+    /// focused it asks for text input, so the input method follows the caret
+    pub fn update(&mut self, input: &Input, timer: &FrameTimer) -> bool {
+        let before = (self.caret, self.anchor);
+        self.blink += timer.delta;
+        self.pointer(input);
+        if !self.focused {
+            self.preedit.0.clear();
+            self.dragging = false;
+            return false;
+        }
+
+        let held = |keys| input.keys_held(keys);
+        let shift = held(&[KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+        let ctrl = held(&[KeyCode::ControlLeft, KeyCode::ControlRight]);
+        let alt = held(&[KeyCode::AltLeft, KeyCode::AltRight]);
+        // macOS edits with Cmd & jumps words with Option
+        let (command, word) = match cfg!(target_os = "macos") {
+            true => (held(&[KeyCode::SuperLeft, KeyCode::SuperRight]), alt),
+            false => (ctrl, ctrl),
+        };
+        let key = |key| input.key_pressed_repeating(key);
+
+        let mut changed = false;
+        // pastes asked for on earlier frames arrive when the browser hands them over
+        if let Some(pasted) = self.clipboard.take_paste() {
+            changed |= self.insert(&single_line(&pasted));
+        }
+        // Ctrl+Alt is AltGr on Windows, which types characters
+        if !command || alt {
+            changed |= self.insert(input.text_typed());
+        }
+
+        if key(KeyCode::ArrowLeft) || key(KeyCode::ArrowRight) {
+            let right = key(KeyCode::ArrowRight);
+            let selection = self.selection();
+            self.caret = match (right, word) {
+                _ if !shift && !selection.is_empty() && !word => match right {
+                    true => selection.end,
+                    false => selection.start,
+                },
+                (true, true) => next_word(&self.text, self.caret),
+                (false, true) => prev_word(&self.text, self.caret),
+                (true, false) => self.next_stop(self.caret),
+                (false, false) => self.prev_stop(self.caret),
+            };
+            if !shift {
+                self.anchor = self.caret;
+            }
+        }
+        if key(KeyCode::Home) || key(KeyCode::End) {
+            self.caret = if key(KeyCode::Home) {
+                0
+            } else {
+                self.text.len()
+            };
+            if !shift {
+                self.anchor = self.caret;
+            }
+        }
+        if key(KeyCode::Backspace) || key(KeyCode::Delete) {
+            if self.selection().is_empty() {
+                self.caret = match (key(KeyCode::Delete), word) {
+                    (true, true) => next_word(&self.text, self.caret),
+                    (false, true) => prev_word(&self.text, self.caret),
+                    (true, false) => self.next_char(self.caret),
+                    (false, false) => self.prev_char(self.caret),
+                };
+            }
+            changed |= self.delete_selection();
+        }
+
+        if command && input.key_pressed(KeyCode::KeyA) {
+            self.select(0..self.text.len());
+        }
+        if command && (input.key_pressed(KeyCode::KeyC) || input.key_pressed(KeyCode::KeyX)) {
+            let selection = self.selection();
+            if !selection.is_empty() {
+                self.clipboard.copy(&self.text[selection]);
+            }
+            if input.key_pressed(KeyCode::KeyX) {
+                changed |= self.delete_selection();
+            }
+        }
+        if command && input.key_pressed(KeyCode::KeyV) {
+            self.clipboard.request_paste();
+            // native clipboards are read right away
+            if let Some(pasted) = self.clipboard.take_paste() {
+                changed |= self.insert(&single_line(&pasted));
+            }
+        }
+
+        match input.ime_preedit() {
+            Some((text, cursor)) => {
+                text.clone_into(&mut self.preedit.0);
+                self.preedit.1 = cursor;
+            }
+            None => self.preedit.0.clear(),
+        }
+        if self.rect.is_some() {
+            input.request_text_input(self.caret_area);
+        }
+        if changed || (self.caret, self.anchor) != before {
+            self.blink = 0.0;
+        }
+        changed
+    }
+
+    /// Focus & place the caret on clicks, select words on double clicks & everything on
+    /// triple clicks, & extend the selection while dragging
+    fn pointer(&mut self, input: &Input) {
+        let Some(rect) = self.rect else { return };
+        let mouse = Vec2::from(input.mouse_position());
+        let shift = input.keys_held(&[KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+        if input.mouse_pressed(MouseButton::Left) {
+            let inside = rect.contains(mouse) && !input.pointer_claimed(Pointer::Mouse);
+            self.focused = inside;
+            self.dragging = inside;
+            if !inside {
+                return;
+            }
+            input.claim_pointer(Pointer::Mouse);
+            let hit = self.hit(mouse.x);
+            match input.click_count(MouseButton::Left) {
+                2 => {
+                    let word = word_at(&self.text, hit);
+                    self.select(word);
+                }
+                3.. => self.select(0..self.text.len()),
+                _ => {
+                    self.caret = hit;
+                    if !shift {
+                        self.anchor = hit;
+                    }
+                }
+            }
+            self.blink = 0.0;
+        } else if self.dragging && input.mouse_held(MouseButton::Left) {
+            self.caret = self.hit(mouse.x);
+        } else {
+            self.dragging = false;
+        }
+    }
+
+    /// Caret offset nearest to screen `x` in the text drawn last, or the caret while that's
+    /// out of date
+    fn hit(&self, x: f32) -> usize {
+        match self.laid_out == self.text {
+            true => nearest_stop(&self.stops, x - self.text_x),
+            false => self.caret,
+        }
+    }
+
+    /// Caret stops of the text drawn last, if it's still the text being edited
+    fn current_stops(&self) -> Option<&[(usize, f32)]> {
+        (self.laid_out == self.text).then_some(&self.stops)
+    }
+
+    /// Offset of the glyph cluster boundary before `offset`, or the character boundary
+    /// before the field is drawn
+    fn prev_stop(&self, offset: usize) -> usize {
+        match self.current_stops() {
+            Some(stops) => stops
+                .iter()
+                .rev()
+                .find(|&&(stop, _)| stop < offset)
+                .map_or(0, |&(stop, _)| stop),
+            None => self.prev_char(offset),
+        }
+    }
+
+    fn next_stop(&self, offset: usize) -> usize {
+        match self.current_stops() {
+            Some(stops) => stops
+                .iter()
+                .find(|&&(stop, _)| stop > offset)
+                .map_or(self.text.len(), |&(stop, _)| stop),
+            None => self.next_char(offset),
+        }
+    }
+
+    /// Backspace & Delete erase characters, so an accent typed last can be taken back
+    fn prev_char(&self, offset: usize) -> usize {
+        let before = self.text[..offset].chars().next_back();
+        offset - before.map_or(0, char::len_utf8)
+    }
+
+    fn next_char(&self, offset: usize) -> usize {
+        let after = self.text[offset..].chars().next();
+        offset + after.map_or(0, char::len_utf8)
+    }
+
+    /// Replace the selection with `text`. Returns true if anything changed
+    fn insert(&mut self, text: &str) -> bool {
+        if text.is_empty() {
+            return false;
+        }
+        self.delete_selection();
+        self.text.insert_str(self.caret, text);
+        self.caret += text.len();
+        self.anchor = self.caret;
+        true
+    }
+
+    /// Returns true if there was a selection to delete
+    fn delete_selection(&mut self) -> bool {
+        let selection = self.selection();
+        if selection.is_empty() {
+            return false;
+        }
+        self.text.replace_range(selection.clone(), "");
+        self.caret = selection.start;
+        self.anchor = selection.start;
+        true
+    }
+
+    /// Draw the field in `rect` (screen pixels): its background, the selection, the text
+    /// scrolled to keep the caret in view & the blinking caret while focused. Composition
+    /// from an input method shows underlined at the caret
+    pub fn draw(&mut self, gfx: &mut Graphics, rect: Rect, style: &TextEditStyle) {
+        self.rect = Some(rect);
+        let composing = !self.preedit.0.is_empty();
+        let mut display = std::mem::take(&mut self.display);
+        display.clear();
+        match composing {
+            true => {
+                display.push_str(&self.text[..self.caret]);
+                display.push_str(&self.preedit.0);
+                display.push_str(&self.text[self.caret..]);
+            }
+            false => display.push_str(&self.text),
+        }
+        if display != self.laid_out || style.font_size != self.layout_size {
+            gfx.caret_stops(&display, style.font_size, &mut self.stops);
+            std::mem::swap(&mut display, &mut self.laid_out);
+            self.layout_size = style.font_size;
+        }
+        self.display = display;
+
+        let caret = match composing {
+            true => self.caret + self.preedit.1.map_or(self.preedit.0.len(), |(_, end)| end),
+            false => self.caret,
+        };
+        let caret_x = stop_x(&self.stops, caret);
+        let line_height = style.font_size * LINE_HEIGHT;
+        let inner = Rect::new(
+            rect.position + vec2(style.padding, 0.0),
+            vec2((rect.size.x - style.padding * 2.0).max(0.0), rect.size.y),
+        );
+        let width = (inner.size.x - style.caret_width).max(0.0);
+        let text_width = self.stops.iter().map(|&(_, x)| x).fold(0.0, f32::max);
+        if caret_x - self.scroll > width {
+            self.scroll = caret_x - width;
+        }
+        self.scroll = self
+            .scroll
+            .min(caret_x)
+            .clamp(0.0, (text_width - width).max(0.0));
+        self.text_x = inner.position.x - self.scroll;
+        let text_y = rect.position.y + (rect.size.y - line_height) * 0.5;
+        self.caret_area = [
+            self.text_x + caret_x,
+            text_y,
+            style.caret_width,
+            line_height,
+        ];
+
+        // spans of the selection or composition to draw, clipped to the text area
+        let (highlight, underline) = match composing {
+            true => (0..0, self.caret..self.caret + self.preedit.0.len()),
+            false => (self.selection(), 0..0),
+        };
+        let clip = |(x0, x1): (f32, f32)| {
+            let x0 = (self.text_x + x0).max(inner.min().x);
+            let x1 = (self.text_x + x1).min(inner.max().x);
+            (x1 > x0).then_some((x0, x1 - x0))
+        };
+        gfx.layer(LAYER).screen_space(true);
+        gfx.on_layer(LAYER, |gfx| {
+            gfx.rect().with(&rect).color(style.background);
+            if self.focused {
+                gfx.rect()
+                    .with(&rect)
+                    .fill(false)
+                    .stroke(2.0)
+                    .stroke_color(style.focus);
+            }
+            selection_spans(&self.stops, highlight, &mut self.spans);
+            for (x, w) in self.spans.iter().filter_map(|&span| clip(span)) {
+                gfx.rect()
+                    .at(vec2(x, text_y))
+                    .size(vec2(w, line_height))
+                    .color(style.selection);
+            }
+            selection_spans(&self.stops, underline, &mut self.spans);
+            for (x, w) in self.spans.iter().filter_map(|&span| clip(span)) {
+                gfx.rect()
+                    .at(vec2(x, text_y + line_height - 2.0))
+                    .size(vec2(w, 1.0))
+                    .color(style.text);
+            }
+            let visible = self.blink % (BLINK_INTERVAL * 2.0) < BLINK_INTERVAL;
+            let [x, y, w, h] = self.caret_area;
+            if self.focused && visible && inner.contains(vec2(x, rect.center().y)) {
+                gfx.rect()
+                    .at(vec2(x, y))
+                    .size(vec2(w, h))
+                    .color(style.caret);
+            }
+        });
+        gfx.text(&self.laid_out)
+            .at(vec2(self.text_x, text_y))
+            .size(style.font_size)
+            .line_height(line_height)
+            .color(style.text)
+            .clip(inner);
+    }
+}
+
+/// `text` with its line breaks turned into spaces & other control characters dropped
+fn single_line(text: &str) -> String {
+    let text = text.replace("\r\n", " ").replace(['\r', '\n', '\t'], " ");
+    text.chars().filter(|c| !c.is_control()).collect()
+}
+
+fn is_word(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Start of the word before `offset`, skipping the spaces & punctuation before it
+fn prev_word(text: &str, offset: usize) -> usize {
+    let before = text[..offset].trim_end_matches(|c| !is_word(c));
+    before.trim_end_matches(is_word).len()
+}
+
+/// End of the word after `offset`, skipping the spaces & punctuation before it
+fn next_word(text: &str, offset: usize) -> usize {
+    let after = text[offset..].trim_start_matches(|c| !is_word(c));
+    text.len() - after.trim_start_matches(is_word).len()
+}
+
+/// The word around `offset`, or just the character after it if that isn't part of one
+fn word_at(text: &str, offset: usize) -> Range<usize> {
+    let start = text[..offset].trim_end_matches(is_word).len();
+    let end = text.len() - text[offset..].trim_start_matches(is_word).len();
+    match start == end {
+        true => offset..offset + text[offset..].chars().next().map_or(0, char::len_utf8),
+        false => start..end,
+    }
+}
+
+/// Offset of the caret stop nearest to `x`
+fn nearest_stop(stops: &[(usize, f32)], x: f32) -> usize {
+    let distance = |stop: &&(usize, f32)| (stop.1 - x).abs();
+    stops
+        .iter()
+        .min_by(|a, b| distance(a).total_cmp(&distance(b)))
+        .map_or(0, |&(offset, _)| offset)
+}
+
+/// X of the caret at `offset`, the last stop at or before it
+fn stop_x(stops: &[(usize, f32)], offset: usize) -> f32 {
+    let index = stops.partition_point(|&(stop, _)| stop <= offset);
+    index.checked_sub(1).map_or(0.0, |i| stops[i].1)
+}
+
+/// Fills `spans` with the `(x, width)` of the clusters in `range`, touching ones merged.
+/// Right to left text within a selection may take several spans
+fn selection_spans(stops: &[(usize, f32)], range: Range<usize>, spans: &mut Vec<(f32, f32)>) {
+    spans.clear();
+    for pair in stops.windows(2) {
+        let [(start, a), (end, b)] = [pair[0], pair[1]];
+        if start < range.start || end > range.end {
+            continue;
+        }
+        let (x0, x1) = (a.min(b), a.max(b));
+        match spans.last_mut() {
+            Some((x, w)) if (*x + *w - x0).abs() < 0.5 => *w = x1 - *x,
+            Some((x, w)) if (x1 - *x).abs() < 0.5 => {
+                *w += *x - x0;
+                *x = x0;
+            }
+            _ => spans.push((x0, x1 - x0)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use egor_app::input::InputEvent;
+
+    fn press(input: &mut Input, key: KeyCode) {
+        input.apply(InputEvent::Key { key, pressed: true }, 0.0);
+    }
+
+    fn release(input: &mut Input, key: KeyCode) {
+        input.apply(
+            InputEvent::Key {
+                key,
+                pressed: false,
+            },
+            0.0,
+        );
+    }
+
+    /// Runs one frame of `field` with `input`, starting the next
+    fn frame(field: &mut TextEditState, input: &mut Input) -> bool {
+        let changed = field.update(input, &FrameTimer::default());
+        input.end_frame();
+        changed
+    }
+
+    #[test]
+    fn typing_moving_and_erasing_edit_at_the_caret() {
+        let mut field = TextEditState::new("hello world");
+        field.set_focus(true);
+        let mut input = Input::default();
+
+        press(&mut input, KeyCode::ControlLeft);
+        press(&mut input, KeyCode::ArrowLeft);
+        frame(&mut field, &mut input);
+        assert_eq!(field.caret(), 6);
+        release(&mut input, KeyCode::ArrowLeft);
+        press(&mut input, KeyCode::Backspace);
+        assert!(frame(&mut field, &mut input));
+        assert_eq!(field.text(), "world");
+        release(&mut input, KeyCode::ControlLeft);
+        release(&mut input, KeyCode::Backspace);
+
+        input.apply(InputEvent::Text("é ".into()), 0.0);
+        press(&mut input, KeyCode::ShiftLeft);
+        press(&mut input, KeyCode::End);
+        frame(&mut field, &mut input);
+        assert_eq!(field.text(), "é world");
+        assert_eq!(&field.text()[field.selection()], "world");
+
+        input.apply(InputEvent::Text("there".into()), 0.0);
+        assert!(frame(&mut field, &mut input));
+        assert_eq!(field.text(), "é there");
+        release(&mut input, KeyCode::ShiftLeft);
+        press(&mut input, KeyCode::Home);
+        press(&mut input, KeyCode::Delete);
+        frame(&mut field, &mut input);
+        assert_eq!(field.text(), " there");
+    }
+
+    #[test]
+    fn cut_text_pastes_back_single_line() {
+        let mut field = TextEditState::new("one two");
+        field.set_focus(true);
+        field.select(4..7);
+        let mut input = Input::default();
+        press(&mut input, KeyCode::ControlLeft);
+        press(&mut input, KeyCode::KeyX);
+        assert!(frame(&mut field, &mut input));
+        assert_eq!(field.text(), "one ");
+
+        field.clipboard.copy("two\nthree");
+        press(&mut input, KeyCode::KeyV);
+        assert!(frame(&mut field, &mut input));
+        assert_eq!(field.text(), "one two three");
+    }
+
+    #[test]
+    fn words_skip_punctuation_and_spaces() {
+        let text = "let x = foo_bar(1);";
+        assert_eq!(next_word(text, 0), 3);
+        assert_eq!(next_word(text, 5), 15);
+        assert_eq!(prev_word(text, text.len()), 16);
+        assert_eq!(prev_word(text, 16), 8);
+        assert_eq!(word_at(text, 10), 8..15);
+        assert_eq!(word_at(text, 6), 6..7);
+    }
+
+    #[test]
+    fn clicks_hit_the_nearest_cluster_edge() {
+        let stops = [(0, 0.0), (1, 10.0), (3, 18.0), (4, 30.0)];
+        assert_eq!(nearest_stop(&stops, -5.0), 0);
+        assert_eq!(nearest_stop(&stops, 15.0), 3);
+        assert_eq!(nearest_stop(&stops, 99.0), 4);
+        // inside a cluster the caret sits at its start
+        assert_eq!(stop_x(&stops, 2), 10.0);
+
+        let mut spans = Vec::new();
+        selection_spans(&stops, 1..4, &mut spans);
+        assert_eq!(spans, [(10.0, 20.0)]);
+        // right to left clusters run the other way
+        let rtl = [(0, 30.0), (2, 20.0), (4, 10.0), (6, 0.0)];
+        selection_spans(&rtl, 2..6, &mut spans);
+        assert_eq!(spans, [(0.0, 20.0)]);
+    }
+}
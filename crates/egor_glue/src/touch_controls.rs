@@ -0,0 +1,419 @@
+//! On-screen joysticks & buttons for touch screens, drawn with egor's own shapes. They
+//! follow the left mouse button too, so they can be tried out on desktop
+//!
+//! Controls claim the touches they follow (see [`Input::claim_pointer`]), so update them
+//! before gameplay code reading taps, & have that code skip claimed pointers:
+//!
+//! ```no_run
+//! # use egor_glue::{app::FrameContext, touch_controls::*};
+//! # use egor_app::input::{MouseButton, Pointer};
+//! # fn f(ctx: &mut FrameContext, stick: &mut VirtualJoystick, fire: &mut VirtualButton) {
+//! let layout = TouchControlsLayout::default();
+//! let screen = ctx.gfx.screen_size();
+//! stick.area = layout.joystick(screen, ctx.ui_scale());
+//! fire.rect = layout.button(0, screen, ctx.ui_scale());
+//!
+//! let movement = stick.update(ctx.input);
+//! fire.update(ctx.input);
+//! let shooting = fire.held()
+//!     || (ctx.input.mouse_held(MouseButton::Left)
+//!         && !ctx.input.pointer_claimed(Pointer::Mouse));
+//!
+//! stick.draw(&mut ctx.gfx);
+//! fire.draw(&mut ctx.gfx);
+//! # }
+//! ```
+
+use egor_app::input::{Input, MouseButton, Pointer};
+use glam::{Vec2, vec2};
+
+use crate::{
+    color::Color,
+    graphics::{Graphics, TextureId},
+    math::Rect,
+};
+
+/// Screen-space layer controls are drawn to
+const LAYER: &str = "egor_touch_controls";
+const IDLE: Color = Color::new([1.0, 1.0, 1.0, 0.25]);
+const ACTIVE: Color = Color::new([1.0, 1.0, 1.0, 0.45]);
+
+/// Where `pointer` is & whether it's still down, if it's down or was let go this frame
+fn pointer_state(input: &Input, pointer: Pointer) -> Option<(Vec2, bool)> {
+    match pointer {
+        Pointer::Mouse => {
+            let held = input.mouse_held(MouseButton::Left);
+            let down = held || input.mouse_released(MouseButton::Left);
+            down.then(|| (input.mouse_position().into(), held))
+        }
+        Pointer::Touch(id) => input.touch(id).map(|t| (t.position.into(), t.held())),
+    }
+}
+
+/// Takes a pointer that went down in `hit` this frame & no other control claimed
+fn grab(input: &Input, hit: impl Fn(Vec2) -> bool) -> Option<(Pointer, Vec2)> {
+    let touches = (input.unclaimed_touches())
+        .filter(|t| t.pressed())
+        .map(|t| (Pointer::Touch(t.id), Vec2::from(t.position)));
+    let mouse = (input.mouse_pressed(MouseButton::Left) && !input.pointer_claimed(Pointer::Mouse))
+        .then(|| (Pointer::Mouse, input.mouse_position().into()));
+    let (pointer, position) = touches.chain(mouse).find(|&(_, p)| hit(p))?;
+    input.claim_pointer(pointer);
+    Some((pointer, position))
+}
+
+/// A thumb stick anywhere in its area: pressing in it centers the stick there & dragging
+/// the thumb away steers. Its value points from the center to the thumb, in screen
+/// directions (+Y down)
+#[derive(Clone, Debug, PartialEq)]
+pub struct VirtualJoystick {
+    /// Where a press grabs the stick. The stick rests at its center
+    pub area: Rect,
+    /// How far the thumb travels, in pixels. Defaults to a third of the area's smaller side
+    pub radius: Option<f32>,
+    /// Fraction of the radius around the center reading as zero
+    pub dead_zone: f32,
+    pointer: Option<Pointer>,
+    center: Vec2,
+    thumb: Vec2,
+    value: Vec2,
+}
+
+impl VirtualJoystick {
+    pub fn new(area: Rect) -> Self {
+        Self {
+            area,
+            radius: None,
+            dead_zone: 0.15,
+            pointer: None,
+            center: area.center(),
+            thumb: area.center(),
+            value: Vec2::ZERO,
+        }
+    }
+
+    /// Set the fraction of the radius around the center reading as zero
+    pub fn dead_zone(mut self, dead_zone: f32) -> Self {
+        self.dead_zone = dead_zone.clamp(0.0, 0.99);
+        self
+    }
+
+    /// Set how far the thumb travels, in pixels
+    pub fn radius(mut self, radius: f32) -> Self {
+        self.radius = Some(radius);
+        self
+    }
+
+    fn travel(&self) -> f32 {
+        self.radius.unwrap_or(self.area.size.min_element() / 3.0)
+    }
+
+    /// Follows the pointer holding the stick, or grabs a new one pressed in its area.
+    /// Returns [`Self::value`]
+    pub fn update(&mut self, input: &Input) -> Vec2 {
+        let held = self.pointer.and_then(|p| pointer_state(input, p));
+        let thumb = match held {
+            Some((position, true)) => Some(position),
+            _ => grab(input, |p| self.area.contains(p)).map(|(pointer, position)| {
+                self.pointer = Some(pointer);
+                self.center = position;
+                position
+            }),
+        };
+        match thumb {
+            Some(thumb) => {
+                let offset = (thumb - self.center).clamp_length_max(self.travel());
+                self.thumb = self.center + offset;
+                self.value = deflection(offset / self.travel(), self.dead_zone);
+            }
+            None => {
+                self.pointer = None;
+                self.center = self.area.center();
+                self.thumb = self.center;
+                self.value = Vec2::ZERO;
+            }
+        }
+        self.value
+    }
+
+    /// Where the stick points, each axis in `-1..=1` & no longer than 1. Zero inside the
+    /// dead zone, rising smoothly from its edge
+    pub fn value(&self) -> Vec2 {
+        self.value
+    }
+
+    /// Whether a finger (or the mouse) holds the stick
+    pub fn active(&self) -> bool {
+        self.pointer.is_some()
+    }
+
+    /// Draws the base & thumb in screen space
+    pub fn draw(&self, gfx: &mut Graphics) {
+        let radius = self.travel();
+        let color = if self.active() { ACTIVE } else { IDLE };
+        gfx.layer(LAYER).screen_space(true);
+        gfx.on_layer(LAYER, |gfx| {
+            gfx.path()
+                .at(self.center)
+                .circle(radius)
+                .thickness(radius * 0.08)
+                .stroke_color(color);
+            gfx.path()
+                .at(self.thumb)
+                .circle(radius * 0.45)
+                .fill_color(color);
+        });
+    }
+}
+
+/// Rescales `offset` (up to 1 long) so the dead zone's edge reads as zero
+fn deflection(offset: Vec2, dead_zone: f32) -> Vec2 {
+    let length = offset.length();
+    if length <= dead_zone {
+        return Vec2::ZERO;
+    }
+    offset / length * ((length - dead_zone) / (1.0 - dead_zone)).min(1.0)
+}
+
+/// A button pressed by a finger (or the mouse) going down in its rect, with the same
+/// pressed/held/released edges as keys. It stays held until that finger is lifted, even
+/// if it slides off
+#[derive(Clone, Debug, PartialEq)]
+pub struct VirtualButton {
+    pub rect: Rect,
+    /// Drawn over the button, stretched to its rect
+    pub icon: Option<TextureId>,
+    pointer: Option<Pointer>,
+    pressed: bool,
+    released: bool,
+}
+
+impl VirtualButton {
+    pub fn new(rect: Rect, icon: Option<TextureId>) -> Self {
+        Self {
+            rect,
+            icon,
+            pointer: None,
+            pressed: false,
+            released: false,
+        }
+    }
+
+    /// Follows the pointer holding the button, or grabs a new one pressed in its rect
+    pub fn update(&mut self, input: &Input) {
+        self.pressed = false;
+        self.released = false;
+        if self.pointer.is_none()
+            && let Some((pointer, _)) = grab(input, |p| self.rect.contains(p))
+        {
+            self.pointer = Some(pointer);
+            self.pressed = true;
+        }
+        if let Some(pointer) = self.pointer
+            && !pointer_state(input, pointer).is_some_and(|(_, held)| held)
+        {
+            self.pointer = None;
+            self.released = true;
+        }
+    }
+
+    /// True if the button went down this frame
+    pub fn pressed(&self) -> bool {
+        self.pressed
+    }
+
+    /// True while the button is down
+    pub fn held(&self) -> bool {
+        self.pointer.is_some()
+    }
+
+    /// True if the button went up this frame. A quick tap is pressed & released in the same
+    /// frame
+    pub fn released(&self) -> bool {
+        self.released
+    }
+
+    /// Draws the button & its icon in screen space
+    pub fn draw(&self, gfx: &mut Graphics) {
+        let radius = self.rect.size.min_element() * 0.5;
+        let color = if self.held() { ACTIVE } else { IDLE };
+        gfx.layer(LAYER).screen_space(true);
+        gfx.on_layer(LAYER, |gfx| {
+            gfx.path()
+                .at(self.rect.center())
+                .circle(radius)
+                .fill_color(color);
+            if let Some(icon) = self.icon {
+                gfx.rect().with(&self.rect).texture(icon);
+            }
+        });
+    }
+}
+
+/// Where the stick & buttons go for the screen's size & orientation. Sizes are in logical
+/// pixels, multiplied by the UI scale ([`crate::app::FrameContext::ui_scale`]) so controls
+/// stay thumb sized on dense screens, & shrunk when a portrait screen is too narrow
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TouchControlsLayout {
+    /// Side of the joystick's square area
+    pub stick_size: f32,
+    /// Side of a button
+    pub button_size: f32,
+    /// Space to the screen's edges & between buttons
+    pub margin: f32,
+    /// Fraction of the height controls are raised by in portrait, closer to resting thumbs
+    pub portrait_lift: f32,
+}
+
+impl Default for TouchControlsLayout {
+    fn default() -> Self {
+        Self {
+            stick_size: 180.0,
+            button_size: 72.0,
+            margin: 24.0,
+            portrait_lift: 0.1,
+        }
+    }
+}
+
+impl TouchControlsLayout {
+    /// Pixels per logical pixel, scaled down until a stick & two buttons fit across
+    fn unit(&self, screen: Vec2, scale: f32) -> f32 {
+        let needed = self.stick_size + self.button_size * 2.0 + self.margin * 4.0;
+        scale.min(screen.x / needed).max(0.0)
+    }
+
+    /// Distance from the bottom edge to the controls' bottom
+    fn bottom(&self, screen: Vec2, unit: f32) -> f32 {
+        let lift = match screen.y > screen.x {
+            true => screen.y * self.portrait_lift,
+            false => 0.0,
+        };
+        self.margin * unit + lift
+    }
+
+    /// The joystick's area, in the bottom-left corner
+    pub fn joystick(&self, screen: Vec2, scale: f32) -> Rect {
+        let unit = self.unit(screen, scale);
+        let size = self.stick_size * unit;
+        let position = vec2(
+            self.margin * unit,
+            screen.y - self.bottom(screen, unit) - size,
+        );
+        Rect::new(position, Vec2::splat(size))
+    }
+
+    /// The `index`th button from the bottom-right corner: stacked upwards in landscape, in
+    /// a row going left in portrait
+    pub fn button(&self, index: usize, screen: Vec2, scale: f32) -> Rect {
+        let unit = self.unit(screen, scale);
+        let (size, step) = (
+            self.button_size * unit,
+            (self.button_size + self.margin) * unit,
+        );
+        let corner = vec2(
+            screen.x - self.margin * unit - size,
+            screen.y - self.bottom(screen, unit) - size,
+        );
+        let offset = match screen.y > screen.x {
+            true => vec2(-step, 0.0),
+            false => vec2(0.0, -step),
+        };
+        Rect::new(corner + offset * index as f32, Vec2::splat(size))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use egor_app::input::{InputEvent, TouchPhase};
+
+    fn touch(input: &mut Input, id: u64, phase: TouchPhase, (x, y): (f64, f64)) {
+        input.apply(InputEvent::Touch { id, phase, x, y }, 0.0);
+    }
+
+    #[test]
+    fn the_stick_follows_its_touch_with_a_dead_zone() {
+        let mut input = Input::default();
+        let mut stick =
+            VirtualJoystick::new(Rect::new(Vec2::ZERO, Vec2::splat(300.0))).radius(100.0);
+
+        touch(&mut input, 7, TouchPhase::Started, (100.0, 100.0));
+        // outside the stick, so left for gameplay
+        touch(&mut input, 8, TouchPhase::Started, (500.0, 100.0));
+        assert_eq!(stick.update(&input), Vec2::ZERO);
+        assert!(stick.active());
+        assert!(input.pointer_claimed(Pointer::Touch(7)));
+        assert!(!input.pointer_claimed(Pointer::Touch(8)));
+
+        input.end_frame();
+        // inside the dead zone
+        touch(&mut input, 7, TouchPhase::Moved, (110.0, 100.0));
+        assert_eq!(stick.update(&input), Vec2::ZERO);
+        // past the radius clamps to full deflection
+        touch(&mut input, 7, TouchPhase::Moved, (100.0, 400.0));
+        assert_eq!(stick.update(&input), vec2(0.0, 1.0));
+
+        touch(&mut input, 7, TouchPhase::Ended, (100.0, 400.0));
+        assert_eq!(stick.update(&input), Vec2::ZERO);
+        assert!(!stick.active());
+        assert!(deflection(vec2(0.575, 0.0), 0.15).abs_diff_eq(vec2(0.5, 0.0), 1e-6));
+    }
+
+    #[test]
+    fn buttons_have_key_like_edges() {
+        let mut input = Input::default();
+        let mut button = VirtualButton::new(Rect::new(Vec2::ZERO, Vec2::splat(50.0)), None);
+        let mut other = VirtualButton::new(Rect::new(Vec2::ZERO, Vec2::splat(50.0)), None);
+
+        touch(&mut input, 1, TouchPhase::Started, (10.0, 10.0));
+        button.update(&input);
+        // the touch is claimed, so overlapping controls don't both take it
+        other.update(&input);
+        assert!(button.pressed() && button.held() && !button.released());
+        assert!(!other.held());
+
+        input.end_frame();
+        // sliding off keeps it held
+        touch(&mut input, 1, TouchPhase::Moved, (90.0, 10.0));
+        button.update(&input);
+        assert!(!button.pressed() && button.held());
+
+        touch(&mut input, 1, TouchPhase::Ended, (90.0, 10.0));
+        button.update(&input);
+        assert!(button.released() && !button.held());
+
+        // a tap within one frame
+        input.end_frame();
+        touch(&mut input, 2, TouchPhase::Started, (10.0, 10.0));
+        touch(&mut input, 2, TouchPhase::Ended, (10.0, 10.0));
+        button.update(&input);
+        assert!(button.pressed() && button.released() && !button.held());
+    }
+
+    #[test]
+    fn layouts_follow_the_orientation_and_scale() {
+        let layout = TouchControlsLayout::default();
+        let landscape = vec2(1600.0, 900.0);
+        assert_eq!(
+            layout.joystick(landscape, 2.0),
+            Rect::new(vec2(48.0, 900.0 - 48.0 - 360.0), Vec2::splat(360.0))
+        );
+        let (first, second) = (
+            layout.button(0, landscape, 2.0),
+            layout.button(1, landscape, 2.0),
+        );
+        assert_eq!(first.max(), vec2(1600.0 - 48.0, 900.0 - 48.0));
+        assert_eq!(second.position, first.position - vec2(0.0, 192.0));
+
+        // too narrow for scale 3, so shrunk to fit, raised & with buttons in a row
+        let portrait = vec2(420.0, 900.0);
+        let stick = layout.joystick(portrait, 3.0);
+        let first = layout.button(0, portrait, 3.0);
+        let second = layout.button(1, portrait, 3.0);
+        assert!(stick.max().x < second.position.x);
+        assert!(first.max().x <= 420.0);
+        assert_eq!(second.position.y, first.position.y);
+        assert!(stick.max().y < 900.0 - 90.0);
+    }
+}
@@ -0,0 +1,412 @@
+use glam::Vec2;
+use std::f32::consts::PI;
+
+use crate::color::Color;
+
+/// Values a [`Tween`] can interpolate
+pub trait Lerp: Copy {
+    /// Value `t` of the way from `self` to `to`. Eases like [`Ease::OutBack`] pass `t`
+    /// outside `0..1` to overshoot
+    fn lerp(self, to: Self, t: f32) -> Self;
+}
+
+impl Lerp for f32 {
+    fn lerp(self, to: Self, t: f32) -> Self {
+        self + (to - self) * t
+    }
+}
+
+impl Lerp for Vec2 {
+    fn lerp(self, to: Self, t: f32) -> Self {
+        Vec2::lerp(self, to, t)
+    }
+}
+
+/// Components are interpolated in linear RGB, the space [`Color`] stores them in
+impl Lerp for Color {
+    fn lerp(self, to: Self, t: f32) -> Self {
+        let (from, to) = (self.components(), to.components());
+        Color::new(std::array::from_fn(|i| from[i].lerp(to[i], t)))
+    }
+}
+
+/// Easing curves, see <https://easings.net> for how each one looks
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Ease {
+    #[default]
+    Linear,
+    InQuad,
+    OutQuad,
+    InOutQuad,
+    InCubic,
+    OutCubic,
+    InOutCubic,
+    InExpo,
+    OutExpo,
+    InOutExpo,
+    /// Pulls back below 0 before heading to 1
+    InBack,
+    /// Overshoots 1 before settling
+    OutBack,
+    InOutBack,
+    /// Wobbles around 0 before snapping to 1
+    InElastic,
+    /// Snaps past 1 & wobbles into place
+    OutElastic,
+    InOutElastic,
+}
+
+impl Ease {
+    /// Eased progress for linear progress `t` in `0..=1`. Starts at 0 & ends at 1
+    pub fn apply(self, t: f32) -> f32 {
+        const BACK: f32 = 1.70158;
+        const IN_OUT_BACK: f32 = BACK * 1.525;
+        const ELASTIC: f32 = 2.0 * PI / 3.0;
+        const IN_OUT_ELASTIC: f32 = 2.0 * PI / 4.5;
+
+        let t = t.clamp(0.0, 1.0);
+        // exact ends, the exponential curves only approach them
+        if t == 0.0 || t == 1.0 {
+            return t;
+        }
+        match self {
+            Self::Linear => t,
+            Self::InQuad => t * t,
+            Self::OutQuad => 1.0 - (1.0 - t).powi(2),
+            Self::InOutQuad if t < 0.5 => 2.0 * t * t,
+            Self::InOutQuad => 1.0 - (-2.0 * t + 2.0).powi(2) / 2.0,
+            Self::InCubic => t.powi(3),
+            Self::OutCubic => 1.0 - (1.0 - t).powi(3),
+            Self::InOutCubic if t < 0.5 => 4.0 * t.powi(3),
+            Self::InOutCubic => 1.0 - (-2.0 * t + 2.0).powi(3) / 2.0,
+            Self::InExpo => 2f32.powf(10.0 * t - 10.0),
+            Self::OutExpo => 1.0 - 2f32.powf(-10.0 * t),
+            Self::InOutExpo if t < 0.5 => 2f32.powf(20.0 * t - 10.0) / 2.0,
+            Self::InOutExpo => (2.0 - 2f32.powf(-20.0 * t + 10.0)) / 2.0,
+            Self::InBack => (BACK + 1.0) * t.powi(3) - BACK * t * t,
+            Self::OutBack => 1.0 + (BACK + 1.0) * (t - 1.0).powi(3) + BACK * (t - 1.0).powi(2),
+            Self::InOutBack if t < 0.5 => {
+                (2.0 * t).powi(2) * ((IN_OUT_BACK + 1.0) * 2.0 * t - IN_OUT_BACK) / 2.0
+            }
+            Self::InOutBack => {
+                let u = 2.0 * t - 2.0;
+                (u * u * ((IN_OUT_BACK + 1.0) * u + IN_OUT_BACK) + 2.0) / 2.0
+            }
+            Self::InElastic => -(2f32.powf(10.0 * t - 10.0)) * ((10.0 * t - 10.75) * ELASTIC).sin(),
+            Self::OutElastic => 2f32.powf(-10.0 * t) * ((10.0 * t - 0.75) * ELASTIC).sin() + 1.0,
+            Self::InOutElastic => {
+                let wave = ((20.0 * t - 11.125) * IN_OUT_ELASTIC).sin();
+                if t < 0.5 {
+                    -(2f32.powf(20.0 * t - 10.0) * wave) / 2.0
+                } else {
+                    2f32.powf(-20.0 * t + 10.0) * wave / 2.0 + 1.0
+                }
+            }
+        }
+    }
+}
+
+/// Eases a value from `from` to `to` over `duration` seconds, advanced with
+/// [`Self::update`] each frame
+///
+/// ```
+/// # use egor_glue::tween::{Ease, Tween};
+/// let mut zoom = Tween::new(1.0, 2.0, 0.5, Ease::OutCubic);
+/// while !zoom.finished() {
+///     let _zoom = zoom.update(1.0 / 60.0);
+/// }
+/// assert_eq!(zoom.value(), 2.0);
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct Tween<T> {
+    from: T,
+    to: T,
+    duration: f32,
+    elapsed: f32,
+    ease: Ease,
+}
+
+impl<T: Lerp> Tween<T> {
+    pub fn new(from: T, to: T, duration: f32, ease: Ease) -> Self {
+        Self {
+            from,
+            to,
+            duration,
+            elapsed: 0.0,
+            ease,
+        }
+    }
+
+    /// Advance by `dt` seconds (e.g. [`egor_app::time::FrameTimer::delta`]) & return the
+    /// current value
+    pub fn update(&mut self, dt: f32) -> T {
+        self.elapsed = (self.elapsed + dt).min(self.duration);
+        self.value()
+    }
+
+    /// Current value, exactly the target once finished
+    pub fn value(&self) -> T {
+        if self.finished() {
+            return self.to;
+        }
+        self.from.lerp(self.to, self.ease.apply(self.progress()))
+    }
+
+    /// Linear progress in `0..=1`, before easing
+    pub fn progress(&self) -> f32 {
+        if self.duration > 0.0 {
+            self.elapsed / self.duration
+        } else {
+            1.0
+        }
+    }
+
+    pub fn finished(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+
+    /// The value this tween ends at
+    pub fn target(&self) -> T {
+        self.to
+    }
+}
+
+/// Value owned by [`Tweens`]. Handles of removed values stay stale when their slot is
+/// reused, so they can't reach the value added in their place
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TweenHandle {
+    index: usize,
+    generation: u32,
+}
+
+struct Slot<T> {
+    value: T,
+    tween: Option<Tween<T>>,
+}
+
+/// A slot & how many values it has held, see [`TweenHandle`]
+struct Entry<T> {
+    generation: u32,
+    slot: Option<Slot<T>>,
+}
+
+/// Owns values of one type & the tweens moving them, so animations can be fired & forgotten.
+/// Call [`Self::update`] once per frame, then read values through their handles
+///
+/// ```
+/// # use egor_glue::tween::{Ease, Tweens};
+/// let mut tweens = Tweens::new();
+/// let scale = tweens.add(1.0);
+/// // on hover
+/// tweens.go(scale, 1.2, 0.15, Ease::OutBack);
+/// // every frame
+/// tweens.update(1.0 / 60.0);
+/// assert!(tweens.get(scale) > 1.0);
+/// ```
+pub struct Tweens<T> {
+    entries: Vec<Entry<T>>,
+}
+
+impl<T> Default for Tweens<T> {
+    fn default() -> Self {
+        Self {
+            entries: Vec::new(),
+        }
+    }
+}
+
+impl<T: Lerp> Tweens<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start owning `value`, returning its handle
+    pub fn add(&mut self, value: T) -> TweenHandle {
+        let slot = Some(Slot { value, tween: None });
+        let index = match self.entries.iter().position(|e| e.slot.is_none()) {
+            Some(index) => index,
+            None => {
+                self.entries.push(Entry {
+                    generation: 0,
+                    slot: None,
+                });
+                self.entries.len() - 1
+            }
+        };
+        let entry = &mut self.entries[index];
+        entry.slot = slot;
+        TweenHandle {
+            index,
+            generation: entry.generation,
+        }
+    }
+
+    /// Drop a value. Its slot may be reused by [`Self::add`], but not its handle.
+    /// Removing a stale handle does nothing
+    pub fn remove(&mut self, handle: TweenHandle) {
+        if let Some(entry) = self.entries.get_mut(handle.index)
+            && entry.generation == handle.generation
+            && entry.slot.is_some()
+        {
+            entry.slot = None;
+            entry.generation = entry.generation.wrapping_add(1);
+        }
+    }
+
+    /// Tween the value to `to` over `duration` seconds, starting from where it is now.
+    /// Replaces any tween already running on it
+    pub fn go(&mut self, handle: TweenHandle, to: T, duration: f32, ease: Ease) {
+        let slot = self.slot_mut(handle);
+        slot.tween = Some(Tween::new(slot.value, to, duration, ease));
+    }
+
+    /// Jump the value to `value`, stopping its tween
+    pub fn set(&mut self, handle: TweenHandle, value: T) {
+        let slot = self.slot_mut(handle);
+        slot.value = value;
+        slot.tween = None;
+    }
+
+    /// Current value, as of the last [`Self::update`]
+    ///
+    /// # Panics
+    /// If the handle was removed, like every method taking a handle but [`Self::remove`]
+    pub fn get(&self, handle: TweenHandle) -> T {
+        self.slot(handle).value
+    }
+
+    /// Whether the value is still on its way to a target
+    pub fn is_tweening(&self, handle: TweenHandle) -> bool {
+        self.slot(handle).tween.is_some()
+    }
+
+    /// Advance every running tween by `dt` seconds, dropping the finished ones
+    pub fn update(&mut self, dt: f32) {
+        for slot in self.entries.iter_mut().filter_map(|e| e.slot.as_mut()) {
+            if let Some(tween) = &mut slot.tween {
+                slot.value = tween.update(dt);
+                if tween.finished() {
+                    slot.tween = None;
+                }
+            }
+        }
+    }
+
+    fn slot(&self, handle: TweenHandle) -> &Slot<T> {
+        let entry = &self.entries[handle.index];
+        entry
+            .slot
+            .as_ref()
+            .filter(|_| entry.generation == handle.generation)
+            .expect("tween handle was removed")
+    }
+
+    fn slot_mut(&mut self, handle: TweenHandle) -> &mut Slot<T> {
+        let entry = &mut self.entries[handle.index];
+        entry
+            .slot
+            .as_mut()
+            .filter(|_| entry.generation == handle.generation)
+            .expect("tween handle was removed")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EASES: [Ease; 16] = [
+        Ease::Linear,
+        Ease::InQuad,
+        Ease::OutQuad,
+        Ease::InOutQuad,
+        Ease::InCubic,
+        Ease::OutCubic,
+        Ease::InOutCubic,
+        Ease::InExpo,
+        Ease::OutExpo,
+        Ease::InOutExpo,
+        Ease::InBack,
+        Ease::OutBack,
+        Ease::InOutBack,
+        Ease::InElastic,
+        Ease::OutElastic,
+        Ease::InOutElastic,
+    ];
+
+    #[test]
+    fn eases_start_at_zero_and_end_at_one() {
+        for ease in EASES {
+            assert_eq!(ease.apply(0.0), 0.0, "{ease:?}");
+            assert_eq!(ease.apply(1.0), 1.0, "{ease:?}");
+            // the halves of in-out curves meet
+            let (below, above) = (ease.apply(0.5 - 1e-4), ease.apply(0.5 + 1e-4));
+            assert!((below - above).abs() < 1e-2, "{ease:?} jumps at 0.5");
+        }
+    }
+
+    #[test]
+    fn non_overshooting_eases_only_rise() {
+        let overshooting = [
+            Ease::InBack,
+            Ease::OutBack,
+            Ease::InOutBack,
+            Ease::InElastic,
+            Ease::OutElastic,
+            Ease::InOutElastic,
+        ];
+        for ease in EASES {
+            let samples: Vec<_> = (0..=100).map(|i| ease.apply(i as f32 / 100.0)).collect();
+            let rises = samples.windows(2).all(|w| w[1] >= w[0]);
+            let in_range = samples.iter().all(|v| (0.0..=1.0).contains(v));
+            if overshooting.contains(&ease) {
+                assert!(!in_range, "{ease:?} should overshoot");
+            } else {
+                assert!(rises && in_range, "{ease:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn tweens_retarget_from_where_they_are() {
+        let mut tweens = Tweens::new();
+        let x = tweens.add(Vec2::ZERO);
+        tweens.go(x, Vec2::new(10.0, 0.0), 1.0, Ease::Linear);
+        tweens.update(0.5);
+        assert_eq!(tweens.get(x), Vec2::new(5.0, 0.0));
+
+        tweens.go(x, Vec2::new(5.0, 10.0), 0.25, Ease::OutCubic);
+        tweens.update(0.2);
+        assert!(tweens.is_tweening(x));
+        tweens.update(0.2);
+        assert!(!tweens.is_tweening(x));
+        assert_eq!(tweens.get(x), Vec2::new(5.0, 10.0));
+
+        let half = Color::BLACK.lerp(Color::WHITE, 0.5);
+        assert_eq!(half.components(), [0.5, 0.5, 0.5, 1.0]);
+    }
+
+    #[test]
+    fn removed_handles_stay_stale() {
+        let mut tweens = Tweens::new();
+        let x = tweens.add(1.0);
+        tweens.remove(x);
+        let y = tweens.add(2.0);
+        assert_ne!(x, y);
+        assert_eq!(tweens.entries.len(), 1, "the slot is reused");
+
+        // removing the old handle again leaves the new value alone
+        tweens.remove(x);
+        assert_eq!(tweens.get(y), 2.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "tween handle was removed")]
+    fn stale_handles_panic() {
+        let mut tweens = Tweens::new();
+        let x = tweens.add(1.0);
+        tweens.remove(x);
+        tweens.add(2.0);
+        tweens.get(x);
+    }
+}
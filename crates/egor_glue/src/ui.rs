@@ -1,9 +1,14 @@
 pub use egui;
 
-use egui::{ClippedPrimitive, Context, TexturesDelta};
+use std::{collections::HashMap, time::Duration};
+
+use egui::{
+    ClippedPrimitive, Context, FullOutput, Pos2, RawInput, Rect, TextureId, TexturesDelta,
+    ViewportId, vec2,
+};
 use egui_wgpu::ScreenDescriptor;
 use egui_wgpu::wgpu::{
-    CommandEncoder, Device, LoadOp, Operations, Queue, RenderPassColorAttachment,
+    CommandEncoder, Device, FilterMode, LoadOp, Operations, Queue, RenderPassColorAttachment,
     RenderPassDescriptor, StoreOp, TextureFormat, TextureView,
 };
 use egui_winit::State;
@@ -13,12 +18,23 @@ pub struct EguiFrame {
     pub clipped_primitives: Vec<ClippedPrimitive>,
     pub textures_delta: TexturesDelta,
     pub pixels_per_point: f32,
+    /// When egui wants to be drawn again, e.g. for an animation or a tooltip delay
+    pub repaint_delay: Option<Duration>,
+}
+
+/// Where a natively registered egui texture samples from
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum NativeTextureSource {
+    Offscreen(u64),
+    Texture(usize),
 }
 
 pub struct EguiRenderer {
     pub ctx: Context,
-    state: egui_winit::State,
+    // absent when running headless, where there is no window to take input from
+    state: Option<egui_winit::State>,
     renderer: egui_wgpu::Renderer,
+    native_textures: HashMap<NativeTextureSource, (TextureId, TextureView)>,
 }
 
 impl EguiRenderer {
@@ -26,6 +42,20 @@ impl EguiRenderer {
         let ctx = Context::default();
         let viewport_id = ctx.viewport_id();
         let state = State::new(ctx.clone(), viewport_id, window, None, None, None);
+        Self::with_state(ctx, Some(state), device, surface_format)
+    }
+
+    /// Create a renderer with no window attached; input is limited to the screen size
+    pub fn headless(device: &Device, surface_format: TextureFormat) -> Self {
+        Self::with_state(Context::default(), None, device, surface_format)
+    }
+
+    fn with_state(
+        ctx: Context,
+        state: Option<State>,
+        device: &Device,
+        surface_format: TextureFormat,
+    ) -> Self {
         let renderer =
             egui_wgpu::Renderer::new(device, surface_format, Default::default(), 1, false);
 
@@ -33,28 +63,124 @@ impl EguiRenderer {
             ctx,
             state,
             renderer,
+            native_textures: HashMap::new(),
+        }
+    }
+
+    /// Returns the egui id for `view`, registering it on first use.
+    /// If the source's view changed (e.g. an offscreen target was resized),
+    /// the existing id is pointed at the new view
+    pub(crate) fn native_texture(
+        &mut self,
+        device: &Device,
+        source: NativeTextureSource,
+        view: &TextureView,
+    ) -> TextureId {
+        if let Some((id, registered)) = self.native_textures.get_mut(&source) {
+            if registered != view {
+                self.renderer.update_egui_texture_from_wgpu_texture(
+                    device,
+                    view,
+                    FilterMode::Linear,
+                    *id,
+                );
+                *registered = view.clone();
+            }
+            return *id;
+        }
+
+        let id = self
+            .renderer
+            .register_native_texture(device, view, FilterMode::Linear);
+        self.native_textures.insert(source, (id, view.clone()));
+        id
+    }
+
+    /// Unregisters a texture previously returned by [`Self::native_texture`]
+    pub(crate) fn free_native_texture(&mut self, id: TextureId) {
+        let before = self.native_textures.len();
+        self.native_textures.retain(|_, (tid, _)| *tid != id);
+        if self.native_textures.len() != before {
+            self.renderer.free_texture(&id);
         }
     }
 
     pub fn handle_event(&mut self, window: &Window, event: &WindowEvent) -> bool {
-        self.state.on_window_event(window, event).consumed
+        self.state
+            .as_mut()
+            .is_some_and(|s| s.on_window_event(window, event).consumed)
     }
 
     pub fn begin_frame(&mut self, window: &Window) -> &Context {
-        let raw_input = self.state.take_egui_input(window);
+        let raw_input = match self.state.as_mut() {
+            Some(state) => state.take_egui_input(window),
+            None => Default::default(),
+        };
+        self.ctx.begin_pass(raw_input);
+        &self.ctx
+    }
+
+    /// Begin a frame without a window, covering `width` x `height` pixels
+    pub fn begin_headless_frame(&mut self, width: u32, height: u32, delta: f32) -> &Context {
+        let raw_input = RawInput {
+            screen_rect: Some(Rect::from_min_size(
+                Pos2::ZERO,
+                vec2(width as f32, height as f32),
+            )),
+            predicted_dt: delta,
+            ..Default::default()
+        };
         self.ctx.begin_pass(raw_input);
         &self.ctx
     }
 
     pub fn end_frame(&mut self, window: &Window) -> EguiFrame {
+        let mut output = self.ctx.end_pass();
+        if let Some(state) = self.state.as_mut() {
+            let platform_output = std::mem::take(&mut output.platform_output);
+            state.handle_platform_output(window, platform_output);
+        }
+        self.finish(output)
+    }
+
+    /// End a frame started with [`Self::begin_headless_frame`]
+    pub fn end_headless_frame(&mut self) -> EguiFrame {
         let output = self.ctx.end_pass();
-        self.state
-            .handle_platform_output(window, output.platform_output);
+        self.finish(output)
+    }
+
+    /// [`Self::begin_frame`] for `window`, or [`Self::begin_headless_frame`] without one
+    pub(crate) fn begin_any_frame(
+        &mut self,
+        window: Option<&Window>,
+        (width, height): (u32, u32),
+        delta: f32,
+    ) -> &Context {
+        match window {
+            Some(window) => self.begin_frame(window),
+            None => self.begin_headless_frame(width, height, delta),
+        }
+    }
+
+    /// [`Self::end_frame`] for `window`, or [`Self::end_headless_frame`] without one
+    pub(crate) fn end_any_frame(&mut self, window: Option<&Window>) -> EguiFrame {
+        match window {
+            Some(window) => self.end_frame(window),
+            None => self.end_headless_frame(),
+        }
+    }
 
+    fn finish(&self, output: FullOutput) -> EguiFrame {
         EguiFrame {
             clipped_primitives: self.ctx.tessellate(output.shapes, output.pixels_per_point),
             textures_delta: output.textures_delta,
             pixels_per_point: output.pixels_per_point,
+            // egui uses `Duration::MAX` for no repaint needed
+            repaint_delay: output
+                .viewport_output
+                .get(&ViewportId::ROOT)
+                .map(|viewport| viewport.repaint_delay)
+                .filter(|&delay| delay != Duration::MAX),
         }
     }
 
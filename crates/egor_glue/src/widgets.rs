@@ -0,0 +1,516 @@
+use egor_app::input::{Input, KeyCode, MouseButton};
+use glam::{Vec2, vec2};
+use std::{
+    fmt::Write,
+    hash::{DefaultHasher, Hash, Hasher},
+    ops::RangeInclusive,
+};
+
+use crate::{color::Color, graphics::Graphics, math::Rect, primitives::BorderRadii, text::Align};
+
+/// Screen-space layer widgets are drawn to
+const LAYER: &str = "egor_widgets";
+/// Keyboard presses a slider's range is split into
+const SLIDER_STEPS: f32 = 20.0;
+
+/// Colors & spacing of [`Widgets`]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WidgetTheme {
+    pub text: Color,
+    /// Fill of idle buttons, checkboxes & slider tracks
+    pub background: Color,
+    pub hovered: Color,
+    pub pressed: Color,
+    /// Checkbox marks & filled part of sliders
+    pub accent: Color,
+    /// Ring around the widget with keyboard focus
+    pub focus: Color,
+    pub font_size: f32,
+    /// Space between a checkbox's box & its mark or label, in pixels
+    pub padding: f32,
+    pub corner_radius: f32,
+    pub focus_width: f32,
+}
+
+impl Default for WidgetTheme {
+    fn default() -> Self {
+        Self {
+            text: Color::WHITE,
+            background: Color::new([0.2, 0.22, 0.27, 1.0]),
+            hovered: Color::new([0.28, 0.31, 0.38, 1.0]),
+            pressed: Color::new([0.14, 0.15, 0.19, 1.0]),
+            accent: Color::new([0.33, 0.6, 0.95, 1.0]),
+            focus: Color::new([0.95, 0.8, 0.3, 1.0]),
+            font_size: 18.0,
+            padding: 4.0,
+            corner_radius: 4.0,
+            focus_width: 2.0,
+        }
+    }
+}
+
+/// Keyboard & mouse state widgets react to in a frame
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) struct WidgetInput {
+    /// Tab, or -1 with Shift held
+    tab: i8,
+    /// Down/up arrows
+    vertical: i8,
+    /// Right/left arrows, stepping sliders or moving focus on other widgets
+    horizontal: i8,
+    /// Enter or Space
+    activate: bool,
+    /// Escape
+    cancel: bool,
+    mouse: Vec2,
+    mouse_pressed: bool,
+    mouse_held: bool,
+    mouse_released: bool,
+}
+
+impl WidgetInput {
+    pub fn new(input: &Input) -> Self {
+        let axis = |neg, pos| input.key_pressed(pos) as i8 - input.key_pressed(neg) as i8;
+        let shift = input.keys_held(&[KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+        let tab = match input.key_pressed(KeyCode::Tab) {
+            true if shift => -1,
+            true => 1,
+            false => 0,
+        };
+        Self {
+            tab,
+            vertical: axis(KeyCode::ArrowUp, KeyCode::ArrowDown),
+            horizontal: axis(KeyCode::ArrowLeft, KeyCode::ArrowRight),
+            activate: input.keys_pressed(&[KeyCode::Enter, KeyCode::NumpadEnter, KeyCode::Space]),
+            cancel: input.key_pressed(KeyCode::Escape),
+            mouse: input.mouse_position().into(),
+            mouse_pressed: input.mouse_pressed(MouseButton::Left),
+            mouse_held: input.mouse_held(MouseButton::Left),
+            mouse_released: input.mouse_released(MouseButton::Left),
+        }
+    }
+}
+
+/// Id of the `nth` widget labeled `label` in a frame
+fn label_id(label: &str, nth: usize) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    (label, nth).hash(&mut hasher);
+    hasher.finish()
+}
+
+/// A widget that can take keyboard focus, in the order it was drawn
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Focusable {
+    id: u64,
+    /// Whether left/right change its value instead of moving focus
+    adjustable: bool,
+}
+
+/// What the mouse & keyboard did to a widget this frame
+#[derive(Clone, Copy, Debug, Default)]
+struct Interaction {
+    hovered: bool,
+    /// Pressed on this widget & still held
+    held: bool,
+    focused: bool,
+    /// Clicked, or activated with the keyboard while focused
+    activated: bool,
+    /// Left/right presses while focused
+    step: i8,
+}
+
+/// Widget state kept across frames: focus, the widget the mouse is pressed on & the order
+/// widgets were drawn in to move focus through
+#[derive(Default)]
+pub(crate) struct WidgetStore {
+    theme: WidgetTheme,
+    input: WidgetInput,
+    /// Focusable widgets of the previous frame, & of this frame so far
+    order: Vec<Focusable>,
+    drawn: Vec<Focusable>,
+    focused: Option<u64>,
+    active: Option<u64>,
+    /// Reused for text the widgets format, e.g. a slider's value
+    text: String,
+}
+
+impl WidgetStore {
+    /// Moves focus with this frame's keys through the widgets drawn last frame. Only Tab
+    /// gives a widget focus, so games keep the arrows & Space until it does
+    pub fn begin_frame(&mut self, input: WidgetInput) {
+        std::mem::swap(&mut self.order, &mut self.drawn);
+        self.drawn.clear();
+        self.input = input;
+        if !input.mouse_held && !input.mouse_released {
+            self.active = None;
+        }
+
+        let focused = self
+            .focused
+            .and_then(|id| self.order.iter().position(|f| f.id == id));
+        if self.focused.is_some() && focused.is_none() {
+            // the focused widget wasn't drawn last frame
+            self.focused = None;
+        }
+        if input.cancel {
+            self.focused = None;
+            return;
+        }
+        let adjustable = focused.is_some_and(|i| self.order[i].adjustable);
+        let horizontal = if adjustable { 0 } else { input.horizontal };
+        let offset = [input.tab, input.vertical, horizontal]
+            .into_iter()
+            .find(|&o| o != 0);
+        if let (Some(offset), false) = (offset, self.order.is_empty()) {
+            let len = self.order.len() as isize;
+            let next = match focused {
+                Some(i) => (i as isize + offset as isize).rem_euclid(len),
+                None if input.tab > 0 => 0,
+                None if input.tab < 0 => len - 1,
+                None => return,
+            };
+            self.focused = Some(self.order[next as usize].id);
+        }
+    }
+
+    /// An id for `label` unique among this frame's widgets, repeated labels told apart by
+    /// how many came before
+    fn id(&self, label: &str) -> u64 {
+        (0..)
+            .map(|nth| label_id(label, nth))
+            .find(|id| !self.drawn.iter().any(|f| f.id == *id))
+            .unwrap()
+    }
+
+    fn interact(&mut self, id: u64, rect: Rect, adjustable: bool) -> Interaction {
+        self.drawn.push(Focusable { id, adjustable });
+        let input = self.input;
+        let hovered = rect.contains(input.mouse);
+        if hovered && input.mouse_pressed {
+            self.active = Some(id);
+            self.focused = Some(id);
+        }
+        let active = self.active == Some(id);
+        let focused = self.focused == Some(id);
+        Interaction {
+            hovered,
+            held: active && input.mouse_held,
+            focused,
+            activated: (active && hovered && input.mouse_released) || (focused && input.activate),
+            step: if focused { input.horizontal } else { 0 },
+        }
+    }
+}
+
+/// A small immediate-mode UI drawn with egor's own shapes & text, for menus & settings
+/// without egui. Get it with [`Graphics::ui`]. Rects are in screen pixels with `(0, 0)` at
+/// the top-left, whatever the camera
+///
+/// Widgets are told apart by their labels. Tab & Shift+Tab move a focus ring through them
+/// in the order they're drawn, as do the arrow keys once one has focus. Enter or Space
+/// presses the focused one & left/right step a focused slider
+///
+/// ```no_run
+/// # use egor_glue::{graphics::Graphics, math::Rect};
+/// # use glam::vec2;
+/// # fn f(gfx: &mut Graphics, muted: &mut bool, volume: &mut f32) {
+/// let mut ui = gfx.ui();
+/// ui.label("Settings", Rect::new(vec2(20.0, 20.0), vec2(200.0, 30.0)));
+/// ui.checkbox("Mute", muted, Rect::new(vec2(20.0, 60.0), vec2(200.0, 30.0)));
+/// ui.slider("Volume", volume, 0.0..=1.0, Rect::new(vec2(20.0, 100.0), vec2(200.0, 30.0)));
+/// if ui.button("Quit", Rect::new(vec2(20.0, 140.0), vec2(200.0, 30.0))) {
+///     // ...
+/// }
+/// # }
+/// ```
+///
+/// Widgets are drawn to their own screen-space layer, after unlisted layers (see
+/// [`Graphics::set_layer_order`]); their text is drawn on top of everything like all text.
+/// Without widget state (e.g. in [`Graphics::render_offscreen`]) they're drawn idle
+pub struct Widgets<'g, 'a> {
+    gfx: &'g mut Graphics<'a>,
+}
+
+impl<'g, 'a> Widgets<'g, 'a> {
+    pub(crate) fn new(gfx: &'g mut Graphics<'a>) -> Self {
+        gfx.layer(LAYER).screen_space(true);
+        Self { gfx }
+    }
+
+    pub fn theme(&mut self) -> WidgetTheme {
+        self.gfx.widget_store().map(|s| s.theme).unwrap_or_default()
+    }
+
+    /// Set the theme for every widget from now on
+    pub fn set_theme(&mut self, theme: WidgetTheme) {
+        if let Some(store) = self.gfx.widget_store() {
+            store.theme = theme;
+        }
+    }
+
+    /// Whether the (first) widget labeled `label` has keyboard focus
+    pub fn has_focus(&mut self, label: &str) -> bool {
+        let store = self.gfx.widget_store();
+        store.is_some_and(|s| s.focused == Some(label_id(label, 0)))
+    }
+
+    fn interact(&mut self, label: &str, rect: Rect, adjustable: bool) -> Interaction {
+        match self.gfx.widget_store() {
+            Some(store) => {
+                let id = store.id(label);
+                store.interact(id, rect, adjustable)
+            }
+            None => Interaction::default(),
+        }
+    }
+
+    fn panel(&mut self, rect: Rect, color: Color, radius: f32) {
+        self.gfx.on_layer(LAYER, |gfx| {
+            gfx.path()
+                .at(rect.position)
+                .round_rect(rect.size, Some(BorderRadii::new(radius)))
+                .fill_color(color);
+        });
+    }
+
+    fn focus_ring(&mut self, rect: Rect, theme: &WidgetTheme) {
+        let grow = Vec2::splat(theme.focus_width);
+        self.gfx.on_layer(LAYER, |gfx| {
+            gfx.path()
+                .at(rect.position - grow)
+                .round_rect(
+                    rect.size + grow * 2.0,
+                    Some(BorderRadii::new(theme.corner_radius + theme.focus_width)),
+                )
+                .thickness(theme.focus_width)
+                .stroke_color(theme.focus);
+        });
+    }
+
+    fn fill(theme: &WidgetTheme, interaction: Interaction) -> Color {
+        match interaction {
+            Interaction { held: true, .. } => theme.pressed,
+            Interaction { hovered: true, .. } => theme.hovered,
+            _ => theme.background,
+        }
+    }
+
+    fn text(&mut self, text: &str, rect: Rect, align: Align, theme: &WidgetTheme) {
+        self.gfx
+            .text(text)
+            .in_rect(rect, align)
+            .size(theme.font_size)
+            .color(theme.text);
+    }
+
+    /// Text in `rect`, vertically centered
+    pub fn label(&mut self, text: &str, rect: Rect) {
+        let theme = self.theme();
+        self.text(text, rect, Align::MiddleLeft, &theme);
+    }
+
+    /// A button filling `rect`. Returns true the frame it's clicked or pressed with the
+    /// keyboard
+    pub fn button(&mut self, label: &str, rect: Rect) -> bool {
+        let theme = self.theme();
+        let interaction = self.interact(label, rect, false);
+        self.panel(rect, Self::fill(&theme, interaction), theme.corner_radius);
+        if interaction.focused {
+            self.focus_ring(rect, &theme);
+        }
+        self.text(label, rect, Align::MiddleCenter, &theme);
+        interaction.activated
+    }
+
+    /// A box as tall as `rect` with `label` beside it, toggling `value`. Returns true when
+    /// `value` changed
+    pub fn checkbox(&mut self, label: &str, value: &mut bool, rect: Rect) -> bool {
+        let theme = self.theme();
+        let interaction = self.interact(label, rect, false);
+        if interaction.activated {
+            *value = !*value;
+        }
+
+        let side = rect.size.y;
+        let check = Rect::new(rect.position, Vec2::splat(side));
+        self.panel(check, Self::fill(&theme, interaction), theme.corner_radius);
+        if *value {
+            let inset = Vec2::splat(theme.padding);
+            let mark = Rect::new(check.position + inset, check.size - inset * 2.0);
+            let radius = (theme.corner_radius - theme.padding).max(0.0);
+            self.panel(mark, theme.accent, radius);
+        }
+        if interaction.focused {
+            self.focus_ring(check, &theme);
+        }
+        let text_offset = side + theme.padding * 2.0;
+        let text_rect = Rect::new(
+            rect.position + vec2(text_offset, 0.0),
+            vec2((rect.size.x - text_offset).max(0.0), side),
+        );
+        self.text(label, text_rect, Align::MiddleLeft, &theme);
+        interaction.activated
+    }
+
+    /// A horizontal slider filling `rect`, dragged with the mouse or stepped by left/right
+    /// while focused. `label` & the value are drawn over it. Returns true when `value`
+    /// changed
+    pub fn slider(
+        &mut self,
+        label: &str,
+        value: &mut f32,
+        range: RangeInclusive<f32>,
+        rect: Rect,
+    ) -> bool {
+        let theme = self.theme();
+        let interaction = self.interact(label, rect, true);
+        let (min, max) = (*range.start(), *range.end());
+        let previous = *value;
+        if interaction.held && rect.size.x > 0.0 {
+            let mouse = self.gfx.widget_store().map_or(0.0, |s| s.input.mouse.x);
+            let t = ((mouse - rect.position.x) / rect.size.x).clamp(0.0, 1.0);
+            *value = min + (max - min) * t;
+        }
+        if interaction.step != 0 {
+            let step = (max - min) / SLIDER_STEPS;
+            *value = (*value + step * interaction.step as f32).clamp(min, max);
+        }
+
+        self.panel(rect, Self::fill(&theme, interaction), theme.corner_radius);
+        let t = match max > min {
+            true => ((*value - min) / (max - min)).clamp(0.0, 1.0),
+            false => 0.0,
+        };
+        if t > 0.0 {
+            let filled = Rect::new(rect.position, vec2(rect.size.x * t, rect.size.y));
+            let radius = theme.corner_radius.min(filled.size.x * 0.5);
+            self.panel(filled, theme.accent, radius);
+        }
+        if interaction.focused {
+            self.focus_ring(rect, &theme);
+        }
+        let store = self.gfx.widget_store();
+        let mut text = store
+            .map(|s| std::mem::take(&mut s.text))
+            .unwrap_or_default();
+        text.clear();
+        let _ = write!(text, "{label}: {value:.2}");
+        self.text(&text, rect, Align::MiddleCenter, &theme);
+        if let Some(store) = self.gfx.widget_store() {
+            store.text = text;
+        }
+        *value != previous
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(store: &mut WidgetStore, input: WidgetInput, widgets: &[(&str, bool)]) {
+        store.begin_frame(input);
+        for &(label, adjustable) in widgets {
+            let id = store.id(label);
+            store.interact(id, Rect::new(Vec2::ZERO, Vec2::ZERO), adjustable);
+        }
+    }
+
+    fn focused<'w>(store: &WidgetStore, widgets: &[(&'w str, bool)]) -> Option<&'w str> {
+        let i = store
+            .order
+            .iter()
+            .position(|f| Some(f.id) == store.focused)?;
+        Some(widgets[i].0)
+    }
+
+    #[test]
+    fn focus_moves_through_widgets_in_draw_order() {
+        let widgets = [
+            ("Play", false),
+            ("Volume", true),
+            ("Mute", false),
+            ("Quit", false),
+        ];
+        let mut store = WidgetStore::default();
+        let press = |tab, vertical, horizontal| WidgetInput {
+            tab,
+            vertical,
+            horizontal,
+            ..Default::default()
+        };
+        let mut step = |input| {
+            frame(&mut store, input, &widgets);
+            // draw a frame without keys so `order` holds this frame's widgets
+            frame(&mut store, WidgetInput::default(), &widgets);
+            focused(&store, &widgets)
+        };
+
+        // nothing is focused until Tab, arrows are left to the game
+        assert_eq!(step(WidgetInput::default()), None);
+        assert_eq!(step(press(0, 1, 0)), None);
+        assert_eq!(step(press(0, 0, -1)), None);
+        assert_eq!(step(press(1, 0, 0)), Some("Play"));
+        assert_eq!(step(press(1, 0, 0)), Some("Volume"));
+        // sliders keep left/right for their value
+        assert_eq!(step(press(0, 0, 1)), Some("Volume"));
+        assert_eq!(step(press(0, 1, 0)), Some("Mute"));
+        assert_eq!(step(press(0, 0, 1)), Some("Quit"));
+        // focus wraps around both ways
+        assert_eq!(step(press(1, 0, 0)), Some("Play"));
+        assert_eq!(step(press(-1, 0, 0)), Some("Quit"));
+        assert_eq!(step(press(0, -1, 0)), Some("Mute"));
+        assert_eq!(
+            step(WidgetInput {
+                cancel: true,
+                ..Default::default()
+            }),
+            None
+        );
+        assert_eq!(step(press(-1, 0, 0)), Some("Quit"));
+    }
+
+    #[test]
+    fn focused_widgets_activate_and_step_with_keys() {
+        let widgets = [("Back", false), ("Back", false), ("Speed", true)];
+        let mut store = WidgetStore::default();
+        let rect = Rect::new(Vec2::ZERO, vec2(10.0, 10.0));
+        // repeated labels get their own ids
+        frame(&mut store, WidgetInput::default(), &widgets);
+        assert_ne!(store.drawn[0].id, store.drawn[1].id);
+
+        let tab = WidgetInput {
+            tab: 1,
+            ..Default::default()
+        };
+        frame(&mut store, tab, &widgets);
+        frame(&mut store, tab, &widgets);
+        assert_eq!(store.focused, Some(store.order[1].id));
+
+        store.begin_frame(WidgetInput {
+            activate: true,
+            ..Default::default()
+        });
+        let first = store.id("Back");
+        assert!(!store.interact(first, rect, false).activated);
+        let second = store.id("Back");
+        assert!(store.interact(second, rect, false).activated);
+        let speed = store.id("Speed");
+        assert_eq!(store.interact(speed, rect, true).step, 0);
+
+        // clicking focuses & activates on release over the widget
+        let at = |mouse_pressed, mouse_held, mouse_released| WidgetInput {
+            mouse: vec2(5.0, 5.0),
+            mouse_pressed,
+            mouse_held,
+            mouse_released,
+            ..Default::default()
+        };
+        store.begin_frame(at(true, true, false));
+        let interaction = store.interact(store.id("Speed"), rect, true);
+        assert!(interaction.held && interaction.focused && !interaction.activated);
+        store.begin_frame(at(false, false, true));
+        assert!(store.interact(store.id("Speed"), rect, true).activated);
+        store.begin_frame(WidgetInput::default());
+        assert!(!store.interact(store.id("Speed"), rect, true).held);
+    }
+}
@@ -28,12 +28,14 @@ impl ApplicationHandler for MinimalApp {
         let window = Arc::new(event_loop.create_window(Default::default()).unwrap());
         let size = window.inner_size();
 
-        let renderer = pollster::block_on(Renderer::new(window.clone(), &MemoryHints::Performance));
+        let renderer = pollster::block_on(Renderer::new(window.clone(), &MemoryHints::Performance))
+            .expect("no GPU to render with");
         let backbuffer = Backbuffer::new(
             renderer.instance(),
             renderer.adapter(),
             renderer.device(),
             window.clone(),
+            renderer.surface_format(),
             size.width,
             size.height,
         );
@@ -55,7 +57,7 @@ impl ApplicationHandler for MinimalApp {
                     let Some(backbuffer) = &mut self.backbuffer else {
                         return;
                     };
-                    let Some(mut frame) = r.begin_frame(backbuffer) else {
+                    let Ok(Some(mut frame)) = r.begin_frame(backbuffer) else {
                         return;
                     };
 
@@ -20,12 +20,14 @@ struct InstanceInput {
     @location(4) translate: vec2<f32>,
     @location(5) color: vec4<f32>,
     @location(6) uv: vec4<f32>,
+    @location(7) color_mode: u32,
 };
 
 struct VertexOutput {
     @builtin(position) position: vec4<f32>,
     @location(0) color: vec4<f32>,
     @location(1) tex_coords: vec2<f32>,
+    @location(2) @interpolate(flat) color_mode: u32,
 };
 
 @vertex
@@ -41,10 +43,25 @@ fn vs_main(vert: VertexInput, inst: InstanceInput) -> VertexOutput {
     out.position = camera.view_proj * vec4<f32>(world_pos, 0.0, 1.0);
     out.color = vert.color * inst.color;
     out.tex_coords = uv;
+    out.color_mode = inst.color_mode;
     return out;
 }
 
 @fragment
 fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
-    return textureSample(texture_binding, texture_sampler, input.tex_coords) * input.color;
+    let tex = textureSample(texture_binding, texture_sampler, input.tex_coords);
+    let alpha = tex.a * input.color.a;
+    switch input.color_mode {
+        // ColorMode::Add
+        case 1u: {
+            return vec4<f32>(tex.rgb + input.color.rgb, alpha);
+        }
+        // ColorMode::Replace
+        case 2u: {
+            return vec4<f32>(input.color.rgb, alpha);
+        }
+        default: {
+            return tex * input.color;
+        }
+    }
 }
@@ -3,14 +3,19 @@ use wgpu::{
     RenderPass,
 };
 
-use crate::{instance::Instance, vertex::Vertex};
+use crate::{
+    instance::Instance,
+    memory::{Allocation, MemoryCategory, MemoryTracker},
+    vertex::Vertex,
+};
 
 /// A batch of geometry (vertices + indices) that can be drawn in a single GPU call
 ///
 /// Tracks CPU vertex/index data, lazily uploads GPU buffers and prevents overflowing `u16` indices.
 /// Supports two draw paths:
 /// - Baked geometry (vertices + indices) for paths, polygons, arbitrary meshes
-/// - Instanced drawing (instance buffer) for quads/rects/sprites via a static unit quad
+/// - Instanced drawing (instance buffer) for quads/rects/sprites via a static unit quad,
+///   or for any retained mesh set with [`Self::set_mesh`]
 pub struct GeometryBatch {
     vertices: Vec<Vertex>,
     indices: Vec<u16>,
@@ -21,8 +26,11 @@ pub struct GeometryBatch {
     instances: Vec<Instance>,
     instance_buffer: Option<Buffer>,
     instances_dirty: bool,
+    mesh: Option<usize>,
     max_verticies: usize,
     max_indices: usize,
+    // counts the buffers' bytes once uploaded
+    memory: Option<Allocation>,
 }
 
 impl Default for GeometryBatch {
@@ -49,8 +57,10 @@ impl GeometryBatch {
             instances: Vec::new(),
             instance_buffer: None,
             instances_dirty: false,
+            mesh: None,
             max_verticies,
             max_indices,
+            memory: None,
         }
     }
 
@@ -92,9 +102,12 @@ impl GeometryBatch {
         ))
     }
 
-    /// Adds vertices/indices, returns false if it would overflow
+    /// Adds vertices/indices, returns false if it would overflow or an index points past
+    /// `verts`. Nothing is added then, so a bad buffer never leaves garbage to draw
     pub fn push(&mut self, verts: &[Vertex], indices: &[u16]) -> bool {
-        if self.would_overflow(verts.len(), indices.len()) {
+        if self.would_overflow(verts.len(), indices.len())
+            || indices.iter().any(|&i| i as usize >= verts.len())
+        {
             return false;
         }
 
@@ -114,8 +127,55 @@ impl GeometryBatch {
         self.instances_dirty = true;
     }
 
+    /// Draw this batch's instances as the mesh with id `mesh` (see
+    /// [`crate::Renderer::add_mesh`]) instead of the unit quad
+    pub fn set_mesh(&mut self, mesh: Option<usize>) {
+        self.mesh = mesh;
+    }
+
+    /// Mesh id instances are drawn with, `None` for the unit quad
+    pub fn mesh(&self) -> Option<usize> {
+        self.mesh
+    }
+
+    /// Baked vertices currently in the batch
+    pub fn vertices(&self) -> &[Vertex] {
+        &self.vertices
+    }
+
+    /// Baked vertices to change after they were written, e.g. to move them. Marks them dirty
+    pub fn vertices_mut(&mut self) -> &mut [Vertex] {
+        self.vertices_dirty = true;
+        &mut self.vertices
+    }
+
+    /// Baked indices currently in the batch, relative to [`Self::vertices`]
+    pub fn indices(&self) -> &[u16] {
+        &self.indices
+    }
+
+    /// Instances currently in the batch
+    pub fn instances(&self) -> &[Instance] {
+        &self.instances
+    }
+
+    /// Number of baked vertices currently in the batch
+    pub fn vertex_count(&self) -> usize {
+        self.vertices.len()
+    }
+
+    /// Number of baked indices currently in the batch
+    pub fn index_count(&self) -> usize {
+        self.indices.len()
+    }
+
+    /// Number of instances currently in the batch
+    pub fn instance_count(&self) -> usize {
+        self.instances.len()
+    }
+
     /// Returns true if there is nothing to draw in either path
-    pub(crate) fn is_empty(&self) -> bool {
+    pub fn is_empty(&self) -> bool {
         self.indices.is_empty() && self.instances.is_empty()
     }
 
@@ -130,7 +190,7 @@ impl GeometryBatch {
     }
 
     // Uploads buffers to GPU only if needed
-    pub(crate) fn upload(&mut self, device: &Device, queue: &Queue) {
+    pub(crate) fn upload(&mut self, device: &Device, queue: &Queue, memory: &MemoryTracker) {
         if !self.vertices_dirty && !self.indices_dirty && !self.instances_dirty {
             return;
         }
@@ -203,23 +263,34 @@ impl GeometryBatch {
             );
             self.instances_dirty = false;
         }
+
+        let buffers = [
+            &self.vertex_buffer,
+            &self.index_buffer,
+            &self.instance_buffer,
+        ];
+        let bytes = buffers.into_iter().flatten().map(Buffer::size).sum();
+        if self.memory.as_ref().map(Allocation::bytes) != Some(bytes) {
+            self.memory = Some(memory.track(MemoryCategory::Geometry, bytes));
+        }
     }
 
-    /// Draws baked geometry and/or instanced quads as separate draw calls
+    /// Draws baked geometry and/or instances as separate draw calls.
+    /// `instanced` is the (vertex buffer, index buffer, index count) every instance repeats
     pub(crate) fn draw(
         &self,
         r_pass: &mut RenderPass,
-        quad_vb: &Buffer,
-        quad_ib: &Buffer,
+        instanced: Option<(&Buffer, &Buffer, u32)>,
         dummy_instance: &Buffer,
     ) {
         if !self.instances.is_empty()
             && let Some(instance_buf) = &self.instance_buffer
+            && let Some((vb, ib, index_count)) = instanced
         {
-            r_pass.set_vertex_buffer(0, quad_vb.slice(..));
+            r_pass.set_vertex_buffer(0, vb.slice(..));
             r_pass.set_vertex_buffer(1, instance_buf.slice(..));
-            r_pass.set_index_buffer(quad_ib.slice(..), IndexFormat::Uint16);
-            r_pass.draw_indexed(0..6, 0, 0..self.instances.len() as u32);
+            r_pass.set_index_buffer(ib.slice(..), IndexFormat::Uint16);
+            r_pass.draw_indexed(0..index_count, 0, 0..self.instances.len() as u32);
         }
         if !self.indices.is_empty()
             && let (Some(vb), Some(ib)) = (&self.vertex_buffer, &self.index_buffer)
@@ -231,3 +302,48 @@ impl GeometryBatch {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// xorshift, enough to shake out index buffers without a dependency
+    fn next(state: &mut u32) -> u32 {
+        *state ^= *state << 13;
+        *state ^= *state >> 17;
+        *state ^= *state << 5;
+        *state
+    }
+
+    #[test]
+    fn pushes_with_out_of_range_indices_are_rejected_whole() {
+        let mut batch = GeometryBatch::new(64, 96);
+        let mut state = 0x9e37_79b9;
+        for _ in 0..10_000 {
+            let vert_count = next(&mut state) as usize % 24;
+            let index_count = next(&mut state) as usize % 36;
+            let verts = vec![Vertex::zeroed(); vert_count];
+            // mostly in range, sometimes just past it
+            let indices: Vec<u16> = (0..index_count)
+                .map(|_| (next(&mut state) % (vert_count as u32 + 2)) as u16)
+                .collect();
+            let before = (batch.vertex_count(), batch.index_count());
+            let valid = indices.iter().all(|&i| (i as usize) < vert_count);
+            let fits = !batch.would_overflow(vert_count, index_count);
+
+            assert_eq!(batch.push(&verts, &indices), valid && fits);
+            if !(valid && fits) {
+                assert_eq!((batch.vertex_count(), batch.index_count()), before);
+            }
+            assert!(
+                batch
+                    .indices()
+                    .iter()
+                    .all(|&i| (i as usize) < batch.vertex_count())
+            );
+            if !fits {
+                batch.clear();
+            }
+        }
+    }
+}
@@ -0,0 +1,321 @@
+use std::{borrow::Cow, fmt};
+
+use ktx2::{Format, Header, LevelIndex, Reader, TransferFunction};
+use wgpu::{AstcBlock, AstcChannel, Extent3d, Features, TextureFormat};
+
+/// Why a KTX2 texture couldn't be loaded, see [`crate::Renderer::add_texture_compressed`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompressedTextureError {
+    /// Not a KTX2 container, or one whose levels are cut short
+    Invalid,
+    /// The levels are supercompressed, e.g. Basis Universal or zstd
+    Supercompressed,
+    /// Cube maps, arrays & 1D or 3D textures can't be loaded
+    NotA2dTexture,
+    /// The payload isn't RGBA8, BC1, BC3, BC7, ETC2 or ASTC. Holds the container's `vkFormat`
+    UnsupportedFormat(u32),
+}
+
+impl fmt::Display for CompressedTextureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Invalid => write!(f, "not a valid KTX2 container"),
+            Self::Supercompressed => write!(f, "supercompressed KTX2 levels aren't supported"),
+            Self::NotA2dTexture => write!(f, "only 2D KTX2 textures are supported"),
+            Self::UnsupportedFormat(format) => {
+                write!(f, "unsupported KTX2 format (vkFormat {format})")
+            }
+        }
+    }
+}
+
+impl std::error::Error for CompressedTextureError {}
+
+/// Texture compression the renderer enables on devices that support it
+pub(crate) const COMPRESSION_FEATURES: Features = Features::TEXTURE_COMPRESSION_BC
+    .union(Features::TEXTURE_COMPRESSION_ETC2)
+    .union(Features::TEXTURE_COMPRESSION_ASTC);
+
+const ASTC_BLOCKS: [(Format, AstcBlock); 14] = [
+    (Format::ASTC_4x4_UNORM_BLOCK, AstcBlock::B4x4),
+    (Format::ASTC_5x4_UNORM_BLOCK, AstcBlock::B5x4),
+    (Format::ASTC_5x5_UNORM_BLOCK, AstcBlock::B5x5),
+    (Format::ASTC_6x5_UNORM_BLOCK, AstcBlock::B6x5),
+    (Format::ASTC_6x6_UNORM_BLOCK, AstcBlock::B6x6),
+    (Format::ASTC_8x5_UNORM_BLOCK, AstcBlock::B8x5),
+    (Format::ASTC_8x6_UNORM_BLOCK, AstcBlock::B8x6),
+    (Format::ASTC_8x8_UNORM_BLOCK, AstcBlock::B8x8),
+    (Format::ASTC_10x5_UNORM_BLOCK, AstcBlock::B10x5),
+    (Format::ASTC_10x6_UNORM_BLOCK, AstcBlock::B10x6),
+    (Format::ASTC_10x8_UNORM_BLOCK, AstcBlock::B10x8),
+    (Format::ASTC_10x10_UNORM_BLOCK, AstcBlock::B10x10),
+    (Format::ASTC_12x10_UNORM_BLOCK, AstcBlock::B12x10),
+    (Format::ASTC_12x12_UNORM_BLOCK, AstcBlock::B12x12),
+];
+
+/// The wgpu format of a KTX2 `vkFormat`
+fn wgpu_format(format: Format) -> Option<TextureFormat> {
+    use TextureFormat as T;
+
+    let format = match format {
+        Format::R8G8B8A8_UNORM => T::Rgba8Unorm,
+        Format::R8G8B8A8_SRGB => T::Rgba8UnormSrgb,
+        Format::BC1_RGBA_UNORM_BLOCK => T::Bc1RgbaUnorm,
+        Format::BC1_RGBA_SRGB_BLOCK => T::Bc1RgbaUnormSrgb,
+        Format::BC3_UNORM_BLOCK => T::Bc3RgbaUnorm,
+        Format::BC3_SRGB_BLOCK => T::Bc3RgbaUnormSrgb,
+        Format::BC7_UNORM_BLOCK => T::Bc7RgbaUnorm,
+        Format::BC7_SRGB_BLOCK => T::Bc7RgbaUnormSrgb,
+        Format::ETC2_R8G8B8_UNORM_BLOCK => T::Etc2Rgb8Unorm,
+        Format::ETC2_R8G8B8_SRGB_BLOCK => T::Etc2Rgb8UnormSrgb,
+        Format::ETC2_R8G8B8A1_UNORM_BLOCK => T::Etc2Rgb8A1Unorm,
+        Format::ETC2_R8G8B8A1_SRGB_BLOCK => T::Etc2Rgb8A1UnormSrgb,
+        Format::ETC2_R8G8B8A8_UNORM_BLOCK => T::Etc2Rgba8Unorm,
+        Format::ETC2_R8G8B8A8_SRGB_BLOCK => T::Etc2Rgba8UnormSrgb,
+        // each sRGB ASTC format directly follows its UNORM one
+        _ => ASTC_BLOCKS.iter().find_map(|&(unorm, block)| {
+            let channel = match format.value().wrapping_sub(unorm.value()) {
+                0 => AstcChannel::Unorm,
+                1 => AstcChannel::UnormSrgb,
+                _ => return None,
+            };
+            Some(T::Astc { block, channel })
+        })?,
+    };
+    Some(format)
+}
+
+/// Decodes one `w` x `h` level of a compressed `format` into tightly packed RGBA8
+fn decode_rgba(format: TextureFormat, data: &[u8], (w, h): (u32, u32)) -> Option<Vec<u8>> {
+    use texture2ddecoder::*;
+
+    let (w, h) = (w as usize, h as usize);
+    let mut pixels = vec![0u32; w * h];
+    let decoded = match format.remove_srgb_suffix() {
+        TextureFormat::Bc1RgbaUnorm => decode_bc1(data, w, h, &mut pixels),
+        TextureFormat::Bc3RgbaUnorm => decode_bc3(data, w, h, &mut pixels),
+        TextureFormat::Bc7RgbaUnorm => decode_bc7(data, w, h, &mut pixels),
+        TextureFormat::Etc2Rgb8Unorm => decode_etc2_rgb(data, w, h, &mut pixels),
+        TextureFormat::Etc2Rgb8A1Unorm => decode_etc2_rgba1(data, w, h, &mut pixels),
+        TextureFormat::Etc2Rgba8Unorm => decode_etc2_rgba8(data, w, h, &mut pixels),
+        TextureFormat::Astc { .. } => {
+            let (bw, bh) = format.block_dimensions();
+            decode_astc(data, w, h, bw as usize, bh as usize, &mut pixels)
+        }
+        _ => return None,
+    };
+    decoded.ok()?;
+    // the decoder packs pixels as BGRA
+    let rgba = pixels.iter().flat_map(|pixel| {
+        let [b, g, r, a] = pixel.to_le_bytes();
+        [r, g, b, a]
+    });
+    Some(rgba.collect())
+}
+
+/// Size of mip `level` of a `size` texture, in pixels
+fn level_size((w, h): (u32, u32), level: usize) -> (u32, u32) {
+    ((w >> level).max(1), (h >> level).max(1))
+}
+
+/// Bytes of a whole `size` level in `format`, rounded up to full blocks
+pub(crate) fn level_layout(format: TextureFormat, (w, h): (u32, u32)) -> (Extent3d, u32, u32) {
+    let (bw, bh) = format.block_dimensions();
+    let extent = Extent3d {
+        width: w,
+        height: h,
+        depth_or_array_layers: 1,
+    }
+    .physical_size(format);
+    let bytes_per_row = extent.width / bw * format.block_copy_size(None).unwrap();
+    (extent, bytes_per_row, extent.height / bh)
+}
+
+/// A KTX2 texture ready to upload, in the container's format or decoded to RGBA8
+#[derive(Debug)]
+pub(crate) struct CompressedImage<'a> {
+    pub format: TextureFormat,
+    pub size: (u32, u32),
+    /// Mip levels, largest first
+    pub levels: Vec<Cow<'a, [u8]>>,
+}
+
+impl<'a> CompressedImage<'a> {
+    /// Reads a KTX2 container, keeping its payload when a device with `features` can sample
+    /// it & decoding it to RGBA8 otherwise. Its transfer function picks sRGB or linear
+    pub fn parse(data: &'a [u8], features: Features) -> Result<Self, CompressedTextureError> {
+        let reader = Reader::new(data).map_err(|_| CompressedTextureError::Invalid)?;
+        let header = reader.header();
+        if header.supercompression_scheme.is_some() {
+            return Err(CompressedTextureError::Supercompressed);
+        }
+        if header.pixel_height == 0
+            || header.pixel_depth > 1
+            || header.layer_count > 1
+            || header.face_count != 1
+        {
+            return Err(CompressedTextureError::NotA2dTexture);
+        }
+        let mut format = header.format.and_then(wgpu_format).ok_or_else(|| {
+            let vk_format = header.format.map_or(0, |format| format.value());
+            CompressedTextureError::UnsupportedFormat(vk_format)
+        })?;
+        // the data format descriptor's transfer function has the last word over the vkFormat
+        format = match reader.transfer_function() {
+            Some(TransferFunction::SRGB) => format.add_srgb_suffix(),
+            Some(_) => format.remove_srgb_suffix(),
+            None => format,
+        };
+        let srgb = format.is_srgb();
+
+        let size = (header.pixel_width, header.pixel_height);
+        let (bw, bh) = format.block_dimensions();
+        let sampleable =
+            features.contains(format.required_features()) && size.0 % bw == 0 && size.1 % bh == 0;
+        let mut levels = Vec::new();
+        // the reader checked the level index & that each level lies within `data`
+        let index = data[Header::LENGTH..].chunks_exact(LevelIndex::LENGTH);
+        for (i, entry) in index.take(reader.levels().len()).enumerate() {
+            let entry = LevelIndex::from_bytes(entry.try_into().unwrap());
+            let level = &data[entry.byte_offset as usize..][..entry.byte_length as usize];
+            let (_, bytes_per_row, rows) = level_layout(format, level_size(size, i));
+            if level.len() != (bytes_per_row * rows) as usize {
+                return Err(CompressedTextureError::Invalid);
+            }
+            levels.push(if sampleable {
+                Cow::Borrowed(level)
+            } else {
+                let decoded = decode_rgba(format, level, level_size(size, i));
+                Cow::Owned(decoded.ok_or(CompressedTextureError::Invalid)?)
+            });
+        }
+        if !sampleable && format.is_compressed() {
+            format = match srgb {
+                true => TextureFormat::Rgba8UnormSrgb,
+                false => TextureFormat::Rgba8Unorm,
+            };
+        }
+
+        Ok(Self {
+            format,
+            size,
+            levels,
+        })
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use ktx2::{Index, dfd};
+
+    /// A KTX2 container of a `w` x `h` texture in `format` with `levels`, largest first
+    pub(crate) fn ktx2(format: Format, (w, h): (u32, u32), levels: &[&[u8]]) -> Vec<u8> {
+        let (basic, type_size) = dfd::Basic::from_format(format).unwrap();
+        let dfd = dfd::Block::Basic(basic).to_vec();
+        let dfd_offset = Header::LENGTH + LevelIndex::LENGTH * levels.len();
+        let header = Header {
+            format: Some(format),
+            type_size,
+            pixel_width: w,
+            pixel_height: h,
+            pixel_depth: 0,
+            layer_count: 0,
+            face_count: 1,
+            level_count: levels.len() as u32,
+            supercompression_scheme: None,
+            index: Index {
+                dfd_byte_offset: dfd_offset as u32,
+                dfd_byte_length: 4 + dfd.len() as u32,
+                kvd_byte_offset: 0,
+                kvd_byte_length: 0,
+                sgd_byte_offset: 0,
+                sgd_byte_length: 0,
+            },
+        };
+
+        let mut out = header.as_bytes().to_vec();
+        let mut offset = dfd_offset + 4 + dfd.len();
+        for level in levels {
+            let index = LevelIndex {
+                byte_offset: offset as u64,
+                byte_length: level.len() as u64,
+                uncompressed_byte_length: level.len() as u64,
+            };
+            out.extend(index.as_bytes());
+            offset += level.len();
+        }
+        out.extend((4 + dfd.len() as u32).to_le_bytes());
+        out.extend(dfd);
+        levels.iter().for_each(|level| out.extend(*level));
+        out
+    }
+
+    const WHITE: [u8; 8] = [0xff, 0xff, 0, 0, 0, 0, 0, 0];
+    // every pixel picks the second, black endpoint
+    const BLACK: [u8; 8] = [0xff, 0xff, 0, 0, 0x55, 0x55, 0x55, 0x55];
+
+    #[test]
+    fn payloads_stay_compressed_only_where_sampled() {
+        let level0 = [WHITE, BLACK].concat();
+        let file = ktx2(Format::BC1_RGBA_SRGB_BLOCK, (8, 4), &[&level0, &WHITE]);
+
+        let image = CompressedImage::parse(&file, Features::TEXTURE_COMPRESSION_BC).unwrap();
+        assert_eq!(image.format, TextureFormat::Bc1RgbaUnormSrgb);
+        assert_eq!(image.size, (8, 4));
+        assert!(matches!(image.levels[0], Cow::Borrowed(level) if level == level0));
+        assert_eq!(*image.levels[1], WHITE);
+
+        // ETC2 & ASTC alone can't sample BC, so it's decoded with its mips
+        let image = CompressedImage::parse(
+            &file,
+            COMPRESSION_FEATURES.difference(Features::TEXTURE_COMPRESSION_BC),
+        )
+        .unwrap();
+        assert_eq!(image.format, TextureFormat::Rgba8UnormSrgb);
+        let pixels = &image.levels[0];
+        assert_eq!(pixels.len(), 8 * 4 * 4);
+        assert_eq!(pixels[..4], [255; 4]);
+        assert_eq!(pixels[4 * 4..][..4], [0, 0, 0, 255]);
+        assert_eq!(image.levels[1].len(), 4 * 2 * 4);
+
+        // linear containers decode to linear pixels
+        let file = ktx2(Format::BC1_RGBA_UNORM_BLOCK, (8, 4), &[&level0]);
+        let image = CompressedImage::parse(&file, Features::empty()).unwrap();
+        assert_eq!(image.format, TextureFormat::Rgba8Unorm);
+        assert_eq!(image.levels.len(), 1);
+
+        // sizes that aren't whole blocks can't be created compressed
+        let file = ktx2(Format::BC1_RGBA_SRGB_BLOCK, (6, 4), &[&level0]);
+        let image = CompressedImage::parse(&file, COMPRESSION_FEATURES).unwrap();
+        assert_eq!(image.format, TextureFormat::Rgba8UnormSrgb);
+        assert_eq!(image.levels[0].len(), 6 * 4 * 4);
+    }
+
+    #[test]
+    fn malformed_containers_are_rejected() {
+        let parse = |file: &[u8]| CompressedImage::parse(file, COMPRESSION_FEATURES).err();
+        assert_eq!(
+            parse(b"not a ktx2 file"),
+            Some(CompressedTextureError::Invalid)
+        );
+        // the second level is a block short
+        let file = ktx2(Format::BC7_SRGB_BLOCK, (8, 8), &[&[0; 64], &[]]);
+        assert_eq!(parse(&file), Some(CompressedTextureError::Invalid));
+        let file = ktx2(Format::R16_UNORM, (2, 2), &[&[0; 8]]);
+        assert_eq!(
+            parse(&file),
+            Some(CompressedTextureError::UnsupportedFormat(70))
+        );
+
+        let file = ktx2(Format::ASTC_6x6_SRGB_BLOCK, (12, 6), &[&[0; 32]]);
+        let image = CompressedImage::parse(&file, COMPRESSION_FEATURES).unwrap();
+        assert_eq!(
+            image.format,
+            TextureFormat::Astc {
+                block: AstcBlock::B6x6,
+                channel: AstcChannel::UnormSrgb
+            }
+        );
+    }
+}
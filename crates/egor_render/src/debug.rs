@@ -0,0 +1,157 @@
+//! Debug groups & markers naming each part of a frame in GPU debuggers, so a capture reads
+//! as a tree (scene, geometry, `batch 3: texture 2, shader default`, text, post effects, egui)
+//! instead of a flat list of draws. They're only recorded with the `gpu_debug` feature;
+//! without it every call here compiles to nothing
+//!
+//! Capturing a frame with RenderDoc (Vulkan, DX12 or GL):
+//! 1. Build with `--features gpu_debug`
+//! 2. In RenderDoc's "Launch Application" tab pick the built executable & its working
+//!    directory, then "Launch"
+//! 3. Press F12 (or "Capture Frame(s) Immediately") while the frame of interest is up
+//! 4. Open the capture: the "Event Browser" shows the groups, each batch's draw under
+//!    `batch N`, with the pipeline state & bound texture in "Pipeline State" & "Texture
+//!    Viewer"
+//!
+//! On macOS run from Xcode with "GPU Frame Capture" set to Metal & use the camera button;
+//! the same groups show in the debug navigator. Browsers with WebGPU show them in their
+//! GPU tools too
+//!
+//! For bugs a capture can't show, `App::wgpu_trace(dir)` (or [`crate::DeviceRequest::trace`])
+//! records every wgpu call into `dir/trace.ron` on native, for replaying with wgpu's `player`
+
+use std::fmt;
+
+use wgpu::{CommandEncoder, RenderPass};
+
+/// What debug groups & markers can be recorded into, an encoder or one of its passes
+pub trait DebugLabels {
+    fn push_debug_group(&mut self, label: &str);
+    fn pop_debug_group(&mut self);
+    fn insert_debug_marker(&mut self, label: &str);
+}
+
+impl DebugLabels for CommandEncoder {
+    fn push_debug_group(&mut self, label: &str) {
+        CommandEncoder::push_debug_group(self, label);
+    }
+
+    fn pop_debug_group(&mut self) {
+        CommandEncoder::pop_debug_group(self);
+    }
+
+    fn insert_debug_marker(&mut self, label: &str) {
+        CommandEncoder::insert_debug_marker(self, label);
+    }
+}
+
+impl DebugLabels for RenderPass<'_> {
+    fn push_debug_group(&mut self, label: &str) {
+        RenderPass::push_debug_group(self, label);
+    }
+
+    fn pop_debug_group(&mut self) {
+        RenderPass::pop_debug_group(self);
+    }
+
+    fn insert_debug_marker(&mut self, label: &str) {
+        RenderPass::insert_debug_marker(self, label);
+    }
+}
+
+/// Records everything `f` does into `target` inside a group named `label`:
+///
+/// ```no_run
+/// # fn f(encoder: &mut egor_render::CommandEncoder) {
+/// egor_render::debug::group(encoder, format_args!("minimap"), |encoder| {
+///     // passes begun here show up under "minimap"
+/// });
+/// # }
+/// ```
+#[inline]
+pub fn group<T: DebugLabels + ?Sized, R>(
+    target: &mut T,
+    label: fmt::Arguments,
+    f: impl FnOnce(&mut T) -> R,
+) -> R {
+    #[cfg(feature = "gpu_debug")]
+    target.push_debug_group(&label.to_string());
+    #[cfg(not(feature = "gpu_debug"))]
+    let _ = label;
+    let result = f(target);
+    #[cfg(feature = "gpu_debug")]
+    target.pop_debug_group();
+    result
+}
+
+/// Marks the point `target` has recorded up to as `label`
+#[inline]
+pub fn marker<T: DebugLabels + ?Sized>(target: &mut T, label: fmt::Arguments) {
+    #[cfg(feature = "gpu_debug")]
+    target.insert_debug_marker(&label.to_string());
+    #[cfg(not(feature = "gpu_debug"))]
+    let _ = (target, label);
+}
+
+/// Shows an optional id as itself or `default`
+pub(crate) struct Id(pub Option<usize>);
+
+impl fmt::Display for Id {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.0 {
+            Some(id) => id.fmt(f),
+            None => f.write_str("default"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct Recorded(Vec<String>);
+
+    impl DebugLabels for Recorded {
+        fn push_debug_group(&mut self, label: &str) {
+            self.0.push(format!("push {label}"));
+        }
+
+        fn pop_debug_group(&mut self) {
+            self.0.push("pop".into());
+        }
+
+        fn insert_debug_marker(&mut self, label: &str) {
+            self.0.push(format!("marker {label}"));
+        }
+    }
+
+    #[test]
+    fn groups_nest_around_what_they_record_with_gpu_debug_only() {
+        let mut recorded = Recorded::default();
+        let drawn = group(&mut recorded, format_args!("scene"), |r| {
+            let label = format_args!("batch 0: texture {}, shader {}", Id(Some(2)), Id(None));
+            group(r, label, |r| marker(r, format_args!("draw")));
+            7
+        });
+        assert_eq!(drawn, 7);
+        let expected: &[&str] = match cfg!(feature = "gpu_debug") {
+            true => &[
+                "push scene",
+                "push batch 0: texture 2, shader default",
+                "marker draw",
+                "pop",
+                "pop",
+            ],
+            false => &[],
+        };
+        assert_eq!(recorded.0, expected);
+
+        // balanced on a real encoder, which fails validation otherwise
+        let (device, queue) = crate::noop_device();
+        let mut encoder = device.create_command_encoder(&Default::default());
+        group(&mut encoder, format_args!("frame"), |e| {
+            marker(e, format_args!("start"))
+        });
+        queue.submit(Some(encoder.finish()));
+    }
+}
@@ -0,0 +1,528 @@
+use wgpu::{
+    Adapter, AdapterInfo, BackendOptions, Backends, Device, DeviceDescriptor, Features, Instance,
+    InstanceDescriptor, Limits, MemoryHints, NoopBackendOptions, PowerPreference, Queue,
+    RequestAdapterError, RequestAdapterOptions, Surface, SurfaceTarget, TextureFormat,
+    WindowHandle, util::new_instance_with_webgpu_detection,
+};
+
+use crate::{RendererError, target};
+
+/// The GPU a [`crate::Renderer`] draws with: instance, adapter, device & queue. Cloning
+/// shares the same device, so several renderers or windows can draw with one GPU
+#[derive(Clone, Debug)]
+pub struct GpuContext {
+    pub instance: Instance,
+    pub adapter: Adapter,
+    pub device: Device,
+    pub queue: Queue,
+    downgrade: Option<GpuDowngrade>,
+}
+
+impl GpuContext {
+    /// Creates a device on an adapter of `backends` picked by [`DeviceRequest::adapter`],
+    /// without a surface. Windows drawn with it need an adapter that can present to them,
+    /// see [`Self::with_surface`]
+    pub async fn new(
+        backends: Backends,
+        request: &DeviceRequest,
+        memory_hints: &MemoryHints,
+    ) -> Result<Self, RendererError> {
+        let instance = new_instance(backends).await;
+        let adapter = select_adapter(&instance, &request.adapter, None)
+            .await
+            .map_err(RendererError::Adapter)?;
+        Self::with_adapter(instance, adapter, request, memory_hints).await
+    }
+
+    /// Like [`Self::new`], on an adapter that can present to `window`. Returns the window's
+    /// surface too, e.g. for [`Self::surface_format`]
+    pub async fn with_surface(
+        window: impl Into<SurfaceTarget<'static>> + WindowHandle,
+        backends: Backends,
+        request: &DeviceRequest,
+        memory_hints: &MemoryHints,
+    ) -> Result<(Self, Surface<'static>), RendererError> {
+        let instance = new_instance(backends).await;
+        let surface = instance
+            .create_surface(window)
+            .map_err(RendererError::Surface)?;
+        // Required for WebGL to prevent selecting a non-presentable device
+        let adapter = select_adapter(&instance, &request.adapter, Some(&surface))
+            .await
+            .map_err(RendererError::Adapter)?;
+        let context = Self::with_adapter(instance, adapter, request, memory_hints).await?;
+        Ok((context, surface))
+    }
+
+    async fn with_adapter(
+        instance: Instance,
+        adapter: Adapter,
+        request: &DeviceRequest,
+        memory_hints: &MemoryHints,
+    ) -> Result<Self, RendererError> {
+        let info = adapter.get_info();
+        log::info!(
+            target: "egor_render::device",
+            "drawing with {} ({:?}, {:?})",
+            info.name,
+            info.device_type,
+            info.backend
+        );
+        let (features, limits, downgrade) =
+            request.negotiate(adapter.features(), &adapter.limits());
+        // sample compressed textures as they are wherever possible
+        #[cfg(feature = "ktx2")]
+        let features = features | (adapter.features() & crate::compressed::COMPRESSION_FEATURES);
+        let (device, queue) = adapter
+            .request_device(&DeviceDescriptor {
+                required_features: features,
+                required_limits: limits,
+                memory_hints: memory_hints.clone(),
+                trace: request.wgpu_trace(),
+                ..Default::default()
+            })
+            .await
+            .map_err(RendererError::Device)?;
+        Ok(Self {
+            instance,
+            adapter,
+            device,
+            queue,
+            downgrade,
+        })
+    }
+
+    /// The first format of `preferred` that `surface` supports on this adapter, or the
+    /// surface's default format
+    pub fn surface_format(
+        &self,
+        surface: &Surface,
+        preferred: &[TextureFormat],
+    ) -> Result<TextureFormat, RendererError> {
+        let supported = surface.get_capabilities(&self.adapter).formats;
+        if let Some(format) = target::negotiate_format(&supported, preferred) {
+            return Ok(format);
+        }
+        let config = surface.get_default_config(&self.adapter, 1, 1);
+        Ok(config.ok_or(RendererError::UnsupportedSurface)?.format)
+    }
+
+    /// What the adapter couldn't provide of the [`DeviceRequest`] the device was created
+    /// with, `None` if it got everything
+    pub fn gpu_downgrade(&self) -> Option<&GpuDowngrade> {
+        self.downgrade.as_ref()
+    }
+
+    /// Name, vendor, type & backend of the adapter the device was created on
+    pub fn adapter_info(&self) -> AdapterInfo {
+        self.adapter.get_info()
+    }
+}
+
+/// Picks an adapter as `preference` asks, trying each of its [`AdapterPreference::attempts`]
+/// in turn & failing with the last one's error
+async fn select_adapter(
+    instance: &Instance,
+    preference: &AdapterPreference,
+    surface: Option<&Surface<'_>>,
+) -> Result<Adapter, RequestAdapterError> {
+    #[cfg(not(target_arch = "wasm32"))]
+    if let AdapterPreference::Specific(name) = preference {
+        let name = name.to_lowercase();
+        let named = instance
+            .enumerate_adapters(Backends::all())
+            .into_iter()
+            .filter(|adapter| surface.is_none_or(|s| adapter.is_surface_supported(s)))
+            .find(|adapter| adapter.get_info().name.to_lowercase().contains(&name));
+        match named {
+            Some(adapter) => return Ok(adapter),
+            None => log::warn!(
+                target: "egor_render::device",
+                "no adapter named like {name:?}, picking another"
+            ),
+        }
+    }
+    request_adapter(instance, preference.attempts(), surface).await
+}
+
+/// The first adapter found by `attempts` of (power preference, software only), or the last
+/// attempt's error
+async fn request_adapter(
+    instance: &Instance,
+    attempts: &[(PowerPreference, bool)],
+    surface: Option<&Surface<'_>>,
+) -> Result<Adapter, RequestAdapterError> {
+    let options = |&(power_preference, force_fallback_adapter)| RequestAdapterOptions {
+        power_preference,
+        force_fallback_adapter,
+        compatible_surface: surface,
+    };
+    let (last, first) = attempts.split_last().expect("no adapter attempts");
+    for attempt in first {
+        if let Ok(adapter) = instance.request_adapter(&options(attempt)).await {
+            return Ok(adapter);
+        }
+    }
+    instance.request_adapter(&options(last)).await
+}
+
+/// An instance of `backends`, without WebGPU where the browser lacks it. wgpu's no-op
+/// backend only draws nothing, so it's used when it's the one backend asked for
+async fn new_instance(backends: Backends) -> Instance {
+    new_instance_with_webgpu_detection(&InstanceDescriptor {
+        backends,
+        backend_options: BackendOptions {
+            noop: NoopBackendOptions {
+                enable: backends == Backends::NOOP,
+            },
+            ..Default::default()
+        },
+        ..Default::default()
+    })
+    .await
+}
+
+/// Which GPU adapter to draw with, see [`DeviceRequest::adapter`]. Any other adapter the
+/// platform lists is tried before giving up on the one asked for. Where a software renderer
+/// is listed like a GPU, e.g. Mesa's llvmpipe on Linux, every preference may still end up on
+/// it when there's no GPU; check [`GpuContext::adapter_info`]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum AdapterPreference {
+    /// The fastest GPU, e.g. the discrete one of a laptop
+    #[default]
+    HighPerformance,
+    /// The GPU easiest on the battery, e.g. the integrated one of a laptop
+    LowPower,
+    /// The fastest GPU, or a software renderer (llvmpipe, WARP) when there's none, e.g. on
+    /// VMs & CI runners. Only this also asks for the fallback adapter, which some software
+    /// renderers are only listed as, e.g. WARP on Windows
+    SoftwareFallbackAllowed,
+    /// The first adapter whose name contains this, ignoring case, e.g. `"nvidia"` or
+    /// `"llvmpipe"`. Falls back like [`Self::HighPerformance`] if there's none, & always in
+    /// the browser, which doesn't list its adapters
+    Specific(String),
+}
+
+impl AdapterPreference {
+    /// Adapter requests to make in order until one finds an adapter, as (power preference,
+    /// software only)
+    fn attempts(&self) -> &'static [(PowerPreference, bool)] {
+        match self {
+            Self::HighPerformance | Self::Specific(_) => &[
+                (PowerPreference::HighPerformance, false),
+                (PowerPreference::None, false),
+            ],
+            Self::LowPower => &[
+                (PowerPreference::LowPower, false),
+                (PowerPreference::None, false),
+            ],
+            Self::SoftwareFallbackAllowed => &[
+                (PowerPreference::HighPerformance, false),
+                (PowerPreference::None, false),
+                (PowerPreference::None, true),
+            ],
+        }
+    }
+}
+
+/// Optional features & limits to ask the GPU device for, on top of what egor needs.
+/// Whatever the adapter can't provide is dropped instead of failing, see [`GpuDowngrade`]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DeviceRequest {
+    /// Features to enable, e.g. `Features::TEXTURE_BINDING_ARRAY`
+    pub features: Features,
+    /// Limits to require, e.g. a higher `max_texture_dimension_2d` for 8k atlases. `None`
+    /// keeps egor's defaults: wgpu's defaults natively & WebGL2's in the browser
+    pub limits: Option<Limits>,
+    /// Adapter to create the device on
+    pub adapter: AdapterPreference,
+    /// Directory to record a wgpu API trace into, for replaying a bug elsewhere. Native
+    /// only; the directory is created if it doesn't exist
+    #[cfg(feature = "gpu_debug")]
+    pub trace: Option<std::path::PathBuf>,
+}
+
+/// A limit the adapter supports less of than was requested
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LimitShortfall {
+    /// Name of the [`Limits`] field
+    pub name: &'static str,
+    pub requested: u64,
+    /// What the device got instead, the adapter's best
+    pub supported: u64,
+}
+
+/// What a [`DeviceRequest`] asked for that the adapter can't provide. The device is
+/// created with everything else. Only features & limits the request set itself count, i.e.
+/// limits other than egor's defaults: defaults the adapter falls short of are lowered silently
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GpuDowngrade {
+    pub missing_features: Features,
+    pub limits: Vec<LimitShortfall>,
+}
+
+impl DeviceRequest {
+    /// What the device records API calls into, see [`Self::trace`]
+    fn wgpu_trace(&self) -> wgpu::Trace {
+        #[cfg(all(feature = "gpu_debug", not(target_arch = "wasm32")))]
+        if let Some(dir) = &self.trace {
+            // wgpu logs that tracing was removed, but wgpu-core's `trace` feature records
+            return match std::fs::create_dir_all(dir) {
+                Ok(()) => wgpu::Trace::Directory(dir.clone()),
+                Err(e) => {
+                    log::warn!(
+                        target: "egor_render::device",
+                        "not recording a wgpu trace into {}: {e}",
+                        dir.display()
+                    );
+                    wgpu::Trace::Off
+                }
+            };
+        }
+        #[cfg(all(feature = "gpu_debug", target_arch = "wasm32"))]
+        if self.trace.is_some() {
+            log::warn!(target: "egor_render::device", "wgpu traces can't be recorded on the web");
+        }
+        wgpu::Trace::Off
+    }
+
+    /// Features & limits of `self` the adapter supports, & what it doesn't if anything
+    pub(crate) fn negotiate(
+        &self,
+        supported_features: Features,
+        supported_limits: &Limits,
+    ) -> (Features, Limits, Option<GpuDowngrade>) {
+        let features = self.features & supported_features;
+        let defaults = default_limits();
+        let mut limits = self.limits.clone().unwrap_or_else(|| defaults.clone());
+        let mut shortfalls = Vec::new();
+        clamp_limits(&mut limits, supported_limits, &defaults, &mut shortfalls);
+
+        let downgrade = GpuDowngrade {
+            missing_features: self.features - features,
+            limits: shortfalls,
+        };
+        let downgraded = !downgrade.missing_features.is_empty() || !downgrade.limits.is_empty();
+        (features, limits, downgraded.then_some(downgrade))
+    }
+}
+
+fn default_limits() -> Limits {
+    if cfg!(target_arch = "wasm32") {
+        Limits::downlevel_webgl2_defaults()
+    } else {
+        Limits::default()
+    }
+}
+
+/// Lowers every maximum of `limits` above `allowed` & raises every alignment below it,
+/// recording each change to a limit that was asked for, i.e. isn't as in `defaults`
+fn clamp_limits(
+    limits: &mut Limits,
+    allowed: &Limits,
+    defaults: &Limits,
+    shortfalls: &mut Vec<LimitShortfall>,
+) {
+    macro_rules! clamp {
+        ($pick:ident: $($name:ident),+) => {$(
+            let value = limits.$name.$pick(allowed.$name);
+            if value != limits.$name && limits.$name != defaults.$name {
+                shortfalls.push(LimitShortfall {
+                    name: stringify!($name),
+                    requested: limits.$name as u64,
+                    supported: allowed.$name as u64,
+                });
+            }
+            limits.$name = value;
+        )+};
+    }
+
+    clamp!(min:
+        max_texture_dimension_1d,
+        max_texture_dimension_2d,
+        max_texture_dimension_3d,
+        max_texture_array_layers,
+        max_bind_groups,
+        max_bindings_per_bind_group,
+        max_dynamic_uniform_buffers_per_pipeline_layout,
+        max_dynamic_storage_buffers_per_pipeline_layout,
+        max_sampled_textures_per_shader_stage,
+        max_samplers_per_shader_stage,
+        max_storage_buffers_per_shader_stage,
+        max_storage_textures_per_shader_stage,
+        max_uniform_buffers_per_shader_stage,
+        max_binding_array_elements_per_shader_stage,
+        max_uniform_buffer_binding_size,
+        max_storage_buffer_binding_size,
+        max_vertex_buffers,
+        max_buffer_size,
+        max_vertex_attributes,
+        max_vertex_buffer_array_stride,
+        max_inter_stage_shader_components,
+        max_color_attachments,
+        max_color_attachment_bytes_per_sample,
+        max_compute_workgroup_storage_size,
+        max_compute_invocations_per_workgroup,
+        max_compute_workgroup_size_x,
+        max_compute_workgroup_size_y,
+        max_compute_workgroup_size_z,
+        max_compute_workgroups_per_dimension,
+        max_push_constant_size,
+        max_non_sampler_bindings
+    );
+    clamp!(max:
+        min_uniform_buffer_offset_alignment,
+        min_storage_buffer_offset_alignment
+    );
+    // wgpu only checks subgroup sizes when both are set
+    if limits.min_subgroup_size > 0 && limits.max_subgroup_size > 0 {
+        clamp!(max: min_subgroup_size);
+        clamp!(min: max_subgroup_size);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unsupported_requests_fall_back_to_what_the_adapter_has() {
+        let adapter = Limits {
+            max_texture_dimension_2d: 4096,
+            min_uniform_buffer_offset_alignment: 256,
+            ..Limits::default()
+        };
+        let request = DeviceRequest {
+            features: Features::TEXTURE_BINDING_ARRAY | Features::PUSH_CONSTANTS,
+            limits: Some(Limits {
+                max_texture_dimension_2d: 16384,
+                min_uniform_buffer_offset_alignment: 64,
+                ..Limits::default()
+            }),
+            ..DeviceRequest::default()
+        };
+
+        let (features, limits, downgrade) =
+            request.negotiate(Features::TEXTURE_BINDING_ARRAY, &adapter);
+        assert_eq!(features, Features::TEXTURE_BINDING_ARRAY);
+        assert!(limits.check_limits(&adapter));
+        let downgrade = downgrade.unwrap();
+        assert_eq!(downgrade.missing_features, Features::PUSH_CONSTANTS);
+        assert_eq!(
+            downgrade.limits,
+            [
+                LimitShortfall {
+                    name: "max_texture_dimension_2d",
+                    requested: 16384,
+                    supported: 4096,
+                },
+                LimitShortfall {
+                    name: "min_uniform_buffer_offset_alignment",
+                    requested: 64,
+                    supported: 256,
+                },
+            ]
+        );
+
+        // defaults the adapter lacks are lowered without a downgrade, they weren't asked for
+        let (_, limits, downgrade) = DeviceRequest::default().negotiate(Features::all(), &adapter);
+        assert_eq!(limits, default_limits().using_resolution(adapter.clone()));
+        assert_eq!(downgrade, None);
+        let request = DeviceRequest {
+            limits: Some(Limits {
+                max_texture_dimension_3d: 4096,
+                ..default_limits()
+            }),
+            ..DeviceRequest::default()
+        };
+        let weak = Limits {
+            max_texture_dimension_3d: 1024,
+            max_bind_groups: 2,
+            ..adapter
+        };
+        let (_, limits, downgrade) = request.negotiate(Features::empty(), &weak);
+        assert_eq!(limits.max_bind_groups, 2);
+        let names: Vec<_> = downgrade.unwrap().limits.iter().map(|s| s.name).collect();
+        assert_eq!(names, ["max_texture_dimension_3d"]);
+        let (_, _, downgrade) =
+            DeviceRequest::default().negotiate(Features::empty(), &default_limits());
+        assert_eq!(downgrade, None);
+    }
+
+    #[test]
+    fn only_software_fallback_forces_software_adapters() {
+        let software = |preference: AdapterPreference| {
+            preference
+                .attempts()
+                .iter()
+                .filter(|(_, software)| *software)
+                .count()
+        };
+        assert_eq!(software(AdapterPreference::HighPerformance), 0);
+        assert_eq!(software(AdapterPreference::LowPower), 0);
+        assert_eq!(software(AdapterPreference::Specific("nvidia".into())), 0);
+        // hardware first, software last
+        let attempts = AdapterPreference::SoftwareFallbackAllowed.attempts();
+        assert_eq!(attempts.last(), Some(&(PowerPreference::None, true)));
+        assert!(
+            attempts[..attempts.len() - 1]
+                .iter()
+                .all(|(_, software)| !software)
+        );
+    }
+
+    /// Run on CI with a software renderer installed (Mesa's llvmpipe, Windows' WARP), so
+    /// machines without a GPU are known to draw
+    #[test]
+    #[ignore = "needs a software adapter, e.g. llvmpipe or WARP"]
+    fn software_adapters_draw_frames() {
+        use crate::{Renderer, batch::GeometryBatch, instance::Instance as Quad};
+        use wgpu::DeviceType;
+
+        let instance = pollster::block_on(new_instance(Backends::all()));
+        let software = [(PowerPreference::None, true)];
+        let adapter = pollster::block_on(request_adapter(&instance, &software, None)).unwrap();
+        let context = pollster::block_on(GpuContext::with_adapter(
+            instance,
+            adapter,
+            &DeviceRequest::default(),
+            &MemoryHints::Performance,
+        ))
+        .unwrap();
+        assert_eq!(context.adapter_info().device_type, DeviceType::Cpu);
+
+        let format = TextureFormat::Rgba8UnormSrgb;
+        let mut renderer = Renderer::with_context(context, format);
+        let identity = [
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ];
+        renderer.upload_camera_matrix(identity);
+        let target = renderer.create_offscreen_target(4, 4, format);
+
+        // a red quad over all of clip space
+        let mut batch = GeometryBatch::default();
+        let red = [1.0, 0.0, 0.0, 1.0];
+        batch.push_instance(Quad::new(
+            [2.0, 0.0, 0.0, 2.0],
+            [0.0, 0.0],
+            red,
+            [0.0, 0.0, 1.0, 1.0],
+        ));
+        let mut encoder = renderer
+            .device()
+            .create_command_encoder(&Default::default());
+        {
+            let view = target.render_view();
+            let mut pass =
+                renderer.begin_render_pass_with_clear(&mut encoder, view, [0.0, 0.0, 0.0, 1.0]);
+            renderer.draw_batch(&mut pass, &mut batch, None, None);
+        }
+        renderer.queue().submit(Some(encoder.finish()));
+
+        let pixels = target.read_pixels(renderer.device(), renderer.queue());
+        assert!(pixels.chunks(4).all(|pixel| pixel == [255, 0, 0, 255]));
+    }
+}
@@ -1,10 +1,28 @@
 use wgpu::{BufferAddress, VertexAttribute, VertexBufferLayout, VertexFormat, VertexStepMode};
 
-/// Per-instance data for 2D instanced drawing (56 bytes)
+/// How an instance's color combines with its texture sample
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ColorMode {
+    /// `texture * color`, the usual tint
+    #[default]
+    Multiply,
+    /// `texture + color`, for additive highlights. Alpha is still multiplied
+    Add,
+    /// Solid `color` in the texture's shape, e.g. flashing a sprite on hit.
+    /// Alpha is still multiplied
+    Replace,
+}
+
+/// Per-instance data for 2D instanced drawing (80 bytes)
 ///
 /// Uses a compact 2D affine representation instead of a full `mat4x4`:
 /// - `affine`: column-major 2×2 rotation+scale matrix `[col0.x, col0.y, col1.x, col1.y]`
 /// - `translate`: world-space translation `[x, y]`
+/// - `color_mode`: a [`ColorMode`] as `u32`
+/// - `layer`: texture array layer to sample, ignored by plain textures
+/// - `params`: free for custom shaders, e.g. a per-sprite dissolve amount, read as
+///   `input.params` in the prelude's `VertexOutput`. The built-in shader ignores it
 #[repr(C)]
 #[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct Instance {
@@ -12,6 +30,9 @@ pub struct Instance {
     pub translate: [f32; 2],
     pub color: [f32; 4],
     pub uv: [f32; 4],
+    pub color_mode: u32,
+    pub layer: u32,
+    pub params: [f32; 4],
 }
 
 impl Instance {
@@ -21,9 +42,38 @@ impl Instance {
             translate,
             color,
             uv,
+            color_mode: ColorMode::Multiply as u32,
+            layer: 0,
+            params: [0.0; 4],
         }
     }
 
+    pub fn with_color_mode(mut self, mode: ColorMode) -> Self {
+        self.color_mode = mode as u32;
+        self
+    }
+
+    pub fn with_layer(mut self, layer: u32) -> Self {
+        self.layer = layer;
+        self
+    }
+
+    pub fn with_params(mut self, params: [f32; 4]) -> Self {
+        self.params = params;
+        self
+    }
+
+    /// Field names of [`Self::desc`]'s attributes, in order, as the shader prelude declares them
+    pub(crate) const ATTRIBUTE_NAMES: [&str; 7] = [
+        "affine",
+        "translate",
+        "color",
+        "uv",
+        "color_mode",
+        "layer",
+        "params",
+    ];
+
     pub(crate) fn desc() -> VertexBufferLayout<'static> {
         use std::mem;
         VertexBufferLayout {
@@ -54,6 +104,24 @@ impl Instance {
                     shader_location: 6,
                     format: VertexFormat::Float32x4,
                 },
+                // color mode
+                VertexAttribute {
+                    offset: 56,
+                    shader_location: 7,
+                    format: VertexFormat::Uint32,
+                },
+                // texture array layer
+                VertexAttribute {
+                    offset: 60,
+                    shader_location: 8,
+                    format: VertexFormat::Uint32,
+                },
+                // custom shader params
+                VertexAttribute {
+                    offset: 64,
+                    shader_location: 9,
+                    format: VertexFormat::Float32x4,
+                },
             ],
         }
     }
@@ -64,6 +132,9 @@ impl Instance {
             translate: [0.0, 0.0],
             color: [1.0; 4],
             uv: [0.0, 0.0, 1.0, 1.0],
+            color_mode: ColorMode::Multiply as u32,
+            layer: 0,
+            params: [0.0; 4],
         }
     }
 }
@@ -1,86 +1,182 @@
 pub mod batch;
+#[cfg(feature = "ktx2")]
+mod compressed;
+pub mod debug;
+mod device;
 pub mod frame;
 pub mod instance;
+mod memory;
+mod mesh;
 mod pipeline;
+mod shader_error;
 pub mod target;
 mod texture;
 mod uniforms;
 pub mod vertex;
 
-pub use wgpu::{Device, MemoryHints, Queue, RenderPass, TextureFormat};
+#[cfg(feature = "ktx2")]
+pub use compressed::CompressedTextureError;
+pub use device::{AdapterPreference, DeviceRequest, GpuContext, GpuDowngrade, LimitShortfall};
+pub use memory::{LARGEST_TEXTURES, MemoryReport};
+pub use pipeline::PRELUDE_DIRECTIVE;
+pub use shader_error::ShaderError;
+pub use texture::TextureRegionError;
+pub use wgpu;
+pub use wgpu::{
+    AdapterInfo, Backends, CommandEncoder, CompositeAlphaMode, Device, Features, Limits,
+    MemoryHints, Queue, RenderPass, SurfaceError, TextureFormat, TextureView,
+};
 
+use std::{cell::Cell, fmt};
 use wgpu::{
-    Adapter, BindGroup, BindGroupDescriptor, BindGroupEntry, Buffer, BufferUsages, Color,
-    CommandEncoder, DeviceDescriptor, Instance, LoadOp, Operations, RenderPassColorAttachment,
-    RenderPassDescriptor, RequestAdapterOptions, StoreOp, SurfaceTarget, TextureView, WindowHandle,
-    util::{BufferInitDescriptor, DeviceExt, new_instance_with_webgpu_detection},
+    Adapter, BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout, Buffer, BufferUsages,
+    Color, CreateSurfaceError, Instance, LoadOp, Operations, RenderPassColorAttachment,
+    RenderPassDescriptor, RequestAdapterError, RequestDeviceError, StoreOp, SurfaceTarget,
+    WindowHandle,
+    util::{BufferInitDescriptor, DeviceExt},
 };
 
 use crate::{
     batch::GeometryBatch,
     frame::Frame,
+    memory::{Allocation, MemoryCategory, MemoryTracker},
+    mesh::Meshes,
     pipeline::Pipelines,
     target::{OffscreenTarget, RenderTarget},
     texture::Textures,
     uniforms::{CameraUniform, Uniforms},
-    vertex::{QUAD_INDICES, QUAD_VERTICES},
+    vertex::{QUAD_INDICES, QUAD_VERTICES, Vertex},
 };
 
-pub(crate) struct Gpu {
-    pub instance: Instance,
-    pub adapter: Adapter,
-    pub device: Device,
-    pub queue: Queue,
+/// Why a [`Renderer`] couldn't be created
+#[derive(Debug)]
+pub enum RendererError {
+    /// The window's surface couldn't be created
+    Surface(CreateSurfaceError),
+    /// No GPU adapter is available, e.g. the browser supports neither WebGPU nor WebGL2
+    Adapter(RequestAdapterError),
+    /// The adapter can't present to the window's surface
+    UnsupportedSurface,
+    /// The adapter refused to create a device with the required limits
+    Device(RequestDeviceError),
+}
+
+impl fmt::Display for RendererError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Surface(e) => write!(f, "couldn't create a surface: {e}"),
+            Self::Adapter(e) => write!(f, "no suitable GPU adapter: {e}"),
+            Self::UnsupportedSurface => write!(f, "the GPU adapter can't present to the window"),
+            Self::Device(e) => write!(f, "couldn't create a GPU device: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for RendererError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Surface(e) => Some(e),
+            Self::Adapter(e) => Some(e),
+            Self::UnsupportedSurface => None,
+            Self::Device(e) => Some(e),
+        }
+    }
+}
+
+/// What a custom render pass needs to draw consistently with egor: the main camera &
+/// the target's format & size. See [`Renderer::pass_resources`]
+pub struct PassResources<'a> {
+    /// Uniform buffer holding the main camera's view-projection matrix (a `mat4x4<f32>`)
+    pub camera_buffer: &'a Buffer,
+    /// Bind group of [`Self::camera_buffer`], laid out as [`Self::camera_layout`]
+    pub camera_bind_group: &'a BindGroup,
+    /// Layout for custom pipelines to bind the camera with, visible to vertex shaders
+    pub camera_layout: &'a BindGroupLayout,
+    /// Format of the target being drawn to
+    pub format: TextureFormat,
+    /// Size of the target in pixels
+    pub size: (u32, u32),
 }
 
 /// Low-level GPU renderer built on `wgpu`
 ///
 /// Handles rendering pipelines, surface configuration, resources (textures, buffers), & drawing
 pub struct Renderer {
-    gpu: Gpu,
+    gpu: GpuContext,
     pipelines: Pipelines,
     quad_vertex_buffer: Buffer,
     quad_index_buffer: Buffer,
     dummy_instance_buffer: Buffer,
-    camera_bind_group: BindGroup,
-    camera_buffer: Buffer,
+    // slot 0 is the main camera, extra slots are created on demand
+    camera_slots: Vec<(Buffer, BindGroup, Allocation)>,
     surface_format: TextureFormat,
     uniforms: Uniforms,
     textures: Textures,
+    meshes: Meshes,
     clear_color: Color,
+    premultiplied: bool,
+    /// Bytes uploaded per texture through `update_texture*`, see [`Self::texture_uploads`]
+    texture_uploads: Vec<(usize, usize)>,
+    memory: MemoryTracker,
+    /// Batches drawn this frame, numbering their debug groups
+    batches_drawn: Cell<usize>,
 }
 
 impl Renderer {
     /// Creates a renderer & initializes GPU state using the window's surface
     ///
-    /// Sets up wgpu, pipelines, default texture & camera resources
+    /// Sets up wgpu, pipelines, default texture & camera resources.
+    /// Fails when no GPU can draw to the window, e.g. a browser with WebGL disabled
     pub async fn new(
         window: impl Into<SurfaceTarget<'static>> + WindowHandle,
         memory_hints: &MemoryHints,
-    ) -> Self {
-        let instance = new_instance_with_webgpu_detection(&Default::default()).await;
-        let surface = instance.create_surface(window).unwrap();
-        let adapter = instance
-            .request_adapter(&RequestAdapterOptions {
-                // Required for WebGL to prevent selecting a non-presentable device
-                compatible_surface: Some(&surface),
-                ..Default::default()
-            })
-            .await
-            .unwrap();
-        let (device, queue) = adapter
-            .request_device(&DeviceDescriptor {
-                #[cfg(target_arch = "wasm32")]
-                required_limits: wgpu::Limits::downlevel_webgl2_defaults(),
-                memory_hints: memory_hints.clone(),
-                ..Default::default()
-            })
-            .await
-            .unwrap();
-
-        let surface_config = surface.get_default_config(&adapter, 1, 1).unwrap();
-        let surface_format = surface_config.format;
-        let pipelines = Pipelines::new(&device, surface_format);
+    ) -> Result<Self, RendererError> {
+        Self::with_preferred_formats(window, memory_hints, &[]).await
+    }
+
+    /// Like [`Self::new`], but renders in the first format of `preferred` the surface supports,
+    /// e.g. `Rgba16Float` for HDR output. Falls back to the surface's default format when none
+    /// are supported. Read the outcome with [`Self::surface_format`]
+    pub async fn with_preferred_formats(
+        window: impl Into<SurfaceTarget<'static>> + WindowHandle,
+        memory_hints: &MemoryHints,
+        preferred: &[TextureFormat],
+    ) -> Result<Self, RendererError> {
+        Self::with_device_request(window, memory_hints, preferred, &DeviceRequest::default()).await
+    }
+
+    /// Like [`Self::with_preferred_formats`], also asking the device for the features &
+    /// limits of `request`. Those the adapter lacks are left out rather than failing, see
+    /// [`Self::gpu_downgrade`]
+    pub async fn with_device_request(
+        window: impl Into<SurfaceTarget<'static>> + WindowHandle,
+        memory_hints: &MemoryHints,
+        preferred: &[TextureFormat],
+        request: &DeviceRequest,
+    ) -> Result<Self, RendererError> {
+        let (context, surface) =
+            GpuContext::with_surface(window, Backends::all(), request, memory_hints).await?;
+        let format = context.surface_format(&surface, preferred)?;
+        Ok(Self::with_context(context, format))
+    }
+
+    /// Creates a renderer without a window or surface, drawing only to offscreen targets
+    /// of the given `format`. Useful for tests, replays & other windowless rendering
+    pub async fn headless(
+        format: TextureFormat,
+        memory_hints: &MemoryHints,
+    ) -> Result<Self, RendererError> {
+        let request = DeviceRequest::default();
+        let context = GpuContext::new(Backends::all(), &request, memory_hints).await?;
+        Ok(Self::with_context(context, format))
+    }
+
+    /// Creates a renderer drawing with an existing GPU, e.g. one shared with other
+    /// renderers through [`Self::context`]. Pipelines target `surface_format`; windows get a
+    /// [`target::Backbuffer`] each, created from the context
+    pub fn with_context(context: GpuContext, surface_format: TextureFormat) -> Self {
+        let device = &context.device;
+        let pipelines = Pipelines::new(device, surface_format);
 
         let quad_vertex_buffer = device.create_buffer_init(&BufferInitDescriptor {
             label: Some("Static Unit Quad VB"),
@@ -97,6 +193,35 @@ impl Renderer {
             contents: bytemuck::bytes_of(&instance::Instance::identity()),
             usage: BufferUsages::VERTEX,
         });
+        let memory = MemoryTracker::default();
+        let camera_slots = vec![Self::create_camera_slot(device, &pipelines, &memory)];
+        let uniforms = Uniforms::new(device, memory.clone());
+        let textures = Textures::new(device, &context.queue, memory.clone());
+
+        Renderer {
+            gpu: context,
+            pipelines,
+            quad_vertex_buffer,
+            quad_index_buffer,
+            dummy_instance_buffer,
+            camera_slots,
+            surface_format,
+            uniforms,
+            textures,
+            meshes: Meshes::default(),
+            clear_color: Color::BLACK,
+            premultiplied: false,
+            texture_uploads: Vec::new(),
+            memory,
+            batches_drawn: Cell::new(0),
+        }
+    }
+
+    fn create_camera_slot(
+        device: &Device,
+        pipelines: &Pipelines,
+        memory: &MemoryTracker,
+    ) -> (Buffer, BindGroup, Allocation) {
         let camera_buffer = device.create_buffer_init(&BufferInitDescriptor {
             label: None,
             contents: bytemuck::bytes_of(&CameraUniform::default()),
@@ -112,29 +237,15 @@ impl Renderer {
             }],
         });
 
-        let uniforms = Uniforms::new(&device);
-        let textures = Textures::new(&device, &queue);
-
-        Renderer {
-            gpu: Gpu {
-                instance,
-                adapter,
-                device,
-                queue,
-            },
-            pipelines,
-            quad_vertex_buffer,
-            quad_index_buffer,
-            dummy_instance_buffer,
-            camera_bind_group,
-            camera_buffer,
-            surface_format,
-            uniforms,
-            textures,
-            clear_color: Color::BLACK,
-        }
+        let allocation = memory.track(MemoryCategory::Uniforms, camera_buffer.size());
+        (camera_buffer, camera_bind_group, allocation)
     }
 
+    /// The GPU this renderer draws with. Clone it to draw with the same device elsewhere,
+    /// e.g. through [`Self::with_context`]
+    pub fn context(&self) -> &GpuContext {
+        &self.gpu
+    }
     /// Returns a reference to the underlying wgpu `Instance`
     pub fn instance(&self) -> &Instance {
         &self.gpu.instance
@@ -152,25 +263,87 @@ impl Renderer {
         &self.gpu.queue
     }
 
+    /// Features the device was created with
+    pub fn features(&self) -> Features {
+        self.gpu.device.features()
+    }
+    /// Limits the device was created with
+    pub fn limits(&self) -> Limits {
+        self.gpu.device.limits()
+    }
+    /// What the adapter couldn't provide of the [`DeviceRequest`] the renderer was created
+    /// with, `None` if it got everything
+    pub fn gpu_downgrade(&self) -> Option<&GpuDowngrade> {
+        self.gpu.gpu_downgrade()
+    }
+    /// Name, vendor, type & backend of the adapter the renderer draws with
+    pub fn adapter_info(&self) -> AdapterInfo {
+        self.gpu.adapter_info()
+    }
+
+    /// The main camera & a target's `format` & `size`, for drawing with custom pipelines
+    /// in the same space as egor's batches
+    pub fn pass_resources(&self, format: TextureFormat, size: (u32, u32)) -> PassResources<'_> {
+        let (camera_buffer, camera_bind_group, _) = &self.camera_slots[0];
+        PassResources {
+            camera_buffer,
+            camera_bind_group,
+            camera_layout: &self.pipelines.camera_layout,
+            format,
+            size,
+        }
+    }
+
+    /// Format the built-in & custom pipelines render to. Window backbuffers should be
+    /// configured with it (see [`target::Backbuffer::new`])
+    pub fn surface_format(&self) -> TextureFormat {
+        self.surface_format
+    }
+
     /// Sets the clear color for future render passes
     pub fn set_clear_color(&mut self, color: [f64; 4]) {
+        let scale = if self.premultiplied { color[3] } else { 1.0 };
         self.clear_color = Color {
-            r: color[0],
-            g: color[1],
-            b: color[2],
+            r: color[0] * scale,
+            g: color[1] * scale,
+            b: color[2] * scale,
             a: color[3],
         };
     }
 
+    /// Composite future clear colors for a surface with alpha `mode`, the one
+    /// [`target::Backbuffer::alpha_mode`] picked. They're premultiplied by their alpha only
+    /// for [`CompositeAlphaMode::PreMultiplied`]
+    pub fn set_alpha_mode(&mut self, mode: CompositeAlphaMode) {
+        self.premultiplied = target::premultiplies(mode);
+    }
+
+    /// Whether clear colors are premultiplied, see [`Self::set_alpha_mode`]
+    pub fn premultiplied(&self) -> bool {
+        self.premultiplied
+    }
+
     /// Begins a frame with the given render target
-    pub fn begin_frame(&mut self, target: &mut dyn RenderTarget) -> Option<Frame> {
-        let (view, presentable) = target.acquire(&self.gpu.device)?;
+    ///
+    /// Lost or outdated surfaces are reconfigured & retried once. Returns `Ok(None)` when
+    /// the frame should be skipped, & an error only when the surface can't recover
+    /// (out of memory)
+    pub fn begin_frame(
+        &mut self,
+        target: &mut dyn RenderTarget,
+    ) -> Result<Option<Frame>, SurfaceError> {
+        let Some((view, presentable)) = target::acquire_with_recovery(target, &self.gpu.device)?
+        else {
+            return Ok(None);
+        };
+        self.pipelines.poll_compiles();
+        self.batches_drawn.set(0);
         let encoder = self.gpu.device.create_command_encoder(&Default::default());
-        Some(Frame {
+        Ok(Some(Frame {
             view,
             encoder,
             presentable,
-        })
+        }))
     }
 
     /// Ends the frame by submitting commands and presenting
@@ -184,13 +357,50 @@ impl Renderer {
         &'a self,
         encoder: &'a mut CommandEncoder,
         view: &'a TextureView,
+    ) -> RenderPass<'a> {
+        Self::begin_cleared_pass(encoder, view, self.clear_color)
+    }
+
+    /// Begins a render pass that clears the view to `color` instead of the renderer's clear color.
+    /// Useful for rendering into transparent offscreen targets
+    pub fn begin_render_pass_with_clear<'a>(
+        &'a self,
+        encoder: &'a mut CommandEncoder,
+        view: &'a TextureView,
+        color: [f64; 4],
+    ) -> RenderPass<'a> {
+        let [r, g, b, a] = color;
+        Self::begin_cleared_pass(encoder, view, Color { r, g, b, a })
+    }
+
+    /// Begins a render pass that keeps what is already in the view, for drawing on top of it
+    pub fn begin_overlay_pass<'a>(
+        &'a self,
+        encoder: &'a mut CommandEncoder,
+        view: &'a TextureView,
+    ) -> RenderPass<'a> {
+        Self::begin_pass(encoder, view, LoadOp::Load)
+    }
+
+    fn begin_cleared_pass<'a>(
+        encoder: &'a mut CommandEncoder,
+        view: &'a TextureView,
+        clear_color: Color,
+    ) -> RenderPass<'a> {
+        Self::begin_pass(encoder, view, LoadOp::Clear(clear_color))
+    }
+
+    fn begin_pass<'a>(
+        encoder: &'a mut CommandEncoder,
+        view: &'a TextureView,
+        load: LoadOp<Color>,
     ) -> RenderPass<'a> {
         encoder.begin_render_pass(&RenderPassDescriptor {
             color_attachments: &[Some(RenderPassColorAttachment {
                 view,
                 resolve_target: None,
                 ops: Operations {
-                    load: LoadOp::Clear(self.clear_color),
+                    load,
                     store: StoreOp::Store,
                 },
             })],
@@ -205,38 +415,107 @@ impl Renderer {
         batch: &mut GeometryBatch,
         texture_id: Option<usize>,
         shader_id: Option<usize>,
+    ) {
+        self.draw_batch_with_camera(r_pass, batch, texture_id, shader_id, 0);
+    }
+
+    /// Draws a geometry batch using the camera uploaded to `camera_slot`.
+    /// See [`Self::upload_camera_matrix_to`]
+    pub fn draw_batch_with_camera(
+        &self,
+        r_pass: &mut RenderPass<'_>,
+        batch: &mut GeometryBatch,
+        texture_id: Option<usize>,
+        shader_id: Option<usize>,
+        camera_slot: usize,
     ) {
         if batch.is_empty() {
             return;
         }
+        batch.upload(&self.gpu.device, &self.gpu.queue, &self.memory);
+        self.draw_uploaded_batch(r_pass, batch, texture_id, shader_id, camera_slot);
+        batch.clear();
+    }
 
-        batch.upload(&self.gpu.device, &self.gpu.queue);
+    /// Uploads a batch's geometry now instead of when it's first drawn, see
+    /// [`Self::draw_uploaded_batch`]
+    pub fn upload_batch(&self, batch: &mut GeometryBatch) {
+        batch.upload(&self.gpu.device, &self.gpu.queue, &self.memory);
+    }
 
+    /// Draws a batch uploaded with [`Self::upload_batch`] as is, without clearing it, so
+    /// retained geometry draws again every frame with no CPU work or upload
+    pub fn draw_uploaded_batch(
+        &self,
+        r_pass: &mut RenderPass<'_>,
+        batch: &GeometryBatch,
+        texture_id: Option<usize>,
+        shader_id: Option<usize>,
+        camera_slot: usize,
+    ) {
+        if batch.is_empty() {
+            return;
+        }
+        let n = self.batches_drawn.replace(self.batches_drawn.get() + 1);
+        let label = format_args!(
+            "batch {n}: texture {}, shader {}, camera {camera_slot}",
+            debug::Id(texture_id),
+            debug::Id(shader_id),
+        );
+        debug::group(r_pass, label, |r_pass| {
+            self.encode_batch(r_pass, batch, texture_id, shader_id, camera_slot)
+        });
+    }
+
+    fn encode_batch(
+        &self,
+        r_pass: &mut RenderPass<'_>,
+        batch: &GeometryBatch,
+        texture_id: Option<usize>,
+        shader_id: Option<usize>,
+        camera_slot: usize,
+    ) {
         let texture = self.textures.get(texture_id);
         texture.bind(r_pass, 0);
 
-        let (pipeline, uniform_ids) = self.pipelines.resolve(shader_id);
+        let (pipeline, uniform_ids) = self.pipelines.resolve(shader_id, texture.is_array());
 
         r_pass.set_pipeline(pipeline);
-        r_pass.set_bind_group(1, &self.camera_bind_group, &[]);
+        let (_, camera_bind_group, _) = &self.camera_slots[camera_slot];
+        r_pass.set_bind_group(1, camera_bind_group, &[]);
 
         for (i, &uid) in uniform_ids.iter().enumerate() {
             r_pass.set_bind_group((2 + i) as u32, self.uniforms.bind_group(uid), &[]);
         }
 
-        batch.draw(
-            r_pass,
-            &self.quad_vertex_buffer,
-            &self.quad_index_buffer,
-            &self.dummy_instance_buffer,
-        );
-        batch.clear();
+        // instances repeat the batch's mesh, or the unit quad if it has none.
+        // Instances of a freed or empty mesh are dropped
+        let instanced = match batch.mesh() {
+            None => Some((&self.quad_vertex_buffer, &self.quad_index_buffer, 6)),
+            Some(id) => self
+                .meshes
+                .get(id)
+                .filter(|m| m.index_count > 0)
+                .map(|m| (&m.vertex_buffer, &m.index_buffer, m.index_count)),
+        };
+        batch.draw(r_pass, instanced, &self.dummy_instance_buffer);
     }
 
     /// Uploads the given view-projection matrix to the GPU for use in vertex transforms
     pub fn upload_camera_matrix(&mut self, view_proj: [[f32; 4]; 4]) {
+        self.upload_camera_matrix_to(0, view_proj);
+    }
+
+    /// Uploads a view-projection matrix to an extra camera slot, creating it if needed.
+    /// Slot 0 is the main camera used by [`Self::draw_batch`]
+    pub fn upload_camera_matrix_to(&mut self, slot: usize, view_proj: [[f32; 4]; 4]) {
+        while self.camera_slots.len() <= slot {
+            let camera_slot =
+                Self::create_camera_slot(&self.gpu.device, &self.pipelines, &self.memory);
+            self.camera_slots.push(camera_slot);
+        }
         self.gpu.queue.write_buffer(
-            &self.camera_buffer,
+            &self.camera_slots[slot].0,
             0,
             bytemuck::bytes_of(&CameraUniform { view_proj }),
         );
@@ -249,7 +528,9 @@ impl Renderer {
         height: u32,
         format: TextureFormat,
     ) -> OffscreenTarget {
-        OffscreenTarget::new(&self.gpu.device, width, height, format)
+        let mut target = OffscreenTarget::new(&self.gpu.device, width, height, format);
+        target.track(&self.memory);
+        target
     }
 
     /// Adds an offscreen target texture & returns its id
@@ -257,30 +538,181 @@ impl Renderer {
         self.textures.insert_offscreen(&self.gpu.device, offscreen)
     }
 
+    /// Points an existing texture id at an offscreen target, replacing what it previously sampled
+    pub fn update_offscreen_texture(&mut self, index: usize, offscreen: &mut OffscreenTarget) {
+        self.textures
+            .replace_offscreen(&self.gpu.device, index, offscreen);
+    }
+
+    /// Whether a texture with id `index` exists. Drawing with one that doesn't, or was
+    /// unloaded, falls back to the default white texture
+    pub fn texture_exists(&self, index: usize) -> bool {
+        self.textures.exists(index)
+    }
+    /// Number of texture ids handed out, ids run from 0 up to it. Unloaded ones are counted
+    pub fn texture_count(&self) -> usize {
+        self.textures.len()
+    }
+
+    /// Returns the view of a texture by id, if it exists
+    pub fn texture_view(&self, index: usize) -> Option<&TextureView> {
+        self.textures.view(index)
+    }
+
     /// Adds a new texture from image bytes & returns its id
     pub fn add_texture(&mut self, data: &[u8]) -> usize {
         self.textures
             .insert(&self.gpu.device, &self.gpu.queue, data)
     }
 
+    /// Adds a KTX2 texture & returns its id. BC, ETC2 & ASTC payloads stay compressed on the
+    /// GPU when the device samples them, otherwise they're decoded to RGBA8. Mip levels are
+    /// kept & the container's transfer function picks sRGB or linear sampling, read the
+    /// outcome with [`Self::texture_format`]. Supercompressed containers, e.g. Basis
+    /// Universal or zstd, aren't transcoded & fail with [`CompressedTextureError::Supercompressed`]
+    #[cfg(feature = "ktx2")]
+    pub fn add_texture_compressed(&mut self, data: &[u8]) -> Result<usize, CompressedTextureError> {
+        let features = self.gpu.device.features();
+        self.textures
+            .insert_compressed(&self.gpu.device, &self.gpu.queue, data, features)
+    }
+
+    /// Format a texture is stored in on the GPU, if it exists
+    pub fn texture_format(&self, index: usize) -> Option<TextureFormat> {
+        self.textures.format(index)
+    }
+
     /// Adds a texture from raw RGBA bytes & returns its id
     pub fn add_texture_raw(&mut self, w: u32, h: u32, data: &[u8]) -> usize {
         self.textures
             .insert_raw(&self.gpu.device, &self.gpu.queue, w, h, data)
     }
 
-    /// Replaces an existing texture with new image data
-    pub fn update_texture(&mut self, index: usize, data: &[u8]) {
+    /// Adds a texture from raw RGBA bytes that's sampled with linear filtering & clamped edges,
+    /// for smooth images like gradients that get stretched. Returns its id
+    pub fn add_texture_raw_linear(&mut self, w: u32, h: u32, data: &[u8]) -> usize {
+        self.textures
+            .insert_raw_linear(&self.gpu.device, &self.gpu.queue, w, h, data)
+    }
+
+    /// Adds a texture from image bytes whose UVs tile outside `0..1` instead of clamping,
+    /// for scrolling backgrounds & repeating patterns. Returns its id
+    pub fn add_texture_repeating(&mut self, data: &[u8]) -> usize {
+        self.textures
+            .insert_repeating(&self.gpu.device, &self.gpu.queue, data)
+    }
+
+    /// Like [`Self::add_texture_repeating`], from raw RGBA bytes
+    pub fn add_texture_raw_repeating(&mut self, w: u32, h: u32, data: &[u8]) -> usize {
+        self.textures
+            .insert_raw_repeating(&self.gpu.device, &self.gpu.queue, w, h, data)
+    }
+
+    /// Adds image bytes as the next layer of a texture array & returns the array's id with the
+    /// layer. Sprites sharing the id draw in one batch, each picking its layer through
+    /// [`instance::Instance::with_layer`], saving a bind group switch per texture.
+    /// The first image sets the array's size; others of a different size, or every image on
+    /// devices without texture arrays (see [`Self::supports_texture_arrays`]), become
+    /// standalone textures at layer 0. Arrays always draw with the built-in shader
+    pub fn add_texture_into_array(&mut self, data: &[u8]) -> (usize, u32) {
+        self.textures
+            .insert_into_array(&self.gpu.device, &self.gpu.queue, data)
+    }
+
+    /// Like [`Self::add_texture_into_array`], from raw RGBA bytes
+    pub fn add_texture_raw_into_array(&mut self, w: u32, h: u32, data: &[u8]) -> (usize, u32) {
+        self.textures
+            .insert_raw_into_array(&self.gpu.device, &self.gpu.queue, (w, h), data)
+    }
+
+    /// Whether the device supports texture arrays, see [`Self::add_texture_into_array`]
+    pub fn supports_texture_arrays(&self) -> bool {
+        self.textures.supports_arrays()
+    }
+
+    /// Overwrites a `w` x `h` region at (`x`, `y`) of a texture with tightly packed RGBA data,
+    /// uploading only that region into the existing texture. Cheaper than
+    /// [`Self::update_texture_raw`] for streamed textures where little changes per frame
+    pub fn update_texture_region(
+        &mut self,
+        index: usize,
+        x: u32,
+        y: u32,
+        w: u32,
+        h: u32,
+        data: &[u8],
+    ) -> Result<(), TextureRegionError> {
+        self.record_upload(index, data.len());
         self.textures
+            .write_region(&self.gpu.queue, index, (x, y), (w, h), data)
+    }
+
+    /// Replaces an existing texture with new image data, keeping how it's sampled.
+    /// Texture arrays are left untouched
+    pub fn update_texture(&mut self, index: usize, data: &[u8]) {
+        let bytes = self
+            .textures
             .replace(&self.gpu.device, &self.gpu.queue, index, data);
+        self.record_upload(index, bytes);
     }
 
-    /// Replaces an existing texture with raw RGBA bytes
+    /// Replaces an existing texture with raw RGBA bytes. Same-sized data is written into the
+    /// texture; a new size recreates it
     pub fn update_texture_raw(&mut self, index: usize, w: u32, h: u32, data: &[u8]) {
+        self.record_upload(index, data.len());
         self.textures
             .replace_raw(&self.gpu.device, &self.gpu.queue, index, w, h, data);
     }
 
+    /// Drops a texture & frees its GPU memory once no submitted frame still samples it.
+    /// The id isn't reused; drawing with it afterwards draws the default white texture.
+    /// Views of offscreen targets free nothing, the target owns their memory
+    pub fn unload_texture(&mut self, index: usize) {
+        self.textures.remove(index);
+    }
+
+    /// Estimated GPU memory of the textures, offscreen targets & buffers created through this
+    /// renderer, kept up to date as they're replaced, resized, grown & dropped
+    pub fn memory_report(&self) -> MemoryReport {
+        self.memory.report(self.textures.sizes())
+    }
+
+    /// Estimated bytes of textures, texture arrays & offscreen targets alone. Cheaper than
+    /// [`Self::memory_report`] for checking a budget every frame
+    pub fn texture_memory(&self) -> u64 {
+        self.memory.texture_bytes()
+    }
+
+    fn record_upload(&mut self, index: usize, bytes: usize) {
+        match self.texture_uploads.iter_mut().find(|(id, _)| *id == index) {
+            Some((_, total)) => *total += bytes,
+            None => self.texture_uploads.push((index, bytes)),
+        }
+    }
+
+    /// Texture ids & how many bytes were uploaded to each through `update_texture*` &
+    /// `*_texture_region` since [`Self::clear_texture_uploads`]
+    pub fn texture_uploads(&self) -> &[(usize, usize)] {
+        &self.texture_uploads
+    }
+
+    pub fn clear_texture_uploads(&mut self) {
+        self.texture_uploads.clear();
+    }
+
+    /// Uploads geometry once & returns a mesh id. Draw it by pushing instances into a
+    /// [`GeometryBatch`] with [`GeometryBatch::set_mesh`]; it stays on the GPU until
+    /// [`Self::free_mesh`]
+    pub fn add_mesh(&mut self, vertices: &[Vertex], indices: &[u16]) -> usize {
+        self.meshes
+            .insert(&self.gpu.device, &self.memory, vertices, indices)
+    }
+
+    /// Releases a mesh's GPU buffers. Its id may be reused by the next [`Self::add_mesh`]
+    pub fn free_mesh(&mut self, id: usize) {
+        self.meshes.remove(id);
+    }
+
     /// Creates a uniform buffer and returns its id
     pub fn add_uniform(&mut self, data: &[u8]) -> usize {
         self.uniforms.insert(&self.gpu.device, data)
@@ -291,8 +723,17 @@ impl Renderer {
         self.uniforms.write(&self.gpu.queue, id, data);
     }
 
-    /// Creates a custom shader pipeline from WGSL source code
-    /// Returns the pipeline index for use in draw calls
+    /// WGSL a [`PRELUDE_DIRECTIVE`] line in a custom shader is replaced with: egor's texture &
+    /// camera bindings, vertex, instance & output structs & `egor_vertex`, the built-in vertex
+    /// transform. Shaders without their own `vs_main` get one calling `egor_vertex`
+    pub fn shader_prelude(&self) -> String {
+        pipeline::shader_prelude()
+    }
+
+    /// Creates a custom shader pipeline from WGSL source code, expanding a
+    /// [`PRELUDE_DIRECTIVE`] line into [`Self::shader_prelude`].
+    /// Returns the pipeline index for use in draw calls. Shaders that fail to compile show up
+    /// in [`Self::shader_errors`] & draw with the built-in pipeline
     pub fn add_shader(&mut self, wgsl_source: &str) -> usize {
         self.pipelines
             .add_custom(&self.gpu.device, self.surface_format, wgsl_source, &[], &[])
@@ -314,4 +755,86 @@ impl Renderer {
             uniform_ids,
         )
     }
+
+    /// Recompiles shader `shader_id` from new WGSL source, e.g. after its file changed. Until
+    /// a version compiles, the last one that did keeps drawing
+    pub fn replace_shader(&mut self, shader_id: usize, wgsl_source: &str) {
+        let layouts = vec![self.uniforms.layout(); self.pipelines.uniform_count(shader_id)];
+        self.pipelines.replace_custom(
+            &self.gpu.device,
+            self.surface_format,
+            shader_id,
+            wgsl_source,
+            &layouts,
+        );
+    }
+
+    /// Errors of custom shaders whose latest source failed to compile, cleared once a shader
+    /// compiles. On WebGPU they arrive a frame or so after the shader was added
+    pub fn shader_errors(&self) -> &[ShaderError] {
+        self.pipelines.errors()
+    }
+}
+
+/// Device of wgpu's no-op backend, for tests that create resources without a GPU
+#[cfg(test)]
+pub(crate) fn noop_device() -> (Device, Queue) {
+    use wgpu::{BackendOptions, InstanceDescriptor, NoopBackendOptions, RequestAdapterOptions};
+
+    let instance = Instance::new(&InstanceDescriptor {
+        backends: Backends::NOOP,
+        backend_options: BackendOptions {
+            noop: NoopBackendOptions { enable: true },
+            ..Default::default()
+        },
+        ..Default::default()
+    });
+    let adapter =
+        pollster::block_on(instance.request_adapter(&RequestAdapterOptions::default())).unwrap();
+    pollster::block_on(adapter.request_device(&Default::default())).unwrap()
+}
+
+/// [`GpuContext`] on wgpu's no-op backend, as renderers get it
+#[cfg(test)]
+pub(crate) fn noop_context() -> GpuContext {
+    let request = DeviceRequest::default();
+    pollster::block_on(GpuContext::new(
+        Backends::NOOP,
+        &request,
+        &MemoryHints::Performance,
+    ))
+    .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renderers_sharing_a_context_draw_with_one_device() {
+        let context = noop_context();
+        assert_eq!(context.gpu_downgrade(), None);
+        let mut first = Renderer::with_context(context.clone(), TextureFormat::Rgba8UnormSrgb);
+        let second = Renderer::with_context(context, TextureFormat::Bgra8Unorm);
+        assert_eq!(first.device(), second.device());
+        assert_eq!(first.queue(), second.queue());
+        assert_eq!(second.surface_format(), TextureFormat::Bgra8Unorm);
+
+        // textures & memory accounting stay with the renderer that made them
+        let before = second.texture_count();
+        first.add_texture_raw(2, 2, &[255; 16]);
+        assert_eq!(first.texture_count(), before + 1);
+        assert_eq!(second.texture_count(), before);
+        let bytes = |renderer: &Renderer| renderer.memory_report().textures_bytes;
+        assert_eq!(bytes(&first), bytes(&second) + 16);
+    }
+
+    #[test]
+    fn camera_slots_count_as_uniforms() {
+        let mut renderer = Renderer::with_context(noop_context(), TextureFormat::Rgba8UnormSrgb);
+        let before = renderer.memory_report().buffers_bytes;
+        renderer.upload_camera_matrix_to(2, [[0.0; 4]; 4]);
+        let slot = size_of::<CameraUniform>() as u64;
+        assert_eq!(renderer.memory_report().buffers_bytes, before + 2 * slot);
+    }
 }
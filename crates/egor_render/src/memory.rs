@@ -0,0 +1,221 @@
+//! Estimated GPU memory of what the renderer allocates, see [`crate::Renderer::memory_report`]
+
+use std::{
+    cmp::Reverse,
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+use wgpu::{Extent3d, TextureDimension, TextureFormat};
+
+/// Most textures listed in [`MemoryReport::largest`]
+pub const LARGEST_TEXTURES: usize = 8;
+
+/// What an allocation is for, the categories of [`MemoryReport::by_category`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum MemoryCategory {
+    Textures,
+    TextureArrays,
+    OffscreenTargets,
+    Geometry,
+    Meshes,
+    Uniforms,
+}
+
+impl MemoryCategory {
+    const ALL: [Self; 6] = [
+        Self::Textures,
+        Self::TextureArrays,
+        Self::OffscreenTargets,
+        Self::Geometry,
+        Self::Meshes,
+        Self::Uniforms,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Textures => "textures",
+            Self::TextureArrays => "texture arrays",
+            Self::OffscreenTargets => "offscreen targets",
+            Self::Geometry => "geometry",
+            Self::Meshes => "meshes",
+            Self::Uniforms => "uniforms",
+        }
+    }
+
+    fn is_texture(self) -> bool {
+        matches!(
+            self,
+            Self::Textures | Self::TextureArrays | Self::OffscreenTargets
+        )
+    }
+}
+
+/// Bytes allocated per category by one renderer, shared with everything it allocates
+#[derive(Clone, Default)]
+pub(crate) struct MemoryTracker(Arc<[AtomicU64; MemoryCategory::ALL.len()]>);
+
+impl MemoryTracker {
+    /// Counts `bytes` of `category` until the returned allocation is dropped
+    pub fn track(&self, category: MemoryCategory, bytes: u64) -> Allocation {
+        self.0[category as usize].fetch_add(bytes, Ordering::Relaxed);
+        Allocation {
+            tracker: self.clone(),
+            category,
+            bytes,
+        }
+    }
+
+    pub fn bytes(&self, category: MemoryCategory) -> u64 {
+        self.0[category as usize].load(Ordering::Relaxed)
+    }
+
+    /// Bytes of every texture category
+    pub fn texture_bytes(&self) -> u64 {
+        let textures = MemoryCategory::ALL.into_iter().filter(|c| c.is_texture());
+        textures.map(|c| self.bytes(c)).sum()
+    }
+
+    /// Totals of every category, with `largest` textures as ids & bytes
+    pub fn report(&self, mut largest: Vec<(usize, u64)>) -> MemoryReport {
+        largest.sort_by_key(|&(_, bytes)| Reverse(bytes));
+        largest.truncate(LARGEST_TEXTURES);
+        let (textures, buffers) = MemoryCategory::ALL
+            .into_iter()
+            .partition::<Vec<_>, _>(|c| c.is_texture());
+        MemoryReport {
+            textures_bytes: textures.into_iter().map(|c| self.bytes(c)).sum(),
+            buffers_bytes: buffers.into_iter().map(|c| self.bytes(c)).sum(),
+            by_category: MemoryCategory::ALL
+                .into_iter()
+                .map(|c| (c.name().to_owned(), self.bytes(c)))
+                .collect(),
+            largest,
+        }
+    }
+}
+
+/// Bytes of one GPU resource, counted by its [`MemoryTracker`] until dropped along with it
+pub(crate) struct Allocation {
+    tracker: MemoryTracker,
+    category: MemoryCategory,
+    bytes: u64,
+}
+
+impl Allocation {
+    pub fn bytes(&self) -> u64 {
+        self.bytes
+    }
+
+    pub fn tracker(&self) -> &MemoryTracker {
+        &self.tracker
+    }
+}
+
+impl Drop for Allocation {
+    fn drop(&mut self) {
+        self.tracker.0[self.category as usize].fetch_sub(self.bytes, Ordering::Relaxed);
+    }
+}
+
+/// Estimated bytes of a texture in its format, compressed blocks included, over all of its
+/// mip levels & layers
+pub(crate) fn texture_bytes(texture: &wgpu::Texture) -> u64 {
+    estimate(texture.format(), texture.size(), texture.mip_level_count())
+}
+
+fn estimate(format: TextureFormat, size: Extent3d, mip_levels: u32) -> u64 {
+    let (block_width, block_height) = format.block_dimensions();
+    // depth & multi-planar formats have no single block size, count them as 4 bytes a pixel
+    let block_size = format.block_copy_size(None).unwrap_or(4) as u64;
+    (0..mip_levels)
+        .map(|level| {
+            let mip = size.mip_level_size(level, TextureDimension::D2);
+            let mip = mip.physical_size(format);
+            let blocks = (mip.width / block_width) as u64 * (mip.height / block_height) as u64;
+            blocks * block_size * mip.depth_or_array_layers as u64
+        })
+        .sum()
+}
+
+/// Estimated GPU memory allocated through the renderer, see
+/// [`crate::Renderer::memory_report`]. The surface's own textures aren't included, nor is
+/// anything allocated outside the renderer on its device, such as text & egui atlases
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MemoryReport<T = usize> {
+    /// Textures, texture arrays & offscreen targets
+    pub textures_bytes: u64,
+    /// Geometry, mesh & uniform buffers, camera slots included
+    pub buffers_bytes: u64,
+    /// Bytes of every kind of allocation by name
+    pub by_category: Vec<(String, u64)>,
+    /// Texture ids & bytes of the largest textures, largest first. Offscreen targets only
+    /// count towards their category
+    pub largest: Vec<(T, u64)>,
+}
+
+impl<T> MemoryReport<T> {
+    pub fn total_bytes(&self) -> u64 {
+        self.textures_bytes + self.buffers_bytes
+    }
+
+    /// Turns the texture ids of [`Self::largest`] into another id type
+    pub fn map_textures<U>(self, mut f: impl FnMut(T) -> U) -> MemoryReport<U> {
+        MemoryReport {
+            textures_bytes: self.textures_bytes,
+            buffers_bytes: self.buffers_bytes,
+            by_category: self.by_category,
+            largest: self
+                .largest
+                .into_iter()
+                .map(|(id, bytes)| (f(id), bytes))
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texture_of(format: TextureFormat, size: u32, mips: u32, layers: u32) -> u64 {
+        let size = Extent3d {
+            width: size,
+            height: size,
+            depth_or_array_layers: layers,
+        };
+        estimate(format, size, mips)
+    }
+
+    #[test]
+    fn texture_estimates_follow_the_format() {
+        assert_eq!(texture_of(TextureFormat::Rgba8UnormSrgb, 8, 1, 1), 256);
+        assert_eq!(texture_of(TextureFormat::Rgba16Float, 8, 1, 3), 3 * 512);
+        // 8x8 + 4x4 + 2x2 + 1x1
+        assert_eq!(texture_of(TextureFormat::R8Unorm, 8, 4, 1), 85);
+        // 16 byte 4x4 blocks, mips below a block still take a whole one
+        assert_eq!(
+            texture_of(TextureFormat::Bc7RgbaUnormSrgb, 8, 4, 1),
+            (4 + 1 + 1 + 1) * 16
+        );
+    }
+
+    #[test]
+    fn dropped_allocations_are_no_longer_counted() {
+        let tracker = MemoryTracker::default();
+        let texture = tracker.track(MemoryCategory::Textures, 100);
+        let target = tracker.track(MemoryCategory::OffscreenTargets, 50);
+        let mesh = tracker.track(MemoryCategory::Meshes, 30);
+        assert_eq!(tracker.texture_bytes(), 150);
+
+        let report = tracker.report(vec![(0, 10), (2, 100), (1, 40)]);
+        assert_eq!((report.textures_bytes, report.buffers_bytes), (150, 30));
+        assert_eq!(report.largest, [(2, 100), (1, 40), (0, 10)]);
+        assert!(report.by_category.contains(&("meshes".to_owned(), 30)));
+
+        drop((texture, target, mesh));
+        assert_eq!(tracker.report(Vec::new()).total_bytes(), 0);
+    }
+}
@@ -0,0 +1,69 @@
+use wgpu::{
+    Buffer, BufferUsages, Device,
+    util::{BufferInitDescriptor, DeviceExt},
+};
+
+use crate::{
+    memory::{Allocation, MemoryCategory, MemoryTracker},
+    vertex::Vertex,
+};
+
+/// Geometry uploaded once & kept on the GPU, drawn by instancing it like the unit quad
+pub(crate) struct Mesh {
+    pub vertex_buffer: Buffer,
+    pub index_buffer: Buffer,
+    pub index_count: u32,
+    _memory: Allocation,
+}
+
+/// Retained meshes by id. Freed slots are reused by the next insert
+#[derive(Default)]
+pub(crate) struct Meshes {
+    store: Vec<Option<Mesh>>,
+}
+
+impl Meshes {
+    pub fn insert(
+        &mut self,
+        device: &Device,
+        memory: &MemoryTracker,
+        vertices: &[Vertex],
+        indices: &[u16],
+    ) -> usize {
+        let bytes = size_of_val(vertices) + size_of_val(indices);
+        let mesh = Mesh {
+            vertex_buffer: device.create_buffer_init(&BufferInitDescriptor {
+                label: Some("Mesh VB"),
+                contents: bytemuck::cast_slice(vertices),
+                usage: BufferUsages::VERTEX,
+            }),
+            index_buffer: device.create_buffer_init(&BufferInitDescriptor {
+                label: Some("Mesh IB"),
+                contents: bytemuck::cast_slice(indices),
+                usage: BufferUsages::INDEX,
+            }),
+            index_count: indices.len() as u32,
+            _memory: memory.track(MemoryCategory::Meshes, bytes as u64),
+        };
+        match self.store.iter().position(Option::is_none) {
+            Some(id) => {
+                self.store[id] = Some(mesh);
+                id
+            }
+            None => {
+                self.store.push(Some(mesh));
+                self.store.len() - 1
+            }
+        }
+    }
+
+    pub fn get(&self, id: usize) -> Option<&Mesh> {
+        self.store.get(id)?.as_ref()
+    }
+
+    pub fn remove(&mut self, id: usize) {
+        if let Some(slot) = self.store.get_mut(id) {
+            *slot = None;
+        }
+    }
+}
@@ -1,27 +1,155 @@
+use std::{
+    borrow::Cow,
+    pin::Pin,
+    task::{Context, Poll, Waker},
+};
+
 use wgpu::{
-    BindGroupLayout, BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingType, BlendState,
-    BufferBindingType, ColorTargetState, ColorWrites, Device, FragmentState,
+    BindGroupLayout, BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingType, BlendComponent,
+    BlendState, BufferBindingType, ColorTargetState, ColorWrites, Device, FragmentState,
     PipelineLayoutDescriptor, RenderPipeline, RenderPipelineDescriptor, SamplerBindingType,
     ShaderModuleDescriptor, ShaderSource, ShaderStages, TextureFormat, TextureSampleType,
-    TextureViewDimension, VertexState, include_wgsl,
+    TextureViewDimension, VertexBufferLayout, VertexFormat, VertexState, include_wgsl,
+};
+
+use crate::{instance::Instance, shader_error::ShaderError, vertex::Vertex};
+
+/// Straight alpha blending for color, with alpha accumulating coverage like premultiplied
+/// blending does. Drawing over a transparent clear keeps the target premultiplied, which
+/// transparent windows are composited as
+const BLEND: BlendState = BlendState {
+    color: BlendState::ALPHA_BLENDING.color,
+    alpha: BlendComponent::OVER,
 };
 
-use crate::{instance::Instance, vertex::Vertex};
+/// Line of a custom shader that's replaced by [`crate::Renderer::shader_prelude`]
+pub const PRELUDE_DIRECTIVE: &str = "//! egor:prelude";
+
+/// Output of `egor_vertex`, the built-in vertex transform, & the default `vs_main` built on it
+const VERTEX_OUTPUT: &str = "struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+    @location(1) tex_coords: vec2<f32>,
+    @location(2) @interpolate(flat) color_mode: u32,
+    @location(3) @interpolate(flat) params: vec4<f32>,
+}
+fn egor_vertex(vert: VertexInput, inst: InstanceInput) -> VertexOutput {
+    let rotscale = mat2x2<f32>(inst.affine.xy, inst.affine.zw);
+    let world_pos = rotscale * vert.position + inst.translate;
+    var out: VertexOutput;
+    out.position = camera.view_proj * vec4<f32>(world_pos, 0.0, 1.0);
+    out.color = vert.color * inst.color;
+    out.tex_coords = mix(inst.uv.xy, inst.uv.zw, vert.tex_coords);
+    out.color_mode = inst.color_mode;
+    out.params = inst.params;
+    return out;
+}
+";
+
+const DEFAULT_VS_MAIN: &str = "@vertex
+fn vs_main(vert: VertexInput, inst: InstanceInput) -> VertexOutput {
+    return egor_vertex(vert, inst);
+}
+";
+
+/// WGSL declaring what custom shaders share with the built-in pipeline: the texture & camera
+/// bindings, `VertexInput`, `InstanceInput`, `VertexOutput` & `egor_vertex`, the built-in
+/// vertex transform. The inputs are generated from the buffer layouts, so they can't drift
+pub(crate) fn shader_prelude() -> String {
+    let mut wgsl = String::from(
+        "@group(0) @binding(0) var texture_binding: texture_2d<f32>;
+@group(0) @binding(1) var texture_sampler: sampler;
+struct CameraUniform {
+    view_proj: mat4x4<f32>,
+}
+@group(1) @binding(0) var<uniform> camera: CameraUniform;
+",
+    );
+    push_input_struct(
+        &mut wgsl,
+        "VertexInput",
+        &Vertex::desc(),
+        &Vertex::ATTRIBUTE_NAMES,
+    );
+    push_input_struct(
+        &mut wgsl,
+        "InstanceInput",
+        &Instance::desc(),
+        &Instance::ATTRIBUTE_NAMES,
+    );
+    wgsl.push_str(VERTEX_OUTPUT);
+    wgsl
+}
+
+fn push_input_struct(wgsl: &mut String, name: &str, layout: &VertexBufferLayout, fields: &[&str]) {
+    wgsl.push_str(&format!("struct {name} {{\n"));
+    for (attribute, field) in layout.attributes.iter().zip(fields) {
+        let ty = match attribute.format {
+            VertexFormat::Float32x2 => "vec2<f32>",
+            VertexFormat::Float32x4 => "vec4<f32>",
+            VertexFormat::Uint32 => "u32",
+            format => unreachable!("no WGSL type for {format:?} in the prelude"),
+        };
+        let location = attribute.shader_location;
+        wgsl.push_str(&format!("    @location({location}) {field}: {ty},\n"));
+    }
+    wgsl.push_str("}\n");
+}
+
+/// Replaces the [`PRELUDE_DIRECTIVE`] line of `source` with [`shader_prelude`] & the default
+/// `vs_main` when the shader has none. It's all spliced onto that one line, so errors in the
+/// shader still point at the lines of the user's file
+pub(crate) fn expand_prelude(source: &str) -> Cow<'_, str> {
+    let mut start = 0;
+    for line in source.split_inclusive('\n') {
+        let end = start + line.trim_end_matches(['\r', '\n']).len();
+        if line.trim() == PRELUDE_DIRECTIVE {
+            let mut prelude = shader_prelude();
+            if !source.contains("fn vs_main") {
+                prelude.push_str(DEFAULT_VS_MAIN);
+            }
+            let prelude = prelude.replace('\n', " ");
+            return format!("{}{prelude}{}", &source[..start], &source[end..]).into();
+        }
+        start += line.len();
+    }
+    source.into()
+}
 
 pub(crate) struct CustomPipeline {
-    pipeline: RenderPipeline,
+    /// Last pipeline that compiled, `None` until one does
+    pipeline: Option<RenderPipeline>,
     uniform_ids: Vec<usize>,
 }
 
+/// A popped error scope. It's `Send` off the web so [`crate::Renderer`] stays `Send`
+#[cfg(not(target_arch = "wasm32"))]
+type ErrorScope = Pin<Box<dyn Future<Output = Option<wgpu::Error>> + Send>>;
+#[cfg(target_arch = "wasm32")]
+type ErrorScope = Pin<Box<dyn Future<Output = Option<wgpu::Error>>>>;
+
+/// Validation of a custom pipeline, swapped in once its error scope comes back clean.
+/// Native backends resolve the scope at once; WebGPU in a later frame
+struct Compile {
+    shader_id: usize,
+    pipeline: RenderPipeline,
+    source: String,
+    error: ErrorScope,
+}
+
 /// Contains all render pipelines and bind group layouts for [`crate::Renderer`]
 ///
 /// Centralizes GPU pipeline configuration, including:
 /// - The main primitive rendering pipeline (textured quads, sprites, shapes)
-/// - Texture bind group layout (for sampling textures in shaders)
+/// - Its twin sampling texture arrays by each instance's layer
+/// - Texture bind group layouts (for sampling textures in shaders)
 /// - Camera bind group layout (for view/projection transforms)
 pub(crate) struct Pipelines {
     primitive: RenderPipeline,
+    array_primitive: RenderPipeline,
     custom: Vec<CustomPipeline>,
+    compiling: Vec<Compile>,
+    errors: Vec<ShaderError>,
     texture_layout: BindGroupLayout,
     pub camera_layout: BindGroupLayout,
 }
@@ -29,21 +157,39 @@ pub(crate) struct Pipelines {
 impl Pipelines {
     /// Creates all pipelines and bind group layouts for the given device and surface format
     pub fn new(device: &Device, surface_format: TextureFormat) -> Self {
-        let texture_layout = create_texture_bind_group_layout(device);
+        let texture_layout = create_texture_bind_group_layout(device, TextureViewDimension::D2);
+        let array_texture_layout =
+            create_texture_bind_group_layout(device, TextureViewDimension::D2Array);
         let camera_layout = create_camera_bind_group_layout(device);
 
-        let primitive =
-            create_primitive_pipeline(device, surface_format, &texture_layout, &camera_layout);
+        let primitive = create_primitive_pipeline(
+            device,
+            surface_format,
+            &texture_layout,
+            &camera_layout,
+            include_wgsl!("../shader.wgsl"),
+        );
+        let array_primitive = create_primitive_pipeline(
+            device,
+            surface_format,
+            &array_texture_layout,
+            &camera_layout,
+            include_wgsl!("../shader_array.wgsl"),
+        );
 
         Self {
             primitive,
+            array_primitive,
             custom: Vec::new(),
+            compiling: Vec::new(),
+            errors: Vec::new(),
             texture_layout,
             camera_layout,
         }
     }
 
-    /// Creates a custom shader pipeline from WGSL source
+    /// Creates a custom shader pipeline from WGSL source. Shaders that fail to compile draw
+    /// with the built-in pipeline until they're replaced with one that does
     pub fn add_custom(
         &mut self,
         device: &Device,
@@ -52,6 +198,32 @@ impl Pipelines {
         uniform_layouts: &[&BindGroupLayout],
         uniform_ids: &[usize],
     ) -> usize {
+        self.custom.push(CustomPipeline {
+            pipeline: None,
+            uniform_ids: uniform_ids.to_vec(),
+        });
+        let shader_id = self.custom.len() - 1;
+        self.replace_custom(
+            device,
+            surface_format,
+            shader_id,
+            wgsl_source,
+            uniform_layouts,
+        );
+        shader_id
+    }
+
+    /// Recompiles custom shader `shader_id` from new WGSL source, keeping its last pipeline
+    /// that compiled if this one doesn't
+    pub fn replace_custom(
+        &mut self,
+        device: &Device,
+        surface_format: TextureFormat,
+        shader_id: usize,
+        wgsl_source: &str,
+        uniform_layouts: &[&BindGroupLayout],
+    ) {
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
         let pipeline = create_custom_pipeline(
             device,
             surface_format,
@@ -60,19 +232,54 @@ impl Pipelines {
             uniform_layouts,
             wgsl_source,
         );
-
-        self.custom.push(CustomPipeline {
+        self.compiling.push(Compile {
+            shader_id,
             pipeline,
-            uniform_ids: uniform_ids.to_vec(),
+            source: wgsl_source.to_owned(),
+            error: Box::pin(device.pop_error_scope()),
         });
-        self.custom.len() - 1
+        self.poll_compiles();
+    }
+
+    /// Swaps in pipelines whose validation came back clean & records the errors of those that
+    /// didn't, replacing the shader's previous errors either way
+    pub fn poll_compiles(&mut self) {
+        let mut cx = Context::from_waker(Waker::noop());
+        self.compiling.retain_mut(|compile| {
+            let Poll::Ready(error) = compile.error.as_mut().poll(&mut cx) else {
+                return true;
+            };
+            let shader_id = compile.shader_id;
+            self.errors.retain(|error| error.shader_id != shader_id);
+            match error {
+                Some(error) => {
+                    let error = ShaderError::new(shader_id, &compile.source, &error);
+                    self.errors.push(error);
+                }
+                None => self.custom[shader_id].pipeline = Some(compile.pipeline.clone()),
+            }
+            false
+        });
+    }
+
+    /// Errors of custom shaders whose last compile failed
+    pub fn errors(&self) -> &[ShaderError] {
+        &self.errors
+    }
+
+    /// Uniform ids bound after the built-in groups by custom shader `shader_id`
+    pub fn uniform_count(&self, shader_id: usize) -> usize {
+        self.custom[shader_id].uniform_ids.len()
     }
 
-    pub fn resolve(&self, shader_id: Option<usize>) -> (&RenderPipeline, &[usize]) {
-        if let Some(custom) = shader_id.and_then(|id| self.custom.get(id)) {
-            (&custom.pipeline, &custom.uniform_ids)
-        } else {
-            (&self.primitive, &[])
+    /// Pipeline & uniform ids drawing with `shader_id`. Custom shaders only sample plain 2D
+    /// textures, so texture arrays always draw with the built-in shader
+    pub fn resolve(&self, shader_id: Option<usize>, array: bool) -> (&RenderPipeline, &[usize]) {
+        let custom = shader_id.and_then(|id| self.custom.get(id));
+        match custom.and_then(|custom| Some((custom.pipeline.as_ref()?, &custom.uniform_ids))) {
+            _ if array => (&self.array_primitive, &[]),
+            Some((pipeline, uniform_ids)) => (pipeline, uniform_ids),
+            None => (&self.primitive, &[]),
         }
     }
 }
@@ -80,9 +287,12 @@ impl Pipelines {
 /// Creates the bind group layout for texture sampling
 ///
 /// Defines two bindings:
-/// - Binding 0: 2D texture (fragment shader)
+/// - Binding 0: 2D texture or texture array, per `view_dimension` (fragment shader)
 /// - Binding 1: Sampler (fragment shader)
-fn create_texture_bind_group_layout(device: &Device) -> BindGroupLayout {
+fn create_texture_bind_group_layout(
+    device: &Device,
+    view_dimension: TextureViewDimension,
+) -> BindGroupLayout {
     device.create_bind_group_layout(&BindGroupLayoutDescriptor {
         label: Some("Texture Bind Group Layout"),
         entries: &[
@@ -91,7 +301,7 @@ fn create_texture_bind_group_layout(device: &Device) -> BindGroupLayout {
                 visibility: ShaderStages::FRAGMENT,
                 ty: BindingType::Texture {
                     sample_type: TextureSampleType::Float { filterable: true },
-                    view_dimension: TextureViewDimension::D2,
+                    view_dimension,
                     multisampled: false,
                 },
                 count: None,
@@ -138,8 +348,9 @@ fn create_primitive_pipeline(
     surface_format: TextureFormat,
     texture_layout: &BindGroupLayout,
     camera_layout: &BindGroupLayout,
+    shader: ShaderModuleDescriptor,
 ) -> RenderPipeline {
-    let shader = device.create_shader_module(include_wgsl!("../shader.wgsl"));
+    let shader = device.create_shader_module(shader);
 
     let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
         label: Some("Primitive Pipeline Layout"),
@@ -164,7 +375,7 @@ fn create_primitive_pipeline(
             entry_point: Some("fs_main"),
             targets: &[Some(ColorTargetState {
                 format: surface_format,
-                blend: Some(BlendState::ALPHA_BLENDING),
+                blend: Some(BLEND),
                 write_mask: ColorWrites::ALL,
             })],
             compilation_options: Default::default(),
@@ -191,7 +402,7 @@ fn create_custom_pipeline(
 ) -> RenderPipeline {
     let shader = device.create_shader_module(ShaderModuleDescriptor {
         label: Some("Custom Shader"),
-        source: ShaderSource::Wgsl(wgsl_source.into()),
+        source: ShaderSource::Wgsl(expand_prelude(wgsl_source)),
     });
 
     let mut layouts: Vec<&BindGroupLayout> = vec![texture_layout, camera_layout];
@@ -220,7 +431,7 @@ fn create_custom_pipeline(
             entry_point: Some("fs_main"),
             targets: &[Some(ColorTargetState {
                 format: surface_format,
-                blend: Some(BlendState::ALPHA_BLENDING),
+                blend: Some(BLEND),
                 write_mask: ColorWrites::ALL,
             })],
             compilation_options: Default::default(),
@@ -229,3 +440,77 @@ fn create_custom_pipeline(
         cache: None,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INVERT: &str = "// invert
+//! egor:prelude
+
+@fragment
+fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
+    let tex = textureSample(texture_binding, texture_sampler, input.tex_coords);
+    return vec4<f32>(1.0 - tex.rgb, tex.a) * input.color;
+}
+";
+
+    #[test]
+    fn the_prelude_fills_in_everything_but_the_fragment_shader() {
+        let expanded = expand_prelude(INVERT);
+        assert_eq!(expanded.lines().count(), INVERT.lines().count());
+        assert!(expanded.contains("fn vs_main"));
+        assert!(expanded.contains("@location(7) color_mode: u32"));
+        assert!(expanded.contains("@location(9) params: vec4<f32>"));
+        assert_eq!(expand_prelude("fn fs_main() {}"), "fn fs_main() {}");
+
+        let (device, _) = crate::noop_device();
+        let mut pipelines = Pipelines::new(&device, TextureFormat::Rgba8UnormSrgb);
+        // builds the pipeline, validating the expanded shader
+        pipelines.add_custom(&device, TextureFormat::Rgba8UnormSrgb, INVERT, &[], &[]);
+        // instance params reach the fragment shader
+        let dissolve = INVERT.replace("tex.a)", "tex.a * step(input.params.x, 0.5))");
+        pipelines.add_custom(&device, TextureFormat::Rgba8UnormSrgb, &dissolve, &[], &[]);
+        assert!(pipelines.errors().is_empty());
+
+        // errors keep the line numbers of the user's file
+        let broken = INVERT.replace("1.0 - tex.rgb", "1.0 - tex.nope");
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let _ = device.create_shader_module(ShaderModuleDescriptor {
+            label: None,
+            source: ShaderSource::Wgsl(expand_prelude(&broken)),
+        });
+        let error = pollster::block_on(device.pop_error_scope()).unwrap();
+        assert!(format!("{error:?}").contains("wgsl:7:"));
+    }
+
+    #[test]
+    fn broken_shaders_keep_the_last_pipeline_that_compiled() {
+        let (device, _) = crate::noop_device();
+        let format = TextureFormat::Rgba8UnormSrgb;
+        let mut pipelines = Pipelines::new(&device, format);
+        let broken = INVERT.replace("1.0 - tex.rgb", "1.0 - tex.nope");
+        let good = pipelines.add_custom(&device, format, INVERT, &[], &[]);
+        let bad = pipelines.add_custom(&device, format, &broken, &[], &[]);
+        let compiled = pipelines.resolve(Some(good), false).0.clone();
+
+        // a shader that never compiled draws with the built-in pipeline
+        assert_eq!(pipelines.resolve(Some(bad), false).0, &pipelines.primitive);
+        let error = &pipelines.errors()[0];
+        assert_eq!(
+            (error.shader_id, error.line, error.col),
+            (bad, Some(7), Some(32))
+        );
+        assert!(error.excerpt.as_ref().unwrap().contains("tex.nope"));
+
+        pipelines.replace_custom(&device, format, good, &broken, &[]);
+        assert_eq!(pipelines.errors().len(), 2);
+        assert_eq!(pipelines.resolve(Some(good), false).0, &compiled);
+
+        // compiling again clears the shader's errors
+        pipelines.replace_custom(&device, format, good, INVERT, &[]);
+        pipelines.replace_custom(&device, format, bad, INVERT, &[]);
+        assert!(pipelines.errors().is_empty());
+        assert_ne!(pipelines.resolve(Some(good), false).0, &compiled);
+    }
+}
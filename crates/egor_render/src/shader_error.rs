@@ -0,0 +1,105 @@
+use std::fmt;
+
+/// A custom shader that failed to compile, see [`crate::Renderer::shader_errors`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ShaderError {
+    pub shader_id: usize,
+    /// 1-based line in the shader's source, when the error points at one. Pipeline errors,
+    /// like a missing entry point, don't
+    pub line: Option<u32>,
+    /// 1-based column on `line`
+    pub col: Option<u32>,
+    /// What went wrong, without wgpu's framing
+    pub message: String,
+    /// Source line the error points at
+    pub excerpt: Option<String>,
+}
+
+impl ShaderError {
+    /// Pulls the location & message out of a validation error of compiling `source`
+    pub(crate) fn new(shader_id: usize, source: &str, error: &wgpu::Error) -> Self {
+        let text = error.to_string();
+        let (line, col) = location(&text).unzip();
+        let excerpt = line
+            .and_then(|line| source.lines().nth(line as usize - 1))
+            .map(str::to_owned);
+        Self {
+            shader_id,
+            line,
+            col,
+            message: message(&text),
+            excerpt,
+        }
+    }
+}
+
+impl fmt::Display for ShaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "shader {}", self.shader_id)?;
+        if let Some(line) = self.line {
+            write!(f, ":{line}")?;
+        }
+        if let Some(col) = self.col {
+            write!(f, ":{col}")?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+impl std::error::Error for ShaderError {}
+
+/// Line & column of naga's `┌─ wgsl:LINE:COL` marker
+fn location(text: &str) -> Option<(u32, u32)> {
+    let (_, rest) = text.split_once("wgsl:")?;
+    let mut numbers = rest.split(|c: char| !c.is_ascii_digit());
+    let line = numbers.next()?.parse().ok()?;
+    let col = numbers.next()?.parse().ok()?;
+    Some((line, col))
+}
+
+/// The shader's parsing or validation error when there's one, else the innermost cause
+fn message(text: &str) -> String {
+    let mut lines = text.lines().map(str::trim).filter(|line| !line.is_empty());
+    if let Some(line) = lines.clone().find(|line| line.starts_with("Shader '"))
+        && let Some((_, message)) = line.split_once("error: ")
+    {
+        return message.to_owned();
+    }
+    lines.next_back().unwrap_or(text).to_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PARSING: &str = "Validation Error
+
+Caused by:
+  In Device::create_shader_module, label = 'Custom Shader'
+
+Shader 'Custom Shader' parsing error: invalid field accessor `nope`
+  ┌─ wgsl:7:32
+  │
+7 │     return vec4<f32>(1.0 - tex.nope, tex.a) * input.color;
+  │                                ^^^^ invalid accessor
+
+
+      invalid field accessor `nope`
+";
+
+    const PIPELINE: &str = "Validation Error
+
+Caused by:
+  In Device::create_render_pipeline, label = 'Custom Pipeline'
+    Error matching ShaderStages(FRAGMENT) shader requirements against the pipeline
+      Unable to find entry point 'fs_main'
+";
+
+    #[test]
+    fn locations_and_messages_come_out_of_wgpu_errors() {
+        assert_eq!(location(PARSING), Some((7, 32)));
+        assert_eq!(message(PARSING), "invalid field accessor `nope`");
+        assert_eq!(location(PIPELINE), None);
+        assert_eq!(message(PIPELINE), "Unable to find entry point 'fs_main'");
+    }
+}
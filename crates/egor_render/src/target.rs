@@ -1,22 +1,111 @@
+pub use image::RgbaImage;
 use wgpu::{
-    Adapter, CommandEncoder, Device, Extent3d, Instance, PresentMode, Surface,
-    SurfaceConfiguration, SurfaceError, SurfaceTarget, Texture, TextureDescriptor,
-    TextureDimension, TextureFormat, TextureUsages, TextureView, WindowHandle,
+    Adapter, Buffer, BufferAsyncError, BufferDescriptor, BufferUsages,
+    COPY_BYTES_PER_ROW_ALIGNMENT, CommandEncoder, CompositeAlphaMode, Device, Extent3d, Instance,
+    MapMode, PresentMode, Queue, Surface, SurfaceConfiguration, SurfaceError, SurfaceTarget,
+    TexelCopyBufferInfo, TexelCopyBufferLayout, Texture, TextureDescriptor, TextureDimension,
+    TextureFormat, TextureUsages, TextureView, WindowHandle,
 };
 
-use crate::frame::Presentable;
+use std::{
+    future::{self, Future},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+    task::{Poll, Waker},
+};
+
+use crate::{
+    frame::Presentable,
+    memory::{Allocation, MemoryCategory, MemoryTracker, texture_bytes},
+};
+
+static NEXT_OFFSCREEN_ID: AtomicU64 = AtomicU64::new(0);
+
+/// A view to render into, plus anything that must be presented afterwards (swapchain)
+pub type AcquiredView = (TextureView, Option<Box<dyn Presentable>>);
 
 /// Trait for render targets (backbuffers, offscreen textures, etc.)
 pub trait RenderTarget {
     fn format(&self) -> TextureFormat;
     fn size(&self) -> (u32, u32);
     /// Returns the view and optionally something that must be presented (swapchain)
-    fn acquire(&mut self, device: &Device) -> Option<(TextureView, Option<Box<dyn Presentable>>)>;
+    fn acquire(&mut self, device: &Device) -> Result<AcquiredView, SurfaceError>;
+    /// Zero-sized requests (e.g. a minimized window) are ignored & keep the current size
     fn resize(&mut self, device: &Device, w: u32, h: u32);
+    /// Recreate the target after it was lost or became outdated.
+    /// Only useful for backbuffer targets
+    fn reconfigure(&mut self, _device: &Device) {}
     /// Only useful for backbuffer targets
     fn set_vsync(&mut self, _device: &Device, _on: bool) {}
 }
 
+/// Acquire a view from `target`, recovering from a lost or outdated surface
+/// by reconfiguring it & retrying once
+///
+/// Returns `Ok(None)` when the frame should be skipped (timeouts, a zero-sized target, or
+/// a surface that is still unusable after the retry). Only unrecoverable errors are returned
+pub(crate) fn acquire_with_recovery(
+    target: &mut dyn RenderTarget,
+    device: &Device,
+) -> Result<Option<AcquiredView>, SurfaceError> {
+    if is_zero_sized(target.size()) {
+        return Ok(None);
+    }
+    let mut retried = false;
+    loop {
+        match target.acquire(device) {
+            Ok(acquired) => return Ok(Some(acquired)),
+            Err(SurfaceError::Lost | SurfaceError::Outdated) if !retried => {
+                target.reconfigure(device);
+                retried = true;
+            }
+            Err(SurfaceError::OutOfMemory) => return Err(SurfaceError::OutOfMemory),
+            Err(SurfaceError::Timeout | SurfaceError::Lost | SurfaceError::Outdated) => {
+                return Ok(None);
+            }
+            Err(e) => {
+                log::error!(target: "egor_render::surface", "surface error: {e:?}");
+                return Ok(None);
+            }
+        }
+    }
+}
+
+/// Whether either side is zero, which wgpu surfaces & textures can't be created with
+fn is_zero_sized((w, h): (u32, u32)) -> bool {
+    w == 0 || h == 0
+}
+
+/// Returns the first of `preferred` that is in `supported`
+pub(crate) fn negotiate_format(
+    supported: &[TextureFormat],
+    preferred: &[TextureFormat],
+) -> Option<TextureFormat> {
+    preferred.iter().copied().find(|f| supported.contains(f))
+}
+
+/// The best of `supported` for compositing a surface with what's behind the window:
+/// premultiplied, then postmultiplied, then whatever the platform sets up
+pub(crate) fn transparent_alpha_mode(
+    supported: &[CompositeAlphaMode],
+) -> Option<CompositeAlphaMode> {
+    [
+        CompositeAlphaMode::PreMultiplied,
+        CompositeAlphaMode::PostMultiplied,
+        CompositeAlphaMode::Inherit,
+    ]
+    .into_iter()
+    .find(|mode| supported.contains(mode))
+}
+
+/// Whether a surface composited with `mode` expects colors premultiplied by their alpha.
+/// Other modes either ignore alpha or multiply it in themselves
+pub(crate) fn premultiplies(mode: CompositeAlphaMode) -> bool {
+    mode == CompositeAlphaMode::PreMultiplied
+}
+
 /// Renders to the window's backbuffer (swapchain)
 pub struct Backbuffer {
     surface: Surface<'static>,
@@ -24,20 +113,51 @@ pub struct Backbuffer {
 }
 
 impl Backbuffer {
+    /// Creates the window's swapchain in `format`, which should match
+    /// [`crate::Renderer::surface_format`] so pipelines can draw into it.
+    /// A zero-sized (minimized) window starts at 1x1 until it's resized
     pub fn new(
         instance: &Instance,
         adapter: &Adapter,
         device: &Device,
         window: impl Into<SurfaceTarget<'static>> + WindowHandle,
+        format: TextureFormat,
         w: u32,
         h: u32,
     ) -> Self {
         let surface = instance.create_surface(window).unwrap();
-        let mut config = surface.get_default_config(adapter, w, h).unwrap();
+        let mut config = surface
+            .get_default_config(adapter, w.max(1), h.max(1))
+            .unwrap();
+        config.format = format;
         config.present_mode = PresentMode::AutoVsync;
         surface.configure(device, &config);
         Self { surface, config }
     }
+
+    /// Let transparent pixels show what's behind the window (which must be created
+    /// transparent) or go back to an opaque surface. Returns whether the surface composites
+    /// alpha, false when the adapter only supports opaque surfaces
+    pub fn set_transparent(
+        &mut self,
+        adapter: &Adapter,
+        device: &Device,
+        transparent: bool,
+    ) -> bool {
+        let supported = self.surface.get_capabilities(adapter).alpha_modes;
+        let mode = match transparent {
+            true => transparent_alpha_mode(&supported),
+            false => Some(CompositeAlphaMode::Auto),
+        };
+        self.config.alpha_mode = mode.unwrap_or(CompositeAlphaMode::Auto);
+        self.surface.configure(device, &self.config);
+        transparent && mode.is_some()
+    }
+
+    /// How the surface is composited with what's behind the window
+    pub fn alpha_mode(&self) -> CompositeAlphaMode {
+        self.config.alpha_mode
+    }
 }
 
 impl RenderTarget for Backbuffer {
@@ -49,28 +169,24 @@ impl RenderTarget for Backbuffer {
         (self.config.width, self.config.height)
     }
 
-    fn acquire(&mut self, device: &Device) -> Option<(TextureView, Option<Box<dyn Presentable>>)> {
-        match self.surface.get_current_texture() {
-            Ok(surface_texture) => {
-                let view = surface_texture.texture.create_view(&Default::default());
-                Some((view, Some(Box::new(surface_texture))))
-            }
-            Err(SurfaceError::Outdated) => {
-                self.resize(device, self.config.width, self.config.height);
-                None
-            }
-            Err(e) => {
-                eprintln!("Surface error: {:?}", e);
-                None
-            }
-        }
+    fn acquire(&mut self, _: &Device) -> Result<AcquiredView, SurfaceError> {
+        let surface_texture = self.surface.get_current_texture()?;
+        let view = surface_texture.texture.create_view(&Default::default());
+        Ok((view, Some(Box::new(surface_texture))))
     }
 
     fn resize(&mut self, device: &Device, w: u32, h: u32) {
+        if is_zero_sized((w, h)) || self.size() == (w, h) {
+            return;
+        }
         (self.config.width, self.config.height) = (w, h);
         self.surface.configure(device, &self.config);
     }
 
+    fn reconfigure(&mut self, device: &Device) {
+        self.surface.configure(device, &self.config);
+    }
+
     fn set_vsync(&mut self, device: &Device, on: bool) {
         self.config.present_mode = if on {
             PresentMode::Fifo
@@ -83,6 +199,7 @@ impl RenderTarget for Backbuffer {
 
 /// Renders to an offscreen texture that can be read back or used as a texture
 pub struct OffscreenTarget {
+    id: u64,
     render_texture: Texture,
     render_view: TextureView,
     sample_texture: Texture,
@@ -90,6 +207,8 @@ pub struct OffscreenTarget {
     format: TextureFormat,
     width: u32,
     height: u32,
+    // counts both textures once created through the renderer, kept across resizes
+    memory: Option<Allocation>,
 }
 
 impl OffscreenTarget {
@@ -128,6 +247,7 @@ impl OffscreenTarget {
         let sample_view = sample_texture.create_view(&Default::default());
 
         Self {
+            id: NEXT_OFFSCREEN_ID.fetch_add(1, Ordering::Relaxed),
             render_texture,
             render_view,
             sample_texture,
@@ -135,9 +255,21 @@ impl OffscreenTarget {
             format,
             width,
             height,
+            memory: None,
         }
     }
 
+    /// Counts the target's textures in `memory` until it's dropped
+    pub(crate) fn track(&mut self, memory: &MemoryTracker) {
+        let bytes = texture_bytes(&self.render_texture) + texture_bytes(&self.sample_texture);
+        self.memory = Some(memory.track(MemoryCategory::OffscreenTargets, bytes));
+    }
+
+    /// Unique identifier of this target, preserved across resizes
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
     pub fn texture(&self) -> &Texture {
         &self.sample_texture
     }
@@ -162,6 +294,162 @@ impl OffscreenTarget {
             },
         );
     }
+
+    /// Read the rendered pixels back to the CPU, blocking until the GPU is done.
+    /// Returns tightly packed rows of pixels in the target's format
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn read_pixels(&self, device: &Device, queue: &Queue) -> Vec<u8> {
+        let (buffer, row_bytes) = self.copy_to_readback(device, queue);
+        let slice = buffer.slice(..);
+        slice.map_async(MapMode::Read, |_| {});
+        let _ = device.poll(wgpu::PollType::Wait);
+
+        let pixels = unpad_rows(&slice.get_mapped_range(), row_bytes);
+        buffer.unmap();
+        pixels
+    }
+
+    /// Like [`Self::read_pixels`] without blocking, for wasm where the GPU can't be waited on.
+    /// The copy is queued right away, so the target can be drawn to again before awaiting.
+    /// On native the future checks the device without waiting each time it's polled & is
+    /// woken once a later submit finishes the copy.
+    /// Resolves to no pixels if the readback buffer couldn't be mapped
+    pub fn read_pixels_async(
+        &self,
+        device: &Device,
+        queue: &Queue,
+    ) -> impl Future<Output = Vec<u8>> + 'static {
+        let (buffer, row_bytes) = self.copy_to_readback(device, queue);
+        let state = Arc::new(Mutex::new(MapState::default()));
+        let callback_state = state.clone();
+        buffer.slice(..).map_async(MapMode::Read, move |result| {
+            let mut state = callback_state.lock().unwrap();
+            state.result = Some(result);
+            if let Some(waker) = state.waker.take() {
+                waker.wake();
+            }
+        });
+        // native backends only run map callbacks when polled, which submits also do
+        #[cfg(not(target_arch = "wasm32"))]
+        let device = device.clone();
+
+        async move {
+            let mapped = future::poll_fn(|cx| {
+                #[cfg(not(target_arch = "wasm32"))]
+                let _ = device.poll(wgpu::PollType::Poll);
+                let mut state = state.lock().unwrap();
+                match state.result.take() {
+                    Some(result) => Poll::Ready(result.is_ok()),
+                    None => {
+                        state.waker = Some(cx.waker().clone());
+                        Poll::Pending
+                    }
+                }
+            })
+            .await;
+            if !mapped {
+                return Vec::new();
+            }
+            let pixels = unpad_rows(&buffer.slice(..).get_mapped_range(), row_bytes);
+            buffer.unmap();
+            pixels
+        }
+    }
+
+    /// Read the rendered pixels back as an RGBA image, blocking until the GPU is done.
+    /// `None` if the target isn't an 8-bit RGBA or BGRA format
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn read_image(&self, device: &Device, queue: &Queue) -> Option<RgbaImage> {
+        rgba_image(
+            self.format,
+            self.width,
+            self.height,
+            self.read_pixels(device, queue),
+        )
+    }
+
+    /// Like [`Self::read_image`] without blocking, see [`Self::read_pixels_async`]
+    pub fn read_image_async(
+        &self,
+        device: &Device,
+        queue: &Queue,
+    ) -> impl Future<Output = Option<RgbaImage>> + 'static {
+        let (format, width, height) = (self.format, self.width, self.height);
+        let pixels = self.read_pixels_async(device, queue);
+        async move { rgba_image(format, width, height, pixels.await) }
+    }
+
+    /// Queue a copy of the render texture into a mappable buffer.
+    /// Returns the buffer & the unpadded bytes per row
+    fn copy_to_readback(&self, device: &Device, queue: &Queue) -> (Buffer, u32) {
+        let row_bytes = self.width * self.format.block_copy_size(None).unwrap_or(4);
+        let padded_row_bytes = padded_row_bytes(row_bytes);
+        let buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("Offscreen Readback Buffer"),
+            size: (padded_row_bytes * self.height) as u64,
+            usage: BufferUsages::COPY_DST | BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = device.create_command_encoder(&Default::default());
+        encoder.copy_texture_to_buffer(
+            self.render_texture.as_image_copy(),
+            TexelCopyBufferInfo {
+                buffer: &buffer,
+                layout: TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_row_bytes),
+                    rows_per_image: None,
+                },
+            },
+            Extent3d {
+                width: self.width,
+                height: self.height,
+                depth_or_array_layers: 1,
+            },
+        );
+        queue.submit(Some(encoder.finish()));
+        (buffer, row_bytes)
+    }
+}
+
+/// Progress of an async buffer map, shared with the map callback
+#[derive(Default)]
+struct MapState {
+    result: Option<Result<(), BufferAsyncError>>,
+    waker: Option<Waker>,
+}
+
+/// Buffer copies require rows aligned to 256 bytes
+fn padded_row_bytes(row_bytes: u32) -> u32 {
+    row_bytes.div_ceil(COPY_BYTES_PER_ROW_ALIGNMENT) * COPY_BYTES_PER_ROW_ALIGNMENT
+}
+
+/// Drops the alignment padding from the end of every row
+fn unpad_rows(mapped: &[u8], row_bytes: u32) -> Vec<u8> {
+    let padded_row_bytes = padded_row_bytes(row_bytes) as usize;
+    let mut pixels = Vec::with_capacity(mapped.len() / padded_row_bytes * row_bytes as usize);
+    for row in mapped.chunks(padded_row_bytes) {
+        pixels.extend_from_slice(&row[..row_bytes as usize]);
+    }
+    pixels
+}
+
+/// Wraps read back pixels as an RGBA image, swizzling BGRA targets
+fn rgba_image(
+    format: TextureFormat,
+    width: u32,
+    height: u32,
+    mut pixels: Vec<u8>,
+) -> Option<RgbaImage> {
+    match format {
+        TextureFormat::Rgba8Unorm | TextureFormat::Rgba8UnormSrgb => {}
+        TextureFormat::Bgra8Unorm | TextureFormat::Bgra8UnormSrgb => {
+            pixels.chunks_exact_mut(4).for_each(|px| px.swap(0, 2));
+        }
+        _ => return None,
+    }
+    RgbaImage::from_raw(width, height, pixels)
 }
 
 impl RenderTarget for OffscreenTarget {
@@ -173,16 +461,192 @@ impl RenderTarget for OffscreenTarget {
         (self.width, self.height)
     }
 
-    fn acquire(&mut self, _: &Device) -> Option<(TextureView, Option<Box<dyn Presentable>>)> {
+    fn acquire(&mut self, _: &Device) -> Result<AcquiredView, SurfaceError> {
         // no presentation needed for offscreen targets
-        Some((self.render_view.clone(), None))
+        Ok((self.render_view.clone(), None))
     }
 
     fn resize(&mut self, device: &Device, w: u32, h: u32) {
-        if self.width == w && self.height == h {
+        if is_zero_sized((w, h)) || (self.width, self.height) == (w, h) {
             return;
         }
         // recreate the texture with new dimensions
+        let id = self.id;
+        let memory = self.memory.take();
         *self = Self::new(device, w, h, self.format);
+        self.id = id;
+        if let Some(memory) = memory {
+            self.track(memory.tracker());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Target that fails with queued errors before handing out views
+    struct FlakyTarget {
+        view: TextureView,
+        size: (u32, u32),
+        errors: Vec<SurfaceError>,
+        reconfigures: usize,
+    }
+
+    impl RenderTarget for FlakyTarget {
+        fn format(&self) -> TextureFormat {
+            TextureFormat::Rgba8Unorm
+        }
+
+        fn size(&self) -> (u32, u32) {
+            self.size
+        }
+
+        fn acquire(&mut self, _: &Device) -> Result<AcquiredView, SurfaceError> {
+            match self.errors.pop() {
+                Some(e) => Err(e),
+                None => Ok((self.view.clone(), None)),
+            }
+        }
+
+        fn resize(&mut self, _: &Device, _: u32, _: u32) {}
+
+        fn reconfigure(&mut self, _: &Device) {
+            self.reconfigures += 1;
+        }
+    }
+
+    fn flaky_target(errors: Vec<SurfaceError>) -> (Device, FlakyTarget) {
+        let (device, _) = crate::noop_device();
+        let view = OffscreenTarget::new(&device, 1, 1, TextureFormat::Rgba8Unorm)
+            .render_view()
+            .clone();
+
+        let target = FlakyTarget {
+            view,
+            size: (1, 1),
+            errors,
+            reconfigures: 0,
+        };
+        (device, target)
+    }
+
+    #[test]
+    fn lost_surface_is_reconfigured_and_retried_once() {
+        let (device, mut target) = flaky_target(vec![SurfaceError::Lost]);
+        let acquired = acquire_with_recovery(&mut target, &device).unwrap();
+        assert!(acquired.is_some());
+        assert_eq!(target.reconfigures, 1);
+
+        // still outdated after the retry, so the frame is skipped
+        target.errors = vec![SurfaceError::Outdated, SurfaceError::Lost];
+        let acquired = acquire_with_recovery(&mut target, &device).unwrap();
+        assert!(acquired.is_none());
+        assert_eq!(target.reconfigures, 2);
+    }
+
+    #[test]
+    fn timeouts_skip_and_out_of_memory_is_reported() {
+        let (device, mut target) = flaky_target(vec![SurfaceError::Timeout]);
+        assert!(
+            acquire_with_recovery(&mut target, &device)
+                .unwrap()
+                .is_none()
+        );
+        assert_eq!(target.reconfigures, 0);
+
+        target.errors = vec![SurfaceError::OutOfMemory];
+        assert!(matches!(
+            acquire_with_recovery(&mut target, &device),
+            Err(SurfaceError::OutOfMemory)
+        ));
+    }
+
+    #[test]
+    fn zero_sized_targets_skip_frames_and_keep_their_size() {
+        // a minimized window's surface can't be acquired, so it isn't tried
+        let (device, mut target) = flaky_target(vec![SurfaceError::Lost]);
+        target.size = (0, 720);
+        assert!(
+            acquire_with_recovery(&mut target, &device)
+                .unwrap()
+                .is_none()
+        );
+        assert_eq!((target.errors.len(), target.reconfigures), (1, 0));
+
+        let mut offscreen = OffscreenTarget::new(&device, 64, 32, TextureFormat::Rgba8Unorm);
+        offscreen.resize(&device, 0, 0);
+        offscreen.resize(&device, 128, 0);
+        assert_eq!(offscreen.size(), (64, 32));
+        offscreen.resize(&device, 128, 16);
+        assert_eq!(offscreen.size(), (128, 16));
+    }
+
+    #[test]
+    fn negotiates_first_supported_preference() {
+        let supported = [TextureFormat::Bgra8UnormSrgb, TextureFormat::Rgba16Float];
+        let preferred = [
+            TextureFormat::Rgb10a2Unorm,
+            TextureFormat::Rgba16Float,
+            TextureFormat::Bgra8UnormSrgb,
+        ];
+        assert_eq!(
+            negotiate_format(&supported, &preferred),
+            Some(TextureFormat::Rgba16Float)
+        );
+        assert_eq!(negotiate_format(&supported, &preferred[..1]), None);
+        assert_eq!(negotiate_format(&supported, &[]), None);
+    }
+
+    #[test]
+    fn transparency_prefers_premultiplied_alpha() {
+        use CompositeAlphaMode::*;
+        assert_eq!(
+            transparent_alpha_mode(&[Opaque, PostMultiplied, PreMultiplied]),
+            Some(PreMultiplied)
+        );
+        assert_eq!(
+            transparent_alpha_mode(&[Inherit, PostMultiplied]),
+            Some(PostMultiplied)
+        );
+        assert_eq!(transparent_alpha_mode(&[Opaque, Inherit]), Some(Inherit));
+        assert_eq!(transparent_alpha_mode(&[Opaque]), None);
+    }
+
+    #[test]
+    fn only_premultiplied_surfaces_premultiply_colors() {
+        use CompositeAlphaMode::*;
+        assert!(premultiplies(PreMultiplied));
+        for mode in [Auto, Opaque, PostMultiplied, Inherit] {
+            assert!(!premultiplies(mode), "{mode:?}");
+        }
+    }
+
+    #[test]
+    fn readback_rows_drop_their_alignment_padding() {
+        // 300 pixels is 1200 bytes, padded to 1280 per row
+        let (width, height) = (300u32, 200u32);
+        let padded = padded_row_bytes(width * 4) as usize;
+        assert_eq!(padded, 1280);
+        let mut mapped = vec![0xAA; padded * height as usize];
+        for (y, row) in mapped.chunks_mut(padded).enumerate() {
+            for (x, px) in row[..width as usize * 4].chunks_mut(4).enumerate() {
+                px.copy_from_slice(&[x as u8, y as u8, (x >> 8) as u8, 255]);
+            }
+        }
+
+        let pixels = unpad_rows(&mapped, width * 4);
+        let image = rgba_image(TextureFormat::Rgba8Unorm, width, height, pixels).unwrap();
+        assert_eq!(image.get_pixel(0, 0).0, [0, 0, 0, 255]);
+        assert_eq!(image.get_pixel(299, 199).0, [43, 199, 1, 255]);
+        assert!(image.pixels().all(|px| px.0[3] == 255));
+    }
+
+    #[test]
+    fn bgra_readback_is_swizzled_and_hdr_is_refused() {
+        let bgra = vec![1, 2, 3, 4];
+        let image = rgba_image(TextureFormat::Bgra8UnormSrgb, 1, 1, bgra).unwrap();
+        assert_eq!(image.get_pixel(0, 0).0, [3, 2, 1, 4]);
+        assert!(rgba_image(TextureFormat::Rgba16Float, 1, 1, vec![0; 8]).is_none());
     }
 }
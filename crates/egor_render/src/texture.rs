@@ -1,19 +1,94 @@
+use std::fmt;
+
 use wgpu::{
     AddressMode, BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout,
     BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingResource, BindingType, Device,
     Extent3d, FilterMode, Origin3d, Queue, RenderPass, Sampler, SamplerBindingType,
     SamplerDescriptor, ShaderStages, TexelCopyBufferLayout, TexelCopyTextureInfo, TextureAspect,
     TextureDescriptor, TextureDimension, TextureFormat, TextureSampleType, TextureUsages,
-    TextureView, TextureViewDimension,
+    TextureView, TextureViewDescriptor, TextureViewDimension,
+};
+
+#[cfg(feature = "ktx2")]
+use crate::compressed::{CompressedImage, CompressedTextureError};
+use crate::{
+    memory::{Allocation, MemoryCategory, MemoryTracker, texture_bytes},
+    target::{OffscreenTarget, RenderTarget},
 };
 
-use crate::target::OffscreenTarget;
+/// How a texture is sampled, kept so replacing its data keeps the same look
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum SamplerKind {
+    /// Nearest filtering & clamped edges
+    #[default]
+    Nearest,
+    /// Linear filtering & clamped edges
+    LinearClamp,
+    /// Nearest filtering, tiling UVs outside `0..1`
+    Repeat,
+}
+
+/// Why a texture region couldn't be written, see [`crate::Renderer::update_texture_region`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextureRegionError {
+    /// No texture has this id
+    UnknownTexture,
+    /// Offscreen, compressed & mipmapped textures & texture arrays can't be written to
+    NotWritable,
+    /// The region reaches past the texture's `size`
+    OutOfBounds { size: (u32, u32) },
+    /// The data isn't `width * height * 4` bytes of the region
+    InvalidData { expected: usize, actual: usize },
+}
+
+impl fmt::Display for TextureRegionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownTexture => write!(f, "no texture with this id"),
+            Self::NotWritable => write!(
+                f,
+                "offscreen, compressed & mipmapped textures & texture arrays can't be written"
+            ),
+            Self::OutOfBounds { size: (w, h) } => {
+                write!(f, "region reaches past the {w}x{h} texture")
+            }
+            Self::InvalidData { expected, actual } => {
+                write!(
+                    f,
+                    "region needs {expected} bytes of RGBA data, got {actual}"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for TextureRegionError {}
+
+/// Layers a texture array first holds. Capacities stay powers of two, never a multiple of 6,
+/// so the GL backend can't mistake an array for a cube map
+const INITIAL_ARRAY_LAYERS: u32 = 4;
+
+/// Layer bookkeeping of a texture array, see [`Textures::insert_raw_into_array`]
+#[derive(Clone, Copy, Debug)]
+struct ArrayLayers {
+    size: (u32, u32),
+    len: u32,
+    capacity: u32,
+}
 
 /// A GPU texture that can be bound in shaders for rendering
 ///
 /// Wraps a `wgpu::Texture`, its view, sampler, & bind group
 pub(crate) struct Texture {
+    // only kept for textures created from bytes, which can be partially rewritten
+    texture: Option<wgpu::Texture>,
+    view: TextureView,
     bind_group: BindGroup,
+    sampler: SamplerKind,
+    array: Option<ArrayLayers>,
+    format: TextureFormat,
+    // counts the texture's bytes while it's in the store
+    memory: Option<Allocation>,
 }
 
 impl Texture {
@@ -52,6 +127,29 @@ impl Texture {
         data: &[u8],
         width: u32,
         height: u32,
+    ) -> Self {
+        let format = TextureFormat::Rgba8UnormSrgb;
+        Self::from_levels(
+            device,
+            queue,
+            layout,
+            sampler,
+            format,
+            (width, height),
+            &[data],
+        )
+    }
+
+    /// Creates a texture in `format` from its mip levels, largest first, each tightly packed
+    /// rows of pixels or blocks
+    fn from_levels(
+        device: &Device,
+        queue: &Queue,
+        layout: &BindGroupLayout,
+        sampler: &Sampler,
+        format: TextureFormat,
+        (width, height): (u32, u32),
+        levels: &[&[u8]],
     ) -> Self {
         let texture = device.create_texture(&TextureDescriptor {
             label: None,
@@ -60,37 +158,92 @@ impl Texture {
                 height,
                 depth_or_array_layers: 1,
             },
-            mip_level_count: 1,
+            mip_level_count: levels.len() as u32,
             sample_count: 1,
             dimension: TextureDimension::D2,
-            format: TextureFormat::Rgba8UnormSrgb,
+            format,
             usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
             view_formats: &[],
         });
 
-        queue.write_texture(
-            TexelCopyTextureInfo {
-                texture: &texture,
-                mip_level: 0,
-                origin: Origin3d::ZERO,
-                aspect: TextureAspect::All,
-            },
-            data,
-            TexelCopyBufferLayout {
-                offset: 0,
-                bytes_per_row: Some(4 * width),
-                rows_per_image: Some(height),
-            },
-            Extent3d {
+        for (level, data) in levels.iter().enumerate() {
+            let size = texture
+                .size()
+                .mip_level_size(level as u32, TextureDimension::D2);
+            // compressed levels are written whole blocks at a time
+            let size = size.physical_size(format);
+            let (block_width, block_height) = format.block_dimensions();
+            let block_size = format.block_copy_size(None).unwrap();
+            queue.write_texture(
+                TexelCopyTextureInfo {
+                    texture: &texture,
+                    mip_level: level as u32,
+                    origin: Origin3d::ZERO,
+                    aspect: TextureAspect::All,
+                },
+                data,
+                TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(size.width / block_width * block_size),
+                    rows_per_image: Some(size.height / block_height),
+                },
+                size,
+            );
+        }
+        let view = texture.create_view(&Default::default());
+
+        Self {
+            bind_group: Self::create_bind_group(device, layout, &view, sampler),
+            texture: Some(texture),
+            view,
+            sampler: SamplerKind::Nearest,
+            array: None,
+            format,
+            memory: None,
+        }
+    }
+
+    /// Creates an empty `width` x `height` texture array with room for `capacity` layers
+    fn empty_array(
+        device: &Device,
+        layout: &BindGroupLayout,
+        sampler: &Sampler,
+        (width, height): (u32, u32),
+        capacity: u32,
+    ) -> Self {
+        let texture = device.create_texture(&TextureDescriptor {
+            label: Some("Texture Array"),
+            size: Extent3d {
                 width,
                 height,
-                depth_or_array_layers: 1,
+                depth_or_array_layers: capacity,
             },
-        );
-        let view = texture.create_view(&Default::default());
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::Rgba8UnormSrgb,
+            usage: TextureUsages::TEXTURE_BINDING
+                | TextureUsages::COPY_DST
+                | TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&TextureViewDescriptor {
+            dimension: Some(TextureViewDimension::D2Array),
+            ..Default::default()
+        });
 
         Self {
             bind_group: Self::create_bind_group(device, layout, &view, sampler),
+            texture: Some(texture),
+            view,
+            sampler: SamplerKind::Nearest,
+            array: Some(ArrayLayers {
+                size: (width, height),
+                len: 0,
+                capacity,
+            }),
+            format: TextureFormat::Rgba8UnormSrgb,
+            memory: None,
         }
     }
 
@@ -101,12 +254,19 @@ impl Texture {
     /// and builds the bind group required for sampling in shaders
     fn from_view(
         view: &TextureView,
+        format: TextureFormat,
         device: &Device,
         layout: &BindGroupLayout,
         sampler: &Sampler,
     ) -> Self {
         Self {
+            texture: None,
+            view: view.clone(),
             bind_group: Self::create_bind_group(device, layout, view, sampler),
+            sampler: SamplerKind::LinearClamp,
+            array: None,
+            format,
+            memory: None,
         }
     }
 
@@ -136,39 +296,79 @@ impl Texture {
     pub fn bind(&self, pass: &mut RenderPass, index: u32) {
         pass.set_bind_group(index, &self.bind_group, &[]);
     }
+
+    /// Whether this is a texture array, sampled by the array pipeline
+    pub fn is_array(&self) -> bool {
+        self.array.is_some()
+    }
+
+    /// Format the texture is stored in on the GPU
+    pub fn format(&self) -> TextureFormat {
+        self.format
+    }
+}
+
+fn create_bind_group_layout(
+    device: &Device,
+    label: &str,
+    view_dimension: TextureViewDimension,
+) -> BindGroupLayout {
+    device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+        label: Some(label),
+        entries: &[
+            BindGroupLayoutEntry {
+                binding: 0,
+                visibility: ShaderStages::FRAGMENT,
+                ty: BindingType::Texture {
+                    sample_type: TextureSampleType::Float { filterable: true },
+                    view_dimension,
+                    multisampled: false,
+                },
+                count: None,
+            },
+            BindGroupLayoutEntry {
+                binding: 1,
+                visibility: ShaderStages::FRAGMENT,
+                ty: BindingType::Sampler(SamplerBindingType::Filtering),
+                count: None,
+            },
+        ],
+    })
 }
 
 pub(crate) struct Textures {
     layout: BindGroupLayout,
+    array_layout: BindGroupLayout,
+    /// Most layers an array may grow to, rounded down to a power of two. Below 2 arrays
+    /// are unsupported
+    max_array_layers: u32,
+    /// Id of the texture array, created by the first [`Self::insert_raw_into_array`]
+    array: Option<usize>,
     default_sampler: Sampler,
     linear_clamp_sampler: Sampler,
+    repeat_sampler: Sampler,
     default_texture: Texture,
-    store: Vec<Texture>,
+    /// Unloaded textures leave their slot empty, so later ids stay valid
+    store: Vec<Option<Texture>>,
+    memory: MemoryTracker,
 }
 
 impl Textures {
-    pub fn new(device: &Device, queue: &Queue) -> Self {
-        let layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
-            label: Some("Texture Bind Group Layout"),
-            entries: &[
-                BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: ShaderStages::FRAGMENT,
-                    ty: BindingType::Texture {
-                        sample_type: TextureSampleType::Float { filterable: true },
-                        view_dimension: TextureViewDimension::D2,
-                        multisampled: false,
-                    },
-                    count: None,
-                },
-                BindGroupLayoutEntry {
-                    binding: 1,
-                    visibility: ShaderStages::FRAGMENT,
-                    ty: BindingType::Sampler(SamplerBindingType::Filtering),
-                    count: None,
-                },
-            ],
-        });
+    pub fn new(device: &Device, queue: &Queue, memory: MemoryTracker) -> Self {
+        let layout = create_bind_group_layout(
+            device,
+            "Texture Bind Group Layout",
+            TextureViewDimension::D2,
+        );
+        let array_layout = create_bind_group_layout(
+            device,
+            "Texture Array Bind Group Layout",
+            TextureViewDimension::D2Array,
+        );
+        let max_array_layers = match device.limits().max_texture_array_layers {
+            0 => 0,
+            max => 1 << max.ilog2(),
+        };
 
         let default_sampler = device.create_sampler(&Default::default());
 
@@ -180,14 +380,33 @@ impl Textures {
             ..Default::default()
         });
 
+        let repeat_sampler = device.create_sampler(&SamplerDescriptor {
+            address_mode_u: AddressMode::Repeat,
+            address_mode_v: AddressMode::Repeat,
+            ..Default::default()
+        });
+
         let default_texture = Texture::create_default(device, queue, &layout, &default_sampler);
 
         Self {
             layout,
+            array_layout,
+            max_array_layers,
+            array: None,
             default_sampler,
             linear_clamp_sampler,
+            repeat_sampler,
             default_texture,
             store: Vec::new(),
+            memory,
+        }
+    }
+
+    fn sampler(&self, kind: SamplerKind) -> &Sampler {
+        match kind {
+            SamplerKind::Nearest => &self.default_sampler,
+            SamplerKind::LinearClamp => &self.linear_clamp_sampler,
+            SamplerKind::Repeat => &self.repeat_sampler,
         }
     }
 
@@ -197,11 +416,64 @@ impl Textures {
         (w, h, img)
     }
 
+    fn loaded(&self, id: usize) -> Option<&Texture> {
+        self.store.get(id)?.as_ref()
+    }
+
+    pub fn view(&self, id: usize) -> Option<&TextureView> {
+        self.loaded(id).map(|t| &t.view)
+    }
+
+    pub fn format(&self, id: usize) -> Option<TextureFormat> {
+        self.loaded(id).map(Texture::format)
+    }
+
+    /// Number of ids handed out, unloaded ones included
+    pub fn len(&self) -> usize {
+        self.store.len()
+    }
+
+    pub fn exists(&self, id: usize) -> bool {
+        self.loaded(id).is_some()
+    }
+
+    /// Unloaded & unknown ids get the default texture
     pub fn get(&self, id: Option<usize>) -> &Texture {
-        id.and_then(|i| self.store.get(i))
+        id.and_then(|i| self.loaded(i))
             .unwrap_or(&self.default_texture)
     }
 
+    /// Drops the texture, freeing its GPU memory once no queued work uses it. Its id stays
+    /// unused; removing the texture array sends later array inserts to a new one
+    pub fn remove(&mut self, id: usize) {
+        if let Some(slot) = self.store.get_mut(id) {
+            *slot = None;
+        }
+        if self.array == Some(id) {
+            self.array = None;
+        }
+    }
+
+    /// Ids & estimated bytes of every loaded texture that owns its memory
+    pub fn sizes(&self) -> Vec<(usize, u64)> {
+        let loaded = self.store.iter().enumerate();
+        let tracked = loaded.filter_map(|(id, t)| Some((id, t.as_ref()?.memory.as_ref()?)));
+        tracked.map(|(id, memory)| (id, memory.bytes())).collect()
+    }
+
+    /// Counts the texture's bytes until it's replaced or removed. Views of offscreen targets
+    /// are counted by the target
+    fn tracked(&self, mut texture: Texture) -> Texture {
+        if let Some(gpu) = &texture.texture {
+            let category = match texture.is_array() {
+                true => MemoryCategory::TextureArrays,
+                false => MemoryCategory::Textures,
+            };
+            texture.memory = Some(self.memory.track(category, texture_bytes(gpu)));
+        }
+        texture
+    }
+
     pub fn insert(&mut self, device: &Device, queue: &Queue, data: &[u8]) -> usize {
         let (w, h, img) = Self::decode_rgba(data);
         self.insert_raw(device, queue, w, h, &img)
@@ -215,22 +487,258 @@ impl Textures {
         h: u32,
         data: &[u8],
     ) -> usize {
-        let id = self.store.len();
-        self.store.push(Texture::from_bytes(
+        self.insert_sampled(device, queue, (w, h), data, SamplerKind::Nearest)
+    }
+
+    /// Like [`Self::insert_raw`], but sampled with linear filtering & clamped edges
+    pub fn insert_raw_linear(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        w: u32,
+        h: u32,
+        data: &[u8],
+    ) -> usize {
+        self.insert_sampled(device, queue, (w, h), data, SamplerKind::LinearClamp)
+    }
+
+    pub fn insert_repeating(&mut self, device: &Device, queue: &Queue, data: &[u8]) -> usize {
+        let (w, h, img) = Self::decode_rgba(data);
+        self.insert_raw_repeating(device, queue, w, h, &img)
+    }
+
+    /// Like [`Self::insert_raw`], but UVs outside `0..1` tile the texture
+    pub fn insert_raw_repeating(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        w: u32,
+        h: u32,
+        data: &[u8],
+    ) -> usize {
+        self.insert_sampled(device, queue, (w, h), data, SamplerKind::Repeat)
+    }
+
+    fn insert_sampled(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        (w, h): (u32, u32),
+        data: &[u8],
+        kind: SamplerKind,
+    ) -> usize {
+        let sampler = self.sampler(kind);
+        let mut texture = Texture::from_bytes(device, queue, &self.layout, sampler, data, w, h);
+        texture.sampler = kind;
+        self.push(texture)
+    }
+
+    /// Adds a KTX2 texture in its own format if a device with `features` samples it,
+    /// decoded to RGBA8 otherwise, keeping its mip levels
+    #[cfg(feature = "ktx2")]
+    pub fn insert_compressed(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        data: &[u8],
+        features: wgpu::Features,
+    ) -> Result<usize, CompressedTextureError> {
+        let image = CompressedImage::parse(data, features)?;
+        let levels: Vec<_> = image.levels.iter().map(|level| &**level).collect();
+        let texture = Texture::from_levels(
             device,
             queue,
             &self.layout,
             &self.default_sampler,
+            image.format,
+            image.size,
+            &levels,
+        );
+        Ok(self.push(texture))
+    }
+
+    pub fn insert_into_array(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        data: &[u8],
+    ) -> (usize, u32) {
+        let (w, h, img) = Self::decode_rgba(data);
+        self.insert_raw_into_array(device, queue, (w, h), &img)
+    }
+
+    /// Whether [`Self::insert_raw_into_array`] can create a texture array on this device
+    pub fn supports_arrays(&self) -> bool {
+        self.max_array_layers >= 2
+    }
+
+    /// Adds a `w` x `h` image as the next layer of the texture array, growing it as needed.
+    /// Returns the array's id & the layer. The first image sets the array's size; images of
+    /// another size, or past the device's layer limit, become standalone textures at layer 0,
+    /// as does everything when arrays are unsupported
+    pub fn insert_raw_into_array(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        (w, h): (u32, u32),
+        data: &[u8],
+    ) -> (usize, u32) {
+        if !self.supports_arrays() {
+            return (self.insert_raw(device, queue, w, h, data), 0);
+        }
+        let id = match self.array {
+            Some(id) => id,
+            None => {
+                let capacity = INITIAL_ARRAY_LAYERS.min(self.max_array_layers);
+                let array = Texture::empty_array(
+                    device,
+                    &self.array_layout,
+                    &self.default_sampler,
+                    (w, h),
+                    capacity,
+                );
+                let id = self.push(array);
+                self.array = Some(id);
+                id
+            }
+        };
+        let Some(layers) = self.loaded(id).and_then(|t| t.array) else {
+            unreachable!("the array id always points at a texture array");
+        };
+        if layers.size != (w, h) || layers.len == self.max_array_layers {
+            return (self.insert_raw(device, queue, w, h, data), 0);
+        }
+        if layers.len == layers.capacity {
+            self.grow_array(device, queue, id, layers);
+        }
+
+        let layer = layers.len;
+        let Some(array) = &mut self.store[id] else {
+            unreachable!("the array was just looked up");
+        };
+        queue.write_texture(
+            TexelCopyTextureInfo {
+                texture: array.texture.as_ref().unwrap(),
+                mip_level: 0,
+                origin: Origin3d {
+                    x: 0,
+                    y: 0,
+                    z: layer,
+                },
+                aspect: TextureAspect::All,
+            },
             data,
-            w,
-            h,
-        ));
-        id
+            TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(4 * w),
+                rows_per_image: Some(h),
+            },
+            Extent3d {
+                width: w,
+                height: h,
+                depth_or_array_layers: 1,
+            },
+        );
+        if let Some(layers) = &mut array.array {
+            layers.len += 1;
+        }
+        (id, layer)
     }
 
-    pub fn replace(&mut self, device: &Device, queue: &Queue, id: usize, data: &[u8]) {
+    /// Doubles the array's capacity, copying its layers into a new texture on the GPU
+    fn grow_array(&mut self, device: &Device, queue: &Queue, id: usize, layers: ArrayLayers) {
+        let capacity = (layers.capacity * 2).min(self.max_array_layers);
+        let mut grown = Texture::empty_array(
+            device,
+            &self.array_layout,
+            &self.default_sampler,
+            layers.size,
+            capacity,
+        );
+        let old = self.loaded(id).and_then(|t| t.texture.as_ref());
+        let (Some(old), Some(new)) = (old, &grown.texture) else {
+            unreachable!("texture arrays keep their texture");
+        };
+        let mut encoder = device.create_command_encoder(&Default::default());
+        encoder.copy_texture_to_texture(
+            old.as_image_copy(),
+            new.as_image_copy(),
+            Extent3d {
+                width: layers.size.0,
+                height: layers.size.1,
+                depth_or_array_layers: layers.len,
+            },
+        );
+        queue.submit([encoder.finish()]);
+        grown.array = Some(ArrayLayers { capacity, ..layers });
+        self.store[id] = Some(self.tracked(grown));
+    }
+
+    fn push(&mut self, texture: Texture) -> usize {
+        let texture = self.tracked(texture);
+        self.store.push(Some(texture));
+        self.store.len() - 1
+    }
+
+    /// Overwrites a `w` x `h` region at (`x`, `y`) of the existing texture with tightly
+    /// packed RGBA data, keeping its bind group
+    pub fn write_region(
+        &self,
+        queue: &Queue,
+        id: usize,
+        (x, y): (u32, u32),
+        (w, h): (u32, u32),
+        data: &[u8],
+    ) -> Result<(), TextureRegionError> {
+        let texture = self.loaded(id).ok_or(TextureRegionError::UnknownTexture)?;
+        let Some(texture) = texture.texture.as_ref().filter(|t| {
+            !texture.is_array() && !t.format().is_compressed() && t.mip_level_count() == 1
+        }) else {
+            return Err(TextureRegionError::NotWritable);
+        };
+        let size = texture.size();
+        if x.checked_add(w).is_none_or(|right| right > size.width)
+            || y.checked_add(h).is_none_or(|bottom| bottom > size.height)
+        {
+            return Err(TextureRegionError::OutOfBounds {
+                size: (size.width, size.height),
+            });
+        }
+        let expected = 4 * w as usize * h as usize;
+        if data.len() != expected {
+            return Err(TextureRegionError::InvalidData {
+                expected,
+                actual: data.len(),
+            });
+        }
+
+        queue.write_texture(
+            TexelCopyTextureInfo {
+                texture,
+                mip_level: 0,
+                origin: Origin3d { x, y, z: 0 },
+                aspect: TextureAspect::All,
+            },
+            data,
+            TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(4 * w),
+                rows_per_image: Some(h),
+            },
+            Extent3d {
+                width: w,
+                height: h,
+                depth_or_array_layers: 1,
+            },
+        );
+        Ok(())
+    }
+
+    /// Returns how many bytes of pixels the image decoded to
+    pub fn replace(&mut self, device: &Device, queue: &Queue, id: usize, data: &[u8]) -> usize {
         let (w, h, img) = Self::decode_rgba(data);
         self.replace_raw(device, queue, id, w, h, &img);
+        img.len()
     }
 
     pub fn replace_raw(
@@ -242,25 +750,181 @@ impl Textures {
         h: u32,
         data: &[u8],
     ) {
-        self.store[id] = Texture::from_bytes(
+        // other textures share the array's id, replacing it would drop them
+        let Some(old) = self.loaded(id).filter(|t| !t.is_array()) else {
+            return;
+        };
+        // same size, so write into the texture & keep its bind group
+        let size = old.texture.as_ref().map(|t| t.size());
+        if size.is_some_and(|size| (size.width, size.height) == (w, h))
+            && self.write_region(queue, id, (0, 0), (w, h), data).is_ok()
+        {
+            return;
+        }
+        // keep the sampler the texture was created with
+        let kind = old.sampler;
+        let sampler = self.sampler(kind);
+        let mut texture = Texture::from_bytes(device, queue, &self.layout, sampler, data, w, h);
+        texture.sampler = kind;
+        self.store[id] = Some(self.tracked(texture));
+    }
+
+    pub fn replace_offscreen(&mut self, device: &Device, id: usize, offscreen: &OffscreenTarget) {
+        self.store[id] = Some(Texture::from_view(
+            offscreen.view(),
+            offscreen.format(),
             device,
-            queue,
             &self.layout,
-            &self.default_sampler,
-            data,
-            w,
-            h,
-        );
+            &self.linear_clamp_sampler,
+        ));
     }
 
     pub fn insert_offscreen(&mut self, device: &Device, offscreen: &OffscreenTarget) -> usize {
         let id = self.store.len();
-        self.store.push(Texture::from_view(
+        self.store.push(Some(Texture::from_view(
             offscreen.view(),
+            offscreen.format(),
             device,
             &self.layout,
             &self.linear_clamp_sampler,
-        ));
+        )));
         id
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pipeline::Pipelines;
+
+    #[test]
+    fn same_sized_images_share_a_growing_array() {
+        let (device, queue) = crate::noop_device();
+        // builds the array pipeline too, validating its shader
+        Pipelines::new(&device, TextureFormat::Rgba8UnormSrgb);
+        let mut textures = Textures::new(&device, &queue, MemoryTracker::default());
+        assert!(textures.supports_arrays());
+
+        let pixels = [255u8; 4 * 4 * 4];
+        // past the initial capacity, so the array grows once
+        let added: Vec<_> = (0..6)
+            .map(|_| textures.insert_raw_into_array(&device, &queue, (4, 4), &pixels))
+            .collect();
+        let array = added[0].0;
+        assert!(added.iter().all(|&(id, _)| id == array));
+        let layers: Vec<_> = added.iter().map(|&(_, layer)| layer).collect();
+        assert_eq!(layers, [0, 1, 2, 3, 4, 5]);
+        assert!(textures.get(Some(array)).is_array());
+        assert_eq!(
+            textures.write_region(&queue, array, (0, 0), (4, 4), &pixels),
+            Err(TextureRegionError::NotWritable)
+        );
+
+        let (standalone, layer) =
+            textures.insert_raw_into_array(&device, &queue, (2, 2), &pixels[..16]);
+        assert_ne!(standalone, array);
+        assert_eq!(layer, 0);
+        assert!(!textures.get(Some(standalone)).is_array());
+        assert_eq!(
+            textures.insert_raw_into_array(&device, &queue, (4, 4), &pixels),
+            (array, 6)
+        );
+    }
+
+    #[test]
+    fn regions_write_into_the_existing_texture() {
+        let (device, queue) = crate::noop_device();
+        let mut textures = Textures::new(&device, &queue, MemoryTracker::default());
+        let id = textures.insert_raw(&device, &queue, 8, 8, &[0; 4 * 8 * 8]);
+        let original = textures.loaded(id).unwrap().texture.clone();
+
+        let patch = [255u8; 4 * 2 * 3];
+        assert_eq!(
+            textures.write_region(&queue, id, (6, 5), (2, 3), &patch),
+            Ok(())
+        );
+        assert_eq!(
+            textures.write_region(&queue, id, (7, 5), (2, 3), &patch),
+            Err(TextureRegionError::OutOfBounds { size: (8, 8) })
+        );
+        assert_eq!(
+            textures.write_region(&queue, id, (0, u32::MAX), (2, 3), &patch),
+            Err(TextureRegionError::OutOfBounds { size: (8, 8) })
+        );
+        assert_eq!(
+            textures.write_region(&queue, id, (0, 0), (2, 2), &patch),
+            Err(TextureRegionError::InvalidData {
+                expected: 16,
+                actual: 24
+            })
+        );
+        assert_eq!(
+            textures.write_region(&queue, id + 1, (0, 0), (2, 3), &patch),
+            Err(TextureRegionError::UnknownTexture)
+        );
+
+        // same size keeps the texture, a new size recreates it
+        textures.replace_raw(&device, &queue, id, 8, 8, &[255; 4 * 8 * 8]);
+        assert_eq!(textures.loaded(id).unwrap().texture, original);
+        textures.replace_raw(&device, &queue, id, 4, 4, &[255; 4 * 4 * 4]);
+        assert_ne!(textures.loaded(id).unwrap().texture, original);
+    }
+
+    #[test]
+    fn unloading_returns_texture_memory_to_the_baseline() {
+        let (device, queue) = crate::noop_device();
+        let memory = MemoryTracker::default();
+        let mut textures = Textures::new(&device, &queue, memory.clone());
+        let baseline = memory.texture_bytes();
+
+        let small = textures.insert_raw(&device, &queue, 4, 4, &[0; 4 * 4 * 4]);
+        assert_eq!(memory.texture_bytes(), baseline + 64);
+        // a new size recreates the texture, dropping the old one's bytes
+        textures.replace_raw(&device, &queue, small, 8, 8, &[0; 4 * 8 * 8]);
+        assert_eq!(memory.texture_bytes(), baseline + 256);
+        // growing the array past its initial 4 layers doubles it
+        let (array, _) = (0..5)
+            .map(|_| textures.insert_raw_into_array(&device, &queue, (2, 2), &[0; 16]))
+            .last()
+            .unwrap();
+        assert_eq!(memory.bytes(MemoryCategory::TextureArrays), 8 * 16);
+        assert_eq!(textures.sizes(), [(small, 256), (array, 8 * 16)]);
+
+        textures.remove(small);
+        textures.remove(array);
+        assert_eq!(memory.texture_bytes(), baseline);
+        assert!(!textures.exists(small));
+        assert!(!textures.get(Some(small)).is_array());
+        assert_eq!(
+            textures.write_region(&queue, small, (0, 0), (1, 1), &[0; 4]),
+            Err(TextureRegionError::UnknownTexture)
+        );
+        // the next array insert starts a new array
+        let (new_array, layer) = textures.insert_raw_into_array(&device, &queue, (2, 2), &[0; 16]);
+        assert_ne!(new_array, array);
+        assert_eq!(layer, 0);
+    }
+
+    #[cfg(feature = "ktx2")]
+    #[test]
+    fn decoded_ktx2_textures_keep_their_mips() {
+        let (device, queue) = crate::noop_device();
+        let mut textures = Textures::new(&device, &queue, MemoryTracker::default());
+        let file = crate::compressed::tests::ktx2(
+            ktx2::Format::BC7_SRGB_BLOCK,
+            (8, 8),
+            &[&[0; 64], &[0; 16], &[0; 16], &[0; 16]],
+        );
+        // the noop device has no compression features
+        let id = textures
+            .insert_compressed(&device, &queue, &file, device.features())
+            .unwrap();
+        assert_eq!(textures.format(id), Some(TextureFormat::Rgba8UnormSrgb));
+        let texture = textures.loaded(id).unwrap().texture.as_ref().unwrap();
+        assert_eq!(texture.mip_level_count(), 4);
+        assert_eq!(
+            textures.write_region(&queue, id, (0, 0), (1, 1), &[0; 4]),
+            Err(TextureRegionError::NotWritable)
+        );
+    }
+}
@@ -5,6 +5,8 @@ use wgpu::{
     util::{BufferInitDescriptor, DeviceExt},
 };
 
+use crate::memory::{Allocation, MemoryCategory, MemoryTracker};
+
 #[repr(C)]
 #[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
 pub(crate) struct CameraUniform {
@@ -27,15 +29,17 @@ impl Default for CameraUniform {
 struct UniformEntry {
     buffer: Buffer,
     bind_group: BindGroup,
+    _memory: Allocation,
 }
 
 pub(crate) struct Uniforms {
     layout: BindGroupLayout,
     store: Vec<UniformEntry>,
+    memory: MemoryTracker,
 }
 
 impl Uniforms {
-    pub fn new(device: &Device) -> Self {
+    pub fn new(device: &Device, memory: MemoryTracker) -> Self {
         let layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
             label: Some("Shared Uniform Layout"),
             entries: &[BindGroupLayoutEntry {
@@ -53,6 +57,7 @@ impl Uniforms {
         Self {
             layout,
             store: Vec::new(),
+            memory,
         }
     }
 
@@ -81,7 +86,12 @@ impl Uniforms {
         });
 
         let id = self.store.len();
-        self.store.push(UniformEntry { buffer, bind_group });
+        let memory = self.memory.track(MemoryCategory::Uniforms, buffer.size());
+        self.store.push(UniformEntry {
+            buffer,
+            bind_group,
+            _memory: memory,
+        });
         id
     }
 
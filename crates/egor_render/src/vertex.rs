@@ -28,6 +28,9 @@ impl Vertex {
         Zeroable::zeroed()
     }
 
+    /// Field names of [`Self::desc`]'s attributes, in order, as the shader prelude declares them
+    pub(crate) const ATTRIBUTE_NAMES: [&str; 3] = ["position", "color", "tex_coords"];
+
     /// Returns the vertex buffer layout
     ///
     /// This must match the vertex shader input layout:
@@ -3,8 +3,9 @@ use egor::{
         App, FrameContext,
         egui::{Context, Window},
     },
+    input::KeyCode,
     math::{Rect, Vec2, vec2},
-    render::{Align, Color, Graphics},
+    render::{Align, Camera, Color, Graphics},
 };
 use rand::Rng;
 
@@ -62,24 +63,25 @@ struct Player {
     cell: Cell,
     speed: f32,
     absorbed_food: i32,
+    color: Color,
 }
 
 impl Player {
-    fn new(bounds: Vec2) -> Self {
+    fn new(center: Vec2, color: Color) -> Self {
         Self {
             cell: Cell {
-                center: bounds / 2.0,
+                center,
                 radius: 10.0,
                 absorbed: false,
             },
             speed: 80.0,
             absorbed_food: 0,
+            color,
         }
     }
 
-    fn update(&mut self, mouse_world: Vec2, food: &mut [Food], dt: f32) {
-        let dir = (mouse_world - self.cell.center).normalize_or_zero();
-        self.cell.center += dir * self.speed * dt;
+    fn update(&mut self, dir: Vec2, food: &mut [Food], dt: f32) {
+        self.cell.center += dir.normalize_or_zero() * self.speed * dt;
 
         for f in food.iter_mut() {
             if let Some(growth_factor) = self.cell.try_absorb(&mut f.cell) {
@@ -90,12 +92,7 @@ impl Player {
     }
 
     fn render(&self, gfx: &mut Graphics) {
-        draw_circle(
-            gfx,
-            self.cell.center,
-            self.cell.radius,
-            Color::new([0.0, 0.0, 0.55, 1.0]),
-        );
+        draw_circle(gfx, self.cell.center, self.cell.radius, self.color);
     }
 }
 
@@ -119,11 +116,11 @@ impl Creature {
     fn update(
         &mut self,
         food: &mut [Food],
-        player: &mut Option<Player>,
+        players: &mut [Option<Player>],
         opps: &mut [Creature],
         dt: f32,
     ) {
-        if let Some(p) = player {
+        for p in players.iter_mut().flatten() {
             self.speed -= self.cell.try_absorb(&mut p.cell).unwrap_or(0.0);
             p.speed -= p.cell.try_absorb(&mut self.cell).unwrap_or(0.0);
         }
@@ -156,19 +153,45 @@ impl Creature {
 struct World {
     bounds: Vec2,
     food: Vec<Food>,
-    player: Option<Player>,
+    /// Player 1, then player 2 once they join. `None` once eaten
+    players: Vec<Option<Player>>,
     creatures: Vec<Creature>,
 }
 
 impl World {
     fn new(bounds: Vec2) -> Self {
+        let player = Player::new(bounds / 2.0, Color::new([0.0, 0.0, 0.55, 1.0]));
         Self {
             bounds,
             food: (0..FOOD_COUNT).map(|_| Food::random(bounds)).collect(),
-            player: Some(Player::new(bounds)),
+            players: vec![Some(player)],
             creatures: (0..99).map(|_| Creature::random(bounds)).collect(),
         }
     }
+
+    fn alive_players(&self) -> impl Iterator<Item = (usize, &Player)> {
+        self.players
+            .iter()
+            .enumerate()
+            .filter_map(|(i, p)| p.as_ref().map(|p| (i, p)))
+    }
+
+    fn render(&self, gfx: &mut Graphics) {
+        gfx.rect()
+            .at(Vec2::ZERO)
+            .size(self.bounds)
+            .color(Color::WHITE);
+
+        for f in &self.food {
+            f.render(gfx);
+        }
+        for c in &self.creatures {
+            c.render(gfx);
+        }
+        for (_, p) in self.alive_players() {
+            p.render(gfx);
+        }
+    }
 }
 
 #[derive(PartialEq)]
@@ -181,7 +204,8 @@ enum GameState {
 struct Game {
     state: GameState,
     world: World,
-    camera_target: Vec2,
+    /// What each player's view follows
+    camera_targets: Vec<Vec2>,
     zoom: f32,
 }
 
@@ -190,78 +214,110 @@ impl Game {
         Self {
             state: GameState::Playing,
             world: World::new(Vec2::splat(WORLD_SIZE)),
-            camera_target: Vec2::ZERO,
+            camera_targets: vec![Vec2::ZERO],
             zoom: 1.0,
         }
     }
 
-    fn update(&mut self, mouse_world: Vec2, dt: f32) {
+    /// Player 2 joins next to player 1, splitting the screen
+    fn join(&mut self) {
+        if self.world.players.len() > 1 || self.state != GameState::Playing {
+            return;
+        }
+        let center = self.world.bounds / 2.0 + vec2(100.0, 0.0);
+        let color = Color::new([0.0, 0.5, 0.0, 1.0]);
+        self.world.players.push(Some(Player::new(center, color)));
+        self.camera_targets.push(center);
+    }
+
+    /// `dirs` holds the direction each player is steering in
+    fn update(&mut self, dirs: &[Vec2], dt: f32) {
         if self.state != GameState::Playing {
             return;
         }
 
-        if let Some(p) = &mut self.world.player {
-            p.update(mouse_world, &mut self.world.food, dt);
-            self.camera_target = p.cell.center;
-        } else if let Some(c) = self
+        let biggest = self
             .world
             .creatures
             .iter()
             .max_by(|a, b| a.cell.radius.partial_cmp(&b.cell.radius).unwrap())
-        {
-            self.camera_target = c.cell.center;
+            .map(|c| c.cell.center);
+        for (i, player) in self.world.players.iter_mut().enumerate() {
+            if let Some(p) = player {
+                p.update(dirs[i], &mut self.world.food, dt);
+                self.camera_targets[i] = p.cell.center;
+            } else if let Some(center) = biggest {
+                self.camera_targets[i] = center;
+            }
+        }
+
+        if let [Some(p1), Some(p2)] = &mut self.world.players[..] {
+            p1.cell.try_absorb(&mut p2.cell);
+            p2.cell.try_absorb(&mut p1.cell);
         }
 
         for c_idx in 0..self.world.creatures.len() + 1 {
             let (left, right) = self.world.creatures.split_at_mut(c_idx);
             if let Some(c) = left.last_mut() {
-                c.update(&mut self.world.food, &mut self.world.player, right, dt);
+                c.update(&mut self.world.food, &mut self.world.players, right, dt);
             }
         }
 
         self.world.creatures.retain(|c| !c.cell.absorbed);
         self.world.food.retain(|f| !f.cell.absorbed);
 
-        if self.world.player.as_ref().is_some_and(|p| p.cell.absorbed) {
-            self.world.player = None;
+        for player in &mut self.world.players {
+            if player.as_ref().is_some_and(|p| p.cell.absorbed) {
+                *player = None;
+            }
         }
 
         while self.world.food.len() < FOOD_COUNT {
             self.world.food.push(Food::random(self.world.bounds));
         }
 
-        if self.world.player.is_some() && self.world.creatures.is_empty() {
+        let alive = self.world.alive_players().count();
+        if alive > 0 && self.world.creatures.is_empty() {
             self.state = GameState::Win;
-        } else if self.world.player.is_none() && self.world.creatures.len() == 1 {
+        } else if alive == 0 && self.world.creatures.len() == 1 {
             self.state = GameState::Lose;
         }
     }
 
+    /// One view per player, side by side
+    fn views(&self, screen_size: Vec2) -> Vec<Rect> {
+        let count = self.camera_targets.len() as f32;
+        let size = vec2(screen_size.x / count, screen_size.y);
+        (0..self.camera_targets.len())
+            .map(|i| Rect::new(vec2(size.x * i as f32, 0.0), size))
+            .collect()
+    }
+
     fn render(&self, screen_size: Vec2, gfx: &mut Graphics, egui_ctx: &Context, fps: u32) {
         gfx.clear(Color::new([0.53, 0.81, 0.98, 1.0]));
         match self.state {
             GameState::Playing => {
-                gfx.rect()
-                    .at(Vec2::ZERO)
-                    .size(self.world.bounds)
-                    .color(Color::WHITE);
-
-                for f in &self.world.food {
-                    f.render(gfx);
-                }
-                for c in &self.world.creatures {
-                    c.render(gfx);
-                }
-                if let Some(p) = &self.world.player {
-                    p.render(gfx);
+                for (view, &target) in self
+                    .views(screen_size)
+                    .into_iter()
+                    .zip(&self.camera_targets)
+                {
+                    gfx.viewport(view, Camera::default(), |gfx| {
+                        gfx.camera().set_zoom(self.zoom);
+                        gfx.camera().center(target, view.size);
+                        self.world.render(gfx);
+                    });
                 }
 
                 Window::new("Stats").show(egui_ctx, |ui| {
                     ui.label(format!("FPS: {}", fps));
                     ui.label(format!("Creatures: {}", self.world.creatures.len()));
-                    if let Some(p) = &self.world.player {
-                        ui.label(format!("Player Radius: {:.2}", p.cell.radius));
-                        ui.label(format!("Food Eaten: {}", p.absorbed_food));
+                    for (i, p) in self.world.alive_players() {
+                        ui.label(format!("Player {} Radius: {:.2}", i + 1, p.cell.radius));
+                        ui.label(format!("Player {} Food Eaten: {}", i + 1, p.absorbed_food));
+                    }
+                    if self.world.players.len() == 1 {
+                        ui.label("Press Enter for a second player (WASD)");
                     }
                 });
 
@@ -272,8 +328,8 @@ impl Game {
                     .enumerate()
                     .map(|(i, c)| (format!("Creature {}", i + 1), c.cell.radius))
                     .collect();
-                if let Some(p) = &self.world.player {
-                    board.push(("Player".to_string(), p.cell.radius));
+                for (i, p) in self.world.alive_players() {
+                    board.push((format!("Player {}", i + 1), p.cell.radius));
                 }
                 board.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
                 board.truncate(10);
@@ -311,24 +367,39 @@ fn draw_circle(gfx: &mut Graphics, center: Vec2, radius: f32, color: Color) {
 fn main() {
     let mut game = Game::new();
 
-    App::new().vsync(false).title("Egor Agar Demo").run(
-        move |FrameContext {
-                  gfx,
-                  input,
-                  timer,
-                  egui_ctx,
-                  ..
-              }| {
-            let screen_size = gfx.screen_size();
-
-            game.zoom *= 1.0 + input.mouse_scroll() * 0.1;
-            gfx.camera().set_zoom(game.zoom);
-            gfx.camera().center(game.camera_target, screen_size);
-
-            let mouse_pos = gfx.camera().screen_to_world(input.mouse_position().into());
-
-            game.update(mouse_pos, timer.delta);
-            game.render(screen_size, gfx, egui_ctx, timer.fps);
-        },
-    );
+    // the wheel zooms, so don't let it scroll the page on the web
+    App::new()
+        .vsync(false)
+        .title("Egor Agar Demo")
+        .capture_scroll(true)
+        .run(
+            move |FrameContext {
+                      gfx,
+                      input,
+                      timer,
+                      egui_ctx,
+                      ..
+                  }| {
+                let screen_size = gfx.screen_size();
+
+                if input.key_pressed(KeyCode::Enter) {
+                    game.join();
+                }
+                game.zoom = (game.zoom * (1.0 + input.mouse_scroll() * 0.1)).clamp(0.1, 10.0);
+
+                // player 1 steers toward the cursor, which lives in the first view
+                let view = game.views(screen_size)[0];
+                let mouse = Vec2::from(input.mouse_position()) - view.position;
+                let mouse_dir = (mouse - view.size / 2.0) / game.zoom;
+                let keys_dir = vec2(
+                    input.key_held(KeyCode::KeyD) as i8 as f32
+                        - input.key_held(KeyCode::KeyA) as i8 as f32,
+                    input.key_held(KeyCode::KeyS) as i8 as f32
+                        - input.key_held(KeyCode::KeyW) as i8 as f32,
+                );
+
+                game.update(&[mouse_dir, keys_dir], timer.delta);
+                game.render(screen_size, gfx, egui_ctx, timer.fps);
+            },
+        );
 }
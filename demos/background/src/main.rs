@@ -0,0 +1,82 @@
+//! Logs how many frames ran while the browser tab was hidden under each `Background` mode.
+//! Pick one with `#pause`, `#throttle` or `#continue` at the end of the URL, then switch tabs
+
+use std::{cell::Cell, rc::Rc};
+
+use egor::{
+    app::{App, Background, FrameContext},
+    math::vec2,
+    render::Color,
+};
+
+/// Frames per second `#throttle` keeps running at while hidden
+const THROTTLE_FPS: u32 = 10;
+const LINES: usize = 12;
+
+/// Mode from the URL hash on the web or the first argument natively
+fn mode() -> Background {
+    #[cfg(target_arch = "wasm32")]
+    let arg = web_sys::window()
+        .and_then(|w| w.location().hash().ok())
+        .unwrap_or_default();
+    #[cfg(not(target_arch = "wasm32"))]
+    let arg = std::env::args().nth(1).unwrap_or_default();
+    match arg.trim_start_matches('#') {
+        "throttle" => Background::ThrottleTo(THROTTLE_FPS),
+        "continue" => Background::Continue,
+        _ => Background::Pause,
+    }
+}
+
+fn log(message: &str) {
+    #[cfg(target_arch = "wasm32")]
+    web_sys::console::log_1(&message.into());
+    #[cfg(not(target_arch = "wasm32"))]
+    println!("{message}");
+}
+
+fn main() {
+    let mode = mode();
+    // frames run since the tab was hidden, shared with the suspend hook
+    let hidden_frames = Rc::new(Cell::new(None::<u64>));
+    let suspended = hidden_frames.clone();
+    let mut lines = vec![format!("{mode:?}: switch tabs & come back")];
+
+    App::new()
+        .title("Egor Background Demo")
+        .background_behavior(mode)
+        .on_suspend(move || {
+            suspended.set(Some(0));
+            log("hidden");
+        })
+        .on_resume(|| log("visible"))
+        .run(move |FrameContext { gfx, timer, .. }| {
+            if timer.resumed {
+                let frames = hidden_frames.take().unwrap_or_default();
+                let line = format!(
+                    "away {:.1} s, {frames} frames ran hidden, stepped {:.1} ms on return",
+                    timer.delta_raw,
+                    timer.delta * 1000.0
+                );
+                log(&line);
+                lines.push(line);
+                if lines.len() > LINES {
+                    lines.remove(1);
+                }
+            } else if let Some(frames) = hidden_frames.get() {
+                hidden_frames.set(Some(frames + 1));
+            }
+
+            gfx.clear(Color::BLACK);
+            for (i, line) in lines.iter().enumerate() {
+                gfx.text(line)
+                    .at(vec2(16.0, 16.0 + i as f32 * 24.0))
+                    .size(18.0)
+                    .color(Color::WHITE);
+            }
+            gfx.text(&format!("frame {}", timer.frame))
+                .at(vec2(16.0, 16.0 + (LINES + 1) as f32 * 24.0))
+                .size(18.0)
+                .color(Color::GREEN);
+        });
+}
@@ -0,0 +1,179 @@
+use egor::{
+    app::{App, FrameContext},
+    input::KeyCode,
+    math::{Vec2, vec2},
+    render::{Color, PassResources, wgpu},
+};
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use wgpu::util::DeviceExt;
+
+const STAR_COUNT: usize = 4000;
+const FIELD_SIZE: f32 = 4000.0;
+const SHIP_SIZE: f32 = 24.0;
+const SHIP_SPEED: f32 = 400.0;
+
+const STAR_SHADER: &str = r#"
+struct Camera {
+    view_proj: mat4x4<f32>,
+};
+@group(0) @binding(0) var<uniform> camera: Camera;
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) brightness: f32,
+};
+
+@vertex
+fn vs_main(@location(0) position: vec2<f32>, @location(1) brightness: f32) -> VertexOutput {
+    var out: VertexOutput;
+    out.position = camera.view_proj * vec4<f32>(position, 0.0, 1.0);
+    out.brightness = brightness;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(vec3<f32>(in.brightness), 1.0);
+}
+"#;
+
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct Star {
+    position: [f32; 2],
+    brightness: f32,
+}
+
+/// A point-list pipeline drawing stars in world space with egor's camera
+struct Starfield {
+    pipeline: wgpu::RenderPipeline,
+    stars: wgpu::Buffer,
+}
+
+impl Starfield {
+    fn new(device: &wgpu::Device, resources: &PassResources) -> Self {
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Starfield Shader"),
+            source: wgpu::ShaderSource::Wgsl(STAR_SHADER.into()),
+        });
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Starfield Layout"),
+            bind_group_layouts: &[resources.camera_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Starfield Pipeline"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &module,
+                entry_point: Some("vs_main"),
+                buffers: &[wgpu::VertexBufferLayout {
+                    array_stride: size_of::<Star>() as u64,
+                    step_mode: wgpu::VertexStepMode::Vertex,
+                    attributes: &wgpu::vertex_attr_array![0 => Float32x2, 1 => Float32],
+                }],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &module,
+                entry_point: Some("fs_main"),
+                targets: &[Some(resources.format.into())],
+                compilation_options: Default::default(),
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::PointList,
+                ..Default::default()
+            },
+            depth_stencil: None,
+            multisample: Default::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        // seeded so every run, snapshots included, shows the same sky
+        let mut rng = StdRng::seed_from_u64(0x57A2);
+        let stars: Vec<Star> = (0..STAR_COUNT)
+            .map(|_| Star {
+                position: [
+                    rng.gen_range(-FIELD_SIZE..FIELD_SIZE),
+                    rng.gen_range(-FIELD_SIZE..FIELD_SIZE),
+                ],
+                brightness: rng.gen_range(0.2..1.0),
+            })
+            .collect();
+        let stars = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Stars"),
+            contents: bytemuck::cast_slice(&stars),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+
+        Self { pipeline, stars }
+    }
+
+    fn draw(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        view: &wgpu::TextureView,
+        resources: &PassResources,
+    ) {
+        // the view already holds egor's shapes, so draw over them
+        let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Starfield Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            ..Default::default()
+        });
+        pass.set_pipeline(&self.pipeline);
+        pass.set_bind_group(0, resources.camera_bind_group, &[]);
+        pass.set_vertex_buffer(0, self.stars.slice(..));
+        pass.draw(0..STAR_COUNT as u32, 0..1);
+    }
+}
+
+fn main() {
+    let mut starfield: Option<Starfield> = None;
+    let mut ship = Vec2::ZERO;
+
+    App::new()
+        .title("Egor Custom Pass Demo")
+        .custom_pass(move |device, _queue, encoder, view, resources| {
+            starfield
+                .get_or_insert_with(|| Starfield::new(device, resources))
+                .draw(encoder, view, resources);
+        })
+        .run(
+            move |FrameContext {
+                      gfx, input, timer, ..
+                  }| {
+                let axis = |neg: [KeyCode; 2], pos: [KeyCode; 2]| {
+                    let held = |keys: [KeyCode; 2]| keys.iter().any(|&k| input.key_held(k));
+                    held(pos) as i8 as f32 - held(neg) as i8 as f32
+                };
+                let direction = vec2(
+                    axis(
+                        [KeyCode::KeyA, KeyCode::ArrowLeft],
+                        [KeyCode::KeyD, KeyCode::ArrowRight],
+                    ),
+                    axis(
+                        [KeyCode::KeyW, KeyCode::ArrowUp],
+                        [KeyCode::KeyS, KeyCode::ArrowDown],
+                    ),
+                );
+                ship += direction.normalize_or_zero() * SHIP_SPEED * timer.delta;
+
+                let screen_size = gfx.screen_size();
+                gfx.camera().center(ship, screen_size);
+                gfx.clear(Color::new([0.02, 0.02, 0.06, 1.0]));
+                gfx.rect()
+                    .at(ship - Vec2::splat(SHIP_SIZE / 2.0))
+                    .size(Vec2::splat(SHIP_SIZE))
+                    .color(Color::new([1.0, 0.4, 0.2, 1.0]));
+            },
+        );
+}
@@ -0,0 +1,28 @@
+//! egor:prelude
+
+fn hash(p: vec2<f32>) -> f32 {
+    return fract(sin(dot(p, vec2<f32>(127.1, 311.7))) * 43758.5453);
+}
+
+fn noise(p: vec2<f32>) -> f32 {
+    let i = floor(p);
+    let f = fract(p);
+    let u = f * f * (3.0 - 2.0 * f);
+    let a = mix(hash(i), hash(i + vec2<f32>(1.0, 0.0)), u.x);
+    let b = mix(hash(i + vec2<f32>(0.0, 1.0)), hash(i + vec2<f32>(1.0, 1.0)), u.x);
+    return mix(a, b, u.y);
+}
+
+@fragment
+fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
+    // x: how much has burned away, y: a seed so cards don't burn alike
+    let threshold = input.params.x;
+    let p = input.tex_coords * 6.0 + input.params.y;
+    let n = noise(p) * 0.65 + noise(p * 2.0) * 0.35;
+    if n < threshold {
+        discard;
+    }
+    let glow = 1.0 - smoothstep(0.0, 0.06, n - threshold);
+    let ember = vec3<f32>(1.0, 0.55, 0.1) * glow * step(0.001, threshold);
+    return vec4<f32>(input.color.rgb + ember, input.color.a);
+}
@@ -0,0 +1,49 @@
+use egor::{
+    app::{App, FrameContext},
+    math::vec2,
+    render::Color,
+};
+
+const COLUMNS: usize = 8;
+const ROWS: usize = 4;
+const CARD: f32 = 80.0;
+const GAP: f32 = 16.0;
+
+fn main() {
+    let mut shader = 0;
+    let mut elapsed = 0.0;
+
+    App::new()
+        .title("Egor Dissolve Demo")
+        .window_size(800, 600)
+        .run(move |FrameContext { gfx, timer, .. }| {
+            if timer.frame == 0 {
+                shader = gfx.load_shader(include_str!("../shaders/dissolve.wgsl"));
+            }
+            elapsed += timer.delta;
+            gfx.clear(Color::new([0.08, 0.08, 0.1, 1.0]));
+
+            let size = gfx.screen_size();
+            let grid = vec2(COLUMNS as f32, ROWS as f32) * (CARD + GAP) - GAP;
+            let origin = (size - grid) * 0.5;
+            gfx.with_shader(shader, |gfx| {
+                for i in 0..COLUMNS * ROWS {
+                    let (column, row) = (i % COLUMNS, i / COLUMNS);
+                    // every card burns & comes back on its own phase
+                    let phase = elapsed * 0.6 + i as f32 * 0.37;
+                    let threshold = (phase.sin() * 0.5 + 0.5) * 1.05;
+                    let hue = row as f32 / ROWS as f32;
+                    gfx.rect()
+                        .at(origin + vec2(column as f32, row as f32) * (CARD + GAP))
+                        .size(vec2(CARD, CARD))
+                        .color(Color::new([0.3 + hue * 0.5, 0.4, 0.9 - hue * 0.5, 1.0]))
+                        .params([threshold, i as f32 * 13.7, 0.0, 0.0]);
+                }
+            });
+
+            gfx.text(&format!("{} cards, one draw call", COLUMNS * ROWS))
+                .at(vec2(16.0, 16.0))
+                .size(18.0)
+                .color(Color::WHITE);
+        });
+}
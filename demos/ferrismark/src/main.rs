@@ -2,7 +2,7 @@ use egor::{
     app::{App, FrameContext},
     input::MouseButton,
     math::{Vec2, vec2},
-    render::Color,
+    render::{Color, TextureId},
 };
 use rand::{Rng, rngs::ThreadRng};
 
@@ -34,12 +34,29 @@ fn bounce(pos: &mut Vec2, vel: &mut Vec2, bounds: Vec2, size: f32) {
     }
 }
 
+/// Generates a tileable `STAR_TILE` square of `count` stars, opaque only for the back layer
+fn star_layer(rng: &mut ThreadRng, count: usize, brightness: u8, back: bool) -> Vec<u8> {
+    let size = STAR_TILE as usize;
+    let backdrop = if back { [10, 12, 30, 255] } else { [0; 4] };
+    let mut pixels = backdrop.repeat(size * size);
+    for _ in 0..count {
+        let i = rng.gen_range(0..size * size) * 4;
+        pixels[i..i + 4].copy_from_slice(&[brightness, brightness, 255, 255]);
+    }
+    pixels
+}
+
 const CRAB_SIZE: f32 = 32.0;
 const CRAB_SPEED: f32 = 600.0;
+const STAR_TILE: u32 = 128;
+/// Star count, brightness & scroll speed (tiles per second) of each parallax layer, back to front
+const STAR_LAYERS: [(usize, u8, f32); 3] = [(60, 90, 0.02), (30, 170, 0.06), (12, 255, 0.15)];
 
 fn main() {
     let mut crabs = Vec::new();
-    let mut ferris_tex = 0;
+    let mut ferris_tex = TextureId::default();
+    let mut star_textures = Vec::new();
+    let mut scroll = 0.0;
     let mut rng = rand::thread_rng();
 
     App::new().title("Egor Ferrismark Demo").run(
@@ -51,6 +68,18 @@ fn main() {
             if timer.frame == 0 {
                 ferris_tex = gfx.load_texture(include_bytes!("../assets/ferris_smol.png"));
                 crabs.extend((0..2).map(|_| spawn_crab(&mut rng, size)));
+                for (i, (count, brightness, _)) in STAR_LAYERS.into_iter().enumerate() {
+                    let pixels = star_layer(&mut rng, count, brightness, i == 0);
+                    let id = gfx.load_texture_raw_repeating(STAR_TILE, STAR_TILE, &pixels);
+                    star_textures.push(id);
+                }
+            }
+
+            scroll += timer.delta;
+            let tiles = size / STAR_TILE as f32;
+            for (&texture, (.., speed)) in star_textures.iter().zip(STAR_LAYERS) {
+                let offset = vec2(scroll * speed, 0.0);
+                gfx.scrolling_background(texture, offset).uv_scale(tiles);
             }
 
             if input.mouse_pressed(MouseButton::Left) {
@@ -70,12 +99,20 @@ fn main() {
                 .at((size.x / 2.0 - 50.0, 20.0))
                 .size(20.0)
                 .color(Color::WHITE);
-            gfx.text(&format!("Crabs: {}", crabs.len()))
-                .at(vec2(10.0, 10.0))
-                .color(Color::WHITE);
-            gfx.text(&format!("FPS: {}", timer.fps))
-                .at(vec2(10.0, 28.0))
-                .color(Color::WHITE);
+            // counters change every frame, so they skip text shaping
+            let stats = format!(
+                "Crabs: {}\nFPS: {:.0} | p95: {:.1}ms",
+                crabs.len(),
+                timer.fps_avg(),
+                timer.frame_time_ms_p95()
+            );
+            // 16 units tall is 2x the 6x8 debug font cells
+            let columns = stats.lines().map(str::len).max().unwrap_or(0);
+            gfx.rect()
+                .at(vec2(6.0, 6.0))
+                .size(vec2(columns as f32 * 12.0 + 8.0, 40.0))
+                .color(Color::BLACK.with_alpha(0.6));
+            gfx.debug_text(&stats).at(vec2(10.0, 10.0)).size(16.0);
         },
     );
 }
@@ -0,0 +1,111 @@
+//! A flag waving on its pole, a textured grid mesh deformed every frame with
+//! `gfx.mesh_raw`. Hold space for a stronger wind
+
+use egor::{
+    app::{App, FrameContext},
+    input::KeyCode,
+    math::{Vec2, vec2},
+    render::{Color, MeshVertex, TextureId},
+};
+
+const COLUMNS: usize = 32;
+const ROWS: usize = 18;
+const FLAG_SIZE: Vec2 = vec2(420.0, 240.0);
+const POLE_TOP: Vec2 = vec2(200.0, 120.0);
+const TEXTURE_SIZE: u32 = 64;
+
+/// Two stripes & a disc, so the folds are easy to follow
+fn flag_pixels() -> Vec<u8> {
+    let size = TEXTURE_SIZE as f32;
+    (0..TEXTURE_SIZE * TEXTURE_SIZE)
+        .flat_map(|i| {
+            let (x, y) = ((i % TEXTURE_SIZE) as f32, (i / TEXTURE_SIZE) as f32);
+            let center = vec2(size * 0.35, size * 0.5);
+            if vec2(x, y).distance(center) < size * 0.2 {
+                [250, 200, 40, 255]
+            } else if y < size * 0.5 {
+                [30, 90, 200, 255]
+            } else {
+                [220, 40, 50, 255]
+            }
+        })
+        .collect()
+}
+
+/// Two triangles per grid cell, built once since only the positions move
+fn grid_indices() -> Vec<u16> {
+    let corner = |x: usize, y: usize| (y * (COLUMNS + 1) + x) as u16;
+    let mut indices = Vec::with_capacity(COLUMNS * ROWS * 6);
+    for y in 0..ROWS {
+        for x in 0..COLUMNS {
+            let (a, b) = (corner(x, y), corner(x + 1, y));
+            let (c, d) = (corner(x, y + 1), corner(x + 1, y + 1));
+            indices.extend_from_slice(&[a, b, d, a, d, c]);
+        }
+    }
+    indices
+}
+
+/// Moves every vertex of the flag to where the wind has blown it after `blown` seconds.
+/// The cloth is pinned to the pole, so waves grow towards the free edge, & shaded by how
+/// steep the wave is there
+fn wave(vertices: &mut Vec<MeshVertex>, blown: f32, wind: f32) {
+    vertices.clear();
+    for y in 0..=ROWS {
+        for x in 0..=COLUMNS {
+            let uv = vec2(x as f32 / COLUMNS as f32, y as f32 / ROWS as f32);
+            let phase = uv.x * 9.0 - blown * 4.0 + uv.y * 1.5;
+            let amplitude = uv.x * 18.0 * wind;
+            // the cloth bunches up a little where it bends
+            let offset = vec2(
+                -phase.sin().abs() * amplitude * 0.3,
+                phase.sin() * amplitude,
+            );
+            let shade = 0.8 + 0.2 * phase.cos();
+            vertices.push(MeshVertex::new(
+                POLE_TOP + uv * FLAG_SIZE + offset,
+                uv,
+                Color::new([shade, shade, shade, 1.0]),
+            ));
+        }
+    }
+}
+
+fn main() {
+    let mut flag = TextureId::default();
+    let indices = grid_indices();
+    let mut vertices = Vec::with_capacity((COLUMNS + 1) * (ROWS + 1));
+    let mut blown = 0.0;
+
+    App::new().title("Egor Flag Demo").run(
+        move |FrameContext {
+                  gfx, input, timer, ..
+              }| {
+            if timer.frame == 0 {
+                let pixels = flag_pixels();
+                flag = gfx.load_texture_raw_repeating(TEXTURE_SIZE, TEXTURE_SIZE, &pixels);
+            }
+            let wind = if input.key_held(KeyCode::Space) {
+                2.0
+            } else {
+                1.0
+            };
+            // advanced by the wind instead of derived from the time, so gusts don't jump
+            blown += timer.delta * wind;
+            wave(&mut vertices, blown, wind);
+
+            gfx.clear(Color::new([0.55, 0.75, 0.95, 1.0]));
+            gfx.rect()
+                .at(POLE_TOP - vec2(10.0, 10.0))
+                .size(vec2(10.0, 520.0))
+                .color(Color::new([0.3, 0.3, 0.32, 1.0]));
+            gfx.mesh_raw(Some(flag))
+                .vertices(&vertices)
+                .indices(&indices);
+
+            gfx.text("hold space for a stronger wind")
+                .at(vec2(16.0, 16.0))
+                .color(Color::BLACK);
+        },
+    );
+}
@@ -1,7 +1,7 @@
 use egor::{
     app::{App, FrameContext},
-    math::vec2,
-    render::Color,
+    math::{Rect, vec2},
+    render::{Align, Color},
 };
 
 #[repr(C)]
@@ -63,6 +63,31 @@ fn main() {
 
             gfx.text(&format!("FPS: {}", timer.fps))
                 .at(vec2(10.0, 10.0))
-                .color(Color::WHITE);
+                .color(Color::WHITE)
+                .background(Color::BLACK.with_alpha(0.6))
+                .padding(4.0)
+                .background_radius(4.0);
+
+            // typography options, combined & across multiple lines
+            let styles = Rect::new(vec2(0.0, size.y - 120.0), vec2(size.x, 100.0));
+            gfx.text("Bold & underlined\nacross two lines")
+                .in_rect(styles, Align::MiddleLeft)
+                .size(18.0)
+                .color(Color::LIGHT_GRAY)
+                .bold()
+                .underline();
+            gfx.text("Light & struck")
+                .in_rect(styles, Align::TopCenter)
+                .size(18.0)
+                .color(Color::LIGHT_GRAY)
+                .weight(300)
+                .strikethrough();
+            gfx.text("Italic, underlined\n& struck through")
+                .in_rect(styles, Align::BottomRight)
+                .size(18.0)
+                .color(Color::LIGHT_GRAY)
+                .italic()
+                .underline()
+                .strikethrough();
         });
 }
@@ -1,7 +1,7 @@
 use egor::{
     app::{App, FrameContext},
     math::{Vec2, vec2},
-    render::{Color, Graphics, OffscreenTarget, RenderTarget},
+    render::{Color, Graphics, OffscreenTarget, RenderTarget, TextureId},
 };
 
 use std::fs;
@@ -14,7 +14,7 @@ fn load_effect(gfx: &mut Graphics, effect: &str) -> usize {
 
 fn main() {
     let mut offscreen_target = None;
-    let mut texture_id = 0;
+    let mut texture_id = TextureId::default();
 
     App::new()
         .title("Egor Hot Reload/Post Processing Demo")
@@ -0,0 +1,100 @@
+use std::{cell::RefCell, rc::Rc};
+
+use egor::{
+    app::{App, FrameContext},
+    dialogs::FileFilter,
+    input::KeyCode,
+    math::vec2,
+    render::{Anchor, Color, TextureId},
+};
+
+struct Picture {
+    name: String,
+    bytes: Vec<u8>,
+    size: (u32, u32),
+    texture: Option<TextureId>,
+}
+
+fn main() {
+    // dialog callbacks run between frames, so they hand their file over through this
+    let opened: Rc<RefCell<Option<Picture>>> = Rc::default();
+    let status = Rc::new(RefCell::new(String::from("Press O to open a PNG")));
+    let mut picture: Option<Picture> = None;
+    let png = [FileFilter::new("PNG image", &["png"])];
+
+    App::new().title("Egor Image Viewer Demo").run(
+        move |FrameContext {
+                  gfx, app, input, ..
+              }| {
+            if let Some(mut new) = opened.borrow_mut().take() {
+                new.texture = Some(gfx.load_texture(&new.bytes));
+                *status.borrow_mut() = format!("{} ({}x{})", new.name, new.size.0, new.size.1);
+                picture = Some(new);
+            }
+
+            if let Some(dialogs) = app.dialogs()
+                && !dialogs.is_open()
+            {
+                if input.key_pressed(KeyCode::KeyO) {
+                    let (opened, status) = (opened.clone(), status.clone());
+                    dialogs.open_file(&png, move |file| {
+                        let Some(file) = file else { return };
+                        let decoded = file.read().map_err(|e| e.to_string()).and_then(|bytes| {
+                            let image =
+                                image::load_from_memory(&bytes).map_err(|e| e.to_string())?;
+                            Ok((bytes, (image.width(), image.height())))
+                        });
+                        match decoded {
+                            Ok((bytes, size)) => {
+                                *opened.borrow_mut() = Some(Picture {
+                                    name: file.name(),
+                                    bytes,
+                                    size,
+                                    texture: None,
+                                })
+                            }
+                            Err(e) => *status.borrow_mut() = format!("{}: {e}", file.name()),
+                        }
+                    });
+                }
+                if input.key_pressed(KeyCode::KeyS)
+                    && let Some(picture) = &picture
+                {
+                    let (bytes, status) = (picture.bytes.clone(), status.clone());
+                    dialogs.save_file(&png, &picture.name, move |file| {
+                        let Some(file) = file else { return };
+                        *status.borrow_mut() = match file.write(&bytes) {
+                            Ok(()) => format!("Saved {}", file.name()),
+                            Err(e) => format!("{}: {e}", file.name()),
+                        };
+                    });
+                }
+            }
+
+            gfx.clear(Color::new([0.12, 0.12, 0.14, 1.0]));
+            let screen = gfx.screen_size();
+            if let Some(Picture {
+                size: (w, h),
+                texture: Some(texture),
+                ..
+            }) = picture
+            {
+                // fit inside the window, never upscaled
+                let size = vec2(w as f32, h as f32);
+                let fit = (screen - vec2(40.0, 80.0)) / size;
+                gfx.rect()
+                    .anchor(Anchor::Center)
+                    .at(screen / 2.0)
+                    .size(size * fit.min_element().min(1.0))
+                    .texture(texture);
+            }
+
+            gfx.text(&status.borrow())
+                .at(vec2(10.0, 10.0))
+                .color(Color::WHITE);
+            gfx.text("O: open   S: save a copy")
+                .at(vec2(10.0, screen.y - 30.0))
+                .color(Color::LIGHT_GRAY);
+        },
+    );
+}
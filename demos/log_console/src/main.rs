@@ -0,0 +1,105 @@
+//! An egui console listing what the engine & game log, through `App::log_to`.
+//! Space logs from the game, S loads a broken shader to make the engine log an error
+
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+
+use egor::{
+    app::{
+        App, FrameContext, Level, LevelFilter,
+        egui::{Color32, RichText, ScrollArea, Window},
+    },
+    input::KeyCode,
+    math::vec2,
+    render::Color,
+};
+
+/// Most lines the console keeps
+const MAX_LINES: usize = 200;
+
+struct Line {
+    level: Level,
+    frame: u64,
+    target: String,
+    message: String,
+}
+
+fn level_color(level: Level) -> Color32 {
+    match level {
+        Level::Error => Color32::LIGHT_RED,
+        Level::Warn => Color32::YELLOW,
+        Level::Info => Color32::LIGHT_GREEN,
+        Level::Debug | Level::Trace => Color32::GRAY,
+    }
+}
+
+fn main() {
+    let lines = Arc::new(Mutex::new(VecDeque::new()));
+    let sink = lines.clone();
+    let mut presses = 0;
+
+    App::new()
+        .title("Egor Log Console Demo")
+        .log_level(LevelFilter::Warn)
+        .log_module("demo_egor_log_console", LevelFilter::Info)
+        .log_module("egor_glue", LevelFilter::Info)
+        .log_to(move |record| {
+            let mut lines = sink.lock().unwrap();
+            if lines.len() == MAX_LINES {
+                lines.pop_front();
+            }
+            lines.push_back(Line {
+                level: record.level,
+                frame: record.frame,
+                target: record.target.to_owned(),
+                message: record.message.clone(),
+            });
+        })
+        .run(
+            move |FrameContext {
+                      gfx,
+                      input,
+                      egui_ctx,
+                      ..
+                  }| {
+                if input.key_pressed(KeyCode::Space) {
+                    presses += 1;
+                    log::info!("space pressed {presses} times");
+                }
+                if input.key_pressed(KeyCode::KeyS) {
+                    gfx.load_shader("@fragment fn fs_main() -> @location(0) vec4<f32> { nope }");
+                }
+
+                gfx.clear(Color::new([0.1, 0.1, 0.12, 1.0]));
+                gfx.text("Space logs from the game, S loads a broken shader")
+                    .at(vec2(16.0, 16.0))
+                    .color(Color::WHITE);
+
+                Window::new("Console")
+                    .default_size([520.0, 260.0])
+                    .show(egui_ctx, |ui| {
+                        if ui.button("Clear").clicked() {
+                            lines.lock().unwrap().clear();
+                        }
+                        ScrollArea::vertical().stick_to_bottom(true).show(ui, |ui| {
+                            for line in lines.lock().unwrap().iter() {
+                                ui.horizontal(|ui| {
+                                    ui.label(
+                                        RichText::new(format!("{:>6}", line.frame)).monospace(),
+                                    );
+                                    ui.label(
+                                        RichText::new(line.level.as_str())
+                                            .monospace()
+                                            .color(level_color(line.level)),
+                                    );
+                                    ui.label(RichText::new(&line.target).monospace().weak());
+                                    ui.label(&line.message);
+                                });
+                            }
+                        });
+                    });
+            },
+        );
+}
@@ -0,0 +1,59 @@
+//! A frame time HUD floating over the desktop in a transparent, always on top window.
+//! Needs a compositor on Linux/X11; press Escape to close it
+
+use egor::{
+    app::{App, FrameContext},
+    input::KeyCode,
+    math::{Rect, Vec2, vec2},
+    render::{Align, BorderRadii, Color},
+};
+
+const SIZE: (u32, u32) = (320, 140);
+const PADDING: f32 = 12.0;
+
+fn main() {
+    App::new()
+        .title("Egor Overlay Demo")
+        .window_size(SIZE.0, SIZE.1)
+        .transparent(true)
+        .always_on_top(true)
+        .resizable(false)
+        .frame_history(120)
+        .run(
+            |FrameContext {
+                 gfx,
+                 input,
+                 timer,
+                 app,
+                 ..
+             }| {
+                if input.key_pressed(KeyCode::Escape) {
+                    app.request_exit();
+                }
+
+                let size = gfx.screen_size();
+                // fully transparent, so only the panel below covers the desktop
+                gfx.clear(Color::TRANSPARENT);
+                gfx.path()
+                    .round_rect(size, Some(BorderRadii::new(16.0)))
+                    .fill_color(Color::new([0.05, 0.05, 0.1, 0.6]));
+
+                gfx.text(&format!(
+                    "{:.0} fps  p95 {:.1} ms",
+                    timer.fps_avg(),
+                    timer.frame_time_ms_p95()
+                ))
+                .in_rect(
+                    Rect::new(Vec2::splat(PADDING), vec2(size.x - PADDING * 2.0, 24.0)),
+                    Align::MiddleLeft,
+                )
+                .size(20.0)
+                .color(Color::WHITE);
+
+                gfx.frame_graph(Rect::new(
+                    vec2(PADDING, 48.0),
+                    vec2(size.x - PADDING * 2.0, size.y - 48.0 - PADDING),
+                ));
+            },
+        );
+}
@@ -0,0 +1,98 @@
+//! Three layers scrolling at different speeds with `gfx.with_parallax`: fixed stars, slow
+//! hills & the ground moving with the camera. Scroll with the arrow keys, zoom with +/-
+
+use egor::{
+    app::{App, FrameContext},
+    input::KeyCode,
+    math::{Vec2, vec2},
+    render::{Color, Graphics},
+};
+
+const SCROLL_SPEED: f32 = 400.0;
+const HILLS: Vec2 = vec2(0.4, 1.0);
+const HILL_WIDTH: f32 = 320.0;
+const GROUND_Y: f32 = 420.0;
+const TILE_WIDTH: f32 = 80.0;
+
+/// Cheap hash in 0..1 so the stars & hills stay put between frames
+fn hash(i: i32) -> f32 {
+    let x = (i as u32).wrapping_mul(0x9e37_79b9) ^ 0x85eb_ca6b;
+    (x.wrapping_mul(0xc2b2_ae35) >> 8) as f32 / (1 << 24) as f32
+}
+
+/// Indices of the `width` wide slots overlapping the camera's view
+fn visible_slots(gfx: &mut Graphics, width: f32) -> std::ops::Range<i32> {
+    let screen = gfx.screen_size();
+    let view = gfx.camera().viewport(screen);
+    (view.min().x / width).floor() as i32..(view.max().x / width).ceil() as i32 + 1
+}
+
+fn stars(gfx: &mut Graphics) {
+    let screen = gfx.screen_size();
+    for i in 0..120 {
+        let position = vec2(hash(i), hash(i + 1000) * 0.6) * screen;
+        let size = 1.0 + hash(i + 2000) * 2.0;
+        gfx.rect()
+            .at(position)
+            .size(Vec2::splat(size))
+            .color(Color::WHITE);
+    }
+}
+
+fn hills(gfx: &mut Graphics) {
+    for i in visible_slots(gfx, HILL_WIDTH) {
+        let left = i as f32 * HILL_WIDTH;
+        let height = 80.0 + hash(i) * 140.0;
+        gfx.polygon()
+            .points(&[
+                vec2(left - HILL_WIDTH * 0.5, GROUND_Y),
+                vec2(left + HILL_WIDTH * 0.5, GROUND_Y - height),
+                vec2(left + HILL_WIDTH * 1.5, GROUND_Y),
+            ])
+            .color(Color::new([0.15, 0.3 + hash(i + 7) * 0.1, 0.2, 1.0]));
+    }
+}
+
+fn ground(gfx: &mut Graphics) {
+    for i in visible_slots(gfx, TILE_WIDTH) {
+        let shade = if i % 2 == 0 { 0.35 } else { 0.3 };
+        gfx.rect()
+            .at(vec2(i as f32 * TILE_WIDTH, GROUND_Y))
+            .size(vec2(TILE_WIDTH, 400.0))
+            .color(Color::new([shade, shade * 0.7, 0.15, 1.0]));
+    }
+}
+
+fn main() {
+    let mut position = Vec2::ZERO;
+    let mut zoom = 1.0;
+
+    App::new().title("Egor Parallax Demo").run(
+        move |FrameContext {
+                  gfx, input, timer, ..
+              }| {
+            let dx = input.keys_held(&[KeyCode::ArrowRight]) as i8
+                - input.keys_held(&[KeyCode::ArrowLeft]) as i8;
+            let dy = input.keys_held(&[KeyCode::ArrowDown]) as i8
+                - input.keys_held(&[KeyCode::ArrowUp]) as i8;
+            position += vec2(dx as f32, dy as f32) * SCROLL_SPEED * timer.delta;
+            if input.key_pressed(KeyCode::Equal) {
+                zoom *= 1.25;
+            }
+            if input.key_pressed(KeyCode::Minus) {
+                zoom /= 1.25;
+            }
+            gfx.camera().set_zoom(zoom);
+            gfx.camera().target(position);
+
+            gfx.clear(Color::new([0.02, 0.02, 0.08, 1.0]));
+            gfx.with_parallax(Vec2::ZERO, stars);
+            gfx.with_parallax(HILLS, hills);
+            ground(gfx);
+
+            gfx.text("arrow keys scroll, +/- zoom")
+                .at(vec2(16.0, 16.0))
+                .color(Color::WHITE);
+        },
+    );
+}
@@ -0,0 +1,82 @@
+use egor::{
+    app::{App, FrameContext},
+    input::KeyCode,
+    math::{Vec2, vec2},
+    render::{Color, PostEffect},
+};
+
+const KEYS: [KeyCode; 4] = [
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+];
+const TILE: f32 = 48.0;
+
+fn main() {
+    let mut enabled = [true, false, false, false];
+    let mut reversed = false;
+    let mut time = 0.0f32;
+
+    App::new()
+        .title("Egor Post Effects Demo")
+        .window_size(800, 600)
+        .run(
+            move |FrameContext {
+                      gfx, input, timer, ..
+                  }| {
+                time += timer.delta;
+                for (on, key) in enabled.iter_mut().zip(KEYS) {
+                    *on ^= input.key_pressed(key);
+                }
+                reversed ^= input.key_pressed(KeyCode::KeyR);
+
+                let size = gfx.screen_size();
+                gfx.clear(Color::new([0.08, 0.08, 0.12, 1.0]));
+                let (cols, rows) = ((size.x / TILE) as u32 + 1, (size.y / TILE) as u32 + 1);
+                for y in 0..rows {
+                    for x in 0..cols {
+                        let hue = (x + y) as f32 * 0.3 + time;
+                        let color = Color::new([
+                            0.5 + 0.5 * hue.sin(),
+                            0.5 + 0.5 * (hue + 2.1).sin(),
+                            0.5 + 0.5 * (hue + 4.2).sin(),
+                            1.0,
+                        ]);
+                        gfx.rect()
+                            .at(vec2(x as f32, y as f32) * TILE + Vec2::splat(4.0))
+                            .size(Vec2::splat(TILE - 8.0))
+                            .color(color);
+                    }
+                }
+                gfx.polygon()
+                    .at(size * 0.5 + vec2(time.cos(), time.sin()) * size.y * 0.25)
+                    .radius(size.y * 0.1)
+                    .segments(48)
+                    .color(Color::WHITE);
+
+                // parameters change every frame, which only rewrites the effects' uniforms
+                let effects = gfx
+                    .post_effects()
+                    .vignette(0.6 + 0.3 * time.sin())
+                    .chromatic_aberration(4.0 + 3.0 * (time * 2.0).sin())
+                    .pixelate(6.0 + 3.0 * (time * 0.5).sin())
+                    .crt(0.08, 0.4);
+                let effects = PostEffect::ALL
+                    .into_iter()
+                    .zip(enabled)
+                    .fold(effects, |effects, (effect, on)| {
+                        effects.set_enabled(effect, on)
+                    });
+                let mut order = PostEffect::ALL;
+                if reversed {
+                    order.reverse();
+                }
+                let status = format!(
+                    "1-4 toggle, R reverses: {:?}",
+                    effects.order(&order).enabled()
+                );
+                gfx.debug_text(&status).at(vec2(10.0, 10.0)).size(16.0);
+            },
+        );
+}
@@ -2,10 +2,9 @@ mod lightning;
 
 use egor::{
     app::{App, FrameContext},
-    math::{Vec2, vec2},
+    math::{Vec2, random::Rng, vec2},
     render::Color,
 };
-use rand::Rng;
 use secs::World;
 use std::f32::consts::TAU;
 
@@ -52,7 +51,8 @@ struct IceCube {
 egor::main!(main);
 pub fn main() {
     let world = World::default();
-    let mut rng = rand::thread_rng();
+    // seeded, so every run puts on the same show
+    let mut rng = Rng::new(0x5eed);
     let mut spawn_timer = 0.0;
     let mut shake = Vec2::ZERO;
 
@@ -69,20 +69,20 @@ pub fn main() {
                 spawn_timer = 0.0;
                 let mut rand_pos = || {
                     vec2(
-                        rng.gen_range(-screen.x * 0.45..screen.x * 0.45),
-                        rng.gen_range(-screen.y * 0.45..screen.y * 0.45),
+                        rng.range(-screen.x * 0.45..screen.x * 0.45),
+                        rng.range(-screen.y * 0.45..screen.y * 0.45),
                     )
                 };
                 let (a, b) = (rand_pos(), rand_pos());
                 let dir = (b - a).normalize_or_zero();
 
-                match rng.gen_range(0..3) {
+                match rng.range(0..3) {
                     0 => {
                         world.spawn((Fireball {
                             pos: a,
-                            vel: dir * rng.gen_range(200.0..300.0),
-                            life: rng.gen_range(1.2..1.8),
-                            size: rng.gen_range(20.0..30.0),
+                            vel: dir * rng.range(200.0..300.0),
+                            life: rng.range(1.2..1.8),
+                            size: rng.range(20.0..30.0),
                             trail_timer: 0.0,
                             depth: 0,
                         },));
@@ -90,17 +90,17 @@ pub fn main() {
                     1 => {
                         world.spawn((IceCube {
                             pos: b,
-                            vel: dir * rng.gen_range(220.0..320.0),
-                            life: rng.gen_range(1.0..1.6),
-                            size: rng.gen_range(18.0..28.0),
-                            rotation: rng.gen_range(0.0..TAU),
-                            rot_vel: rng.gen_range(-8.0..8.0),
+                            vel: dir * rng.range(220.0..320.0),
+                            life: rng.range(1.0..1.6),
+                            size: rng.range(18.0..28.0),
+                            rotation: rng.range(0.0..TAU),
+                            rot_vel: rng.range(-8.0..8.0),
                             depth: 0,
                         },));
                     }
                     _ => {
                         lightning::spawn(&world, &mut rng, a, b, 0, 0);
-                        shake += vec2(rng.gen_range(-6.0..6.0), rng.gen_range(-6.0..6.0));
+                        shake += vec2(rng.range(-6.0..6.0), rng.range(-6.0..6.0));
                     }
                 }
             }
@@ -117,28 +117,28 @@ pub fn main() {
                 if f.life <= 0.0 {
                     let base = if f.depth == 0 { 60 } else { 30 };
                     for _ in 0..base.min(MAX_PARTICLES - particle_count) {
-                        let a = rng.gen_range(0.0..TAU);
+                        let a = rng.range(0.0..TAU);
                         particle_spawns.push(Particle {
                             pos: f.pos,
-                            vel: vec2(a.cos(), a.sin()) * rng.gen_range(100.0..400.0),
-                            life: rng.gen_range(0.5..1.4),
+                            vel: vec2(a.cos(), a.sin()) * rng.range(100.0..400.0),
+                            life: rng.range(0.5..1.4),
                             max_life: 1.4,
-                            size: rng.gen_range(5.0..16.0),
-                            color: [1.0, rng.gen_range(0.3..0.9), rng.gen_range(0.0..0.2)],
+                            size: rng.range(5.0..16.0),
+                            color: [1.0, rng.range(0.3..0.9), rng.range(0.0..0.2)],
                             particle_type: ParticleType::Fire,
                         });
                     }
 
                     if f.depth < 5 {
-                        let split_count = rng.gen_range(1..5) as f32 * 3.0;
+                        let split_count = rng.range(1..5) as f32 * 3.0;
                         let base_ang = f.vel.y.atan2(f.vel.x);
                         for i in 0..split_count as u16 {
                             let spread = (i as f32 - split_count * 0.5) * 0.4;
-                            let ang = base_ang + spread + rng.gen_range(-0.2..0.2);
+                            let ang = base_ang + spread + rng.range(-0.2..0.2);
                             fireball_spawns.push(Fireball {
                                 pos: f.pos,
-                                vel: vec2(ang.cos(), ang.sin()) * rng.gen_range(180.0..280.0),
-                                life: rng.gen_range(0.7..1.2),
+                                vel: vec2(ang.cos(), ang.sin()) * rng.range(180.0..280.0),
+                                life: rng.range(0.7..1.2),
                                 size: f.size * 0.65,
                                 trail_timer: 0.0,
                                 depth: f.depth + 3,
@@ -146,7 +146,7 @@ pub fn main() {
                         }
                     }
 
-                    shake += vec2(rng.gen_range(-18.0..18.0), rng.gen_range(-18.0..18.0));
+                    shake += vec2(rng.range(-18.0..18.0), rng.range(-18.0..18.0));
                     world.despawn(e);
                     return;
                 }
@@ -156,13 +156,12 @@ pub fn main() {
                 if f.trail_timer > 0.02 && particle_count < MAX_PARTICLES {
                     f.trail_timer = 0.0;
                     particle_spawns.push(Particle {
-                        pos: f.pos + vec2(rng.gen_range(-4.0..4.0), rng.gen_range(-4.0..4.0)),
-                        vel: -f.vel * 0.3
-                            + vec2(rng.gen_range(-40.0..40.0), rng.gen_range(-40.0..40.0)),
+                        pos: f.pos + vec2(rng.range(-4.0..4.0), rng.range(-4.0..4.0)),
+                        vel: -f.vel * 0.3 + vec2(rng.range(-40.0..40.0), rng.range(-40.0..40.0)),
                         life: 0.5,
                         max_life: 0.5,
                         size: f.size * 0.5,
-                        color: [1.0, rng.gen_range(0.4..0.7), 0.0],
+                        color: [1.0, rng.range(0.4..0.7), 0.0],
                         particle_type: ParticleType::Fire,
                     });
                 }
@@ -189,39 +188,39 @@ pub fn main() {
                 if ice.life <= 0.0 {
                     let base = if ice.depth == 0 { 50 } else { 25 };
                     for _ in 0..base.min(MAX_PARTICLES - particle_count) {
-                        let a = rng.gen_range(0.0..TAU);
+                        let a = rng.range(0.0..TAU);
                         particle_spawns.push(Particle {
                             pos: ice.pos,
-                            vel: vec2(a.cos(), a.sin()) * rng.gen_range(80.0..300.0),
-                            life: rng.gen_range(0.6..1.3),
+                            vel: vec2(a.cos(), a.sin()) * rng.range(80.0..300.0),
+                            life: rng.range(0.6..1.3),
                             max_life: 1.3,
-                            size: rng.gen_range(4.0..12.0),
-                            color: [rng.gen_range(0.6..0.9), rng.gen_range(0.8..1.0), 1.0],
+                            size: rng.range(4.0..12.0),
+                            color: [rng.range(0.6..0.9), rng.range(0.8..1.0), 1.0],
                             particle_type: ParticleType::Ice,
                         });
                     }
 
                     if ice.depth < 6 {
                         let split_count = if ice.depth == 0 {
-                            rng.gen_range(3..5)
+                            rng.range(3..5)
                         } else {
-                            rng.gen_range(2..4)
+                            rng.range(2..4)
                         };
                         for _ in 0..split_count {
-                            let a = rng.gen_range(0.0..TAU);
+                            let a = rng.range(0.0..TAU);
                             ice_spawns.push(IceCube {
                                 pos: ice.pos,
-                                vel: vec2(a.cos(), a.sin()) * rng.gen_range(150.0..250.0),
-                                life: rng.gen_range(0.6..1.0),
+                                vel: vec2(a.cos(), a.sin()) * rng.range(150.0..250.0),
+                                life: rng.range(0.6..1.0),
                                 size: ice.size * 0.55,
-                                rotation: rng.gen_range(0.0..TAU),
-                                rot_vel: rng.gen_range(-10.0..10.0),
+                                rotation: rng.range(0.0..TAU),
+                                rot_vel: rng.range(-10.0..10.0),
                                 depth: ice.depth + 1,
                             });
                         }
                     }
 
-                    shake += vec2(rng.gen_range(-4.0..4.0), rng.gen_range(-4.0..4.0));
+                    shake += vec2(rng.range(-4.0..4.0), rng.range(-4.0..4.0));
                     world.despawn(e);
                     return;
                 }
@@ -293,6 +292,8 @@ pub fn main() {
 
             world.flush_despawned();
             gfx.text(&format!("particles: {} | fps: {:.0}", drawn, timer.fps))
-                .color(Color::WHITE);
+                .color(Color::WHITE)
+                .background(Color::BLACK.with_alpha(0.6))
+                .padding(4.0);
         });
 }
@@ -1,11 +1,10 @@
 use std::f32::consts::TAU;
 
 use egor::{
-    math::{Vec2, vec2},
+    math::{Vec2, random::Rng, vec2},
     render::{Color, Graphics},
     time::FrameTimer,
 };
-use rand::Rng;
 use secs::World;
 
 struct LightningSeg {
@@ -18,7 +17,7 @@ struct LightningSeg {
 
 pub fn spawn(
     world: &World,
-    rng: &mut impl Rng,
+    rng: &mut Rng,
     mut start: Vec2,
     target: Vec2,
     depth: usize,
@@ -33,10 +32,10 @@ pub fn spawn(
     let thickness = 4.0 / (branch as f32 + 1.0);
 
     while traveled < total_len {
-        let seg_len: f32 = rng.gen_range(8.0..20.0);
+        let seg_len: f32 = rng.range(8.0..20.0);
         let step_len = seg_len.min(total_len - traveled);
         let dir = (target - start).normalize_or_zero();
-        let offset_angle: f32 = rng.gen_range(-0.8..0.8);
+        let offset_angle: f32 = rng.range(-0.8..0.8);
         let seg_dir = vec2(
             dir.x * offset_angle.cos() - dir.y * offset_angle.sin(),
             dir.x * offset_angle.sin() + dir.y * offset_angle.cos(),
@@ -46,20 +45,20 @@ pub fn spawn(
         world.spawn((LightningSeg {
             a: start,
             b: next,
-            life: rng.gen_range(0.12..0.22),
-            glow: rng.gen_range(0.7..1.0),
+            life: rng.range(0.12..0.22),
+            glow: rng.range(0.7..1.0),
             thickness,
         },));
 
-        if branch < 3 && rng.gen_bool(0.5) {
-            let forks = if branch == 0 { rng.gen_range(1..3) } else { 1 };
+        if branch < 3 && rng.chance(0.5) {
+            let forks = if branch == 0 { rng.range(1..3) } else { 1 };
             for _ in 0..forks {
-                let fork_angle: f32 = rng.gen_range(-TAU / 3.0..TAU / 3.0);
+                let fork_angle: f32 = rng.range(-TAU / 3.0..TAU / 3.0);
                 let fork_dir = vec2(
                     dir.x * fork_angle.cos() - dir.y * fork_angle.sin(),
                     dir.x * fork_angle.sin() + dir.y * fork_angle.cos(),
                 );
-                let fork_len: f32 = rng.gen_range(40.0..100.0) / (branch as f32 + 1.0);
+                let fork_len: f32 = rng.range(40.0..100.0) / (branch as f32 + 1.0);
                 spawn(
                     world,
                     rng,
@@ -1,6 +1,8 @@
 fn main() {
     // This is called from a public `main()` defined in lib.rs
     // `Egor_main()` macro expects it to be passed in so it can gen `android_main()` entry point there
-    // We simply call that `main()` here as well since desktop and wasm expect the normal entry point
+    // We simply call that `main()` here as well since desktop expects the normal entry point
+    // On wasm the macro also generates the start function, so calling it here would run it twice
+    #[cfg(not(target_arch = "wasm32"))]
     demo_egor_secs_particles::main();
 }
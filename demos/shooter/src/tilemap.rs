@@ -8,7 +8,7 @@ use serde_json::from_str;
 
 use egor::{
     math::{Rect, Vec2, vec2},
-    render::{Color, Graphics},
+    render::{Color, Graphics, StaticGeometryId, TextureId},
 };
 
 #[derive(Deserialize, Debug)]
@@ -21,6 +21,12 @@ pub struct TiledObject {
     pub height: f32,
 }
 
+#[derive(Deserialize, Debug)]
+pub struct TiledProperty {
+    pub name: String,
+    pub value: serde_json::Value,
+}
+
 #[derive(Deserialize, Debug)]
 pub struct TiledLayer {
     pub width: Option<u32>,
@@ -29,6 +35,17 @@ pub struct TiledLayer {
     pub visible: bool,
     pub data: Option<Vec<u32>>,
     pub objects: Option<Vec<TiledObject>>,
+    #[serde(default)]
+    pub properties: Vec<TiledProperty>,
+}
+
+impl TiledLayer {
+    /// Layers with a `static` bool property set in Tiled never change, so they're baked once
+    pub fn is_static(&self) -> bool {
+        self.properties
+            .iter()
+            .any(|p| p.name == "static" && p.value.as_bool() == Some(true))
+    }
 }
 
 #[derive(Deserialize, Debug)]
@@ -63,6 +80,15 @@ impl TiledMap {
         (self.width, self.height)
     }
 
+    /// The whole map in world units
+    pub fn bounds(&self) -> Rect {
+        let (tw, th) = self.tile_size();
+        Rect::new(
+            Vec2::ZERO,
+            vec2(self.width as f32 * tw, self.height as f32 * th),
+        )
+    }
+
     pub fn tile_to_world(&self, x: u32, y: u32) -> Vec2 {
         vec2(
             x as f32 * self.tilewidth as f32,
@@ -96,7 +122,7 @@ impl TiledMap {
 }
 
 struct TilesetInfo {
-    tex_id: usize,
+    tex_id: TextureId,
     first_gid: u32,
     tile_w: u32,
     tile_h: u32,
@@ -107,7 +133,8 @@ struct TilesetInfo {
 
 pub struct EgorMap {
     tiled: TiledMap,
-    sets: HashMap<u32, TilesetInfo>, // key = first_gid
+    sets: HashMap<u32, TilesetInfo>,         // key = first_gid
+    baked: HashMap<usize, StaticGeometryId>, // key = layer index
 }
 
 impl EgorMap {
@@ -115,6 +142,7 @@ impl EgorMap {
         Self {
             tiled: TiledMap::load(json_data),
             sets: HashMap::new(),
+            baked: HashMap::new(),
         }
     }
 
@@ -148,29 +176,47 @@ impl EgorMap {
         }
     }
 
+    /// Draws the tiles in view. Static layers are baked whole on the first call & drawn
+    /// from the GPU after that, so load the tilesets first
     pub fn render(&mut self, gfx: &mut Graphics) {
         let screen = gfx.screen_size();
         let view = gfx.camera().viewport(screen);
-        let (tw, th) = self.tiled.tile_size();
 
-        for layer in &self.tiled.layers {
+        for (i, layer) in self.tiled.layers.iter().enumerate() {
             if !layer.visible || layer.data.is_none() {
                 continue;
             }
-
-            for (x, y, gid) in self.tiled.visible_tiles(layer, &view) {
-                let (info, uv) = match self.lookup_gid(gid) {
-                    Some(v) => v,
-                    None => continue,
-                };
-
-                gfx.rect()
-                    .at(self.tiled.tile_to_world(x, y))
-                    .size(Vec2::new(tw, th))
-                    .texture(info.tex_id)
-                    .color(Color::WHITE)
-                    .uv(uv);
+            if !layer.is_static() {
+                self.draw_tiles(gfx, layer, &view);
+                continue;
             }
+
+            let chunk = match self.baked.get(&i) {
+                Some(&chunk) => chunk,
+                None => {
+                    let bounds = self.tiled.bounds();
+                    let chunk = gfx.bake_static(|gfx| self.draw_tiles(gfx, layer, &bounds));
+                    self.baked.insert(i, chunk);
+                    chunk
+                }
+            };
+            gfx.draw_static(chunk);
+        }
+    }
+
+    fn draw_tiles(&self, gfx: &mut Graphics, layer: &TiledLayer, rect: &Rect) {
+        let (tw, th) = self.tiled.tile_size();
+        for (x, y, gid) in self.tiled.visible_tiles(layer, rect) {
+            let (info, uv) = match self.lookup_gid(gid) {
+                Some(v) => v,
+                None => continue,
+            };
+
+            gfx.rect()
+                .at(self.tiled.tile_to_world(x, y))
+                .size(Vec2::new(tw, th))
+                .sprite(info.tex_id, uv)
+                .color(Color::WHITE);
         }
     }
 
@@ -0,0 +1,41 @@
+use egor::{
+    app::{App, FrameContext},
+    math::{Rect, vec2},
+    render::Color,
+    text_edit::{TextEditState, TextEditStyle},
+};
+
+fn main() {
+    let mut fields = [
+        ("Name", TextEditState::new("Ferris")),
+        ("Greeting", TextEditState::new("")),
+    ];
+    let style = TextEditStyle::default();
+
+    App::new().title("Egor Text Field Demo").run(
+        move |FrameContext {
+                  gfx, input, timer, ..
+              }| {
+            gfx.clear(Color::new([0.1, 0.1, 0.14, 1.0]));
+            for (i, (label, field)) in fields.iter_mut().enumerate() {
+                field.update(input, timer);
+                let position = vec2(40.0, 60.0 + i as f32 * 90.0);
+                gfx.text(label)
+                    .at(position - vec2(0.0, 28.0))
+                    .size(18.0)
+                    .color(Color::WHITE);
+                field.draw(gfx, Rect::new(position, vec2(320.0, 40.0)), &style);
+            }
+
+            let name = fields[0].1.text();
+            let greeting = match fields[1].1.text() {
+                "" => "Hello",
+                greeting => greeting,
+            };
+            gfx.text(&format!("{greeting}, {name}!"))
+                .at(vec2(40.0, 250.0))
+                .size(24.0)
+                .color(Color::WHITE);
+        },
+    );
+}
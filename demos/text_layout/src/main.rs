@@ -0,0 +1,46 @@
+use egor::{
+    app::{App, FrameContext},
+    math::{Rect, vec2},
+    render::{Align, Color, TextAlign},
+};
+
+const PARAGRAPH: &str = "egor keeps 2D graphics dead simple. Shapes, sprites & text all go \
+through tiny builders, so a panel like this one is a handful of lines.";
+const PADDING: f32 = 16.0;
+
+fn main() {
+    App::new()
+        .title("Egor Text Layout Demo")
+        .run(|FrameContext { gfx, .. }| {
+            gfx.clear(Color::new([0.1, 0.1, 0.14, 1.0]));
+            let size = gfx.screen_size();
+            let panel_size = vec2((size.x - PADDING * 4.0) / 3.0, size.y * 0.5);
+
+            let aligns = [
+                ("Left", TextAlign::Left),
+                ("Center", TextAlign::Center),
+                ("Right", TextAlign::Right),
+            ];
+            for (i, (name, align)) in aligns.into_iter().enumerate() {
+                let position = vec2(PADDING + i as f32 * (panel_size.x + PADDING), size.y * 0.25);
+                let panel = Rect::new(position, panel_size);
+                gfx.rect()
+                    .with(&panel)
+                    .fill(false)
+                    .stroke(2.0)
+                    .color(Color::WHITE);
+
+                gfx.text(name)
+                    .at(position - vec2(0.0, 28.0))
+                    .size(20.0)
+                    .color(Color::WHITE);
+                // the block is centered in the panel, each line inside it by `align`
+                gfx.text(PARAGRAPH)
+                    .in_rect(panel, Align::MiddleCenter)
+                    .text_align(align)
+                    .wrap(panel_size.x - PADDING * 2.0)
+                    .size(18.0)
+                    .color(Color::WHITE);
+            }
+        });
+}
@@ -19,7 +19,7 @@
 //! - [`app::App`] - application lifecycle and main loop
 //! - [`render::Graphics`] - high-level 2D drawing API
 //! - [`time::FrameTimer`] - frame timing and delta time
-//! - [`input::Input`] - keyboard and mouse state
+//! - [`input::Input`] - keyboard, mouse & touch state
 //!
 //! ## Minimal Example: Draw a Rectangle
 //! ```no_run
@@ -31,6 +31,20 @@
 //! });
 //! ```
 //!
+//! ## Coordinates
+//! World units map 1:1 to pixels at zoom 1.0, with +X right & +Y down.
+//! By default `(0, 0)` is the top-left corner of the window; use
+//! [`app::App::origin`] with [`render::Origin::Center`] to put it at the center instead.
+//!
+//! Every `rotate` takes radians from +X toward +Y, which is clockwise on screen since +Y
+//! points down; `rotate_deg` takes degrees. Rectangles, polygons, polylines, paths, meshes &
+//! text all turn the same way around their own origin, & textures are upright at 0.
+//! Migrating: rectangles used to add a hidden quarter turn (0 pointed up), so add
+//! `std::f32::consts::FRAC_PI_2` to old rectangle angles to keep sprites facing the same way
+//!
+//! Mouse positions & screen-space drawing use physical pixels. Convert between those, egui's
+//! logical points & world space with [`app::FrameContext::convert`] & [`math::Space`]
+//!
 //! ## Crate Layout
 //! `egor` is a meta crate that re-exports `egor_*` crates for convenience:
 //! - [`egor_render`] - WGPU-based 2D rendering
@@ -40,9 +54,19 @@
 //! ## Cargo Features
 //! Feature | Description | Default
 //! ---|---|---
-//! `log` | Enable logging via `egor_app/log` | opt-in
+//! `log` | Log to the console & [`app::App::log_to`] sinks, with levels per module via `egor_app/log` | opt-in
 //! `hot_reload` | Hot-reload support via `egor_glue/hot_reload` | opt-in
 //! `ui`         | Enable egui integration via `egor_glue/ui` | opt-in
+//! `serde`      | (De)serialize [`render::Color`] as `[r, g, b, a]` & [`render::Camera`] as a versioned [`render::CameraState`], load/save [`app::AppConfig`] as TOML & record/replay input via `egor_glue/serde` | opt-in
+//! `profiling`  | Record [`render::Graphics::profile_scope`] timings via `egor_glue/profiling` | opt-in
+//! `snapshot`   | Run [`app::App::run`] headless & save frames as PNGs when `cargo xtask snapshot` asks for them via `egor_glue/snapshot` | opt-in
+//! `ktx2`       | Load BC, ETC2 & ASTC textures from KTX2 containers with `Graphics::load_texture_compressed` via `egor_render/ktx2` | opt-in
+//! `parallel`   | Tessellate [`render::Graphics::parallel_shapes`] on a rayon thread pool on native via `egor_glue/parallel` | opt-in
+//! `render_thread` | Upload, encode & present frames on a dedicated thread on native via `egor_glue/render_thread`, so waiting on the GPU doesn't stall input | opt-in
+//! `alloc_guard` | Catch per-frame allocations with `Graphics::assert_no_frame_allocations` in debug builds, once you install `alloc_guard::CountingAlloc` as your global allocator, via `egor_glue/alloc_guard` | opt-in
+//! `gpu_debug`  | Name every pass & batch in RenderDoc or Xcode captures with debug groups (see [`render::debug`]) via `egor_render/gpu_debug` | opt-in
+//! `clipboard`  | Copy & paste through the system clipboard on native in [`text_edit::TextEditState`] via `egor_app/clipboard` | opt-in
+//! `dialogs`    | Open & save file dialogs through [`app::AppControl::dialogs`] via `egor_app/dialogs` | opt-in
 //! `webgl`      | WebGL backend for `egor_render` | opt-in
 //! `angle`      | ANGLE backend for `egor_render` | opt-in
 //! `gles`       | OpenGL ES backend for `egor_render` | opt-in
@@ -53,16 +77,47 @@
 //! - Optional backends can be enabled to override defaults or for cross-platform targeting
 
 pub mod app {
-    pub use egor_app::WindowEvent;
+    #[cfg(feature = "log")]
+    pub use egor_app::logging::{Level, LevelFilter, LogRecord};
     #[cfg(target_os = "android")]
     pub use egor_app::{ANDROID_APP, AndroidApp};
-    pub use egor_glue::app::{App, FrameContext};
+    pub use egor_app::{AppConfig, Background, OccludedBehavior, RedrawMode, Resize, WindowEvent};
+    pub use egor_glue::app::{
+        App, AppControl, FixedContext, FrameContext, FrameSummary, FrameUpdate, InitApp,
+        MAX_TICKS_PER_FRAME, RenderThreadSend,
+    };
+    #[cfg(feature = "serde")]
+    pub use egor_glue::config::{self, ConfigError};
+    #[cfg(not(target_arch = "wasm32"))]
+    pub use egor_glue::headless::{FrameOutput, HeadlessApp};
     #[cfg(feature = "ui")]
     pub use egor_glue::ui::egui;
 }
 
+#[cfg(feature = "alloc_guard")]
+pub mod alloc_guard {
+    pub use egor_glue::alloc_guard::CountingAlloc;
+}
+
+#[cfg(feature = "ui")]
+pub mod dev {
+    pub use egor_glue::dev::{CaptureInspector, capture_inspector, shader_errors_window};
+}
+
+#[cfg(feature = "dialogs")]
+pub mod dialogs {
+    pub use egor_app::dialogs::{Dialogs, FileFilter, FileHandle};
+}
+
 pub mod input {
-    pub use egor_app::input::{Input, KeyCode, MouseButton};
+    pub use egor_app::input::{
+        DEFAULT_MULTI_CLICK_INTERVAL, Ime, Input, InputEvent, InputSnapshot, KeyCode,
+        KeyEventRecord, MULTI_CLICK_DISTANCE, MouseButton, Pointer, Touch, TouchPhase,
+    };
+    #[cfg(feature = "serde")]
+    pub use egor_app::record::{
+        InputRecording, RECORDING_VERSION, REPLAY_ABORT_KEY, RecordingError,
+    };
 }
 
 pub mod time {
@@ -71,30 +126,100 @@ pub mod time {
 
 pub mod render {
     pub use egor_glue::{
+        atlas::{AtlasError, SubTexture, TextureAtlas},
+        bitmap_font::{BitmapFont, BitmapTextBuilder, Glyph},
+        camera::{Camera, CameraState, Origin},
+        capture::{BatchRecord, DrawKind, DrawRecord, FrameCapture},
         color::Color,
-        graphics::Graphics,
-        primitives::{Anchor, BorderRadii},
-        text::Align,
+        gizmos::Gizmos,
+        graphics::{Graphics, StaticGeometryId, TextureId},
+        layer::Layer,
+        mesh::{MeshBaker, MeshBuilder, MeshVertex, RawMeshBuilder},
+        post::{PostEffect, PostEffects},
+        primitives::{Anchor, BorderRadii, ParallelShapes, QualityMode},
+        profiler::{ProfileReport, ProfileScope},
+        text::{Align, TextAlign, TextDirection, TextTruncate},
+        widgets::{WidgetTheme, Widgets},
     };
+    #[cfg(feature = "ktx2")]
+    pub use egor_render::CompressedTextureError;
     pub use egor_render::{
-        MemoryHints,
-        target::{OffscreenTarget, RenderTarget},
+        AdapterInfo, AdapterPreference, Backends, Features, GpuContext, GpuDowngrade,
+        LimitShortfall, Limits, MemoryHints, MemoryReport, PRELUDE_DIRECTIVE, PassResources,
+        RendererError, ShaderError, TextureFormat, TextureRegionError, debug,
+        instance::ColorMode,
+        target::{OffscreenTarget, RenderTarget, RgbaImage},
+        wgpu,
     };
 }
 
+pub mod text_edit {
+    pub use egor_app::clipboard::Clipboard;
+    pub use egor_glue::text_edit::{TextEditState, TextEditStyle};
+}
+
+pub mod touch_controls {
+    pub use egor_glue::touch_controls::{TouchControlsLayout, VirtualButton, VirtualJoystick};
+}
+
+pub mod tween {
+    pub use egor_glue::tween::{Ease, Lerp, Tween, TweenHandle, Tweens};
+}
+
 pub mod math {
-    pub use egor_glue::math::{IVec2, Rect, Vec2, ivec2, vec2};
+    pub use egor_glue::coords::{Coords, Space};
+    pub use egor_glue::math::{IVec2, Rect, Transform, Vec2, ivec2, vec2};
+    pub use egor_glue::physics::{MoveResult, RayHit, move_and_collide, raycast_rects};
+    pub use egor_glue::random;
+}
+
+#[doc(hidden)]
+pub mod __private {
+    #[cfg(target_arch = "wasm32")]
+    pub use wasm_bindgen;
+
+    /// Implemented by functions [`crate::main`] accepts as an entry point
+    #[diagnostic::on_unimplemented(
+        message = "egor::main! expects a `fn()`, found `{Self}`",
+        label = "not a `fn()`",
+        note = "pass a function taking no arguments, e.g. `egor::main!(start)` with `fn start()`"
+    )]
+    pub trait MainFn {}
+    impl<F: FnOnce()> MainFn for F {}
+
+    /// Fails to compile when `main` isn't a `fn()`
+    pub const fn check_main<F: MainFn>(_main: &F) {}
 }
 
 #[macro_export]
 /// Invoke this by passing your main function as an argument.
 /// Ensures unusual platforms like android get initialized properly.
+///
+/// Use it from a library crate (`crate-type = ["cdylib", "rlib"]`); it generates
+/// `android_main()` for Android & a `wasm-bindgen` start function for wasm, so no
+/// separate shim is needed. The passed function must be a `fn()`
+///
+/// Anything needing the GPU (e.g. `load_texture`) must happen once the surface exists,
+/// in [`app::App::init`] or the update closure, not directly in the passed function
+// rustfmt re-indents the wasm start attribute further on every run
+#[rustfmt::skip]
 macro_rules! main {
     ($main:expr) => {
+        const _: () = $crate::__private::check_main(&$main);
+
         #[cfg(target_os = "android")]
         #[unsafe(no_mangle)]
-        fn android_main(app: ::egor::app::AndroidApp) {
-            let _ = ::egor::app::ANDROID_APP.set(app);
+        fn android_main(app: $crate::app::AndroidApp) {
+            let _ = $crate::app::ANDROID_APP.set(app);
+            ($main)();
+        }
+
+        #[cfg(target_arch = "wasm32")]
+        #[$crate::__private::wasm_bindgen::prelude::wasm_bindgen(
+            start,
+            wasm_bindgen = $crate::__private::wasm_bindgen
+        )]
+        fn __egor_wasm_start() {
             ($main)();
         }
     };
@@ -0,0 +1,8 @@
+fn main() {
+    // intel_tex_2 builds its ASTC encoder from C++ without linking the C++ runtime
+    match std::env::var("CARGO_CFG_TARGET_OS").as_deref() {
+        Ok("macos") => println!("cargo:rustc-link-lib=c++"),
+        Ok("windows") => {}
+        _ => println!("cargo:rustc-link-lib=stdc++"),
+    }
+}
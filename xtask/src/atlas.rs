@@ -0,0 +1,289 @@
+use image::RgbaImage;
+use serde::Serialize;
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Must match the version `egor_glue::atlas` reads
+const MANIFEST_VERSION: u32 = 1;
+
+/// Where an image landed: page index & top-left corner
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct Placement {
+    page: usize,
+    x: u32,
+    y: u32,
+}
+
+/// Result of [`pack`]
+struct Packing {
+    /// Placement of each input, in input order
+    placements: Vec<Placement>,
+    /// Used width & height of every page
+    page_sizes: Vec<(u32, u32)>,
+}
+
+/// A row of images sharing the same top edge
+struct Shelf {
+    y: u32,
+    height: u32,
+    next_x: u32,
+}
+
+#[derive(Default)]
+struct Page {
+    shelves: Vec<Shelf>,
+    next_y: u32,
+}
+
+impl Page {
+    /// Reserves a `w` x `h` spot with `padding` kept free right & below it
+    fn place(&mut self, (w, h): (u32, u32), max_size: u32, padding: u32) -> Option<(u32, u32)> {
+        if let Some(shelf) = self
+            .shelves
+            .iter_mut()
+            .find(|s| s.height >= h && s.next_x + w <= max_size)
+        {
+            let position = (shelf.next_x, shelf.y);
+            shelf.next_x += w + padding;
+            return Some(position);
+        }
+        if self.next_y + h > max_size {
+            return None;
+        }
+        let position = (0, self.next_y);
+        self.shelves.push(Shelf {
+            y: self.next_y,
+            height: h,
+            next_x: w + padding,
+        });
+        self.next_y += h + padding;
+        Some(position)
+    }
+}
+
+/// Shelf-packs `sizes` into as many `max_size` square pages as needed, tallest first so
+/// shelves stay tight
+fn pack(sizes: &[(u32, u32)], max_size: u32, padding: u32) -> Result<Packing, String> {
+    let mut order: Vec<_> = (0..sizes.len()).collect();
+    order.sort_by_key(|&i| std::cmp::Reverse((sizes[i].1, sizes[i].0)));
+
+    let mut pages: Vec<Page> = Vec::new();
+    let mut placements = vec![Placement::default(); sizes.len()];
+    for i in order {
+        let (w, h) = sizes[i];
+        if w > max_size || h > max_size {
+            return Err(format!(
+                "a {w}x{h} image doesn't fit in {max_size}x{max_size}"
+            ));
+        }
+        let placed = pages
+            .iter_mut()
+            .enumerate()
+            .find_map(|(page, p)| Some((page, p.place((w, h), max_size, padding)?)));
+        let (page, (x, y)) = match placed {
+            Some(placed) => placed,
+            None => {
+                let mut page = Page::default();
+                let position = page.place((w, h), max_size, padding).unwrap();
+                pages.push(page);
+                (pages.len() - 1, position)
+            }
+        };
+        placements[i] = Placement { page, x, y };
+    }
+
+    let mut page_sizes = vec![(0, 0); pages.len()];
+    for (p, &(w, h)) in placements.iter().zip(sizes) {
+        let size = &mut page_sizes[p.page];
+        *size = (size.0.max(p.x + w), size.1.max(p.y + h));
+    }
+    Ok(Packing {
+        placements,
+        page_sizes,
+    })
+}
+
+#[derive(Serialize)]
+struct Manifest {
+    version: u32,
+    pages: Vec<ManifestPage>,
+    regions: BTreeMap<String, ManifestRegion>,
+}
+
+#[derive(Serialize)]
+struct ManifestPage {
+    image: String,
+    width: u32,
+    height: u32,
+}
+
+#[derive(Serialize)]
+struct ManifestRegion {
+    page: usize,
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+}
+
+/// PNGs under `dir`, named by their path relative to it without the extension
+fn collect_pngs(dir: &Path, prefix: &str, out: &mut Vec<(String, PathBuf)>) {
+    let mut entries: Vec<_> = fs::read_dir(dir)
+        .unwrap_or_else(|e| panic!("failed to read {}: {e}", dir.display()))
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    entries.sort();
+    for path in entries {
+        let stem = path.file_stem().unwrap().to_string_lossy();
+        if path.is_dir() {
+            collect_pngs(&path, &format!("{prefix}{stem}/"), out);
+        } else if path
+            .extension()
+            .is_some_and(|e| e.eq_ignore_ascii_case("png"))
+        {
+            out.push((format!("{prefix}{stem}"), path));
+        }
+    }
+}
+
+/// File name of page `index`: the output name for the first page, `name_<index>` after it
+fn page_path(output_png: &Path, index: usize) -> PathBuf {
+    if index == 0 {
+        return output_png.to_path_buf();
+    }
+    let stem = output_png.file_stem().unwrap().to_string_lossy();
+    output_png.with_file_name(format!("{stem}_{index}.png"))
+}
+
+/// Packs every PNG under `input_dir` into atlas pages & writes them with a manifest
+/// for `Graphics::load_atlas_manifest`
+pub fn pack_atlas(
+    input_dir: &Path,
+    output_png: &Path,
+    output_json: &Path,
+    max_size: u32,
+    padding: u32,
+) {
+    let mut inputs = Vec::new();
+    collect_pngs(input_dir, "", &mut inputs);
+    let images: Vec<RgbaImage> = inputs
+        .iter()
+        .map(|(_, path)| {
+            image::open(path)
+                .unwrap_or_else(|e| panic!("failed to load {}: {e}", path.display()))
+                .into_rgba8()
+        })
+        .collect();
+
+    let sizes: Vec<_> = images.iter().map(|img| img.dimensions()).collect();
+    let Packing {
+        placements,
+        page_sizes,
+    } = pack(&sizes, max_size, padding).unwrap_or_else(|e| {
+        eprintln!("error: {e}");
+        std::process::exit(1);
+    });
+
+    let mut pages: Vec<_> = page_sizes
+        .iter()
+        .map(|&(w, h)| RgbaImage::new(w, h))
+        .collect();
+    let mut regions = BTreeMap::new();
+    for (((name, _), image), p) in inputs.into_iter().zip(&images).zip(&placements) {
+        image::imageops::replace(&mut pages[p.page], image, p.x.into(), p.y.into());
+        let (width, height) = image.dimensions();
+        let region = ManifestRegion {
+            page: p.page,
+            x: p.x,
+            y: p.y,
+            width,
+            height,
+        };
+        regions.insert(name, region);
+    }
+
+    let mut manifest_pages = Vec::with_capacity(pages.len());
+    for (index, page) in pages.iter().enumerate() {
+        let path = page_path(output_png, index);
+        page.save(&path)
+            .unwrap_or_else(|e| panic!("failed to write {}: {e}", path.display()));
+        manifest_pages.push(ManifestPage {
+            image: path.file_name().unwrap().to_string_lossy().into_owned(),
+            width: page.width(),
+            height: page.height(),
+        });
+    }
+
+    let manifest = Manifest {
+        version: MANIFEST_VERSION,
+        pages: manifest_pages,
+        regions,
+    };
+    let json = serde_json::to_string_pretty(&manifest).unwrap();
+    fs::write(output_json, json)
+        .unwrap_or_else(|e| panic!("failed to write {}: {e}", output_json.display()));
+    println!(
+        "packed {} images into {} page(s)",
+        images.len(),
+        manifest.pages.len()
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Asserts every image is inside its page & `padding` away from every other one
+    fn assert_padded_without_overlaps(sizes: &[(u32, u32)], packing: &Packing, padding: u32) {
+        let placements = &packing.placements;
+        for (i, (a, &(aw, ah))) in placements.iter().zip(sizes).enumerate() {
+            let (pw, ph) = packing.page_sizes[a.page];
+            assert!(
+                a.x + aw <= pw && a.y + ah <= ph,
+                "image {i} leaves its page"
+            );
+            for (b, &(bw, bh)) in placements.iter().zip(sizes).skip(i + 1) {
+                let apart = a.page != b.page
+                    || a.x + aw + padding <= b.x
+                    || b.x + bw + padding <= a.x
+                    || a.y + ah + padding <= b.y
+                    || b.y + bh + padding <= a.y;
+                assert!(apart, "{a:?} & {b:?} overlap or break the padding");
+            }
+        }
+    }
+
+    #[test]
+    fn packs_every_image_without_overlaps() {
+        let sizes: Vec<_> = (0..200)
+            .map(|i| (8 + (i * 7) % 40, 8 + (i * 13) % 32))
+            .collect();
+        let packing = pack(&sizes, 512, 2).unwrap();
+        assert_eq!(packing.placements.len(), sizes.len());
+        assert_eq!(packing.page_sizes.len(), 1);
+        assert_padded_without_overlaps(&sizes, &packing, 2);
+    }
+
+    #[test]
+    fn overflow_spills_onto_more_pages() {
+        // four 60x60 images per 128x128 page with 2px padding
+        let sizes = vec![(60, 60); 9];
+        let packing = pack(&sizes, 128, 2).unwrap();
+        assert_eq!(packing.page_sizes, [(122, 122), (122, 122), (60, 60)]);
+        let last_page = packing.placements.iter().filter(|p| p.page == 2);
+        assert_eq!(last_page.count(), 1);
+        assert_padded_without_overlaps(&sizes, &packing, 2);
+
+        assert!(pack(&[(129, 4)], 128, 0).is_err());
+    }
+
+    #[test]
+    fn extra_pages_are_numbered_after_the_output_name() {
+        let output = Path::new("assets/sprites.png");
+        assert_eq!(page_path(output, 0), output);
+        assert_eq!(page_path(output, 2), Path::new("assets/sprites_2.png"));
+    }
+}
@@ -0,0 +1,235 @@
+use clap::ValueEnum;
+use image::{RgbaImage, imageops::FilterType};
+use intel_tex_2::{RgbaSurface, bc7, etc1};
+use ktx2::{Format, Header, Index, LevelIndex, dfd};
+use std::{fs, path::Path, time::Instant};
+
+/// Block compression `cargo xtask ktx2` encodes to. ASTC containers from other tools, e.g.
+/// `astcenc` or `toktx`, load all the same
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Codec {
+    /// BC7, for desktop GPUs
+    Bc7,
+    /// ETC2 RGB, for GLES & Android. Drops alpha
+    Etc2,
+}
+
+impl Codec {
+    fn format(self, linear: bool) -> Format {
+        match (self, linear) {
+            (Self::Bc7, false) => Format::BC7_SRGB_BLOCK,
+            (Self::Bc7, true) => Format::BC7_UNORM_BLOCK,
+            (Self::Etc2, false) => Format::ETC2_R8G8B8_SRGB_BLOCK,
+            (Self::Etc2, true) => Format::ETC2_R8G8B8_UNORM_BLOCK,
+        }
+    }
+
+    /// Compresses an image whose size is a multiple of the 4x4 blocks both codecs use
+    fn compress(self, image: &RgbaImage) -> Vec<u8> {
+        let surface = RgbaSurface {
+            data: image.as_raw(),
+            width: image.width(),
+            height: image.height(),
+            stride: image.width() * 4,
+        };
+        let opaque = image.pixels().all(|p| p[3] == 255);
+        match self {
+            Self::Bc7 if opaque => bc7::compress_blocks(&bc7::opaque_basic_settings(), &surface),
+            Self::Bc7 => bc7::compress_blocks(&bc7::alpha_basic_settings(), &surface),
+            // ETC1 blocks are valid ETC2 RGB blocks
+            Self::Etc2 => etc1::compress_blocks(&etc1::slow_settings(), &surface),
+        }
+    }
+}
+
+/// Alignment of every level's data, a multiple of each codec's block size
+const LEVEL_ALIGNMENT: usize = 16;
+
+/// Grows an image to whole 4x4 blocks, repeating its last row & column so edges don't
+/// blend with black
+fn pad_to_blocks(image: &RgbaImage) -> RgbaImage {
+    let (w, h) = image.dimensions();
+    let padded = (w.next_multiple_of(4), h.next_multiple_of(4));
+    if padded == (w, h) {
+        return image.clone();
+    }
+    RgbaImage::from_fn(padded.0, padded.1, |x, y| {
+        *image.get_pixel(x.min(w - 1), y.min(h - 1))
+    })
+}
+
+/// Mip levels of `image`, largest first, down to 1x1 if `mips` is set
+fn mip_chain(image: &RgbaImage, mips: bool) -> Vec<RgbaImage> {
+    let (w, h) = image.dimensions();
+    let count = match mips {
+        true => w.max(h).ilog2() + 1,
+        false => 1,
+    };
+    let mut levels = vec![image.clone()];
+    for level in 1..count {
+        let size = ((w >> level).max(1), (h >> level).max(1));
+        levels.push(image::imageops::resize(
+            image,
+            size.0,
+            size.1,
+            FilterType::Triangle,
+        ));
+    }
+    levels
+}
+
+/// Encodes `image` into a KTX2 container, storing its levels smallest first as the spec asks
+pub fn encode(image: &RgbaImage, codec: Codec, linear: bool, mips: bool) -> Vec<u8> {
+    let format = codec.format(linear);
+    let levels: Vec<_> = mip_chain(image, mips)
+        .iter()
+        .map(|level| codec.compress(&pad_to_blocks(level)))
+        .collect();
+
+    let (basic, type_size) = dfd::Basic::from_format(format).unwrap();
+    let dfd = dfd::Block::Basic(basic).to_vec();
+    let dfd_offset = Header::LENGTH + LevelIndex::LENGTH * levels.len();
+    let dfd_length = 4 + dfd.len();
+    let kvd = key_value(b"KTXwriter", b"egor xtask");
+    let kvd_offset = dfd_offset + dfd_length;
+
+    let mut data = Vec::new();
+    let mut offsets = vec![0; levels.len()];
+    let mut offset = (kvd_offset + kvd.len()).next_multiple_of(LEVEL_ALIGNMENT);
+    for (i, level) in levels.iter().enumerate().rev() {
+        data.resize(offset - (kvd_offset + kvd.len()), 0);
+        offsets[i] = offset;
+        data.extend(level);
+        offset = (offset + level.len()).next_multiple_of(LEVEL_ALIGNMENT);
+    }
+
+    let header = Header {
+        format: Some(format),
+        type_size,
+        pixel_width: image.width(),
+        pixel_height: image.height(),
+        pixel_depth: 0,
+        layer_count: 0,
+        face_count: 1,
+        level_count: levels.len() as u32,
+        supercompression_scheme: None,
+        index: Index {
+            dfd_byte_offset: dfd_offset as u32,
+            dfd_byte_length: dfd_length as u32,
+            kvd_byte_offset: kvd_offset as u32,
+            kvd_byte_length: kvd.len() as u32,
+            sgd_byte_offset: 0,
+            sgd_byte_length: 0,
+        },
+    };
+    let mut out = header.as_bytes().to_vec();
+    for (level, offset) in levels.iter().zip(offsets) {
+        let index = LevelIndex {
+            byte_offset: offset as u64,
+            byte_length: level.len() as u64,
+            uncompressed_byte_length: level.len() as u64,
+        };
+        out.extend(index.as_bytes());
+    }
+    out.extend((dfd_length as u32).to_le_bytes());
+    out.extend(dfd);
+    out.extend(kvd);
+    out.extend(data);
+    out
+}
+
+/// A key/value entry padded to 4 bytes, both NUL terminated
+fn key_value(key: &[u8], value: &[u8]) -> Vec<u8> {
+    let length = key.len() + value.len() + 2;
+    let mut entry = (length as u32).to_le_bytes().to_vec();
+    entry.extend(key);
+    entry.push(0);
+    entry.extend(value);
+    entry.push(0);
+    entry.resize(entry.len().next_multiple_of(4), 0);
+    entry
+}
+
+pub fn ktx2(input: &Path, output: &Path, codec: Codec, linear: bool, mips: bool) {
+    let png = fs::read(input).unwrap_or_else(|e| panic!("failed to read {}: {e}", input.display()));
+    let start = Instant::now();
+    let image = image::load_from_memory(&png)
+        .unwrap_or_else(|e| panic!("failed to load {}: {e}", input.display()))
+        .into_rgba8();
+    let decode_time = start.elapsed();
+    if codec == Codec::Etc2 && image.pixels().any(|p| p[3] < 255) {
+        eprintln!("warning: ETC2 RGB drops the alpha of {}", input.display());
+    }
+
+    let ktx2 = encode(&image, codec, linear, mips);
+    fs::write(output, &ktx2)
+        .unwrap_or_else(|e| panic!("failed to write {}: {e}", output.display()));
+
+    let start = Instant::now();
+    let reader = ktx2::Reader::new(&ktx2[..]).expect("encoded an invalid KTX2 container");
+    let read_time = start.elapsed();
+    let mib = |bytes: usize| bytes as f32 / (1 << 20) as f32;
+    println!(
+        "{}: {:.1} MiB PNG, decoded to {:.1} MiB of RGBA8 in {decode_time:.1?}",
+        input.display(),
+        mib(png.len()),
+        mib(image.len()),
+    );
+    println!(
+        "{}: {:.1} MiB of {codec:?} in {} levels, read in {read_time:.1?} & uploaded as is",
+        output.display(),
+        mib(ktx2.len()),
+        reader.levels().len(),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn levels_are_stored_smallest_first_in_whole_blocks() {
+        let image = RgbaImage::from_fn(10, 6, |x, y| {
+            image::Rgba([x as u8 * 20, y as u8 * 40, 0, 128])
+        });
+        for (codec, block_bytes) in [(Codec::Bc7, 16), (Codec::Etc2, 8)] {
+            let file = encode(&image, codec, false, true);
+            let reader = ktx2::Reader::new(&file[..]).unwrap();
+            let header = reader.header();
+            assert_eq!(header.format, Some(codec.format(false)));
+            assert_eq!((header.pixel_width, header.pixel_height), (10, 6));
+            assert_eq!(
+                reader.transfer_function(),
+                Some(ktx2::TransferFunction::SRGB)
+            );
+            assert!(reader.key_value_data().any(|(key, _)| key == "KTXwriter"));
+
+            // 10x6, 5x3, 2x1 & 1x1 pixels
+            let blocks = [3 * 2, 2, 1, 1];
+            let lengths: Vec<_> = reader.levels().map(|level| level.data.len()).collect();
+            assert_eq!(lengths, blocks.map(|b| b * block_bytes));
+            let offsets: Vec<_> = (0..blocks.len())
+                .map(|i| {
+                    let entry = &file[Header::LENGTH + i * LevelIndex::LENGTH..];
+                    LevelIndex::from_bytes(entry[..LevelIndex::LENGTH].try_into().unwrap())
+                        .byte_offset
+                })
+                .collect();
+            assert!(offsets.windows(2).all(|pair| pair[0] > pair[1]));
+            assert!(
+                offsets
+                    .iter()
+                    .all(|offset| offset % LEVEL_ALIGNMENT as u64 == 0)
+            );
+        }
+
+        let file = encode(&image, Codec::Bc7, true, false);
+        let reader = ktx2::Reader::new(&file[..]).unwrap();
+        assert_eq!(reader.header().format, Some(Format::BC7_UNORM_BLOCK));
+        assert_eq!(
+            reader.transfer_function(),
+            Some(ktx2::TransferFunction::Linear)
+        );
+        assert_eq!(reader.levels().len(), 1);
+    }
+}
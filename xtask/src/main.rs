@@ -1,3 +1,8 @@
+mod atlas;
+mod ktx2;
+mod snapshot;
+mod web;
+
 use clap::{Parser, Subcommand};
 use serde::Deserialize;
 use std::{
@@ -27,6 +32,69 @@ enum Cmd {
         #[arg(long)]
         hot_reload: bool,
     },
+    /// Pack a folder of PNGs into atlas pages & a JSON manifest of named regions
+    PackAtlas {
+        input_dir: PathBuf,
+        output_png: PathBuf,
+        output_json: PathBuf,
+        /// Largest page width & height; images that don't fit spill onto more pages
+        #[arg(long, default_value_t = 4096)]
+        max_size: u32,
+        /// Empty pixels between images so neighbours don't bleed when sampled
+        #[arg(long, default_value_t = 2)]
+        padding: u32,
+    },
+    /// Compress a PNG into a KTX2 texture for `load_texture_compressed`, with mip levels
+    Ktx2 {
+        input: PathBuf,
+        output: PathBuf,
+        #[arg(long, value_enum, default_value_t = ktx2::Codec::Bc7)]
+        codec: ktx2::Codec,
+        /// Sample the texture as linear data instead of sRGB color, e.g. for normal maps
+        #[arg(long)]
+        linear: bool,
+        /// Only store the full size level
+        #[arg(long)]
+        no_mips: bool,
+    },
+    /// Run demos with a `snapshot` feature headless & save some of their frames as PNGs
+    Snapshot {
+        /// Demo to snapshot, every demo with a `snapshot` feature by default
+        demo: Option<String>,
+        /// Frames to run with no input, 1/60s apart
+        #[arg(long, default_value_t = 10)]
+        frames: u32,
+        /// Comma separated frames to save, starting at 0 (defaults to the last)
+        #[arg(long)]
+        capture: Option<String>,
+        /// egor features to render with besides `snapshot`, e.g. `gles` for the baselines
+        #[arg(long, num_args = 0..)]
+        features: Vec<String>,
+        #[arg(long, default_value = "target/snapshots")]
+        out: PathBuf,
+    },
+    /// Compare snapshots to baselines, writing `*.diff.png` for changed frames & failing on frames without one
+    SnapshotDiff {
+        #[arg(long, default_value = "snapshots")]
+        baseline: PathBuf,
+        #[arg(long, default_value = "target/snapshots")]
+        current: PathBuf,
+        /// Fraction of pixels that may change before a frame counts as changed
+        #[arg(long, default_value_t = 0.001)]
+        threshold: f32,
+        /// How different a pixel may look before it counts as changed, from 0 to 1
+        #[arg(long, default_value_t = 0.05)]
+        tolerance: f32,
+    },
+    /// Build a demo for the web into static files, with a manifest & icons to install it
+    BundleWasm {
+        demo: String,
+        #[arg(long, num_args = 0..)]
+        features: Vec<String>,
+        /// Output directory, `target/web/<demo>` by default
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
 }
 
 #[derive(Deserialize)]
@@ -39,6 +107,14 @@ struct Manifest {
 #[derive(Deserialize)]
 struct Package {
     name: String,
+    #[serde(default)]
+    metadata: Metadata,
+}
+
+#[derive(Deserialize, Default)]
+struct Metadata {
+    #[serde(default)]
+    egor: web::WebMetadata,
 }
 
 #[derive(Deserialize)]
@@ -47,20 +123,27 @@ struct Bin {
     path: Option<String>,
 }
 
-fn read_bin_name(demo_dir: &Path) -> String {
+impl Manifest {
+    fn bin_name(&self) -> String {
+        self.bins
+            .iter()
+            .find(|bin| bin.path.as_deref() == Some("src/main.rs"))
+            .and_then(|bin| bin.name.clone())
+            .unwrap_or_else(|| self.package.name.clone())
+    }
+}
+
+fn read_manifest(demo_dir: &Path) -> Manifest {
     let manifest_path = demo_dir.join("Cargo.toml");
     let manifest_str = fs::read_to_string(&manifest_path)
         .unwrap_or_else(|e| panic!("failed to read {}: {e}", manifest_path.display()));
 
-    let manifest: Manifest = toml::from_str(&manifest_str)
-        .unwrap_or_else(|e| panic!("failed to parse {}: {e}", manifest_path.display()));
+    toml::from_str(&manifest_str)
+        .unwrap_or_else(|e| panic!("failed to parse {}: {e}", manifest_path.display()))
+}
 
-    manifest
-        .bins
-        .iter()
-        .find(|bin| bin.path.as_deref() == Some("src/main.rs"))
-        .and_then(|bin| bin.name.clone())
-        .unwrap_or(manifest.package.name)
+fn read_bin_name(demo_dir: &Path) -> String {
+    read_manifest(demo_dir).bin_name()
 }
 
 impl Cmd {
@@ -70,26 +153,23 @@ impl Cmd {
             release,
             features,
             ..
-        } = self;
+        } = self
+        else {
+            unreachable!("only `run` launches demos");
+        };
 
         let mut features = features.clone();
         let demo_dir = PathBuf::from("demos").join(demo);
-        let index_path = demo_dir.join("index.html");
         let bin_name = read_bin_name(&demo_dir);
+        let mut web_files = Vec::new();
 
         let mut cmd = match self {
             Cmd::Run { wasm: true, .. } => {
-                fs::write(
-                    &index_path,
-                    format!(
-                        r#"<!DOCTYPE html><html><head><meta charset="utf-8"><meta name="viewport" content="width=device-width,initial-scale=1"><link data-trunk rel="rust" data-bin="{bin_name}"/><style>body{{margin:0}}canvas{{display:block;width:100vw;height:100vh}}</style></head></html>"#
-                    ),
-                )
-                .expect("failed to create index.html");
-
-                let path = index_path.clone();
+                web_files = web::write_web_files(&demo_dir);
+
+                let files = web_files.clone();
                 ctrlc::set_handler(move || {
-                    let _ = fs::remove_file(&path);
+                    web::remove_web_files(&files);
                     process::exit(1);
                 })
                 .expect("failed to set Ctrl-C handler");
@@ -136,9 +216,7 @@ impl Cmd {
         println!("> {:?}", cmd);
         let status = cmd.status().expect("failed to spawn command");
 
-        if let Cmd::Run { wasm: true, .. } = self {
-            let _ = fs::remove_file(&index_path);
-        }
+        web::remove_web_files(&web_files);
 
         if !status.success() {
             process::exit(status.code().unwrap_or(1));
@@ -147,5 +225,39 @@ impl Cmd {
 }
 
 fn main() {
-    Cli::parse().cmd.run();
+    match Cli::parse().cmd {
+        Cmd::PackAtlas {
+            input_dir,
+            output_png,
+            output_json,
+            max_size,
+            padding,
+        } => atlas::pack_atlas(&input_dir, &output_png, &output_json, max_size, padding),
+        Cmd::Ktx2 {
+            input,
+            output,
+            codec,
+            linear,
+            no_mips,
+        } => ktx2::ktx2(&input, &output, codec, linear, !no_mips),
+        Cmd::Snapshot {
+            demo,
+            frames,
+            capture,
+            features,
+            out,
+        } => snapshot::snapshot(demo.as_deref(), frames, capture.as_deref(), &features, &out),
+        Cmd::SnapshotDiff {
+            baseline,
+            current,
+            threshold,
+            tolerance,
+        } => snapshot::snapshot_diff(&baseline, &current, threshold, tolerance),
+        Cmd::BundleWasm {
+            demo,
+            features,
+            out,
+        } => web::bundle_wasm(&demo, &features, out.as_deref()),
+        cmd => cmd.run(),
+    }
 }
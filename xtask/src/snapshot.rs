@@ -0,0 +1,222 @@
+use image::{Rgba, RgbaImage};
+use std::{
+    collections::BTreeSet,
+    ffi::OsString,
+    fs,
+    path::{self, Path, PathBuf},
+    process::{self, Command},
+};
+
+use crate::read_bin_name;
+
+/// Demos with a `snapshot` feature, sorted by name
+fn snapshot_demos() -> Vec<String> {
+    let mut demos: Vec<_> = fs::read_dir("demos")
+        .expect("failed to read demos")
+        .filter_map(|entry| {
+            let dir = entry.ok()?.path();
+            let manifest: toml::Table =
+                toml::from_str(&fs::read_to_string(dir.join("Cargo.toml")).ok()?).ok()?;
+            let features = manifest.get("features")?.as_table()?;
+            features
+                .contains_key("snapshot")
+                .then(|| dir.file_name()?.to_str().map(String::from))?
+        })
+        .collect();
+    demos.sort();
+    demos
+}
+
+/// Run `demo` (or every demo with a `snapshot` feature) headless for `frames` frames &
+/// save the `capture`d ones (the last by default) to `<out>/<demo>/frame_NNNN.png`, with
+/// the egor `features` enabled too
+pub fn snapshot(
+    demo: Option<&str>,
+    frames: u32,
+    capture: Option<&str>,
+    features: &[String],
+    out: &Path,
+) {
+    let demos = match demo {
+        Some(demo) => vec![demo.to_string()],
+        None => snapshot_demos(),
+    };
+
+    let features: Vec<_> = ["snapshot".to_string()]
+        .into_iter()
+        .chain(features.iter().map(|f| format!("egor/{f}")))
+        .collect();
+    for demo in demos {
+        let demo_dir = PathBuf::from("demos").join(&demo);
+        let out_dir = path::absolute(out.join(&demo)).expect("failed to resolve output folder");
+        // stale frames from an earlier run would be diffed as if they were new
+        let _ = fs::remove_dir_all(&out_dir);
+
+        let mut cmd = Command::new("cargo");
+        cmd.args(["run", "--bin", &read_bin_name(&demo_dir)])
+            .arg("--features")
+            .arg(features.join(","))
+            .env("EGOR_SNAPSHOT_DIR", &out_dir)
+            .env("EGOR_SNAPSHOT_FRAMES", frames.to_string())
+            .current_dir(&demo_dir);
+        if let Some(capture) = capture {
+            cmd.env("EGOR_SNAPSHOT_CAPTURE", capture);
+        }
+
+        println!("> {:?}", cmd);
+        let status = cmd.status().expect("failed to spawn command");
+        if !status.success() {
+            process::exit(status.code().unwrap_or(1));
+        }
+    }
+}
+
+/// How far apart two pixels look, from 0 to 1. Channels are weighted by how bright they
+/// appear & alpha counts as much as the color
+fn pixel_distance(a: Rgba<u8>, b: Rgba<u8>) -> f32 {
+    let d = |i: usize| (a[i] as f32 - b[i] as f32) / 255.0;
+    let color = (0.299 * d(0).powi(2) + 0.587 * d(1).powi(2) + 0.114 * d(2).powi(2)).sqrt();
+    color.max(d(3).abs())
+}
+
+/// Fraction of pixels further apart than `tolerance` & an image of them: changed pixels
+/// in red over a dimmed grayscale `current`
+fn compare(baseline: &RgbaImage, current: &RgbaImage, tolerance: f32) -> (f32, RgbaImage) {
+    let mut changed = 0;
+    let diff = RgbaImage::from_fn(current.width(), current.height(), |x, y| {
+        let (a, b) = (*baseline.get_pixel(x, y), *current.get_pixel(x, y));
+        if pixel_distance(a, b) > tolerance {
+            changed += 1;
+            return Rgba([255, 0, 0, 255]);
+        }
+        let luma = 0.299 * b[0] as f32 + 0.587 * b[1] as f32 + 0.114 * b[2] as f32;
+        let dimmed = (luma * 0.3) as u8;
+        Rgba([dimmed, dimmed, dimmed, 255])
+    });
+    let pixels = (current.width() * current.height()).max(1);
+    (changed as f32 / pixels as f32, diff)
+}
+
+/// Entries of `dir` passing `keep`, by file name. Empty if `dir` doesn't exist
+fn entries(dir: &Path, keep: impl Fn(&Path) -> bool) -> BTreeSet<OsString> {
+    let Ok(read) = fs::read_dir(dir) else {
+        return BTreeSet::new();
+    };
+    read.filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| keep(path))
+        .filter_map(|path| path.file_name().map(OsString::from))
+        .collect()
+}
+
+/// Saved frames, leaving out diff images from an earlier run
+fn is_frame(path: &Path) -> bool {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    name.ends_with(".png") && !name.ends_with(".diff.png")
+}
+
+/// Compare every `<current>/<demo>/*.png` to the same file under `baseline`, printing
+/// the demos that changed & writing `*.diff.png` next to the changed images. An image
+/// changed when more than `threshold` of its pixels are further apart than `tolerance`.
+/// Exits with 1 if anything changed, is missing or has no baseline
+pub fn snapshot_diff(baseline: &Path, current: &Path, threshold: f32, tolerance: f32) {
+    let is_dir = |path: &Path| path.is_dir();
+    let mut demos = entries(baseline, is_dir);
+    demos.append(&mut entries(current, is_dir));
+
+    let mut changed_demos = Vec::new();
+    for demo in demos {
+        let (baseline_dir, current_dir) = (baseline.join(&demo), current.join(&demo));
+        let mut images = entries(&baseline_dir, is_frame);
+        images.append(&mut entries(&current_dir, is_frame));
+        let demo = demo.to_string_lossy();
+
+        let mut changed = false;
+        for name in images {
+            let (baseline_path, current_path) = (baseline_dir.join(&name), current_dir.join(&name));
+            let name = name.to_string_lossy();
+            if !baseline_path.exists() {
+                println!("{demo}/{name}: no baseline in {}", baseline.display());
+                changed = true;
+                continue;
+            }
+            let Ok(current_image) = image::open(&current_path) else {
+                println!("{demo}/{name}: missing from {}", current.display());
+                changed = true;
+                continue;
+            };
+            let baseline_image = image::open(&baseline_path)
+                .unwrap_or_else(|e| panic!("failed to read {}: {e}", baseline_path.display()))
+                .into_rgba8();
+            let current_image = current_image.into_rgba8();
+            if baseline_image.dimensions() != current_image.dimensions() {
+                println!(
+                    "{demo}/{name}: size changed from {:?} to {:?}",
+                    baseline_image.dimensions(),
+                    current_image.dimensions()
+                );
+                changed = true;
+                continue;
+            }
+
+            let (fraction, diff) = compare(&baseline_image, &current_image, tolerance);
+            if fraction > threshold {
+                let diff_path = current_path.with_extension("diff.png");
+                diff.save(&diff_path)
+                    .unwrap_or_else(|e| panic!("failed to write {}: {e}", diff_path.display()));
+                println!(
+                    "{demo}/{name}: {:.2}% of pixels changed, see {}",
+                    fraction * 100.0,
+                    diff_path.display()
+                );
+                changed = true;
+            }
+        }
+        if changed {
+            changed_demos.push(demo.into_owned());
+        }
+    }
+
+    if changed_demos.is_empty() {
+        println!("no snapshots changed");
+    } else {
+        println!("changed: {}", changed_demos.join(", "));
+        process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn small_color_shifts_stay_within_tolerance() {
+        let baseline = RgbaImage::from_pixel(4, 4, Rgba([100, 150, 200, 255]));
+        let mut current = RgbaImage::from_pixel(4, 4, Rgba([102, 148, 203, 255]));
+        let (fraction, _) = compare(&baseline, &current, 0.05);
+        assert_eq!(fraction, 0.0);
+
+        current.put_pixel(1, 2, Rgba([100, 150, 200, 0]));
+        current.put_pixel(3, 3, Rgba([100, 250, 200, 255]));
+        let (fraction, diff) = compare(&baseline, &current, 0.05);
+        assert_eq!(fraction, 2.0 / 16.0);
+        assert_eq!(*diff.get_pixel(1, 2), Rgba([255, 0, 0, 255]));
+        assert_eq!(*diff.get_pixel(3, 3), Rgba([255, 0, 0, 255]));
+        assert_ne!(*diff.get_pixel(0, 0), Rgba([255, 0, 0, 255]));
+    }
+
+    #[test]
+    fn diff_images_arent_frames() {
+        assert!(is_frame(Path::new("health_bar/frame_0010.png")));
+        assert!(!is_frame(Path::new("health_bar/frame_0010.diff.png")));
+        assert!(!is_frame(Path::new("health_bar/notes.txt")));
+    }
+
+    #[test]
+    fn green_differences_look_bigger_than_blue() {
+        let gray = Rgba([128, 128, 128, 255]);
+        let green = pixel_distance(gray, Rgba([128, 178, 128, 255]));
+        let blue = pixel_distance(gray, Rgba([128, 128, 178, 255]));
+        assert!(green > blue * 2.0);
+        assert_eq!(pixel_distance(gray, gray), 0.0);
+    }
+}
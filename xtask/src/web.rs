@@ -0,0 +1,266 @@
+use image::{Rgba, RgbaImage, imageops::FilterType};
+use serde::Deserialize;
+use serde_json::json;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::{self, Command},
+};
+
+use crate::read_manifest;
+
+/// Icon sizes browsers ask a web app manifest for before offering to install it
+const ICON_SIZES: [u32; 2] = [192, 512];
+
+/// `[package.metadata.egor]` of a demo, describing how it looks once installed from the web
+#[derive(Deserialize, Default, Debug, PartialEq)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct WebMetadata {
+    /// Page & app name, the package name by default
+    pub title: Option<String>,
+    /// Square PNG relative to the demo, a plain `background-color` tile by default
+    pub icon: Option<PathBuf>,
+    /// `any`, `portrait`, `landscape` or any other web app manifest orientation
+    pub orientation: Option<String>,
+    /// CSS color behind the canvas & of the splash screen
+    pub background_color: Option<String>,
+}
+
+impl WebMetadata {
+    fn title<'a>(&'a self, package: &'a str) -> &'a str {
+        self.title.as_deref().unwrap_or(package)
+    }
+
+    fn background_color(&self) -> &str {
+        self.background_color.as_deref().unwrap_or("#000000")
+    }
+}
+
+/// Page trunk builds from, filling the screen edge to edge on phones (notch included)
+pub fn index_html(meta: &WebMetadata, package: &str, bin_name: &str) -> String {
+    let title = escape_html(meta.title(package));
+    let background = escape_html(meta.background_color());
+    let icons: String = ICON_SIZES
+        .iter()
+        .map(|size| {
+            format!(
+                r#"<link data-trunk rel="copy-file" href="{}"/>"#,
+                icon_name(*size)
+            )
+        })
+        .collect();
+    format!(
+        r#"<!DOCTYPE html><html><head><meta charset="utf-8"><meta name="viewport" content="width=device-width,initial-scale=1,viewport-fit=cover,user-scalable=no"><meta name="theme-color" content="{background}"><meta name="mobile-web-app-capable" content="yes"><meta name="apple-mobile-web-app-capable" content="yes"><meta name="apple-mobile-web-app-status-bar-style" content="black-translucent"><title>{title}</title><link rel="manifest" href="manifest.webmanifest"><link rel="icon" href="{icon}"><link rel="apple-touch-icon" href="{icon}"><link data-trunk rel="copy-file" href="manifest.webmanifest"/>{icons}<link data-trunk rel="rust" data-bin="{bin_name}"/><style>html,body{{margin:0;height:100%;overflow:hidden;overscroll-behavior:none;background:{background}}}canvas{{display:block;position:fixed;inset:0;width:100%;height:100%;touch-action:none;outline:none}}</style></head></html>"#,
+        icon = icon_name(ICON_SIZES[0]),
+    )
+}
+
+/// Web app manifest letting browsers install the demo fullscreen with its own icon
+pub fn web_manifest(meta: &WebMetadata, package: &str) -> String {
+    let title = meta.title(package);
+    let background = meta.background_color();
+    let icons: Vec<_> = ICON_SIZES
+        .iter()
+        .map(|size| {
+            json!({
+                "src": icon_name(*size),
+                "sizes": format!("{size}x{size}"),
+                "type": "image/png",
+            })
+        })
+        .collect();
+    let manifest = json!({
+        "name": title,
+        "short_name": title,
+        "start_url": ".",
+        "display": "fullscreen",
+        "orientation": meta.orientation.as_deref().unwrap_or("any"),
+        "background_color": background,
+        "theme_color": background,
+        "icons": icons,
+    });
+    serde_json::to_string_pretty(&manifest).expect("failed to serialize web app manifest")
+}
+
+/// Escapes `text` for HTML text & quoted attributes, so titles & colors from `Cargo.toml`
+/// can't break out of the page
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn icon_name(size: u32) -> String {
+    format!("icon-{size}.png")
+}
+
+/// Parses `#rgb`, `#rrggbb` or `#rrggbbaa`, the only colors a fallback icon can be filled with
+fn parse_hex(color: &str) -> Option<Rgba<u8>> {
+    let hex = color.strip_prefix('#')?;
+    let channel = |i: usize, len: usize| {
+        let digits = hex.get(i * len..(i + 1) * len)?;
+        let value = u8::from_str_radix(digits, 16).ok()?;
+        Some(if len == 1 { value * 17 } else { value })
+    };
+    match hex.len() {
+        3 => Some(Rgba([channel(0, 1)?, channel(1, 1)?, channel(2, 1)?, 255])),
+        6 | 8 => Some(Rgba([
+            channel(0, 2)?,
+            channel(1, 2)?,
+            channel(2, 2)?,
+            if hex.len() == 8 { channel(3, 2)? } else { 255 },
+        ])),
+        _ => None,
+    }
+}
+
+/// Writes `index.html`, `manifest.webmanifest` & the icons into `demo_dir`,
+/// returning every file written so they can be removed once trunk is done
+pub fn write_web_files(demo_dir: &Path) -> Vec<PathBuf> {
+    let manifest = read_manifest(demo_dir);
+    let meta = &manifest.package.metadata.egor;
+    let package = &manifest.package.name;
+
+    let source = meta.icon.as_ref().map(|icon| {
+        let path = demo_dir.join(icon);
+        image::open(&path).unwrap_or_else(|e| panic!("failed to open icon {}: {e}", path.display()))
+    });
+    let fill = parse_hex(meta.background_color()).unwrap_or(Rgba([0, 0, 0, 255]));
+
+    let mut written = Vec::new();
+    let mut write = |name: &str, contents: &[u8]| {
+        let path = demo_dir.join(name);
+        fs::write(&path, contents)
+            .unwrap_or_else(|e| panic!("failed to write {}: {e}", path.display()));
+        written.push(path);
+    };
+    write(
+        "index.html",
+        index_html(meta, package, &manifest.bin_name()).as_bytes(),
+    );
+    write(
+        "manifest.webmanifest",
+        web_manifest(meta, package).as_bytes(),
+    );
+
+    for size in ICON_SIZES {
+        let icon = match &source {
+            Some(source) => source
+                .resize_to_fill(size, size, FilterType::Lanczos3)
+                .into_rgba8(),
+            None => RgbaImage::from_pixel(size, size, fill),
+        };
+        let mut png = Vec::new();
+        icon.write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+            .expect("failed to encode icon");
+        write(&icon_name(size), &png);
+    }
+    written
+}
+
+pub fn remove_web_files(files: &[PathBuf]) {
+    for file in files {
+        let _ = fs::remove_file(file);
+    }
+}
+
+/// Build `demo` for the web in release mode into `out`, ready to be served as static files
+pub fn bundle_wasm(demo: &str, features: &[String], out: Option<&Path>) {
+    let demo_dir = PathBuf::from("demos").join(demo);
+    let out = out.map_or_else(|| PathBuf::from("target/web").join(demo), Path::to_path_buf);
+    let out = std::path::absolute(&out).expect("failed to resolve output directory");
+
+    let files = write_web_files(&demo_dir);
+    let cleanup = files.clone();
+    ctrlc::set_handler(move || {
+        remove_web_files(&cleanup);
+        process::exit(1);
+    })
+    .expect("failed to set Ctrl-C handler");
+
+    let mut cmd = Command::new("trunk");
+    cmd.args(["build", "--release", "--dist"]).arg(&out);
+    if !features.is_empty() {
+        let prefixed: Vec<_> = features.iter().map(|f| format!("egor/{f}")).collect();
+        cmd.arg("--features").arg(prefixed.join(","));
+    }
+    cmd.current_dir(&demo_dir);
+
+    println!("> {:?}", cmd);
+    let status = cmd.status().expect("failed to spawn command");
+    remove_web_files(&files);
+
+    if !status.success() {
+        process::exit(status.code().unwrap_or(1));
+    }
+    println!("bundled {demo} into {}", out.display());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn metadata_reads_kebab_case_keys() {
+        let table: toml::Table = toml::from_str(
+            r##"
+            title = "Shooter"
+            icon = "assets/icon.png"
+            orientation = "landscape"
+            background-color = "#102030"
+            "##,
+        )
+        .unwrap();
+        let meta: WebMetadata = table.try_into().unwrap();
+        assert_eq!(meta.title.as_deref(), Some("Shooter"));
+        assert_eq!(meta.icon, Some(PathBuf::from("assets/icon.png")));
+        assert_eq!(meta.orientation.as_deref(), Some("landscape"));
+        assert_eq!(meta.background_color.as_deref(), Some("#102030"));
+    }
+
+    #[test]
+    fn manifest_falls_back_to_the_package() {
+        let manifest: serde_json::Value =
+            serde_json::from_str(&web_manifest(&WebMetadata::default(), "shooter")).unwrap();
+        assert_eq!(manifest["name"], "shooter");
+        assert_eq!(manifest["orientation"], "any");
+        assert_eq!(manifest["display"], "fullscreen");
+        assert_eq!(manifest["icons"][1]["sizes"], "512x512");
+
+        let html = index_html(&WebMetadata::default(), "shooter", "shooter_bin");
+        assert!(html.contains("viewport-fit=cover"));
+        assert!(html.contains(r#"data-bin="shooter_bin""#));
+        assert!(html.contains("<title>shooter</title>"));
+    }
+
+    #[test]
+    fn page_values_are_escaped() {
+        let meta = WebMetadata {
+            title: Some("Tom & Jerry </title><script>".into()),
+            background_color: Some(r#"red" onload="x"#.into()),
+            ..Default::default()
+        };
+        let html = index_html(&meta, "demo", "demo");
+        assert!(html.contains("<title>Tom &amp; Jerry &lt;/title&gt;&lt;script&gt;</title>"));
+        assert!(html.contains(r#"content="red&quot; onload=&quot;x""#));
+        assert!(!html.contains("<script>"));
+    }
+
+    #[test]
+    fn hex_colors_parse_in_every_length() {
+        assert_eq!(parse_hex("#fff"), Some(Rgba([255, 255, 255, 255])));
+        assert_eq!(parse_hex("#102030"), Some(Rgba([16, 32, 48, 255])));
+        assert_eq!(parse_hex("#10203040"), Some(Rgba([16, 32, 48, 64])));
+        assert_eq!(parse_hex("teal"), None);
+        assert_eq!(parse_hex("#12345"), None);
+    }
+}